@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE shifts;
+DROP INDEX staff_personnel_number;
+ALTER TABLE staff DROP COLUMN personnel_number;
@@ -0,0 +1,11 @@
+-- Personnel number as used by the scheduling tool and payroll
+ALTER TABLE staff ADD COLUMN personnel_number TEXT;
+CREATE UNIQUE INDEX staff_personnel_number ON staff(personnel_number);
+
+-- Create table for planned shifts
+CREATE TABLE shifts (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    staff_id INTEGER NOT NULL REFERENCES staff(id),
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+DROP INDEX events_token;
+ALTER TABLE events DROP COLUMN token;
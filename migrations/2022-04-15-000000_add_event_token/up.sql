@@ -0,0 +1,3 @@
+-- Client generated token so that an inserted event can be found again even with multiple terminals writing
+ALTER TABLE events ADD COLUMN token TEXT;
+CREATE UNIQUE INDEX events_token ON events(token);
@@ -0,0 +1,6 @@
+-- Months that were closed in the monthly closing, identified by their first day
+CREATE TABLE closed_months (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    month DATE NOT NULL UNIQUE,
+    closed_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,5 @@
+DROP INDEX events_staff_id_created_at;
+DROP INDEX events_kind_created_at;
+DROP INDEX events_created_at;
+ALTER TABLE events DROP COLUMN staff_id;
+ALTER TABLE events DROP COLUMN kind;
@@ -0,0 +1,7 @@
+-- Kind and staff member of each event, copied out of event_json so that queries can filter by them.
+-- Existing events are filled in by the application when it opens the database, see db::connect.
+ALTER TABLE events ADD COLUMN kind TEXT;
+ALTER TABLE events ADD COLUMN staff_id INTEGER;
+CREATE INDEX events_created_at ON events(created_at);
+CREATE INDEX events_kind_created_at ON events(kind, created_at);
+CREATE INDEX events_staff_id_created_at ON events(staff_id, created_at);
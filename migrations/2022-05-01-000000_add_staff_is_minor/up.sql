@@ -0,0 +1,2 @@
+-- Staff members under 18 are subject to the youth labor law
+ALTER TABLE staff ADD COLUMN is_minor BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Department or pay group, the evaluation can be exported separately for each one
+ALTER TABLE staff ADD COLUMN department TEXT;
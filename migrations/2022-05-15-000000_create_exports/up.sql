@@ -0,0 +1,11 @@
+-- History of generated exports, to look up which file was sent for a month
+CREATE TABLE exports (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    month DATE NOT NULL,
+    department TEXT,
+    path TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    generated_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+DROP INDEX events_dedup_key;
+ALTER TABLE events DROP COLUMN dedup_key;
@@ -0,0 +1,4 @@
+-- Status changes of the same person to the same status within one second are saved only once,
+-- e.g. when a confirmation is sent twice. Other events leave the key empty and are not restricted.
+ALTER TABLE events ADD COLUMN dedup_key TEXT;
+CREATE UNIQUE INDEX events_dedup_key ON events(dedup_key);
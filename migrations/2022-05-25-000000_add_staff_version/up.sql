@@ -0,0 +1,2 @@
+-- Incremented on every save, so that a terminal does not overwrite changes it has not seen yet
+ALTER TABLE staff ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+DROP INDEX staff_history_changed_at;
+DROP TABLE staff_history;
@@ -0,0 +1,11 @@
+-- Changes of the staff list, so that the owner can review them
+CREATE TABLE staff_history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    staff_id INTEGER NOT NULL REFERENCES staff(id),
+    changed_at TIMESTAMP NOT NULL,
+    kind TEXT NOT NULL,
+    field TEXT,
+    old_value TEXT,
+    new_value TEXT
+);
+CREATE INDEX staff_history_changed_at ON staff_history(changed_at);
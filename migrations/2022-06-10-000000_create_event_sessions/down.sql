@@ -0,0 +1,2 @@
+DROP INDEX event_sessions_business_date;
+DROP TABLE event_sessions;
@@ -0,0 +1,10 @@
+-- Events (parties) detected from the number of staff members working, for grouping in the statistics
+CREATE TABLE event_sessions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    business_date DATE NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP NOT NULL,
+    peak_count INTEGER NOT NULL,
+    staff_minutes BIGINT NOT NULL
+);
+CREATE INDEX event_sessions_business_date ON event_sessions(business_date);
@@ -0,0 +1,9 @@
+-- Announcements to the staff on the Timetrack tab, e.g. "Heute Abrechnung bis 04:00!"
+-- Only the newest one is shown, an empty text removes the announcement.
+CREATE TABLE announcements (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    text TEXT NOT NULL,
+    show_from TIMESTAMP,
+    show_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE staff DROP COLUMN wage_cents_per_hour;
@@ -0,0 +1,2 @@
+-- Hourly wage in cents, the evaluation calculates the wage of each staff member from it
+ALTER TABLE staff ADD COLUMN wage_cents_per_hour INTEGER;
@@ -0,0 +1,2 @@
+-- Name shown at the terminal instead of the legal name, e.g. a nickname
+ALTER TABLE staff ADD COLUMN display_name TEXT;
@@ -0,0 +1,276 @@
+//! Consistency checks of the database that are run once a week.
+//! They catch corruption (e.g. from crashes) before it shows up in the monthly evaluation.
+use crate::models::{StaffMember, StaffNames, WorkEvent, WorkEventT, WorkStatus};
+use crate::settings::Settings;
+use crate::storage::Storage;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditProblem {
+    /// Two staff members share a PIN or dongle, or the PIN of one is the dongle of another.
+    /// With login by personnel number, the personnel numbers are included.
+    DuplicateIdent(String, String, String),
+    /// The pregenerated 6am boundary at the start of a business day is missing.
+    Missing6am(NaiveDate),
+    UnknownStaff(NaiveDateTime, i32, String),
+    AlreadyWorking(NaiveDateTime, String),
+    AlreadyAway(NaiveDateTime, String),
+}
+
+impl fmt::Display for AuditProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            AuditProblem::DuplicateIdent(ident, name1, name2) => format!(
+                "{} und {} haben dieselbe PIN/denselben Dongle \"{}\"",
+                name1, name2, ident
+            ),
+            AuditProblem::Missing6am(date) => format!(
+                "Am {} fehlt die Tagesgrenze um 6 Uhr morgens",
+                date.format("%d.%m.%Y")
+            ),
+            AuditProblem::UnknownStaff(date, uuid, name) => format!(
+                "Um {} wurde der Status von {} geändert, aber es gibt keinen Mitarbeiter mit ID {}",
+                date, name, uuid
+            ),
+            AuditProblem::AlreadyWorking(date, name) => format!(
+                "Um {} wurde der Status von {} auf 'Arbeiten' gesetzt während er/sie schon am Arbeiten war",
+                date, name
+            ),
+            AuditProblem::AlreadyAway(date, name) => format!(
+                "Um {} wurde der Status von {} auf 'Pause' gesetzt während er/sie schon in der Pause war",
+                date, name
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+/// PINs and dongles must be disjoint (see `StaffMember::get_by_ident`), and personnel numbers too if they are used for login.
+pub fn audit_staff(staff: &[StaffMember], personnel_number_login: bool) -> Vec<AuditProblem> {
+    let mut problems = Vec::new();
+    let mut owners: HashMap<&str, &str> = HashMap::new();
+
+    for staff_member in staff {
+        let personnel_number = staff_member
+            .personnel_number
+            .as_deref()
+            .filter(|_| personnel_number_login);
+        for ident in [
+            Some(staff_member.pin.as_str()),
+            Some(staff_member.cardid.as_str()),
+            personnel_number,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            match owners.get(ident) {
+                // The own PIN may be the same as the own personnel number.
+                Some(owner) if *owner == staff_member.name => {}
+                Some(owner) => problems.push(AuditProblem::DuplicateIdent(
+                    ident.to_owned(),
+                    String::from(*owner),
+                    staff_member.name.clone(),
+                )),
+                None => {
+                    owners.insert(ident, &staff_member.name);
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Check that every business day from start_date to end_date (exclusive) has a 6am boundary and that the
+/// status changes of each staff member alternate between Working and Away.
+pub fn audit_events(
+    events: &[WorkEventT],
+    staff_ids: &HashSet<i32>,
+    names: &StaffNames,
+    settings: &Settings,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Vec<AuditProblem> {
+    let mut problems = Vec::new();
+
+    // The boundary is stored one second before the business day it starts.
+    let boundaries = events
+        .iter()
+        .filter(|eventt| eventt.event == WorkEvent::_6am)
+        .map(|eventt| settings.business_date(eventt.created_at + Duration::seconds(1)))
+        .collect::<HashSet<_>>();
+    let mut date = start_date;
+    while date < end_date {
+        if !boundaries.contains(&date) {
+            problems.push(AuditProblem::Missing6am(date));
+        }
+        date = date.succ();
+    }
+
+    // Status of each staff member since the last 6am boundary. At the boundary everyone is signed off.
+    let mut statuses: HashMap<i32, WorkStatus> = HashMap::new();
+    for eventt in events {
+        match &eventt.event {
+            WorkEvent::_6am => statuses.clear(),
+            WorkEvent::StatusChange(uuid, recorded, status) => {
+                let name = names.resolve(*uuid, recorded, false);
+                if !staff_ids.contains(uuid) {
+                    problems.push(AuditProblem::UnknownStaff(
+                        eventt.created_at,
+                        *uuid,
+                        name.clone(),
+                    ));
+                }
+                match (statuses.insert(*uuid, *status), status) {
+                    (Some(WorkStatus::Working), WorkStatus::Working) => {
+                        problems.push(AuditProblem::AlreadyWorking(eventt.created_at, name))
+                    }
+                    (Some(WorkStatus::Away), WorkStatus::Away) => {
+                        problems.push(AuditProblem::AlreadyAway(eventt.created_at, name))
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    problems
+}
+
+/// Run all checks on the events from the start of the given business day up to the given time.
+pub fn run(
+    start_date: NaiveDate,
+    end_time: NaiveDateTime,
+    staff: &[StaffMember],
+    settings: &Settings,
+    storage: &mut dyn Storage,
+) -> Vec<AuditProblem> {
+    // Including the boundary that starts the first business day.
+    let start_time = settings.business_day_start(start_date) - Duration::seconds(1);
+    let events = storage.load_events_between(Some(start_time), Some(end_time));
+    let staff_ids = storage
+        .load_all_staff_ids()
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let names = storage.load_staff_names();
+
+    let mut problems = audit_staff(staff, settings.kiosk.personnel_number_login);
+    problems.extend(audit_events(
+        &events,
+        &staff_ids,
+        &names,
+        settings,
+        start_date,
+        settings.business_date(end_time).succ(),
+    ));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::models::{NewStaffMember, NewWorkEventT, WorkEventT};
+    use chrono::NaiveTime;
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn inconsistent_events() {
+        let day1 = NaiveDate::from_ymd(2000, 1, 1);
+        let day2 = NaiveDate::from_ymd(2000, 1, 2);
+        let working = |id, time| {
+            WorkEventT::new(
+                id,
+                day1.and_hms(time, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            )
+        };
+        let events = vec![
+            WorkEventT::new(1, day1.and_hms(5, 59, 59), WorkEvent::_6am),
+            working(2, 20),
+            working(3, 22),
+        ];
+        let staff_ids = [1].into_iter().collect();
+
+        assert_eq!(
+            audit_events(
+                &events,
+                &staff_ids,
+                &StaffNames::default(),
+                &Settings::default(),
+                day1,
+                day2.succ()
+            ),
+            vec![
+                AuditProblem::Missing6am(day2),
+                AuditProblem::AlreadyWorking(day1.and_hms(22, 0, 0), String::from("Aaron")),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_run() {
+        let start_date = NaiveDate::from_ymd(2022, 3, 7);
+        for cutoff_time in [NaiveTime::from_hms(6, 0, 0), NaiveTime::from_hms(23, 0, 0)] {
+            let settings = Settings {
+                cutoff_time,
+                ..Settings::default()
+            };
+            let mut connection = SqliteConnection::establish(":memory:").unwrap();
+            connection.run_pending_migrations(MIGRATIONS).unwrap();
+            let staff_member = db::insert_staff(
+                NewStaffMember::new(
+                    String::from("Aaron"),
+                    String::from("1111"),
+                    String::from("1111111111"),
+                    String::new(),
+                )
+                .unwrap(),
+                &mut connection,
+            )
+            .unwrap();
+            // The boundaries of the week and the business day of the audit, except for Thursday.
+            for date in (0..8)
+                .map(|days| start_date + Duration::days(days))
+                .filter(|date| *date != start_date + Duration::days(3))
+            {
+                let boundary = settings.business_day_start(date) - Duration::seconds(1);
+                db::insert_event(
+                    NewWorkEventT::new(boundary, WorkEvent::_6am),
+                    &mut connection,
+                );
+            }
+            let sign_in = settings.business_day_start(start_date) + Duration::hours(2);
+            db::insert_event(
+                NewWorkEventT::new(
+                    sign_in,
+                    WorkEvent::StatusChange(
+                        staff_member.uuid(),
+                        String::from("Aaron"),
+                        WorkStatus::Working,
+                    ),
+                ),
+                &mut connection,
+            );
+            let now =
+                settings.business_day_start(start_date + Duration::days(7)) + Duration::hours(6);
+
+            assert_eq!(
+                run(
+                    start_date,
+                    now,
+                    std::slice::from_ref(&staff_member),
+                    &settings,
+                    &mut connection
+                ),
+                vec![AuditProblem::Missing6am(start_date + Duration::days(3))]
+            );
+        }
+    }
+}
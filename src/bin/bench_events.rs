@@ -0,0 +1,125 @@
+//! Benchmark the queries of the monthly evaluation on a database with many events, and check that loading the status
+//! at the start of a month does not get slower with the number of events before it.
+//! Usage: bench_events <path of a new database file> [number of events]
+use chrono::{Duration, NaiveDate, NaiveTime};
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::error::Error;
+use std::time::Instant;
+use stechuhr::{
+    date_ext::NaiveDateExt,
+    db,
+    models::{NewWorkEventT, WorkEvent, WorkStatus},
+};
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+const STAFF: i32 = 40;
+const BATCH_SIZE: usize = 10_000;
+
+/// Every day has a 6am event and two shifts for each staff member.
+fn generate_day(date: NaiveDate) -> Vec<NewWorkEventT> {
+    let mut day = vec![NewWorkEventT::new(
+        date.and_time(NaiveTime::from_hms(5, 59, 59)),
+        WorkEvent::_6am,
+    )];
+    for uuid in 1..=STAFF {
+        let name = format!("Person {}", uuid);
+        for (start, end) in [(18, 21), (22, 25)] {
+            let start = date.and_hms(0, uuid as u32, 0) + Duration::hours(start);
+            let end = date.and_hms(0, uuid as u32, 0) + Duration::hours(end);
+            day.push(NewWorkEventT::new(
+                start,
+                WorkEvent::StatusChange(uuid, name.clone(), WorkStatus::Working),
+            ));
+            day.push(NewWorkEventT::new(
+                end,
+                WorkEvent::StatusChange(uuid, name.clone(), WorkStatus::Away),
+            ));
+        }
+    }
+    day
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut args = std::env::args().skip(1);
+    let path = args
+        .next()
+        .ok_or("Usage: bench_events <path of a new database file> [number of events]")?;
+    let count = match args.next() {
+        Some(count) => count.parse::<usize>()?,
+        None => 1_000_000,
+    };
+
+    let mut connection = SqliteConnection::establish(&path)?;
+    connection.run_pending_migrations(MIGRATIONS)?;
+
+    let start = Instant::now();
+    let mut date = NaiveDate::from_ymd(2000, 1, 1);
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut inserted = 0;
+    while inserted < count {
+        batch.extend(generate_day(date));
+        date = date.succ();
+        if batch.len() >= BATCH_SIZE || inserted + batch.len() >= count {
+            batch.truncate(count - inserted);
+            connection.transaction(|connection| db::insert_events(&batch, connection))?;
+            inserted += batch.len();
+            batch.clear();
+        }
+    }
+    println!(
+        "{} events until {} inserted in {:?}",
+        inserted,
+        date,
+        start.elapsed()
+    );
+
+    // Evaluate the last complete month.
+    let month_end = date.first_dom();
+    let month_start = month_end.pred().first_dom();
+    let (start_time, end_time) = (month_start.and_hms(6, 0, 0), month_end.and_hms(6, 0, 0));
+
+    let start = Instant::now();
+    let all_previous = db::load_events_between(None, Some(start_time), &mut connection);
+    let all_month = db::load_events_between(Some(start_time), Some(end_time), &mut connection);
+    println!(
+        "Unfiltered: {} previous and {} events of the month loaded in {:?}",
+        all_previous.len(),
+        all_month.len(),
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let previous = db::load_status_events_before(start_time, &mut connection);
+    let month = db::load_events_of_kinds_between(
+        &[
+            WorkEvent::KIND_STATUS_CHANGE,
+            WorkEvent::KIND_6AM,
+            WorkEvent::KIND_CORRECTION,
+        ],
+        start_time,
+        end_time,
+        &mut connection,
+    );
+    println!(
+        "Indexed: {} previous and {} events of the month loaded in {:?}",
+        previous.len(),
+        month.len(),
+        start.elapsed()
+    );
+
+    // The initial status only needs the events since the last 6am event, so it takes as long at the end of the
+    // history as at its start.
+    for month_start in [NaiveDate::from_ymd(2000, 2, 1), month_start] {
+        let start = Instant::now();
+        let previous = db::load_status_events_before(month_start.and_hms(6, 0, 0), &mut connection);
+        println!(
+            "Status at {}: {} previous events loaded in {:?}",
+            month_start,
+            previous.len(),
+            start.elapsed()
+        );
+    }
+
+    Ok(())
+}
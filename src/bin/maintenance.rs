@@ -0,0 +1,56 @@
+//! Maintenance of the database that changes many rows at once.
+//! Usage: maintenance [--dry-run] <action>
+//!   purge-log <YYYY-MM-DD>            delete info and error messages before the date
+//!   migrate-6am [<YYYY-MM-DD>]        move the day boundaries from the date on (default today) to the configured sign-off time
+//!   merge-staff <duplicate id> <id>   move the events and shifts of a duplicate staff member and delete it
+//!   dedupe-6am                        delete duplicate day boundaries, keeping one per day
+use chrono::{Local, NaiveDate};
+use dotenv::dotenv;
+use std::error::Error;
+use stechuhr::{db, maintenance, settings::Settings};
+
+const USAGE: &str = "Usage: maintenance [--dry-run] purge-log <YYYY-MM-DD> | migrate-6am [<YYYY-MM-DD>] | merge-staff <duplicate id> <id> | dedupe-6am";
+
+fn parse_date(arg: Option<&String>) -> Result<Option<NaiveDate>, Box<dyn Error>> {
+    match arg {
+        Some(arg) => Ok(Some(NaiveDate::parse_from_str(arg, "%Y-%m-%d")?)),
+        None => Ok(None),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    env_logger::init();
+
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let dry_run = args.first().is_some_and(|arg| arg == "--dry-run");
+    if dry_run {
+        args.remove(0);
+    }
+
+    let mut connection = db::establish_connection();
+    let report = match args.first().map(String::as_str) {
+        Some("purge-log") => {
+            let before = parse_date(args.get(1))?.ok_or(USAGE)?;
+            maintenance::purge_log(before.and_hms(0, 0, 0), dry_run, &mut connection)?
+        }
+        Some("migrate-6am") => {
+            let settings = Settings::load()?;
+            let from = parse_date(args.get(1))?.unwrap_or_else(|| Local::today().naive_local());
+            maintenance::migrate_6am(from, &settings, dry_run, &mut connection)?
+        }
+        Some("merge-staff") => {
+            let duplicate = args.get(1).ok_or(USAGE)?.parse()?;
+            let into = args.get(2).ok_or(USAGE)?.parse()?;
+            maintenance::merge_staff(duplicate, into, dry_run, &mut connection)?
+        }
+        Some("dedupe-6am") => {
+            let settings = Settings::load()?;
+            maintenance::dedupe_6am(&settings, dry_run, &mut connection)?
+        }
+        _ => return Err(USAGE.into()),
+    };
+
+    print!("{}", report);
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! Validation of status changes that are inserted afterwards, e.g. in the monthly closing. A wrong correction
+//! is hard to spot later since it silently changes the evaluation, so we reject everything that would leave
+//! the events of the staff member in an inconsistent state.
+use crate::models::{WorkEvent, WorkStatus};
+use crate::settings::Settings;
+use crate::storage::Storage;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::{error, fmt};
+
+const TIME_FORMAT: &str = "%d.%m.%Y %H:%M";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorrectionError {
+    InFuture(NaiveDateTime),
+    /// Everyone is signed off at the end of the business day, so nothing can be inserted at or after that.
+    AfterBoundary(NaiveDateTime),
+    /// The staff member was added on the given business day.
+    BeforeEmployment(NaiveDate),
+    AlreadyStatus(NaiveDateTime, WorkStatus),
+    /// The next status change of the business day sets the same status.
+    BreaksSequence(NaiveDateTime, WorkStatus),
+}
+
+impl error::Error for CorrectionError {}
+
+impl fmt::Display for CorrectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            CorrectionError::InFuture(time) => format!(
+                "Die Korrektur am {} liegt in der Zukunft.",
+                time.format(TIME_FORMAT)
+            ),
+            CorrectionError::AfterBoundary(boundary) => format!(
+                "Der Betriebstag endet am {} mit der automatischen Abmeldung, die Korrektur muss davor liegen.",
+                boundary.format("%d.%m.%Y %H:%M:%S")
+            ),
+            CorrectionError::BeforeEmployment(date) => format!(
+                "Die Person wurde erst am {} angelegt, davor kann nichts korrigiert werden.",
+                date.format("%d.%m.%Y")
+            ),
+            CorrectionError::AlreadyStatus(time, status) => format!(
+                "Die Person ist am {} bereits auf \"{}\" gesetzt.",
+                time.format(TIME_FORMAT),
+                status
+            ),
+            CorrectionError::BreaksSequence(next_time, status) => format!(
+                "Die nächste Statusänderung am {} setzt die Person ebenfalls auf \"{}\". Arbeit und Pause müssen sich abwechseln.",
+                next_time.format(TIME_FORMAT),
+                status
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+/// Check that setting the staff member to `status` at `time` keeps their status changes alternating within the
+/// business day. At the start of a business day everyone is away.
+pub fn check(
+    uuid: i32,
+    time: NaiveDateTime,
+    status: WorkStatus,
+    now: NaiveDateTime,
+    settings: &Settings,
+    storage: &mut dyn Storage,
+) -> Result<(), CorrectionError> {
+    if time > now {
+        return Err(CorrectionError::InFuture(time));
+    }
+
+    let business_date = settings.business_date(time);
+    let start_time = settings.business_day_start(business_date);
+    let end_time = start_time + Duration::days(1);
+    // The 6am event is one second before the next business day.
+    let boundary = end_time - Duration::seconds(1);
+    if time >= boundary {
+        return Err(CorrectionError::AfterBoundary(boundary));
+    }
+
+    if let Some(added_at) = storage.load_staff_added_at(uuid) {
+        let added_date = settings.business_date(added_at);
+        if business_date < added_date {
+            return Err(CorrectionError::BeforeEmployment(added_date));
+        }
+    }
+
+    let changes = storage
+        .load_staff_events_between(uuid, start_time, end_time)
+        .into_iter()
+        .filter_map(|eventt| match eventt.event {
+            WorkEvent::StatusChange(_, _, status) => Some((eventt.created_at, status)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let previous = changes
+        .iter()
+        .rfind(|(created_at, _)| *created_at <= time)
+        .map_or(WorkStatus::Away, |(_, status)| *status);
+    if previous == status {
+        return Err(CorrectionError::AlreadyStatus(time, status));
+    }
+    if let Some((next_time, next_status)) =
+        changes.iter().find(|(created_at, _)| *created_at > time)
+    {
+        if *next_status == status {
+            return Err(CorrectionError::BreaksSequence(*next_time, status));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::models::{NewStaffHistoryEntry, NewStaffMember, NewWorkEventT};
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn check_correction() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let aaron = db::insert_staff(
+            NewStaffMember::new(
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                String::new(),
+            )
+            .unwrap(),
+            &mut connection,
+        )
+        .unwrap();
+        let uuid = aaron.uuid();
+        let day = NaiveDate::from_ymd(2022, 6, 3);
+        db::insert_staff_history(
+            &[NewStaffHistoryEntry::added(uuid, day.and_hms(12, 0, 0))],
+            &mut connection,
+        )
+        .unwrap();
+        db::insert_event(
+            NewWorkEventT::new(
+                day.and_hms(20, 0, 0),
+                WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Working),
+            ),
+            &mut connection,
+        );
+        db::insert_event(
+            NewWorkEventT::new(
+                day.and_hms(23, 0, 0),
+                WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Away),
+            ),
+            &mut connection,
+        );
+
+        let settings = Settings::default();
+        let now = day.and_hms(12, 0, 0) + Duration::days(7);
+        let mut try_insert =
+            |time, status| check(uuid, time, status, now, &settings, &mut connection);
+
+        // forgot to sign off after a second shift
+        assert_eq!(
+            try_insert(day.succ().and_hms(0, 0, 0), WorkStatus::Working),
+            Ok(())
+        );
+        // the sign-in at 20:00 would become a second one
+        assert_eq!(
+            try_insert(day.and_hms(19, 0, 0), WorkStatus::Working),
+            Err(CorrectionError::BreaksSequence(
+                day.and_hms(20, 0, 0),
+                WorkStatus::Working
+            ))
+        );
+        assert_eq!(
+            try_insert(day.and_hms(19, 0, 0), WorkStatus::Away),
+            Err(CorrectionError::AlreadyStatus(
+                day.and_hms(19, 0, 0),
+                WorkStatus::Away
+            ))
+        );
+        assert_eq!(
+            try_insert(day.and_hms(21, 0, 0), WorkStatus::Away),
+            Err(CorrectionError::BreaksSequence(
+                day.and_hms(23, 0, 0),
+                WorkStatus::Away
+            ))
+        );
+        assert_eq!(
+            try_insert(day.succ().and_hms(5, 59, 59), WorkStatus::Working),
+            Err(CorrectionError::AfterBoundary(
+                day.succ().and_hms(5, 59, 59)
+            ))
+        );
+        assert_eq!(
+            try_insert(day.pred().and_hms(20, 0, 0), WorkStatus::Working),
+            Err(CorrectionError::BeforeEmployment(day))
+        );
+        assert_eq!(
+            try_insert(now + Duration::minutes(1), WorkStatus::Working),
+            Err(CorrectionError::InFuture(now + Duration::minutes(1)))
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use chrono::{Datelike, NaiveDate};
+use chrono::format::{DelayedFormat, StrftimeItems};
+use chrono::{Datelike, Duration, Locale, NaiveDate, NaiveDateTime, NaiveTime};
 
 pub trait NaiveDateExt
 where
@@ -25,3 +26,39 @@ impl NaiveDateExt for NaiveDate {
         self.with_day(last_day).unwrap()
     }
 }
+
+pub trait NaiveDateTimeExt {
+    fn business_date(self, cutoff: NaiveTime) -> NaiveDate;
+}
+
+impl NaiveDateTimeExt for NaiveDateTime {
+    /// The business day a point in time belongs to.
+    /// Everything after midnight but before the cutoff still belongs to the previous day's event.
+    fn business_date(self, cutoff: NaiveTime) -> NaiveDate {
+        if self.time() < cutoff {
+            self.date() - Duration::days(1)
+        } else {
+            self.date()
+        }
+    }
+}
+
+/// Localized formatting for the naive types, which chrono only offers for `Date` and `DateTime`.
+pub trait LocalizedFormat {
+    fn format_localized<'a>(
+        &self,
+        fmt: &'a str,
+        locale: Locale,
+    ) -> DelayedFormat<StrftimeItems<'a>>;
+}
+
+impl LocalizedFormat for NaiveDate {
+    fn format_localized<'a>(
+        &self,
+        fmt: &'a str,
+        locale: Locale,
+    ) -> DelayedFormat<StrftimeItems<'a>> {
+        let items = StrftimeItems::new_with_locale(fmt, locale);
+        DelayedFormat::new_with_locale(Some(*self), None, items, locale)
+    }
+}
@@ -1,26 +1,40 @@
 use crate::models::{
-    DBStaffMember, NewStaffMember, NewWorkEventT, PasswordHash, StaffMember, WorkEvent, WorkEventT,
-    WorkStatus,
+    Announcement, DBStaffMember, EventSession, Export, NewAnnouncement, NewClosedMonth,
+    NewEventSession, NewExport, NewShift, NewStaffHistoryEntry, NewStaffMember, NewWorkEventT,
+    PasswordHash, Shift, StaffHistoryEntry, StaffHistoryKind, StaffMember, StaffNames, WorkEvent,
+    WorkEventT, WorkStatus,
 };
 use crate::schema;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 use pbkdf2::{password_hash::PasswordVerifier, Pbkdf2};
 use std::borrow::Cow;
-use std::env;
+use std::{env, error, fmt};
 
 pub fn establish_connection() -> SqliteConnection {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    SqliteConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url))
+    connect(&database_url).expect(&format!("Error connecting to {}", database_url))
+}
+
+/// Open the database and fill in the columns that older versions of the program did not save, see `index_events`.
+/// Every program working on the database opens it like this, since the queries rely on these columns.
+pub fn connect(database_url: &str) -> ConnectionResult<SqliteConnection> {
+    let mut connection = SqliteConnection::establish(database_url)?;
+    match index_events(&mut connection) {
+        Ok(0) => {}
+        Ok(count) => log::info!("{} Events indiziert", count),
+        // Fails if the migrations did not run yet, which the self test reports.
+        Err(e) => log::error!("Fehler beim Indizieren der Events: {}", e),
+    }
+    Ok(connection)
 }
 
 ///*************************/
 /// Loading
 ///*************************/
 
-/// Load a staff member from the database.
-fn load_staff(connection: &mut SqliteConnection) -> Vec<DBStaffMember> {
+/// Load all active staff members from the database.
+pub fn load_staff(connection: &mut SqliteConnection) -> Vec<DBStaffMember> {
     use schema::staff::dsl::*;
     staff
         .filter(is_active.eq(true))
@@ -28,6 +42,33 @@ fn load_staff(connection: &mut SqliteConnection) -> Vec<DBStaffMember> {
         .expect("Error loading staff from DB")
 }
 
+/// Load all staff members, including deleted ones, e.g. to evaluate a period in which they still worked.
+pub fn load_staff_including_inactive(connection: &mut SqliteConnection) -> Vec<DBStaffMember> {
+    use schema::staff::dsl::*;
+    staff
+        .load::<DBStaffMember>(connection)
+        .expect("Error loading staff from DB")
+}
+
+/// Load the ids of all staff members, including deleted ones.
+pub fn load_all_staff_ids(connection: &mut SqliteConnection) -> Vec<i32> {
+    use schema::staff::dsl::*;
+    staff
+        .select(id)
+        .load::<i32>(connection)
+        .expect("Error loading staff from DB")
+}
+
+/// Load the current names of all staff members, including deleted ones.
+pub fn load_staff_names(connection: &mut SqliteConnection) -> StaffNames {
+    use schema::staff::dsl::*;
+    let names = staff
+        .select((id, name))
+        .load::<(i32, String)>(connection)
+        .expect("Error loading staff from DB");
+    StaffNames::new(names.into_iter().collect())
+}
+
 /// Load all events in the specified range from the database.
 pub fn load_events_between(
     start_time: Option<NaiveDateTime>,
@@ -43,18 +84,244 @@ pub fn load_events_between(
         .filter(created_at.ge(start_time))
         .filter(created_at.lt(end_time))
         .order_by(created_at.asc())
+        .select((id, created_at, event_json))
         .load::<WorkEventT>(connection)
         .expect("Error loading events");
 
     evts
 }
 
+/// Load the events of the given kinds in the specified range from the database.
+pub fn load_events_of_kinds_between(
+    kinds: &[&str],
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(kind.eq_any(kinds))
+        .filter(created_at.ge(start_time))
+        .filter(created_at.lt(end_time))
+        .order_by(created_at.asc())
+        .select((id, created_at, event_json))
+        .load::<WorkEventT>(connection)
+        .expect("Error loading events")
+}
+
+/// Load the latest events of the given kind, the latest first.
+pub fn load_latest_events_of_kind(
+    event_kind: &str,
+    limit: i64,
+    connection: &mut SqliteConnection,
+) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(kind.eq(event_kind))
+        .order_by((created_at.desc(), id.desc()))
+        .limit(limit)
+        .select((id, created_at, event_json))
+        .load::<WorkEventT>(connection)
+        .expect("Error loading events")
+}
+
+/// Time of the latest event before the given time. The 6am events are pregenerated, so later events may exist.
+pub fn load_last_event_time(
+    before: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Option<NaiveDateTime> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(created_at.lt(before))
+        .select(diesel::dsl::max(created_at))
+        .first::<Option<NaiveDateTime>>(connection)
+        .expect("Error loading events")
+}
+
+/// Load the events about the given staff member in the specified range from the database.
+pub fn load_staff_events_between(
+    uuid: i32,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(staff_id.eq(uuid))
+        .filter(created_at.ge(start_time))
+        .filter(created_at.lt(end_time))
+        .order_by(created_at.asc())
+        .select((id, created_at, event_json))
+        .load::<WorkEventT>(connection)
+        .expect("Error loading events")
+}
+
+/// Load the status changes of the given staff member and the 6am events in the specified range from the database.
+pub fn load_staff_status_events_between(
+    uuid: i32,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(
+            staff_id
+                .eq(uuid)
+                .and(kind.eq(WorkEvent::KIND_STATUS_CHANGE))
+                .or(kind.eq(WorkEvent::KIND_6AM)),
+        )
+        .filter(created_at.ge(start_time))
+        .filter(created_at.lt(end_time))
+        .order_by(created_at.asc())
+        .select((id, created_at, event_json))
+        .load::<WorkEventT>(connection)
+        .expect("Error loading events")
+}
+
+/// Load all events about the given staff member, e.g. to archive them.
+pub fn load_all_staff_events(uuid: i32, connection: &mut SqliteConnection) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    events
+        .filter(staff_id.eq(uuid))
+        .order_by(created_at.asc())
+        .select((id, created_at, event_json))
+        .load::<WorkEventT>(connection)
+        .expect("Error loading events")
+}
+
+/// Load the events needed to compute the status of staff members at the given time.
+/// Everyone is set to Away at 6am, so only the status changes since the last 6am event matter.
+pub fn load_status_events_before(
+    before: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<WorkEventT> {
+    use schema::events::dsl::*;
+
+    let last_6am = events
+        .filter(kind.eq(WorkEvent::KIND_6AM))
+        .filter(created_at.lt(before))
+        .select(diesel::dsl::max(created_at))
+        .first::<Option<NaiveDateTime>>(connection)
+        .expect("Error loading events")
+        .unwrap_or(NaiveDateTime::MIN);
+
+    load_events_of_kinds_between(
+        &[WorkEvent::KIND_6AM, WorkEvent::KIND_STATUS_CHANGE],
+        last_6am,
+        before,
+        connection,
+    )
+}
+
+/// Load all planned shifts that overlap the specified range from the database.
+pub fn load_shifts_between(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<Shift> {
+    use schema::shifts::dsl::*;
+
+    shifts
+        .filter(end_time.gt(start))
+        .filter(start_time.lt(end))
+        .order_by(start_time.asc())
+        .load::<Shift>(connection)
+        .expect("Error loading shifts")
+}
+
+/// Load the most recently generated exports, newest first.
+pub fn load_exports(limit: i64, connection: &mut SqliteConnection) -> Vec<Export> {
+    use schema::exports::dsl::*;
+
+    exports
+        .order_by(id.desc())
+        .limit(limit)
+        .load::<Export>(connection)
+        .expect("Error loading exports")
+}
+
+/// Load the detected events of the business days in the specified range, both inclusive.
+pub fn load_event_sessions_between(
+    first_date: NaiveDate,
+    last_date: NaiveDate,
+    connection: &mut SqliteConnection,
+) -> Vec<EventSession> {
+    use schema::event_sessions::dsl::*;
+
+    event_sessions
+        .filter(business_date.ge(first_date))
+        .filter(business_date.le(last_date))
+        .order_by(started_at.asc())
+        .load::<EventSession>(connection)
+        .expect("Error loading event sessions")
+}
+
+/// Load the newest announcement, which replaces all earlier ones.
+pub fn load_announcement(connection: &mut SqliteConnection) -> Option<Announcement> {
+    use schema::announcements::dsl::*;
+
+    announcements
+        .order_by(id.desc())
+        .first::<Announcement>(connection)
+        .optional()
+        .expect("Error loading announcement")
+}
+
+/// When the staff member was added, if that was recorded in the staff history.
+pub fn load_staff_added_at(uuid: i32, connection: &mut SqliteConnection) -> Option<NaiveDateTime> {
+    use schema::staff_history::dsl::*;
+
+    staff_history
+        .filter(staff_id.eq(uuid))
+        .order_by(changed_at.asc())
+        .load::<StaffHistoryEntry>(connection)
+        .expect("Error loading staff history")
+        .into_iter()
+        .find(|entry| entry.kind() == Some(StaffHistoryKind::Added))
+        .map(|entry| entry.changed_at)
+}
+
+/// Load the changes of the staff list in the specified range, oldest first.
+pub fn load_staff_history_between(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<StaffHistoryEntry> {
+    use schema::staff_history::dsl::*;
+
+    staff_history
+        .filter(changed_at.ge(start))
+        .filter(changed_at.lt(end))
+        .order_by(id.asc())
+        .load::<StaffHistoryEntry>(connection)
+        .expect("Error loading staff history")
+}
+
+/// Check if the month starting at the given first day was closed already.
+pub fn is_month_closed(first_day: NaiveDate, connection: &mut SqliteConnection) -> bool {
+    use schema::closed_months::dsl::*;
+
+    closed_months
+        .filter(month.eq(first_day))
+        .count()
+        .get_result::<i64>(connection)
+        .expect("Error loading closed months")
+        > 0
+}
+
 pub fn load_state(
     current_time: NaiveDateTime,
     connection: &mut SqliteConnection,
 ) -> Vec<StaffMember> {
     let loaded_staff = load_staff(connection);
-    let previous_events = load_events_between(None, Some(current_time), connection);
+    let previous_events = load_status_events_before(current_time, connection);
     let staff = staff_compute_status(loaded_staff, &previous_events);
 
     staff
@@ -64,22 +331,74 @@ pub fn load_state(
 /// Saving
 ///*************************/
 
+/// Saving a staff member failed.
+#[derive(Debug)]
+pub enum SaveStaffError {
+    Diesel(diesel::result::Error),
+    /// The record was changed by another terminal since it was loaded. Contains the record as it is now, boxed since
+    /// it is much larger than the other variant.
+    Conflict(Box<DBStaffMember>),
+}
+
+impl error::Error for SaveStaffError {}
+
+impl fmt::Display for SaveStaffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveStaffError::Diesel(e) => fmt::Display::fmt(e, f),
+            SaveStaffError::Conflict(record) => write!(
+                f,
+                "{} wurde zwischenzeitlich an einem anderen Terminal geändert. Bitte nochmal versuchen.",
+                record.name()
+            ),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for SaveStaffError {
+    fn from(e: diesel::result::Error) -> Self {
+        SaveStaffError::Diesel(e)
+    }
+}
+
 /// Save a single staff member into the database.
+/// The record is only overwritten if it still has the version the staff member was loaded with,
+/// afterwards the staff member has the new version.
 pub fn save_staff_member(
-    staff_member: &StaffMember,
+    staff_member: &mut StaffMember,
     connection: &mut SqliteConnection,
-) -> QueryResult<()> {
-    let staff_member = DBStaffMember::from(Cow::Borrowed(staff_member));
+) -> Result<(), SaveStaffError> {
+    use schema::staff::dsl::*;
 
-    diesel::update(&staff_member)
-        .set(&staff_member)
+    let loaded_version = staff_member.version;
+    let record =
+        DBStaffMember::from(Cow::Borrowed(&*staff_member)).with_version(loaded_version + 1);
+
+    let updated = diesel::update(staff.find(record.uuid()).filter(version.eq(loaded_version)))
+        .set(&record)
         .execute(connection)?;
+    if updated == 0 {
+        let current = staff
+            .find(record.uuid())
+            .first::<DBStaffMember>(connection)?;
+        return Err(SaveStaffError::Conflict(Box::new(current)));
+    }
+
+    staff_member.version = record.version();
     Ok(())
 }
 
-pub fn save_staff(staff_v: &[StaffMember], connection: &mut SqliteConnection) -> QueryResult<()> {
+/// Save all staff members. Those that were changed by another terminal in the meantime are skipped, so that
+/// their newer data is kept.
+pub fn save_staff(
+    staff_v: &mut [StaffMember],
+    connection: &mut SqliteConnection,
+) -> QueryResult<()> {
     for staff_member in staff_v {
-        save_staff_member(staff_member, connection)?;
+        match save_staff_member(staff_member, connection) {
+            Ok(()) | Err(SaveStaffError::Conflict(_)) => {}
+            Err(SaveStaffError::Diesel(e)) => return Err(e),
+        }
     }
     Ok(())
 }
@@ -108,23 +427,127 @@ pub fn insert_staff(
     Ok(newly_inserted.with_status(WorkStatus::Away))
 }
 
-pub fn insert_event(new_event: NewWorkEventT, connection: &mut SqliteConnection) -> WorkEventT {
+/// Insert an event and return it as saved. Returns None if the event is a duplicate of a status change
+/// that was saved in the same second, see `NewWorkEventT::dedup_key`.
+pub fn insert_event(
+    new_event: NewWorkEventT,
+    connection: &mut SqliteConnection,
+) -> Option<WorkEventT> {
     use schema::events::dsl::*;
 
-    diesel::insert_into(events)
-        .values(new_event)
+    // Ignoring a conflicting token makes it safe to retry the insert, the event is only saved once.
+    diesel::insert_or_ignore_into(events)
+        .values(&new_event)
         .execute(connection)
         .expect("Error inserting new event");
 
-    let mut newly_inserted = events
-        .order_by(id.desc())
-        .limit(1)
-        .load::<WorkEventT>(connection)
-        .expect("Error loading newly inserted event");
+    // Look up the event by its token since other terminals may have inserted events in the meantime.
+    // If it is missing the insert was ignored because of the dedup key.
+    events
+        .filter(token.eq(new_event.token()))
+        .select((id, created_at, event_json))
+        .first::<WorkEventT>(connection)
+        .optional()
+        .expect("Error loading newly inserted event")
+}
 
-    let newly_inserted = newly_inserted.remove(0);
+/// Insert the 6am event at the given time unless there already is one, e.g. because it was pregenerated.
+/// Returns whether it was inserted.
+pub fn ensure_6am_event(time: NaiveDateTime, connection: &mut SqliteConnection) -> bool {
+    use schema::events::dsl::*;
+
+    let existing = events
+        .filter(kind.eq(WorkEvent::KIND_6AM))
+        .filter(created_at.eq(time))
+        .count()
+        .get_result::<i64>(connection)
+        .expect("Error loading events");
+    if existing > 0 {
+        return false;
+    }
+    insert_event(NewWorkEventT::new(time, WorkEvent::_6am), connection).is_some()
+}
+
+pub fn insert_events(
+    new_events: &[NewWorkEventT],
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::events::dsl::*;
+
+    diesel::insert_or_ignore_into(events)
+        .values(new_events)
+        .execute(connection)
+}
+
+pub fn insert_shifts(
+    new_shifts: &[NewShift],
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::shifts::dsl::*;
+
+    diesel::insert_into(shifts)
+        .values(new_shifts)
+        .execute(connection)
+}
+
+pub fn insert_closed_month(
+    closed_month: NewClosedMonth,
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::closed_months::dsl::*;
+
+    diesel::insert_into(closed_months)
+        .values(closed_month)
+        .execute(connection)
+}
+
+pub fn insert_export(
+    new_export: NewExport,
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::exports::dsl::*;
+
+    diesel::insert_into(exports)
+        .values(new_export)
+        .execute(connection)
+}
 
-    newly_inserted
+pub fn insert_announcement(
+    new_announcement: NewAnnouncement,
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::announcements::dsl::*;
+
+    diesel::insert_into(announcements)
+        .values(new_announcement)
+        .execute(connection)
+}
+
+pub fn insert_staff_history(
+    entries: &[NewStaffHistoryEntry],
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::staff_history::dsl::*;
+
+    diesel::insert_into(staff_history)
+        .values(entries)
+        .execute(connection)
+}
+
+/// Replace the detected events of a business day, e.g. after the events of that day were corrected.
+pub fn replace_event_sessions(
+    date: NaiveDate,
+    sessions: &[NewEventSession],
+    connection: &mut SqliteConnection,
+) -> QueryResult<usize> {
+    use schema::event_sessions::dsl::*;
+
+    connection.transaction(|connection| {
+        diesel::delete(event_sessions.filter(business_date.eq(date))).execute(connection)?;
+        diesel::insert_into(event_sessions)
+            .values(sessions)
+            .execute(connection)
+    })
 }
 
 pub fn insert_password(new_password: PasswordHash, connection: &mut SqliteConnection) {
@@ -159,6 +582,25 @@ pub fn verify_password(password: &str, connection: &mut SqliteConnection) -> boo
     return false;
 }
 
+/// Fill in the kind and staff member of events that were saved before these columns existed.
+pub fn index_events(connection: &mut SqliteConnection) -> QueryResult<usize> {
+    use schema::events::dsl::*;
+
+    connection.transaction(|connection| {
+        let unindexed = events
+            .filter(kind.is_null())
+            .select((id, event_json))
+            .load::<(i32, WorkEvent)>(connection)?;
+
+        for (event_id, event) in &unindexed {
+            diesel::update(events.find(event_id))
+                .set((kind.eq(event.kind()), staff_id.eq(event.staff_id())))
+                .execute(connection)?;
+        }
+        Ok(unindexed.len())
+    })
+}
+
 fn staff_compute_status(staff: Vec<DBStaffMember>, events: &[WorkEventT]) -> Vec<StaffMember> {
     staff
         .into_iter()
@@ -170,19 +612,82 @@ pub fn staff_member_compute_status(
     staff_member: DBStaffMember,
     previous_events: &[WorkEventT],
 ) -> StaffMember {
+    let status = compute_status(staff_member.uuid(), previous_events);
+    staff_member.with_status(status)
+}
+
+/// The work status of a staff member after the given events. Everyone is away after the 6am boundary.
+pub fn compute_status(uuid: i32, previous_events: &[WorkEventT]) -> WorkStatus {
     for eventt in previous_events.iter().rev() {
         match eventt.event {
-            WorkEvent::StatusChange(id, _, status) if id == staff_member.uuid() => {
-                return staff_member.with_status(status);
+            WorkEvent::StatusChange(id, _, status) if id == uuid => return status,
+            WorkEvent::_6am => return WorkStatus::Away,
+            _ => {}
+        }
+    }
+
+    WorkStatus::Away
+}
+
+/// Changes of the staff list made by another terminal that shares the database.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StaffChanges {
+    pub added: Vec<i32>,
+    pub removed: Vec<i32>,
+    pub changed: Vec<i32>,
+}
+
+impl StaffChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Bring the local staff list up to date with the records from the database.
+/// The work status of known staff members is kept, new staff members get their status from the previous events.
+pub fn merge_staff(
+    staff: &mut Vec<StaffMember>,
+    records: Vec<DBStaffMember>,
+    previous_events: &[WorkEventT],
+) -> StaffChanges {
+    let mut changes = StaffChanges::default();
+
+    staff.retain(|staff_member| {
+        let is_active = records
+            .iter()
+            .any(|record| record.uuid() == staff_member.uuid());
+        if !is_active {
+            changes.removed.push(staff_member.uuid());
+        }
+        is_active
+    });
+
+    for record in records {
+        match StaffMember::get_by_uuid_mut(staff, record.uuid()) {
+            Some(staff_member) => {
+                if !staff_member.matches_record(&record) {
+                    changes.changed.push(record.uuid());
+                    *staff_member = record.with_status(staff_member.status);
+                }
             }
-            WorkEvent::_6am => {
-                return staff_member.with_status(WorkStatus::Away);
+            None => {
+                changes.added.push(record.uuid());
+                staff.push(staff_member_compute_status(record, previous_events));
             }
-            _ => {}
         }
     }
 
-    return staff_member.with_status(WorkStatus::Away);
+    changes
+}
+
+/// Reload the staff list from the database, see `merge_staff`.
+pub fn refresh_staff(
+    staff: &mut Vec<StaffMember>,
+    previous_events: &[WorkEventT],
+    connection: &mut SqliteConnection,
+) -> StaffChanges {
+    let records = load_staff(connection);
+    merge_staff(staff, records, previous_events)
 }
 
 pub fn delete_staff_member(
@@ -198,8 +703,67 @@ pub fn delete_staff_member(
             is_active.eq(false),
             pin.eq(None::<String>),
             cardid.eq(None::<String>),
+            version.eq(version + 1),
         ))
         .execute(connection)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn connect_indexes_old_events() {
+        let path = env::temp_dir().join("stechuhr-test-index.sqlite3");
+        let _ = std::fs::remove_file(&path);
+        let database_url = path.to_string_lossy().into_owned();
+        let time = NaiveDate::from_ymd(2022, 3, 1).and_hms(20, 0, 0);
+
+        // An event saved before the kind and staff member were saved in their own columns.
+        let mut connection = SqliteConnection::establish(&database_url).unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let uuid = insert_staff(
+            NewStaffMember::new(
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                String::new(),
+            )
+            .unwrap(),
+            &mut connection,
+        )
+        .unwrap()
+        .uuid();
+        insert_event(
+            NewWorkEventT::new(
+                time,
+                WorkEvent::StatusChange(uuid, String::from("Aaron"), WorkStatus::Working),
+            ),
+            &mut connection,
+        );
+        diesel::update(schema::events::table)
+            .set((
+                schema::events::kind.eq(None::<String>),
+                schema::events::staff_id.eq(None::<i32>),
+            ))
+            .execute(&mut connection)
+            .unwrap();
+        assert!(
+            load_status_events_before(time + chrono::Duration::hours(1), &mut connection)
+                .is_empty()
+        );
+        drop(connection);
+
+        let mut connection = connect(&database_url).unwrap();
+        let staff = load_state(time + chrono::Duration::hours(1), &mut connection);
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(staff[0].status, WorkStatus::Working);
+    }
+}
@@ -0,0 +1,93 @@
+//! Free space on the disk of the export directory. An export that runs out of space fails halfway and leaves a
+//! truncated file behind, so we check before writing and warn on the dashboard while the disk is nearly full.
+//! The space is queried with df, so it is not known on systems without it.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{error, fmt, io};
+
+/// Free space that must be left for an export to be started. Exports are at most a few megabytes.
+pub const EXPORT_RESERVE_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowDiskSpace {
+    pub dir: PathBuf,
+    pub available: u64,
+}
+
+impl error::Error for LowDiskSpace {}
+
+impl fmt::Display for LowDiskSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Auf dem Datenträger von {} sind nur noch {} frei. Bitte Platz schaffen, bevor exportiert wird.",
+            self.dir.display(),
+            format_bytes(self.available)
+        )
+    }
+}
+
+/// Format a number of bytes in megabytes, as shown to the users.
+pub fn format_bytes(bytes: u64) -> String {
+    format!("{} MB", bytes / (1024 * 1024))
+}
+
+/// Free space in bytes on the disk containing `path`. The path need not exist yet, e.g. an export directory that
+/// is created on the first export, then its closest existing parent is used.
+pub fn available_bytes(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|dir| dir.exists())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let output = Command::new("df").arg("-Pk").arg(existing).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Ausgabe von df konnte nicht gelesen werden",
+        )
+    })
+}
+
+/// Read the available space from the POSIX output of `df -Pk`, which is given in blocks of 1024 bytes.
+fn parse_df(output: &str) -> Option<u64> {
+    let kilobytes = output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Check that there is enough space left for an export into `dir`.
+/// If the free space cannot be determined we try anyway, writing the export reports its own errors.
+pub fn preflight(dir: &Path) -> Result<(), LowDiskSpace> {
+    match available_bytes(dir) {
+        Ok(available) if available < EXPORT_RESERVE_BYTES => Err(LowDiskSpace {
+            dir: dir.to_owned(),
+            available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_df_output() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda2         61255492  47000000  11116740      81% /\n";
+        assert_eq!(parse_df(output), Some(11116740 * 1024));
+        assert_eq!(parse_df("Filesystem 1024-blocks Used Available\n"), None);
+        assert_eq!(parse_df(""), None);
+    }
+}
@@ -0,0 +1,220 @@
+//! Calculation of the hours worked by each staff member, as used by the statistics tab. Other tools, e.g. a
+//! web report, can use it to compute exactly the same hours from the events of the database.
+//!
+//! The semantics of an evaluation:
+//! - A staff member works from a status change to `Working` until the next status change to `Away` or the 6am
+//!   event, which signs everybody off at the end of the business day. Only the first 6am event of a day counts.
+//! - Each interval of work is split into the buckets given by `BucketBounds`, and on Sundays and holidays (and
+//!   Saturdays) into their own buckets if enabled. Minutes are rounded up per bucket, see `WorkDuration::num_minutes`.
+//! - Intervals shorter than the minimum of `BucketBounds::with_min_interval` are not counted, e.g. from swiping
+//!   twice by accident. They are reported as `SoftStatisticsError::ShortInterval`.
+//! - Intervals longer than the maximum of `BucketBounds::with_max_interval` are counted but reported as
+//!   `SoftStatisticsError::ShiftTooLong`, since somebody probably forgot to sign off.
+//! - Inconsistencies in the events, e.g. signing in twice, are collected as `SoftStatisticsError` and do not stop
+//!   the evaluation. An `EvaluationError` means that no result can be given at all.
+mod event_sm;
+mod time_eval;
+
+use crate::models::StaffMember;
+use crate::rest_period::RestViolation;
+use crate::youth_protection::Violation;
+use chrono::{Duration, NaiveDateTime};
+use std::{error, fmt};
+
+pub use event_sm::{evaluate_hours_for_events, EventSM};
+pub use time_eval::{BucketBounds, WorkDuration};
+
+/// The result of the evaluation of one staff member.
+#[derive(Debug)]
+pub struct PersonHours {
+    /// The staff member with the status at the start of the evaluation period.
+    pub staff_member: StaffMember,
+    pub duration: WorkDuration,
+    /// Sum of the lengths of all intervals in which the staff member was working, to check the buckets against.
+    pub interval_total: Duration,
+    /// Number of manual corrections during the evaluation period.
+    pub corrections: usize,
+    /// Each interval in which the staff member was working, e.g. for the export of single shifts.
+    pub intervals: Vec<IntervalMinutes>,
+}
+
+impl PersonHours {
+    fn new(staff_member: StaffMember) -> Self {
+        Self {
+            staff_member,
+            duration: WorkDuration::zero(),
+            interval_total: Duration::zero(),
+            corrections: 0,
+            intervals: Vec::new(),
+        }
+    }
+}
+
+/// A contiguous interval of work with its minutes in each bucket, in the order of `WorkDuration`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalMinutes {
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub minutes: [i64; 6],
+}
+
+/// The result of an evaluation, with the staff members in the order in which they were given.
+#[derive(Debug)]
+pub struct Evaluation {
+    pub hours: Vec<PersonHours>,
+    pub soft_errors: Vec<SoftStatisticsError>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EvaluationError {
+    DurationError(Duration, Duration),
+    /// The buckets of a staff member do not add up to their work time, i.e. a bug in `time_eval`.
+    Unbalanced(String, Duration, Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SoftStatisticsError {
+    AlreadyWorking(NaiveDateTime, String),
+    AlreadyAway(NaiveDateTime, String),
+    StaffStillWorking(NaiveDateTime, String),
+    /// A staff member under 18 worked longer or later than allowed.
+    YouthProtection(String, Violation),
+    /// A staff member signed in again before the minimum rest was over, see `rest_period`.
+    RestPeriod(String, RestViolation),
+    /// An interval of work that was too short to be counted, with its start and length.
+    ShortInterval(NaiveDateTime, String, Duration),
+    /// An interval of work that was implausibly long, with its start and length. It is counted nevertheless.
+    ShiftTooLong(NaiveDateTime, String, Duration),
+}
+
+impl error::Error for EvaluationError {}
+impl error::Error for SoftStatisticsError {}
+
+impl SoftStatisticsError {
+    /// Name of the staff member that the error is about.
+    pub fn staff_name(&self) -> &str {
+        match self {
+            Self::AlreadyWorking(_, name)
+            | Self::AlreadyAway(_, name)
+            | Self::StaffStillWorking(_, name)
+            | Self::ShortInterval(_, name, _)
+            | Self::ShiftTooLong(_, name, _)
+            | Self::YouthProtection(name, _)
+            | Self::RestPeriod(name, _) => name,
+        }
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Self::DurationError(d1, d2) => {
+                format!("Error adding durations {} and {}", d1, d2)
+            }
+            Self::Unbalanced(name, buckets, intervals) => format!(
+                "Fehler in der Auswertung: Die Zeitbereiche von {} ergeben {} Sekunden, gearbeitet wurden aber {} Sekunden. Bitte Adrian Bescheid sagen.",
+                name,
+                buckets.num_seconds(),
+                intervals.num_seconds()
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+impl fmt::Display for SoftStatisticsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Self::AlreadyWorking(date, name) => format!(
+                "Um {} wurde der Status von {} auf 'Arbeiten' gesetzt während er/sie schon am Arbeiten war. Inkonsistente Datenbank, bitte Adrian Bescheid sagen.",
+                date, name
+            ),
+            Self::AlreadyAway(date, name) => format!(
+                "Um {} wurde der Status von {} auf 'Pause' gesetzt während er/sie schon in der Pause war. Inkonsistente Datenbank, bitte Adrian Bescheid sagen.",
+                date, name
+            ),
+            Self::StaffStillWorking(date, name) => format!(
+                "Um {} arbeitet {} noch um 6 Uhr morgens. Es wurde wahrscheinlich vergessen sich abzumelden.",
+                date, name
+            ),
+            Self::YouthProtection(name, violation @ Violation::Night(_)) => format!(
+                "Jugendschutz bei {}: {}, die Zeit nach Mitternacht wurde nicht angerechnet",
+                name, violation
+            ),
+            Self::YouthProtection(name, violation) => {
+                format!("Jugendschutz bei {}: {}", name, violation)
+            }
+            Self::RestPeriod(name, violation) => {
+                format!("Ruhezeit bei {}: {}", name, violation)
+            }
+            Self::ShortInterval(date, name, duration) => format!(
+                "Um {} war {} nur {} Sekunden am Arbeiten, vermutlich wurde doppelt gestempelt. Die Zeit wurde nicht angerechnet.",
+                date,
+                name,
+                duration.num_seconds()
+            ),
+            Self::ShiftTooLong(date, name, duration) => format!(
+                "Ab {} war {} {} Stunden am Stück am Arbeiten, vermutlich wurde vergessen sich abzumelden. Die Zeit wurde angerechnet, bitte prüfen.",
+                date,
+                name,
+                duration.num_hours()
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DBStaffMember, WorkEvent, WorkEventT, WorkStatus};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn evaluate_split_shift() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let date = NaiveDate::from_ymd(2000, 1, 1);
+        let status = |id, time, status| {
+            WorkEventT::new(
+                id,
+                time,
+                WorkEvent::StatusChange(1, String::from("Aaron"), status),
+            )
+        };
+        let events = vec![
+            status(1, date.and_hms(18, 0, 0), WorkStatus::Working),
+            status(2, date.and_hms(22, 0, 0), WorkStatus::Away),
+            status(3, date.succ().and_hms(1, 0, 0), WorkStatus::Working),
+            WorkEventT::new(4, date.succ().and_hms(5, 59, 59), WorkEvent::_6am),
+        ];
+
+        let evaluation = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &[],
+            date.and_hms(6, 0, 0),
+            &BucketBounds::new([6, 20, 0]),
+        )
+        .unwrap();
+
+        let hours = &evaluation.hours[0];
+        assert_eq!(hours.staff_member.name, "Aaron");
+        assert_eq!(hours.duration.num_minutes(), [120, 120, 300, 0, 0, 0]);
+        assert_eq!(hours.interval_total, Duration::seconds(9 * 60 * 60 - 1));
+        assert_eq!(hours.intervals.len(), 2);
+        assert_eq!(
+            evaluation.soft_errors,
+            vec![SoftStatisticsError::StaffStillWorking(
+                date.succ().and_hms(5, 59, 59),
+                String::from("Aaron")
+            )]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use super::{
+    BucketBounds, Evaluation, EvaluationError, IntervalMinutes, PersonHours, SoftStatisticsError,
+    WorkDuration,
+};
+use crate::{
+    db,
+    models::{DBStaffMember, StaffMember, WorkEvent, WorkEventT, WorkStatus},
+    youth_protection,
+};
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+
+enum EventSMLabel {
+    Working(NaiveDateTime),
+    Away,
+}
+
+/// State machine to compute the WorkDuration of a StaffMember based on a collection of events.
+pub struct EventSM<'a> {
+    bounds: &'a BucketBounds,
+    hours_raw: PersonHours,
+    soft_errors: Vec<SoftStatisticsError>,
+    label: EventSMLabel,
+}
+
+impl<'a> EventSM<'a> {
+    pub fn new(
+        staff_member: &StaffMember,
+        initial_start_time: Option<NaiveDateTime>,
+        bounds: &'a BucketBounds,
+    ) -> Self {
+        let label = if let Some(start_time) = initial_start_time {
+            EventSMLabel::Working(start_time)
+        } else {
+            EventSMLabel::Away
+        };
+
+        Self {
+            bounds,
+            hours_raw: PersonHours::new(staff_member.clone()),
+            soft_errors: Vec::new(),
+            label,
+        }
+    }
+
+    fn append_soft_error(&mut self, error: SoftStatisticsError) {
+        self.soft_errors.push(error);
+    }
+
+    /// Report an interval that is longer than the longest allowed shift, e.g. because the sign-off was forgotten.
+    fn check_too_long(&mut self, start_time: NaiveDateTime, end_time: NaiveDateTime) {
+        if self.bounds.is_too_long(start_time, end_time) {
+            self.append_soft_error(SoftStatisticsError::ShiftTooLong(
+                start_time,
+                self.hours_raw.staff_member.name.clone(),
+                end_time - start_time,
+            ));
+        }
+    }
+
+    fn add_time(
+        &mut self,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<(), EvaluationError> {
+        // Work of minors after midnight is not credited, it is reported as a youth protection error instead.
+        let (start_time, end_time) = if self.hours_raw.staff_member.is_minor {
+            match youth_protection::credited_interval(start_time, end_time) {
+                Some(interval) => interval,
+                None => return Ok(()),
+            }
+        } else {
+            (start_time, end_time)
+        };
+        let additional_work_time =
+            WorkDuration::from_start_end_time(start_time, end_time, self.bounds);
+        let new_duration = self.hours_raw.duration.checked_add(&additional_work_time)?;
+        self.hours_raw.duration = new_duration;
+        self.hours_raw.intervals.push(IntervalMinutes {
+            start_time,
+            end_time,
+            minutes: additional_work_time.num_minutes(),
+        });
+        let interval_length = WorkDuration::interval_length(start_time, end_time);
+        self.hours_raw.interval_total = self
+            .hours_raw
+            .interval_total
+            .checked_add(&interval_length)
+            .ok_or(EvaluationError::DurationError(
+                self.hours_raw.interval_total,
+                interval_length,
+            ))?;
+        Ok(())
+    }
+
+    pub fn process(&mut self, event: &WorkEventT) -> Result<(), EvaluationError> {
+        // Corrections do not change the status, they are only counted.
+        if let WorkEvent::Correction(uuid, _, _) = &event.event {
+            if *uuid == self.hours_raw.staff_member.uuid() {
+                self.hours_raw.corrections += 1;
+            }
+            return Ok(());
+        }
+
+        match self.label {
+            EventSMLabel::Away => match event.event {
+                WorkEvent::StatusChange(uuid, _, WorkStatus::Working)
+                    if self.hours_raw.staff_member.uuid() == uuid =>
+                {
+                    self.label = EventSMLabel::Working(event.created_at);
+                    Ok(())
+                }
+                WorkEvent::StatusChange(uuid, _, WorkStatus::Away)
+                    if self.hours_raw.staff_member.uuid() == uuid =>
+                {
+                    self.append_soft_error(SoftStatisticsError::AlreadyAway(
+                        event.created_at,
+                        self.hours_raw.staff_member.name.clone(),
+                    ));
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+            EventSMLabel::Working(start_time) => match event.event {
+                WorkEvent::StatusChange(uuid, _, WorkStatus::Away)
+                    if self.hours_raw.staff_member.uuid() == uuid =>
+                {
+                    if self.bounds.is_too_short(start_time, event.created_at) {
+                        self.append_soft_error(SoftStatisticsError::ShortInterval(
+                            start_time,
+                            self.hours_raw.staff_member.name.clone(),
+                            event.created_at - start_time,
+                        ));
+                    } else {
+                        self.check_too_long(start_time, event.created_at);
+                        self.add_time(start_time, event.created_at)?;
+                    }
+                    self.label = EventSMLabel::Away;
+                    Ok(())
+                }
+                WorkEvent::StatusChange(uuid, _, WorkStatus::Working)
+                    if self.hours_raw.staff_member.uuid() == uuid =>
+                {
+                    self.append_soft_error(SoftStatisticsError::AlreadyWorking(
+                        event.created_at,
+                        self.hours_raw.staff_member.name.clone(),
+                    ));
+                    Ok(())
+                }
+                WorkEvent::_6am => {
+                    self.append_soft_error(SoftStatisticsError::StaffStillWorking(
+                        event.created_at,
+                        self.hours_raw.staff_member.name.clone(),
+                    ));
+                    self.check_too_long(start_time, event.created_at);
+                    self.add_time(start_time, event.created_at)?;
+                    self.label = EventSMLabel::Away;
+                    Ok(())
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    pub fn finish(self) -> (PersonHours, Vec<SoftStatisticsError>) {
+        (self.hours_raw, self.soft_errors)
+    }
+}
+
+/// Compute the work time of the given staff members from the events of the evaluation period, which starts at
+/// `start_time` and must be ordered from oldest to newest. `previous_events` are the status changes before the
+/// period and only determine who is already working at `start_time`.
+/// Soft errors are inconsistencies in the events that are reported but do not stop the evaluation.
+pub fn evaluate_hours_for_events(
+    raw_staff: Vec<DBStaffMember>,
+    events: &[WorkEventT],
+    previous_events: &[WorkEventT],
+    start_time: NaiveDateTime,
+    bounds: &BucketBounds,
+) -> Result<Evaluation, EvaluationError> {
+    // Set the initial status for staff members.
+    // Atm we only do evaluation starting at 6am on the 1st of the month, so no one will be working as we set everyone to non-working at 6am.
+    let staff = raw_staff
+        .into_iter()
+        // Compute the initial status.
+        .map(|staff_member| db::staff_member_compute_status(staff_member, previous_events))
+        .collect::<Vec<_>>();
+
+    let mut events_by_staff = events_by_staff_member(&staff, events);
+
+    let (hours, soft_errors): (Vec<PersonHours>, Vec<Vec<SoftStatisticsError>>) = staff
+        .iter()
+        // Associate with each staff member a WorkDuration, which counts the minutes of work time
+        .map(|staff_member| {
+            let events = events_by_staff
+                .remove(&staff_member.uuid())
+                .unwrap_or_default();
+            evaluate_hours_for_staff_member(staff_member, &events, start_time, bounds)
+        })
+        .collect::<Result<Vec<(PersonHours, Vec<SoftStatisticsError>)>, EvaluationError>>()?
+        .into_iter()
+        .unzip();
+
+    Ok(Evaluation {
+        hours,
+        soft_errors: soft_errors.into_iter().flatten().collect(),
+    })
+}
+
+/// Split the events by staff member so that each state machine only sees the events concerning them.
+/// The 6am events concern everybody. Only the first one of a day is used, duplicates, e.g. from pregenerating
+/// them twice, would sign off again everyone who started working in between.
+fn events_by_staff_member<'e>(
+    staff: &[StaffMember],
+    events: &'e [WorkEventT],
+) -> HashMap<i32, Vec<&'e WorkEventT>> {
+    let mut events_by_staff = staff
+        .iter()
+        .map(|staff_member| (staff_member.uuid(), Vec::new()))
+        .collect::<HashMap<_, _>>();
+    let mut boundary_dates = HashSet::new();
+
+    for eventt in events {
+        match eventt.event.staff_id() {
+            Some(uuid) => {
+                if let Some(staff_events) = events_by_staff.get_mut(&uuid) {
+                    staff_events.push(eventt);
+                }
+            }
+            None if eventt.event == WorkEvent::_6am => {
+                if !boundary_dates.insert(eventt.created_at.date()) {
+                    continue;
+                }
+                for staff_events in events_by_staff.values_mut() {
+                    staff_events.push(eventt);
+                }
+            }
+            None => {}
+        }
+    }
+
+    events_by_staff
+}
+
+/// Create a EventSM state machine and feed all WorkEventT events to it to compute the StaffMemberHours.
+fn evaluate_hours_for_staff_member(
+    staff_member: &StaffMember,
+    events: &[&WorkEventT],
+    start_time: NaiveDateTime,
+    bounds: &BucketBounds,
+) -> Result<(PersonHours, Vec<SoftStatisticsError>), EvaluationError> {
+    let initial_start_time = if staff_member.status == WorkStatus::Working {
+        Some(start_time)
+    } else {
+        None
+    };
+
+    let mut event_sm = EventSM::new(staff_member, initial_start_time, bounds);
+
+    for event in events {
+        event_sm.process(event)?;
+    }
+
+    let (hours, mut soft_errors) = event_sm.finish();
+    // Safety net for changes to time_eval: the buckets must add up to the time worked.
+    let bucket_total = hours.duration.total();
+    if bucket_total != hours.interval_total {
+        return Err(EvaluationError::Unbalanced(
+            staff_member.name.clone(),
+            bucket_total,
+            hours.interval_total,
+        ));
+    }
+    if staff_member.is_minor {
+        let end_time = events.last().map_or(start_time, |eventt| eventt.created_at);
+        let intervals =
+            youth_protection::work_intervals(events.iter().copied(), staff_member.uuid(), end_time);
+        // The evaluation starts at the cutoff time of the first business day.
+        soft_errors.extend(
+            youth_protection::check(&intervals, start_time.time())
+                .into_iter()
+                .map(|violation| {
+                    SoftStatisticsError::YouthProtection(staff_member.name.clone(), violation)
+                }),
+        );
+    }
+
+    Ok((hours, soft_errors))
+}
@@ -0,0 +1,259 @@
+use super::EvaluationError;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use std::cmp::min;
+use std::collections::HashSet;
+
+type Secs = i64;
+const SECS_PER_HOUR: Secs = 60 * 60;
+
+const SECS_PER_DAY: Secs = 24 * SECS_PER_HOUR;
+
+/// Offset of the buckets for Sundays and holidays in a WorkDuration.
+const SUNDAY_OFFSET: usize = 3;
+
+/// The three time buckets of a day, given by the hour at which each one starts.
+/// A bucket that starts at the same hour as the next one is empty and never gets any time.
+/// Time on Sundays and holidays, and optionally Saturdays, can be counted in three more buckets with the same bounds.
+#[derive(Debug, Clone)]
+pub struct BucketBounds {
+    starts: [Secs; 3],
+    /* None if Sundays and holidays are counted like any other day */
+    holidays: Option<HashSet<NaiveDate>>,
+    saturdays: bool,
+    /* shorter intervals of work are not counted, see `BucketBounds::with_min_interval` */
+    min_interval: Duration,
+    /* longer intervals of work are reported, see `BucketBounds::with_max_interval` */
+    max_interval: Option<Duration>,
+}
+
+impl BucketBounds {
+    pub fn new(bucket_starts: [u32; 3]) -> Self {
+        let [b1, b2, b3] = bucket_starts;
+        Self {
+            starts: [
+                b1 as Secs * SECS_PER_HOUR,
+                b2 as Secs * SECS_PER_HOUR,
+                b3 as Secs * SECS_PER_HOUR,
+            ],
+            holidays: None,
+            saturdays: false,
+            min_interval: Duration::zero(),
+            max_interval: None,
+        }
+    }
+
+    /// Count the time on Sundays and the given holidays separately.
+    pub fn with_sunday_buckets(mut self, holidays: &[NaiveDate]) -> Self {
+        self.holidays = Some(holidays.iter().copied().collect());
+        self
+    }
+
+    /// Also count the time on Saturdays in the buckets of Sundays, if they are counted separately.
+    pub fn with_saturdays(mut self, saturdays: bool) -> Self {
+        self.saturdays = saturdays;
+        self
+    }
+
+    /// Do not count intervals of work that are shorter than the given duration, e.g. from swiping twice by accident.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Whether an interval of work is too short to be counted.
+    pub fn is_too_short(&self, start_time: NaiveDateTime, end_time: NaiveDateTime) -> bool {
+        end_time - start_time < self.min_interval
+    }
+
+    /// Report intervals of work that are longer than the given duration, e.g. when somebody forgot to sign off.
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = Some(max_interval);
+        self
+    }
+
+    /// Whether an interval of work is too long to be plausible.
+    pub fn is_too_long(&self, start_time: NaiveDateTime, end_time: NaiveDateTime) -> bool {
+        self.max_interval
+            .is_some_and(|max_interval| end_time - start_time > max_interval)
+    }
+
+    pub fn has_sunday_buckets(&self) -> bool {
+        self.holidays.is_some()
+    }
+
+    /// Index of the bucket that contains the given hour of the day.
+    pub fn bucket_of_hour(&self, hour: u32) -> usize {
+        DurationSMLabel::from_absolute_seconds(hour as Secs * SECS_PER_HOUR, self).0
+    }
+
+    fn is_sunday(&self, date: NaiveDate) -> bool {
+        match &self.holidays {
+            Some(holidays) => match date.weekday() {
+                Weekday::Sun => true,
+                Weekday::Sat => self.saturdays || holidays.contains(&date),
+                _ => holidays.contains(&date),
+            },
+            None => false,
+        }
+    }
+}
+
+/// Index of one of the three buckets.
+struct DurationSMLabel(usize);
+
+impl DurationSMLabel {
+    /* Compute the number of seconds in one time period */
+    fn to_duration_seconds(&self, bounds: &BucketBounds) -> Secs {
+        let start = self.to_start_seconds(bounds);
+        let end = bounds.starts[(self.0 + 1) % 3];
+        (end - start).rem_euclid(SECS_PER_DAY)
+    }
+
+    /* Compute the first second of each time period */
+    fn to_start_seconds(&self, bounds: &BucketBounds) -> Secs {
+        bounds.starts[self.0]
+    }
+
+    /* Compute a label for a number of seconds between midnight and midnight of the following day */
+    fn from_absolute_seconds(s: Secs, bounds: &BucketBounds) -> Self {
+        assert!(s < SECS_PER_DAY);
+
+        // The bucket we are in is the one whose start we passed most recently.
+        let idx = (0..3)
+            .filter(|idx| bounds.starts[*idx] != bounds.starts[(idx + 1) % 3])
+            .min_by_key(|idx| (s - bounds.starts[*idx]).rem_euclid(SECS_PER_DAY))
+            .unwrap();
+        Self(idx)
+    }
+}
+
+/// State machine to distribute seconds between two datetimes into buckets.
+struct DurationSM<'b> {
+    bounds: &'b BucketBounds,
+    buckets: [Secs; 3],
+    label: DurationSMLabel,
+    current_seconds: Secs, /* offset within the current time period (only used at start if starting time is not aligned) */
+}
+
+impl<'b> DurationSM<'b> {
+    /* Initialize a state machine from an initial seconds value to choose the starting label. */
+    fn new(start_seconds: Secs, bounds: &'b BucketBounds) -> Self {
+        assert!(start_seconds < SECS_PER_DAY);
+        let label = DurationSMLabel::from_absolute_seconds(start_seconds, bounds);
+        let current_seconds =
+            (start_seconds - label.to_start_seconds(bounds)).rem_euclid(SECS_PER_DAY);
+
+        Self {
+            bounds,
+            buckets: [0, 0, 0],
+            label,
+            current_seconds,
+        }
+    }
+
+    /* Advance to the next time period. */
+    fn next_step(&mut self) {
+        self.label = DurationSMLabel((self.label.0 + 1) % 3);
+    }
+
+    /* Returns the number of seconds in the current time period. */
+    fn get_current_seconds(&self) -> Secs {
+        self.label.to_duration_seconds(self.bounds) - self.current_seconds
+    }
+
+    /* Compute the number of time that can be added in the current time period and add it to the current bucket.
+     * The time that can be added must be less or equal to the iven total number of seconds left. */
+    fn add_time(&mut self, s: Secs) {
+        self.buckets[self.label.0] += s;
+        self.current_seconds = 0;
+    }
+}
+
+/// Work time in the three buckets of normal days, followed by the three buckets of Sundays and holidays.
+#[derive(Debug)]
+pub struct WorkDuration([Duration; 6]);
+
+impl WorkDuration {
+    pub fn zero() -> Self {
+        WorkDuration([Duration::zero(); 6])
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, EvaluationError> {
+        let mut result = WorkDuration::zero();
+        for (r, (t, s)) in result.0.iter_mut().zip(self.0.iter().zip(rhs.0.iter())) {
+            *r = s
+                .checked_add(t)
+                .ok_or(EvaluationError::DurationError(*s, *t))?;
+        }
+        Ok(result)
+    }
+
+    pub fn from_start_end_time(
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        bounds: &BucketBounds,
+    ) -> Self {
+        // TODO ensure that naivedatetime is in correct timezone
+        // by default:
+        // 4 Uhr - 20 Uhr -> bucket 1
+        // 20 Uhr - 24 Uhr -> bucket 2
+        // 24 Uhr - 4 Uhr -> bucket 3
+        //
+        // like in os
+        // compute total number of seconds in duration
+        // get start seconds in day
+        // while total_seconds > 0
+        //   get seconds until next threshold
+        //   put then into respective bucket
+        //   subtract from total
+        assert!(start_time < end_time);
+
+        // Split at midnight since Sundays and holidays go into their own buckets.
+        let mut seconds = [0; 6];
+        let mut day_start = start_time;
+        while day_start < end_time {
+            let day_end = min(end_time, day_start.date().succ().and_hms(0, 0, 0));
+            let offset = if bounds.is_sunday(day_start.date()) {
+                SUNDAY_OFFSET
+            } else {
+                0
+            };
+
+            let current_seconds = day_start.num_seconds_from_midnight() as i64;
+            let mut seconds_remaining =
+                WorkDuration::interval_length(day_start, day_end).num_seconds();
+            let mut sm = DurationSM::new(current_seconds, bounds);
+
+            while seconds_remaining > 0 {
+                let s = min(seconds_remaining, sm.get_current_seconds());
+                seconds_remaining -= s;
+                sm.add_time(s);
+                sm.next_step();
+            }
+
+            for (idx, s) in sm.buckets.iter().enumerate() {
+                seconds[offset + idx] += s;
+            }
+            day_start = day_end;
+        }
+
+        WorkDuration(seconds.map(Duration::seconds))
+    }
+
+    /// Length of an interval in whole seconds. Both ends are truncated to the second (instead of the difference),
+    /// so that the lengths of the parts of an interval add up to the length of the whole interval.
+    pub fn interval_length(start_time: NaiveDateTime, end_time: NaiveDateTime) -> Duration {
+        Duration::seconds(end_time.timestamp() - start_time.timestamp())
+    }
+
+    /// Sum of all buckets.
+    pub fn total(&self) -> Duration {
+        self.0.iter().fold(Duration::zero(), |total, d| total + *d)
+    }
+
+    pub fn num_minutes(&self) -> [i64; 6] {
+        // add 59 seconds to everything to round up minutes.
+        let s59 = Duration::seconds(59);
+        self.0.map(|t| (t + s59).num_minutes())
+    }
+}
@@ -0,0 +1,93 @@
+//! Distribution of newly saved events to the parts of the user interface that show them, e.g. the log and the
+//! dashboard. Each of them keeps its own state instead of all reading one list of events. Further receivers,
+//! e.g. to synchronize several terminals or to call webhooks, only need to implement `EventSubscriber`.
+use crate::models::WorkEventT;
+use std::mem;
+
+pub trait EventSubscriber {
+    /// Called for each event after it was saved, in the order in which they were saved.
+    fn on_event(&mut self, eventt: &WorkEventT);
+
+    /// Called when a new business day starts, before the events of the new day.
+    fn on_new_business_day(&mut self) {}
+}
+
+/// Collects the saved events until they are dispatched to the subscribers, which is done after each update
+/// of the application. That way the subscribers need not be borrowed while the events are created.
+#[derive(Debug, Default)]
+pub struct EventBus {
+    pending: Vec<WorkEventT>,
+    new_business_day: bool,
+}
+
+impl EventBus {
+    pub fn publish(&mut self, eventt: WorkEventT) {
+        self.pending.push(eventt);
+    }
+
+    pub fn start_business_day(&mut self) {
+        self.new_business_day = true;
+    }
+
+    /// Send everything that happened since the last dispatch to the subscribers.
+    pub fn dispatch(&mut self, subscribers: &mut [&mut dyn EventSubscriber]) {
+        if mem::take(&mut self.new_business_day) {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.on_new_business_day();
+            }
+        }
+        for eventt in self.pending.drain(..) {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.on_event(&eventt);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkEvent;
+    use chrono::NaiveDate;
+
+    #[derive(Default)]
+    struct Log(Vec<WorkEventT>);
+
+    impl EventSubscriber for Log {
+        fn on_event(&mut self, eventt: &WorkEventT) {
+            self.0.push(eventt.clone());
+        }
+
+        fn on_new_business_day(&mut self) {
+            self.0.clear();
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let eventt = |msg: &str| {
+            WorkEventT::new(
+                0,
+                NaiveDate::from_ymd(2022, 6, 1).and_hms(20, 0, 0),
+                WorkEvent::Info(String::from(msg)),
+            )
+        };
+        let mut bus = EventBus::default();
+        let (mut first, mut second) = (Log::default(), Log::default());
+
+        bus.publish(eventt("a"));
+        bus.publish(eventt("b"));
+        bus.dispatch(&mut [&mut first, &mut second]);
+        assert_eq!(first.0, vec![eventt("a"), eventt("b")]);
+        assert_eq!(second.0, first.0);
+
+        // Events are only sent once.
+        bus.dispatch(&mut [&mut first]);
+        assert_eq!(first.0.len(), 2);
+
+        bus.start_business_day();
+        bus.publish(eventt("c"));
+        bus.dispatch(&mut [&mut first]);
+        assert_eq!(first.0, vec![eventt("c")]);
+    }
+}
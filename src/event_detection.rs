@@ -0,0 +1,168 @@
+//! Detection of events (the parties, not `WorkEvent`s) from the number of staff members working.
+//! An event starts with the first sign-in after a quiet period in which nobody worked and ends with the last
+//! sign-off before such a period. The statistics use them to group the work time, without anybody having to
+//! mark the start and end of an event by hand.
+use crate::models::{NewEventSession, WorkEvent, WorkEventT, WorkStatus};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::collections::HashSet;
+
+/// An event that is still being detected.
+struct OpenSession {
+    started_at: NaiveDateTime,
+    /// When the last staff member signed off, `None` while somebody is working.
+    quiet_since: Option<NaiveDateTime>,
+    peak_count: usize,
+    staff_seconds: i64,
+}
+
+impl OpenSession {
+    fn finish(self, business_date: NaiveDate, ended_at: NaiveDateTime) -> NewEventSession {
+        NewEventSession {
+            business_date,
+            started_at: self.started_at,
+            ended_at,
+            peak_count: self.peak_count as i32,
+            staff_minutes: self.staff_seconds / 60,
+        }
+    }
+}
+
+/// Detect the events in the status changes and 6am events of a business day, ordered from oldest to newest.
+/// The 6am boundary signs off everyone. Staff members still working at `until` are counted until then.
+pub fn detect(
+    events: &[WorkEventT],
+    business_date: NaiveDate,
+    quiet: Duration,
+    until: NaiveDateTime,
+) -> Vec<NewEventSession> {
+    let mut sessions = Vec::new();
+    let mut working = HashSet::new();
+    let mut open: Option<OpenSession> = None;
+    let mut last_change: Option<NaiveDateTime> = None;
+
+    for eventt in events {
+        let time = eventt.created_at;
+        if let (Some(session), Some(last_change)) = (open.as_mut(), last_change) {
+            session.staff_seconds += working.len() as i64 * (time - last_change).num_seconds();
+        }
+        last_change = Some(time);
+
+        match &eventt.event {
+            WorkEvent::StatusChange(uuid, _, WorkStatus::Working) => {
+                let quiet_since = open.as_ref().and_then(|session| session.quiet_since);
+                match quiet_since {
+                    Some(quiet_since) if time - quiet_since >= quiet => {
+                        if let Some(session) = open.take() {
+                            sessions.push(session.finish(business_date, quiet_since));
+                        }
+                    }
+                    _ => {}
+                }
+                let session = open.get_or_insert(OpenSession {
+                    started_at: time,
+                    quiet_since: None,
+                    peak_count: 0,
+                    staff_seconds: 0,
+                });
+                working.insert(*uuid);
+                session.quiet_since = None;
+                session.peak_count = session.peak_count.max(working.len());
+            }
+            WorkEvent::StatusChange(uuid, _, WorkStatus::Away) => {
+                let is_last = working.remove(uuid) && working.is_empty();
+                if let Some(session) = open.as_mut().filter(|_| is_last) {
+                    session.quiet_since = Some(time);
+                }
+            }
+            WorkEvent::_6am if !working.is_empty() => {
+                working.clear();
+                if let Some(session) = open.as_mut() {
+                    session.quiet_since = Some(time);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(mut session) = open {
+        let ended_at = match session.quiet_since {
+            Some(quiet_since) => quiet_since,
+            None => {
+                if let Some(last_change) = last_change {
+                    session.staff_seconds +=
+                        working.len() as i64 * (until - last_change).num_seconds().max(0);
+                }
+                until
+            }
+        };
+        sessions.push(session.finish(business_date, ended_at));
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_sessions() {
+        let date = NaiveDate::from_ymd(2022, 6, 3);
+        let at = |h, m| {
+            if h < 6 {
+                date.succ().and_hms(h, m, 0)
+            } else {
+                date.and_hms(h, m, 0)
+            }
+        };
+        let status = |uuid, (h, m), status| {
+            WorkEventT::new(
+                0,
+                at(h, m),
+                WorkEvent::StatusChange(uuid, String::new(), status),
+            )
+        };
+        let events = [
+            // setup in the afternoon
+            status(1, (14, 0), WorkStatus::Working),
+            status(1, (15, 0), WorkStatus::Away),
+            // a short break does not end the event
+            status(1, (15, 30), WorkStatus::Working),
+            status(2, (16, 0), WorkStatus::Working),
+            status(1, (17, 0), WorkStatus::Away),
+            status(2, (17, 0), WorkStatus::Away),
+            // the party at night, one staff member forgot to sign off
+            status(1, (22, 0), WorkStatus::Working),
+            status(2, (22, 0), WorkStatus::Working),
+            status(3, (23, 0), WorkStatus::Working),
+            status(2, (3, 0), WorkStatus::Away),
+            status(3, (3, 0), WorkStatus::Away),
+            WorkEventT::new(0, date.succ().and_hms(5, 59, 59), WorkEvent::_6am),
+        ];
+
+        let sessions = detect(&events, date, Duration::hours(2), at(5, 59));
+        assert_eq!(
+            sessions,
+            vec![
+                NewEventSession {
+                    business_date: date,
+                    started_at: at(14, 0),
+                    ended_at: at(17, 0),
+                    peak_count: 2,
+                    staff_minutes: 60 + 30 + 2 * 60,
+                },
+                NewEventSession {
+                    business_date: date,
+                    started_at: at(22, 0),
+                    ended_at: date.succ().and_hms(5, 59, 59),
+                    peak_count: 3,
+                    staff_minutes: 2 * 60 + 3 * 240 + 179,
+                },
+            ]
+        );
+
+        // A running event ends at `until`.
+        let sessions = detect(&events[..8], date, Duration::hours(2), at(23, 0));
+        assert_eq!(sessions[1].ended_at, at(23, 0));
+        assert_eq!(sessions[1].staff_minutes, 120);
+    }
+}
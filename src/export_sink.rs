@@ -0,0 +1,464 @@
+//! Destinations to which exported files are sent in addition to the export directory.
+//! Uploads and emails are sent with curl, so it must be installed on the terminal.
+//! Credentials are passed to curl on stdin, since its command line can be read by every user of the terminal.
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, error, fmt, fs, io, process};
+
+const MIME_BOUNDARY: &str = "stechuhr-export";
+
+#[derive(Debug)]
+pub enum SinkError {
+    IO(io::Error),
+    Curl(String),
+}
+
+impl error::Error for SinkError {}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SinkError::IO(e) => write!(f, "Datei konnte nicht übertragen werden: {}", e),
+            SinkError::Curl(msg) => write!(f, "Übertragung fehlgeschlagen: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for SinkError {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+/// A destination for exported files.
+pub trait ExportSink {
+    /// Send all files of one export run.
+    fn deliver(&self, files: &[PathBuf]) -> Result<(), SinkError>;
+}
+
+/// Copy the files into another directory, e.g. a mounted network share.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSink {
+    pub dir: PathBuf,
+}
+
+impl ExportSink for FileSink {
+    fn deliver(&self, files: &[PathBuf]) -> Result<(), SinkError> {
+        fs::create_dir_all(&self.dir)?;
+        for file in files {
+            fs::copy(file, self.dir.join(file_name(file)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Send the files as attachments of one email, e.g. to the payroll office.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailSink {
+    /// SMTP server, e.g. "smtps://mail.example.org:465".
+    pub smtp_url: String,
+    pub user: String,
+    pub password: String,
+    pub from: String,
+    /// Recipients separated by commas.
+    pub to: String,
+}
+
+impl EmailSink {
+    fn message(&self, subject: &str, files: &[PathBuf]) -> Result<String, SinkError> {
+        let mut message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+            self.from,
+            self.to,
+            encode_header(subject),
+            MIME_BOUNDARY
+        );
+        message += &format!(
+            "--{}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nIm Anhang befinden sich die exportierten Dateien.\r\n",
+            MIME_BOUNDARY
+        );
+        for file in files {
+            message += &format!(
+                "--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: base64\r\nContent-Disposition: attachment; filename*=UTF-8''{}\r\n\r\n",
+                MIME_BOUNDARY,
+                content_type(file),
+                percent_encode(&file_name(file))
+            );
+            let encoded = base64_encode(&fs::read(file)?);
+            // Lines of an email must not be longer than 76 characters.
+            for line in encoded.as_bytes().chunks(76) {
+                message += std::str::from_utf8(line).unwrap();
+                message += "\r\n";
+            }
+        }
+        message += &format!("--{}--\r\n", MIME_BOUNDARY);
+        Ok(message)
+    }
+
+    /// Send the files as attachments of one email with the given subject.
+    pub fn send(&self, subject: &str, files: &[PathBuf]) -> Result<(), SinkError> {
+        let message = self.message(subject, files)?;
+        let (message_path, mut message_file) = create_private_temp_file("eml")?;
+        let written = message_file.write_all(message.as_bytes());
+        drop(message_file);
+        if let Err(e) = written {
+            let _ = fs::remove_file(&message_path);
+            return Err(e.into());
+        }
+
+        let mut args = vec![
+            String::from("--ssl-reqd"),
+            String::from("--url"),
+            self.smtp_url.clone(),
+            String::from("--mail-from"),
+            self.from.clone(),
+        ];
+        for recipient in self.to.split(',') {
+            args.push(String::from("--mail-rcpt"));
+            args.push(recipient.trim().to_owned());
+        }
+        args.push(String::from("--upload-file"));
+        args.push(message_path.display().to_string());
+
+        let sent = curl(
+            &args,
+            &[("user", format!("{}:{}", self.user, self.password))],
+        );
+        // A failed transfer is reported even if the message could not be deleted afterwards.
+        let removed = fs::remove_file(&message_path);
+        sent.and(removed.map_err(SinkError::from))
+    }
+}
+
+impl ExportSink for EmailSink {
+    fn deliver(&self, files: &[PathBuf]) -> Result<(), SinkError> {
+        self.send("Stechuhr Export", files)
+    }
+}
+
+/// Upload the files into a WebDAV directory, e.g. a Nextcloud folder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebDavSink {
+    /// URL of the directory, e.g. "https://cloud.example.org/remote.php/dav/files/stechuhr/Auswertung".
+    pub url: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl ExportSink for WebDavSink {
+    fn deliver(&self, files: &[PathBuf]) -> Result<(), SinkError> {
+        for file in files {
+            curl(
+                &[
+                    String::from("--upload-file"),
+                    file.display().to_string(),
+                    upload_url(&self.url, file),
+                ],
+                &[("user", format!("{}:{}", self.user, self.password))],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Upload the files into an S3 bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3Sink {
+    /// e.g. "https://s3.eu-central-1.amazonaws.com"
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl ExportSink for S3Sink {
+    fn deliver(&self, files: &[PathBuf]) -> Result<(), SinkError> {
+        let bucket_url = format!("{}/{}", self.endpoint.trim_end_matches('/'), self.bucket);
+        for file in files {
+            curl(
+                &[
+                    String::from("--aws-sigv4"),
+                    format!("aws:amz:{}:s3", self.region),
+                    String::from("--upload-file"),
+                    file.display().to_string(),
+                    upload_url(&bucket_url, file),
+                ],
+                &[("user", format!("{}:{}", self.access_key, self.secret_key))],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkKind {
+    File(FileSink),
+    Email(EmailSink),
+    WebDav(WebDavSink),
+    S3(S3Sink),
+}
+
+impl SinkKind {
+    pub fn sink(&self) -> &dyn ExportSink {
+        match self {
+            SinkKind::File(sink) => sink,
+            SinkKind::Email(sink) => sink,
+            SinkKind::WebDav(sink) => sink,
+            SinkKind::S3(sink) => sink,
+        }
+    }
+}
+
+/// An export sink as configured in the settings file. Credentials are stored in plain text there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinkSettings {
+    /// Name shown in the settings and in messages.
+    pub name: String,
+    /// Only enabled sinks receive the files of an export run.
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// MIME type of an attachment, by the extension of the file.
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Some("json") => "application/json",
+        Some("csv") => "text/csv; charset=utf-8",
+        _ => "text/tab-separated-values; charset=utf-8",
+    }
+}
+
+fn upload_url(dir_url: &str, file: &Path) -> String {
+    format!(
+        "{}/{}",
+        dir_url.trim_end_matches('/'),
+        percent_encode(&file_name(file))
+    )
+}
+
+/// Run curl with the given arguments. The options given as name and value, e.g. credentials, are read from stdin.
+fn curl(args: &[String], options: &[(&str, String)]) -> Result<(), SinkError> {
+    let mut child = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--config", "-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(curl_config(options).as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(SinkError::Curl(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ))
+    }
+}
+
+/// Options in the format of a curl config file, with the values quoted.
+fn curl_config(options: &[(&str, String)]) -> String {
+    options
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+            format!("{} = \"{}\"\n", name, value)
+        })
+        .collect()
+}
+
+/// Create a new file in the temporary directory that only the current user can read.
+/// The name is unique, so that exports running at the same time do not overwrite each other's files.
+fn create_private_temp_file(extension: &str) -> io::Result<(PathBuf, fs::File)> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or_default();
+    for attempt in 0.. {
+        let path = env::temp_dir().join(format!(
+            "stechuhr-export-{}-{}-{}.{}",
+            process::id(),
+            nanos,
+            attempt,
+            extension
+        ));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("Temporary file names are exhausted")
+}
+
+/// A header value of an email, encoded as described in RFC 2047 if it is not plain ASCII.
+/// Each encoded word must not be longer than 75 characters, so long values are split into several of them.
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_owned();
+    }
+
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in value.chars() {
+        // 45 bytes are 60 characters in base64, which leaves room for the charset.
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", base64_encode(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    words.push(format!("=?UTF-8?B?{}?=", base64_encode(chunk.as_bytes())));
+    words.join("\r\n ")
+}
+
+/// Encode everything except unreserved characters, for file names in URLs and email headers.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * idx)) & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        assert_eq!(
+            percent_encode("2022-03 März.tsv"),
+            "2022-03%20M%C3%A4rz.tsv"
+        );
+    }
+
+    #[test]
+    fn credentials() {
+        assert_eq!(
+            curl_config(&[("user", String::from("stechuhr:ge\"heim\\"))]),
+            "user = \"stechuhr:ge\\\"heim\\\\\"\n"
+        );
+        assert_eq!(encode_header("Stechuhr Export"), "Stechuhr Export");
+        let long = encode_header(&"ä".repeat(30));
+        assert!(long
+            .split("\r\n ")
+            .all(|word| word.len() <= 75 && word.starts_with("=?UTF-8?B?")));
+
+        let (first, _) = create_private_temp_file("eml").unwrap();
+        let (second, _) = create_private_temp_file("eml").unwrap();
+        assert_ne!(first, second);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(&first).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn email_message() {
+        let sink = EmailSink {
+            smtp_url: String::from("smtps://mail.example.org:465"),
+            user: String::from("stechuhr"),
+            password: String::from("geheim"),
+            from: String::from("stechuhr@example.org"),
+            to: String::from("buchhaltung@example.org"),
+        };
+        let file = env::temp_dir().join("stechuhr-email-test.xlsx");
+        fs::write(&file, "Man").unwrap();
+        let message = sink
+            .message("Auswertung 2022-03 März", std::slice::from_ref(&file))
+            .unwrap();
+        fs::remove_file(file).unwrap();
+
+        assert!(message.contains("Subject: =?UTF-8?B?QXVzd2VydHVuZyAyMDIyLTAzIE3DpHJ6?=\r\n"));
+        assert!(message.contains(
+            "Content-Type: application/vnd.openxmlformats-officedocument.spreadsheetml.sheet\r\n"
+        ));
+        assert!(message.contains("filename*=UTF-8''stechuhr-email-test.xlsx\r\n\r\nTWFu\r\n"));
+    }
+
+    #[test]
+    fn sink_settings() {
+        let json = r#"{"name": "Nextcloud", "enabled": true, "type": "webdav", "url": "https://cloud.example.org/dav", "user": "stechuhr", "password": "geheim"}"#;
+        let settings: SinkSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            settings.kind,
+            SinkKind::WebDav(WebDavSink {
+                url: String::from("https://cloud.example.org/dav"),
+                user: String::from("stechuhr"),
+                password: String::from("geheim"),
+            })
+        );
+
+        let dir = env::temp_dir().join("stechuhr-sink-test");
+        let file = env::temp_dir().join("stechuhr-sink-test.tsv");
+        fs::write(&file, "Name").unwrap();
+        FileSink { dir: dir.clone() }
+            .deliver(std::slice::from_ref(&file))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("stechuhr-sink-test.tsv")).unwrap(),
+            "Name"
+        );
+        fs::remove_file(file).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+//! Detection of forgotten sign-offs. Somebody who is still working when the 6am boundary ends the business day
+//! almost always forgot to sign off, so their shift is counted until 6am. When they sign in the next time we
+//! suggest a realistic end of that shift based on how long their shifts usually are.
+use crate::models::{WorkEvent, WorkEventT, WorkStatus};
+use chrono::{Duration, NaiveDateTime, Timelike};
+
+/// How far back the events are searched, both for the forgotten sign-off and for the usual shift length.
+pub const HISTORY_DAYS: i64 = 60;
+/// With fewer finished shifts the median says nothing, so the default length is used.
+const MIN_SHIFTS: usize = 3;
+const DEFAULT_SHIFT_HOURS: i64 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgottenSignOff {
+    pub uuid: i32,
+    pub start: NaiveDateTime,
+    /// The 6am boundary that ended the shift.
+    pub boundary: NaiveDateTime,
+    pub suggested_end: NaiveDateTime,
+    /// Median length of the finished shifts, `None` if there were too few.
+    pub usual_length: Option<Duration>,
+}
+
+/// The median of the given durations, which are sorted in place.
+fn median(durations: &mut [Duration]) -> Option<Duration> {
+    durations.sort();
+    let mid = durations.len() / 2;
+    match durations.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(durations[mid]),
+        _ => Some((durations[mid - 1] + durations[mid]) / 2),
+    }
+}
+
+/// Look at the events of the staff member and the 6am events, ordered from oldest to newest.
+/// Returns the last shift if it was ended by a 6am boundary instead of a sign-off. An entered correction
+/// is a sign-off before the boundary, so a shift is only found until it has been corrected.
+pub fn detect(events: &[WorkEventT], uuid: i32) -> Option<ForgottenSignOff> {
+    let mut working_since = None;
+    let mut finished_shifts = Vec::new();
+    let mut last_forgotten = None;
+
+    for eventt in events {
+        match eventt.event {
+            WorkEvent::StatusChange(id, _, WorkStatus::Working) if id == uuid => {
+                working_since.get_or_insert(eventt.created_at);
+            }
+            WorkEvent::StatusChange(id, _, WorkStatus::Away) if id == uuid => {
+                if let Some(start) = working_since.take() {
+                    finished_shifts.push(eventt.created_at - start);
+                    last_forgotten = None;
+                }
+            }
+            WorkEvent::_6am => {
+                if let Some(start) = working_since.take() {
+                    last_forgotten = Some((start, eventt.created_at));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (start, boundary) = last_forgotten?;
+    let usual_length = Some(finished_shifts.as_mut_slice())
+        .filter(|shifts| shifts.len() >= MIN_SHIFTS)
+        .and_then(median);
+    let length = usual_length.unwrap_or_else(|| Duration::hours(DEFAULT_SHIFT_HOURS));
+    // The correction is entered in minutes and must lie before the boundary.
+    let suggested_end = (start + length)
+        .min(boundary - Duration::minutes(1))
+        .max(start);
+    let suggested_end = suggested_end.with_second(0).unwrap_or(suggested_end);
+
+    Some(ForgottenSignOff {
+        uuid,
+        start,
+        boundary,
+        suggested_end,
+        usual_length,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn detect_forgotten_sign_off() {
+        let day = |d| NaiveDate::from_ymd(2022, 4, d);
+        let status = |time: NaiveDateTime, status| {
+            WorkEventT::new(0, time, WorkEvent::StatusChange(1, String::new(), status))
+        };
+        let boundary = |d| WorkEventT::new(0, day(d).and_hms(5, 59, 59), WorkEvent::_6am);
+
+        let mut events = Vec::new();
+        for (d, hours) in [(1, 4), (2, 5), (3, 6)] {
+            events.push(status(day(d).and_hms(17, 0, 0), WorkStatus::Working));
+            events.push(status(day(d).and_hms(17 + hours, 0, 0), WorkStatus::Away));
+            events.push(boundary(d + 1));
+        }
+        assert_eq!(detect(&events, 1), None);
+
+        events.push(status(day(4).and_hms(20, 30, 0), WorkStatus::Working));
+        events.push(boundary(5));
+        assert_eq!(
+            detect(&events, 1),
+            Some(ForgottenSignOff {
+                uuid: 1,
+                start: day(4).and_hms(20, 30, 0),
+                boundary: day(5).and_hms(5, 59, 59),
+                suggested_end: day(5).and_hms(1, 30, 0),
+                usual_length: Some(Duration::hours(5)),
+            })
+        );
+        // Other staff members are not affected.
+        assert_eq!(detect(&events, 2), None);
+
+        // The correction is a sign-off before the boundary.
+        events.insert(
+            events.len() - 1,
+            status(day(5).and_hms(1, 30, 0), WorkStatus::Away),
+        );
+        assert_eq!(detect(&events, 1), None);
+    }
+
+    #[test]
+    fn suggestion_before_boundary() {
+        let start = NaiveDate::from_ymd(2022, 4, 1).and_hms(3, 0, 0);
+        let events = [
+            WorkEventT::new(
+                0,
+                start,
+                WorkEvent::StatusChange(1, String::new(), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                0,
+                NaiveDate::from_ymd(2022, 4, 1).and_hms(5, 59, 59),
+                WorkEvent::_6am,
+            ),
+        ];
+        let forgotten = detect(&events, 1).unwrap();
+        assert_eq!(forgotten.usual_length, None);
+        assert_eq!(
+            forgotten.suggested_end,
+            NaiveDate::from_ymd(2022, 4, 1).and_hms(5, 58, 0)
+        );
+    }
+}
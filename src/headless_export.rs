@@ -0,0 +1,70 @@
+//! Export of the evaluation of a month without a window, e.g. for the payroll script of the venue that runs at night.
+//! It uses the same evaluation and file format as the statistics tab and prints the path of the written file.
+//! Usage: stechuhr --export <YYYY-MM> --format json
+use crate::{SharedData, StatsTab};
+use chrono::{Local, NaiveDate, TimeZone};
+use std::error::Error;
+use std::path::PathBuf;
+use stechuhr::settings::Settings;
+use stechuhr::storage::Storage;
+
+const USAGE: &str = "Usage: stechuhr --export <YYYY-MM> --format json";
+
+/// Formats that can be exported without a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Json,
+}
+
+/// The first day of the month and the format from the arguments after `--export`.
+fn parse_args(args: &[String]) -> Result<(NaiveDate, ExportFormat), Box<dyn Error>> {
+    let month = args.first().ok_or(USAGE)?;
+    let month = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|_| format!("Ungültiger Monat {}. {}", month, USAGE))?;
+    let format = match (
+        args.get(1).map(String::as_str),
+        args.get(2).map(String::as_str),
+    ) {
+        (Some("--format"), Some("json")) => ExportFormat::Json,
+        (Some("--format"), Some(format)) => {
+            return Err(format!("Unbekanntes Format {}. {}", format, USAGE).into())
+        }
+        _ => return Err(USAGE.into()),
+    };
+    if args.len() > 3 {
+        return Err(USAGE.into());
+    }
+    Ok((month, format))
+}
+
+/// Write the evaluation of the month given in the arguments and return the path of the file.
+pub fn run(
+    args: impl Iterator<Item = String>,
+    storage: Box<dyn Storage>,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let (month, format) = parse_args(&args.collect::<Vec<_>>())?;
+    let settings = Settings::load()?;
+    let mut shared = SharedData::headless(storage, settings);
+    let date = Local.from_local_date(&month).unwrap();
+    let path = match format {
+        ExportFormat::Json => StatsTab::write_json(&mut shared, date, false)?,
+    };
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_args(&args(&["2022-04", "--format", "json"])).unwrap(),
+            (NaiveDate::from_ymd(2022, 4, 1), ExportFormat::Json)
+        );
+        assert!(parse_args(&args(&["2022-04"])).is_err());
+        assert!(parse_args(&args(&["2022-13", "--format", "json"])).is_err());
+        assert!(parse_args(&args(&["2022-04", "--format", "xml"])).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+//! Signs of life for the supervisor of the kiosk, so that it can restart the application if the UI loop hangs.
+use chrono::{DateTime, Local};
+use std::path::Path;
+use std::{env, fs, io};
+
+/// Write the current time into the heartbeat file.
+/// We write into a temporary file first so that the supervisor never reads a half-written timestamp.
+pub fn write(path: &Path, now: DateTime<Local>) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, now.to_rfc3339())?;
+    fs::rename(tmp_path, path)
+}
+
+/// Notify the systemd watchdog if we are running as a service with WatchdogSec set.
+/// Does nothing if NOTIFY_SOCKET is not set. Abstract sockets (starting with '@') are not supported.
+#[cfg(unix)]
+pub fn notify_watchdog() -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    match env::var("NOTIFY_SOCKET") {
+        Ok(socket_path) if socket_path.starts_with('/') => {
+            let socket = UnixDatagram::unbound()?;
+            socket.send_to(b"WATCHDOG=1", socket_path)?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify_watchdog() -> io::Result<()> {
+    Ok(())
+}
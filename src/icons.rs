@@ -53,6 +53,22 @@ pub mod emoji {
     pub const crossmark: Emoji = Emoji::new('\u{274E}');
     pub const trashcan: Emoji = Emoji::new('\u{1F5D1}');
     pub const floppydisk: Emoji = Emoji::new('\u{1F4BE}');
+    pub const pause: Emoji = Emoji::new('\u{23F8}');
+    pub const circle: Emoji = Emoji::new('\u{25CB}');
+    pub const calendar: Emoji = Emoji::new('\u{1F4C5}');
+    pub const warning: Emoji = Emoji::new('\u{26A0}');
+}
+
+/// Monochrome symbols for the staff status that differ in shape, not only in color.
+/// Used instead of the colored emojis for people who cannot tell red and green apart.
+#[allow(non_upper_case_globals)]
+pub mod colorblind {
+    use super::*;
+
+    pub const working: Emoji = Emoji::new('\u{25CF}');
+    pub const on_break: Emoji = Emoji::new('\u{25D0}');
+    pub const away: Emoji = Emoji::new('\u{2716}');
+    pub const not_scheduled: Emoji = Emoji::new('\u{25CB}');
 }
 
 pub fn icon(emoji: Emoji) -> Text {
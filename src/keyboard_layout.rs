@@ -0,0 +1,50 @@
+//! Card readers act as keyboards. If the reader and the terminal use different keyboard layouts,
+//! the digits of a dongle ID arrive as the symbols that are on the same keys in the other layout.
+
+/// Keyboard layouts that a reader may be configured for, with the characters it then sends for the digits 0-9.
+const LAYOUTS: [(&str, &str); 3] = [
+    ("QWERTZ mit Umschalt", "=!\"§$%&/()"),
+    ("QWERTY mit Umschalt", ")!@#$%^&*("),
+    ("AZERTY", "à&é\"'(-è_ç"),
+];
+
+/// Translate an ID that was typed with a different keyboard layout back to digits.
+/// Returns the name of the layout and the digits, or None if the input does not match any known layout.
+/// Layouts are tried in order, so an input that fits several layouts is read with the first one.
+pub fn translate(input: &str) -> Option<(&'static str, String)> {
+    if input.is_empty() || input.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    LAYOUTS.iter().find_map(|(layout, digits)| {
+        input
+            .chars()
+            .map(|c| {
+                digits
+                    .chars()
+                    .position(|d| d == c)
+                    .and_then(|idx| char::from_digit(idx as u32, 10))
+            })
+            .collect::<Option<String>>()
+            .map(|translated| (*layout, translated))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_layouts() {
+        assert_eq!(
+            translate("&é\"'(-è_çà"),
+            Some(("AZERTY", String::from("1234567890")))
+        );
+        assert_eq!(
+            translate("!\"§$%&/()="),
+            Some(("QWERTZ mit Umschalt", String::from("1234567890")))
+        );
+        assert_eq!(translate("1234567890"), None);
+        assert_eq!(translate("12345abcde"), None);
+    }
+}
@@ -1,9 +1,30 @@
+pub mod audit;
+pub mod correction;
 pub mod date_ext;
 pub mod db;
+pub mod disk_space;
+pub mod evaluation;
+pub mod event_bus;
+pub mod event_detection;
+pub mod export_sink;
+pub mod forgotten_sign_off;
+pub mod heartbeat;
 pub mod icons;
+pub mod keyboard_layout;
+pub mod lifecycle;
+pub mod maintenance;
 pub mod models;
+pub mod night_summary;
+pub mod quick_action;
+pub mod rest_period;
 pub mod schema;
+pub mod self_test;
+pub mod settings;
+pub mod shift_plan;
+pub mod storage;
 pub mod style;
+pub mod write_latency;
+pub mod youth_protection;
 
 #[macro_use]
 extern crate diesel;
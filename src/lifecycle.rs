@@ -0,0 +1,104 @@
+//! Start and end of the application as events in the log, so that a gap in the swipes can be told apart from the
+//! terminal being down when a night is reconstructed later. A run that ended without `Lifecycle::Stopped` is
+//! detected at the next start, e.g. after a crash or a power loss.
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Version of the running application.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone, Serialize, Deserialize)]
+pub enum Lifecycle {
+    /// The application was started with the given version.
+    Started(String),
+    /// The application was closed normally.
+    Stopped,
+    /// The previous run ended without being closed. The time is that of its last event.
+    Crashed(NaiveDateTime),
+    /// The version changed since the previous run, from the first to the second.
+    VersionChanged(String, String),
+}
+
+/// Events to record when the application starts.
+/// `previous` are the lifecycle events of the previous runs, the latest first, and `last_seen` the time of the
+/// latest event of any kind. `Started` comes last, so the latest two events always include that of the previous run.
+pub fn startup_events(
+    previous: &[Lifecycle],
+    last_seen: Option<NaiveDateTime>,
+    version: &str,
+) -> Vec<Lifecycle> {
+    let mut events = Vec::new();
+    if let (Some(latest), Some(last_seen)) = (previous.first(), last_seen) {
+        if *latest != Lifecycle::Stopped {
+            events.push(Lifecycle::Crashed(last_seen));
+        }
+    }
+    let previous_version = previous.iter().find_map(|event| match event {
+        Lifecycle::Started(version) => Some(version),
+        _ => None,
+    });
+    if let Some(previous_version) = previous_version.filter(|previous| *previous != version) {
+        events.push(Lifecycle::VersionChanged(
+            previous_version.clone(),
+            String::from(version),
+        ));
+    }
+    events.push(Lifecycle::Started(String::from(version)));
+    events
+}
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lifecycle::Started(version) => write!(f, "gestartet (Version {})", version),
+            Lifecycle::Stopped => write!(f, "beendet"),
+            Lifecycle::Crashed(last_seen) => write!(
+                f,
+                "wurde nicht ordentlich beendet, letztes Ereignis um {}",
+                last_seen.format("%d.%m.%Y %H:%M:%S")
+            ),
+            Lifecycle::VersionChanged(from, to) => {
+                write!(f, "wurde von Version {} auf {} aktualisiert", from, to)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn detect_crash_and_update() {
+        let last_seen = NaiveDate::from_ymd(2022, 4, 2).and_hms(1, 23, 45);
+        let started = |version: &str| Lifecycle::Started(String::from(version));
+
+        assert_eq!(startup_events(&[], None, "1.0.0"), vec![started("1.0.0")]);
+        assert_eq!(
+            startup_events(
+                &[Lifecycle::Stopped, started("1.0.0")],
+                Some(last_seen),
+                "1.0.0"
+            ),
+            vec![started("1.0.0")]
+        );
+        assert_eq!(
+            startup_events(
+                &[started("1.0.0"), Lifecycle::Crashed(last_seen)],
+                Some(last_seen),
+                "1.1.0"
+            ),
+            vec![
+                Lifecycle::Crashed(last_seen),
+                Lifecycle::VersionChanged(String::from("1.0.0"), String::from("1.1.0")),
+                started("1.1.0"),
+            ]
+        );
+        assert_eq!(
+            Lifecycle::Crashed(last_seen).to_string(),
+            "wurde nicht ordentlich beendet, letztes Ereignis um 02.04.2022 01:23:45"
+        );
+    }
+}
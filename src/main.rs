@@ -3,31 +3,63 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+mod headless_export;
+#[cfg(test)]
+mod recording_storage;
+#[cfg(feature = "headless-simulation")]
+mod simulation;
 mod tabs;
 
-use chrono::{DateTime, Local, Locale, NaiveTime};
-use chrono::{NaiveDateTime, TimeZone};
-use diesel::prelude::*;
+use chrono::{DateTime, Datelike, Local, Timelike};
+use chrono::{Duration, NaiveDate, NaiveDateTime, TimeZone};
 use dotenv::dotenv;
 use iced::alignment::Vertical;
 #[allow(unused_imports)]
 use iced::Color;
 use iced::{
-    button, executor, scrollable, window, Application, Button, Column, Command, Container, Element,
-    Length, Scrollable, Settings, Subscription, Text,
+    button, executor, scrollable, window, Alignment, Application, Button, Column, Command,
+    Container, Element, Length, Row, Scrollable, Settings, Subscription, Text,
 };
 use iced_aw::{modal, Card, Modal, TabBar, TabLabel};
-use iced_native::{event::Status, keyboard, Event};
-use std::{error, fmt, io};
-use stechuhr::db;
+use iced_native::{event::Status, keyboard, mouse, touch, Event};
+use std::path::PathBuf;
+use std::{env, error, fmt, fs, io, mem, process, time::Instant};
+use stechuhr::correction::CorrectionError;
+use stechuhr::date_ext::LocalizedFormat;
+use stechuhr::disk_space::{self, LowDiskSpace};
+use stechuhr::event_bus::{EventBus, EventSubscriber};
+use stechuhr::lifecycle::Lifecycle;
 use stechuhr::models::*;
+use stechuhr::night_summary::NightSummary;
+use stechuhr::self_test::{self, SelfTestProblem};
+use stechuhr::settings::{
+    ClosePolicy, LogTexts, NightSummaryMode, Settings as StechuhrSettings, SettingsError,
+};
+use stechuhr::shift_plan::ShiftPlanError;
+use stechuhr::storage::Storage;
+use stechuhr::write_latency::{self, SlowestWrite, TimedStorage};
+use stechuhr::{audit, db, event_detection, heartbeat, lifecycle, maintenance};
 
+use tabs::components::VirtualList;
 use tabs::management::{ManagementError, ManagementMessage, ManagementTab};
+use tabs::requests::TabRequest;
+use tabs::settings::{SettingsMessage, SettingsTab};
 use tabs::statistics::{StatisticsError, StatsMessage, StatsTab};
 use tabs::timetrack::{TimetrackMessage, TimetrackTab};
 
 const HEADER_SIZE: u16 = 32;
 const TAB_PADDING: u16 = 16;
+const TAB_COUNT: usize = 4;
+/// Seconds within which closing must be repeated to sign everyone off, see `ClosePolicy::SignOff`.
+const EXIT_CONFIRM_SECONDS: i64 = 60;
+/// Interval of the free space check, which runs df and is therefore not done every second.
+const DISK_SPACE_CHECK_SECONDS: u32 = 300;
+/// Ticks that are further apart than this mean that the computer was suspended, see `Stechuhr::catch_up`.
+const SUSPEND_GAP_SECONDS: i64 = 60;
+/// Height of the window until it reports its size, large enough for a full HD kiosk screen.
+const DEFAULT_WINDOW_HEIGHT: u32 = 1080;
+/// Height of a line in the log, including the spacing to the next one.
+const LOG_LINE_HEIGHT: u16 = 25;
 
 pub fn main() -> iced::Result {
     // DONE what does this accomplish? any side-effects?
@@ -35,25 +67,107 @@ pub fn main() -> iced::Result {
     dotenv().ok();
 
     env_logger::init();
-    let connection = db::establish_connection();
+
+    // With --simulate the update loop runs without a window against the given database, see `simulation`.
+    #[cfg(feature = "headless-simulation")]
+    if env::args().nth(1).as_deref() == Some("--simulate") {
+        if let Err(e) = simulation::run(env::args().skip(2)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    let mut connection = db::establish_connection();
+
+    // With --export the evaluation of a month is written without a window, see `headless_export`.
+    if env::args().nth(1).as_deref() == Some("--export") {
+        match headless_export::run(env::args().skip(2), Box::new(connection)) {
+            Ok(path) => {
+                println!("{}", path.display());
+                process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let problems = self_test::run(
+        &StechuhrSettings::load().unwrap_or_default(),
+        Local::now().naive_local(),
+        &mut connection,
+    );
+    // With --check we only report the problems, e.g. after an update.
+    if env::args().any(|arg| arg == "--check") {
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        if problems.is_empty() {
+            println!("Selbsttest erfolgreich.");
+            process::exit(0);
+        }
+        process::exit(1);
+    }
 
     Stechuhr::run(Settings {
         // a.d. set this so that we can handle the close request ourselves to sync data to db
         exit_on_close_request: false,
-        ..Settings::with_flags(connection)
+        ..Settings::with_flags((Box::new(connection), problems))
     })
 }
 
 pub struct SharedData {
     current_time: DateTime<Local>,
     staff: Vec<StaffMember>,
-    events: Vec<WorkEventT>,
-    connection: SqliteConnection,
+    /// Newly saved events, which are sent to the log and the tabs after each update.
+    event_bus: EventBus,
+    /// Current names of all staff members to show events of renamed staff members consistently.
+    staff_names: StaffNames,
+    /// Planned shifts of the current business day.
+    shifts: Vec<Shift>,
+    storage: Box<dyn Storage>,
     prompt_modal_state: modal::State<PromptModalState>,
     window_mode: window::Mode,
+    settings: StechuhrSettings,
+    /// Set while the configured card reader is not connected, see `Stechuhr::check_reader`.
+    reader_missing: bool,
+    /// Free space of the export directory while it is below the warning threshold, see `Stechuhr::check_disk_space`.
+    disk_space_low: Option<u64>,
+    /// Slowest write to the database of the last seconds and when it happened, see `Stechuhr::check_write_latency`.
+    slow_write: Option<(DateTime<Local>, std::time::Duration)>,
+    /// Requests of the tabs to each other, see `Stechuhr::route_requests`.
+    requests: Vec<TabRequest>,
+    /// Newest announcement to the staff, reloaded with the staff list so that changes of other terminals are shown.
+    announcement: Option<Announcement>,
+    /// Height of the window in pixels, which no scrollable exceeds, see `VirtualList::visible_rows`.
+    window_height: u32,
 }
 
 impl SharedData {
+    /// Shared data without a window for exports from the command line, see `headless_export`.
+    fn headless(mut storage: Box<dyn Storage>, settings: StechuhrSettings) -> Self {
+        let now = Local::now();
+        SharedData {
+            current_time: now,
+            staff: storage.load_state(now.naive_local()),
+            event_bus: EventBus::default(),
+            staff_names: storage.load_staff_names(),
+            shifts: Vec::new(),
+            storage,
+            prompt_modal_state: modal::State::default(),
+            window_mode: window::Mode::Hidden,
+            settings,
+            reader_missing: false,
+            disk_space_low: None,
+            slow_write: None,
+            requests: Vec::new(),
+            announcement: None,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+        }
+    }
+
     /// Log a WorkEvent in the scrollbar area at the bottom and also persist it to the DB.
     fn create_event(&mut self, event: WorkEvent) {
         let new_eventt = NewWorkEventT::now(event);
@@ -61,9 +175,16 @@ impl SharedData {
     }
 
     fn log_eventt(&mut self, new_eventt: NewWorkEventT) {
-        let eventt = db::insert_event(new_eventt, &mut self.connection);
-        // This breaks the ordering of events (since we have the pregenerated 6am boundaries in the future)
-        self.events.push(eventt);
+        match self.storage.insert_event(new_eventt) {
+            // This breaks the ordering of events (since we have the pregenerated 6am boundaries in the future)
+            Some(eventt) => self.event_bus.publish(eventt),
+            None => log::warn!("Doppelte Statusänderung wurde nicht gespeichert."),
+        }
+    }
+
+    /// Ask another tab to do something after the current update, see `TabRequest`.
+    fn request(&mut self, request: TabRequest) {
+        self.requests.push(request);
     }
 
     /// Log an information event.
@@ -78,10 +199,28 @@ impl SharedData {
         self.create_event(WorkEvent::Error(e));
     }
 
+    /// Record changes of the staff list for the report in the "Auswertung" tab.
+    /// A failure is only logged since the change itself was saved.
+    fn record_staff_history(&mut self, entries: Vec<NewStaffHistoryEntry>) {
+        if let Err(e) = self.storage.insert_staff_history(&entries) {
+            self.log_error(format!(
+                "Änderung der Mitarbeiterliste konnte nicht im Verlauf gespeichert werden: {}",
+                e
+            ));
+        }
+    }
+
     /// Open a modal to more prominently show some piece of information.
+    /// Messages are queued while the modal is open, e.g. several errors of one import, so that none is lost.
     fn prompt_message(&mut self, msg: String) {
         self.prompt_modal_state.show(true);
-        self.prompt_modal_state.inner_mut().msg = msg;
+        self.prompt_modal_state.inner_mut().push(msg);
+    }
+
+    /// Close the modal together with all queued messages.
+    fn close_prompt(&mut self) {
+        self.prompt_modal_state.show(false);
+        self.prompt_modal_state.inner_mut().clear();
     }
 
     /// Handle a result of some computation by showing the error message in a prompt.
@@ -95,6 +234,100 @@ impl SharedData {
         }
     }
 
+    /// Load the planned shifts of the current business day.
+    fn reload_shifts(&mut self) {
+        let business_day_start = self
+            .settings
+            .business_day_start(self.settings.business_date(self.current_time.naive_local()));
+        self.shifts = self
+            .storage
+            .load_shifts_between(business_day_start, business_day_start + Duration::days(1));
+    }
+
+    /// Check the consistency of the database for the last week and write the result to the log and the journal.
+    fn run_audit(&mut self) {
+        let now = self.current_time.naive_local();
+        let start_date = self.settings.business_date(now) - Duration::days(7);
+        let problems = audit::run(
+            start_date,
+            now,
+            &self.staff,
+            &self.settings,
+            self.storage.as_mut(),
+        );
+
+        let period = format!(
+            "{} bis {}",
+            start_date.format("%d.%m.%Y"),
+            now.format("%d.%m.%Y")
+        );
+        if problems.is_empty() {
+            let msg = format!("Datenbankprüfung {}: keine Probleme gefunden.", period);
+            log::info!("{}", &msg);
+            self.log_info(msg);
+        } else {
+            for problem in &problems {
+                log::error!("Datenbankprüfung: {}", problem);
+            }
+            let problems = problems
+                .iter()
+                .map(|problem| problem.to_string())
+                .collect::<Vec<_>>();
+            self.log_error(format!(
+                "Datenbankprüfung {}: {} Probleme gefunden. {}",
+                period,
+                problems.len(),
+                problems.join("; ")
+            ));
+        }
+    }
+
+    /// Detect the events of a business day and save them for the statistics, replacing those detected before.
+    fn record_event_sessions(
+        &mut self,
+        date: NaiveDate,
+    ) -> Result<Vec<NewEventSession>, StechuhrError> {
+        let start_time = self.settings.business_day_start(date);
+        let end_time = start_time + Duration::days(1);
+        let events = self.storage.load_events_of_kinds_between(
+            &[WorkEvent::KIND_6AM, WorkEvent::KIND_STATUS_CHANGE],
+            start_time,
+            end_time,
+        );
+        let sessions = event_detection::detect(
+            &events,
+            date,
+            Duration::minutes(self.settings.event_quiet_minutes.into()),
+            end_time.min(self.current_time.naive_local()),
+        );
+        self.storage.replace_event_sessions(date, &sessions)?;
+        Ok(sessions)
+    }
+
+    /// Write the summary of a finished business day into the export directory and print it if configured.
+    fn write_night_summary(&mut self, date: NaiveDate) -> Result<PathBuf, StechuhrError> {
+        let start_time = self.settings.business_day_start(date);
+        let end_time = start_time + Duration::days(1);
+        let events = self.storage.load_events_of_kinds_between(
+            &[WorkEvent::KIND_6AM, WorkEvent::KIND_STATUS_CHANGE],
+            start_time,
+            end_time,
+        );
+        let summary = NightSummary::new(&events, date, end_time, &self.staff_names);
+
+        disk_space::preflight(&self.settings.export_dir)?;
+        fs::create_dir_all(&self.settings.export_dir)?;
+        let path = self
+            .settings
+            .export_dir
+            .join(format!("Betriebstag {}.txt", date.format("%Y-%m-%d")));
+        summary.write(
+            &path,
+            self.settings.night_summary == NightSummaryMode::Print,
+        )?;
+        Ok(path)
+    }
+
     /// Set every staff member that is working to "Away" and corresponding StatusChange events.
     fn sign_off_all_staff(&mut self, sign_off_time: NaiveDateTime) -> Vec<NewWorkEventT> {
         self.staff
@@ -102,63 +335,647 @@ impl SharedData {
             .filter(|staff_member| staff_member.status == WorkStatus::Working)
             .map(|staff_member| {
                 let uuid = staff_member.uuid();
-                let name = staff_member.name.clone();
                 let new_status = WorkStatus::Away;
                 staff_member.status = new_status;
                 NewWorkEventT::new(
                     sign_off_time,
-                    WorkEvent::StatusChange(uuid, name, new_status),
+                    WorkEvent::StatusChange(uuid, String::new(), new_status),
                 )
             })
             .collect()
     }
 }
 
+/// Events of the current business day, shown in the log at the bottom.
+#[derive(Debug, Default)]
+struct EventLog {
+    events: Vec<WorkEventT>,
+}
+
+impl EventSubscriber for EventLog {
+    fn on_event(&mut self, eventt: &WorkEventT) {
+        self.events.push(eventt.clone());
+    }
+
+    /// A new business day starts with an empty log.
+    fn on_new_business_day(&mut self) {
+        self.events.clear();
+    }
+}
+
+/// Formatted lines of the log, so that the events are not described again in every frame.
+/// Lines are kept as long as their events and the names and settings they were formatted with are unchanged.
+#[derive(Debug, Default)]
+struct LogLines {
+    /// Ids of the events the lines were formatted from.
+    ids: Vec<i32>,
+    lines: Vec<String>,
+    staff_names: StaffNames,
+    show_former_names: bool,
+    locale: String,
+    texts: LogTexts,
+}
+
+impl LogLines {
+    /// Format everything again, e.g. after the events were reloaded from the database.
+    fn clear(&mut self) {
+        self.ids.clear();
+        self.lines.clear();
+    }
+
+    /// Bring the lines up to date with the events. Usually only new events at the end need to be formatted.
+    fn update(
+        &mut self,
+        events: &[WorkEventT],
+        shared: &SharedData,
+        time_format: &str,
+    ) -> &[String] {
+        let settings = &shared.settings;
+        if self.staff_names != shared.staff_names
+            || self.show_former_names != settings.show_former_names
+            || self.locale != settings.locale
+            || self.texts != settings.log_texts
+        {
+            self.clear();
+            self.staff_names = shared.staff_names.clone();
+            self.show_former_names = settings.show_former_names;
+            self.locale.clone_from(&settings.locale);
+            self.texts.clone_from(&settings.log_texts);
+        }
+
+        let unchanged = self
+            .ids
+            .iter()
+            .zip(events)
+            .take_while(|(id, eventt)| **id == eventt.id())
+            .count();
+        self.ids.truncate(unchanged);
+        self.lines.truncate(unchanged);
+        for eventt in &events[unchanged..] {
+            let time = Local.from_local_datetime(&eventt.created_at).unwrap();
+            self.ids.push(eventt.id());
+            self.lines.push(format!(
+                "{}: {}",
+                time.format_localized(time_format, settings.locale()),
+                shared.staff_names.describe(
+                    &eventt.event,
+                    settings.show_former_names,
+                    &settings.log_texts
+                )
+            ));
+        }
+        &self.lines
+    }
+}
+
 #[derive(Debug, PartialEq, Default)]
 struct PromptModalState {
-    msg: String,
+    messages: Vec<String>,
+    /// Index of the message that is shown.
+    current: usize,
     ok_button_state: button::State,
+    previous_button_state: button::State,
+    next_button_state: button::State,
+}
+
+impl PromptModalState {
+    /// Queue a message, the same message is only shown once in a row.
+    fn push(&mut self, msg: String) {
+        if self.messages.last() != Some(&msg) {
+            self.messages.push(msg);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.messages.clear();
+        self.current = 0;
+    }
+
+    fn current(&self) -> &str {
+        self.messages
+            .get(self.current)
+            .map_or("", |msg| msg.as_str())
+    }
+
+    fn previous(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    fn next(&mut self) {
+        if self.current + 1 < self.messages.len() {
+            self.current += 1;
+        }
+    }
 }
 
 struct Stechuhr {
     shared: SharedData,
+    log: EventLog,
+    log_lines: LogLines,
     log_scroll: scrollable::State,
+    log_list: VirtualList,
     active_tab: StechuhrTab,
     should_exit: bool,
+    /// Time at which closing was refused with a warning, see `ClosePolicy::SignOff`.
+    exit_warned_at: Option<NaiveDateTime>,
+    /// Day on which the weekly audit last ran, so that it runs only once.
+    last_audit: Option<NaiveDate>,
+    /// Set while the heartbeat cannot be written, so that we only log the first failure.
+    heartbeat_failed: bool,
+    /// Handle to the slowest write of the storage during the current update.
+    slowest_write: SlowestWrite,
+    /// Events of previous business days that were loaded on request to be shown above the current log.
+    older_events: Vec<WorkEventT>,
+    /// Start of the oldest business day in `older_events`.
+    older_events_start: Option<NaiveDateTime>,
+    older_events_state: button::State,
+    older_log_lines: LogLines,
+    resync_state: button::State,
     timetrack: TimetrackTab,
     management: ManagementTab,
     statistics: StatsTab,
+    settings: SettingsTab,
 }
 
 impl Stechuhr {
-    /// Generate a container containing a scrollable with all WorkEvents.
+    /// Send the events saved during the last update to everyone who shows them.
+    fn dispatch_events(&mut self) {
+        self.shared
+            .event_bus
+            .dispatch(&mut [&mut self.log, &mut self.timetrack]);
+    }
+
+    /// Deliver the requests that the tabs made to each other during the last update.
+    /// Handling a request may lead to further requests, which are delivered as well.
+    /// Requests that run asynchronously are returned as commands.
+    fn route_requests(&mut self) -> Command<Message> {
+        let mut commands = Vec::new();
+        while !self.shared.requests.is_empty() {
+            for request in mem::take(&mut self.shared.requests) {
+                match request {
+                    TabRequest::Management(request) => {
+                        self.management.handle_request(&mut self.shared, request)
+                    }
+                    TabRequest::Exit(mode) => self.exit_application(mode),
+                    TabRequest::PickExportDir(directory) => commands.push(Command::perform(
+                        rfd::AsyncFileDialog::new()
+                            .set_title("Speicherort der Exporte wählen")
+                            .set_directory(directory)
+                            .pick_folder(),
+                        |folder| {
+                            Message::Statistics(StatsMessage::ExportDirChosen(
+                                folder.map(|folder| folder.path().to_path_buf()),
+                            ))
+                        },
+                    )),
+                }
+            }
+        }
+        Command::batch(commands)
+    }
+
+    /// Record the start in the log, and before it whether the previous run crashed or had another version.
+    fn record_startup(&mut self) {
+        let now = self.shared.current_time.naive_local();
+        let previous = self
+            .shared
+            .storage
+            .load_latest_events_of_kind(WorkEvent::KIND_LIFECYCLE, 2)
+            .into_iter()
+            .filter_map(|eventt| match eventt.event {
+                WorkEvent::Lifecycle(lifecycle) => Some(lifecycle),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let last_seen = self.shared.storage.load_last_event_time(now);
+
+        for lifecycle in lifecycle::startup_events(&previous, last_seen, lifecycle::VERSION) {
+            if let Lifecycle::Crashed(_) = lifecycle {
+                log::error!("Stechuhr {}", lifecycle);
+            }
+            self.shared.create_event(WorkEvent::Lifecycle(lifecycle));
+        }
+    }
+
+    /// Show the supervisor that the UI loop is still running.
+    fn heartbeat(&mut self) {
+        let result = match &self.shared.settings.heartbeat_path {
+            Some(path) => heartbeat::write(path, self.shared.current_time),
+            None => Ok(()),
+        }
+        .and_then(|()| heartbeat::notify_watchdog());
+
+        match result {
+            Ok(()) => self.heartbeat_failed = false,
+            Err(e) if !self.heartbeat_failed => {
+                self.heartbeat_failed = true;
+                let e = format!("Lebenszeichen konnte nicht geschrieben werden: {}", e);
+                log::error!("{}", &e);
+                self.shared.log_error(e);
+            }
+            Err(_) => {}
+        }
+    }
+
+    /// Swipes are silently lost while the card reader is unplugged, so we warn as long as its device file is missing.
+    fn check_reader(&mut self) {
+        let reader_missing = match &self.shared.settings.kiosk.reader_device {
+            Some(device) => !device.exists(),
+            None => false,
+        };
+        if reader_missing == self.shared.reader_missing {
+            return;
+        }
+
+        self.shared.reader_missing = reader_missing;
+        if reader_missing {
+            let e = String::from("Der Kartenleser ist nicht mehr verbunden.");
+            log::error!("{}", &e);
+            self.shared.log_error(e);
+        } else {
+            self.shared
+                .log_info(String::from("Der Kartenleser ist wieder verbunden."));
+        }
+    }
+
+    /// Exports fail once the disk is full, so we warn as long as the free space is below the threshold.
+    /// Nothing is shown if the free space cannot be determined.
+    fn check_disk_space(&mut self) {
+        let threshold = self
+            .shared
+            .settings
+            .disk_space_warning_mb
+            .saturating_mul(1024 * 1024);
+        let disk_space_low = disk_space::available_bytes(&self.shared.settings.export_dir)
+            .ok()
+            .filter(|available| *available < threshold);
+        let was_low = self.shared.disk_space_low.is_some();
+        self.shared.disk_space_low = disk_space_low;
+        match disk_space_low {
+            Some(available) if !was_low => {
+                let e = format!(
+                    "Auf dem Datenträger des Exportverzeichnisses sind nur noch {} frei.",
+                    disk_space::format_bytes(available)
+                );
+                log::error!("{}", &e);
+                self.shared.log_error(e);
+            }
+            None if was_low => self.shared.log_info(String::from(
+                "Auf dem Datenträger des Exportverzeichnisses ist wieder genug Platz frei.",
+            )),
+            _ => {}
+        }
+    }
+
+    /// The window does not react while the database is written, which looks like a crash if the database is slow.
+    /// So slow writes are shown on the dashboard for some seconds, and one that exceeds the threshold is logged.
+    fn check_write_latency(&mut self) {
+        let now = self.shared.current_time;
+        // Keep showing the slowest write until nothing was slow for some seconds.
+        let shown = self.shared.slow_write.filter(|(at, _)| {
+            now - *at < Duration::seconds(write_latency::SLOW_WRITE_SHOWN_SECONDS)
+        });
+        let slowest = self.slowest_write.take().filter(|duration| {
+            *duration >= std::time::Duration::from_millis(write_latency::SLOW_WRITE_MILLIS)
+        });
+        self.shared.slow_write = match (shown, slowest) {
+            (Some((_, shown)), Some(slowest)) => Some((now, shown.max(slowest))),
+            (None, Some(slowest)) => Some((now, slowest)),
+            (shown, None) => shown,
+        };
+
+        let threshold =
+            std::time::Duration::from_millis(self.shared.settings.slow_write_warning_ms);
+        let was_warned = shown.is_some_and(|(_, shown)| shown >= threshold);
+        if let Some(slowest) = slowest.filter(|slowest| *slowest >= threshold && !was_warned) {
+            let e = format!(
+                "Speichern in der Datenbank dauerte {}. Ist die Verbindung zur Datenbank langsam?",
+                write_latency::format_duration(slowest)
+            );
+            log::warn!("{}", &e);
+            self.shared.log_error(e);
+        }
+    }
+
+    /// No ticks arrive while the computer is suspended, e.g. when the kiosk sleeps overnight. Afterwards we do
+    /// what was missed at the end of each business day in between: the 6am event is saved if it was not
+    /// pregenerated and everyone who is still working is signed off.
+    fn catch_up(&mut self, from: NaiveDateTime, to: NaiveDateTime) {
+        let msg = format!(
+            "Die Stechuhr lief von {} bis {} nicht, z.B. weil der Computer im Ruhezustand war.",
+            from.format("%d.%m.%Y %H:%M:%S"),
+            to.format("%d.%m.%Y %H:%M:%S")
+        );
+        log::warn!("{}", &msg);
+        self.shared.log_error(msg);
+
+        let settings = &self.shared.settings;
+        let mut date = settings.business_date(from);
+        let mut boundaries = Vec::new();
+        while date < settings.business_date(to) {
+            // The boundary at `to` itself is handled by the regular tick.
+            let boundary = settings.business_day_start(date.succ()) - Duration::seconds(1);
+            if from < boundary && boundary < to {
+                boundaries.push(boundary);
+            }
+            date = date.succ();
+        }
+
+        for boundary in boundaries {
+            let inserted = self.shared.storage.ensure_6am_event(boundary);
+            let signed_off = self.shared.sign_off_all_staff(boundary);
+            if inserted || !signed_off.is_empty() {
+                self.shared.log_info(format!(
+                    "Betriebstagsgrenze am {} wurde nachgeholt, {} Personen abgemeldet.",
+                    boundary.format("%d.%m.%Y %H:%M:%S"),
+                    signed_off.len()
+                ));
+            }
+        }
+    }
+
+    /// Reload the staff list in case another terminal sharing the database changed it.
+    fn refresh_staff(&mut self) {
+        // Rows of the management tab are addressed by index, so we must not reorder them while a deletion is pending.
+        if self.management.is_deleting() {
+            return;
+        }
+
+        let now = self.shared.current_time.naive_local();
+        let previous_events = self.shared.storage.load_status_events_before(now);
+
+        let previous_staff = self.shared.staff.clone();
+        let changes = self
+            .shared
+            .storage
+            .refresh_staff(&mut self.shared.staff, &previous_events);
+        if changes.is_empty() {
+            return;
+        }
+        self.shared.staff_names.update(&self.shared.staff);
+
+        self.shared.log_info(format!(
+            "Mitarbeiterliste wurde aus der Datenbank aktualisiert: {} neu, {} entfernt, {} geändert.",
+            changes.added.len(),
+            changes.removed.len(),
+            changes.changed.len()
+        ));
+        self.management
+            .staff_changed(&mut self.shared, &previous_staff, &changes);
+    }
+
+    /// Reload the staff, their work status and the events of the business day from the database, e.g. after it was
+    /// fixed with sqlite3 while Stechuhr keeps running. Unlike `refresh_staff` the work status is recomputed too.
+    fn resync(&mut self) {
+        if self.management.is_deleting() {
+            self.shared.prompt_message(String::from(
+                "Bitte zuerst das Löschen abschließen, bevor neu geladen wird.",
+            ));
+            return;
+        }
+
+        let now = self.shared.current_time.naive_local();
+        let previous_events = self.shared.storage.load_status_events_before(now);
+        let previous_staff = self.shared.staff.clone();
+        let mut changes = self
+            .shared
+            .storage
+            .refresh_staff(&mut self.shared.staff, &previous_events);
+        let mut status_changes = 0;
+        for staff_member in &mut self.shared.staff {
+            let status = db::compute_status(staff_member.uuid(), &previous_events);
+            if staff_member.status != status {
+                staff_member.status = status;
+                status_changes += 1;
+                if !changes.changed.contains(&staff_member.uuid()) {
+                    changes.changed.push(staff_member.uuid());
+                }
+            }
+        }
+        self.shared.staff_names = self.shared.storage.load_staff_names();
+        self.shared.announcement = self.shared.storage.load_announcement();
+        self.shared.reload_shifts();
+        self.management
+            .staff_changed(&mut self.shared, &previous_staff, &changes);
+        self.statistics.resync(self.shared.storage.as_mut());
+
+        // The log and the dashboard start over with the events from the database.
+        let business_day_start = self
+            .shared
+            .settings
+            .business_day_start(self.shared.settings.business_date(now));
+        let events = self
+            .shared
+            .storage
+            .load_events_between(Some(business_day_start), Some(now));
+        self.shared.event_bus.start_business_day();
+        for eventt in events {
+            self.shared.event_bus.publish(eventt);
+        }
+        self.older_events.clear();
+        self.older_events_start = None;
+        self.log_lines.clear();
+
+        self.shared.log_info(format!(
+            "Daten wurden neu aus der Datenbank geladen: {} Personen, {} mit geändertem Status.",
+            self.shared.staff.len(),
+            status_changes
+        ));
+    }
+
+    /// Close the window, unless the close policy of the settings forbids it while staff members are working.
+    /// Closing for a restart keeps everyone's status regardless of the policy, see `ExitMode`.
+    fn exit_application(&mut self, mode: ExitMode) {
+        let now = self.shared.current_time.naive_local();
+        let close_policy = match mode {
+            ExitMode::Restart => ClosePolicy::Allow,
+            ExitMode::Quit | ExitMode::Maintenance => self.shared.settings.kiosk.close_policy,
+        };
+        let working = self
+            .shared
+            .staff
+            .iter()
+            .filter(|staff_member| staff_member.status == WorkStatus::Working)
+            .count();
+
+        if working > 0 {
+            match close_policy {
+                ClosePolicy::Block => {
+                    self.shared.prompt_message(String::from(
+                        "Es sind noch Personen am Arbeiten. Bitte zuerst alle auf \"Pause\" stellen oder das Event beenden.",
+                    ));
+                    return;
+                }
+                ClosePolicy::SignOff => {
+                    let is_confirmed = self.exit_warned_at.is_some_and(|warned_at| {
+                        now - warned_at <= Duration::seconds(EXIT_CONFIRM_SECONDS)
+                    });
+                    if !is_confirmed {
+                        self.exit_warned_at = Some(now);
+                        self.shared.prompt_message(format!(
+                            "Es sind noch {} Personen am Arbeiten. Wenn die Stechuhr innerhalb einer Minute nochmal geschlossen wird, werden alle automatisch abgemeldet.",
+                            working
+                        ));
+                        return;
+                    }
+                    for new_eventt in self.shared.sign_off_all_staff(now) {
+                        self.shared.log_eventt(new_eventt);
+                    }
+                    self.shared.log_info(format!(
+                        "Stechuhr beendet, {} Personen wurden automatisch abgemeldet.",
+                        working
+                    ));
+                }
+                ClosePolicy::Allow => {
+                    self.shared.log_info(format!(
+                        "Stechuhr beendet, {} Personen sind weiterhin am Arbeiten.",
+                        working
+                    ));
+                }
+            }
+        }
+
+        let result = self
+            .shared
+            .storage
+            .save_staff(&mut self.shared.staff)
+            .map_err(StechuhrError::Diesel)
+            .and_then(|()| match mode {
+                ExitMode::Maintenance => self.backup(),
+                ExitMode::Quit | ExitMode::Restart => Ok(()),
+            });
+        match result {
+            Ok(()) => {
+                self.shared
+                    .create_event(WorkEvent::Lifecycle(Lifecycle::Stopped));
+                self.should_exit = true;
+            }
+            Err(e) => self.shared.handle_result(Err(e)),
+        }
+    }
+
+    /// Copy the database into the backup directory of the settings, see `maintenance::backup`.
+    fn backup(&mut self) -> Result<(), StechuhrError> {
+        let dir = &self.shared.settings.backup_dir;
+        fs::create_dir_all(dir)?;
+        let path = maintenance::backup_path(dir, self.shared.current_time.naive_local());
+        self.shared.storage.backup(&path)?;
+        self.shared.log_info(format!(
+            "Datenbank wurde nach {} gesichert.",
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// Switching tabs locks the password protected tabs again.
+    fn select_tab(&mut self, new_tab: usize) {
+        self.management.deauth();
+        self.settings.deauth();
+        self.active_tab = StechuhrTab::from(new_tab);
+    }
+
+    /// Prepend the events of the business day before the oldest one shown in the log.
+    /// They are only loaded on request so that startup does not have to read the whole history.
+    fn load_older_events(&mut self) {
+        let end = self.older_events_start.unwrap_or_else(|| {
+            let now = self.shared.current_time.naive_local();
+            self.shared
+                .settings
+                .business_day_start(self.shared.settings.business_date(now))
+        });
+        let start = end - Duration::days(1);
+
+        let mut events = self
+            .shared
+            .storage
+            .load_events_between(Some(start), Some(end));
+        events.append(&mut self.older_events);
+        self.older_events = events;
+        self.older_events_start = Some(start);
+    }
+
+    /// Generate a container containing a scrollable with all WorkEvents of the current business day,
+    /// preceded by the events of previous days that were loaded on request.
     fn get_logview<'a>(
         log_scroll: &'a mut scrollable::State,
+        log_list: &VirtualList,
+        older_events_state: &'a mut button::State,
+        resync_state: &'a mut button::State,
+        older_lines: &[String],
+        lines: &[String],
         shared: &SharedData,
     ) -> Element<'a, Message> {
+        let business_date = shared
+            .settings
+            .business_date(shared.current_time.naive_local());
+        let header = Text::new(format!(
+            "Betriebstag {}",
+            business_date.format_localized("%A, %e. %B %Y", shared.settings.locale())
+        ))
+        .size(stechuhr::TEXT_SIZE);
+        let header = Row::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push(header)
+            .push(Button::new(resync_state, Text::new("Neu laden (F5)")).on_press(Message::Resync));
+
         let log_initial = Scrollable::new(log_scroll)
-            .on_scroll(|d| {
-                if d == 1.0 {
-                    Message::ScrollSnap
-                } else {
-                    Message::Nop
-                }
-            })
+            .on_scroll(Message::LogScrolled)
             .width(Length::Fill)
-            .spacing(5)
-            .padding(5);
+            .padding(5)
+            .push(
+                Button::new(
+                    older_events_state,
+                    Text::new("Vortag laden").size(stechuhr::TEXT_SIZE),
+                )
+                .on_press(Message::LoadOlderEvents),
+            );
+
+        // Only the lines that can be visible are built, the log of a busy night has thousands of them.
+        let rows = older_lines.len() + lines.len();
+        let visible = log_list.visible_rows(rows, shared.window_height);
+        let built = older_lines
+            .iter()
+            .chain(lines)
+            .skip(visible.start)
+            .take(visible.len())
+            .map(|line| {
+                Text::new(line.as_str())
+                    .height(Length::Units(log_list.row_height()))
+                    .into()
+            });
+        let log_view = log_list.push_rows(log_initial, rows, visible, built);
+
+        Column::new().push(header).push(log_view).into()
+    }
+}
 
-        let log_view = shared.events.iter().fold(log_initial, |log_view, eventt| {
-            let time = Local.from_local_datetime(&eventt.created_at).unwrap();
+/// How the application is closed. Closing the window always quits normally, the other modes are chosen in the
+/// settings tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitMode {
+    /// Subject to the close policy of the settings.
+    Quit,
+    /// For an update during an event: everyone keeps working and their status is taken from the events on the
+    /// next start.
+    Restart,
+    /// Like `Quit`, but the database is backed up first. A failed backup keeps the application open.
+    Maintenance,
+}
 
-            log_view.push(Text::new(format!(
-                "{}: {}",
-                time.format_localized("%T", Locale::de_DE).to_string(),
-                eventt.event
-            )))
-        });
+impl ExitMode {
+    pub const ALL: [ExitMode; 3] = [ExitMode::Quit, ExitMode::Restart, ExitMode::Maintenance];
+}
+
+impl fmt::Display for ExitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ExitMode::Quit => "Beenden",
+            ExitMode::Restart => "Neustart, Status bleibt",
+            ExitMode::Maintenance => "Wartung mit Sicherung",
+        };
 
-        log_view.into()
+        fmt::Display::fmt(str, f)
     }
 }
 
@@ -167,6 +984,7 @@ enum StechuhrTab {
     Timetrack = 0,
     Management = 1,
     Statistics = 2,
+    Settings = 3,
 }
 
 impl From<usize> for StechuhrTab {
@@ -175,6 +993,7 @@ impl From<usize> for StechuhrTab {
             0 => Self::Timetrack,
             1 => Self::Management,
             2 => Self::Statistics,
+            3 => Self::Settings,
             _ => panic!("Unknown active_tab: {}", active_tab),
         }
     }
@@ -185,12 +1004,20 @@ enum Message {
     Tick(DateTime<Local>),
     ExitApplication,
     ExitPrompt,
+    PreviousPrompt,
+    NextPrompt,
     TabSelected(usize),
     Timetrack(TimetrackMessage),
     Management(ManagementMessage),
     Statistics(StatsMessage),
+    Settings(SettingsMessage),
     HandleEvent(Event),
-    ScrollSnap,
+    LoadOlderEvents,
+    Resync,
+    LogScrolled(f32),
+    WindowResized(u32),
+    /// Only runs the end of an update, e.g. to deliver requests in the tests.
+    #[cfg(test)]
     Nop,
     ToggleFullscreen,
 }
@@ -198,7 +1025,8 @@ enum Message {
 impl Application for Stechuhr {
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = SqliteConnection;
+    /// The storage, usually the database connection, and the problems found by the self test at startup.
+    type Flags = (Box<dyn Storage>, Vec<SelfTestProblem>);
 
     fn should_exit(&self) -> bool {
         self.should_exit
@@ -209,32 +1037,100 @@ impl Application for Stechuhr {
         self.shared.window_mode
     }
 
-    fn new(mut connection: SqliteConnection) -> (Self, Command<Message>) {
-        let staff = db::load_state(Local::now().naive_local(), &mut connection);
+    fn new((storage, problems): Self::Flags) -> (Self, Command<Message>) {
+        let (storage, slowest_write) = TimedStorage::new(storage);
+        let mut storage: Box<dyn Storage> = Box::new(storage);
+        let settings = StechuhrSettings::load().unwrap_or_else(|e| {
+            log::error!("{}", e);
+            StechuhrSettings::default()
+        });
+        let window_mode = if settings.kiosk.fullscreen {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        };
+        let startup = Instant::now();
+        let now = Local::now().naive_local();
+        let staff = storage.load_state(now);
+        let staff_names = storage.load_staff_names();
+        let announcement = storage.load_announcement();
+        // The log shows everything that happened since the start of the current business day.
+        let business_day_start = settings.business_day_start(settings.business_date(now));
+        let events = storage.load_events_between(Some(business_day_start), Some(now));
+        let shifts =
+            storage.load_shifts_between(business_day_start, business_day_start + Duration::days(1));
+        log::info!(
+            "Zustand in {} ms geladen ({} Events)",
+            startup.elapsed().as_millis(),
+            events.len()
+        );
         let management = ManagementTab::new(&staff);
+        let statistics = StatsTab::new(storage.as_mut());
         // Log should follow new events by default.
         let mut log_scroll = scrollable::State::default();
-        log_scroll.snap_to(1.0);
-
-        (
-            Self {
-                shared: SharedData {
-                    current_time: Local::now(),
-                    staff,
-                    events: Vec::new(),
-                    connection: connection,
-                    prompt_modal_state: modal::State::default(),
-                    window_mode: window::Mode::Fullscreen,
-                },
-                log_scroll,
-                active_tab: StechuhrTab::Timetrack,
-                should_exit: false,
-                timetrack: TimetrackTab::new(),
-                management,
-                statistics: StatsTab::new(),
+        let mut log_list = VirtualList::new(LOG_LINE_HEIGHT);
+        log_list.snap_to(&mut log_scroll, 1.0);
+        let mut event_bus = EventBus::default();
+        for eventt in events {
+            event_bus.publish(eventt);
+        }
+
+        let mut stechuhr = Self {
+            shared: SharedData {
+                current_time: Local::now(),
+                staff,
+                event_bus,
+                staff_names,
+                shifts,
+                storage,
+                prompt_modal_state: modal::State::default(),
+                window_mode,
+                settings,
+                reader_missing: false,
+                disk_space_low: None,
+                slow_write: None,
+                requests: Vec::new(),
+                announcement,
+                window_height: DEFAULT_WINDOW_HEIGHT,
             },
-            Command::none(),
-        )
+            log: EventLog::default(),
+            log_lines: LogLines::default(),
+            log_scroll,
+            log_list,
+            active_tab: StechuhrTab::Timetrack,
+            should_exit: false,
+            exit_warned_at: None,
+            last_audit: None,
+            heartbeat_failed: false,
+            slowest_write,
+            older_events: Vec::new(),
+            older_events_start: None,
+            older_events_state: button::State::default(),
+            older_log_lines: LogLines::default(),
+            resync_state: button::State::default(),
+            timetrack: TimetrackTab::new(),
+            management,
+            statistics,
+            settings: SettingsTab::new(),
+        };
+
+        stechuhr.record_startup();
+        // All problems are shown at once, so that they can be fixed before Stechuhr is used.
+        if !problems.is_empty() {
+            let problems = problems
+                .iter()
+                .map(|problem| problem.to_string())
+                .collect::<Vec<_>>();
+            for problem in &problems {
+                stechuhr.shared.log_error(problem.clone());
+            }
+            stechuhr.shared.prompt_message(format!(
+                "Beim Selbsttest wurden Probleme gefunden:\n{}",
+                problems.join("\n")
+            ));
+        }
+        stechuhr.dispatch_events();
+        (stechuhr, Command::none())
     }
 
     fn title(&self) -> String {
@@ -244,38 +1140,87 @@ impl Application for Stechuhr {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Tick(local_time) => {
+                let previous_time = self.shared.current_time;
+                let previous_date = self
+                    .shared
+                    .settings
+                    .business_date(previous_time.naive_local());
                 self.shared.current_time = local_time;
+                if local_time - previous_time > Duration::seconds(SUSPEND_GAP_SECONDS) {
+                    self.catch_up(previous_time.naive_local(), local_time.naive_local());
+                }
+                self.heartbeat();
+                self.check_reader();
+                if local_time.num_seconds_from_midnight() % DISK_SPACE_CHECK_SECONDS == 0 {
+                    self.check_disk_space();
+                }
+
+                // A new business day starts with an empty log.
+                if self.shared.settings.business_date(local_time.naive_local()) != previous_date {
+                    self.shared.event_bus.start_business_day();
+                    self.older_events.clear();
+                    self.older_events_start = None;
+                    self.shared.reload_shifts();
+
+                    // The events of the finished business day are detected for the statistics.
+                    match self.shared.record_event_sessions(previous_date) {
+                        Ok(sessions) => {
+                            for session in sessions {
+                                self.shared.log_info(format!(
+                                    "Veranstaltung erkannt: {} bis {}, bis zu {} Personen gleichzeitig.",
+                                    session.started_at.format("%d.%m.%Y %H:%M"),
+                                    session.ended_at.format("%d.%m.%Y %H:%M"),
+                                    session.peak_count
+                                ));
+                            }
+                        }
+                        Err(e) => self.shared.log_error(format!(
+                            "Veranstaltungen konnten nicht erkannt werden: {}",
+                            e
+                        )),
+                    }
+
+                    if self.shared.settings.night_summary != NightSummaryMode::Off {
+                        match self.shared.write_night_summary(previous_date) {
+                            Ok(path) => self.shared.log_info(format!(
+                                "Zusammenfassung des Betriebstags wurde in der Datei {} gespeichert.",
+                                path.display()
+                            )),
+                            Err(e) => self.shared.log_error(format!(
+                                "Zusammenfassung des Betriebstags konnte nicht erstellt werden: {}",
+                                e
+                            )),
+                        }
+                    }
+                }
 
                 // If it's just before 6am, sign off all staff. The 6am barrier event will already exist so we don't have to create it again.
-                if local_time.time() == NaiveTime::from_hms(5, 59, 59) {
+                if local_time.time() == self.shared.settings.sign_off_time() {
                     let _ = self.shared.sign_off_all_staff(local_time.naive_local());
                 }
-            }
-            Message::ExitApplication => {
-                if self
-                    .shared
-                    .staff
-                    .iter()
-                    .any(|staff_member| staff_member.status == WorkStatus::Working)
+
+                let refresh_seconds = self.shared.settings.staff_refresh_seconds;
+                if refresh_seconds > 0
+                    && local_time.num_seconds_from_midnight() % refresh_seconds == 0
                 {
-                    self.shared.prompt_message(String::from(
-                        "Es sind noch Personen am Arbeiten. Bitte zuerst alle auf \"Pause\" stellen oder das Event beenden.",
-                    ));
-                } else {
-                    match db::save_staff(&self.shared.staff, &mut self.shared.connection) {
-                        Ok(()) => self.should_exit = true,
-                        Err(e) => self.shared.handle_result(Err(StechuhrError::Diesel(e))),
-                    }
+                    self.refresh_staff();
+                    self.shared.announcement = self.shared.storage.load_announcement();
+                }
+
+                // Weekly consistency check during quiet hours.
+                if local_time.weekday() == self.shared.settings.audit_weekday
+                    && local_time.time() >= self.shared.settings.audit_time
+                    && self.last_audit != Some(local_time.naive_local().date())
+                {
+                    self.last_audit = Some(local_time.naive_local().date());
+                    self.shared.run_audit();
                 }
             }
-            Message::ExitPrompt => {
-                self.shared.prompt_modal_state.show(false);
-                self.shared.prompt_modal_state.inner_mut().msg.clear();
-            }
-            Message::TabSelected(new_tab) => {
-                self.management.deauth();
-                self.active_tab = StechuhrTab::from(new_tab);
-            }
+            Message::ExitApplication => self.exit_application(ExitMode::Quit),
+            Message::ExitPrompt => self.shared.close_prompt(),
+            Message::PreviousPrompt => self.shared.prompt_modal_state.inner_mut().previous(),
+            Message::NextPrompt => self.shared.prompt_modal_state.inner_mut().next(),
+            Message::TabSelected(new_tab) => self.select_tab(new_tab),
             Message::Timetrack(timetrack_message) => {
                 self.timetrack.update(&mut self.shared, timetrack_message);
             }
@@ -285,11 +1230,25 @@ impl Application for Stechuhr {
             Message::Statistics(stats_message) => {
                 self.statistics.update(&mut self.shared, stats_message);
             }
+            Message::Settings(settings_message) => {
+                self.settings.update(&mut self.shared, settings_message);
+            }
             Message::HandleEvent(Event::Keyboard(keyboard::Event::KeyPressed {
-                key_code: keyboard::KeyCode::Enter,
+                key_code: keyboard::KeyCode::Enter | keyboard::KeyCode::Escape,
                 ..
-            })) if self.shared.prompt_modal_state.is_shown() => {
-                self.shared.prompt_modal_state.show(false)
+            })) if self.shared.prompt_modal_state.is_shown() => self.shared.close_prompt(),
+            // Ctrl+Tab switches between the tabs, Tab alone moves the focus within a tab.
+            Message::HandleEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Tab,
+                modifiers,
+            })) if modifiers.control() => {
+                let active_tab = self.active_tab as usize;
+                let new_tab = if modifiers.shift() {
+                    (active_tab + TAB_COUNT - 1) % TAB_COUNT
+                } else {
+                    (active_tab + 1) % TAB_COUNT
+                };
+                self.select_tab(new_tab);
             }
             Message::HandleEvent(e) => match StechuhrTab::from(self.active_tab) {
                 StechuhrTab::Timetrack => self
@@ -301,30 +1260,59 @@ impl Application for Stechuhr {
                 StechuhrTab::Statistics => self
                     .statistics
                     .update(&mut self.shared, StatsMessage::HandleEvent(e)),
+                StechuhrTab::Settings => self
+                    .settings
+                    .update(&mut self.shared, SettingsMessage::HandleEvent(e)),
             },
-            Message::ScrollSnap => {
-                self.log_scroll.snap_to(1.0);
+            Message::LoadOlderEvents => self.load_older_events(),
+            Message::Resync => self.resync(),
+            Message::LogScrolled(offset) => {
+                // Once scrolled to the bottom, the log follows new events again.
+                if offset == 1.0 {
+                    self.log_list.snap_to(&mut self.log_scroll, 1.0);
+                } else {
+                    self.log_list.scrolled(offset);
+                }
             }
+            Message::WindowResized(height) => self.shared.window_height = height,
             Message::ToggleFullscreen => {
                 self.shared.window_mode = match self.shared.window_mode {
                     window::Mode::Fullscreen => window::Mode::Windowed,
                     _ => window::Mode::Fullscreen,
                 }
             }
+            #[cfg(test)]
             Message::Nop => {}
         };
-        Command::none()
+        let command = self.route_requests();
+        self.check_write_latency();
+        self.dispatch_events();
+        command
     }
 
     // DONE what is '_ in Element<'_, ...>?
     // explicitly elided lifetime. can also be set to 'a
     fn view(&mut self) -> Element<'_, Self::Message> {
+        let started = Instant::now();
         // log area at the bottom
-        let logview = Container::new(Stechuhr::get_logview(&mut self.log_scroll, &self.shared))
-            .padding(TAB_PADDING)
-            .width(Length::Fill)
-            .height(Length::FillPortion(20))
-            .style(stechuhr::style::LogviewStyle);
+        // Events of previous days also show the date so that they are not confused with today's.
+        let older_lines =
+            self.older_log_lines
+                .update(&self.older_events, &self.shared, "%d.%m. %T");
+        let lines = self.log_lines.update(&self.log.events, &self.shared, "%T");
+        let logview = Container::new(Stechuhr::get_logview(
+            &mut self.log_scroll,
+            &self.log_list,
+            &mut self.older_events_state,
+            &mut self.resync_state,
+            older_lines,
+            lines,
+            &self.shared,
+        ))
+        .padding(TAB_PADDING)
+        .width(Length::Fill)
+        .height(Length::FillPortion(20))
+        .style(stechuhr::style::LogviewStyle);
 
         // tab area at the top
         let tab_bar = TabBar::new(self.active_tab as usize, Message::TabSelected)
@@ -332,13 +1320,15 @@ impl Application for Stechuhr {
             .text_size(HEADER_SIZE)
             .push(self.timetrack.tab_label())
             .push(self.management.tab_label())
-            .push(self.statistics.tab_label());
+            .push(self.statistics.tab_label())
+            .push(self.settings.tab_label());
 
         // content of the currently active tab
         let tab_content = match self.active_tab {
             StechuhrTab::Timetrack => self.timetrack.view(&mut self.shared),
             StechuhrTab::Management => self.management.view(&mut self.shared),
             StechuhrTab::Statistics => self.statistics.view(&mut self.shared),
+            StechuhrTab::Settings => self.settings.view(&mut self.shared),
         };
         let tab_content = Container::new(tab_content)
             .padding(TAB_PADDING)
@@ -352,12 +1342,36 @@ impl Application for Stechuhr {
 
         // content has to be embedded into global modal
         let modal = Modal::new(&mut self.shared.prompt_modal_state, content, move |state| {
-            Card::new(Text::new("Information"), Text::new(&state.msg))
-                .foot(
-                    Button::new(&mut state.ok_button_state, Text::new("Ok"))
-                        .width(Length::Shrink)
-                        .on_press(Message::ExitPrompt),
-                )
+            // With several messages we show where we are and allow to go back and forth.
+            let count = state.messages.len();
+            let title = if count > 1 {
+                format!("Information ({} von {})", state.current + 1, count)
+            } else {
+                String::from("Information")
+            };
+            let body = Text::new(state.current());
+            let mut foot = Row::new().spacing(10);
+            if count > 1 {
+                let mut previous =
+                    Button::new(&mut state.previous_button_state, Text::new("Zurück"));
+                if state.current > 0 {
+                    previous = previous.on_press(Message::PreviousPrompt);
+                }
+                let mut next = Button::new(&mut state.next_button_state, Text::new("Weiter"));
+                if state.current + 1 < count {
+                    next = next.on_press(Message::NextPrompt);
+                }
+                foot = foot.push(previous).push(next);
+            }
+            let ok_label = if count > 1 { "Alle schließen" } else { "Ok" };
+            foot = foot.push(
+                Button::new(&mut state.ok_button_state, Text::new(ok_label))
+                    .width(Length::Shrink)
+                    .on_press(Message::ExitPrompt),
+            );
+
+            Card::new(Text::new(title), body)
+                .foot(foot)
                 .width(Length::Shrink)
                 .on_close(Message::ExitPrompt)
                 .into()
@@ -366,6 +1380,7 @@ impl Application for Stechuhr {
         .on_esc(Message::ExitPrompt);
 
         let element: Element<'_, Self::Message> = modal.into();
+        log::trace!("Ansicht in {} µs aufgebaut", started.elapsed().as_micros());
         // uncomment to enable debug mode that shows black outlines of containers
         // element.explain(Color::BLACK)
         element
@@ -382,6 +1397,9 @@ impl Application for Stechuhr {
                 (_, Event::Window(iced_native::window::Event::CloseRequested)) => {
                     Some(Message::ExitApplication)
                 }
+                (_, Event::Window(iced_native::window::Event::Resized { height, .. })) => {
+                    Some(Message::WindowResized(height))
+                }
                 (
                     Status::Ignored,
                     Event::Keyboard(keyboard::Event::KeyPressed {
@@ -389,6 +1407,18 @@ impl Application for Stechuhr {
                         ..
                     }),
                 ) => Some(Message::ToggleFullscreen),
+                (
+                    Status::Ignored,
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code: keyboard::KeyCode::F5,
+                        ..
+                    }),
+                ) => Some(Message::Resync),
+                /* Buttons capture presses but we need them to detect long presses on the dashboard. */
+                (_, e @ Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)))
+                | (_, e @ Event::Touch(touch::Event::FingerPressed { .. })) => {
+                    Some(Message::HandleEvent(e))
+                }
                 /* We need to be careful to only handle events that have not been captured elsewhere.
                  * Otherwise it can happen that we handle the "enter" again which originally opened the submission modal. */
                 (Status::Ignored, e) => Some(Message::HandleEvent(e)),
@@ -440,6 +1470,10 @@ pub enum StechuhrError {
     Management(ManagementError),
     Statistics(StatisticsError),
     Model(ModelError),
+    Settings(SettingsError),
+    ShiftPlan(ShiftPlanError),
+    Correction(CorrectionError),
+    DiskSpace(LowDiskSpace),
     Diesel(diesel::result::Error),
     Opener(opener::OpenError),
     CSV(csv::Error),
@@ -447,6 +1481,15 @@ pub enum StechuhrError {
     Str(String),
 }
 
+impl From<db::SaveStaffError> for StechuhrError {
+    fn from(e: db::SaveStaffError) -> Self {
+        match e {
+            db::SaveStaffError::Diesel(e) => Self::Diesel(e),
+            conflict => Self::Str(conflict.to_string()),
+        }
+    }
+}
+
 impl From<ManagementError> for StechuhrError {
     fn from(e: ManagementError) -> Self {
         Self::Management(e)
@@ -465,6 +1508,30 @@ impl From<ModelError> for StechuhrError {
     }
 }
 
+impl From<SettingsError> for StechuhrError {
+    fn from(e: SettingsError) -> Self {
+        Self::Settings(e)
+    }
+}
+
+impl From<ShiftPlanError> for StechuhrError {
+    fn from(e: ShiftPlanError) -> Self {
+        Self::ShiftPlan(e)
+    }
+}
+
+impl From<CorrectionError> for StechuhrError {
+    fn from(e: CorrectionError) -> Self {
+        Self::Correction(e)
+    }
+}
+
+impl From<LowDiskSpace> for StechuhrError {
+    fn from(e: LowDiskSpace) -> Self {
+        Self::DiskSpace(e)
+    }
+}
+
 impl From<csv::Error> for StechuhrError {
     fn from(e: csv::Error) -> Self {
         Self::CSV(e)
@@ -497,6 +1564,10 @@ impl fmt::Display for StechuhrError {
             StechuhrError::Management(e) => e.fmt(f),
             StechuhrError::Statistics(e) => e.fmt(f),
             StechuhrError::Model(e) => e.fmt(f),
+            StechuhrError::Settings(e) => e.fmt(f),
+            StechuhrError::ShiftPlan(e) => e.fmt(f),
+            StechuhrError::Correction(e) => e.fmt(f),
+            StechuhrError::DiskSpace(e) => e.fmt(f),
             StechuhrError::Diesel(e) => e.fmt(f),
             StechuhrError::Opener(e) => e.fmt(f),
             StechuhrError::CSV(e) => e.fmt(f),
@@ -512,13 +1583,37 @@ mod tests {
     use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
     use diesel::Connection;
     use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-    use iced::Application;
+    use iced::{keyboard, Application};
+    use iced_native::Event;
+    use pbkdf2::{
+        password_hash::{PasswordHasher, SaltString},
+        Pbkdf2,
+    };
     use stechuhr::{
         db,
-        models::{NewStaffMember, NewWorkEventT, StaffMember, WorkEvent, WorkStatus},
+        lifecycle::{self, Lifecycle},
+        models::{
+            NewStaffMember, NewWorkEventT, PasswordHash, StaffMember, WorkEvent, WorkEventT,
+            WorkStatus,
+        },
+        quick_action::{QuickAction, QuickActionStep},
+        settings::ClosePolicy,
+        storage::Storage,
     };
 
-    use crate::{tabs::timetrack::TimetrackMessage, Message, Stechuhr};
+    use crate::{
+        recording_storage::RecordingStorage,
+        tabs::management::{
+            AnnouncementMessage, ManagementMessage, ManagementRequest, StaffField, StaffListMessage,
+        },
+        tabs::requests::TabRequest,
+        tabs::settings::SettingsMessage,
+        tabs::statistics::{ExportOptionsMessage, StatsMessage},
+        tabs::timetrack::{
+            GroupSignInMessage, PinChangeMessage, QuickAdminMessage, TimetrackMessage,
+        },
+        ExitMode, LogLines, Message, Stechuhr, StechuhrTab,
+    };
 
     const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
@@ -538,6 +1633,7 @@ mod tests {
                     String::from("Aaron"),
                     String::from("1111"),
                     String::from("1111111111"),
+                    String::from("1001"),
                 )
                 .unwrap(),
                 &mut connection,
@@ -550,6 +1646,7 @@ mod tests {
                     String::from("Beeron"),
                     String::from("2222"),
                     String::from("2222222222"),
+                    String::from("1002"),
                 )
                 .unwrap(),
                 &mut connection,
@@ -581,7 +1678,7 @@ mod tests {
     fn simulate_start_work() {
         let (connection, _) = setup_testdb();
 
-        let (mut app, _) = Stechuhr::new(connection);
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
 
         assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
         assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
@@ -603,7 +1700,7 @@ mod tests {
     fn simulate_end_work() {
         let (connection, _) = setup_testdb();
 
-        let (mut app, _) = Stechuhr::new(connection);
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
 
         app.shared.staff[0].status = WorkStatus::Working;
 
@@ -619,14 +1716,1008 @@ mod tests {
         assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
     }
 
-    /// Create Stechuhr application and simulate passing the 6am barrier.
+    /// Create Stechuhr application and swipe a dongle twice in a row, the second swipe is refused.
     #[test]
-    fn simulate_6am() {
+    fn simulate_double_swipe() {
         let (connection, _) = setup_testdb();
 
-        let (mut app, _) = Stechuhr::new(connection);
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let swipe = |app: &mut Stechuhr| {
+            app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+                String::from("1111111111"),
+            )));
+            app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+            app.update(Message::Timetrack(
+                TimetrackMessage::ConfirmSubmitBreakInput,
+            ));
+        };
 
-        app.shared.staff[0].status = WorkStatus::Working;
+        swipe(&mut app);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        swipe(&mut app);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        assert!(app
+            .shared
+            .prompt_modal_state
+            .inner()
+            .current()
+            .contains("wurde gerade erst an- oder abgemeldet"));
+
+        app.shared.current_time += chrono::Duration::seconds(61);
+        swipe(&mut app);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+    }
+
+    /// Create Stechuhr application and simulate starting work with the personnel number.
+    #[test]
+    fn simulate_personnel_number_login() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let submit = |app: &mut Stechuhr| {
+            app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+                String::from("1002"),
+            )));
+            app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+            app.update(Message::Timetrack(
+                TimetrackMessage::ConfirmSubmitBreakInput,
+            ));
+        };
+
+        submit(&mut app);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
+
+        app.shared.settings.kiosk.personnel_number_login = true;
+        submit(&mut app);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Working);
+
+        // A PIN that is the personnel number of someone else is rejected.
+        for (field, value) in [
+            (StaffField::Name, "Ceeron"),
+            (StaffField::Pin, "1001"),
+            (StaffField::CardId, "3333333333"),
+        ] {
+            app.update(Message::Management(ManagementMessage::Staff(
+                StaffListMessage::ChangeNew(field, String::from(value)),
+            )));
+        }
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::SubmitNewRow,
+        )));
+        assert_eq!(app.shared.staff.len(), 2);
+    }
+
+    /// Create Stechuhr application and try to turn on the login by personnel number while it is the PIN of someone else.
+    #[test]
+    fn simulate_personnel_number_collision() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        for (field, value) in [
+            (StaffField::Name, "Ceeron"),
+            (StaffField::Pin, "1002"),
+            (StaffField::CardId, "3333333333"),
+        ] {
+            app.update(Message::Management(ManagementMessage::Staff(
+                StaffListMessage::ChangeNew(field, String::from(value)),
+            )));
+        }
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::SubmitNewRow,
+        )));
+        assert_eq!(app.shared.staff.len(), 3);
+
+        app.update(Message::Settings(SettingsMessage::Reset));
+        app.update(Message::Settings(
+            SettingsMessage::TogglePersonnelNumberLogin(true),
+        ));
+        app.update(Message::Settings(SettingsMessage::Save));
+        assert!(!app.shared.settings.kiosk.personnel_number_login);
+        assert!(app
+            .shared
+            .prompt_modal_state
+            .inner()
+            .current()
+            .contains("Beeron und Ceeron haben dieselbe PIN/denselben Dongle \"1002\""));
+    }
+
+    /// Create Stechuhr application and simulate ending the event, which needs the confirmation word.
+    #[test]
+    fn simulate_end_event() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+
+        app.shared.settings.end_event_word = String::from("ENDE");
+        app.shared.staff[0].status = WorkStatus::Working;
+        app.shared.staff[1].status = WorkStatus::Working;
+
+        app.update(Message::Timetrack(TimetrackMessage::EndEvent));
+        app.update(Message::Timetrack(TimetrackMessage::ConfirmEndEvent));
+
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Working);
+
+        app.update(Message::Timetrack(TimetrackMessage::ChangeEndEventWord(
+            String::from("ENDE"),
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::ConfirmEndEvent));
+
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
+    }
+
+    /// Create Stechuhr application and simulate an admin adding a marker to the log.
+    #[test]
+    fn simulate_marker() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+
+        app.update(Message::Management(ManagementMessage::ChangeMarker(
+            String::from(" Polizei-Kontrolle "),
+        )));
+        app.update(Message::Management(ManagementMessage::SubmitMarker));
+
+        assert_eq!(
+            app.log.events.last().unwrap().event,
+            WorkEvent::Marker(String::from("Polizei-Kontrolle"))
+        );
+    }
+
+    /// Create Stechuhr application and load the log of the previous business day on request.
+    #[test]
+    fn simulate_load_older_events() {
+        let (mut connection, _) = setup_testdb();
+        let yesterday = Local::now().naive_local() - chrono::Duration::days(1);
+        db::insert_event(
+            NewWorkEventT::new(yesterday, WorkEvent::Info(String::from("Gestern"))),
+            &mut connection,
+        );
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        assert!(app.older_events.is_empty());
+
+        app.update(Message::LoadOlderEvents);
+        app.update(Message::LoadOlderEvents);
+
+        assert!(app
+            .older_events
+            .iter()
+            .any(|eventt| eventt.event == WorkEvent::Info(String::from("Gestern"))));
+        assert!(!app
+            .log
+            .events
+            .iter()
+            .any(|eventt| eventt.event == WorkEvent::Info(String::from("Gestern"))));
+    }
+
+    /// Identical status changes within one second are only saved once.
+    #[test]
+    fn duplicate_status_change() {
+        let (mut connection, staff) = setup_testdb();
+        let time = NaiveDate::from_ymd(2000, 1, 1).and_hms_milli(20, 0, 0, 100);
+        let working = WorkEvent::StatusChange(staff[0].uuid(), String::new(), WorkStatus::Working);
+        let mut insert =
+            |time, event| db::insert_event(NewWorkEventT::new(time, event), &mut connection);
+
+        assert!(insert(time, working.clone()).is_some());
+        assert!(insert(time + chrono::Duration::milliseconds(500), working.clone()).is_none());
+        assert!(insert(time + chrono::Duration::seconds(1), working.clone()).is_some());
+        // Other people and other kinds of events are not affected.
+        let working_beeron =
+            WorkEvent::StatusChange(staff[1].uuid(), String::new(), WorkStatus::Working);
+        assert!(insert(time, working_beeron).is_some());
+        let info = WorkEvent::Info(String::from("Test"));
+        assert!(insert(time, info.clone()).is_some());
+        assert!(insert(time, info).is_some());
+    }
+
+    /// Create Stechuhr application and sign in with a card reader that uses the wrong keyboard layout.
+    #[test]
+    fn simulate_wrong_keyboard_layout() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let swipe = |app: &mut Stechuhr| {
+            // "1111111111" with the shift key held on a QWERTZ layout
+            app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+                String::from("!!!!!!!!!!"),
+            )));
+            app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+            app.update(Message::Timetrack(
+                TimetrackMessage::ConfirmSubmitBreakInput,
+            ));
+        };
+
+        swipe(&mut app);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        assert!(app
+            .shared
+            .prompt_modal_state
+            .inner()
+            .current()
+            .starts_with("Tastaturlayout prüfen"));
+
+        app.shared.settings.kiosk.correct_keyboard_layout = true;
+        swipe(&mut app);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+    }
+
+    /// Create Stechuhr application and show several messages at once, which are queued in the prompt.
+    #[test]
+    fn prompt_queue() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.prompt_message(String::from("Erster Fehler"));
+        app.shared.prompt_message(String::from("Zweiter Fehler"));
+        app.shared.prompt_message(String::from("Zweiter Fehler"));
+        let state = app.shared.prompt_modal_state.inner();
+        assert_eq!(state.messages.len(), 2);
+        assert_eq!(state.current(), "Erster Fehler");
+
+        app.update(Message::NextPrompt);
+        app.update(Message::NextPrompt);
+        assert_eq!(
+            app.shared.prompt_modal_state.inner().current(),
+            "Zweiter Fehler"
+        );
+        app.update(Message::PreviousPrompt);
+        assert_eq!(
+            app.shared.prompt_modal_state.inner().current(),
+            "Erster Fehler"
+        );
+
+        // Closing discards all messages, the next one is shown on its own.
+        app.update(Message::ExitPrompt);
+        assert!(!app.shared.prompt_modal_state.is_shown());
+        app.shared.prompt_message(String::from("Dritter Fehler"));
+        let state = app.shared.prompt_modal_state.inner();
+        assert_eq!(state.messages, vec![String::from("Dritter Fehler")]);
+        assert_eq!(state.current(), "Dritter Fehler");
+    }
+
+    /// Create Stechuhr application and switch tabs with the keyboard.
+    #[test]
+    fn simulate_keyboard_navigation() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let press_tab = |app: &mut Stechuhr, modifiers: keyboard::Modifiers| {
+            app.update(Message::HandleEvent(Event::Keyboard(
+                keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Tab,
+                    modifiers,
+                },
+            )));
+        };
+
+        press_tab(&mut app, keyboard::Modifiers::CTRL);
+        assert!(matches!(app.active_tab, StechuhrTab::Management));
+        press_tab(
+            &mut app,
+            keyboard::Modifiers::CTRL | keyboard::Modifiers::SHIFT,
+        );
+        press_tab(
+            &mut app,
+            keyboard::Modifiers::CTRL | keyboard::Modifiers::SHIFT,
+        );
+        assert!(matches!(app.active_tab, StechuhrTab::Settings));
+    }
+
+    /// Create Stechuhr application and show an event of a staff member that was renamed afterwards.
+    #[test]
+    fn simulate_rename() {
+        let (connection, staff) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let event =
+            WorkEvent::StatusChange(staff[0].uuid(), String::from("Aaron"), WorkStatus::Working);
+
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::Change(0, StaffField::Name, String::from("Aaronia")),
+        )));
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::SubmitRow(0),
+        )));
+
+        assert_eq!(
+            app.shared
+                .staff_names
+                .describe(&event, true, &app.shared.settings.log_texts),
+            "Status von Aaronia (früher Aaron) wurde auf \"Arbeit\" gesetzt"
+        );
+        assert_eq!(
+            app.shared
+                .staff_names
+                .describe(&event, false, &app.shared.settings.log_texts),
+            "Status von Aaronia wurde auf \"Arbeit\" gesetzt"
+        );
+
+        // Venues can choose their own phrasing.
+        app.shared.settings.log_texts.status_change = String::from("{name}: {status}");
+        app.shared.settings.log_texts.boundary = String::from("Betriebsschluss");
+        assert_eq!(
+            app.shared
+                .staff_names
+                .describe(&event, false, &app.shared.settings.log_texts),
+            "Aaronia: Arbeit"
+        );
+        assert_eq!(
+            app.shared.staff_names.describe(
+                &WorkEvent::_6am,
+                false,
+                &app.shared.settings.log_texts
+            ),
+            "Betriebsschluss"
+        );
+    }
+
+    /// Create Stechuhr application and delete a staff member, whose data is archived first.
+    #[test]
+    fn simulate_delete_archives() {
+        let (connection, staff) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let export_dir = std::env::temp_dir().join("stechuhr_delete_test");
+        app.shared.settings.export_dir = export_dir.clone();
+        app.shared.create_event(WorkEvent::StatusChange(
+            staff[0].uuid(),
+            String::new(),
+            WorkStatus::Working,
+        ));
+
+        app.update(Message::Management(ManagementMessage::DeleteRow(0)));
+        app.update(Message::Management(ManagementMessage::ConfirmDeleteRow));
+
+        assert_eq!(app.shared.staff.len(), 1);
+        let archive = std::fs::read_dir(export_dir.join("Archiv"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let content = std::fs::read_to_string(&archive).unwrap();
+        assert!(content.contains("Name\tAaron"));
+        assert!(content.contains("Status von Aaron wurde auf"));
+        std::fs::remove_dir_all(export_dir).unwrap();
+    }
+
+    /// Check what editing and deleting a staff member reads and writes, without looking into the database.
+    #[test]
+    fn management_storage_calls() {
+        let (mut storage, calls) = RecordingStorage::new();
+        storage
+            .insert_staff(
+                NewStaffMember::new(
+                    String::from("Aaron"),
+                    String::from("1111"),
+                    String::from("1111111111"),
+                    String::from("1001"),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let (mut app, _) = Stechuhr::new((Box::new(storage), Vec::new()));
+        let export_dir = std::env::temp_dir().join("stechuhr_storage_calls_test");
+        app.shared.settings.export_dir = export_dir.clone();
+        calls.borrow_mut().clear();
+
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::Change(0, StaffField::Name, String::from("Aaronia")),
+        )));
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::SubmitRow(0),
+        )));
+        // The rename is recorded for the report and logged as well.
+        assert_eq!(
+            *calls.borrow(),
+            ["save_staff_member", "insert_staff_history", "insert_event"]
+        );
+
+        calls.borrow_mut().clear();
+        app.update(Message::Management(ManagementMessage::DeleteRow(0)));
+        app.update(Message::Management(ManagementMessage::ConfirmDeleteRow));
+        // The archive is written and logged before the staff member is deleted, the deletion is recorded after.
+        assert_eq!(
+            *calls.borrow(),
+            [
+                "load_all_staff_events",
+                "insert_event",
+                "delete_staff_member",
+                "insert_staff_history"
+            ]
+        );
+        assert!(app.shared.staff.is_empty());
+        std::fs::remove_dir_all(export_dir).unwrap();
+    }
+
+    /// Check that hidden and deleted staff members are only loaded for the evaluation on request.
+    #[test]
+    fn statistics_storage_calls() {
+        let (storage, calls) = RecordingStorage::new();
+        let (mut app, _) = Stechuhr::new((Box::new(storage), Vec::new()));
+        let export_dir = std::env::temp_dir().join("stechuhr_statistics_calls_test");
+        app.shared.settings.export_dir = export_dir.clone();
+        let generate = |app: &mut Stechuhr| {
+            calls.borrow_mut().clear();
+            app.update(Message::Statistics(StatsMessage::Generate));
+            app.shared.close_prompt();
+            calls.borrow().clone()
+        };
+
+        // The start of the evaluation is logged first.
+        let evaluation = [
+            "insert_event",
+            "load_status_events_before",
+            "load_events_of_kinds_between",
+        ];
+        let recorded = generate(&mut app);
+        assert_eq!(recorded[..3], evaluation);
+        assert!(recorded.contains(&"insert_export"));
+        assert!(!recorded.contains(&"load_staff_including_inactive"));
+
+        app.update(Message::Statistics(StatsMessage::Options(
+            ExportOptionsMessage::ToggleIncludeInactive(true),
+        )));
+        let recorded = generate(&mut app);
+        assert_eq!(recorded[..3], evaluation);
+        assert_eq!(recorded[3], "load_staff_including_inactive");
+        std::fs::remove_dir_all(export_dir).unwrap();
+    }
+
+    /// Create Stechuhr application and edit a staff member that another terminal changed in the meantime.
+    #[test]
+    fn simulate_concurrent_staff_edit() {
+        let (connection, staff) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let now = NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 0, 0);
+        let mut other_terminal = staff[0].clone();
+        other_terminal.pin = String::from("4444");
+        app.shared
+            .storage
+            .save_staff_member(&mut other_terminal)
+            .unwrap();
+
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::Change(0, StaffField::Name, String::from("Aaronia")),
+        )));
+        app.update(Message::Management(ManagementMessage::Staff(
+            StaffListMessage::SubmitRow(0),
+        )));
+        // Nothing is saved until the conflict is resolved.
+        assert_eq!(app.shared.staff[0].name, "Aaron");
+        assert_eq!(app.shared.storage.load_state(now)[0].pin, "4444");
+
+        app.update(Message::Management(ManagementMessage::OverwriteConflict));
+        assert_eq!(app.shared.staff[0].name, "Aaronia");
+        let saved = &app.shared.storage.load_state(now)[0];
+        assert_eq!(saved.name, "Aaronia");
+        assert_eq!(saved.pin, "1111");
+        assert_eq!(saved.version, 2);
+    }
+
+    /// Create Stechuhr application and simulate a staff member changing their PIN.
+    #[test]
+    fn simulate_pin_change() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+
+        let submit = |app: &mut Stechuhr, input: &str| {
+            app.update(Message::Timetrack(TimetrackMessage::PinChange(
+                PinChangeMessage::ChangeInput(String::from(input)),
+            )));
+            app.update(Message::Timetrack(TimetrackMessage::PinChange(
+                PinChangeMessage::Submit,
+            )));
+        };
+
+        app.update(Message::Timetrack(TimetrackMessage::PinChange(
+            PinChangeMessage::Open,
+        )));
+        submit(&mut app, "1111111111");
+        submit(&mut app, "1111");
+        // The PIN of another staff member is rejected.
+        submit(&mut app, "2222");
+        submit(&mut app, "3333");
+        submit(&mut app, "3333");
+
+        assert_eq!(app.shared.staff[0].pin, "3333");
+        let now = NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 0, 0);
+        assert_eq!(app.shared.storage.load_state(now)[0].pin, "3333");
+    }
+
+    /// Create Stechuhr application and let a team lead sign in another staff member together with themselves.
+    #[test]
+    fn simulate_group_sign_in() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let group_sign_in = |app: &mut Stechuhr, message| {
+            app.update(Message::Timetrack(TimetrackMessage::GroupSignIn(message)));
+        };
+        let uuid = app.shared.staff[1].uuid();
+
+        group_sign_in(&mut app, GroupSignInMessage::Open);
+        group_sign_in(
+            &mut app,
+            GroupSignInMessage::ChangeInput(String::from("1111")),
+        );
+        group_sign_in(&mut app, GroupSignInMessage::Submit);
+        group_sign_in(&mut app, GroupSignInMessage::Toggle(uuid, true));
+        group_sign_in(&mut app, GroupSignInMessage::Confirm);
+
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Working);
+        let sign_ins = app
+            .log
+            .events
+            .iter()
+            .filter(|eventt| matches!(eventt.event, WorkEvent::StatusChange(..)))
+            .collect::<Vec<_>>();
+        assert_eq!(sign_ins.len(), 2);
+        assert_eq!(sign_ins[0].created_at, sign_ins[1].created_at);
+    }
+
+    /// Create Stechuhr application and let a tab ask the management tab to hide a staff member.
+    #[test]
+    fn route_requests() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let uuid = app.shared.staff[1].uuid();
+        app.shared
+            .request(TabRequest::Management(ManagementRequest::SetVisible(
+                uuid, false,
+            )));
+        app.update(Message::Nop);
+
+        assert!(app.shared.requests.is_empty());
+        assert!(app.shared.staff[0].is_visible);
+        assert!(!app.shared.staff[1].is_visible);
+        let now = NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 0, 0);
+        assert!(!app.shared.storage.load_state(now)[1].is_visible);
+    }
+
+    /// Create Stechuhr application and drive it headless like the load test, each card is swiped twice.
+    #[cfg(feature = "headless-simulation")]
+    #[test]
+    fn headless_simulation() {
+        let (connection, staff) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let cardids = staff
+            .iter()
+            .map(|staff_member| staff_member.cardid.clone())
+            .collect::<Vec<_>>();
+        let report = crate::simulation::simulate(&mut app, &cardids, 4);
+
+        assert_eq!(report.swipes.len(), 4);
+        assert_eq!(report.views.len(), 4);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
+        for staff_member in &staff {
+            let events = app
+                .shared
+                .storage
+                .load_all_staff_events(staff_member.uuid());
+            assert_eq!(events.len(), 2);
+        }
+    }
+
+    /// Create Stechuhr application and set an announcement for the Timetrack tab that expires after an hour.
+    #[test]
+    fn simulate_announcement() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let announcement = |app: &mut Stechuhr, message| {
+            app.update(Message::Management(ManagementMessage::Announcement(
+                message,
+            )));
+        };
+        let now = app.shared.current_time.naive_local();
+        let format = |time: chrono::NaiveDateTime| time.format("%d.%m.%Y %H:%M").to_string();
+
+        announcement(
+            &mut app,
+            AnnouncementMessage::ChangeText(String::from("Heute Abrechnung bis 04:00!")),
+        );
+        announcement(
+            &mut app,
+            AnnouncementMessage::ChangeUntil(format(now - chrono::Duration::hours(1))),
+        );
+        announcement(&mut app, AnnouncementMessage::Save);
+        assert_eq!(app.shared.announcement, None);
+
+        announcement(
+            &mut app,
+            AnnouncementMessage::ChangeUntil(format(now + chrono::Duration::hours(1))),
+        );
+        announcement(&mut app, AnnouncementMessage::Save);
+        let saved = app.shared.announcement.clone().unwrap();
+        assert_eq!(saved.text, "Heute Abrechnung bis 04:00!");
+        assert!(saved.is_shown(now));
+        assert!(!saved.is_shown(now + chrono::Duration::hours(2)));
+        assert_eq!(app.shared.storage.load_announcement(), Some(saved));
+
+        announcement(&mut app, AnnouncementMessage::Remove);
+        assert!(!app.shared.announcement.unwrap().is_shown(now));
+    }
+
+    /// Create Stechuhr application and simulate the admin actions on the dashboard, which expire after two minutes.
+    #[test]
+    fn simulate_quick_admin() {
+        let (mut connection, _) = setup_testdb();
+        let salt = SaltString::new("stechuhrtest").unwrap();
+        let phc = Pbkdf2
+            .hash_password("admin".as_ref(), &salt)
+            .unwrap()
+            .to_string();
+        db::insert_password(PasswordHash::new(phc), &mut connection);
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let quick_admin = |app: &mut Stechuhr, message| {
+            app.update(Message::Timetrack(TimetrackMessage::QuickAdmin(message)));
+        };
+
+        app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+            String::from("1111"),
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+        app.update(Message::Timetrack(
+            TimetrackMessage::ConfirmSubmitBreakInput,
+        ));
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+
+        // Nothing happens before the password is entered.
+        quick_admin(&mut app, QuickAdminMessage::Open);
+        quick_admin(&mut app, QuickAdminMessage::Undo);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+
+        quick_admin(
+            &mut app,
+            QuickAdminMessage::ChangePassword(String::from("admin")),
+        );
+        quick_admin(&mut app, QuickAdminMessage::Unlock);
+        quick_admin(&mut app, QuickAdminMessage::Undo);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        // An undo is a correction and cannot be undone again.
+        quick_admin(&mut app, QuickAdminMessage::Undo);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+
+        app.shared.staff[1].status = WorkStatus::Working;
+        let uuid = app.shared.staff[1].uuid();
+        quick_admin(&mut app, QuickAdminMessage::SignOff(uuid));
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
+
+        app.shared.staff[1].status = WorkStatus::Working;
+        app.shared.current_time += chrono::Duration::minutes(3);
+        quick_admin(&mut app, QuickAdminMessage::SignOff(uuid));
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Working);
+    }
+
+    /// Create Stechuhr application and run a custom quick action that signs off a department.
+    #[test]
+    fn simulate_quick_action() {
+        let (mut connection, _) = setup_testdb();
+        let salt = SaltString::new("stechuhrtest").unwrap();
+        let phc = Pbkdf2
+            .hash_password("admin".as_ref(), &salt)
+            .unwrap()
+            .to_string();
+        db::insert_password(PasswordHash::new(phc), &mut connection);
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.settings.quick_actions = vec![QuickAction {
+            label: String::from("Security fertig"),
+            steps: vec![
+                QuickActionStep::SignOff {
+                    department: Some(String::from("Security")),
+                },
+                QuickActionStep::Marker {
+                    text: String::from("Security ist weg"),
+                },
+            ],
+        }];
+        app.shared.staff[0].department = Some(String::from("Security"));
+        app.shared.staff[0].status = WorkStatus::Working;
+        app.shared.staff[1].status = WorkStatus::Working;
+
+        // Nothing happens before the password is entered.
+        app.update(Message::Timetrack(TimetrackMessage::RunQuickAction(0)));
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+
+        app.update(Message::Timetrack(TimetrackMessage::QuickAdmin(
+            QuickAdminMessage::ChangePassword(String::from("admin")),
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::QuickAdmin(
+            QuickAdminMessage::Unlock,
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::RunQuickAction(0)));
+
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Working);
+        assert!(app
+            .log
+            .events
+            .iter()
+            .any(|eventt| eventt.event == WorkEvent::Marker(String::from("Security ist weg"))));
+    }
+
+    /// Create Stechuhr application and sign in somebody who did not sign off after their last shift.
+    #[test]
+    fn simulate_forgotten_sign_off() {
+        let (mut connection, staff) = setup_testdb();
+        let salt = SaltString::new("stechuhrtest").unwrap();
+        let phc = Pbkdf2
+            .hash_password("admin".as_ref(), &salt)
+            .unwrap()
+            .to_string();
+        db::insert_password(PasswordHash::new(phc), &mut connection);
+        let uuid = staff[0].uuid();
+        db::insert_event(
+            NewWorkEventT::new(
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0),
+                WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Working),
+            ),
+            &mut connection,
+        );
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.current_time = Local
+            .from_local_datetime(&NaiveDate::from_ymd(2000, 1, 2).and_hms(18, 0, 0))
+            .unwrap();
+        let quick_admin = |app: &mut Stechuhr, message| {
+            app.update(Message::Timetrack(TimetrackMessage::QuickAdmin(message)));
+        };
+
+        app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+            String::from("1111"),
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+        app.update(Message::Timetrack(
+            TimetrackMessage::ConfirmSubmitBreakInput,
+        ));
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+
+        // Without enough finished shifts a shift of six hours is suggested.
+        quick_admin(
+            &mut app,
+            QuickAdminMessage::ChangePassword(String::from("admin")),
+        );
+        quick_admin(&mut app, QuickAdminMessage::Unlock);
+        quick_admin(&mut app, QuickAdminMessage::SubmitForgottenEnd);
+
+        let events = app.shared.storage.load_staff_events_between(
+            uuid,
+            NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0),
+            NaiveDate::from_ymd(2000, 1, 2).and_hms(6, 0, 0),
+        );
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[1].created_at,
+            NaiveDate::from_ymd(2000, 1, 2).and_hms(2, 0, 0)
+        );
+        assert_eq!(
+            events[1].event,
+            WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Away)
+        );
+        // The new shift is not affected.
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+    }
+
+    /// Create Stechuhr application and unplug the card reader.
+    #[test]
+    fn simulate_reader_unplugged() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let device = std::env::temp_dir().join("stechuhr_reader_test");
+        std::fs::write(&device, "").unwrap();
+        app.shared.settings.kiosk.reader_device = Some(device.clone());
+        let tick = |app: &mut Stechuhr| {
+            app.update(Message::Tick(
+                Local
+                    .from_local_datetime(&NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0))
+                    .unwrap(),
+            ));
+        };
+
+        tick(&mut app);
+        assert!(!app.shared.reader_missing);
+
+        std::fs::remove_file(&device).unwrap();
+        tick(&mut app);
+        tick(&mut app);
+        assert!(app.shared.reader_missing);
+        // The disconnect is only logged once.
+        let errors = app
+            .log
+            .events
+            .iter()
+            .filter(|eventt| matches!(eventt.event, WorkEvent::Error(_)))
+            .count();
+        assert_eq!(errors, 1);
+
+        std::fs::write(&device, "").unwrap();
+        tick(&mut app);
+        assert!(!app.shared.reader_missing);
+        std::fs::remove_file(&device).unwrap();
+    }
+
+    /// Create Stechuhr application and evaluate a range of one business day, after choosing an invalid end.
+    #[test]
+    fn simulate_range_evaluation() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let export_dir = std::env::temp_dir().join("stechuhr_range_test");
+        app.shared.settings.export_dir = export_dir.clone();
+        let today = app
+            .shared
+            .settings
+            .business_date(app.shared.current_time.naive_local());
+        let date = |date: NaiveDate| Message::Statistics(StatsMessage::SubmitDate(date.into()));
+        let range_end =
+            |date: NaiveDate| Message::Statistics(StatsMessage::SubmitRangeEnd(date.into()));
+
+        let generate = |app: &mut Stechuhr| {
+            app.update(Message::Statistics(StatsMessage::GenerateRange));
+            std::fs::read_dir(&export_dir)
+                .map(|files| files.count())
+                .unwrap_or(0)
+        };
+
+        app.update(date(today));
+        app.update(range_end(today.pred()));
+        assert!(app.shared.prompt_modal_state.is_shown());
+        app.shared.close_prompt();
+        assert_eq!(generate(&mut app), 0);
+
+        app.update(range_end(today));
+        assert_eq!(generate(&mut app), 1);
+        app.shared.close_prompt();
+        let name = format!(
+            "{} bis {}.tsv",
+            today.format("%Y-%m-%d"),
+            today.format("%Y-%m-%d")
+        );
+        std::fs::remove_file(export_dir.join(name)).unwrap();
+
+        // Moving the start after the end discards the range.
+        app.update(date(today.succ()));
+        assert_eq!(generate(&mut app), 0);
+        std::fs::remove_dir_all(export_dir).unwrap();
+    }
+
+    /// Create Stechuhr application and fill the disk by raising the warning threshold above any free space.
+    #[test]
+    fn simulate_low_disk_space() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.settings.export_dir = std::env::temp_dir().join("stechuhr_disk_space_test");
+        app.shared.settings.disk_space_warning_mb = u64::MAX;
+        app.check_disk_space();
+        assert!(app.shared.disk_space_low.is_some());
+        app.check_disk_space();
+        app.dispatch_events();
+        let errors = app
+            .log
+            .events
+            .iter()
+            .filter(|eventt| matches!(eventt.event, WorkEvent::Error(_)))
+            .count();
+        assert_eq!(errors, 1);
+
+        app.shared.settings.disk_space_warning_mb = 0;
+        app.check_disk_space();
+        assert_eq!(app.shared.disk_space_low, None);
+    }
+
+    /// Create Stechuhr application and close it while someone is working, with each close policy.
+    #[test]
+    fn simulate_close_policy() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.staff[0].status = WorkStatus::Working;
+
+        app.update(Message::ExitApplication);
+        assert!(!app.should_exit());
+
+        app.shared.settings.kiosk.close_policy = ClosePolicy::SignOff;
+        app.update(Message::ExitApplication);
+        assert!(!app.should_exit());
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        app.update(Message::ExitApplication);
+        assert!(app.should_exit());
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+
+        app.should_exit = false;
+        app.shared.staff[0].status = WorkStatus::Working;
+        app.shared.settings.kiosk.close_policy = ClosePolicy::Allow;
+        app.update(Message::ExitApplication);
+        assert!(app.should_exit());
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+    }
+
+    /// Create Stechuhr application and close it from the settings tab for a restart and for maintenance.
+    #[test]
+    fn simulate_exit_modes() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.shared.staff[0].status = WorkStatus::Working;
+
+        // A restart ignores the close policy and keeps the status.
+        app.update(Message::Settings(SettingsMessage::Exit(ExitMode::Restart)));
+        assert!(app.should_exit());
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+
+        app.should_exit = false;
+        app.update(Message::Settings(SettingsMessage::Exit(
+            ExitMode::Maintenance,
+        )));
+        assert!(!app.should_exit());
+
+        // The database of the tests is in a transaction, in which it cannot be backed up.
+        app.shared.staff[0].status = WorkStatus::Away;
+        app.shared.settings.backup_dir = std::env::temp_dir().join("stechuhr-test");
+        app.update(Message::Settings(SettingsMessage::Exit(
+            ExitMode::Maintenance,
+        )));
+        assert!(!app.should_exit());
+        assert!(matches!(
+            app.log.events.last().unwrap().event,
+            WorkEvent::Error(_)
+        ));
+    }
+
+    /// Start the application several times on the same database, once without closing it in between.
+    #[test]
+    fn simulate_lifecycle() {
+        let (connection, _) = setup_testdb();
+        let lifecycle = |app: &Stechuhr| {
+            app.log
+                .events
+                .iter()
+                .filter_map(|eventt| match &eventt.event {
+                    WorkEvent::Lifecycle(lifecycle) => Some(lifecycle.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let started = Lifecycle::Started(String::from(lifecycle::VERSION));
+
+        let (app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        assert_eq!(lifecycle(&app), vec![started.clone()]);
+
+        // The first run was not closed.
+        let (mut app, _) = Stechuhr::new((app.shared.storage, Vec::new()));
+        let events = lifecycle(&app);
+        assert!(matches!(events[..], [_, Lifecycle::Crashed(_), _]));
+        assert_eq!(events.last(), Some(&started));
+
+        app.update(Message::Settings(SettingsMessage::Exit(ExitMode::Quit)));
+        assert!(app.should_exit());
+        let (app, _) = Stechuhr::new((app.shared.storage, Vec::new()));
+        let events = lifecycle(&app);
+        assert_eq!(events[events.len() - 2..], [Lifecycle::Stopped, started]);
+    }
+
+    /// Create Stechuhr application and simulate passing the 6am barrier.
+    #[test]
+    fn simulate_6am() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+
+        app.shared.staff[0].status = WorkStatus::Working;
 
         app.update(Message::Tick(
             Local
@@ -638,6 +2729,118 @@ mod tests {
         assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
     }
 
+    /// Format the log and check that lines are only formatted again when something changed.
+    #[test]
+    fn log_lines() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let uuid = app.shared.staff[0].uuid();
+        let time = NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0);
+        let status = |id| {
+            WorkEventT::new(
+                id,
+                time,
+                WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Working),
+            )
+        };
+        let mut lines = LogLines::default();
+
+        let mut events = vec![
+            status(1),
+            WorkEventT::new(2, time, WorkEvent::Info(String::from("a"))),
+        ];
+        assert_eq!(
+            lines.update(&events, &app.shared, "%T"),
+            [
+                "20:00:00: Status von Aaron wurde auf \"Arbeit\" gesetzt",
+                "20:00:00: Info: a"
+            ]
+        );
+
+        // Known events are not formatted again, so the changed message is not picked up.
+        events[1] = WorkEventT::new(2, time, WorkEvent::Info(String::from("b")));
+        events.push(WorkEventT::new(3, time, WorkEvent::Info(String::from("c"))));
+        assert_eq!(
+            lines.update(&events, &app.shared, "%T")[1..],
+            ["20:00:00: Info: a", "20:00:00: Info: c"]
+        );
+
+        // Renaming a staff member formats everything again.
+        app.shared.staff[0].name = String::from("Zacharias");
+        app.shared.staff_names.update(&app.shared.staff);
+        assert_eq!(
+            lines.update(&events, &app.shared, "%T"),
+            [
+                "20:00:00: Status von Zacharias wurde auf \"Arbeit\" gesetzt",
+                "20:00:00: Info: b",
+                "20:00:00: Info: c"
+            ]
+        );
+    }
+
+    /// Create Stechuhr application and suspend the computer over two business day boundaries.
+    #[test]
+    fn simulate_suspend() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        let tick = |app: &mut Stechuhr, time| {
+            app.update(Message::Tick(Local.from_local_datetime(&time).unwrap()));
+        };
+        tick(&mut app, NaiveDate::from_ymd(2000, 1, 2).and_hms(20, 0, 0));
+        app.shared.staff[0].status = WorkStatus::Working;
+
+        tick(&mut app, NaiveDate::from_ymd(2000, 1, 4).and_hms(8, 0, 0));
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+        // The missing boundaries were saved.
+        for day in [3, 4] {
+            assert!(!app
+                .shared
+                .storage
+                .ensure_6am_event(NaiveDate::from_ymd(2000, 1, day).and_hms(5, 59, 59)));
+        }
+        assert!(app
+            .log
+            .events
+            .iter()
+            .any(|eventt| matches!(eventt.event, WorkEvent::Error(_))));
+
+        // Regular ticks are no gap.
+        let logged = app.log.events.len();
+        tick(&mut app, NaiveDate::from_ymd(2000, 1, 4).and_hms(8, 0, 1));
+        assert_eq!(app.log.events.len(), logged);
+    }
+
+    /// Create Stechuhr application, fix the database behind its back and reload.
+    #[test]
+    fn simulate_resync() {
+        let (connection, _) = setup_testdb();
+
+        let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+        app.update(Message::Tick(
+            Local
+                .from_local_datetime(&NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 1))
+                .unwrap(),
+        ));
+        let aaron = app.shared.staff[0].clone();
+        let sign_in = WorkEvent::StatusChange(aaron.uuid(), aaron.name, WorkStatus::Working);
+        app.shared.storage.insert_event(NewWorkEventT::new(
+            NaiveDate::from_ymd(2000, 1, 1).and_hms(19, 0, 0),
+            sign_in.clone(),
+        ));
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Away);
+
+        app.update(Message::Resync);
+        assert_eq!(app.shared.staff[0].status, WorkStatus::Working);
+        assert_eq!(app.shared.staff[1].status, WorkStatus::Away);
+        assert!(app.log.events.iter().any(|eventt| eventt.event == sign_in));
+        assert!(matches!(
+            app.log.events.last().map(|eventt| &eventt.event),
+            Some(WorkEvent::Info(_))
+        ));
+    }
+
     /// Create Stechuhr application and load staff that is already working.
     #[test]
     fn load_working() {
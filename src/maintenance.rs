@@ -0,0 +1,455 @@
+//! Maintenance actions that change many rows at once, run with the `maintenance` binary.
+//! Each action first collects what it would change, so that it can be previewed without writing.
+use crate::db;
+use crate::models::WorkEvent;
+use crate::schema;
+use crate::settings::Settings;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a maintenance action changed, or would change in a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Report {
+    pub description: String,
+    /// Number of changed rows per table.
+    pub rows: Vec<(&'static str, usize)>,
+    /// Names of the staff members whose data is changed.
+    pub people: BTreeSet<String>,
+    /// Time of the first and last changed event.
+    pub range: Option<(NaiveDateTime, NaiveDateTime)>,
+    pub dry_run: bool,
+}
+
+impl Report {
+    fn new(description: String, dry_run: bool) -> Self {
+        Report {
+            description,
+            dry_run,
+            ..Report::default()
+        }
+    }
+
+    fn with_events(mut self, times: impl Iterator<Item = NaiveDateTime>) -> Self {
+        let times = times.collect::<Vec<_>>();
+        self.rows.push(("events", times.len()));
+        self.range = times
+            .iter()
+            .min()
+            .zip(times.iter().max())
+            .map(|(first, last)| (*first, *last));
+        self
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.dry_run {
+            writeln!(
+                f,
+                "Probelauf, es wurde nichts geändert: {}",
+                self.description
+            )?;
+        } else {
+            writeln!(f, "{}", self.description)?;
+        }
+        for (table, count) in &self.rows {
+            writeln!(f, "  {} Zeilen in {}", count, table)?;
+        }
+        if let Some((first, last)) = self.range {
+            writeln!(
+                f,
+                "  Zeitraum: {} bis {}",
+                first.format("%d.%m.%Y %H:%M:%S"),
+                last.format("%d.%m.%Y %H:%M:%S")
+            )?;
+        }
+        if !self.people.is_empty() {
+            let people = self.people.iter().cloned().collect::<Vec<_>>();
+            writeln!(f, "  Betroffene Personen: {}", people.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Delete info and error messages of the log before the given time. Status changes, corrections
+/// and markers are kept since they are the records of the working hours.
+pub fn purge_log(
+    before: NaiveDateTime,
+    dry_run: bool,
+    connection: &mut SqliteConnection,
+) -> QueryResult<Report> {
+    use schema::events::dsl::*;
+
+    connection.transaction(|connection| {
+        let purged = events
+            .filter(kind.eq_any([WorkEvent::KIND_INFO, WorkEvent::KIND_ERROR]))
+            .filter(created_at.lt(before))
+            .select((id, created_at))
+            .load::<(i32, NaiveDateTime)>(connection)?;
+
+        let report = Report::new(
+            format!(
+                "Meldungen im Protokoll vor dem {} löschen",
+                before.format("%d.%m.%Y %H:%M")
+            ),
+            dry_run,
+        )
+        .with_events(purged.iter().map(|(_, time)| *time));
+
+        if !dry_run {
+            let ids = purged
+                .iter()
+                .map(|(event_id, _)| *event_id)
+                .collect::<Vec<_>>();
+            diesel::delete(events.filter(id.eq_any(ids))).execute(connection)?;
+        }
+        Ok(report)
+    })
+}
+
+/// The business day that a 6am event starts. It is saved one second before the start of the day, so this holds for
+/// whatever cutoff time was configured when it was saved.
+fn boundary_date(time: NaiveDateTime) -> NaiveDate {
+    (time + Duration::seconds(1)).date()
+}
+
+/// Move the pregenerated 6am events of the business days from the given one on to the configured sign-off time,
+/// after the cutoff time was changed. Earlier events are kept since the evaluation of past months must not change.
+pub fn migrate_6am(
+    from: NaiveDate,
+    settings: &Settings,
+    dry_run: bool,
+    connection: &mut SqliteConnection,
+) -> QueryResult<Report> {
+    use schema::events::dsl::*;
+
+    connection.transaction(|connection| {
+        let moved = events
+            .filter(kind.eq(WorkEvent::KIND_6AM))
+            .filter(created_at.ge(from.pred().and_hms(0, 0, 0)))
+            .select((id, created_at))
+            .load::<(i32, NaiveDateTime)>(connection)?
+            .into_iter()
+            .filter(|(_, time)| boundary_date(*time) >= from)
+            .map(|(event_id, time)| {
+                let boundary =
+                    settings.business_day_start(boundary_date(time)) - Duration::seconds(1);
+                (event_id, time, boundary)
+            })
+            .filter(|(_, time, boundary)| time != boundary)
+            .collect::<Vec<_>>();
+
+        let report = Report::new(
+            format!(
+                "Tagesgrenzen ab dem {} auf {} Uhr verschieben",
+                from.format("%d.%m.%Y"),
+                settings.sign_off_time().format("%H:%M:%S")
+            ),
+            dry_run,
+        )
+        .with_events(moved.iter().map(|(_, time, _)| *time));
+
+        if !dry_run {
+            for (event_id, _, boundary) in &moved {
+                diesel::update(events.find(event_id))
+                    .set(created_at.eq(boundary))
+                    .execute(connection)?;
+            }
+        }
+        Ok(report)
+    })
+}
+
+/// Delete duplicate 6am events, e.g. from pregenerating them twice. Of the 6am events of a business day the one at
+/// the sign-off time is kept, or the earliest one if none is at that time.
+pub fn dedupe_6am(
+    settings: &Settings,
+    dry_run: bool,
+    connection: &mut SqliteConnection,
+) -> QueryResult<Report> {
+    use schema::events::dsl::*;
+
+    connection.transaction(|connection| {
+        let boundaries = events
+            .filter(kind.eq(WorkEvent::KIND_6AM))
+            .order((created_at, id))
+            .select((id, created_at))
+            .load::<(i32, NaiveDateTime)>(connection)?;
+
+        let mut kept = BTreeMap::new();
+        for (event_id, time) in &boundaries {
+            let date = boundary_date(*time);
+            let boundary = settings.business_day_start(date) - Duration::seconds(1);
+            let entry = kept.entry(date).or_insert((*event_id, *time));
+            if entry.1 != boundary && *time == boundary {
+                *entry = (*event_id, *time);
+            }
+        }
+        let deleted = boundaries
+            .into_iter()
+            .filter(|(event_id, time)| kept[&boundary_date(*time)].0 != *event_id)
+            .collect::<Vec<_>>();
+
+        let report = Report::new(String::from("Doppelte Tagesgrenzen löschen"), dry_run)
+            .with_events(deleted.iter().map(|(_, time)| *time));
+
+        if !dry_run {
+            let ids = deleted
+                .iter()
+                .map(|(event_id, _)| *event_id)
+                .collect::<Vec<_>>();
+            diesel::delete(events.filter(id.eq_any(ids))).execute(connection)?;
+        }
+        Ok(report)
+    })
+}
+
+/// Move all events and shifts of a staff member that was created twice to the other record, and delete the duplicate.
+pub fn merge_staff(
+    duplicate: i32,
+    into: i32,
+    dry_run: bool,
+    connection: &mut SqliteConnection,
+) -> QueryResult<Report> {
+    connection.transaction(|connection| {
+        let names = db::load_staff_names(connection);
+        let duplicate_name = names.resolve(duplicate, "", false);
+        let into_name = names.resolve(into, "", false);
+
+        let merged = schema::events::table
+            .filter(schema::events::staff_id.eq(duplicate))
+            .select((
+                schema::events::id,
+                schema::events::created_at,
+                schema::events::event_json,
+            ))
+            .load::<(i32, NaiveDateTime, WorkEvent)>(connection)?;
+        let shifts = schema::shifts::table.filter(schema::shifts::staff_id.eq(duplicate));
+        let shift_count = shifts.count().get_result::<i64>(connection)? as usize;
+
+        let mut report = Report::new(
+            format!(
+                "{} (ID {}) mit {} (ID {}) zusammenführen",
+                duplicate_name, duplicate, into_name, into
+            ),
+            dry_run,
+        )
+        .with_events(merged.iter().map(|(_, time, _)| *time));
+        report.rows.push(("shifts", shift_count));
+        report.rows.push(("staff", 1));
+        report.people.insert(duplicate_name);
+        report.people.insert(into_name);
+
+        if !dry_run {
+            for (event_id, _, event) in merged {
+                // The dedup key contains the old staff member, merged events are not deduplicated like older events.
+                diesel::update(schema::events::table.find(event_id))
+                    .set((
+                        schema::events::event_json.eq(event.with_staff_id(into)),
+                        schema::events::staff_id.eq(into),
+                        schema::events::dedup_key.eq(None::<String>),
+                    ))
+                    .execute(connection)?;
+            }
+            diesel::update(shifts)
+                .set(schema::shifts::staff_id.eq(into))
+                .execute(connection)?;
+            diesel::update(schema::staff::table.find(duplicate))
+                .set((
+                    schema::staff::is_active.eq(false),
+                    schema::staff::pin.eq(None::<String>),
+                    schema::staff::cardid.eq(None::<String>),
+                    schema::staff::version.eq(schema::staff::version + 1),
+                ))
+                .execute(connection)?;
+        }
+        Ok(report)
+    })
+}
+
+/// File in the given directory for a backup of the database made at the given time, see `backup`.
+pub fn backup_path(dir: &Path, now: NaiveDateTime) -> PathBuf {
+    dir.join(format!(
+        "stechuhr {}.sqlite3",
+        now.format("%Y-%m-%d %H-%M-%S")
+    ))
+}
+
+/// Write a copy of the database to the given file, e.g. before maintenance of the terminal.
+/// SQLite makes the copy consistent even if other terminals write meanwhile. It cannot run inside a transaction.
+pub fn backup(path: &Path, connection: &mut SqliteConnection) -> QueryResult<()> {
+    diesel::sql_query("VACUUM INTO ?")
+        .bind::<diesel::sql_types::Text, _>(path.to_string_lossy().into_owned())
+        .execute(connection)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewStaffMember, NewWorkEventT, WorkStatus};
+    use chrono::NaiveTime;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn merge_dry_run() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let mut insert_staff = |name: &str, ident: &str| {
+            db::insert_staff(
+                NewStaffMember::new(
+                    String::from(name),
+                    ident.repeat(4),
+                    ident.repeat(10),
+                    String::new(),
+                )
+                .unwrap(),
+                &mut connection,
+            )
+            .unwrap()
+            .uuid()
+        };
+        let aaron = insert_staff("Aaron", "1");
+        let duplicate = insert_staff("Aaron 2", "2");
+        let time = NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0);
+        db::insert_event(
+            NewWorkEventT::new(
+                time,
+                WorkEvent::StatusChange(duplicate, String::new(), WorkStatus::Working),
+            ),
+            &mut connection,
+        );
+
+        let report = merge_staff(duplicate, aaron, true, &mut connection).unwrap();
+        assert_eq!(
+            report.rows,
+            vec![("events", 1), ("shifts", 0), ("staff", 1)]
+        );
+        assert_eq!(report.range, Some((time, time)));
+        assert!(report.to_string().starts_with("Probelauf"));
+        assert_eq!(db::load_staff(&mut connection).len(), 2);
+
+        let report = merge_staff(duplicate, aaron, false, &mut connection).unwrap();
+        assert_eq!(report.rows[0], ("events", 1));
+        assert_eq!(db::load_staff(&mut connection).len(), 1);
+        assert_eq!(
+            db::load_all_staff_events(aaron, &mut connection)[0].event,
+            WorkEvent::StatusChange(aaron, String::new(), WorkStatus::Working)
+        );
+    }
+
+    #[test]
+    fn dedupe_boundaries() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let date = NaiveDate::from_ymd(2000, 1, 2);
+        let settings = Settings::default();
+        let sign_off_time = settings.sign_off_time();
+        for time in [
+            date.and_hms(4, 59, 59),
+            date.and_time(sign_off_time),
+            date.and_hms(6, 30, 0),
+            date.succ().and_hms(4, 59, 59),
+        ] {
+            db::insert_event(NewWorkEventT::new(time, WorkEvent::_6am), &mut connection);
+        }
+
+        let report = dedupe_6am(&settings, true, &mut connection).unwrap();
+        assert_eq!(report.rows, vec![("events", 2)]);
+        assert_eq!(
+            report.range,
+            Some((date.and_hms(4, 59, 59), date.and_hms(6, 30, 0)))
+        );
+
+        dedupe_6am(&settings, false, &mut connection).unwrap();
+        assert_eq!(
+            load_boundaries(&mut connection),
+            vec![date.and_time(sign_off_time), date.succ().and_hms(4, 59, 59)]
+        );
+    }
+
+    fn load_boundaries(connection: &mut SqliteConnection) -> Vec<NaiveDateTime> {
+        schema::events::table
+            .filter(schema::events::kind.eq(WorkEvent::KIND_6AM))
+            .select(schema::events::created_at)
+            .order(schema::events::created_at)
+            .load::<NaiveDateTime>(connection)
+            .unwrap()
+    }
+
+    /// With the cutoff at midnight, the boundary of a business day is saved on the day before.
+    #[test]
+    fn boundaries_at_midnight() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let date = NaiveDate::from_ymd(2000, 1, 2);
+        for time in [
+            date.pred().and_hms(5, 59, 59),
+            date.and_hms(5, 59, 59),
+            date.succ().and_hms(5, 59, 59),
+        ] {
+            db::insert_event(NewWorkEventT::new(time, WorkEvent::_6am), &mut connection);
+        }
+        let settings = Settings {
+            cutoff_time: NaiveTime::from_hms(0, 0, 0),
+            ..Settings::default()
+        };
+
+        let report = migrate_6am(date, &settings, false, &mut connection).unwrap();
+        assert_eq!(report.rows, vec![("events", 2)]);
+        assert_eq!(
+            load_boundaries(&mut connection),
+            vec![
+                date.pred().and_hms(5, 59, 59),
+                date.pred().and_hms(23, 59, 59),
+                date.and_hms(23, 59, 59),
+            ]
+        );
+
+        // A boundary pregenerated again after midnight belongs to the same business day.
+        db::insert_event(
+            NewWorkEventT::new(date.and_hms(0, 30, 0), WorkEvent::_6am),
+            &mut connection,
+        );
+        let report = dedupe_6am(&settings, false, &mut connection).unwrap();
+        assert_eq!(
+            report.range,
+            Some((date.and_hms(0, 30, 0), date.and_hms(0, 30, 0)))
+        );
+        assert_eq!(load_boundaries(&mut connection).len(), 3);
+    }
+
+    #[test]
+    fn backup_copy() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        db::insert_staff(
+            NewStaffMember::new(
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                String::new(),
+            )
+            .unwrap(),
+            &mut connection,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join("stechuhr-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = backup_path(&dir, NaiveDate::from_ymd(2022, 7, 1).and_hms(3, 0, 0));
+        let _ = std::fs::remove_file(&path);
+
+        backup(&path, &mut connection).unwrap();
+        let mut copy = SqliteConnection::establish(&path.to_string_lossy()).unwrap();
+        let staff = db::load_staff(&mut copy);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(path.ends_with("stechuhr 2022-07-01 03-00-00.sqlite3"));
+        assert_eq!(staff.len(), 1);
+    }
+}
@@ -1,23 +1,35 @@
 use crate::icons::{self, FONT_EMOJIONE, TEXT_SIZE_EMOJI};
-use crate::schema::{events, passwords, staff};
-use chrono::{Local, NaiveDateTime};
+use crate::keyboard_layout;
+use crate::lifecycle::Lifecycle;
+use crate::schema::{
+    announcements, closed_months, event_sessions, events, exports, passwords, shifts, staff,
+    staff_history,
+};
+use crate::settings::LogTexts;
+use chrono::{Local, NaiveDate, NaiveDateTime};
 use diesel::deserialize::{self, FromSql, Queryable};
 use diesel::serialize::{self, IsNull, Output, ToSql};
 use diesel::sql_types::*;
 use iced::Color;
 use pbkdf2::password_hash::PasswordHash as PBKDF2Hash;
+use rand_core::{OsRng, RngCore};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_lexpr;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
 use std::str::FromStr;
-use std::{cmp, error, fmt};
+use std::{cmp, env, error, fmt};
 
 #[derive(Debug, Clone)]
 pub enum ModelError {
     EmptyName,
     ParsePIN(String),
     ParseCardid(String),
+    ParsePersonnelNumber(String),
+    ParseWage(String),
+    IdentTaken(String),
 }
 
 impl error::Error for ModelError {}
@@ -26,9 +38,25 @@ impl fmt::Display for ModelError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let description = match self {
             ModelError::ParsePIN(pin) => format!("PIN muss aus 4 Ziffern bestehen: \"{}\"", pin),
-            ModelError::ParseCardid(cardid) => {
-                format!("Dongle-ID muss aus 10 Ziffern bestehen: \"{}\"", cardid)
-            }
+            ModelError::ParseCardid(cardid) => match keyboard_layout::translate(cardid) {
+                Some((layout, _)) => format!(
+                    "Dongle-ID muss aus 10 Ziffern bestehen: \"{}\". Tastaturlayout prüfen, der Leser verwendet vermutlich die Tastaturbelegung {}.",
+                    cardid, layout
+                ),
+                None => format!("Dongle-ID muss aus 10 Ziffern bestehen: \"{}\"", cardid),
+            },
+            ModelError::ParsePersonnelNumber(personnel_number) => format!(
+                "Personalnummer darf nur aus Buchstaben, Ziffern und Bindestrichen bestehen: \"{}\"",
+                personnel_number
+            ),
+            ModelError::ParseWage(wage) => format!(
+                "Stundenlohn muss ein Betrag in Euro sein, z.B. 12,50: \"{}\"",
+                wage
+            ),
+            ModelError::IdentTaken(ident) => format!(
+                "\"{}\" wird schon von einer anderen Person als PIN, Dongle oder Personalnummer verwendet",
+                ident
+            ),
             ModelError::EmptyName => String::from("Name darf nicht leer sein"),
         };
         f.write_str(&description)
@@ -93,35 +121,186 @@ impl fmt::Display for WorkStatus {
     }
 }
 
+/// Status of a staff member as shown on the dashboard.
+/// Refines the WorkStatus with what we know about the current business day.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DashboardStatus {
+    Working,
+    /// Not working right now but already worked during the current business day.
+    Break,
+    /// Not working yet although planned in the shift plan (or there is no shift plan).
+    Away,
+    /// Not in the shift plan of the current business day.
+    NotScheduled,
+}
+
+impl DashboardStatus {
+    pub const ALL: [DashboardStatus; 4] = [
+        DashboardStatus::Working,
+        DashboardStatus::Break,
+        DashboardStatus::Away,
+        DashboardStatus::NotScheduled,
+    ];
+
+    pub fn new(status: WorkStatus, worked_today: bool, scheduled: bool) -> Self {
+        match status {
+            WorkStatus::Working => DashboardStatus::Working,
+            WorkStatus::Away if worked_today => DashboardStatus::Break,
+            WorkStatus::Away if scheduled => DashboardStatus::Away,
+            WorkStatus::Away => DashboardStatus::NotScheduled,
+        }
+    }
+
+    pub fn to_icon(&self, colorblind: bool) -> iced::Text {
+        if colorblind {
+            // colors from the Okabe-Ito palette, which stay distinguishable with color vision deficiencies
+            let emoji =
+                match self {
+                    DashboardStatus::Working => icons::colorblind::working
+                        .with_color(Some(Color::from_rgb8(0x00, 0x72, 0xB2))),
+                    DashboardStatus::Break => icons::colorblind::on_break
+                        .with_color(Some(Color::from_rgb8(0xE6, 0x9F, 0x00))),
+                    DashboardStatus::Away => {
+                        icons::colorblind::away.with_color(Some(Color::from_rgb8(0xD5, 0x5E, 0x00)))
+                    }
+                    DashboardStatus::NotScheduled => icons::colorblind::not_scheduled
+                        .with_color(Some(Color::from_rgb8(0x80, 0x80, 0x80))),
+                };
+            return icons::icon(emoji.with_size(TEXT_SIZE_EMOJI + 4));
+        }
+
+        match self {
+            DashboardStatus::Working => WorkStatus::Working.to_unicode(),
+            DashboardStatus::Away => WorkStatus::Away.to_unicode(),
+            DashboardStatus::Break => icons::icon(
+                icons::emoji::pause
+                    .with_color(Some(Color::from_rgb8(0xE6, 0xB4, 0x00)))
+                    .with_size(TEXT_SIZE_EMOJI + 4),
+            ),
+            DashboardStatus::NotScheduled => icons::icon(
+                icons::emoji::circle
+                    .with_color(Some(Color::from_rgb8(0xA0, 0xA0, 0xA0)))
+                    .with_size(TEXT_SIZE_EMOJI + 4),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for DashboardStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            DashboardStatus::Working => "Arbeit",
+            DashboardStatus::Break => "Pause",
+            DashboardStatus::Away => "Abwesend",
+            DashboardStatus::NotScheduled => "Nicht eingeplant",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
 #[derive(
     Debug, PartialEq, Eq, PartialOrd, Clone, AsExpression, FromSqlRow, Serialize, Deserialize,
 )]
 #[diesel(sql_type = Text)]
 pub enum WorkEvent {
+    /// The name is the one the staff member had when the event was created. Since the staff_id column exists
+    /// new events leave it empty and the current name is looked up with `StaffNames`.
     StatusChange(i32, String, WorkStatus),
     _6am,
     Info(String),
     Error(String),
+    /// Marks that the events of a staff member were fixed manually by an admin, with a description of the fix.
+    /// The fix itself is done by separate events, e.g. a StatusChange. The name is recorded like in StatusChange.
+    Correction(i32, String, String),
+    /// Free text entered by an admin to explain unusual time patterns later, e.g. a police check.
+    Marker(String),
+    /// Start or end of the application, see `lifecycle`.
+    Lifecycle(Lifecycle),
 }
 
 impl fmt::Display for WorkEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let str = match self {
-            WorkEvent::StatusChange(_, name, status) => {
-                format!("Status von {} wurde auf \"{}\" gesetzt", name, status)
+        let str = self.describe(&LogTexts::default(), |_, name| name.to_owned());
+        fmt::Display::fmt(&str, f)
+    }
+}
+
+impl WorkEvent {
+    /// Describe the event for the log with the configured `texts`, `name` gives the name to show for a staff member id
+    /// and the recorded name.
+    pub fn describe(&self, texts: &LogTexts, name: impl Fn(i32, &str) -> String) -> String {
+        match self {
+            WorkEvent::StatusChange(uuid, recorded, status) => LogTexts::fill(
+                &texts.status_change,
+                &[
+                    ("name", &name(*uuid, recorded)),
+                    ("status", &status.to_string()),
+                ],
+            ),
+            WorkEvent::_6am => texts.boundary.clone(),
+            WorkEvent::Info(msg) => LogTexts::fill(&texts.info, &[("message", msg)]),
+            WorkEvent::Error(msg) => LogTexts::fill(&texts.error, &[("message", msg)]),
+            WorkEvent::Correction(uuid, recorded, msg) => LogTexts::fill(
+                &texts.correction,
+                &[("name", &name(*uuid, recorded)), ("message", msg)],
+            ),
+            WorkEvent::Marker(msg) => LogTexts::fill(&texts.marker, &[("message", msg)]),
+            WorkEvent::Lifecycle(lifecycle) => {
+                LogTexts::fill(&texts.lifecycle, &[("message", &lifecycle.to_string())])
             }
-            WorkEvent::_6am => String::from("6 Uhr morgens"),
-            WorkEvent::Info(msg) => format!("Info: {}", msg),
-            WorkEvent::Error(msg) => format!("Error: {}", msg),
-        };
+        }
+    }
 
-        fmt::Display::fmt(&str, f)
+    pub const KIND_STATUS_CHANGE: &'static str = "StatusChange";
+    pub const KIND_6AM: &'static str = "_6am";
+    pub const KIND_INFO: &'static str = "Info";
+    pub const KIND_ERROR: &'static str = "Error";
+    pub const KIND_CORRECTION: &'static str = "Correction";
+    pub const KIND_MARKER: &'static str = "Marker";
+    pub const KIND_LIFECYCLE: &'static str = "Lifecycle";
+
+    /// Name of the variant, saved in its own column so that queries can filter by it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WorkEvent::StatusChange(..) => WorkEvent::KIND_STATUS_CHANGE,
+            WorkEvent::_6am => WorkEvent::KIND_6AM,
+            WorkEvent::Info(_) => WorkEvent::KIND_INFO,
+            WorkEvent::Error(_) => WorkEvent::KIND_ERROR,
+            WorkEvent::Correction(..) => WorkEvent::KIND_CORRECTION,
+            WorkEvent::Marker(_) => WorkEvent::KIND_MARKER,
+            WorkEvent::Lifecycle(_) => WorkEvent::KIND_LIFECYCLE,
+        }
+    }
+
+    /// The staff member the event is about, if any.
+    pub fn staff_id(&self) -> Option<i32> {
+        match self {
+            WorkEvent::StatusChange(uuid, _, _) | WorkEvent::Correction(uuid, _, _) => Some(*uuid),
+            WorkEvent::_6am
+            | WorkEvent::Info(_)
+            | WorkEvent::Error(_)
+            | WorkEvent::Marker(_)
+            | WorkEvent::Lifecycle(_) => None,
+        }
+    }
+
+    /// The same event about another staff member, e.g. when merging duplicate staff members.
+    pub fn with_staff_id(self, new_uuid: i32) -> Self {
+        match self {
+            WorkEvent::StatusChange(_, name, status) => {
+                WorkEvent::StatusChange(new_uuid, name, status)
+            }
+            WorkEvent::Correction(_, name, description) => {
+                WorkEvent::Correction(new_uuid, name, description)
+            }
+            event => event,
+        }
     }
 }
 
 #[derive(Debug, Clone, Queryable, PartialEq, Eq, PartialOrd)]
 pub struct WorkEventT {
-    #[allow(unused)]
     id: i32,
     pub created_at: NaiveDateTime,
     pub event: WorkEvent,
@@ -135,6 +314,10 @@ impl WorkEventT {
             event,
         }
     }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
 }
 
 impl Ord for WorkEventT {
@@ -154,19 +337,67 @@ pub struct NewWorkEventT {
     created_at: NaiveDateTime,
     #[diesel(column_name = event_json)]
     pub event: WorkEvent,
+    /* identifies the event after inserting it, so it stays the same when the insert is retried */
+    token: String,
+    /* copies of the event's kind and staff member so that they can be indexed */
+    kind: String,
+    staff_id: Option<i32>,
+    /* same for identical status changes within one second, so that the database saves only one of them */
+    dedup_key: Option<String>,
 }
 
 impl NewWorkEventT {
     pub fn new(created_at: NaiveDateTime, event: WorkEvent) -> Self {
-        NewWorkEventT { created_at, event }
+        NewWorkEventT {
+            created_at,
+            kind: String::from(event.kind()),
+            staff_id: event.staff_id(),
+            dedup_key: NewWorkEventT::dedup_key(created_at, &event),
+            event,
+            token: NewWorkEventT::generate_token(),
+        }
     }
 
     pub fn now(event: WorkEvent) -> Self {
-        NewWorkEventT {
-            created_at: Local::now().naive_local(),
-            event,
+        NewWorkEventT::new(Local::now().naive_local(), event)
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    fn dedup_key(created_at: NaiveDateTime, event: &WorkEvent) -> Option<String> {
+        match event {
+            WorkEvent::StatusChange(uuid, _, status) => Some(format!(
+                "{}:{:?}:{}",
+                uuid,
+                status,
+                created_at.format("%Y-%m-%d %H:%M:%S")
+            )),
+            _ => None,
         }
     }
+
+    /// Generate a random (version 4) UUID.
+    fn generate_token() -> String {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let hex = bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..32]
+        )
+    }
 }
 
 pub struct PIN;
@@ -206,25 +437,75 @@ impl FromStr for Cardid {
 #[derive(Debug, Clone, AsChangeset, Identifiable)]
 #[diesel(table_name = staff)]
 #[diesel(primary_key(uuid))]
+#[diesel(treat_none_as_null = true)]
 pub struct DBStaffMember {
     uuid: i32,
     name: String,
     pin: String,
     cardid: String,
     is_visible: bool,
+    personnel_number: Option<String>,
+    is_minor: bool,
+    department: Option<String>,
+    version: i32,
+    wage_cents_per_hour: Option<i32>,
+    display_name: Option<String>,
 }
 
 impl DBStaffMember {
-    pub fn new(uuid: i32, name: String, pin: String, cardid: String, is_visible: bool) -> Self {
+    pub fn new(
+        uuid: i32,
+        name: String,
+        pin: String,
+        cardid: String,
+        is_visible: bool,
+        personnel_number: Option<String>,
+    ) -> Self {
         Self {
             uuid,
             name,
             pin,
             cardid,
             is_visible,
+            personnel_number,
+            is_minor: false,
+            department: None,
+            version: 0,
+            wage_cents_per_hour: None,
+            display_name: None,
         }
     }
 
+    /// Mark the staff member as under 18, see `youth_protection`.
+    pub fn with_minor(mut self, is_minor: bool) -> Self {
+        self.is_minor = is_minor;
+        self
+    }
+
+    /// Set the department, see `Department`.
+    pub fn with_department(mut self, department: Option<String>) -> Self {
+        self.department = department;
+        self
+    }
+
+    /// Set the hourly wage in cents, see `Wage`.
+    pub fn with_wage(mut self, wage_cents_per_hour: Option<i32>) -> Self {
+        self.wage_cents_per_hour = wage_cents_per_hour;
+        self
+    }
+
+    /// Set the name shown at the terminal, see `StaffMember::display_name`.
+    pub fn with_display_name(mut self, display_name: Option<String>) -> Self {
+        self.display_name = display_name;
+        self
+    }
+
+    /// Set the version, see `StaffMember::version`.
+    pub fn with_version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
     pub fn uuid(&self) -> i32 {
         self.uuid
     }
@@ -233,6 +514,14 @@ impl DBStaffMember {
         &self.name
     }
 
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn is_minor(&self) -> bool {
+        self.is_minor
+    }
+
     pub fn with_status(self, status: WorkStatus) -> StaffMember {
         StaffMember {
             uuid: self.uuid,
@@ -240,6 +529,12 @@ impl DBStaffMember {
             pin: self.pin,
             cardid: self.cardid,
             is_visible: self.is_visible,
+            personnel_number: self.personnel_number,
+            is_minor: self.is_minor,
+            department: self.department,
+            version: self.version,
+            wage_cents_per_hour: self.wage_cents_per_hour,
+            display_name: self.display_name,
             status,
         }
     }
@@ -255,6 +550,18 @@ pub struct StaffMember {
     pub cardid: String,
     pub status: WorkStatus,
     pub is_visible: bool,
+    pub personnel_number: Option<String>,
+    /// Under 18, so the limits of the youth labor law apply.
+    pub is_minor: bool,
+    /// Department or pay group, see `Department`.
+    pub department: Option<String>,
+    /// Version of the database record this staff member was loaded from, see `db::save_staff_member`.
+    pub version: i32,
+    /// Hourly wage in cents, see `Wage`.
+    pub wage_cents_per_hour: Option<i32>,
+    /// Name shown at the terminal instead of the legal name, e.g. a nickname. The legal name is still used in the
+    /// management, the log and the exports.
+    pub display_name: Option<String>,
 }
 
 // DONE for save_staff_member I need a DBStaffMember so I have to convert the &StaffMember to an owned value, which is uneccessary.
@@ -270,6 +577,12 @@ impl<'a> From<Cow<'a, StaffMember>> for DBStaffMember {
             pin: staff_member.pin,
             cardid: staff_member.cardid,
             is_visible: staff_member.is_visible,
+            personnel_number: staff_member.personnel_number,
+            is_minor: staff_member.is_minor,
+            department: staff_member.department,
+            version: staff_member.version,
+            wage_cents_per_hour: staff_member.wage_cents_per_hour,
+            display_name: staff_member.display_name,
         }
     }
 }
@@ -295,6 +608,65 @@ impl StaffMember {
             .find(|staff_member| staff_member.pin == ident || staff_member.cardid == ident)
     }
 
+    pub fn get_by_personnel_number<'a>(staff: &'a [Self], ident: &str) -> Option<&'a Self> {
+        staff
+            .iter()
+            .find(|staff_member| staff_member.personnel_number.as_deref() == Some(ident))
+    }
+
+    /// Find the staff member for an input on the Timetrack tab. Personnel numbers are only accepted if enabled in the settings.
+    /// INVARIANT: pins, cardids and (if enabled) personnel numbers are disjoint
+    pub fn get_by_ident<'a>(
+        staff: &'a [Self],
+        ident: &str,
+        personnel_number_login: bool,
+    ) -> Option<&'a Self> {
+        StaffMember::get_by_pin_or_card_id(staff, ident).or_else(|| {
+            if personnel_number_login {
+                StaffMember::get_by_personnel_number(staff, ident)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Check if a PIN or cardid (or personnel number, if they are used for login) is already used by another staff member than the given one.
+    pub fn is_ident_taken(
+        staff: &[Self],
+        ident: &str,
+        uuid: i32,
+        personnel_number_login: bool,
+    ) -> bool {
+        staff.iter().any(|staff_member| {
+            staff_member.uuid != uuid
+                && (staff_member.pin == ident
+                    || staff_member.cardid == ident
+                    || (personnel_number_login
+                        && staff_member.personnel_number.as_deref() == Some(ident)))
+        })
+    }
+
+    /// With login by personnel number, the identifiers of a new or edited staff member must not be used by anyone else.
+    /// PINs and cardids among each other are already unique in the database.
+    pub fn check_idents_disjoint(
+        staff: &[Self],
+        uuid: Option<i32>,
+        pin: &str,
+        cardid: &str,
+        personnel_number: Option<&str>,
+    ) -> Result<(), ModelError> {
+        let uuid = uuid.unwrap_or(-1);
+        for ident in [Some(pin), Some(cardid), personnel_number]
+            .into_iter()
+            .flatten()
+        {
+            if StaffMember::is_ident_taken(staff, ident, uuid, true) {
+                return Err(ModelError::IdentTaken(ident.to_owned()));
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_by_uuid_mut<'a>(staff: &'a mut [Self], uuid: i32) -> Option<&'a mut Self> {
         staff
             .iter_mut()
@@ -304,6 +676,202 @@ impl StaffMember {
     pub fn get_by_uuid<'a>(staff: &'a [Self], uuid: i32) -> Option<&'a Self> {
         staff.iter().find(|staff_member| staff_member.uuid == uuid)
     }
+
+    /// Name to show at the terminal, the display name if there is one.
+    pub fn shown_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Check if the staff member still has the data of the given database record.
+    pub fn matches_record(&self, record: &DBStaffMember) -> bool {
+        self.uuid == record.uuid
+            && self.name == record.name
+            && self.pin == record.pin
+            && self.cardid == record.cardid
+            && self.is_visible == record.is_visible
+            && self.personnel_number == record.personnel_number
+            && self.is_minor == record.is_minor
+            && self.department == record.department
+            && self.wage_cents_per_hour == record.wage_cents_per_hour
+            && self.display_name == record.display_name
+            && self.version == record.version
+    }
+
+    /// Fields in which the staff member differs from the given database record, with both values.
+    pub fn differences(&self, record: &DBStaffMember) -> Vec<(&'static str, String, String)> {
+        let yes_no = |b: bool| String::from(if b { "ja" } else { "nein" });
+        [
+            ("Name", self.name.clone(), record.name.clone()),
+            (
+                "Anzeigename",
+                self.display_name.clone().unwrap_or_default(),
+                record.display_name.clone().unwrap_or_default(),
+            ),
+            ("PIN", self.pin.clone(), record.pin.clone()),
+            ("Dongle", self.cardid.clone(), record.cardid.clone()),
+            (
+                "Personalnummer",
+                self.personnel_number.clone().unwrap_or_default(),
+                record.personnel_number.clone().unwrap_or_default(),
+            ),
+            (
+                "Abteilung",
+                self.department.clone().unwrap_or_default(),
+                record.department.clone().unwrap_or_default(),
+            ),
+            (
+                "Stundenlohn",
+                Wage::format_optional(self.wage_cents_per_hour),
+                Wage::format_optional(record.wage_cents_per_hour),
+            ),
+            (
+                "Sichtbar",
+                yes_no(self.is_visible),
+                yes_no(record.is_visible),
+            ),
+            (
+                "Minderjährig",
+                yes_no(self.is_minor),
+                yes_no(record.is_minor),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, mine, theirs)| mine != theirs)
+        .collect()
+    }
+}
+
+/// Current names of all staff members, including deleted ones.
+/// Events only contain the name a staff member had when they were created (or none at all), so renaming
+/// someone would make the log inconsistent. Instead we show events with the current name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StaffNames {
+    names: HashMap<i32, String>,
+}
+
+impl StaffNames {
+    pub fn new(names: HashMap<i32, String>) -> Self {
+        Self { names }
+    }
+
+    /// Take over the names of the given staff members, e.g. after they were renamed.
+    pub fn update(&mut self, staff: &[StaffMember]) {
+        for staff_member in staff {
+            self.names
+                .insert(staff_member.uuid(), staff_member.name.clone());
+        }
+    }
+
+    /// The current name of a staff member. If `show_former` is set, a different name that was recorded in the event is added.
+    pub fn resolve(&self, uuid: i32, recorded: &str, show_former: bool) -> String {
+        match self.names.get(&uuid) {
+            Some(current) if recorded.is_empty() || recorded == current || !show_former => {
+                current.clone()
+            }
+            Some(current) => format!("{} (früher {})", current, recorded),
+            None if recorded.is_empty() => format!("Mitarbeiter {}", uuid),
+            None => recorded.to_owned(),
+        }
+    }
+
+    /// Describe the event with the current names of the staff members and the configured texts.
+    pub fn describe(&self, event: &WorkEvent, show_former: bool, texts: &LogTexts) -> String {
+        event.describe(texts, |uuid, recorded| {
+            self.resolve(uuid, recorded, show_former)
+        })
+    }
+}
+
+pub struct PersonnelNumber;
+
+impl PersonnelNumber {
+    /// The personnel number is optional, so an empty input means no personnel number.
+    pub fn parse_optional(s: &str) -> Result<Option<String>, ModelError> {
+        let s = s.trim();
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            s.parse::<PersonnelNumber>()?;
+            Ok(Some(s.to_owned()))
+        }
+    }
+}
+
+impl FromStr for PersonnelNumber {
+    type Err = ModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new(r"^[A-Za-z0-9-]{1,20}$").unwrap();
+        if re.is_match(s) {
+            Ok(PersonnelNumber)
+        } else {
+            Err(ModelError::ParsePersonnelNumber(s.to_owned()))
+        }
+    }
+}
+
+/// Staff members can be assigned to a department or pay group (e.g. an external security firm),
+/// so that the evaluation can be exported separately for each of them.
+pub struct Department;
+
+impl Department {
+    /// The department is optional free text, so an empty input means no department.
+    pub fn parse_optional(s: &str) -> Option<String> {
+        let s = s.trim();
+        if s.is_empty() {
+            None
+        } else {
+            Some(s.to_owned())
+        }
+    }
+}
+
+/// Staff members can have a name that is shown at the terminal instead of their legal name, see
+/// `StaffMember::display_name`.
+pub struct DisplayName;
+
+impl DisplayName {
+    /// The display name is optional, so an empty input means that the legal name is shown.
+    pub fn parse_optional(s: &str) -> Option<String> {
+        let s = s.trim();
+        if s.is_empty() {
+            None
+        } else {
+            Some(s.to_owned())
+        }
+    }
+}
+
+/// Staff members can have an hourly wage, from which the evaluation calculates their wage for the month.
+/// It is entered in Euro and stored in cents.
+pub struct Wage;
+
+impl Wage {
+    /// The wage is optional, so an empty input means no wage.
+    pub fn parse_optional(s: &str) -> Result<Option<i32>, ModelError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(None);
+        }
+        let re = Regex::new(r"^(\d{1,4})(?:[,.](\d{1,2}))?$").unwrap();
+        let captures = re
+            .captures(s)
+            .ok_or_else(|| ModelError::ParseWage(s.to_owned()))?;
+        let euros = captures[1].parse::<i32>().unwrap();
+        // "12,5" means 12 Euro and 50 cents
+        let cents = captures
+            .get(2)
+            .map(|cents| format!("{:0<2}", cents.as_str()).parse::<i32>().unwrap())
+            .unwrap_or(0);
+        Ok(Some(euros * 100 + cents))
+    }
+
+    /// Format the wage in Euro as it is entered, e.g. "12,50".
+    pub fn format_optional(wage_cents_per_hour: Option<i32>) -> String {
+        wage_cents_per_hour
+            .map(|cents| format!("{},{:02}", cents / 100, cents % 100))
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -312,23 +880,327 @@ pub struct NewStaffMember {
     pub name: String,
     pub pin: String,
     pub cardid: String,
+    pub personnel_number: Option<String>,
+    pub department: Option<String>,
+    pub wage_cents_per_hour: Option<i32>,
+    pub display_name: Option<String>,
 }
 
 impl NewStaffMember {
-    pub fn validate(name: &str, pin: &str, cardid: &str) -> Result<(), ModelError> {
+    pub fn validate(
+        name: &str,
+        pin: &str,
+        cardid: &str,
+        personnel_number: &str,
+    ) -> Result<(), ModelError> {
         if name.is_empty() {
             return Err(ModelError::EmptyName);
         }
         let _ = pin.parse::<PIN>()?;
         let _ = cardid.parse::<Cardid>()?;
+        let _ = PersonnelNumber::parse_optional(personnel_number)?;
 
         Ok(())
     }
 
-    pub fn new(name: String, pin: String, cardid: String) -> Result<Self, ModelError> {
-        Self::validate(&name, &pin, &cardid)?;
+    pub fn new(
+        name: String,
+        pin: String,
+        cardid: String,
+        personnel_number: String,
+    ) -> Result<Self, ModelError> {
+        Self::validate(&name, &pin, &cardid, &personnel_number)?;
+        let personnel_number = PersonnelNumber::parse_optional(&personnel_number)?;
+
+        Ok(Self {
+            name,
+            pin,
+            cardid,
+            personnel_number,
+            department: None,
+            wage_cents_per_hour: None,
+            display_name: None,
+        })
+    }
+
+    pub fn with_department(mut self, department: &str) -> Self {
+        self.department = Department::parse_optional(department);
+        self
+    }
+
+    pub fn with_wage(mut self, wage_cents_per_hour: Option<i32>) -> Self {
+        self.wage_cents_per_hour = wage_cents_per_hour;
+        self
+    }
+
+    pub fn with_display_name(mut self, display_name: &str) -> Self {
+        self.display_name = DisplayName::parse_optional(display_name);
+        self
+    }
+}
+
+/// A shift from the shift plan.
+#[derive(Debug, Clone, Queryable, PartialEq, Eq)]
+pub struct Shift {
+    #[allow(unused)]
+    id: i32,
+    pub staff_id: i32,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+}
+
+impl Shift {
+    pub fn new(id: i32, staff_id: i32, start_time: NaiveDateTime, end_time: NaiveDateTime) -> Self {
+        Self {
+            id,
+            staff_id,
+            start_time,
+            end_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Insertable)]
+#[diesel(table_name = shifts)]
+pub struct NewShift {
+    pub staff_id: i32,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+}
+
+impl NewShift {
+    pub fn new(staff_id: i32, start_time: NaiveDateTime, end_time: NaiveDateTime) -> Self {
+        Self {
+            staff_id,
+            start_time,
+            end_time,
+        }
+    }
+
+    /// Check if the same shift is already planned.
+    pub fn is_planned(&self, shifts: &[Shift]) -> bool {
+        shifts.iter().any(|shift| {
+            shift.staff_id == self.staff_id
+                && shift.start_time == self.start_time
+                && shift.end_time == self.end_time
+        })
+    }
+}
+
+/// A month that was closed in the monthly closing. Afterwards no more corrections are allowed.
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = closed_months)]
+pub struct NewClosedMonth {
+    /// First day of the month.
+    pub month: NaiveDate,
+    pub closed_at: NaiveDateTime,
+}
+
+/// Kind of file that was generated in the "Auswertung" tab.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Evaluation,
+    Log,
+    Datev,
+    Json,
+    Sessions,
+    Intervals,
+}
+
+impl ExportKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ExportKind::Evaluation => "evaluation",
+            ExportKind::Log => "log",
+            ExportKind::Datev => "datev",
+            ExportKind::Json => "json",
+            ExportKind::Sessions => "sessions",
+            ExportKind::Intervals => "intervals",
+        }
+    }
+}
+
+impl fmt::Display for ExportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ExportKind::Evaluation => "Auswertung",
+            ExportKind::Log => "Protokoll",
+            ExportKind::Datev => "DATEV",
+            ExportKind::Json => "JSON",
+            ExportKind::Sessions => "Veranstaltungen",
+            ExportKind::Intervals => "Einzelne Schichten",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// A file that was generated in the "Auswertung" tab.
+#[derive(Debug, Clone, Queryable)]
+pub struct Export {
+    #[allow(unused)]
+    id: i32,
+    kind: String,
+    /// First day of the exported month.
+    pub month: NaiveDate,
+    /// Department the evaluation was restricted to.
+    pub department: Option<String>,
+    pub path: String,
+    /// SHA-256 of the file content in hex.
+    pub checksum: String,
+    /// User account on the terminal that generated the file.
+    pub generated_by: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Export {
+    pub fn kind(&self) -> Option<ExportKind> {
+        [
+            ExportKind::Evaluation,
+            ExportKind::Log,
+            ExportKind::Datev,
+            ExportKind::Json,
+            ExportKind::Sessions,
+            ExportKind::Intervals,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == self.kind)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = exports)]
+pub struct NewExport {
+    kind: String,
+    month: NaiveDate,
+    department: Option<String>,
+    path: String,
+    checksum: String,
+    generated_by: String,
+    created_at: NaiveDateTime,
+}
+
+impl NewExport {
+    /// The file is attributed to the user account that runs Stechuhr.
+    pub fn new(
+        kind: ExportKind,
+        month: NaiveDate,
+        department: Option<String>,
+        path: &Path,
+        checksum: String,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        let generated_by = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| String::from("unbekannt"));
+
+        Self {
+            kind: String::from(kind.as_str()),
+            month,
+            department,
+            path: path.display().to_string(),
+            checksum,
+            generated_by,
+            created_at,
+        }
+    }
+}
+
+/// Kind of change of the staff list, see `StaffHistoryEntry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaffHistoryKind {
+    Added,
+    Edited,
+    Deleted,
+}
+
+impl StaffHistoryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            StaffHistoryKind::Added => "added",
+            StaffHistoryKind::Edited => "edited",
+            StaffHistoryKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// A change of the staff list made in the "Verwaltung" tab or by a staff member changing their PIN.
+/// Edits have one entry per changed field.
+#[derive(Debug, Clone, Queryable)]
+pub struct StaffHistoryEntry {
+    #[allow(unused)]
+    id: i32,
+    pub staff_id: i32,
+    pub changed_at: NaiveDateTime,
+    kind: String,
+    pub field: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl StaffHistoryEntry {
+    pub fn kind(&self) -> Option<StaffHistoryKind> {
+        [
+            StaffHistoryKind::Added,
+            StaffHistoryKind::Edited,
+            StaffHistoryKind::Deleted,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == self.kind)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = staff_history)]
+pub struct NewStaffHistoryEntry {
+    staff_id: i32,
+    changed_at: NaiveDateTime,
+    kind: String,
+    field: Option<String>,
+    old_value: Option<String>,
+    new_value: Option<String>,
+}
+
+impl NewStaffHistoryEntry {
+    fn new(staff_id: i32, changed_at: NaiveDateTime, kind: StaffHistoryKind) -> Self {
+        Self {
+            staff_id,
+            changed_at,
+            kind: kind.as_str().to_owned(),
+            field: None,
+            old_value: None,
+            new_value: None,
+        }
+    }
+
+    pub fn added(staff_id: i32, changed_at: NaiveDateTime) -> Self {
+        Self::new(staff_id, changed_at, StaffHistoryKind::Added)
+    }
+
+    pub fn deleted(staff_id: i32, changed_at: NaiveDateTime) -> Self {
+        Self::new(staff_id, changed_at, StaffHistoryKind::Deleted)
+    }
 
-        Ok(Self { name, pin, cardid })
+    /// One entry for each field in which the edited staff member differs from the previous one.
+    /// PINs are secret, so only the fact that a PIN was changed is recorded.
+    pub fn edits(
+        edited: &StaffMember,
+        previous: &StaffMember,
+        changed_at: NaiveDateTime,
+    ) -> Vec<Self> {
+        edited
+            .differences(&DBStaffMember::from(Cow::Borrowed(previous)))
+            .into_iter()
+            .map(|(field, new_value, old_value)| {
+                let is_secret = field == "PIN";
+                Self {
+                    field: Some(field.to_owned()),
+                    old_value: (!is_secret).then_some(old_value),
+                    new_value: (!is_secret).then_some(new_value),
+                    ..Self::new(edited.uuid(), changed_at, StaffHistoryKind::Edited)
+                }
+            })
+            .collect()
     }
 }
 
@@ -364,11 +1236,25 @@ where
     String: FromSql<Text, DB>,
     i32: FromSql<Integer, DB>,
 {
-    type Row = (i32, String, Option<String>, Option<String>, bool, bool);
+    type Row = (
+        i32,
+        String,
+        Option<String>,
+        Option<String>,
+        bool,
+        bool,
+        Option<String>,
+        bool,
+        Option<String>,
+        i32,
+        Option<i32>,
+        Option<String>,
+    );
 
     fn build(row: Self::Row) -> diesel::deserialize::Result<Self> {
-        let pin = row.2.unwrap();
-        let cardid = row.3.unwrap();
+        // Deleted staff members have neither PIN nor dongle.
+        let pin = row.2.unwrap_or_default();
+        let cardid = row.3.unwrap_or_default();
 
         Ok(Self {
             uuid: row.0,
@@ -376,6 +1262,12 @@ where
             pin,
             cardid,
             is_visible: row.4,
+            personnel_number: row.6,
+            is_minor: row.7,
+            department: row.8,
+            version: row.9,
+            wage_cents_per_hour: row.10,
+            display_name: row.11,
         })
     }
 }
@@ -438,3 +1330,61 @@ where
         Ok(serde_lexpr::from_str(&value)?)
     }
 }
+
+/// An event (a party, not a `WorkEvent`) that was detected from the number of staff members working,
+/// see `event_detection`.
+#[derive(Debug, Clone, Queryable)]
+pub struct EventSession {
+    #[allow(unused)]
+    id: i32,
+    pub business_date: NaiveDate,
+    /// First sign-in after a quiet period.
+    pub started_at: NaiveDateTime,
+    /// Last sign-off before a quiet period.
+    pub ended_at: NaiveDateTime,
+    /// Highest number of staff members working at the same time.
+    pub peak_count: i32,
+    /// Sum of the work time of everyone during the event.
+    pub staff_minutes: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Insertable)]
+#[diesel(table_name = event_sessions)]
+pub struct NewEventSession {
+    pub business_date: NaiveDate,
+    pub started_at: NaiveDateTime,
+    pub ended_at: NaiveDateTime,
+    pub peak_count: i32,
+    pub staff_minutes: i64,
+}
+
+/// Announcement to the staff shown on the Timetrack tab of all terminals, e.g. "Heute Abrechnung bis 04:00!".
+#[derive(Debug, Clone, PartialEq, Eq, Queryable)]
+pub struct Announcement {
+    #[allow(unused)]
+    id: i32,
+    /// Empty if the previous announcement was removed.
+    pub text: String,
+    /// Shown from this point in time on, immediately if not set.
+    pub show_from: Option<NaiveDateTime>,
+    /// Shown until this point in time, until it is replaced if not set.
+    pub show_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Announcement {
+    pub fn is_shown(&self, now: NaiveDateTime) -> bool {
+        !self.text.is_empty()
+            && self.show_from.is_none_or(|show_from| show_from <= now)
+            && self.show_until.is_none_or(|show_until| now < show_until)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Insertable)]
+#[diesel(table_name = announcements)]
+pub struct NewAnnouncement {
+    pub text: String,
+    pub show_from: Option<NaiveDateTime>,
+    pub show_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,204 @@
+//! One-page summary of a finished business day, written at the 6am boundary so that the morning crew has a
+//! record of who worked when without opening the statistics tab. It can also be sent to the printer with lp.
+use crate::models::{StaffNames, WorkEvent, WorkEventT};
+use crate::youth_protection;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::path::Path;
+use std::process::Command;
+use std::{fmt, io};
+
+/// Width of the name column, longer names are cut off to keep the summary on one page.
+const NAME_WIDTH: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersonSummary {
+    pub name: String,
+    pub intervals: Vec<(NaiveDateTime, NaiveDateTime)>,
+    pub total: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NightSummary {
+    pub business_date: NaiveDate,
+    /// End of the business day, at which everyone still working was signed off.
+    pub boundary: NaiveDateTime,
+    /// Ordered by the first sign-in.
+    pub people: Vec<PersonSummary>,
+}
+
+impl NightSummary {
+    /// Summarize the status changes and 6am events of a business day, ordered from oldest to newest.
+    pub fn new(
+        events: &[WorkEventT],
+        business_date: NaiveDate,
+        boundary: NaiveDateTime,
+        staff_names: &StaffNames,
+    ) -> Self {
+        let mut uuids = Vec::new();
+        for eventt in events {
+            if let WorkEvent::StatusChange(uuid, ref name, _) = eventt.event {
+                if !uuids.iter().any(|(id, _)| *id == uuid) {
+                    uuids.push((uuid, name.clone()));
+                }
+            }
+        }
+
+        let mut people = uuids
+            .into_iter()
+            .map(|(uuid, recorded)| {
+                let intervals = youth_protection::work_intervals(events, uuid, boundary);
+                let total = intervals
+                    .iter()
+                    .fold(Duration::zero(), |total, (start, end)| {
+                        total + (*end - *start)
+                    });
+                PersonSummary {
+                    name: staff_names.resolve(uuid, &recorded, false),
+                    intervals,
+                    total,
+                }
+            })
+            .filter(|person| !person.intervals.is_empty())
+            .collect::<Vec<_>>();
+        people.sort_by_key(|person| person.intervals[0].0);
+
+        NightSummary {
+            business_date,
+            boundary,
+            people,
+        }
+    }
+
+    /// Write the summary as a text file and print it if requested.
+    pub fn write(&self, path: &Path, print: bool) -> io::Result<()> {
+        std::fs::write(path, self.to_string())?;
+        if print {
+            let output = Command::new("lp").arg(path).output()?;
+            if !output.status.success() {
+                return Err(io::Error::other(
+                    String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!(
+        "{}:{:02}",
+        duration.num_hours(),
+        duration.num_minutes() % 60
+    )
+}
+
+impl fmt::Display for NightSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Zusammenfassung des Betriebstags {}",
+            self.business_date.format("%d.%m.%Y")
+        )?;
+        writeln!(f)?;
+        if self.people.is_empty() {
+            return writeln!(f, "Niemand hat gearbeitet.");
+        }
+
+        let mut signed_off = false;
+        for person in &self.people {
+            let intervals = person
+                .intervals
+                .iter()
+                .map(|(start, end)| {
+                    // The 6am event is one second before the boundary, so we show the full minute.
+                    if *end >= self.boundary - Duration::seconds(1) {
+                        signed_off = true;
+                        format!(
+                            "{}–{}*",
+                            start.format("%H:%M"),
+                            self.boundary.format("%H:%M")
+                        )
+                    } else {
+                        format!("{}–{}", start.format("%H:%M"), end.format("%H:%M"))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let name = person.name.chars().take(NAME_WIDTH).collect::<String>();
+            writeln!(
+                f,
+                "{:<width$}  {:>6}  {}",
+                name,
+                format_duration(person.total),
+                intervals,
+                width = NAME_WIDTH
+            )?;
+        }
+
+        let total = self
+            .people
+            .iter()
+            .fold(Duration::zero(), |total, person| total + person.total);
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<width$}  {:>6}",
+            "Summe",
+            format_duration(total),
+            width = NAME_WIDTH
+        )?;
+        if signed_off {
+            writeln!(f)?;
+            writeln!(f, "* automatisch abgemeldet")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkStatus;
+    use std::collections::HashMap;
+
+    #[test]
+    fn summarize_night() {
+        let date = NaiveDate::from_ymd(2022, 6, 3);
+        let boundary = date.succ().and_hms(6, 0, 0);
+        let status = |uuid, time, status| {
+            WorkEventT::new(
+                0,
+                time,
+                WorkEvent::StatusChange(uuid, String::from("Alt"), status),
+            )
+        };
+        let events = [
+            status(1, date.and_hms(20, 0, 0), WorkStatus::Working),
+            status(2, date.and_hms(22, 0, 0), WorkStatus::Working),
+            status(1, date.and_hms(23, 0, 0), WorkStatus::Away),
+            status(1, date.and_hms(23, 30, 0), WorkStatus::Working),
+            status(1, date.succ().and_hms(2, 0, 0), WorkStatus::Away),
+            WorkEventT::new(0, date.succ().and_hms(5, 59, 59), WorkEvent::_6am),
+        ];
+        let names = StaffNames::new(HashMap::from([
+            (1, String::from("Aaron")),
+            (2, String::from("Beeron")),
+        ]));
+
+        let summary = NightSummary::new(&events, date, boundary, &names);
+        assert_eq!(summary.people.len(), 2);
+        assert_eq!(summary.people[0].name, "Aaron");
+        assert_eq!(summary.people[0].total, Duration::minutes(5 * 60 + 30));
+        assert_eq!(
+            summary.to_string(),
+            "Zusammenfassung des Betriebstags 03.06.2022\n\
+             \n\
+             Aaron                       5:30  20:00–23:00, 23:30–02:00\n\
+             Beeron                      7:59  22:00–06:00*\n\
+             \n\
+             Summe                      13:29\n\
+             \n\
+             * automatisch abgemeldet\n"
+        );
+    }
+}
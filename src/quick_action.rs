@@ -0,0 +1,106 @@
+//! Custom buttons in the admin actions of the dashboard, for the routines of a venue that are not built in.
+//! Each one runs a list of steps from the settings file, e.g.
+//! `{"label": "Security fertig", "steps": [{"action": "sign_off", "department": "Security"}, {"action": "export_snapshot"}]}`.
+use crate::models::{StaffMember, WorkStatus};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum QuickActionStep {
+    /// Sign off everybody who is working in the given department, or everybody if no department is given.
+    /// Each sign-off is recorded as a correction.
+    SignOff {
+        #[serde(default)]
+        department: Option<String>,
+    },
+    /// Insert a marker event with the given text into the log.
+    Marker { text: String },
+    /// Export the list of everybody who is working right now, like the button on the dashboard.
+    ExportSnapshot,
+}
+
+/// A custom button as configured in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickAction {
+    /// Text of the button.
+    pub label: String,
+    /// Steps that are run one after another when the button is pressed.
+    pub steps: Vec<QuickActionStep>,
+}
+
+impl QuickAction {
+    /// An action without label or steps cannot be used.
+    pub fn is_valid(&self) -> bool {
+        !self.label.trim().is_empty() && !self.steps.is_empty()
+    }
+}
+
+/// Staff members who are working, only those of the given department if one is given.
+pub fn working_in(staff: &[StaffMember], department: Option<&str>) -> Vec<i32> {
+    staff
+        .iter()
+        .filter(|staff_member| staff_member.status == WorkStatus::Working)
+        .filter(|staff_member| {
+            department.is_none() || staff_member.department.as_deref() == department
+        })
+        .map(StaffMember::uuid)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DBStaffMember;
+
+    #[test]
+    fn parse_steps() {
+        let json = r#"{"label": "Security fertig", "steps": [
+            {"action": "sign_off", "department": "Security"},
+            {"action": "marker", "text": "Security ist weg"},
+            {"action": "sign_off"},
+            {"action": "export_snapshot"}
+        ]}"#;
+        let action: QuickAction = serde_json::from_str(json).unwrap();
+
+        assert!(action.is_valid());
+        assert_eq!(
+            action.steps,
+            vec![
+                QuickActionStep::SignOff {
+                    department: Some(String::from("Security"))
+                },
+                QuickActionStep::Marker {
+                    text: String::from("Security ist weg")
+                },
+                QuickActionStep::SignOff { department: None },
+                QuickActionStep::ExportSnapshot,
+            ]
+        );
+    }
+
+    #[test]
+    fn working_in_department() {
+        let staff_member = |uuid, name: &str, department: Option<&str>, status| {
+            DBStaffMember::new(
+                uuid,
+                String::from(name),
+                uuid.to_string().repeat(4),
+                uuid.to_string().repeat(10),
+                true,
+                None,
+            )
+            .with_department(department.map(String::from))
+            .with_status(status)
+        };
+        let staff = vec![
+            staff_member(1, "Aaron", Some("Security"), WorkStatus::Working),
+            staff_member(2, "Berta", Some("Security"), WorkStatus::Working),
+            staff_member(3, "Cem", Some("Security"), WorkStatus::Away),
+            staff_member(4, "Dora", None, WorkStatus::Working),
+        ];
+
+        assert_eq!(working_in(&staff, Some("Security")), vec![1, 2]);
+        assert_eq!(working_in(&staff, None), vec![1, 2, 4]);
+        assert_eq!(working_in(&staff, Some("Bar")), Vec::<i32>::new());
+    }
+}
@@ -0,0 +1,233 @@
+//! Storage for tests of the tabs: an empty in-memory database that records which methods of `Storage` are called,
+//! so that a test can check what a flow reads and writes without looking into the tables.
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Connection, QueryResult, SqliteConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use stechuhr::db::{SaveStaffError, StaffChanges};
+use stechuhr::models::*;
+use stechuhr::storage::Storage;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+/// Names of the called methods, in the order of the calls.
+pub type Calls = Rc<RefCell<Vec<&'static str>>>;
+
+pub struct RecordingStorage {
+    connection: SqliteConnection,
+    calls: Calls,
+}
+
+impl RecordingStorage {
+    /// The storage and a handle to its calls, which stays readable after the storage was moved into the application.
+    pub fn new() -> (Self, Calls) {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let calls = Calls::default();
+        let storage = RecordingStorage {
+            connection,
+            calls: Rc::clone(&calls),
+        };
+        (storage, calls)
+    }
+
+    fn record(&mut self, call: &'static str) -> &mut SqliteConnection {
+        self.calls.borrow_mut().push(call);
+        &mut self.connection
+    }
+}
+
+impl Storage for RecordingStorage {
+    fn load_state(&mut self, current_time: NaiveDateTime) -> Vec<StaffMember> {
+        self.record("load_state").load_state(current_time)
+    }
+
+    fn load_staff_names(&mut self) -> StaffNames {
+        self.record("load_staff_names").load_staff_names()
+    }
+
+    fn load_all_staff_ids(&mut self) -> Vec<i32> {
+        self.record("load_all_staff_ids").load_all_staff_ids()
+    }
+
+    fn load_staff_added_at(&mut self, uuid: i32) -> Option<NaiveDateTime> {
+        self.record("load_staff_added_at").load_staff_added_at(uuid)
+    }
+
+    fn refresh_staff(
+        &mut self,
+        staff: &mut Vec<StaffMember>,
+        previous_events: &[WorkEventT],
+    ) -> StaffChanges {
+        self.record("refresh_staff")
+            .refresh_staff(staff, previous_events)
+    }
+
+    fn load_staff_including_inactive(&mut self) -> Vec<DBStaffMember> {
+        self.record("load_staff_including_inactive")
+            .load_staff_including_inactive()
+    }
+
+    fn insert_staff(&mut self, staff_member: NewStaffMember) -> QueryResult<StaffMember> {
+        self.record("insert_staff").insert_staff(staff_member)
+    }
+
+    fn save_staff_member(&mut self, staff_member: &mut StaffMember) -> Result<(), SaveStaffError> {
+        self.record("save_staff_member")
+            .save_staff_member(staff_member)
+    }
+
+    fn save_staff(&mut self, staff: &mut [StaffMember]) -> QueryResult<()> {
+        self.record("save_staff").save_staff(staff)
+    }
+
+    fn delete_staff_member(&mut self, staff_member: StaffMember) -> QueryResult<()> {
+        self.record("delete_staff_member")
+            .delete_staff_member(staff_member)
+    }
+
+    fn insert_staff_history(&mut self, entries: &[NewStaffHistoryEntry]) -> QueryResult<usize> {
+        self.record("insert_staff_history")
+            .insert_staff_history(entries)
+    }
+
+    fn load_staff_history_between(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<StaffHistoryEntry> {
+        self.record("load_staff_history_between")
+            .load_staff_history_between(start, end)
+    }
+
+    fn verify_password(&mut self, password: &str) -> bool {
+        self.record("verify_password").verify_password(password)
+    }
+
+    fn insert_event(&mut self, new_event: NewWorkEventT) -> Option<WorkEventT> {
+        self.record("insert_event").insert_event(new_event)
+    }
+
+    fn load_events_between(
+        &mut self,
+        start_time: Option<NaiveDateTime>,
+        end_time: Option<NaiveDateTime>,
+    ) -> Vec<WorkEventT> {
+        self.record("load_events_between")
+            .load_events_between(start_time, end_time)
+    }
+
+    fn load_events_of_kinds_between(
+        &mut self,
+        kinds: &[&str],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.record("load_events_of_kinds_between")
+            .load_events_of_kinds_between(kinds, start_time, end_time)
+    }
+
+    fn load_status_events_before(&mut self, before: NaiveDateTime) -> Vec<WorkEventT> {
+        self.record("load_status_events_before")
+            .load_status_events_before(before)
+    }
+
+    fn load_latest_events_of_kind(&mut self, event_kind: &str, limit: i64) -> Vec<WorkEventT> {
+        self.record("load_latest_events_of_kind")
+            .load_latest_events_of_kind(event_kind, limit)
+    }
+
+    fn load_last_event_time(&mut self, before: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.record("load_last_event_time")
+            .load_last_event_time(before)
+    }
+
+    fn ensure_6am_event(&mut self, time: NaiveDateTime) -> bool {
+        self.record("ensure_6am_event").ensure_6am_event(time)
+    }
+
+    fn load_staff_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.record("load_staff_events_between")
+            .load_staff_events_between(uuid, start_time, end_time)
+    }
+
+    fn load_staff_status_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.record("load_staff_status_events_between")
+            .load_staff_status_events_between(uuid, start_time, end_time)
+    }
+
+    fn load_all_staff_events(&mut self, uuid: i32) -> Vec<WorkEventT> {
+        self.record("load_all_staff_events")
+            .load_all_staff_events(uuid)
+    }
+
+    fn load_shifts_between(&mut self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Shift> {
+        self.record("load_shifts_between")
+            .load_shifts_between(start, end)
+    }
+
+    fn insert_shifts(&mut self, new_shifts: &[NewShift]) -> QueryResult<usize> {
+        self.record("insert_shifts").insert_shifts(new_shifts)
+    }
+
+    fn load_announcement(&mut self) -> Option<Announcement> {
+        self.record("load_announcement").load_announcement()
+    }
+
+    fn insert_announcement(&mut self, new_announcement: NewAnnouncement) -> QueryResult<usize> {
+        self.record("insert_announcement")
+            .insert_announcement(new_announcement)
+    }
+
+    fn load_event_sessions_between(
+        &mut self,
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+    ) -> Vec<EventSession> {
+        self.record("load_event_sessions_between")
+            .load_event_sessions_between(first_date, last_date)
+    }
+
+    fn replace_event_sessions(
+        &mut self,
+        date: NaiveDate,
+        sessions: &[NewEventSession],
+    ) -> QueryResult<usize> {
+        self.record("replace_event_sessions")
+            .replace_event_sessions(date, sessions)
+    }
+
+    fn is_month_closed(&mut self, first_day: NaiveDate) -> bool {
+        self.record("is_month_closed").is_month_closed(first_day)
+    }
+
+    fn insert_closed_month(&mut self, closed_month: NewClosedMonth) -> QueryResult<usize> {
+        self.record("insert_closed_month")
+            .insert_closed_month(closed_month)
+    }
+
+    fn load_exports(&mut self, limit: i64) -> Vec<Export> {
+        self.record("load_exports").load_exports(limit)
+    }
+
+    fn insert_export(&mut self, new_export: NewExport) -> QueryResult<usize> {
+        self.record("insert_export").insert_export(new_export)
+    }
+
+    fn backup(&mut self, path: &Path) -> QueryResult<()> {
+        self.record("backup").backup(path)
+    }
+}
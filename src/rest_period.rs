@@ -0,0 +1,122 @@
+//! Minimum rest between the work of two business days, e.g. 11 hours by the working hours law (§ 5 ArbZG).
+//! Breaks within a business day are not rests, so only the end of the last interval of a business day and the start
+//! of the first interval of the next business day with work are compared.
+use crate::date_ext::NaiveDateTimeExt;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+
+/// The rest between signing off and signing in again was shorter than required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestViolation {
+    pub signed_off: NaiveDateTime,
+    pub signed_in: NaiveDateTime,
+    pub min_rest: Duration,
+}
+
+impl fmt::Display for RestViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rest = self.signed_in - self.signed_off;
+        write!(
+            f,
+            "nur {}:{:02} h Ruhezeit zwischen {} und {} (mindestens {} h)",
+            rest.num_hours(),
+            rest.num_minutes() % 60,
+            self.signed_off.format("%d.%m.%Y %H:%M"),
+            self.signed_in.format("%d.%m.%Y %H:%M"),
+            self.min_rest.num_hours()
+        )
+    }
+}
+
+/// Check the work intervals of a staff member, ordered by their start, against the minimum rest.
+/// Days are business days ending at the cutoff time.
+pub fn check(
+    intervals: &[(NaiveDateTime, NaiveDateTime)],
+    cutoff: NaiveTime,
+    min_rest: Duration,
+) -> Vec<RestViolation> {
+    let mut violations = Vec::new();
+    // business day of the previous intervals and the end of its work
+    let mut previous: Option<(NaiveDate, NaiveDateTime)> = None;
+
+    for (start, end) in intervals {
+        let date = start.business_date(cutoff);
+        match previous {
+            Some((previous_date, signed_off)) if previous_date == date => {
+                previous = Some((date, signed_off.max(*end)));
+            }
+            _ => {
+                if let Some((_, signed_off)) = previous {
+                    if *start - signed_off < min_rest {
+                        violations.push(RestViolation {
+                            signed_off,
+                            signed_in: *start,
+                            min_rest,
+                        });
+                    }
+                }
+                previous = Some((date, *end));
+            }
+        }
+    }
+    violations
+}
+
+/// Warning for a staff member who signs in at `now`, given their work intervals of the previous business days.
+/// Signing in again after a break on the same business day is no violation, the rest was checked at its start.
+pub fn sign_in_warning(
+    intervals: &[(NaiveDateTime, NaiveDateTime)],
+    now: NaiveDateTime,
+    cutoff: NaiveTime,
+    min_rest: Duration,
+) -> Option<RestViolation> {
+    let mut intervals = intervals.to_vec();
+    intervals.push((now, now));
+    check(&intervals, cutoff, min_rest)
+        .into_iter()
+        .find(|violation| violation.signed_in == now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_rest() {
+        let cutoff = NaiveTime::from_hms(6, 0, 0);
+        let min_rest = Duration::hours(11);
+        let day = |d, h, m| NaiveDate::from_ymd(2000, 1, d).and_hms(h, m, 0);
+        let intervals = vec![
+            (day(3, 20, 0), day(4, 2, 0)),
+            // the rest starts after the last interval of the business day
+            (day(4, 3, 0), day(4, 5, 0)),
+            // 10 hours later
+            (day(4, 15, 0), day(4, 18, 0)),
+            // a break is no rest
+            (day(4, 18, 30), day(4, 23, 0)),
+            // 11 hours later
+            (day(5, 10, 0), day(5, 12, 0)),
+        ];
+
+        let violation = RestViolation {
+            signed_off: day(4, 5, 0),
+            signed_in: day(4, 15, 0),
+            min_rest,
+        };
+        assert_eq!(check(&intervals, cutoff, min_rest), vec![violation.clone()]);
+        assert_eq!(
+            violation.to_string(),
+            "nur 10:00 h Ruhezeit zwischen 04.01.2000 05:00 und 04.01.2000 15:00 (mindestens 11 h)"
+        );
+
+        assert_eq!(
+            sign_in_warning(&intervals[..2], day(4, 15, 0), cutoff, min_rest),
+            Some(violation)
+        );
+        assert_eq!(
+            sign_in_warning(&intervals[..3], day(4, 18, 30), cutoff, min_rest),
+            None
+        );
+        assert_eq!(sign_in_warning(&[], day(4, 15, 0), cutoff, min_rest), None);
+    }
+}
@@ -1,8 +1,54 @@
+table! {
+    announcements (id) {
+        id -> Integer,
+        text -> Text,
+        show_from -> Nullable<Timestamp>,
+        show_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    closed_months (id) {
+        id -> Integer,
+        month -> Date,
+        closed_at -> Timestamp,
+    }
+}
+
+table! {
+    event_sessions (id) {
+        id -> Integer,
+        business_date -> Date,
+        started_at -> Timestamp,
+        ended_at -> Timestamp,
+        peak_count -> Integer,
+        staff_minutes -> BigInt,
+    }
+}
+
+table! {
+    exports (id) {
+        id -> Integer,
+        kind -> Text,
+        month -> Date,
+        department -> Nullable<Text>,
+        path -> Text,
+        checksum -> Text,
+        generated_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     events (id) {
         id -> Integer,
         created_at -> Timestamp,
         event_json -> Text,
+        token -> Nullable<Text>,
+        kind -> Nullable<Text>,
+        staff_id -> Nullable<Integer>,
+        dedup_key -> Nullable<Text>,
     }
 }
 
@@ -13,6 +59,15 @@ table! {
     }
 }
 
+table! {
+    shifts (id) {
+        id -> Integer,
+        staff_id -> Integer,
+        start_time -> Timestamp,
+        end_time -> Timestamp,
+    }
+}
+
 table! {
     staff (id) {
         id -> Integer,
@@ -21,7 +76,38 @@ table! {
         cardid -> Nullable<Text>,
         is_visible -> Bool,
         is_active -> Bool,
+        personnel_number -> Nullable<Text>,
+        is_minor -> Bool,
+        department -> Nullable<Text>,
+        version -> Integer,
+        wage_cents_per_hour -> Nullable<Integer>,
+        display_name -> Nullable<Text>,
+    }
+}
+
+table! {
+    staff_history (id) {
+        id -> Integer,
+        staff_id -> Integer,
+        changed_at -> Timestamp,
+        kind -> Text,
+        field -> Nullable<Text>,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
     }
 }
 
-allow_tables_to_appear_in_same_query!(events, passwords, staff,);
+joinable!(shifts -> staff (staff_id));
+joinable!(staff_history -> staff (staff_id));
+
+allow_tables_to_appear_in_same_query!(
+    announcements,
+    closed_months,
+    event_sessions,
+    events,
+    exports,
+    passwords,
+    shifts,
+    staff,
+    staff_history,
+);
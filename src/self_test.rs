@@ -0,0 +1,164 @@
+//! Checks of the environment that are run at startup (or with `stechuhr --check`), so that a broken installation
+//! is reported at once instead of failing later at random points, e.g. when the first evaluation is exported.
+use crate::icons::{FONT_EMOJIONE, FONT_SYMBOLA};
+use crate::models::{WorkEvent, WorkStatus};
+use crate::schema;
+use crate::settings::Settings;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use iced::Font;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+/// Stechuhr did not exist before this date, so a clock showing an earlier date is wrong (e.g. an empty RTC battery).
+const EARLIEST_SANE_DATE: (i32, u32, u32) = (2022, 1, 1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfTestProblem {
+    Font(&'static str),
+    MissingResource(PathBuf),
+    PendingMigrations,
+    Migrations(String),
+    ExportDir(PathBuf, String),
+    /// The clock is before the earliest sane date or before the last recorded event.
+    Clock(NaiveDateTime, NaiveDateTime),
+}
+
+impl fmt::Display for SelfTestProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            SelfTestProblem::Font(name) => {
+                format!("Schriftart {} ist beschädigt und kann nicht geladen werden", name)
+            }
+            SelfTestProblem::MissingResource(path) => {
+                format!("Datei {} fehlt im Programmverzeichnis", path.display())
+            }
+            SelfTestProblem::PendingMigrations => String::from(
+                "Die Datenbank ist nicht auf dem aktuellen Stand, die Migrationen müssen noch ausgeführt werden",
+            ),
+            SelfTestProblem::Migrations(e) => {
+                format!("Stand der Datenbank konnte nicht geprüft werden: {}", e)
+            }
+            SelfTestProblem::ExportDir(path, e) => format!(
+                "In das Exportverzeichnis {} kann nicht geschrieben werden: {}",
+                path.display(),
+                e
+            ),
+            SelfTestProblem::Clock(now, earliest) => format!(
+                "Die Uhrzeit {} ist falsch, sie müsste mindestens {} sein. Bitte die Uhr des Computers stellen.",
+                now.format("%d.%m.%Y %H:%M:%S"),
+                earliest.format("%d.%m.%Y %H:%M:%S")
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+/// iced only fails when a font is first used, so we check the header of the font file ourselves.
+fn check_font(font: Font) -> Option<SelfTestProblem> {
+    match font {
+        Font::External { name, bytes } => {
+            let is_font = matches!(
+                bytes.get(..4),
+                Some([0x00, 0x01, 0x00, 0x00]) | Some(b"OTTO") | Some(b"true")
+            );
+            (!is_font).then_some(SelfTestProblem::Font(name))
+        }
+        Font::Default => None,
+    }
+}
+
+fn check_resources() -> Vec<SelfTestProblem> {
+    [WorkStatus::Away, WorkStatus::Working]
+        .iter()
+        .map(|status| PathBuf::from(status.to_emoji()))
+        .filter(|path| !path.exists())
+        .map(SelfTestProblem::MissingResource)
+        .collect()
+}
+
+fn check_migrations(connection: &mut SqliteConnection) -> Option<SelfTestProblem> {
+    match connection.has_pending_migration(MIGRATIONS) {
+        Ok(false) => None,
+        Ok(true) => Some(SelfTestProblem::PendingMigrations),
+        Err(e) => Some(SelfTestProblem::Migrations(e.to_string())),
+    }
+}
+
+/// Write and delete a file, since permissions alone do not tell if e.g. a network share is mounted read-only.
+fn check_export_dir(export_dir: &Path) -> Option<SelfTestProblem> {
+    let test_file = export_dir.join(".stechuhr-selbsttest");
+    fs::create_dir_all(export_dir)
+        .and_then(|()| fs::write(&test_file, "Selbsttest"))
+        .and_then(|()| fs::remove_file(&test_file))
+        .err()
+        .map(|e| SelfTestProblem::ExportDir(export_dir.to_owned(), e.to_string()))
+}
+
+/// The pregenerated 6am events are in the future, so they are not taken into account.
+fn check_clock(now: NaiveDateTime, connection: &mut SqliteConnection) -> Option<SelfTestProblem> {
+    use schema::events::dsl::*;
+
+    let (year, month, day) = EARLIEST_SANE_DATE;
+    let last_event = events
+        .filter(kind.ne(WorkEvent::KIND_6AM))
+        .select(diesel::dsl::max(created_at))
+        .first::<Option<NaiveDateTime>>(connection)
+        .ok()
+        .flatten();
+    let earliest = last_event.into_iter().fold(
+        NaiveDate::from_ymd(year, month, day).and_hms(0, 0, 0),
+        |a, b| a.max(b),
+    );
+
+    (now < earliest).then_some(SelfTestProblem::Clock(now, earliest))
+}
+
+/// Run all checks and return every problem that was found.
+pub fn run(
+    settings: &Settings,
+    now: NaiveDateTime,
+    connection: &mut SqliteConnection,
+) -> Vec<SelfTestProblem> {
+    let mut problems = Vec::new();
+    problems.extend(check_font(FONT_SYMBOLA));
+    problems.extend(check_font(FONT_EMOJIONE));
+    problems.extend(check_resources());
+    problems.extend(check_migrations(connection));
+    problems.extend(check_export_dir(&settings.export_dir));
+    problems.extend(check_clock(now, connection));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_test() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        let settings = Settings {
+            export_dir: std::env::temp_dir().join("stechuhr_self_test"),
+            ..Settings::default()
+        };
+        let now = NaiveDate::from_ymd(2022, 6, 1).and_hms(12, 0, 0);
+
+        assert!(run(&settings, now, &mut connection).contains(&SelfTestProblem::PendingMigrations));
+
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        assert_eq!(run(&settings, now, &mut connection), Vec::new());
+
+        let wrong_clock = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(
+            run(&settings, wrong_clock, &mut connection),
+            vec![SelfTestProblem::Clock(
+                wrong_clock,
+                NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0)
+            )]
+        );
+        fs::remove_dir_all(&settings.export_dir).unwrap();
+    }
+}
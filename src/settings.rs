@@ -0,0 +1,790 @@
+//! Runtime settings of the application that can be changed in the "Einstellungen" tab.
+use crate::date_ext::{LocalizedFormat, NaiveDateTimeExt};
+use crate::export_sink::{EmailSink, SinkSettings};
+use crate::quick_action::QuickAction;
+use chrono::{Duration, Locale, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::{env, error, fmt, fs, io};
+
+const DEFAULT_SETTINGS_PATH: &str = "./settings.json";
+
+#[derive(Debug)]
+pub enum SettingsError {
+    IO(io::Error),
+    Json(serde_json::Error),
+    EmptyExportDir,
+    ParseCutoff(String),
+    ParseBucket(String),
+    BucketOrder([u32; 3]),
+    ParseLocale(String),
+    ParseColumns(String),
+    ParseSurcharge(String),
+    ParseWageType(String),
+    ParseConsultantNumber(String),
+    ParseClientNumber(String),
+    InvalidQuickAction(String),
+    /// Login by personnel number needs the personnel numbers to differ from all PINs and dongles.
+    IdentsNotDisjoint(Vec<String>),
+}
+
+impl error::Error for SettingsError {}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            SettingsError::IO(e) => format!(
+                "Einstellungen konnten nicht gelesen/geschrieben werden: {}",
+                e
+            ),
+            SettingsError::Json(e) => format!("Einstellungsdatei ist fehlerhaft: {}", e),
+            SettingsError::EmptyExportDir => String::from("Exportverzeichnis darf nicht leer sein"),
+            SettingsError::ParseCutoff(s) => {
+                format!(
+                    "Tagesgrenze muss im Format HH:MM angegeben werden: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::ParseBucket(s) => {
+                format!(
+                    "Zeitgrenze muss eine Stunde zwischen 0 und 23 sein: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::BucketOrder([b1, b2, b3]) => format!(
+                "Zeitgrenzen {} Uhr, {} Uhr und {} Uhr müssen im Tagesverlauf aufeinander folgen",
+                b1, b2, b3
+            ),
+            SettingsError::ParseLocale(s) => format!("Unbekannte Sprache: \"{}\"", s),
+            SettingsError::ParseColumns(s) => {
+                format!("Spaltenanzahl muss zwischen 1 und 6 liegen: \"{}\"", s)
+            }
+            SettingsError::ParseSurcharge(s) => {
+                format!(
+                    "Zuschlag muss eine ganze Zahl zwischen 0 und 1000 Prozent sein: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::ParseWageType(s) => {
+                format!(
+                    "Lohnart muss eine Zahl zwischen 1 und 9999 sein oder leer bleiben: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::ParseConsultantNumber(s) => {
+                format!(
+                    "Beraternummer muss aus 4 bis 7 Ziffern bestehen oder leer bleiben: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::ParseClientNumber(s) => {
+                format!(
+                    "Mandantennummer muss aus 1 bis 5 Ziffern bestehen oder leer bleiben: \"{}\"",
+                    s
+                )
+            }
+            SettingsError::InvalidQuickAction(label) => format!(
+                "Aktion \"{}\" braucht eine Beschriftung und mindestens einen Schritt",
+                label
+            ),
+            SettingsError::IdentsNotDisjoint(problems) => format!(
+                "Anmeldung mit Personalnummer ist erst möglich, wenn sich PINs, Dongles und Personalnummern nicht überschneiden: {}",
+                problems.join("; ")
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+impl From<serde_json::Error> for SettingsError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Rounding of the minutes in each bucket of the evaluation, as required by payroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    Off,
+    Nearest5,
+    Nearest10,
+    Nearest15,
+    /// Up to the next quarter hour, even a single minute.
+    Up15,
+}
+
+impl Rounding {
+    pub const ALL: [Rounding; 5] = [
+        Rounding::Off,
+        Rounding::Nearest5,
+        Rounding::Nearest10,
+        Rounding::Nearest15,
+        Rounding::Up15,
+    ];
+
+    pub fn apply(self, minutes: i64) -> i64 {
+        let (step, offset) = match self {
+            Rounding::Off => return minutes,
+            Rounding::Nearest5 => (5, 2),
+            Rounding::Nearest10 => (10, 5),
+            Rounding::Nearest15 => (15, 7),
+            Rounding::Up15 => (15, 14),
+        };
+        (minutes + offset) / step * step
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            Rounding::Off => "Keine",
+            Rounding::Nearest5 => "Auf 5 Minuten",
+            Rounding::Nearest10 => "Auf 10 Minuten",
+            Rounding::Nearest15 => "Auf 15 Minuten",
+            Rounding::Up15 => "Immer auf 15 Minuten aufrunden",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// What happens when the window is closed while staff members are still working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosePolicy {
+    /// Closing is refused until everyone is on "Pause" or the event is ended.
+    Block,
+    /// Closing must be repeated within a minute, then everyone still working is signed off.
+    SignOff,
+    /// The window is closed and the status is taken from the events on the next start, e.g. to reboot for updates.
+    Allow,
+}
+
+impl ClosePolicy {
+    pub const ALL: [ClosePolicy; 3] =
+        [ClosePolicy::Block, ClosePolicy::SignOff, ClosePolicy::Allow];
+}
+
+impl fmt::Display for ClosePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ClosePolicy::Block => "Verhindern",
+            ClosePolicy::SignOff => "Warnen und abmelden",
+            ClosePolicy::Allow => "Erlauben, Status bleibt",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// What happens with the summary of a business day that is written at its end, see `night_summary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NightSummaryMode {
+    Off,
+    /// Saved in the export directory.
+    Save,
+    /// Saved in the export directory and sent to the default printer.
+    Print,
+}
+
+impl NightSummaryMode {
+    pub const ALL: [NightSummaryMode; 3] = [
+        NightSummaryMode::Off,
+        NightSummaryMode::Save,
+        NightSummaryMode::Print,
+    ];
+}
+
+impl fmt::Display for NightSummaryMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            NightSummaryMode::Off => "Aus",
+            NightSummaryMode::Save => "Speichern",
+            NightSummaryMode::Print => "Speichern und drucken",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// Options for running Stechuhr on the terminal at the entrance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KioskSettings {
+    /// Start the application in fullscreen mode.
+    pub fullscreen: bool,
+    /// Number of columns of names on the Timetrack dashboard.
+    pub dashboard_columns: usize,
+    /// Show status symbols that can be told apart without seeing colors.
+    pub colorblind_icons: bool,
+    /// Show a keyboard on the screen for the password and the admin inputs, for terminals without a physical keyboard.
+    pub on_screen_keyboard: bool,
+    /// Translate dongle IDs that the card reader typed with a known wrong keyboard layout back to digits.
+    pub correct_keyboard_layout: bool,
+    /// Also accept the personnel number in the Timetrack input. Personnel numbers must then be disjoint from PINs and dongles.
+    pub personnel_number_login: bool,
+    /// What happens when the window is closed while staff members are working.
+    pub close_policy: ClosePolicy,
+    /// Device file of the card reader, e.g. "/dev/input/by-id/usb-…-event-kbd". If set, a warning is shown
+    /// while it is missing, e.g. because the reader was unplugged. Only configurable in the settings file.
+    pub reader_device: Option<PathBuf>,
+}
+
+impl Default for KioskSettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: true,
+            dashboard_columns: 3,
+            colorblind_icons: false,
+            on_screen_keyboard: false,
+            correct_keyboard_layout: false,
+            personnel_number_login: false,
+            close_policy: ClosePolicy::Block,
+            reader_device: None,
+        }
+    }
+}
+
+/// Numbers for the DATEV Lohn import, as assigned by the tax office.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatevSettings {
+    /// Beraternummer of the tax office.
+    pub consultant_number: String,
+    /// Mandantennummer of the business.
+    pub client_number: String,
+    /// Lohnart for each bucket of the evaluation, the three Sunday buckets last. 0 means not configured.
+    pub wage_types: [u32; 6],
+}
+
+/// Texts with which events are described in the log and in the event lists of exports, e.g. "Betriebsschluss"
+/// instead of "6 Uhr morgens". `{name}`, `{status}` and `{message}` are replaced by the values of the event.
+/// Only configurable in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogTexts {
+    /// Uses `{name}` and `{status}`.
+    pub status_change: String,
+    /// The automatic sign-off at the end of the business day.
+    pub boundary: String,
+    /// The remaining texts use `{message}`, `correction` also `{name}`.
+    pub info: String,
+    pub error: String,
+    pub correction: String,
+    pub marker: String,
+    pub lifecycle: String,
+}
+
+impl Default for LogTexts {
+    fn default() -> Self {
+        Self {
+            status_change: String::from("Status von {name} wurde auf \"{status}\" gesetzt"),
+            boundary: String::from("6 Uhr morgens"),
+            info: String::from("Info: {message}"),
+            error: String::from("Error: {message}"),
+            correction: String::from("Korrektur für {name}: {message}"),
+            marker: String::from("Markierung: {message}"),
+            lifecycle: String::from("Stechuhr {message}"),
+        }
+    }
+}
+
+impl LogTexts {
+    /// Replace the placeholders in `template` by their values. Unknown placeholders are kept as they are and the
+    /// values are not searched for placeholders again, e.g. a message that contains "{name}".
+    pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+        let mut filled = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            filled.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            let placeholder = values
+                .iter()
+                .find(|(key, _)| rest.starts_with(key) && rest[key.len()..].starts_with('}'));
+            match placeholder {
+                Some((key, value)) => {
+                    filled.push_str(value);
+                    rest = &rest[key.len() + 1..];
+                }
+                None => filled.push('{'),
+            }
+        }
+        filled.push_str(rest);
+        filled
+    }
+}
+
+impl DatevSettings {
+    /// The export is only possible once the tax office told us the numbers. `buckets` are the indices of the exported
+    /// buckets, an empty bucket never has minutes and needs no wage type.
+    pub fn is_configured(&self, buckets: &[usize]) -> bool {
+        !self.consultant_number.trim().is_empty()
+            && !self.client_number.trim().is_empty()
+            && buckets.iter().all(|idx| self.wage_types[*idx] != 0)
+    }
+}
+
+/// Limits of the work time above which a staff member is marked on the dashboard, so that the shift lead notices
+/// before the monthly evaluation. 0 disables a limit. Only configurable in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkTimeWarnings {
+    /// Minutes of work since the last sign-in.
+    pub continuous_minutes: u32,
+    /// Minutes of work during the current business day, breaks not counted.
+    pub daily_minutes: u32,
+}
+
+impl Default for WorkTimeWarnings {
+    fn default() -> Self {
+        Self {
+            continuous_minutes: 600,
+            daily_minutes: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Directory into which evaluations are written.
+    pub export_dir: PathBuf,
+    /// Time at which the business day ends. Everyone still working is signed off one second before.
+    pub cutoff_time: NaiveTime,
+    /// Starting hours of the three time buckets in the evaluation (day, evening, night).
+    /// Two buckets are configured by giving one of them the start of the next, e.g. 6, 22, 22 for 6–22 and 22–6 Uhr.
+    pub bucket_starts: [u32; 3],
+    /// Count the time on Sundays and holidays in three additional buckets, for the Sunday surcharge.
+    pub sunday_buckets: bool,
+    /// Count Saturdays like Sundays, if those are counted separately.
+    pub saturday_buckets: bool,
+    /// Holidays that are counted like Sundays. Only configurable in the settings file.
+    pub holidays: Vec<NaiveDate>,
+    /// Locale used to format dates in the user interface.
+    pub locale: String,
+    /// Locale used for month names, dates and numbers in exported files.
+    pub export_locale: String,
+    /// Write an Excel file next to each CSV file of the evaluation.
+    pub xlsx_export: bool,
+    /// Add a column with the wage of each staff member to the evaluation, see `Settings::wage_surcharges`.
+    pub wage_column: bool,
+    /// Surcharge in percent on the hourly wage for each bucket of the evaluation, the three Sunday buckets last,
+    /// e.g. 25 for night work.
+    pub wage_surcharges: [u32; 6],
+    /// Rounding of the minutes in each bucket of the evaluation, applied to the sums of the month.
+    pub rounding: Rounding,
+    /// Word that must be typed to confirm ending the event. No word is needed if it is empty.
+    pub end_event_word: String,
+    /// Show the former name of renamed staff members next to the current one in the log.
+    pub show_former_names: bool,
+    /// Day and time of the weekly database audit, should be outside of opening hours.
+    /// Only configurable in the settings file.
+    pub audit_weekday: Weekday,
+    pub audit_time: NaiveTime,
+    /// Interval in seconds to reload the staff list from the database, in case another terminal changed it.
+    /// 0 disables reloading. Only configurable in the settings file.
+    pub staff_refresh_seconds: u32,
+    /// Minutes in which nobody works after which an event is over, see `event_detection`.
+    /// Only configurable in the settings file.
+    pub event_quiet_minutes: u32,
+    /// Free space in megabytes on the disk of the export directory below which a warning is shown on the dashboard.
+    /// Only configurable in the settings file.
+    pub disk_space_warning_mb: u64,
+    /// Summary of the finished business day that is written at its end.
+    pub night_summary: NightSummaryMode,
+    /// Address to which the monthly closing sends the evaluation. The step is skipped if it is empty.
+    /// Only configurable in the settings file.
+    pub closing_email: String,
+    /// Directory into which the database is copied when the application is closed for maintenance.
+    /// Only configurable in the settings file.
+    pub backup_dir: PathBuf,
+    /// Destinations to which exports are sent in addition to the export directory.
+    /// Only configurable in the settings file, but each one can be enabled in the settings tab.
+    pub export_sinks: Vec<SinkSettings>,
+    /// SMTP account and address of the bookkeeping, to which the last evaluation can be sent from the statistics tab.
+    /// Disabled if not set. Only configurable in the settings file.
+    pub bookkeeping_email: Option<EmailSink>,
+    /// File into which a timestamp is written every second, so that a supervisor can detect a hanging application.
+    /// Disabled if not set. Only configurable in the settings file.
+    pub heartbeat_path: Option<PathBuf>,
+    /// Custom buttons in the admin actions of the dashboard, see `quick_action`.
+    /// Only configurable in the settings file.
+    pub quick_actions: Vec<QuickAction>,
+    /// Numbers for the DATEV export.
+    pub datev: DatevSettings,
+    pub work_time_warnings: WorkTimeWarnings,
+    /// Hours of rest that staff members need between the work of two business days, e.g. 11 by the working hours
+    /// law. Signing in earlier is warned about at the terminal and listed in the evaluation, see `rest_period`.
+    /// 0 disables the check. Only configurable in the settings file.
+    pub min_rest_hours: u32,
+    /// Intervals of work that are shorter are not counted in the evaluation but listed as errors, e.g. a dongle that
+    /// was swiped twice by accident. At the terminal a second status change within this time is refused. 0 counts all
+    /// intervals. Only configurable in the settings file.
+    pub min_interval_seconds: u32,
+    /// Intervals of work that are longer are still counted but listed as errors, since somebody probably forgot to
+    /// sign off. Intervals that end with the 6am event are already listed. 0 disables the check. Only configurable in
+    /// the settings file.
+    pub max_shift_hours: u32,
+    /// Milliseconds after which a write to the database is warned about on the dashboard and logged, e.g. when the
+    /// network share of the database is slow, see `write_latency`. Only configurable in the settings file.
+    pub slow_write_warning_ms: u64,
+    pub log_texts: LogTexts,
+    pub kiosk: KioskSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            export_dir: PathBuf::from("./auswertung"),
+            cutoff_time: NaiveTime::from_hms(6, 0, 0),
+            bucket_starts: [4, 20, 0],
+            sunday_buckets: false,
+            saturday_buckets: false,
+            holidays: Vec::new(),
+            locale: String::from("de_DE"),
+            export_locale: String::from("de_DE"),
+            xlsx_export: false,
+            wage_column: false,
+            wage_surcharges: [0; 6],
+            rounding: Rounding::Off,
+            end_event_word: String::new(),
+            show_former_names: true,
+            audit_weekday: Weekday::Mon,
+            audit_time: NaiveTime::from_hms(12, 0, 0),
+            staff_refresh_seconds: 30,
+            event_quiet_minutes: 120,
+            disk_space_warning_mb: 500,
+            night_summary: NightSummaryMode::Off,
+            closing_email: String::new(),
+            backup_dir: PathBuf::from("./sicherung"),
+            export_sinks: Vec::new(),
+            bookkeeping_email: None,
+            heartbeat_path: None,
+            quick_actions: Vec::new(),
+            datev: DatevSettings::default(),
+            work_time_warnings: WorkTimeWarnings::default(),
+            min_rest_hours: 0,
+            min_interval_seconds: 60,
+            max_shift_hours: 12,
+            slow_write_warning_ms: 2000,
+            log_texts: LogTexts::default(),
+            kiosk: KioskSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Path of the settings file, configurable with the SETTINGS_PATH environment variable.
+    pub fn path() -> PathBuf {
+        env::var("SETTINGS_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_SETTINGS_PATH))
+    }
+
+    /// Load the settings file. If it does not exist yet we use the defaults.
+    pub fn load() -> Result<Self, SettingsError> {
+        Self::load_from(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, SettingsError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(path)?;
+        let settings: Settings = serde_json::from_str(&json)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        self.save_to(&Self::path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), SettingsError> {
+        self.validate()?;
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.export_dir.as_os_str().is_empty() {
+            return Err(SettingsError::EmptyExportDir);
+        }
+        Self::validate_buckets(self.bucket_starts)?;
+        Self::parse_locale(&self.locale)?;
+        Self::parse_locale(&self.export_locale)?;
+        if self.kiosk.dashboard_columns == 0 || self.kiosk.dashboard_columns > 6 {
+            return Err(SettingsError::ParseColumns(
+                self.kiosk.dashboard_columns.to_string(),
+            ));
+        }
+        if let Some(action) = self.quick_actions.iter().find(|action| !action.is_valid()) {
+            return Err(SettingsError::InvalidQuickAction(action.label.clone()));
+        }
+        Ok(())
+    }
+
+    /// Index, start and end hour of each bucket in the evaluation. A bucket that starts at the same hour as the
+    /// next one is empty and left out of the exports.
+    pub fn bucket_ranges(&self) -> Vec<(usize, u32, u32)> {
+        let [b1, b2, b3] = self.bucket_starts;
+        [(b1, b2), (b2, b3), (b3, b1)]
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (start, end))| start != end)
+            .map(|(idx, (start, end))| (idx, start, end))
+            .collect()
+    }
+
+    /// The buckets must follow each other in the course of a day, wrapping around at midnight at most once.
+    /// One bucket may be empty, two different hours always follow each other.
+    fn validate_buckets(bucket_starts: [u32; 3]) -> Result<(), SettingsError> {
+        let [b1, b2, b3] = bucket_starts;
+        if let Some(b) = bucket_starts.iter().find(|b| **b >= 24) {
+            return Err(SettingsError::ParseBucket(b.to_string()));
+        }
+
+        let empty = [b1 == b2, b2 == b3, b3 == b1]
+            .iter()
+            .filter(|empty| **empty)
+            .count();
+        match empty {
+            0 => {}
+            1 => return Ok(()),
+            _ => return Err(SettingsError::BucketOrder(bucket_starts)),
+        }
+        let wraps = [b1 >= b2, b2 >= b3, b3 >= b1]
+            .iter()
+            .filter(|wrap| **wrap)
+            .count();
+        if wraps != 1 {
+            return Err(SettingsError::BucketOrder(bucket_starts));
+        }
+        Ok(())
+    }
+
+    pub fn parse_cutoff(s: &str) -> Result<NaiveTime, SettingsError> {
+        NaiveTime::parse_from_str(s.trim(), "%H:%M")
+            .map_err(|_| SettingsError::ParseCutoff(s.to_owned()))
+    }
+
+    pub fn parse_bucket(s: &str) -> Result<u32, SettingsError> {
+        match s.trim().parse::<u32>() {
+            Ok(hour) if hour < 24 => Ok(hour),
+            _ => Err(SettingsError::ParseBucket(s.to_owned())),
+        }
+    }
+
+    pub fn parse_surcharge(s: &str) -> Result<u32, SettingsError> {
+        match s.trim().parse::<u32>() {
+            Ok(percent) if percent <= 1000 => Ok(percent),
+            _ => Err(SettingsError::ParseSurcharge(s.to_owned())),
+        }
+    }
+
+    /// A DATEV Lohnart has at most four digits. An empty input is 0, i.e. not configured.
+    pub fn parse_wage_type(s: &str) -> Result<u32, SettingsError> {
+        let s_trimmed = s.trim();
+        if s_trimmed.is_empty() {
+            return Ok(0);
+        }
+        match s_trimmed.parse::<u32>() {
+            Ok(wage_type) if (1..=9999).contains(&wage_type) => Ok(wage_type),
+            _ => Err(SettingsError::ParseWageType(s.to_owned())),
+        }
+    }
+
+    /// A DATEV Beraternummer has 4 to 7 digits. An empty input means not configured.
+    pub fn parse_consultant_number(s: &str) -> Result<String, SettingsError> {
+        Self::parse_datev_number(s, 4..=7)
+            .ok_or_else(|| SettingsError::ParseConsultantNumber(s.to_owned()))
+    }
+
+    /// A DATEV Mandantennummer has 1 to 5 digits. An empty input means not configured.
+    pub fn parse_client_number(s: &str) -> Result<String, SettingsError> {
+        Self::parse_datev_number(s, 1..=5)
+            .ok_or_else(|| SettingsError::ParseClientNumber(s.to_owned()))
+    }
+
+    fn parse_datev_number(s: &str, digits: RangeInclusive<usize>) -> Option<String> {
+        let s_trimmed = s.trim();
+        if s_trimmed.is_empty()
+            || (digits.contains(&s_trimmed.len()) && s_trimmed.chars().all(|c| c.is_ascii_digit()))
+        {
+            Some(s_trimmed.to_owned())
+        } else {
+            None
+        }
+    }
+
+    pub fn parse_columns(s: &str) -> Result<usize, SettingsError> {
+        match s.trim().parse::<usize>() {
+            Ok(columns) if (1..=6).contains(&columns) => Ok(columns),
+            _ => Err(SettingsError::ParseColumns(s.to_owned())),
+        }
+    }
+
+    pub fn parse_locale(s: &str) -> Result<Locale, SettingsError> {
+        Locale::try_from(s.trim()).map_err(|_| SettingsError::ParseLocale(s.to_owned()))
+    }
+
+    /// The locale for the user interface. Falls back to German if the setting is invalid.
+    pub fn locale(&self) -> Locale {
+        Self::parse_locale(&self.locale).unwrap_or(Locale::de_DE)
+    }
+
+    /// The locale for exported files. Falls back to German if the setting is invalid.
+    pub fn export_locale(&self) -> Locale {
+        Self::parse_locale(&self.export_locale).unwrap_or(Locale::de_DE)
+    }
+
+    /// Format a date for exported files in the order that is usual for the export locale.
+    pub fn format_export_date(&self, date: NaiveDate) -> String {
+        date.format_localized("%x", self.export_locale())
+            .to_string()
+    }
+
+    /// Format a decimal number for exported files with the decimal separator of the export locale.
+    pub fn format_export_decimal(&self, value: f64, precision: usize) -> String {
+        let formatted = format!("{:.*}", precision, value);
+        // chrono does not know about number formats, so we look at the language instead.
+        let language = self.export_locale.split('_').next().unwrap_or("");
+        match language {
+            "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" | "ga" | "mt" => formatted,
+            _ => formatted.replace('.', ","),
+        }
+    }
+
+    /// The time at which all staff members still working are signed off.
+    pub fn sign_off_time(&self) -> NaiveTime {
+        self.cutoff_time - Duration::seconds(1)
+    }
+
+    /// The business day (cutoff to cutoff) that the given point in time belongs to.
+    pub fn business_date(&self, datetime: NaiveDateTime) -> NaiveDate {
+        datetime.business_date(self.cutoff_time)
+    }
+
+    /// The point in time at which the given business day starts.
+    pub fn business_day_start(&self, date: NaiveDate) -> NaiveDateTime {
+        date.and_time(self.cutoff_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatevSettings, LogTexts, Rounding, Settings};
+
+    #[test]
+    fn default_is_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn export_formats() {
+        let mut settings = Settings::default();
+        let date = chrono::NaiveDate::from_ymd(2022, 4, 1);
+        assert_eq!(settings.format_export_date(date), "01.04.2022");
+        assert_eq!(settings.format_export_decimal(7.5, 2), "7,50");
+
+        settings.export_locale = String::from("en_US");
+        assert_eq!(settings.format_export_date(date), "04/01/2022");
+        assert_eq!(settings.format_export_decimal(7.5, 2), "7.50");
+    }
+
+    #[test]
+    fn fill_log_texts() {
+        let values = [("name", "Aaron {message}"), ("message", "Hallo")];
+        assert_eq!(
+            LogTexts::fill("{name} sagt {message} {unknown}", &values),
+            "Aaron {message} sagt Hallo {unknown}"
+        );
+        assert_eq!(
+            LogTexts::fill("Betriebsschluss {", &values),
+            "Betriebsschluss {"
+        );
+    }
+
+    #[test]
+    fn round_minutes() {
+        let rounded =
+            |rounding: Rounding| [0, 7, 8, 22, 23, 61].map(|minutes| rounding.apply(minutes));
+        assert_eq!(rounded(Rounding::Off), [0, 7, 8, 22, 23, 61]);
+        assert_eq!(rounded(Rounding::Nearest5), [0, 5, 10, 20, 25, 60]);
+        assert_eq!(rounded(Rounding::Nearest10), [0, 10, 10, 20, 20, 60]);
+        assert_eq!(rounded(Rounding::Nearest15), [0, 0, 15, 15, 30, 60]);
+        assert_eq!(rounded(Rounding::Up15), [0, 15, 15, 30, 30, 75]);
+    }
+
+    #[test]
+    fn bucket_order() {
+        assert!(Settings::validate_buckets([6, 22, 0]).is_ok());
+        assert!(Settings::validate_buckets([0, 6, 22]).is_ok());
+        assert!(Settings::validate_buckets([22, 0, 6]).is_ok());
+        assert!(Settings::validate_buckets([6, 0, 22]).is_err());
+        assert!(Settings::validate_buckets([6, 6, 22]).is_ok());
+        assert!(Settings::validate_buckets([6, 22, 22]).is_ok());
+        assert!(Settings::validate_buckets([6, 6, 6]).is_err());
+        assert!(Settings::validate_buckets([6, 22, 24]).is_err());
+    }
+
+    #[test]
+    fn wage_types() {
+        assert_eq!(Settings::parse_wage_type(" 120 ").unwrap(), 120);
+        assert_eq!(Settings::parse_wage_type("").unwrap(), 0);
+        assert!(Settings::parse_wage_type("0").is_err());
+        assert!(Settings::parse_wage_type("10000").is_err());
+        assert!(Settings::parse_wage_type("Lohn").is_err());
+    }
+
+    #[test]
+    fn datev_numbers() {
+        assert_eq!(
+            Settings::parse_consultant_number(" 1234567 ").unwrap(),
+            "1234567"
+        );
+        assert_eq!(Settings::parse_consultant_number("").unwrap(), "");
+        assert!(Settings::parse_consultant_number("123").is_err());
+        assert!(Settings::parse_consultant_number("12345678").is_err());
+        assert!(Settings::parse_consultant_number("12 345").is_err());
+        assert_eq!(Settings::parse_client_number("1").unwrap(), "1");
+        assert!(Settings::parse_client_number("123456").is_err());
+        assert!(Settings::parse_client_number("-123").is_err());
+        assert!(Settings::parse_client_number("１２３").is_err());
+    }
+
+    #[test]
+    fn datev_wage_types_of_exported_buckets() {
+        let datev = DatevSettings {
+            consultant_number: String::from("12345"),
+            client_number: String::from("678"),
+            wage_types: [100, 0, 130, 0, 0, 0],
+        };
+        assert!(datev.is_configured(&[0, 2]));
+        assert!(!datev.is_configured(&[0, 1, 2]));
+        assert!(!datev.is_configured(&[0, 2, 3, 5]));
+    }
+
+    #[test]
+    fn two_bucket_ranges() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            settings.bucket_ranges(),
+            vec![(0, 4, 20), (1, 20, 0), (2, 0, 4)]
+        );
+        settings.bucket_starts = [6, 22, 22];
+        assert_eq!(settings.bucket_ranges(), vec![(0, 6, 22), (2, 22, 6)]);
+    }
+}
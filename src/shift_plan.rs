@@ -0,0 +1,342 @@
+//! Parsing of shift plans exported from the scheduling tool, either as CSV or as iCal calendar.
+use crate::models::StaffMember;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+const DATETIME_FORMATS: [&str; 5] = [
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%d.%m.%Y %H:%M",
+];
+
+#[derive(Debug)]
+pub enum ShiftPlanError {
+    IO(io::Error),
+    CSV(csv::Error),
+    UnknownFormat(String),
+    MissingColumn(&'static str),
+    ParseTime(String),
+    EndBeforeStart(usize),
+    ICal(usize, &'static str),
+}
+
+impl error::Error for ShiftPlanError {}
+
+impl fmt::Display for ShiftPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ShiftPlanError::IO(e) => format!("Schichtplan konnte nicht gelesen werden: {}", e),
+            ShiftPlanError::CSV(e) => format!("Schichtplan ist fehlerhaft: {}", e),
+            ShiftPlanError::UnknownFormat(path) => format!(
+                "Unbekanntes Dateiformat, erwartet wird .csv, .tsv oder .ics: \"{}\"",
+                path
+            ),
+            ShiftPlanError::MissingColumn(column) => {
+                format!("Spalte \"{}\" fehlt im Schichtplan", column)
+            }
+            ShiftPlanError::ParseTime(s) => {
+                format!("Ungültige Zeitangabe im Schichtplan: \"{}\"", s)
+            }
+            ShiftPlanError::EndBeforeStart(entry) => format!(
+                "Schicht Nr. {} im Schichtplan endet vor ihrem Beginn",
+                entry
+            ),
+            ShiftPlanError::ICal(entry, field) => format!(
+                "Termin Nr. {} im Kalender fehlt die Angabe \"{}\"",
+                entry, field
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+impl From<io::Error> for ShiftPlanError {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+impl From<csv::Error> for ShiftPlanError {
+    fn from(e: csv::Error) -> Self {
+        Self::CSV(e)
+    }
+}
+
+/// A shift as it appears in the shift plan, not yet assigned to a staff member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedShift {
+    pub name: Option<String>,
+    pub personnel_number: Option<String>,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+}
+
+/// Result of looking up the staff member of a planned shift.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaffMatch {
+    Unique(i32),
+    Ambiguous(Vec<i32>),
+    Unknown,
+}
+
+impl PlannedShift {
+    /// Name or personnel number for displaying the shift.
+    pub fn ident(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.personnel_number.as_deref())
+            .unwrap_or("")
+    }
+
+    /// Find the staff member of this shift. The personnel number takes precedence over the name.
+    /// If no personnel number is given (e.g. in iCal files) the name is also compared against personnel numbers.
+    pub fn match_staff(&self, staff: &[StaffMember]) -> StaffMatch {
+        let by_personnel_number = |personnel_number: &str| {
+            staff
+                .iter()
+                .filter(|staff_member| {
+                    staff_member.personnel_number.as_deref() == Some(personnel_number)
+                })
+                .map(StaffMember::uuid)
+                .collect::<Vec<_>>()
+        };
+        let by_name = |name: &str| {
+            staff
+                .iter()
+                .filter(|staff_member| staff_member.name.trim().eq_ignore_ascii_case(name))
+                .map(StaffMember::uuid)
+                .collect::<Vec<_>>()
+        };
+
+        let candidates = match (&self.personnel_number, &self.name) {
+            (Some(personnel_number), _) => by_personnel_number(personnel_number),
+            (None, Some(name)) => {
+                let candidates = by_personnel_number(name);
+                if candidates.is_empty() {
+                    by_name(name)
+                } else {
+                    candidates
+                }
+            }
+            (None, None) => Vec::new(),
+        };
+
+        match candidates.len() {
+            0 => StaffMatch::Unknown,
+            1 => StaffMatch::Unique(candidates[0]),
+            _ => StaffMatch::Ambiguous(candidates),
+        }
+    }
+}
+
+/// Read a shift plan, the format is chosen by the file extension.
+pub fn parse_file(path: &Path) -> Result<Vec<PlannedShift>, ShiftPlanError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("csv") | Some("tsv") | Some("txt") => parse_csv(&fs::read_to_string(path)?),
+        Some("ics") | Some("ical") => parse_ical(&fs::read_to_string(path)?),
+        _ => Err(ShiftPlanError::UnknownFormat(
+            path.to_string_lossy().into_owned(),
+        )),
+    }
+}
+
+fn parse_datetime(s: &str) -> Result<NaiveDateTime, ShiftPlanError> {
+    let s = s.trim();
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .ok_or_else(|| ShiftPlanError::ParseTime(s.to_owned()))
+}
+
+fn non_empty(s: Option<&str>) -> Option<String> {
+    s.map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+}
+
+/// Parse a CSV shift plan. The first line must contain the column names.
+/// We need the columns "Beginn" and "Ende" and at least one of "Name" and "Personalnummer".
+/// The delimiter (semicolon, tab or comma) is detected from the first line.
+pub fn parse_csv(content: &str) -> Result<Vec<PlannedShift>, ShiftPlanError> {
+    let header = content.lines().next().unwrap_or("");
+    let delimiter = if header.contains(';') {
+        b';'
+    } else if header.contains('\t') {
+        b'\t'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.iter().any(|name| header.eq_ignore_ascii_case(name)))
+    };
+    let name_column = column(&["name"]);
+    let personnel_number_column = column(&["personalnummer", "personalnr", "personalnr."]);
+    let start_column =
+        column(&["beginn", "start"]).ok_or(ShiftPlanError::MissingColumn("Beginn"))?;
+    let end_column = column(&["ende", "end"]).ok_or(ShiftPlanError::MissingColumn("Ende"))?;
+    if name_column.is_none() && personnel_number_column.is_none() {
+        return Err(ShiftPlanError::MissingColumn("Name"));
+    }
+
+    let mut shifts = Vec::new();
+    for (idx, record) in reader.records().enumerate() {
+        let record = record?;
+        let shift = PlannedShift {
+            name: non_empty(name_column.and_then(|column| record.get(column))),
+            personnel_number: non_empty(
+                personnel_number_column.and_then(|column| record.get(column)),
+            ),
+            start_time: parse_datetime(record.get(start_column).unwrap_or(""))?,
+            end_time: parse_datetime(record.get(end_column).unwrap_or(""))?,
+        };
+        if shift.end_time <= shift.start_time {
+            return Err(ShiftPlanError::EndBeforeStart(idx + 1));
+        }
+        shifts.push(shift);
+    }
+
+    Ok(shifts)
+}
+
+/// Parse a date-time value of an iCal property. Times in UTC are converted to local time,
+/// all other times are assumed to be local already.
+fn parse_ical_datetime(value: &str) -> Result<NaiveDateTime, ShiftPlanError> {
+    let (value, is_utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let datetime = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map_err(|_| ShiftPlanError::ParseTime(value.to_owned()))?;
+
+    if is_utc {
+        Ok(Local.from_utc_datetime(&datetime).naive_local())
+    } else {
+        Ok(datetime)
+    }
+}
+
+/// Parse an iCal calendar where each VEVENT is a shift and the SUMMARY holds the name or personnel number.
+pub fn parse_ical(content: &str) -> Result<Vec<PlannedShift>, ShiftPlanError> {
+    // undo line folding, continuation lines start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (
+            line.strip_prefix(' ').or(line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut shifts = Vec::new();
+    let mut in_event = false;
+    let (mut summary, mut start_time, mut end_time) = (None, None, None);
+
+    for line in &lines {
+        let (property, value) = match line.split_once(':') {
+            Some((property, value)) => (property, value.trim()),
+            None => continue,
+        };
+        // strip parameters like DTSTART;TZID=Europe/Berlin
+        let property = property.split(';').next().unwrap_or("");
+
+        match (property, value) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                summary = None;
+                start_time = None;
+                end_time = None;
+            }
+            ("END", "VEVENT") if in_event => {
+                in_event = false;
+                let entry = shifts.len() + 1;
+                let shift = PlannedShift {
+                    name: Some(
+                        summary
+                            .take()
+                            .ok_or(ShiftPlanError::ICal(entry, "SUMMARY"))?,
+                    ),
+                    personnel_number: None,
+                    start_time: start_time
+                        .take()
+                        .ok_or(ShiftPlanError::ICal(entry, "DTSTART"))?,
+                    end_time: end_time
+                        .take()
+                        .ok_or(ShiftPlanError::ICal(entry, "DTEND"))?,
+                };
+                if shift.end_time <= shift.start_time {
+                    return Err(ShiftPlanError::EndBeforeStart(entry));
+                }
+                shifts.push(shift);
+            }
+            ("SUMMARY", value) if in_event => summary = non_empty(Some(value)),
+            ("DTSTART", value) if in_event => start_time = Some(parse_ical_datetime(value)?),
+            ("DTEND", value) if in_event => end_time = Some(parse_ical_datetime(value)?),
+            _ => {}
+        }
+    }
+
+    Ok(shifts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn csv_shift_plan() {
+        let content = "Name;Personalnummer;Beginn;Ende\n\
+                       Aaron;1001;2022-04-01 18:00;2022-04-02 02:00\n\
+                       Beeron;;01.04.2022 20:00;02.04.2022 04:00\n";
+        let shifts = parse_csv(content).unwrap();
+
+        assert_eq!(shifts.len(), 2);
+        assert_eq!(shifts[0].personnel_number.as_deref(), Some("1001"));
+        assert_eq!(shifts[1].personnel_number, None);
+        assert_eq!(
+            shifts[1].end_time,
+            NaiveDate::from_ymd(2022, 4, 2).and_hms(4, 0, 0)
+        );
+    }
+
+    #[test]
+    fn ical_shift_plan() {
+        let content = "BEGIN:VCALENDAR\r\n\
+                       BEGIN:VEVENT\r\n\
+                       SUMMARY:Aaron\r\n\
+                       DTSTART;TZID=Europe/Berlin:20220401T180000\r\n\
+                       DTEND;TZID=Europe/Berlin:20220402T020000\r\n\
+                       END:VEVENT\r\n\
+                       END:VCALENDAR\r\n";
+        let shifts = parse_ical(content).unwrap();
+
+        assert_eq!(
+            shifts,
+            vec![PlannedShift {
+                name: Some(String::from("Aaron")),
+                personnel_number: None,
+                start_time: NaiveDate::from_ymd(2022, 4, 1).and_hms(18, 0, 0),
+                end_time: NaiveDate::from_ymd(2022, 4, 2).and_hms(2, 0, 0),
+            }]
+        );
+    }
+}
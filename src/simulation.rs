@@ -0,0 +1,173 @@
+//! Headless simulation of the update loop for load testing, enabled with the `headless-simulation` feature.
+//! Synthetic staff members swipe their cards in turn while the application runs without a window, which
+//! measures how fast events are saved and how long each message blocks the user interface.
+//! Usage: stechuhr --simulate <path of a database copy> [number of swipes] [number of staff members]
+use crate::tabs::timetrack::TimetrackMessage;
+use crate::{Message, Stechuhr};
+use chrono::Local;
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use iced::Application;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+use stechuhr::{db, models::NewStaffMember};
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+const USAGE: &str =
+    "Usage: stechuhr --simulate <path of a database copy> [number of swipes] [number of staff members]";
+
+/// Durations of the messages of one kind, e.g. all swipes.
+#[derive(Debug, Default)]
+pub struct Latencies(Vec<Duration>);
+
+impl Latencies {
+    fn record(&mut self, started: Instant) {
+        self.0.push(started.elapsed());
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.0.iter().sum()
+    }
+
+    /// The duration below which `percent` of the messages were handled.
+    pub fn percentile(&self, percent: usize) -> Duration {
+        let mut sorted = self.0.clone();
+        sorted.sort();
+        sorted
+            .get(sorted.len().saturating_sub(1) * percent / 100)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn summary(&self, name: &str) -> String {
+        format!(
+            "{:<8} {:>7} messages, p50 {:>9.2?}, p95 {:>9.2?}, p99 {:>9.2?}, max {:>9.2?}",
+            name,
+            self.len(),
+            self.percentile(50),
+            self.percentile(95),
+            self.percentile(99),
+            self.percentile(100)
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Entering the card id, submitting and confirming it.
+    pub swipes: Latencies,
+    pub ticks: Latencies,
+    pub views: Latencies,
+}
+
+/// Swipe the cards in turn, with a tick whenever a second has passed and a view after each message as the
+/// renderer would request it.
+pub fn simulate(app: &mut Stechuhr, cardids: &[String], swipes: usize) -> Report {
+    let mut report = Report::default();
+    // The load test swipes the same card again within seconds, which the terminal would refuse as a double swipe.
+    app.shared.settings.min_interval_seconds = 0;
+    // Identical status changes within one second are saved only once, so the same card is swiped at most twice
+    // per second. For each card we keep the seconds of its last two swipes.
+    let mut recent = vec![[i64::MIN; 2]; cardids.len()];
+    let mut last_tick = Local::now().timestamp();
+
+    for i in (0..cardids.len()).cycle().take(swipes) {
+        if Local::now().timestamp() == recent[i][0] {
+            let millis = 1000 - Local::now().timestamp_subsec_millis().min(999);
+            thread::sleep(Duration::from_millis(millis as u64));
+        }
+
+        let now = Local::now();
+        if now.timestamp() != last_tick {
+            last_tick = now.timestamp();
+            let started = Instant::now();
+            app.update(Message::Tick(now));
+            report.ticks.record(started);
+        }
+
+        let started = Instant::now();
+        app.update(Message::Timetrack(TimetrackMessage::ChangeBreakInput(
+            cardids[i].clone(),
+        )));
+        app.update(Message::Timetrack(TimetrackMessage::SubmitBreakInput));
+        app.update(Message::Timetrack(
+            TimetrackMessage::ConfirmSubmitBreakInput,
+        ));
+        report.swipes.record(started);
+        recent[i] = [recent[i][1], Local::now().timestamp()];
+
+        let started = Instant::now();
+        let _ = app.view();
+        report.views.record(started);
+    }
+    report
+}
+
+/// Card ids of the synthetic staff members, which are created if they do not exist from an earlier run.
+fn prepare_staff(
+    count: usize,
+    connection: &mut SqliteConnection,
+) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let existing = db::load_state(Local::now().naive_local(), connection);
+    let mut cardids = Vec::with_capacity(count);
+    for i in 0..count {
+        let name = format!("Lasttest {}", i + 1);
+        let cardid = match existing
+            .iter()
+            .find(|staff_member| staff_member.name == name)
+        {
+            Some(staff_member) => staff_member.cardid.clone(),
+            None => {
+                let cardid = format!("99{:08}", i);
+                let staff_member =
+                    NewStaffMember::new(name, format!("L{:03}", i), cardid.clone(), String::new())?;
+                db::insert_staff(staff_member, connection)?;
+                cardid
+            }
+        };
+        cardids.push(cardid);
+    }
+    Ok(cardids)
+}
+
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = args.next().ok_or(USAGE)?;
+    let swipes = match args.next() {
+        Some(swipes) => swipes.parse::<usize>()?,
+        None => 10_000,
+    };
+    let staff = match args.next() {
+        Some(staff) => staff.parse::<usize>()?,
+        None => 100,
+    };
+    if staff == 0 || staff > 1000 {
+        return Err("The number of staff members must be between 1 and 1000.".into());
+    }
+
+    let mut connection = SqliteConnection::establish(&path)?;
+    connection.run_pending_migrations(MIGRATIONS)?;
+    let cardids = prepare_staff(staff, &mut connection)?;
+
+    let started = Instant::now();
+    let (mut app, _) = Stechuhr::new((Box::new(connection), Vec::new()));
+    println!("Started in {:?}", started.elapsed());
+
+    let started = Instant::now();
+    let report = simulate(&mut app, &cardids, swipes);
+    println!(
+        "{} swipes of {} staff members in {:?}, {:.0} swipes per second while updating",
+        report.swipes.len(),
+        cardids.len(),
+        started.elapsed(),
+        report.swipes.len() as f64 / report.swipes.total().as_secs_f64()
+    );
+    println!("{}", report.swipes.summary("swipe"));
+    println!("{}", report.ticks.summary("tick"));
+    println!("{}", report.views.summary("view"));
+    Ok(())
+}
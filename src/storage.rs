@@ -0,0 +1,302 @@
+//! The stored data as the tabs see it. The application holds a `Storage` instead of the database connection, so
+//! that the flows of the tabs can be tested with a fake that records which data they read and write.
+//! The tabs only reach the database through this trait, so everything they read and write has a method here.
+use crate::db::{self, SaveStaffError, StaffChanges};
+use crate::maintenance;
+use crate::models::{
+    Announcement, DBStaffMember, EventSession, Export, NewAnnouncement, NewClosedMonth,
+    NewEventSession, NewExport, NewShift, NewStaffHistoryEntry, NewStaffMember, NewWorkEventT,
+    Shift, StaffHistoryEntry, StaffMember, StaffNames, WorkEventT,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{QueryResult, SqliteConnection};
+use std::path::Path;
+
+pub trait Storage {
+    /* staff */
+
+    fn load_state(&mut self, current_time: NaiveDateTime) -> Vec<StaffMember>;
+
+    fn load_staff_names(&mut self) -> StaffNames;
+
+    fn load_all_staff_ids(&mut self) -> Vec<i32>;
+
+    fn load_staff_added_at(&mut self, uuid: i32) -> Option<NaiveDateTime>;
+
+    fn refresh_staff(
+        &mut self,
+        staff: &mut Vec<StaffMember>,
+        previous_events: &[WorkEventT],
+    ) -> StaffChanges;
+
+    fn load_staff_including_inactive(&mut self) -> Vec<DBStaffMember>;
+
+    fn insert_staff(&mut self, staff_member: NewStaffMember) -> QueryResult<StaffMember>;
+
+    fn save_staff_member(&mut self, staff_member: &mut StaffMember) -> Result<(), SaveStaffError>;
+
+    fn save_staff(&mut self, staff: &mut [StaffMember]) -> QueryResult<()>;
+
+    fn delete_staff_member(&mut self, staff_member: StaffMember) -> QueryResult<()>;
+
+    fn insert_staff_history(&mut self, entries: &[NewStaffHistoryEntry]) -> QueryResult<usize>;
+
+    fn load_staff_history_between(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<StaffHistoryEntry>;
+
+    fn verify_password(&mut self, password: &str) -> bool;
+
+    /* events */
+
+    fn insert_event(&mut self, new_event: NewWorkEventT) -> Option<WorkEventT>;
+
+    fn load_events_between(
+        &mut self,
+        start_time: Option<NaiveDateTime>,
+        end_time: Option<NaiveDateTime>,
+    ) -> Vec<WorkEventT>;
+
+    fn load_events_of_kinds_between(
+        &mut self,
+        kinds: &[&str],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT>;
+
+    fn load_status_events_before(&mut self, before: NaiveDateTime) -> Vec<WorkEventT>;
+
+    fn load_latest_events_of_kind(&mut self, event_kind: &str, limit: i64) -> Vec<WorkEventT>;
+
+    fn load_last_event_time(&mut self, before: NaiveDateTime) -> Option<NaiveDateTime>;
+
+    fn ensure_6am_event(&mut self, time: NaiveDateTime) -> bool;
+
+    fn load_staff_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT>;
+
+    fn load_staff_status_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT>;
+
+    fn load_all_staff_events(&mut self, uuid: i32) -> Vec<WorkEventT>;
+
+    /* shifts and announcements */
+
+    fn load_shifts_between(&mut self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Shift>;
+
+    fn insert_shifts(&mut self, new_shifts: &[NewShift]) -> QueryResult<usize>;
+
+    fn load_announcement(&mut self) -> Option<Announcement>;
+
+    fn insert_announcement(&mut self, new_announcement: NewAnnouncement) -> QueryResult<usize>;
+
+    /* statistics */
+
+    fn load_event_sessions_between(
+        &mut self,
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+    ) -> Vec<EventSession>;
+
+    fn replace_event_sessions(
+        &mut self,
+        date: NaiveDate,
+        sessions: &[NewEventSession],
+    ) -> QueryResult<usize>;
+
+    fn is_month_closed(&mut self, first_day: NaiveDate) -> bool;
+
+    fn insert_closed_month(&mut self, closed_month: NewClosedMonth) -> QueryResult<usize>;
+
+    fn load_exports(&mut self, limit: i64) -> Vec<Export>;
+
+    fn insert_export(&mut self, new_export: NewExport) -> QueryResult<usize>;
+
+    /* maintenance */
+
+    fn backup(&mut self, path: &Path) -> QueryResult<()>;
+}
+
+/// The database implements every method with the function of the same name in `db`, or in `maintenance` for backups.
+impl Storage for SqliteConnection {
+    fn load_state(&mut self, current_time: NaiveDateTime) -> Vec<StaffMember> {
+        db::load_state(current_time, self)
+    }
+
+    fn load_staff_names(&mut self) -> StaffNames {
+        db::load_staff_names(self)
+    }
+
+    fn load_all_staff_ids(&mut self) -> Vec<i32> {
+        db::load_all_staff_ids(self)
+    }
+
+    fn load_staff_added_at(&mut self, uuid: i32) -> Option<NaiveDateTime> {
+        db::load_staff_added_at(uuid, self)
+    }
+
+    fn refresh_staff(
+        &mut self,
+        staff: &mut Vec<StaffMember>,
+        previous_events: &[WorkEventT],
+    ) -> StaffChanges {
+        db::refresh_staff(staff, previous_events, self)
+    }
+
+    fn load_staff_including_inactive(&mut self) -> Vec<DBStaffMember> {
+        db::load_staff_including_inactive(self)
+    }
+
+    fn insert_staff(&mut self, staff_member: NewStaffMember) -> QueryResult<StaffMember> {
+        db::insert_staff(staff_member, self)
+    }
+
+    fn save_staff_member(&mut self, staff_member: &mut StaffMember) -> Result<(), SaveStaffError> {
+        db::save_staff_member(staff_member, self)
+    }
+
+    fn save_staff(&mut self, staff: &mut [StaffMember]) -> QueryResult<()> {
+        db::save_staff(staff, self)
+    }
+
+    fn delete_staff_member(&mut self, staff_member: StaffMember) -> QueryResult<()> {
+        db::delete_staff_member(staff_member, self)
+    }
+
+    fn insert_staff_history(&mut self, entries: &[NewStaffHistoryEntry]) -> QueryResult<usize> {
+        db::insert_staff_history(entries, self)
+    }
+
+    fn load_staff_history_between(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<StaffHistoryEntry> {
+        db::load_staff_history_between(start, end, self)
+    }
+
+    fn verify_password(&mut self, password: &str) -> bool {
+        db::verify_password(password, self)
+    }
+
+    fn insert_event(&mut self, new_event: NewWorkEventT) -> Option<WorkEventT> {
+        db::insert_event(new_event, self)
+    }
+
+    fn load_events_between(
+        &mut self,
+        start_time: Option<NaiveDateTime>,
+        end_time: Option<NaiveDateTime>,
+    ) -> Vec<WorkEventT> {
+        db::load_events_between(start_time, end_time, self)
+    }
+
+    fn load_events_of_kinds_between(
+        &mut self,
+        kinds: &[&str],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        db::load_events_of_kinds_between(kinds, start_time, end_time, self)
+    }
+
+    fn load_status_events_before(&mut self, before: NaiveDateTime) -> Vec<WorkEventT> {
+        db::load_status_events_before(before, self)
+    }
+
+    fn load_latest_events_of_kind(&mut self, event_kind: &str, limit: i64) -> Vec<WorkEventT> {
+        db::load_latest_events_of_kind(event_kind, limit, self)
+    }
+
+    fn load_last_event_time(&mut self, before: NaiveDateTime) -> Option<NaiveDateTime> {
+        db::load_last_event_time(before, self)
+    }
+
+    fn ensure_6am_event(&mut self, time: NaiveDateTime) -> bool {
+        db::ensure_6am_event(time, self)
+    }
+
+    fn load_staff_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        db::load_staff_events_between(uuid, start_time, end_time, self)
+    }
+
+    fn load_staff_status_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        db::load_staff_status_events_between(uuid, start_time, end_time, self)
+    }
+
+    fn load_all_staff_events(&mut self, uuid: i32) -> Vec<WorkEventT> {
+        db::load_all_staff_events(uuid, self)
+    }
+
+    fn load_shifts_between(&mut self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Shift> {
+        db::load_shifts_between(start, end, self)
+    }
+
+    fn insert_shifts(&mut self, new_shifts: &[NewShift]) -> QueryResult<usize> {
+        db::insert_shifts(new_shifts, self)
+    }
+
+    fn load_announcement(&mut self) -> Option<Announcement> {
+        db::load_announcement(self)
+    }
+
+    fn insert_announcement(&mut self, new_announcement: NewAnnouncement) -> QueryResult<usize> {
+        db::insert_announcement(new_announcement, self)
+    }
+
+    fn load_event_sessions_between(
+        &mut self,
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+    ) -> Vec<EventSession> {
+        db::load_event_sessions_between(first_date, last_date, self)
+    }
+
+    fn replace_event_sessions(
+        &mut self,
+        date: NaiveDate,
+        sessions: &[NewEventSession],
+    ) -> QueryResult<usize> {
+        db::replace_event_sessions(date, sessions, self)
+    }
+
+    fn is_month_closed(&mut self, first_day: NaiveDate) -> bool {
+        db::is_month_closed(first_day, self)
+    }
+
+    fn insert_closed_month(&mut self, closed_month: NewClosedMonth) -> QueryResult<usize> {
+        db::insert_closed_month(closed_month, self)
+    }
+
+    fn load_exports(&mut self, limit: i64) -> Vec<Export> {
+        db::load_exports(limit, self)
+    }
+
+    fn insert_export(&mut self, new_export: NewExport) -> QueryResult<usize> {
+        db::insert_export(new_export, self)
+    }
+
+    fn backup(&mut self, path: &Path) -> QueryResult<()> {
+        maintenance::backup(path, self)
+    }
+}
@@ -1,10 +1,21 @@
-use iced::{container, text_input, Color, TextInput};
+use iced::{button, container, text_input, Color, TextInput};
 
 pub struct LogviewStyle;
 pub struct TabContentStyle;
+/// Text inputs with a clearly visible outline when focused, so the app can be used with a keyboard alone.
 pub struct TextInputStyle;
 pub struct ManagementRow1;
 pub struct ManagementRow2;
+pub struct NameButtonStyle;
+/// Background of a day in the calendar of a staff member.
+pub struct CalendarDayStyle(pub Color);
+/// Bar in the chart of the hours per staff member.
+pub struct ChartBarStyle(pub Color);
+/// Hour in the preview of the evaluation buckets, the hour of the cutoff time is outlined.
+pub struct TimelineHourStyle {
+    pub color: Color,
+    pub is_cutoff: bool,
+}
 
 impl container::StyleSheet for LogviewStyle {
     fn style(&self) -> container::Style {
@@ -40,7 +51,50 @@ where
     F: 'a + Fn(String) -> M,
     M: Clone,
 {
-    TextInput::new(state, placeholder, value, f).padding(5)
+    TextInput::new(state, placeholder, value, f)
+        .padding(5)
+        .style(TextInputStyle)
+}
+
+/// Outline of the focused element.
+pub const FOCUS_COLOR: Color = Color::from_rgb(0.0, 0.4, 0.9);
+
+impl text_input::StyleSheet for TextInputStyle {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Color::WHITE.into(),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: Color::from_rgb8(150, 150, 150),
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_width: 3.0,
+            border_color: FOCUS_COLOR,
+            ..self.active()
+        }
+    }
+
+    fn hovered(&self) -> text_input::Style {
+        text_input::Style {
+            border_color: Color::BLACK,
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        Color::from_rgb8(150, 150, 150)
+    }
+
+    fn value_color(&self) -> Color {
+        Color::BLACK
+    }
+
+    fn selection_color(&self) -> Color {
+        Color::from_rgb8(170, 200, 240)
+    }
 }
 
 impl container::StyleSheet for ManagementRow1 {
@@ -61,6 +115,46 @@ impl container::StyleSheet for ManagementRow2 {
     }
 }
 
+/// Names on the dashboard are buttons for long presses but should look like plain text.
+impl button::StyleSheet for NameButtonStyle {
+    fn active(&self) -> button::Style {
+        button::Style::default()
+    }
+}
+
+impl container::StyleSheet for CalendarDayStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: Some(self.0.into()),
+            border_radius: 3.0,
+            border_width: 1.0,
+            border_color: Color::from_rgb8(200, 200, 200),
+            ..container::Style::default()
+        }
+    }
+}
+
+impl container::StyleSheet for ChartBarStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: Some(self.0.into()),
+            border_radius: 3.0,
+            ..container::Style::default()
+        }
+    }
+}
+
+impl container::StyleSheet for TimelineHourStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: Some(self.color.into()),
+            border_width: if self.is_cutoff { 3.0 } else { 0.0 },
+            border_color: Color::BLACK,
+            ..container::Style::default()
+        }
+    }
+}
+
 pub fn management_row(even: &mut bool) -> Box<dyn container::StyleSheet> {
     let result: Box<dyn container::StyleSheet> = if *even {
         Box::new(ManagementRow1)
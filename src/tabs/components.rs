@@ -0,0 +1,357 @@
+//! Widgets that are shared between the tabs.
+use std::ops::Range;
+
+use iced::{
+    alignment::Horizontal, button, scrollable, text_input, Alignment, Button, Column, Element,
+    Length, Row, Scrollable, Space, Text,
+};
+
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890ß", "qwertzuiopü", "asdfghjklöä", "yxcvbnm@-."];
+const KEYBOARD_ROWS_SHIFTED: [&str; 4] =
+    ["!\"§$%&/()=?", "QWERTZUIOPÜ", "ASDFGHJKLÖÄ", "YXCVBNM@_:"];
+const KEY_WIDTH: u16 = 50;
+/// Rows of a `VirtualList` that are built above and below the ones that can be visible, so that scrolling
+/// does not show empty space before the next frame.
+const VIRTUAL_LIST_MARGIN: usize = 10;
+
+/// Button with a centered label as used in the foot of modals.
+pub fn modal_button<'a, M: Clone + 'a>(state: &'a mut button::State, label: &str) -> Button<'a, M> {
+    Button::new(
+        state,
+        Text::new(label).horizontal_alignment(Horizontal::Center),
+    )
+    .width(Length::Shrink)
+}
+
+/// Foot of a modal with a confirm and a cancel button.
+/// The confirm button is disabled if there is no confirm message.
+pub fn confirm_foot<'a, M: Clone + 'a>(
+    confirm_state: &'a mut button::State,
+    confirm_label: &str,
+    confirm: Option<M>,
+    cancel_state: &'a mut button::State,
+    cancel_label: &str,
+    cancel: M,
+) -> Row<'a, M> {
+    let mut confirm_button = modal_button(confirm_state, confirm_label);
+    if let Some(confirm) = confirm {
+        confirm_button = confirm_button.on_press(confirm);
+    }
+
+    Row::new()
+        .spacing(10)
+        .padding(5)
+        .width(Length::Fill)
+        .push(confirm_button)
+        .push(modal_button(cancel_state, cancel_label).on_press(cancel))
+}
+
+/// Move the focus to the next text input, or to the previous one if `backwards` is set.
+/// iced does not provide a tab order, so each tab lists its inputs in the order in which they are shown.
+/// If no input is focused yet, the first (or last) one gets the focus.
+pub fn cycle_focus(inputs: &mut [&mut text_input::State], backwards: bool) {
+    let len = inputs.len();
+    if len == 0 {
+        return;
+    }
+
+    let new_idx = match inputs.iter().position(|input| input.is_focused()) {
+        Some(idx) => {
+            inputs[idx].unfocus();
+            if backwards {
+                (idx + len - 1) % len
+            } else {
+                (idx + 1) % len
+            }
+        }
+        None if backwards => len - 1,
+        None => 0,
+    };
+    inputs[new_idx].focus();
+}
+
+/// Key of the on-screen keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnScreenKey {
+    Char(char),
+    Shift,
+    Backspace,
+    Enter,
+}
+
+/// Keyboard on the screen for terminals that only have a touchscreen.
+/// The tab owning the input forwards the pressed keys to `press` together with the value of the input, or to
+/// `press_input` if the keyboard types into whichever of several inputs has the focus.
+#[derive(Default)]
+pub struct OnScreenKeyboard {
+    /* shift only applies to the next key, like on a phone */
+    shifted: bool,
+    /* index of the input that had the focus, see `track_focus` */
+    target: Option<usize>,
+    char_states: Vec<button::State>,
+    shift_state: button::State,
+    backspace_state: button::State,
+    enter_state: button::State,
+}
+
+impl OnScreenKeyboard {
+    /// Apply the key to the value. Returns true if the value should be submitted.
+    pub fn press(&mut self, key: OnScreenKey, value: &mut String) -> bool {
+        match key {
+            OnScreenKey::Char(c) => {
+                value.push(c);
+                self.shifted = false;
+            }
+            OnScreenKey::Shift => self.shifted = !self.shifted,
+            OnScreenKey::Backspace => {
+                value.pop();
+            }
+            OnScreenKey::Enter => {
+                self.shifted = false;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Remember which of the tab's inputs has the focus, by its index in the list of inputs that the tab passes to
+    /// `press_input`. Pressing a key of the keyboard takes the focus away from the input, so the tab calls this
+    /// while building its view.
+    pub fn track_focus(&mut self, focused: Option<usize>) {
+        self.target = focused;
+    }
+
+    /// Apply the key to the input that had the focus, see `track_focus`, and give the focus back to it.
+    /// Returns true if the value should be submitted.
+    pub fn press_input(
+        &mut self,
+        key: OnScreenKey,
+        inputs: Vec<(&mut text_input::State, &mut String)>,
+    ) -> bool {
+        match self.target.and_then(|idx| inputs.into_iter().nth(idx)) {
+            Some((state, value)) => {
+                let submit = self.press(key, value);
+                state.focus();
+                state.move_cursor_to_end();
+                submit
+            }
+            None => false,
+        }
+    }
+
+    pub fn view<'a, M: Clone + 'a>(
+        &'a mut self,
+        on_key: impl Fn(OnScreenKey) -> M,
+    ) -> Element<'a, M> {
+        let rows = if self.shifted {
+            KEYBOARD_ROWS_SHIFTED
+        } else {
+            KEYBOARD_ROWS
+        };
+        let key_count = rows.iter().map(|row| row.chars().count()).sum();
+        self.char_states
+            .resize_with(key_count, button::State::default);
+        let mut char_states = self.char_states.iter_mut();
+
+        let mut keyboard = Column::new().spacing(5).align_items(Alignment::Center);
+        for row in rows {
+            let keys = row.chars().zip(&mut char_states).fold(
+                Row::new().spacing(5),
+                |keys, (c, state)| {
+                    keys.push(
+                        modal_button(state, &c.to_string())
+                            .width(Length::Units(KEY_WIDTH))
+                            .on_press(on_key(OnScreenKey::Char(c))),
+                    )
+                },
+            );
+            keyboard = keyboard.push(keys);
+        }
+
+        keyboard
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(
+                        modal_button(&mut self.shift_state, "Umschalt")
+                            .width(Length::Units(2 * KEY_WIDTH))
+                            .on_press(on_key(OnScreenKey::Shift)),
+                    )
+                    .push(
+                        modal_button(&mut self.backspace_state, "Löschen")
+                            .width(Length::Units(2 * KEY_WIDTH))
+                            .on_press(on_key(OnScreenKey::Backspace)),
+                    )
+                    .push(
+                        modal_button(&mut self.enter_state, "Enter")
+                            .width(Length::Units(2 * KEY_WIDTH))
+                            .on_press(on_key(OnScreenKey::Enter)),
+                    ),
+            )
+            .into()
+    }
+}
+
+/// A list in a scrollable that only builds the rows that can be visible and puts empty space of the same height in
+/// place of the others, since building hundreds of rows for each frame is too slow on the kiosk.
+/// All rows must have the same height, which includes the spacing between them.
+#[derive(Debug, Clone, Copy)]
+pub struct VirtualList {
+    row_height: u16,
+    /* relative offset of the scrollable, 0 is the top and 1 the bottom */
+    offset: f32,
+}
+
+impl VirtualList {
+    pub fn new(row_height: u16) -> Self {
+        VirtualList {
+            row_height,
+            offset: 0.0,
+        }
+    }
+
+    pub fn row_height(&self) -> u16 {
+        self.row_height
+    }
+
+    /// Remember the offset reported by `Scrollable::on_scroll`.
+    pub fn scrolled(&mut self, offset: f32) {
+        self.offset = offset.clamp(0.0, 1.0);
+    }
+
+    /// Scroll to the given relative offset, e.g. 1 to follow new rows at the bottom.
+    pub fn snap_to(&mut self, state: &mut scrollable::State, offset: f32) {
+        state.snap_to(offset);
+        self.scrolled(offset);
+    }
+
+    /// Rows that can be visible in a scrollable that is at most `viewport_height` high.
+    /// The relative offset refers to the height of the content minus the height of the scrollable, which we do
+    /// not know exactly, so we take the rows that can be visible for any height up to `viewport_height`.
+    pub fn visible_rows(&self, rows: usize, viewport_height: u32) -> Range<usize> {
+        let row_height = f32::from(self.row_height);
+        let content_height = rows as f32 * row_height;
+        let viewport_height = viewport_height as f32;
+        let top = self.offset * (content_height - viewport_height).max(0.0);
+        let bottom = self.offset * content_height + (1.0 - self.offset) * viewport_height;
+
+        let first = (top / row_height) as usize;
+        let last = (bottom / row_height).ceil() as usize;
+        first.saturating_sub(VIRTUAL_LIST_MARGIN)..(last + VIRTUAL_LIST_MARGIN).min(rows)
+    }
+
+    /// Push the rows that were built for `visible` into the scrollable, and space in place of the other rows.
+    pub fn push_rows<'a, M: 'a>(
+        &self,
+        scrollable: Scrollable<'a, M>,
+        rows: usize,
+        visible: Range<usize>,
+        built: impl IntoIterator<Item = Element<'a, M>>,
+    ) -> Scrollable<'a, M> {
+        let scrollable = self.push_space(scrollable, visible.start);
+        let scrollable = built
+            .into_iter()
+            .fold(scrollable, |scrollable, row| scrollable.push(row));
+        self.push_space(scrollable, rows.saturating_sub(visible.end))
+    }
+
+    fn push_space<'a, M: 'a>(
+        &self,
+        mut scrollable: Scrollable<'a, M>,
+        rows: usize,
+    ) -> Scrollable<'a, M> {
+        // The height of a Space is limited to u16.
+        let mut height = rows as u64 * u64::from(self.row_height);
+        while height > 0 {
+            let chunk = height.min(u64::from(u16::MAX));
+            scrollable = scrollable.push(Space::new(Length::Fill, Length::Units(chunk as u16)));
+            height -= chunk;
+        }
+        scrollable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OnScreenKey, OnScreenKeyboard, VirtualList, VIRTUAL_LIST_MARGIN};
+    use iced::text_input;
+
+    #[test]
+    fn on_screen_keyboard_input() {
+        let mut keyboard = OnScreenKeyboard::default();
+        let mut value = String::new();
+
+        for key in [
+            OnScreenKey::Char('a'),
+            OnScreenKey::Char('b'),
+            OnScreenKey::Backspace,
+            OnScreenKey::Char('1'),
+        ] {
+            assert!(!keyboard.press(key, &mut value));
+        }
+        assert!(keyboard.press(OnScreenKey::Enter, &mut value));
+        assert_eq!(value, "a1");
+
+        // Shift only applies to the next key.
+        value.clear();
+        for key in [
+            OnScreenKey::Shift,
+            OnScreenKey::Char('Ü'),
+            OnScreenKey::Char('ß'),
+            OnScreenKey::Char('@'),
+        ] {
+            keyboard.press(key, &mut value);
+            assert_eq!(keyboard.shifted, key == OnScreenKey::Shift);
+        }
+        assert_eq!(value, "Üß@");
+    }
+
+    #[test]
+    fn on_screen_keyboard_focused_input() {
+        let mut keyboard = OnScreenKeyboard::default();
+        let mut states = [text_input::State::default(), text_input::State::focused()];
+        let mut values = [String::from("Aaron"), String::from("Beeron")];
+        let focused = states.iter().position(text_input::State::is_focused);
+        keyboard.track_focus(focused);
+        // Pressing a key of the keyboard takes the focus away.
+        states[1].unfocus();
+
+        assert!(!keyboard.press_input(
+            OnScreenKey::Backspace,
+            states.iter_mut().zip(values.iter_mut()).collect()
+        ));
+        assert!(keyboard.press_input(
+            OnScreenKey::Enter,
+            states.iter_mut().zip(values.iter_mut()).collect()
+        ));
+        assert_eq!(values, ["Aaron", "Beero"]);
+        assert!(states[1].is_focused());
+
+        // Nothing had the focus, so the keys go nowhere.
+        keyboard.track_focus(None);
+        assert!(!keyboard.press_input(
+            OnScreenKey::Char('x'),
+            states.iter_mut().zip(values.iter_mut()).collect()
+        ));
+        assert_eq!(values, ["Aaron", "Beero"]);
+    }
+
+    #[test]
+    fn virtual_list_rows() {
+        let mut list = VirtualList::new(25);
+        // Everything fits, so everything is built.
+        assert_eq!(list.visible_rows(10, 1000), 0..10);
+
+        // 1000 rows of 25 pixels in a scrollable of at most 500 pixels, i.e. 20 rows.
+        assert_eq!(list.visible_rows(1000, 500), 0..20 + VIRTUAL_LIST_MARGIN);
+        list.scrolled(1.0);
+        assert_eq!(
+            list.visible_rows(1000, 500),
+            980 - VIRTUAL_LIST_MARGIN..1000
+        );
+        list.scrolled(0.5);
+        let visible = list.visible_rows(1000, 500);
+        assert!(visible.start <= 490 && visible.end >= 510);
+        assert!(visible.len() <= 20 + 2 * 10 + 2 * VIRTUAL_LIST_MARGIN);
+    }
+}
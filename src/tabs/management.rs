@@ -1,219 +1,42 @@
 //! Tab to add/change/get info about users
+mod announcement;
+mod card_diagnostics;
+mod shift_import;
+mod staff_archive;
+mod staff_calendar;
+mod staff_list;
+mod view;
+
 use std::{error, fmt, mem};
 
-use iced::{
-    alignment::{Horizontal, Vertical},
-    button, keyboard, scrollable, text_input, Alignment, Button, Checkbox, Column, Container,
-    Element, Length, Row, Scrollable, Space, Text, TextInput,
-};
-use iced_aw::{modal, Card, Modal, TabLabel};
+use iced::{button, keyboard, scrollable, text_input, Container, Element};
+use iced_aw::{modal, TabLabel};
 use iced_native::Event;
-use stechuhr::{
-    db,
-    icons::{self, TEXT_SIZE_EMOJI},
-    models::*,
-};
+use stechuhr::{db, models::*};
 
+use crate::tabs::components::{self, OnScreenKey, OnScreenKeyboard, VirtualList};
 use crate::{Message, SharedData, StechuhrError, Tab, TAB_PADDING};
-
-struct StaffMemberState {
-    name_state: text_input::State,
-    name_value: String,
-    pin_state: text_input::State,
-    pin_value: String,
-    cardid_state: text_input::State,
-    cardid_value: String,
+use announcement::AnnouncementEditor;
+pub use announcement::AnnouncementMessage;
+use shift_import::ShiftImport;
+pub use shift_import::ShiftImportMessage;
+use staff_calendar::StaffCalendar;
+pub use staff_calendar::StaffCalendarMessage;
+use staff_list::StaffList;
+pub use staff_list::{StaffField, StaffListMessage};
+
+/// Number of text inputs in each row of the staff list.
+const INPUTS_PER_ROW: usize = StaffField::ALL.len();
+/// Height of each row of the staff list, see `VirtualList`.
+const STAFF_ROW_HEIGHT: u16 = 50;
+
+/// Widget states of a row of the staff list, the values of its inputs are in `StaffList`.
+#[derive(Default)]
+struct StaffRowWidgets {
+    input_states: [text_input::State; INPUTS_PER_ROW],
     submit_state: button::State,
-    #[allow(unused)]
+    calendar_state: button::State,
     delete_state: button::State,
-
-    is_visible: bool,
-}
-
-impl StaffMemberState {
-    fn with_name(mut self, name: &String) -> Self {
-        self.name_value.clone_from(name);
-        self
-    }
-
-    fn with_pin(mut self, pin: &String) -> Self {
-        self.pin_value.clone_from(pin);
-        self
-    }
-
-    fn with_cardid(mut self, cardid: &String) -> Self {
-        self.cardid_value.clone_from(cardid);
-        self
-    }
-
-    fn with_visible(mut self, is_visible: bool) -> Self {
-        self.is_visible = is_visible;
-        self
-    }
-}
-
-impl Default for StaffMemberState {
-    fn default() -> Self {
-        Self {
-            name_state: text_input::State::default(),
-            name_value: String::default(),
-            pin_state: text_input::State::default(),
-            pin_value: String::default(),
-            cardid_state: text_input::State::default(),
-            cardid_value: String::default(),
-            submit_state: button::State::default(),
-            delete_state: button::State::default(),
-            is_visible: true,
-        }
-    }
-}
-
-/// Abstracts over the vector of staff members and the vector of their UI elements.
-struct StaffState {
-    member_states: Vec<StaffMemberState>,
-}
-
-impl From<&[StaffMember]> for StaffState {
-    fn from(staff: &[StaffMember]) -> Self {
-        let member_states = staff
-            .iter()
-            .map(|staff_member| {
-                StaffMemberState::default()
-                    .with_name(&staff_member.name)
-                    .with_pin(&staff_member.pin)
-                    .with_cardid(&staff_member.cardid)
-                    .with_visible(staff_member.is_visible)
-            })
-            .collect();
-
-        StaffState::new(member_states)
-    }
-}
-
-impl StaffState {
-    fn new(member_states: Vec<StaffMemberState>) -> Self {
-        StaffState { member_states }
-    }
-
-    fn change_name_state(&mut self, idx: usize, new_name: String) -> Result<(), StechuhrError> {
-        let state = self
-            .member_states
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-        state.name_value = new_name;
-        Ok(())
-    }
-
-    fn change_pin_state(&mut self, idx: usize, new_pin: String) -> Result<(), StechuhrError> {
-        let state = self
-            .member_states
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-        state.pin_value = new_pin;
-        Ok(())
-    }
-
-    fn change_cardid_state(&mut self, idx: usize, new_cardid: String) -> Result<(), StechuhrError> {
-        let state = self
-            .member_states
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-        state.cardid_value = new_cardid;
-        Ok(())
-    }
-
-    fn submit(&mut self, shared: &mut SharedData, idx: usize) -> Result<(), StechuhrError> {
-        let state = self
-            .member_states
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-        let staff_member = shared
-            .staff
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-
-        let name = &state.name_value;
-        let pin = &state.pin_value;
-        let cardid = &state.cardid_value;
-        let is_visible = state.is_visible;
-
-        // use same validation as in submit_new_row
-        NewStaffMember::validate(name, pin, cardid)?;
-        staff_member.name.clone_from(name);
-        staff_member.pin.clone_from(pin);
-        staff_member.cardid.clone_from(cardid);
-        staff_member.is_visible = is_visible;
-
-        // save in db
-        db::save_staff_member(staff_member, &mut shared.connection)?;
-
-        let success_message = format!("Mitarbeiter {} erfolgreich geändert.", name);
-        shared.log_info(success_message);
-
-        Ok(())
-    }
-
-    fn submit_new_row(
-        &mut self,
-        shared: &mut SharedData,
-        new_name: String,
-        new_pin: String,
-        new_cardid: String,
-    ) -> Result<(), StechuhrError> {
-        // save in DB
-        let new_staff_member = NewStaffMember::new(new_name, new_pin, new_cardid)?;
-        let new_staff_member = db::insert_staff(new_staff_member, &mut shared.connection)?;
-
-        self.member_states.push(
-            StaffMemberState::default()
-                .with_name(&new_staff_member.name)
-                .with_pin(&new_staff_member.pin)
-                .with_cardid(&new_staff_member.cardid),
-        );
-
-        let success_message = format!(
-            "Neuer Mitarbeiter {} erfolgreich hinzugefügt.",
-            new_staff_member.name
-        );
-        shared.log_info(success_message);
-
-        shared.staff.push(new_staff_member);
-
-        Ok(())
-    }
-
-    fn delete_row(&mut self, shared: &mut SharedData, idx: usize) -> Result<(), StechuhrError> {
-        if idx >= self.member_states.len() {
-            return Err(ManagementError::IndexError(idx).into());
-        }
-        self.member_states.remove(idx);
-        let staff_member = shared.staff.remove(idx);
-
-        db::delete_staff_member(staff_member, &mut shared.connection)?;
-
-        Ok(())
-    }
-
-    fn toggle_visible(
-        &mut self,
-        shared: &mut SharedData,
-        idx: usize,
-        is_visible: bool,
-    ) -> Result<(), StechuhrError> {
-        let state = self
-            .member_states
-            .get_mut(idx)
-            .ok_or(ManagementError::IndexError(idx))?;
-        state.is_visible = is_visible;
-
-        self.submit(shared, idx)?;
-        Ok(())
-    }
-
-    // fn delete(&mut self, idx: usize) {
-    //     self.states.remove(idx);
-    //     self.staff.remove(idx);
-    // }
 }
 
 pub struct ManagementTab {
@@ -223,20 +46,29 @@ pub struct ManagementTab {
     authorized: bool,
     admin_password_value: String,
     admin_password_state: text_input::State,
+    keyboard: OnScreenKeyboard,
     /* management of staff */
     staff_scroll_state: scrollable::State,
-    staff_state: StaffState,
+    staff_rows: VirtualList,
+    staff_list: StaffList,
+    row_widgets: Vec<StaffRowWidgets>,
     /* adding new staff */
-    new_name_state: text_input::State,
-    new_name_value: String,
-    new_pin_state: text_input::State,
-    new_pin_value: String,
-    new_cardid_state: text_input::State,
-    new_cardid_value: String,
+    new_input_states: [text_input::State; INPUTS_PER_ROW],
     new_submit_state: button::State,
 
     delete_modal_state: modal::State<DeleteModalState>,
     delete_idx: Option<usize>,
+    conflict_modal_state: modal::State<ConflictModalState>,
+    /* importing the shift plan */
+    shift_import: ShiftImport,
+    /* hours per day of a single staff member */
+    calendar: StaffCalendar,
+    /* free text markers in the event log */
+    marker_value: String,
+    marker_state: text_input::State,
+    marker_submit_state: button::State,
+    /* announcement on the Timetrack tab */
+    announcement: AnnouncementEditor,
 }
 
 #[derive(Default)]
@@ -245,6 +77,13 @@ struct DeleteModalState {
     delete_cancel_state: button::State,
 }
 
+#[derive(Default)]
+struct ConflictModalState {
+    overwrite_state: button::State,
+    discard_state: button::State,
+    cancel_state: button::State,
+}
+
 #[derive(Debug, Default)]
 struct WhoamiModalState {
     input_value: String,
@@ -260,21 +99,35 @@ pub enum ManagementMessage {
     /* Pre Login */
     ChangePasswordInput(String),
     SubmitPassword,
+    PasswordKey(OnScreenKey),
     /* After Login */
-    ChangeName(usize, String),
-    ChangePIN(usize, String),
-    ChangeCardID(usize, String),
-    SubmitRow(usize),
-    ToggleVisible(usize, bool),
+    InputKey(OnScreenKey),
+    Staff(StaffListMessage),
     DeleteRow(usize),
     ConfirmDeleteRow,
     CancelDeleteRow,
-    ChangeNewRow(Option<String>, Option<String>, Option<String>),
-    SubmitNewRow,
+    OverwriteConflict,
+    DiscardConflict,
+    CancelConflict,
     GenericSubmit,
+    ShiftImport(ShiftImportMessage),
+    Calendar(StaffCalendarMessage),
+    StaffScrolled(f32),
+    ChangeMarker(String),
+    SubmitMarker,
+    Announcement(AnnouncementMessage),
     HandleEvent(Event),
 }
 
+/// Changes of staff members made outside of the management tab, e.g. on the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManagementRequest {
+    /// Show or hide the staff member on the dashboard, in the same way as the checkbox in the management tab.
+    SetVisible(i32, bool),
+    /// Show the saved data of the staff member, e.g. after they changed their own PIN.
+    ReloadRow(i32),
+}
+
 impl ManagementTab {
     fn auth(&mut self) {
         self.authorized = true;
@@ -286,7 +139,8 @@ impl ManagementTab {
 
     pub fn new(staff: &[StaffMember]) -> Self {
         let mut staff_scroll_state = scrollable::State::default();
-        staff_scroll_state.snap_to(1.0);
+        let mut staff_rows = VirtualList::new(STAFF_ROW_HEIGHT);
+        staff_rows.snap_to(&mut staff_scroll_state, 1.0);
 
         ManagementTab {
             whoami_modal_state: modal::State::default(),
@@ -294,301 +148,99 @@ impl ManagementTab {
             authorized: false,
             admin_password_value: String::from(""),
             admin_password_state: text_input::State::default(),
-            staff_state: StaffState::from(staff),
+            keyboard: OnScreenKeyboard::default(),
+            staff_list: StaffList::new(staff),
+            row_widgets: staff.iter().map(|_| StaffRowWidgets::default()).collect(),
             staff_scroll_state,
+            staff_rows,
 
-            new_name_state: text_input::State::default(),
-            new_name_value: String::from(""),
-            new_pin_state: text_input::State::default(),
-            new_pin_value: String::from(""),
-            new_cardid_state: text_input::State::default(),
-            new_cardid_value: String::from(""),
+            new_input_states: Default::default(),
             new_submit_state: button::State::default(),
 
             delete_modal_state: modal::State::default(),
             delete_idx: None,
+            conflict_modal_state: modal::State::default(),
+
+            shift_import: ShiftImport::new(),
+            calendar: StaffCalendar::new(),
+            marker_value: String::new(),
+            marker_state: text_input::State::default(),
+            marker_submit_state: button::State::default(),
+            announcement: AnnouncementEditor::new(),
         }
     }
 
-    fn submit_new_row(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
-        self.staff_state.submit_new_row(
-            shared,
-            self.new_name_value.clone(),
-            self.new_pin_value.clone(),
-            self.new_cardid_value.clone(),
-        )?;
-
-        self.new_name_value.clear();
-        self.new_pin_value.clear();
-        self.new_cardid_value.clear();
-
-        self.staff_scroll_state.snap_to(1.0);
-
-        Ok(())
-    }
-}
-
-impl ManagementTab {
-    fn text_input<'a, F>(
-        state: &'a mut text_input::State,
-        placeholder: &str,
-        value: &str,
-        f: F,
-    ) -> TextInput<'a, ManagementMessage>
-    where
-        F: 'a + Fn(String) -> ManagementMessage,
-    {
-        stechuhr::style::text_input(state, placeholder, value, f)
-            .on_submit(ManagementMessage::GenericSubmit)
-            .width(Length::FillPortion(3))
-    }
-
-    fn internal_view(&mut self, shared: &mut SharedData) -> Element<'_, ManagementMessage> {
-        const SPACING: u16 = 1;
-        let mut staff_edit = Scrollable::new(&mut self.staff_scroll_state);
-        let mut even = true;
-
-        for (idx, member_state) in self.staff_state.member_states.iter_mut().enumerate() {
-            let staff_row = Container::new(
-                Row::new()
-                    .push(
-                        ManagementTab::text_input(
-                            &mut member_state.name_state,
-                            "Name eingeben",
-                            &member_state.name_value.clone(),
-                            move |s| ManagementMessage::ChangeName(idx, s),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(SPACING), Length::Shrink))
-                    .push(
-                        ManagementTab::text_input(
-                            &mut member_state.pin_state,
-                            "PIN eingeben",
-                            &member_state.pin_value.clone(),
-                            move |s| ManagementMessage::ChangePIN(idx, s),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(SPACING), Length::Shrink))
-                    .push(
-                        ManagementTab::text_input(
-                            &mut member_state.cardid_state,
-                            "Dongle swipen",
-                            &member_state.cardid_value.clone(),
-                            move |s| ManagementMessage::ChangeCardID(idx, s),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(5), Length::Shrink))
-                    .push(
-                        Checkbox::new(
-                            member_state.is_visible,
-                            icons::emoji::eye.codepoint,
-                            move |b| ManagementMessage::ToggleVisible(idx, b),
-                        )
-                        .font(icons::FONT_SYMBOLA)
-                        .text_size(TEXT_SIZE_EMOJI)
-                        .width(Length::FillPortion(8)),
-                    )
-                    .push(
-                        Button::new(
-                            &mut member_state.delete_state,
-                            icons::icon(icons::emoji::trashcan),
-                        )
-                        .on_press(ManagementMessage::DeleteRow(idx))
-                        .width(Length::FillPortion(5)),
-                    )
-                    .push(
-                        Button::new(
-                            &mut member_state.submit_state,
-                            icons::icon(icons::emoji::floppydisk),
-                        )
-                        .on_press(ManagementMessage::SubmitRow(idx))
-                        .width(Length::FillPortion(5)),
-                    )
-                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
-            )
-            .style(stechuhr::style::management_row(&mut even));
-            staff_edit = staff_edit.push(staff_row);
+    /// Handle a request of another tab, see `TabRequest`.
+    pub fn handle_request(&mut self, shared: &mut SharedData, request: ManagementRequest) {
+        match request {
+            ManagementRequest::SetVisible(uuid, is_visible) => {
+                let row = shared
+                    .staff
+                    .iter()
+                    .position(|staff_member| staff_member.uuid() == uuid);
+                if let Some(idx) = row {
+                    self.update(
+                        shared,
+                        ManagementMessage::Staff(StaffListMessage::ToggleVisible(idx, is_visible)),
+                    );
+                }
+            }
+            ManagementRequest::ReloadRow(uuid) => self.staff_list.reload_row(shared, uuid),
         }
+    }
 
-        // last inputs for new staff member
-        {
-            let new_row = Container::new(
-                Row::new()
-                    .push(
-                        ManagementTab::text_input(
-                            &mut self.new_name_state,
-                            "Name eingeben",
-                            &self.new_name_value,
-                            |s| ManagementMessage::ChangeNewRow(Some(s), None, None),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(SPACING), Length::Shrink))
-                    .push(
-                        ManagementTab::text_input(
-                            &mut self.new_pin_state,
-                            "PIN eingeben",
-                            &self.new_pin_value,
-                            |s| ManagementMessage::ChangeNewRow(None, Some(s), None),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(SPACING), Length::Shrink))
-                    .push(
-                        ManagementTab::text_input(
-                            &mut self.new_cardid_state,
-                            "click & swipe RFID dongle",
-                            &self.new_cardid_value,
-                            move |s| ManagementMessage::ChangeNewRow(None, None, Some(s)),
-                        )
-                        .width(Length::FillPortion(25)),
-                    )
-                    .push(Space::new(Length::FillPortion(5), Length::Shrink))
-                    .push(Space::new(Length::FillPortion(13), Length::Shrink))
-                    .push(
-                        Button::new(
-                            &mut self.new_submit_state,
-                            icons::icon(icons::emoji::floppydisk),
-                        )
-                        .on_press(ManagementMessage::SubmitNewRow)
-                        .width(Length::FillPortion(5)),
-                    )
-                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
-            )
-            .style(stechuhr::style::management_row(&mut even));
-            staff_edit = staff_edit.push(new_row);
+    /// Scroll the staff list so that the row with the given index is built, the new row comes after the last one.
+    fn scroll_to_row(&mut self, shared: &SharedData, idx: usize) {
+        let rows = self.staff_list.rows().len();
+        let visible = self.staff_rows.visible_rows(rows, shared.window_height);
+        if idx >= rows {
+            self.staff_rows.snap_to(&mut self.staff_scroll_state, 1.0);
+        } else if !visible.contains(&idx) {
+            self.staff_rows
+                .snap_to(&mut self.staff_scroll_state, idx as f32 / rows as f32);
         }
-
-        let content = Container::new(staff_edit)
-            .width(Length::Fill)
-            .height(Length::FillPortion(90))
-            .center_x()
-            .align_y(Vertical::Top);
-
-        let delete_modal_value = if let Some(delete_idx) = self.delete_idx {
-            if let Some(staff_member) = shared.staff.get(delete_idx) {
-                format!("{} wird gelöscht. Korrekt?", staff_member.name,)
-            } else {
-                String::from("Warnung: das solltest du nicht sehen. Bitte Adrian Bescheid geben.")
-            }
-        } else {
-            String::from("Warnung: das solltest du nicht sehen. Bitte Adrian Bescheid geben.")
-        };
-
-        let modal = Modal::new(&mut self.delete_modal_state, content, move |state| {
-            Card::new(
-                Text::new("Löschen eines Mitarbeiters"),
-                Text::new(&delete_modal_value),
-            )
-            .foot(
-                Row::new()
-                    .spacing(10)
-                    .padding(5)
-                    .width(Length::Fill)
-                    .push(
-                        Button::new(
-                            &mut state.delete_confirm_state,
-                            Text::new("Ok").horizontal_alignment(Horizontal::Center),
-                        )
-                        .width(Length::Shrink)
-                        .on_press(ManagementMessage::ConfirmDeleteRow),
-                    )
-                    .push(
-                        Button::new(
-                            &mut state.delete_cancel_state,
-                            Text::new("Zurück").horizontal_alignment(Horizontal::Center),
-                        )
-                        .width(Length::Shrink)
-                        .on_press(ManagementMessage::CancelDeleteRow),
-                    ),
-            )
-            .width(Length::Shrink)
-            .on_close(ManagementMessage::CancelDeleteRow)
-            .into()
-        })
-        .backdrop(ManagementMessage::CancelDeleteRow)
-        .on_esc(ManagementMessage::CancelDeleteRow);
-
-        modal.into()
     }
 
-    fn public_view(&mut self, shared: &mut SharedData) -> Element<'_, ManagementMessage> {
-        if shared.prompt_modal_state.is_shown() {
-            self.admin_password_state.unfocus();
-        }
+    fn show_conflict(&mut self) {
+        self.conflict_modal_state
+            .show(self.staff_list.conflict().is_some());
+    }
 
-        let content = Column::new()
-            .push(Space::new(Length::Fill, Length::Units(100)))
-            .push(
-                Row::new()
-                    .push(Space::new(Length::FillPortion(2), Length::Shrink))
-                    .push(
-                        stechuhr::style::text_input(
-                            &mut self.admin_password_state,
-                            "Administrator Passwort",
-                            &self.admin_password_value,
-                            ManagementMessage::ChangePasswordInput,
-                        )
-                        .password()
-                        .on_submit(ManagementMessage::SubmitPassword)
-                        .width(Length::FillPortion(3)),
-                    )
-                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
-            )
-            .push(
-                Button::new(
-                    &mut self.whoami_button_state,
-                    Text::new("Wem gehört dieser Dongle?").horizontal_alignment(Horizontal::Center),
-                )
-                .on_press(ManagementMessage::Whoami),
-            )
-            // .padding(100)
-            .spacing(100)
-            .align_items(Alignment::Center);
-
-        let whoami_modal = Modal::new(&mut self.whoami_modal_state, content, move |state| {
-            Card::new(Text::new("Dongle Abfrage"), {
-                state.input_state.focus();
-                stechuhr::style::text_input(
-                    &mut state.input_state,
-                    "",
-                    &state.input_value,
-                    ManagementMessage::ChangeWhoami,
-                )
-                .on_submit(ManagementMessage::SubmitWhoami)
-            })
-            .max_width(300)
-            .width(Length::Fill)
-            .on_close(ManagementMessage::CancelWhoami)
-            .into()
-        })
-        .backdrop(ManagementMessage::CancelWhoami)
-        .on_esc(ManagementMessage::CancelWhoami);
-
-        whoami_modal.into()
+    /// Keep a widget state for each row, after rows were added or the staff list was reloaded.
+    fn resize_row_widgets(&mut self) {
+        self.row_widgets
+            .resize_with(self.staff_list.rows().len(), StaffRowWidgets::default);
     }
 
-    fn collect_inputs(&mut self) -> (Option<usize>, Vec<&mut text_input::State>) {
-        let mut inputs = Vec::with_capacity(3 * (self.staff_state.member_states.len()));
+    pub fn is_deleting(&self) -> bool {
+        self.delete_modal_state.is_shown()
+    }
 
-        for staff_member_state in &mut self.staff_state.member_states {
-            inputs.push(&mut staff_member_state.name_state);
-            inputs.push(&mut staff_member_state.pin_state);
-            inputs.push(&mut staff_member_state.cardid_state);
-        }
+    /// Rebuild the rows after the staff list was reloaded from the database, see `StaffList::staff_changed`.
+    pub fn staff_changed(
+        &mut self,
+        shared: &mut SharedData,
+        previous_staff: &[StaffMember],
+        changes: &db::StaffChanges,
+    ) {
+        self.staff_list
+            .staff_changed(shared, previous_staff, changes);
+        self.resize_row_widgets();
+    }
 
-        inputs.push(&mut self.new_name_state);
-        inputs.push(&mut self.new_pin_state);
-        inputs.push(&mut self.new_cardid_state);
+    /// Text inputs of the staff list with their values in the order in which they are shown, and the index of the
+    /// focused one. Used for moving the focus with the tab key and for the on-screen keyboard.
+    fn collect_inputs(&mut self) -> (Option<usize>, Vec<(&mut text_input::State, &mut String)>) {
+        self.resize_row_widgets();
+        let inputs = self
+            .row_widgets
+            .iter_mut()
+            .flat_map(|widgets| widgets.input_states.iter_mut())
+            .chain(self.new_input_states.iter_mut())
+            .zip(self.staff_list.values_mut())
+            .collect::<Vec<_>>();
 
-        let focus_idx =
-            inputs
-                .iter()
-                .enumerate()
-                .find_map(|(i, input)| if input.is_focused() { Some(i) } else { None });
+        let focus_idx = inputs.iter().position(|(input, _)| input.is_focused());
 
         (focus_idx, inputs)
     }
@@ -606,13 +258,17 @@ impl Tab for ManagementTab {
     }
 
     fn content(&mut self, shared: &mut SharedData) -> Element<'_, Message> {
-        let (_, inputs) = self.collect_inputs();
+        let (focus_idx, inputs) = self.collect_inputs();
         if shared.prompt_modal_state.is_shown() {
-            inputs.into_iter().for_each(|input| input.unfocus());
+            inputs.into_iter().for_each(|(input, _)| input.unfocus());
+            self.shift_import.unfocus();
+            self.marker_state.unfocus();
+            self.announcement.unfocus();
         }
 
         let content: Element<'_, ManagementMessage> = if self.authorized {
             self.admin_password_state.unfocus();
+            self.keyboard.track_focus(focus_idx);
 
             self.internal_view(shared)
         } else {
@@ -642,28 +298,41 @@ impl Tab for ManagementTab {
                 self.admin_password_value = password;
             }
             ManagementMessage::SubmitPassword => {
-                if db::verify_password(self.admin_password_value.trim(), &mut shared.connection) {
+                if shared
+                    .storage
+                    .verify_password(self.admin_password_value.trim())
+                {
                     self.admin_password_value.clear();
                     self.auth();
+                    self.announcement.load(shared.announcement.as_ref());
                 } else {
                     self.admin_password_value.clear();
                     return Err(ManagementError::InvalidPassword.into());
                 }
             }
-            ManagementMessage::ChangeName(idx, new_name) => {
-                self.staff_state.change_name_state(idx, new_name)?;
-            }
-            ManagementMessage::ChangePIN(idx, new_pin) => {
-                self.staff_state.change_pin_state(idx, new_pin)?;
-            }
-            ManagementMessage::ChangeCardID(idx, new_cardid) => {
-                self.staff_state.change_cardid_state(idx, new_cardid)?;
+            ManagementMessage::PasswordKey(key) => {
+                if self.keyboard.press(key, &mut self.admin_password_value) {
+                    return self.update_result(shared, ManagementMessage::SubmitPassword);
+                }
             }
-            ManagementMessage::SubmitRow(idx) => {
-                self.staff_state.submit(shared, idx)?;
+            ManagementMessage::InputKey(key) => {
+                // The keyboard is taken out of the tab while it types into one of the tab's inputs.
+                let mut keyboard = std::mem::take(&mut self.keyboard);
+                let (_, inputs) = self.collect_inputs();
+                let submit = keyboard.press_input(key, inputs);
+                self.keyboard = keyboard;
+                if submit {
+                    return self.update_result(shared, ManagementMessage::GenericSubmit);
+                }
             }
-            ManagementMessage::ToggleVisible(idx, b) => {
-                self.staff_state.toggle_visible(shared, idx, b)?;
+            ManagementMessage::Staff(staff_message) => {
+                let is_new_row = matches!(staff_message, StaffListMessage::SubmitNewRow);
+                self.staff_list.update(shared, staff_message)?;
+                if is_new_row {
+                    self.resize_row_widgets();
+                    self.staff_rows.snap_to(&mut self.staff_scroll_state, 1.0);
+                }
+                self.show_conflict();
             }
             ManagementMessage::DeleteRow(idx) => {
                 self.delete_idx = Some(idx);
@@ -673,27 +342,48 @@ impl Tab for ManagementTab {
                 self.delete_idx = None;
                 self.delete_modal_state.show(false);
             }
+            ManagementMessage::OverwriteConflict => {
+                self.staff_list.overwrite_conflict(shared)?;
+                // The staff member may have been changed yet again.
+                self.show_conflict();
+            }
+            ManagementMessage::DiscardConflict => {
+                self.conflict_modal_state.show(false);
+                self.staff_list.discard_conflict(shared);
+            }
+            ManagementMessage::CancelConflict => {
+                self.conflict_modal_state.show(false);
+                self.staff_list.cancel_conflict();
+            }
             ManagementMessage::ConfirmDeleteRow => {
                 if let Some(delete_idx) = self.delete_idx {
-                    self.staff_state.delete_row(shared, delete_idx)?;
+                    self.staff_list.delete_row(shared, delete_idx)?;
+                    self.row_widgets.remove(delete_idx);
 
                     self.delete_idx = None;
                     self.delete_modal_state.show(false);
                 }
             }
-            ManagementMessage::ChangeNewRow(name, pin, cardid) => {
-                if let Some(name) = name {
-                    self.new_name_value = name;
-                }
-                if let Some(pin) = pin {
-                    self.new_pin_value = pin;
-                }
-                if let Some(cardid) = cardid {
-                    self.new_cardid_value = cardid;
+            ManagementMessage::ShiftImport(message) => {
+                self.shift_import.update(shared, message)?;
+            }
+            ManagementMessage::Calendar(message) => {
+                self.calendar.update(shared, message)?;
+            }
+            ManagementMessage::StaffScrolled(offset) => self.staff_rows.scrolled(offset),
+            ManagementMessage::ChangeMarker(value) => self.marker_value = value,
+            ManagementMessage::SubmitMarker => {
+                let marker = self.marker_value.trim();
+                if marker.is_empty() {
+                    return Err(StechuhrError::Str(String::from(
+                        "Die Markierung darf nicht leer sein.",
+                    )));
                 }
+                shared.create_event(WorkEvent::Marker(marker.to_owned()));
+                self.marker_value.clear();
             }
-            ManagementMessage::SubmitNewRow => {
-                self.submit_new_row(shared)?;
+            ManagementMessage::Announcement(message) => {
+                self.announcement.update(shared, message)?;
             }
             ManagementMessage::Whoami => {
                 self.whoami_modal_state.show(true);
@@ -712,49 +402,38 @@ impl Tab for ManagementTab {
                 );
                 self.whoami_modal_state.show(false);
 
-                let msg = match cardid.parse::<Cardid>() {
-                    Ok(_) => match StaffMember::get_by_card_id(&shared.staff, &cardid) {
-                        Some(staff_member) => format!(
-                            "Der Dongle mit ID \"{}\" gehört {}",
-                            cardid,
-                            staff_member.name.clone()
-                        ),
-                        None => format!("Der Dongle mit ID \"{}\" gehört niemandem", cardid),
-                    },
-                    Err(e) => format!("Ungültige Dongle-ID. {}", e),
-                };
-                shared.prompt_message(msg);
+                // Not trimmed, hidden characters from the reader are what we want to see.
+                shared.prompt_message(card_diagnostics::diagnose(&cardid, &shared.staff));
             }
             ManagementMessage::GenericSubmit => {
                 let (focus_idx, _) = self.collect_inputs();
 
                 if let Some(focus_idx) = focus_idx {
-                    let row_idx = focus_idx / 3;
+                    let row_idx = focus_idx / INPUTS_PER_ROW;
 
-                    if row_idx == self.staff_state.member_states.len() {
+                    let staff_message = if row_idx == self.staff_list.rows().len() {
                         // we are in the last row so we submit
-                        self.submit_new_row(shared)?;
+                        StaffListMessage::SubmitNewRow
                     } else {
                         // one of the existing rows, so just save that
-                        self.staff_state.submit(shared, row_idx)?;
-                    }
+                        StaffListMessage::SubmitRow(row_idx)
+                    };
+                    return self.update_result(shared, ManagementMessage::Staff(staff_message));
                 }
             }
-            // a.d. completely hacked together tab order since iced does not seem to provide it
             ManagementMessage::HandleEvent(Event::Keyboard(keyboard::Event::KeyPressed {
                 key_code: keyboard::KeyCode::Tab,
                 modifiers,
             })) => {
-                let (focus_idx, mut inputs) = self.collect_inputs();
-
-                if let Some(focus_idx) = focus_idx {
-                    let new_focus_idx = if modifiers.shift() {
-                        (focus_idx + inputs.len() - 1) % inputs.len()
-                    } else {
-                        (focus_idx + 1) % inputs.len()
-                    };
-                    inputs.get_mut(focus_idx).unwrap().unfocus();
-                    inputs.get_mut(new_focus_idx).unwrap().focus();
+                let (_, inputs) = self.collect_inputs();
+                let mut inputs = inputs
+                    .into_iter()
+                    .map(|(input, _)| input)
+                    .collect::<Vec<_>>();
+                components::cycle_focus(&mut inputs, modifiers.shift());
+                // Rows that are scrolled out of view are not built, so the focused one is scrolled into view.
+                if let (Some(focus_idx), _) = self.collect_inputs() {
+                    self.scroll_to_row(shared, focus_idx / INPUTS_PER_ROW);
                 }
             }
             // fallthrough to ignore events
@@ -0,0 +1,203 @@
+//! Announcement to the staff on the Timetrack tab, e.g. "Heute Abrechnung bis 04:00!". It is saved in the database
+//! so that all terminals show it after their next refresh, optionally only for a limited time.
+use chrono::NaiveDateTime;
+use iced::{alignment::Horizontal, button, text_input, Alignment, Button, Length, Row, Text};
+use stechuhr::models::*;
+
+use super::ManagementMessage;
+use crate::{SharedData, StechuhrError};
+
+const TIME_FORMAT: &str = "%d.%m.%Y %H:%M";
+
+pub struct AnnouncementEditor {
+    text_value: String,
+    text_state: text_input::State,
+    from_value: String,
+    from_state: text_input::State,
+    until_value: String,
+    until_state: text_input::State,
+    save_state: button::State,
+    remove_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum AnnouncementMessage {
+    ChangeText(String),
+    ChangeFrom(String),
+    ChangeUntil(String),
+    Save,
+    Remove,
+}
+
+impl AnnouncementEditor {
+    pub fn new() -> Self {
+        AnnouncementEditor {
+            text_value: String::new(),
+            text_state: text_input::State::default(),
+            from_value: String::new(),
+            from_state: text_input::State::default(),
+            until_value: String::new(),
+            until_state: text_input::State::default(),
+            save_state: button::State::default(),
+            remove_state: button::State::default(),
+        }
+    }
+
+    pub fn unfocus(&mut self) {
+        self.text_state.unfocus();
+        self.from_state.unfocus();
+        self.until_state.unfocus();
+    }
+
+    /// Fill the inputs with the current announcement, e.g. after logging in.
+    pub fn load(&mut self, announcement: Option<&Announcement>) {
+        let format = |time: Option<NaiveDateTime>| {
+            time.map(|time| time.format(TIME_FORMAT).to_string())
+                .unwrap_or_default()
+        };
+        self.text_value =
+            announcement.map_or_else(String::new, |announcement| announcement.text.clone());
+        self.from_value = format(announcement.and_then(|announcement| announcement.show_from));
+        self.until_value = format(announcement.and_then(|announcement| announcement.show_until));
+    }
+
+    /// An empty input means that the announcement is not limited in that direction.
+    fn parse_time(input: &str) -> Result<Option<NaiveDateTime>, StechuhrError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        NaiveDateTime::parse_from_str(input, TIME_FORMAT)
+            .map(Some)
+            .map_err(|_| {
+                StechuhrError::Str(format!(
+                    "\"{}\" ist keine Zeit im Format TT.MM.JJJJ HH:MM.",
+                    input
+                ))
+            })
+    }
+
+    fn save(
+        &mut self,
+        shared: &mut SharedData,
+        new_announcement: NewAnnouncement,
+    ) -> Result<(), StechuhrError> {
+        shared.storage.insert_announcement(new_announcement)?;
+        shared.announcement = shared.storage.load_announcement();
+        self.load(shared.announcement.as_ref());
+        Ok(())
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: AnnouncementMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            AnnouncementMessage::ChangeText(value) => self.text_value = value,
+            AnnouncementMessage::ChangeFrom(value) => self.from_value = value,
+            AnnouncementMessage::ChangeUntil(value) => self.until_value = value,
+            AnnouncementMessage::Save => {
+                let text = self.text_value.trim().to_owned();
+                if text.is_empty() {
+                    return Err(StechuhrError::Str(String::from(
+                        "Die Ankündigung darf nicht leer sein. Zum Entfernen bitte \"Entfernen\" drücken.",
+                    )));
+                }
+                let show_from = AnnouncementEditor::parse_time(&self.from_value)?;
+                let show_until = AnnouncementEditor::parse_time(&self.until_value)?;
+                let now = shared.current_time.naive_local();
+                if let Some(show_until) = show_until {
+                    if show_until <= now || show_from.is_some_and(|from| show_until <= from) {
+                        return Err(StechuhrError::Str(String::from(
+                            "Das Ende der Ankündigung muss in der Zukunft und nach ihrem Beginn liegen.",
+                        )));
+                    }
+                }
+
+                self.save(
+                    shared,
+                    NewAnnouncement {
+                        text: text.clone(),
+                        show_from,
+                        show_until,
+                        created_at: now,
+                    },
+                )?;
+                shared.log_info(format!("Ankündigung geändert: {}", text));
+            }
+            AnnouncementMessage::Remove => {
+                if shared
+                    .announcement
+                    .as_ref()
+                    .is_none_or(|announcement| announcement.text.is_empty())
+                {
+                    self.load(None);
+                    return Ok(());
+                }
+                let now = shared.current_time.naive_local();
+                self.save(
+                    shared,
+                    NewAnnouncement {
+                        text: String::new(),
+                        show_from: None,
+                        show_until: None,
+                        created_at: now,
+                    },
+                )?;
+                shared.log_info(String::from("Ankündigung entfernt."));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn view(&mut self) -> Row<'_, ManagementMessage> {
+        Row::new()
+            .push(Text::new("Ankündigung"))
+            .push(
+                stechuhr::style::text_input(
+                    &mut self.text_state,
+                    "z.B. Heute Abrechnung bis 04:00!",
+                    &self.text_value,
+                    |s| ManagementMessage::Announcement(AnnouncementMessage::ChangeText(s)),
+                )
+                .on_submit(ManagementMessage::Announcement(AnnouncementMessage::Save))
+                .width(Length::FillPortion(3)),
+            )
+            .push(
+                stechuhr::style::text_input(
+                    &mut self.from_state,
+                    "ab TT.MM.JJJJ HH:MM",
+                    &self.from_value,
+                    |s| ManagementMessage::Announcement(AnnouncementMessage::ChangeFrom(s)),
+                )
+                .width(Length::FillPortion(1)),
+            )
+            .push(
+                stechuhr::style::text_input(
+                    &mut self.until_state,
+                    "bis TT.MM.JJJJ HH:MM",
+                    &self.until_value,
+                    |s| ManagementMessage::Announcement(AnnouncementMessage::ChangeUntil(s)),
+                )
+                .width(Length::FillPortion(1)),
+            )
+            .push(
+                Button::new(
+                    &mut self.save_state,
+                    Text::new("Speichern").horizontal_alignment(Horizontal::Center),
+                )
+                .on_press(ManagementMessage::Announcement(AnnouncementMessage::Save)),
+            )
+            .push(
+                Button::new(
+                    &mut self.remove_state,
+                    Text::new("Entfernen").horizontal_alignment(Horizontal::Center),
+                )
+                .on_press(ManagementMessage::Announcement(AnnouncementMessage::Remove)),
+            )
+            .spacing(20)
+            .padding(10)
+            .align_items(Alignment::Center)
+    }
+}
@@ -0,0 +1,100 @@
+//! Diagnosis of scanned dongle IDs, to find out why a reader does not work reliably.
+use stechuhr::keyboard_layout;
+use stechuhr::models::{Cardid, StaffMember};
+
+/// Characters that a reader may send in addition to the ID and that are not visible in a text input.
+fn is_hidden(c: char) -> bool {
+    c.is_control() || c.is_whitespace() || matches!(c, '\u{200B}'..='\u{200D}' | '\u{FEFF}')
+}
+
+fn owner_description(cardid: &str, staff: &[StaffMember]) -> String {
+    match StaffMember::get_by_card_id(staff, cardid) {
+        Some(staff_member) => format!(
+            "Der Dongle mit ID \"{}\" gehört {}",
+            cardid, staff_member.name
+        ),
+        None => format!("Der Dongle mit ID \"{}\" gehört niemandem", cardid),
+    }
+}
+
+/// Describe the scanned input, why it is not a valid dongle ID and to whom it belongs.
+pub fn diagnose(input: &str, staff: &[StaffMember]) -> String {
+    let bytes = input
+        .bytes()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut lines = vec![
+        format!("Eingabe: \"{}\" ({} Zeichen)", input, input.chars().count()),
+        format!("Bytes: {}", bytes),
+    ];
+
+    if input.parse::<Cardid>().is_ok() {
+        lines.push(owner_description(input, staff));
+        return lines.join("\n");
+    }
+
+    let hidden = input
+        .chars()
+        .filter(|c| is_hidden(*c))
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>();
+    let visible = input.chars().filter(|c| !is_hidden(*c)).collect::<String>();
+    if !hidden.is_empty() {
+        lines.push(format!(
+            "Der Leser hat unsichtbare Zeichen gesendet: {}",
+            hidden.join(", ")
+        ));
+    }
+
+    let cardid = match keyboard_layout::translate(&visible) {
+        Some((layout, translated)) => {
+            lines.push(format!(
+                "Der Leser verwendet vermutlich die Tastaturbelegung {}.",
+                layout
+            ));
+            translated
+        }
+        _ => visible,
+    };
+
+    if cardid.parse::<Cardid>().is_ok() {
+        lines.push(format!(
+            "Gemeint war vermutlich die Dongle-ID \"{}\".",
+            cardid
+        ));
+        lines.push(owner_description(&cardid, staff));
+    } else {
+        let digits = cardid.chars().filter(char::is_ascii_digit).count();
+        lines.push(format!(
+            "Ungültige Dongle-ID. Eine Dongle-ID besteht aus 10 Ziffern, die Eingabe enthält {} Ziffern und {} andere Zeichen.",
+            digits,
+            cardid.chars().count() - digits
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnose_reader_artifacts() {
+        let diagnosis = diagnose("1234567890", &[]);
+        assert!(diagnosis.ends_with("Der Dongle mit ID \"1234567890\" gehört niemandem"));
+
+        let diagnosis = diagnose("1234567890\u{FEFF} ", &[]);
+        assert!(diagnosis.contains("Bytes: 31 32 33 34 35 36 37 38 39 30 EF BB BF 20"));
+        assert!(diagnosis.contains("unsichtbare Zeichen gesendet: U+FEFF, U+0020"));
+        assert!(diagnosis.contains("Gemeint war vermutlich die Dongle-ID \"1234567890\""));
+
+        let diagnosis = diagnose("&é\"'(-è_çà", &[]);
+        assert!(diagnosis.contains("Tastaturbelegung AZERTY"));
+        assert!(diagnosis.contains("\"1234567890\""));
+
+        let diagnosis = diagnose("12345", &[]);
+        assert!(diagnosis.contains("enthält 5 Ziffern und 0 andere Zeichen"));
+    }
+}
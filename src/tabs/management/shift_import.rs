@@ -0,0 +1,293 @@
+//! Import of the shift plan with a dialog to resolve entries that cannot be assigned to a staff member.
+use std::{fmt, mem, path::Path};
+
+use iced::{
+    alignment::Horizontal, button, pick_list, scrollable, text_input, Alignment, Button, Column,
+    Element, Length, PickList, Row, Scrollable, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::{
+    models::*,
+    shift_plan::{self, PlannedShift, StaffMatch},
+};
+
+use super::ManagementMessage;
+use crate::tabs::components;
+use crate::{SharedData, StechuhrError};
+
+/// Staff member chosen for a shift that could not be assigned automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaffChoice {
+    Skip,
+    Staff(i32, String),
+}
+
+impl fmt::Display for StaffChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaffChoice::Skip => f.write_str("Überspringen"),
+            StaffChoice::Staff(_, name) => f.write_str(name),
+        }
+    }
+}
+
+struct Conflict {
+    shift: PlannedShift,
+    reason: &'static str,
+    choice: StaffChoice,
+    pick_state: pick_list::State<StaffChoice>,
+}
+
+#[derive(Default)]
+struct ConflictModalState {
+    conflicts: Vec<Conflict>,
+    choices: Vec<StaffChoice>,
+    scroll_state: scrollable::State,
+    confirm_state: button::State,
+    cancel_state: button::State,
+}
+
+pub struct ShiftImport {
+    path_value: String,
+    path_state: text_input::State,
+    import_state: button::State,
+    /* shifts that were assigned automatically and wait for the conflicts to be resolved */
+    matched: Vec<NewShift>,
+    conflict_modal_state: modal::State<ConflictModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ShiftImportMessage {
+    ChangePath(String),
+    Import,
+    ChooseStaff(usize, StaffChoice),
+    Confirm,
+    Cancel,
+}
+
+impl ShiftImport {
+    pub fn new() -> Self {
+        ShiftImport {
+            path_value: String::new(),
+            path_state: text_input::State::default(),
+            import_state: button::State::default(),
+            matched: Vec::new(),
+            conflict_modal_state: modal::State::default(),
+        }
+    }
+
+    pub fn unfocus(&mut self) {
+        self.path_state.unfocus();
+    }
+
+    /// Read the shift plan and assign the shifts to staff members.
+    /// If some shifts cannot be assigned we ask the user, otherwise the shifts are saved directly.
+    fn import(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let planned_shifts = shift_plan::parse_file(Path::new(self.path_value.trim()))?;
+
+        let mut matched = Vec::new();
+        let mut conflicts = Vec::new();
+        for shift in planned_shifts {
+            match shift.match_staff(&shared.staff) {
+                StaffMatch::Unique(uuid) => {
+                    matched.push(NewShift::new(uuid, shift.start_time, shift.end_time))
+                }
+                StaffMatch::Ambiguous(_) => conflicts.push(Conflict {
+                    shift,
+                    reason: "mehrdeutig",
+                    choice: StaffChoice::Skip,
+                    pick_state: pick_list::State::default(),
+                }),
+                StaffMatch::Unknown => conflicts.push(Conflict {
+                    shift,
+                    reason: "unbekannt",
+                    choice: StaffChoice::Skip,
+                    pick_state: pick_list::State::default(),
+                }),
+            }
+        }
+
+        if conflicts.is_empty() {
+            return self.save(shared, matched, 0);
+        }
+
+        let mut choices = vec![StaffChoice::Skip];
+        choices.extend(shared.staff.iter().map(|staff_member| {
+            StaffChoice::Staff(staff_member.uuid(), staff_member.name.clone())
+        }));
+
+        self.matched = matched;
+        let state = self.conflict_modal_state.inner_mut();
+        state.conflicts = conflicts;
+        state.choices = choices;
+        self.conflict_modal_state.show(true);
+
+        Ok(())
+    }
+
+    /// Save the shifts that are not already planned.
+    fn save(
+        &mut self,
+        shared: &mut SharedData,
+        shifts: Vec<NewShift>,
+        skipped: usize,
+    ) -> Result<(), StechuhrError> {
+        let start = shifts.iter().map(|shift| shift.start_time).min();
+        let end = shifts.iter().map(|shift| shift.end_time).max();
+        let planned = match (start, end) {
+            (Some(start), Some(end)) => shared.storage.load_shifts_between(start, end),
+            _ => Vec::new(),
+        };
+
+        let (duplicates, new_shifts): (Vec<_>, Vec<_>) = shifts
+            .into_iter()
+            .partition(|shift| shift.is_planned(&planned));
+        shared.storage.insert_shifts(&new_shifts)?;
+
+        self.path_value.clear();
+        shared.reload_shifts();
+        shared.log_info(format!(
+            "Schichtplan importiert: {} Schichten übernommen, {} bereits vorhanden, {} übersprungen.",
+            new_shifts.len(),
+            duplicates.len(),
+            skipped
+        ));
+        Ok(())
+    }
+
+    fn confirm(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let conflicts = mem::take(&mut self.conflict_modal_state.inner_mut().conflicts);
+        self.conflict_modal_state.show(false);
+
+        let mut shifts = mem::take(&mut self.matched);
+        let mut skipped = 0;
+        for conflict in conflicts {
+            match conflict.choice {
+                StaffChoice::Staff(uuid, _) => shifts.push(NewShift::new(
+                    uuid,
+                    conflict.shift.start_time,
+                    conflict.shift.end_time,
+                )),
+                StaffChoice::Skip => skipped += 1,
+            }
+        }
+
+        self.save(shared, shifts, skipped)
+    }
+
+    fn cancel(&mut self) {
+        self.matched.clear();
+        self.conflict_modal_state.inner_mut().conflicts.clear();
+        self.conflict_modal_state.show(false);
+    }
+
+    /// Puts the import controls below the given staff list and wraps everything in the conflict dialog.
+    pub fn view<'a>(
+        &'a mut self,
+        staff_edit: Element<'a, ManagementMessage>,
+    ) -> Element<'a, ManagementMessage> {
+        let controls = Row::new()
+            .push(Text::new("Schichtplan importieren"))
+            .push(
+                stechuhr::style::text_input(
+                    &mut self.path_state,
+                    "Pfad zur CSV- oder iCal-Datei",
+                    &self.path_value,
+                    |s| ManagementMessage::ShiftImport(ShiftImportMessage::ChangePath(s)),
+                )
+                .on_submit(ManagementMessage::ShiftImport(ShiftImportMessage::Import))
+                .width(Length::FillPortion(3)),
+            )
+            .push(
+                Button::new(
+                    &mut self.import_state,
+                    Text::new("Importieren").horizontal_alignment(Horizontal::Center),
+                )
+                .on_press(ManagementMessage::ShiftImport(ShiftImportMessage::Import)),
+            )
+            .spacing(20)
+            .padding(10)
+            .align_items(Alignment::Center);
+
+        let content = Column::new().push(staff_edit).push(controls);
+
+        let modal = Modal::new(&mut self.conflict_modal_state, content, |state| {
+            let conflict_list = state.conflicts.iter_mut().enumerate().fold(
+                Scrollable::new(&mut state.scroll_state).spacing(10),
+                |list, (idx, conflict)| {
+                    let description = format!(
+                        "{} ({}): {} - {}",
+                        conflict.shift.ident(),
+                        conflict.reason,
+                        conflict.shift.start_time.format("%d.%m.%Y %H:%M"),
+                        conflict.shift.end_time.format("%d.%m.%Y %H:%M"),
+                    );
+                    list.push(
+                        Row::new()
+                            .push(Text::new(description).width(Length::FillPortion(3)))
+                            .push(
+                                PickList::new(
+                                    &mut conflict.pick_state,
+                                    &state.choices[..],
+                                    Some(conflict.choice.clone()),
+                                    move |choice| {
+                                        ManagementMessage::ShiftImport(
+                                            ShiftImportMessage::ChooseStaff(idx, choice),
+                                        )
+                                    },
+                                )
+                                .width(Length::FillPortion(2)),
+                            )
+                            .spacing(10)
+                            .align_items(Alignment::Center),
+                    )
+                },
+            );
+
+            Card::new(
+                Text::new("Schichtplan: Zuordnung unklar"),
+                Column::new()
+                    .push(Text::new(
+                        "Folgende Schichten konnten keiner Person eindeutig zugeordnet werden:",
+                    ))
+                    .push(conflict_list.max_height(400))
+                    .spacing(10),
+            )
+            .foot(components::confirm_foot(
+                &mut state.confirm_state,
+                "Importieren",
+                Some(ManagementMessage::ShiftImport(ShiftImportMessage::Confirm)),
+                &mut state.cancel_state,
+                "Abbrechen",
+                ManagementMessage::ShiftImport(ShiftImportMessage::Cancel),
+            ))
+            .max_width(800)
+            .on_close(ManagementMessage::ShiftImport(ShiftImportMessage::Cancel))
+            .into()
+        })
+        .backdrop(ManagementMessage::ShiftImport(ShiftImportMessage::Cancel))
+        .on_esc(ManagementMessage::ShiftImport(ShiftImportMessage::Cancel));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: ShiftImportMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            ShiftImportMessage::ChangePath(path) => self.path_value = path,
+            ShiftImportMessage::Import => self.import(shared)?,
+            ShiftImportMessage::ChooseStaff(idx, choice) => {
+                if let Some(conflict) = self.conflict_modal_state.inner_mut().conflicts.get_mut(idx)
+                {
+                    conflict.choice = choice;
+                }
+            }
+            ShiftImportMessage::Confirm => self.confirm(shared)?,
+            ShiftImportMessage::Cancel => self.cancel(),
+        }
+        Ok(())
+    }
+}
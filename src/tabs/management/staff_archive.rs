@@ -0,0 +1,92 @@
+//! Archive of a staff member's data that is written before they are deleted,
+//! since the records of working hours must be kept even after someone left.
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use stechuhr::models::{StaffMember, Wage};
+use stechuhr::settings::Settings;
+
+use crate::tabs::statistics::file_name_part;
+use crate::{SharedData, StechuhrError};
+
+/// Archives are written into this subdirectory of the export directory.
+const ARCHIVE_DIR: &str = "Archiv";
+
+pub fn archive_dir(settings: &Settings) -> PathBuf {
+    settings.export_dir.join(ARCHIVE_DIR)
+}
+
+/// Write the profile and all events of the staff member into a file and return its path.
+pub fn write_archive(
+    shared: &mut SharedData,
+    staff_member: &StaffMember,
+) -> Result<PathBuf, StechuhrError> {
+    let now = shared.current_time.naive_local();
+    let events = shared.storage.load_all_staff_events(staff_member.uuid());
+
+    let dir = archive_dir(&shared.settings);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "{} {}.tsv",
+        file_name_part(&staff_member.name),
+        now.format("%Y-%m-%d %H-%M-%S")
+    ));
+
+    let mut wtr = csv::WriterBuilder::new()
+        // Use Tab as delimiter so that Excel automatically imports it correctly.
+        .delimiter(b'\t')
+        .flexible(true)
+        .from_path(&path)?;
+    for (field, value) in profile(staff_member, now) {
+        wtr.write_record([field, &value])?;
+    }
+    wtr.write_record([""])?;
+    wtr.write_record(["Zeitpunkt", "Ereignis"])?;
+    for eventt in &events {
+        wtr.write_record([
+            eventt.created_at.format("%d.%m.%Y %H:%M:%S").to_string(),
+            shared
+                .staff_names
+                .describe(&eventt.event, true, &shared.settings.log_texts),
+        ])?;
+    }
+    wtr.flush()?;
+
+    shared.log_info(format!(
+        "Daten von {} mit {} Ereignissen archiviert in {}.",
+        staff_member.name,
+        events.len(),
+        path.display()
+    ));
+    Ok(path)
+}
+
+fn profile(staff_member: &StaffMember, now: NaiveDateTime) -> Vec<(&'static str, String)> {
+    vec![
+        ("Name", staff_member.name.clone()),
+        (
+            "Anzeigename",
+            staff_member.display_name.clone().unwrap_or_default(),
+        ),
+        ("ID", staff_member.uuid().to_string()),
+        (
+            "Personalnummer",
+            staff_member.personnel_number.clone().unwrap_or_default(),
+        ),
+        (
+            "Abteilung",
+            staff_member.department.clone().unwrap_or_default(),
+        ),
+        (
+            "Stundenlohn",
+            Wage::format_optional(staff_member.wage_cents_per_hour),
+        ),
+        ("Dongle", staff_member.cardid.clone()),
+        (
+            "Minderjährig",
+            String::from(if staff_member.is_minor { "ja" } else { "nein" }),
+        ),
+        ("Archiviert am", now.format("%d.%m.%Y %H:%M:%S").to_string()),
+    ]
+}
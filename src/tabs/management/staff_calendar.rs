@@ -0,0 +1,328 @@
+//! Calendar of a staff member's month, colored by the hours worked on each business day.
+//! Lets admins check each person at a glance before exporting the evaluation.
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use iced::{
+    alignment::Horizontal, button, Alignment, Button, Color, Column, Container, Element, Length,
+    Row, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::date_ext::{LocalizedFormat, NaiveDateExt};
+use stechuhr::youth_protection;
+
+use super::{ManagementError, ManagementMessage};
+use crate::tabs::components;
+use crate::{SharedData, StechuhrError};
+
+/// Days with less work than this are marked as short.
+const SHORT_HOURS: i64 = 4;
+/// Days with more work than this are marked as long, it is the daily maximum of the working hours act.
+const LONG_HOURS: i64 = 10;
+const WEEKDAYS: [&str; 7] = ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeatLevel {
+    None,
+    Short,
+    Normal,
+    Long,
+}
+
+impl HeatLevel {
+    const ALL: [HeatLevel; 4] = [
+        HeatLevel::None,
+        HeatLevel::Short,
+        HeatLevel::Normal,
+        HeatLevel::Long,
+    ];
+
+    fn from_duration(duration: Duration) -> Self {
+        if duration <= Duration::zero() {
+            HeatLevel::None
+        } else if duration < Duration::hours(SHORT_HOURS) {
+            HeatLevel::Short
+        } else if duration <= Duration::hours(LONG_HOURS) {
+            HeatLevel::Normal
+        } else {
+            HeatLevel::Long
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            HeatLevel::None => Color::WHITE,
+            HeatLevel::Short => Color::from_rgb8(255, 236, 179),
+            HeatLevel::Normal => Color::from_rgb8(165, 214, 167),
+            HeatLevel::Long => Color::from_rgb8(239, 154, 154),
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            HeatLevel::None => String::from("nicht gearbeitet"),
+            HeatLevel::Short => format!("unter {} h", SHORT_HOURS),
+            HeatLevel::Normal => format!("{} bis {} h", SHORT_HOURS, LONG_HOURS),
+            HeatLevel::Long => format!("über {} h", LONG_HOURS),
+        }
+    }
+}
+
+/// First day of the month after the given date.
+fn next_month(month: NaiveDate) -> NaiveDate {
+    let first = month.first_dom();
+    if first.month() == 12 {
+        NaiveDate::from_ymd(first.year() + 1, 1, 1)
+    } else {
+        first.with_month(first.month() + 1).unwrap()
+    }
+}
+
+/// Days of the month arranged in weeks starting on monday. Days outside of the month are None.
+fn calendar_weeks(month: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = month.first_dom();
+    let offset = first.weekday().num_days_from_monday() as i64;
+    let mut weeks = Vec::new();
+    let mut monday = first - Duration::days(offset);
+
+    while monday < next_month(first) {
+        let mut week = [None; 7];
+        for (idx, day) in week.iter_mut().enumerate() {
+            let date = monday + Duration::days(idx as i64);
+            if date.month() == first.month() {
+                *day = Some(date);
+            }
+        }
+        weeks.push(week);
+        monday += Duration::days(7);
+    }
+
+    weeks
+}
+
+fn day_cell<'a>(
+    content: Column<'a, ManagementMessage>,
+    color: Color,
+) -> Element<'a, ManagementMessage> {
+    Container::new(content.align_items(Alignment::Center))
+        .width(Length::Units(70))
+        .height(Length::Units(50))
+        .center_x()
+        .center_y()
+        .style(stechuhr::style::CalendarDayStyle(color))
+        .into()
+}
+
+#[derive(Default)]
+struct CalendarModalState {
+    uuid: i32,
+    name: String,
+    title: String,
+    month: Option<NaiveDate>,
+    /* worked time per business day of the month */
+    days: BTreeMap<NaiveDate, Duration>,
+    previous_state: button::State,
+    next_state: button::State,
+    close_state: button::State,
+}
+
+pub struct StaffCalendar {
+    modal_state: modal::State<CalendarModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StaffCalendarMessage {
+    Open(usize),
+    PreviousMonth,
+    NextMonth,
+    Close,
+}
+
+impl StaffCalendar {
+    pub fn new() -> Self {
+        StaffCalendar {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Load the worked time of the shown staff member for the given month.
+    fn load(&mut self, shared: &mut SharedData, month: NaiveDate) {
+        let state = self.modal_state.inner_mut();
+        let start_time = shared.settings.business_day_start(month);
+        let end_time = shared.settings.business_day_start(next_month(month));
+        let now = shared.current_time.naive_local();
+
+        let events = shared
+            .storage
+            .load_staff_events_between(state.uuid, start_time, end_time);
+        let intervals = youth_protection::work_intervals(&events, state.uuid, end_time.min(now));
+        state.days = youth_protection::daily_durations(&intervals, shared.settings.cutoff_time);
+        state.month = Some(month);
+    }
+
+    pub fn view<'a>(
+        &'a mut self,
+        underlay: Element<'a, ManagementMessage>,
+    ) -> Element<'a, ManagementMessage> {
+        let modal = Modal::new(&mut self.modal_state, underlay, |state| {
+            let navigation = Row::new()
+                .push(
+                    Button::new(&mut state.previous_state, Text::new("<")).on_press(
+                        ManagementMessage::Calendar(StaffCalendarMessage::PreviousMonth),
+                    ),
+                )
+                .push(
+                    Text::new(&state.title)
+                        .horizontal_alignment(Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .push(
+                    Button::new(&mut state.next_state, Text::new(">"))
+                        .on_press(ManagementMessage::Calendar(StaffCalendarMessage::NextMonth)),
+                )
+                .align_items(Alignment::Center);
+
+            let header = WEEKDAYS.iter().fold(Row::new().spacing(5), |row, weekday| {
+                row.push(day_cell(
+                    Column::new().push(Text::new(*weekday)),
+                    Color::WHITE,
+                ))
+            });
+            let weeks = state
+                .month
+                .map(calendar_weeks)
+                .unwrap_or_default()
+                .into_iter()
+                .fold(Column::new().spacing(5).push(header), |column, week| {
+                    let row = week.iter().fold(Row::new().spacing(5), |row, day| {
+                        let cell = match day {
+                            Some(date) => {
+                                let worked =
+                                    state.days.get(date).copied().unwrap_or_else(Duration::zero);
+                                let mut content =
+                                    Column::new().push(Text::new(date.day().to_string()));
+                                if worked > Duration::zero() {
+                                    content = content.push(Text::new(format!(
+                                        "{}:{:02}",
+                                        worked.num_hours(),
+                                        worked.num_minutes() % 60
+                                    )));
+                                }
+                                day_cell(content, HeatLevel::from_duration(worked).color())
+                            }
+                            None => day_cell(Column::new(), Color::TRANSPARENT),
+                        };
+                        row.push(cell)
+                    });
+                    column.push(row)
+                });
+
+            let legend = HeatLevel::ALL
+                .iter()
+                .fold(Row::new().spacing(10), |row, level| {
+                    row.push(
+                        Container::new(Text::new(level.label()))
+                            .padding(5)
+                            .style(stechuhr::style::CalendarDayStyle(level.color())),
+                    )
+                });
+
+            Card::new(
+                Text::new("Kalender"),
+                Column::new()
+                    .push(navigation)
+                    .push(weeks)
+                    .push(legend)
+                    .spacing(10),
+            )
+            .foot(
+                Row::new().padding(5).width(Length::Fill).push(
+                    components::modal_button(&mut state.close_state, "Schließen")
+                        .on_press(ManagementMessage::Calendar(StaffCalendarMessage::Close)),
+                ),
+            )
+            .max_width(600)
+            .on_close(ManagementMessage::Calendar(StaffCalendarMessage::Close))
+            .into()
+        })
+        .backdrop(ManagementMessage::Calendar(StaffCalendarMessage::Close))
+        .on_esc(ManagementMessage::Calendar(StaffCalendarMessage::Close));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: StaffCalendarMessage,
+    ) -> Result<(), StechuhrError> {
+        let month = match message {
+            StaffCalendarMessage::Open(idx) => {
+                let staff_member = shared
+                    .staff
+                    .get(idx)
+                    .ok_or(ManagementError::IndexError(idx))?;
+                let state = self.modal_state.inner_mut();
+                state.uuid = staff_member.uuid();
+                state.name = staff_member.name.clone();
+                self.modal_state.show(true);
+
+                let now = shared.current_time.naive_local();
+                shared.settings.business_date(now).first_dom()
+            }
+            StaffCalendarMessage::PreviousMonth | StaffCalendarMessage::NextMonth => {
+                let month = match self.modal_state.inner_mut().month {
+                    Some(month) => month,
+                    None => return Ok(()),
+                };
+                match message {
+                    StaffCalendarMessage::PreviousMonth => month.pred().first_dom(),
+                    _ => next_month(month),
+                }
+            }
+            StaffCalendarMessage::Close => {
+                self.modal_state.show(false);
+                return Ok(());
+            }
+        };
+
+        self.load(shared, month);
+        let state = self.modal_state.inner_mut();
+        state.title = format!(
+            "{}: {}",
+            state.name,
+            month.format_localized("%B %Y", shared.settings.locale())
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calendar_of_month() {
+        // 2000-02-01 is a tuesday and 2000 is a leap year.
+        let weeks = calendar_weeks(NaiveDate::from_ymd(2000, 2, 1));
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][0], None);
+        assert_eq!(weeks[0][1], Some(NaiveDate::from_ymd(2000, 2, 1)));
+        assert_eq!(weeks[4][1], Some(NaiveDate::from_ymd(2000, 2, 29)));
+        assert_eq!(weeks[4][2], None);
+
+        assert_eq!(HeatLevel::from_duration(Duration::zero()), HeatLevel::None);
+        assert_eq!(
+            HeatLevel::from_duration(Duration::hours(3)),
+            HeatLevel::Short
+        );
+        assert_eq!(
+            HeatLevel::from_duration(Duration::hours(8)),
+            HeatLevel::Normal
+        );
+        assert_eq!(
+            HeatLevel::from_duration(Duration::hours(11)),
+            HeatLevel::Long
+        );
+    }
+}
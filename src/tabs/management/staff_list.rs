@@ -0,0 +1,619 @@
+//! The staff list of the tab without its widgets: the values of the inputs of each row and of the row for adding a
+//! new staff member, and what happens when they are changed and saved. The widget states stay in the tab.
+use std::{mem, slice};
+
+use stechuhr::db;
+use stechuhr::models::*;
+
+use super::{staff_archive, ManagementError};
+use crate::{SharedData, StechuhrError};
+
+/// A text input of a row, in the order in which they are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaffField {
+    Name,
+    DisplayName,
+    Pin,
+    CardId,
+    PersonnelNumber,
+    Department,
+    Wage,
+}
+
+impl StaffField {
+    pub const ALL: [StaffField; 7] = [
+        StaffField::Name,
+        StaffField::DisplayName,
+        StaffField::Pin,
+        StaffField::CardId,
+        StaffField::PersonnelNumber,
+        StaffField::Department,
+        StaffField::Wage,
+    ];
+}
+
+/// The values of the inputs of a row as they are typed, which are only checked when the row is saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaffRow {
+    name: String,
+    display_name: String,
+    pin: String,
+    cardid: String,
+    personnel_number: String,
+    department: String,
+    wage: String,
+    is_visible: bool,
+    is_minor: bool,
+}
+
+impl Default for StaffRow {
+    fn default() -> Self {
+        StaffRow {
+            name: String::new(),
+            display_name: String::new(),
+            pin: String::new(),
+            cardid: String::new(),
+            personnel_number: String::new(),
+            department: String::new(),
+            wage: String::new(),
+            is_visible: true,
+            is_minor: false,
+        }
+    }
+}
+
+impl StaffRow {
+    pub fn from_staff_member(staff_member: &StaffMember) -> Self {
+        StaffRow {
+            name: staff_member.name.clone(),
+            display_name: staff_member.display_name.clone().unwrap_or_default(),
+            pin: staff_member.pin.clone(),
+            cardid: staff_member.cardid.clone(),
+            personnel_number: staff_member.personnel_number.clone().unwrap_or_default(),
+            department: staff_member.department.clone().unwrap_or_default(),
+            wage: Wage::format_optional(staff_member.wage_cents_per_hour),
+            is_visible: staff_member.is_visible,
+            is_minor: staff_member.is_minor,
+        }
+    }
+
+    pub fn value(&self, field: StaffField) -> &str {
+        match field {
+            StaffField::Name => &self.name,
+            StaffField::DisplayName => &self.display_name,
+            StaffField::Pin => &self.pin,
+            StaffField::CardId => &self.cardid,
+            StaffField::PersonnelNumber => &self.personnel_number,
+            StaffField::Department => &self.department,
+            StaffField::Wage => &self.wage,
+        }
+    }
+
+    /// The values of the text inputs in the order of `StaffField::ALL`, for typing with the on-screen keyboard.
+    pub fn values_mut(&mut self) -> [&mut String; 7] {
+        [
+            &mut self.name,
+            &mut self.display_name,
+            &mut self.pin,
+            &mut self.cardid,
+            &mut self.personnel_number,
+            &mut self.department,
+            &mut self.wage,
+        ]
+    }
+
+    fn set(&mut self, field: StaffField, value: String) {
+        let old_value = match field {
+            StaffField::Name => &mut self.name,
+            StaffField::DisplayName => &mut self.display_name,
+            StaffField::Pin => &mut self.pin,
+            StaffField::CardId => &mut self.cardid,
+            StaffField::PersonnelNumber => &mut self.personnel_number,
+            StaffField::Department => &mut self.department,
+            StaffField::Wage => &mut self.wage,
+        };
+        *old_value = value;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub fn is_minor(&self) -> bool {
+        self.is_minor
+    }
+
+    /// Check if the inputs contain changes that were not saved yet.
+    pub fn has_unsaved_edits(&self, staff_member: &StaffMember) -> bool {
+        let saved = StaffRow::from_staff_member(staff_member);
+        StaffField::ALL
+            .iter()
+            .any(|field| self.value(*field) != saved.value(*field))
+    }
+
+    /// The staff member with the values of the row, if they are valid. It is only changed once the edits are saved.
+    pub fn edited(&self, staff_member: &StaffMember) -> Result<StaffMember, StechuhrError> {
+        // use same validation as for a new staff member
+        NewStaffMember::validate(&self.name, &self.pin, &self.cardid, &self.personnel_number)?;
+        let mut edited = staff_member.clone();
+        edited.name.clone_from(&self.name);
+        edited.pin.clone_from(&self.pin);
+        edited.cardid.clone_from(&self.cardid);
+        edited.personnel_number = PersonnelNumber::parse_optional(&self.personnel_number)?;
+        edited.display_name = DisplayName::parse_optional(&self.display_name);
+        edited.department = Department::parse_optional(&self.department);
+        edited.wage_cents_per_hour = Wage::parse_optional(&self.wage)?;
+        edited.is_visible = self.is_visible;
+        edited.is_minor = self.is_minor;
+        Ok(edited)
+    }
+
+    /// The new staff member of the row for adding one, if the values are valid.
+    pub fn new_staff_member(&self) -> Result<NewStaffMember, StechuhrError> {
+        Ok(NewStaffMember::new(
+            self.name.clone(),
+            self.pin.clone(),
+            self.cardid.clone(),
+            self.personnel_number.clone(),
+        )?
+        .with_display_name(&self.display_name)
+        .with_department(&self.department)
+        .with_wage(Wage::parse_optional(&self.wage)?))
+    }
+}
+
+/// Edits of a staff member that could not be saved because another terminal changed the same staff member.
+pub struct StaffConflict {
+    edited: StaffMember,
+    /* the record as it is in the database now */
+    record: DBStaffMember,
+}
+
+impl StaffConflict {
+    /// Text of the modal that asks which of the changes are kept.
+    pub fn description(&self) -> String {
+        let mut description = format!(
+            "{} wurde an einem anderen Terminal geändert, während du die Person bearbeitet hast.",
+            self.record.name()
+        );
+        for (field, mine, theirs) in self.edited.differences(&self.record) {
+            description.push_str(&format!(
+                "\n{}: \"{}\" (deine Änderung), \"{}\" (anderes Terminal)",
+                field, mine, theirs
+            ));
+        }
+        description
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StaffListMessage {
+    Change(usize, StaffField, String),
+    ChangeNew(StaffField, String),
+    SubmitRow(usize),
+    ToggleVisible(usize, bool),
+    ToggleMinor(usize, bool),
+    SubmitNewRow,
+}
+
+/// A row for each staff member in the order of `SharedData::staff`, and the row for adding a new one.
+pub struct StaffList {
+    rows: Vec<StaffRow>,
+    new_row: StaffRow,
+    /* set when saving a row failed because of a concurrent change, see `db::save_staff_member` */
+    conflict: Option<StaffConflict>,
+}
+
+impl StaffList {
+    pub fn new(staff: &[StaffMember]) -> Self {
+        StaffList {
+            rows: staff.iter().map(StaffRow::from_staff_member).collect(),
+            new_row: StaffRow::default(),
+            conflict: None,
+        }
+    }
+
+    pub fn rows(&self) -> &[StaffRow] {
+        &self.rows
+    }
+
+    pub fn new_row(&self) -> &StaffRow {
+        &self.new_row
+    }
+
+    pub fn conflict(&self) -> Option<&StaffConflict> {
+        self.conflict.as_ref()
+    }
+
+    /// The values of all text inputs, row by row and the row for adding a new staff member last.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.rows
+            .iter_mut()
+            .chain(Some(&mut self.new_row))
+            .flat_map(StaffRow::values_mut)
+    }
+
+    fn row_mut(&mut self, idx: usize) -> Result<&mut StaffRow, StechuhrError> {
+        Ok(self
+            .rows
+            .get_mut(idx)
+            .ok_or(ManagementError::IndexError(idx))?)
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: StaffListMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            StaffListMessage::Change(idx, field, value) => self.row_mut(idx)?.set(field, value),
+            StaffListMessage::ChangeNew(field, value) => self.new_row.set(field, value),
+            StaffListMessage::SubmitRow(idx) => self.submit(shared, idx)?,
+            StaffListMessage::ToggleVisible(idx, is_visible) => {
+                self.row_mut(idx)?.is_visible = is_visible;
+                self.submit(shared, idx)?;
+            }
+            StaffListMessage::ToggleMinor(idx, is_minor) => {
+                self.row_mut(idx)?.is_minor = is_minor;
+                self.submit(shared, idx)?;
+            }
+            StaffListMessage::SubmitNewRow => self.submit_new_row(shared)?,
+        }
+        Ok(())
+    }
+
+    fn submit(&mut self, shared: &mut SharedData, idx: usize) -> Result<(), StechuhrError> {
+        let row = self.rows.get(idx).ok_or(ManagementError::IndexError(idx))?;
+        let staff_member = shared
+            .staff
+            .get(idx)
+            .ok_or(ManagementError::IndexError(idx))?;
+        let edited = row.edited(staff_member)?;
+        if shared.settings.kiosk.personnel_number_login {
+            StaffMember::check_idents_disjoint(
+                &shared.staff,
+                Some(edited.uuid()),
+                &edited.pin,
+                &edited.cardid,
+                edited.personnel_number.as_deref(),
+            )?;
+        }
+
+        self.save(shared, edited)
+    }
+
+    /// Save the edited staff member, or keep it as a conflict if another terminal changed it in the meantime.
+    fn save(
+        &mut self,
+        shared: &mut SharedData,
+        mut edited: StaffMember,
+    ) -> Result<(), StechuhrError> {
+        match shared.storage.save_staff_member(&mut edited) {
+            Ok(()) => {}
+            Err(db::SaveStaffError::Conflict(record)) => {
+                self.conflict = Some(StaffConflict {
+                    edited,
+                    record: *record,
+                });
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let now = shared.current_time.naive_local();
+        if let Some(previous) = StaffMember::get_by_uuid(&shared.staff, edited.uuid()) {
+            let entries = NewStaffHistoryEntry::edits(&edited, previous, now);
+            shared.record_staff_history(entries);
+        }
+        shared.staff_names.update(slice::from_ref(&edited));
+        let success_message = format!("Mitarbeiter {} erfolgreich geändert.", edited.name);
+        if let Some(staff_member) = StaffMember::get_by_uuid_mut(&mut shared.staff, edited.uuid()) {
+            *staff_member = edited;
+        }
+        shared.log_info(success_message);
+
+        Ok(())
+    }
+
+    /// Save the edits of a conflict anyway, overwriting the changes of the other terminal.
+    pub fn overwrite_conflict(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        if let Some(StaffConflict { mut edited, record }) = self.conflict.take() {
+            edited.version = record.version();
+            self.save(shared, edited)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the edits of a conflict and show the changes of the other terminal instead.
+    pub fn discard_conflict(&mut self, shared: &mut SharedData) {
+        if let Some(StaffConflict { record, .. }) = self.conflict.take() {
+            let idx = shared
+                .staff
+                .iter()
+                .position(|staff_member| staff_member.uuid() == record.uuid());
+            if let Some(idx) = idx {
+                let status = shared.staff[idx].status;
+                shared.staff[idx] = record.with_status(status);
+                shared
+                    .staff_names
+                    .update(slice::from_ref(&shared.staff[idx]));
+                if let Some(row) = self.rows.get_mut(idx) {
+                    *row = StaffRow::from_staff_member(&shared.staff[idx]);
+                }
+            }
+        }
+    }
+
+    /// Keep the edits of a conflict unsaved.
+    pub fn cancel_conflict(&mut self) {
+        self.conflict = None;
+    }
+
+    fn submit_new_row(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        // The row keeps its values if the new staff member is rejected, so that the input can be corrected.
+        let new_staff_member = self.new_row.new_staff_member()?;
+        if shared.settings.kiosk.personnel_number_login {
+            StaffMember::check_idents_disjoint(
+                &shared.staff,
+                None,
+                &new_staff_member.pin,
+                &new_staff_member.cardid,
+                new_staff_member.personnel_number.as_deref(),
+            )?;
+        }
+        let new_staff_member = shared.storage.insert_staff(new_staff_member)?;
+
+        self.rows
+            .push(StaffRow::from_staff_member(&new_staff_member));
+        self.new_row = StaffRow::default();
+
+        let success_message = format!(
+            "Neuer Mitarbeiter {} erfolgreich hinzugefügt.",
+            new_staff_member.name
+        );
+        shared.log_info(success_message);
+        shared.record_staff_history(vec![NewStaffHistoryEntry::added(
+            new_staff_member.uuid(),
+            shared.current_time.naive_local(),
+        )]);
+
+        shared
+            .staff_names
+            .update(slice::from_ref(&new_staff_member));
+        shared.staff.push(new_staff_member);
+
+        Ok(())
+    }
+
+    pub fn delete_row(&mut self, shared: &mut SharedData, idx: usize) -> Result<(), StechuhrError> {
+        if idx >= self.rows.len() {
+            return Err(ManagementError::IndexError(idx).into());
+        }
+        // The archive must be written before anything is deleted, if it fails the staff member is kept.
+        let staff_member = shared.staff[idx].clone();
+        let archive = staff_archive::write_archive(shared, &staff_member)?;
+        self.rows.remove(idx);
+        shared.staff.remove(idx);
+        let name = staff_member.name.clone();
+        let uuid = staff_member.uuid();
+
+        shared.storage.delete_staff_member(staff_member)?;
+        shared.record_staff_history(vec![NewStaffHistoryEntry::deleted(
+            uuid,
+            shared.current_time.naive_local(),
+        )]);
+
+        shared.prompt_message(format!(
+            "{} wurde gelöscht. Die Daten wurden archiviert in:\n{}",
+            name,
+            archive.display()
+        ));
+        Ok(())
+    }
+
+    /// Show the saved data of a staff member that was changed outside of the management tab.
+    pub fn reload_row(&mut self, shared: &SharedData, uuid: i32) {
+        let row = shared
+            .staff
+            .iter()
+            .position(|staff_member| staff_member.uuid() == uuid);
+        if let Some(idx) = row {
+            if let Some(row) = self.rows.get_mut(idx) {
+                *row = StaffRow::from_staff_member(&shared.staff[idx]);
+            }
+        }
+    }
+
+    /// Rebuild the rows after the staff list was reloaded from the database.
+    /// Unsaved edits are kept. If the same staff member was also changed by another terminal,
+    /// we warn that saving the edits would overwrite those changes.
+    pub fn staff_changed(
+        &mut self,
+        shared: &mut SharedData,
+        previous_staff: &[StaffMember],
+        changes: &db::StaffChanges,
+    ) {
+        let mut previous_rows = mem::take(&mut self.rows)
+            .into_iter()
+            .zip(previous_staff)
+            .filter(|(row, staff_member)| row.has_unsaved_edits(staff_member))
+            .map(|(row, staff_member)| (staff_member.uuid(), row))
+            .collect::<Vec<_>>();
+
+        let mut conflicts = Vec::new();
+        for staff_member in &shared.staff {
+            let edited = previous_rows
+                .iter()
+                .position(|(uuid, _)| *uuid == staff_member.uuid())
+                .map(|idx| previous_rows.remove(idx).1);
+
+            let row = match edited {
+                Some(mut row) => {
+                    if changes.changed.contains(&staff_member.uuid()) {
+                        conflicts.push(staff_member.name.clone());
+                    }
+                    row.is_visible = staff_member.is_visible;
+                    row
+                }
+                None => StaffRow::from_staff_member(staff_member),
+            };
+            self.rows.push(row);
+        }
+
+        if !conflicts.is_empty() {
+            shared.prompt_message(format!(
+                "{} wurde an einem anderen Terminal geändert. Beim Speichern deiner ungespeicherten Änderungen werden diese Änderungen überschrieben.",
+                conflicts.join(", ")
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stechuhr::settings::Settings;
+
+    use super::*;
+    use crate::recording_storage::RecordingStorage;
+
+    fn change(idx: usize, field: StaffField, value: &str) -> StaffListMessage {
+        StaffListMessage::Change(idx, field, String::from(value))
+    }
+
+    fn change_new(field: StaffField, value: &str) -> StaffListMessage {
+        StaffListMessage::ChangeNew(field, String::from(value))
+    }
+
+    #[test]
+    fn edit_row() {
+        let staff_member = DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )
+        .with_department(Some(String::from("Bar")))
+        .with_status(WorkStatus::Away);
+        let mut row = StaffRow::from_staff_member(&staff_member);
+        assert_eq!(row.value(StaffField::Department), "Bar");
+        assert_eq!(row.value(StaffField::Wage), "");
+        assert!(!row.has_unsaved_edits(&staff_member));
+
+        row.set(StaffField::Name, String::from("Aaron A."));
+        row.set(StaffField::Wage, String::from("12,50"));
+        row.is_minor = true;
+        assert!(row.has_unsaved_edits(&staff_member));
+        let edited = row.edited(&staff_member).unwrap();
+        assert_eq!(edited.name, "Aaron A.");
+        assert_eq!(edited.wage_cents_per_hour, Some(1250));
+        assert!(edited.is_minor);
+        assert_eq!(edited.uuid(), staff_member.uuid());
+
+        // Invalid values are only rejected when the row is saved.
+        row.set(StaffField::Pin, String::from("111"));
+        assert!(row.edited(&staff_member).is_err());
+        row.set(StaffField::Pin, String::from("1111"));
+        row.set(StaffField::Name, String::new());
+        assert!(row.edited(&staff_member).is_err());
+    }
+
+    #[test]
+    fn add_and_change_staff() {
+        let (storage, calls) = RecordingStorage::new();
+        let mut shared = SharedData::headless(Box::new(storage), Settings::default());
+        let mut staff_list = StaffList::new(&shared.staff);
+
+        for (field, value) in [
+            (StaffField::Name, "Aaron"),
+            (StaffField::Pin, "1111"),
+            (StaffField::CardId, "1111111111"),
+            (StaffField::Department, "Bar"),
+        ] {
+            staff_list
+                .update(&mut shared, change_new(field, value))
+                .unwrap();
+        }
+        staff_list
+            .update(&mut shared, StaffListMessage::SubmitNewRow)
+            .unwrap();
+        assert_eq!(shared.staff.len(), 1);
+        assert_eq!(shared.staff[0].department.as_deref(), Some("Bar"));
+        assert_eq!(staff_list.rows().len(), 1);
+        assert_eq!(staff_list.rows()[0].value(StaffField::Name), "Aaron");
+        assert_eq!(staff_list.new_row(), &StaffRow::default());
+
+        // A rejected new staff member keeps the typed values.
+        staff_list
+            .update(&mut shared, change_new(StaffField::Name, "Beeron"))
+            .unwrap();
+        assert!(staff_list
+            .update(&mut shared, StaffListMessage::SubmitNewRow)
+            .is_err());
+        assert_eq!(staff_list.new_row().value(StaffField::Name), "Beeron");
+        assert_eq!(shared.staff.len(), 1);
+
+        staff_list
+            .update(&mut shared, change(0, StaffField::Name, "Aaron A."))
+            .unwrap();
+        assert_eq!(shared.staff[0].name, "Aaron");
+        calls.borrow_mut().clear();
+        staff_list
+            .update(&mut shared, StaffListMessage::ToggleVisible(0, false))
+            .unwrap();
+        assert_eq!(shared.staff[0].name, "Aaron A.");
+        assert!(!shared.staff[0].is_visible);
+        assert_eq!(
+            calls.borrow()[..2],
+            ["save_staff_member", "insert_staff_history"]
+        );
+        assert!(staff_list.conflict().is_none());
+
+        assert!(staff_list
+            .update(&mut shared, change(1, StaffField::Name, "Ceeron"))
+            .is_err());
+    }
+
+    #[test]
+    fn keep_unsaved_edits() {
+        let (storage, _) = RecordingStorage::new();
+        let mut shared = SharedData::headless(Box::new(storage), Settings::default());
+        for (name, pin) in [("Aaron", "1111"), ("Beeron", "2222")] {
+            let new_staff_member = NewStaffMember::new(
+                String::from(name),
+                String::from(pin),
+                pin.repeat(3)[..10].to_owned(),
+                String::new(),
+            )
+            .unwrap();
+            let staff_member = shared.storage.insert_staff(new_staff_member).unwrap();
+            shared.staff.push(staff_member);
+        }
+        let mut staff_list = StaffList::new(&shared.staff);
+        staff_list
+            .update(&mut shared, change(1, StaffField::Name, "Beeron B."))
+            .unwrap();
+
+        // Another terminal changed Beeron and added someone in front of them.
+        let previous_staff = shared.staff.clone();
+        shared.staff.insert(1, shared.staff[0].clone());
+        shared.staff[2].name = String::from("Beeron C.");
+        let changes = db::StaffChanges {
+            changed: vec![shared.staff[2].uuid()],
+            ..db::StaffChanges::default()
+        };
+        staff_list.staff_changed(&mut shared, &previous_staff, &changes);
+
+        let names = staff_list
+            .rows()
+            .iter()
+            .map(|row| row.value(StaffField::Name))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Aaron", "Aaron", "Beeron B."]);
+        assert!(shared.prompt_modal_state.is_shown());
+
+        staff_list.reload_row(&shared, shared.staff[2].uuid());
+        assert_eq!(staff_list.rows()[2].value(StaffField::Name), "Beeron C.");
+    }
+}
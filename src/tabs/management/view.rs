@@ -0,0 +1,354 @@
+//! The widgets of the tab, built from the staff list and the other parts of the tab.
+use iced::{
+    alignment::{Horizontal, Vertical},
+    text_input, Alignment, Button, Checkbox, Column, Container, Element, Length, Row, Scrollable,
+    Space, Text, TextInput,
+};
+use iced_aw::{Card, Modal};
+use stechuhr::icons::{self, TEXT_SIZE_EMOJI};
+
+use super::staff_list::{StaffConflict, StaffRow};
+use super::{
+    staff_archive, ManagementMessage, ManagementTab, StaffCalendarMessage, StaffField,
+    StaffListMessage, INPUTS_PER_ROW, STAFF_ROW_HEIGHT,
+};
+use crate::tabs::components;
+use crate::SharedData;
+
+impl ManagementTab {
+    fn text_input<'a, F>(
+        state: &'a mut text_input::State,
+        placeholder: &str,
+        value: &str,
+        f: F,
+    ) -> TextInput<'a, ManagementMessage>
+    where
+        F: 'a + Fn(String) -> ManagementMessage,
+    {
+        stechuhr::style::text_input(state, placeholder, value, f)
+            .on_submit(ManagementMessage::GenericSubmit)
+            .width(Length::FillPortion(3))
+    }
+
+    /// A row with the text inputs of a staff member, or of the new staff member.
+    fn staff_inputs<'a, F>(
+        input_states: &'a mut [text_input::State; INPUTS_PER_ROW],
+        staff_row: &StaffRow,
+        is_new_row: bool,
+        f: F,
+    ) -> Row<'a, ManagementMessage>
+    where
+        F: 'a + Copy + Fn(StaffField, String) -> ManagementMessage,
+    {
+        const SPACING: u16 = 1;
+        let mut row = Row::new();
+        for (idx, (state, field)) in input_states.iter_mut().zip(StaffField::ALL).enumerate() {
+            let (placeholder, width) = match field {
+                StaffField::Name => ("Name eingeben", 17),
+                StaffField::DisplayName => ("Anzeigename", 15),
+                StaffField::Pin => ("PIN eingeben", 12),
+                StaffField::CardId if is_new_row => ("click & swipe RFID dongle", 18),
+                StaffField::CardId => ("Dongle swipen", 18),
+                StaffField::PersonnelNumber => ("Personalnr.", 12),
+                StaffField::Department => ("Abteilung", 15),
+                StaffField::Wage => ("€/Std.", 9),
+            };
+            if idx > 0 {
+                row = row.push(Space::new(Length::FillPortion(SPACING), Length::Shrink));
+            }
+            row = row.push(
+                ManagementTab::text_input(state, placeholder, staff_row.value(field), move |s| {
+                    f(field, s)
+                })
+                .width(Length::FillPortion(width)),
+            );
+        }
+        row
+    }
+
+    pub(super) fn internal_view(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Element<'_, ManagementMessage> {
+        self.resize_row_widgets();
+        let staff_edit = Scrollable::new(&mut self.staff_scroll_state)
+            .on_scroll(ManagementMessage::StaffScrolled);
+        // Only the rows that can be visible are built, see `VirtualList`.
+        let rows = self.staff_list.rows().len();
+        let visible = self.staff_rows.visible_rows(rows, shared.window_height);
+        let mut even = visible.start.is_multiple_of(2);
+        let mut built = Vec::with_capacity(visible.len());
+
+        for (idx, (widgets, staff_row)) in self
+            .row_widgets
+            .iter_mut()
+            .zip(self.staff_list.rows())
+            .enumerate()
+            .skip(visible.start)
+            .take(visible.len())
+        {
+            let inputs = ManagementTab::staff_inputs(
+                &mut widgets.input_states,
+                staff_row,
+                false,
+                move |field, s| ManagementMessage::Staff(StaffListMessage::Change(idx, field, s)),
+            );
+            let staff_row = Container::new(
+                inputs
+                    .push(Space::new(Length::FillPortion(5), Length::Shrink))
+                    .push(
+                        Checkbox::new(
+                            staff_row.is_visible(),
+                            icons::emoji::eye.codepoint,
+                            move |b| {
+                                ManagementMessage::Staff(StaffListMessage::ToggleVisible(idx, b))
+                            },
+                        )
+                        .font(icons::FONT_SYMBOLA)
+                        .text_size(TEXT_SIZE_EMOJI)
+                        .width(Length::FillPortion(8)),
+                    )
+                    .push(
+                        Checkbox::new(staff_row.is_minor(), "U18", move |b| {
+                            ManagementMessage::Staff(StaffListMessage::ToggleMinor(idx, b))
+                        })
+                        .width(Length::FillPortion(8)),
+                    )
+                    .push(
+                        Button::new(
+                            &mut widgets.calendar_state,
+                            icons::icon(icons::emoji::calendar),
+                        )
+                        .on_press(ManagementMessage::Calendar(StaffCalendarMessage::Open(idx)))
+                        .width(Length::FillPortion(5)),
+                    )
+                    .push(
+                        Button::new(
+                            &mut widgets.delete_state,
+                            icons::icon(icons::emoji::trashcan),
+                        )
+                        .on_press(ManagementMessage::DeleteRow(idx))
+                        .width(Length::FillPortion(5)),
+                    )
+                    .push(
+                        Button::new(
+                            &mut widgets.submit_state,
+                            icons::icon(icons::emoji::floppydisk),
+                        )
+                        .on_press(ManagementMessage::Staff(StaffListMessage::SubmitRow(idx)))
+                        .width(Length::FillPortion(5)),
+                    )
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
+            )
+            .height(Length::Units(STAFF_ROW_HEIGHT))
+            .center_y()
+            .style(stechuhr::style::management_row(&mut even));
+            built.push(staff_row.into());
+        }
+        let mut staff_edit = self.staff_rows.push_rows(staff_edit, rows, visible, built);
+
+        // last inputs for new staff member
+        {
+            even = rows.is_multiple_of(2);
+            let inputs = ManagementTab::staff_inputs(
+                &mut self.new_input_states,
+                self.staff_list.new_row(),
+                true,
+                |field, s| ManagementMessage::Staff(StaffListMessage::ChangeNew(field, s)),
+            );
+            let new_row = Container::new(
+                inputs
+                    .push(Space::new(Length::FillPortion(5), Length::Shrink))
+                    .push(Space::new(Length::FillPortion(26), Length::Shrink))
+                    .push(
+                        Button::new(
+                            &mut self.new_submit_state,
+                            icons::icon(icons::emoji::floppydisk),
+                        )
+                        .on_press(ManagementMessage::Staff(StaffListMessage::SubmitNewRow))
+                        .width(Length::FillPortion(5)),
+                    )
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
+            )
+            .style(stechuhr::style::management_row(&mut even));
+            staff_edit = staff_edit.push(new_row);
+        }
+
+        let staff_edit = Container::new(staff_edit)
+            .width(Length::Fill)
+            .height(Length::FillPortion(90))
+            .center_x()
+            .align_y(Vertical::Top);
+
+        let marker_controls = Row::new()
+            .push(Text::new("Markierung im Protokoll"))
+            .push(
+                stechuhr::style::text_input(
+                    &mut self.marker_state,
+                    "z.B. Sperrstunde, Polizei-Kontrolle",
+                    &self.marker_value,
+                    ManagementMessage::ChangeMarker,
+                )
+                .on_submit(ManagementMessage::SubmitMarker)
+                .width(Length::FillPortion(3)),
+            )
+            .push(
+                Button::new(
+                    &mut self.marker_submit_state,
+                    Text::new("Eintragen").horizontal_alignment(Horizontal::Center),
+                )
+                .on_press(ManagementMessage::SubmitMarker),
+            )
+            .spacing(20)
+            .padding(10)
+            .align_items(Alignment::Center);
+
+        let mut staff_edit = Column::new().push(staff_edit);
+        if shared.settings.kiosk.on_screen_keyboard {
+            staff_edit = staff_edit.push(self.keyboard.view(ManagementMessage::InputKey));
+        }
+        let staff_edit = staff_edit
+            .push(marker_controls)
+            .push(self.announcement.view());
+        let content = self.shift_import.view(staff_edit.into());
+        let content = self.calendar.view(content);
+
+        let delete_modal_value = if let Some(delete_idx) = self.delete_idx {
+            if let Some(staff_member) = shared.staff.get(delete_idx) {
+                format!(
+                    "{} wird gelöscht. Vorher werden alle Daten in eine Datei im Ordner {} archiviert. Korrekt?",
+                    staff_member.name,
+                    staff_archive::archive_dir(&shared.settings).display()
+                )
+            } else {
+                String::from("Warnung: das solltest du nicht sehen. Bitte Adrian Bescheid geben.")
+            }
+        } else {
+            String::from("Warnung: das solltest du nicht sehen. Bitte Adrian Bescheid geben.")
+        };
+
+        let modal = Modal::new(&mut self.delete_modal_state, content, move |state| {
+            Card::new(
+                Text::new("Löschen eines Mitarbeiters"),
+                Text::new(&delete_modal_value),
+            )
+            .foot(components::confirm_foot(
+                &mut state.delete_confirm_state,
+                "Ok",
+                Some(ManagementMessage::ConfirmDeleteRow),
+                &mut state.delete_cancel_state,
+                "Zurück",
+                ManagementMessage::CancelDeleteRow,
+            ))
+            .width(Length::Shrink)
+            .on_close(ManagementMessage::CancelDeleteRow)
+            .into()
+        })
+        .backdrop(ManagementMessage::CancelDeleteRow)
+        .on_esc(ManagementMessage::CancelDeleteRow);
+
+        let conflict_modal_value = self
+            .staff_list
+            .conflict()
+            .map(StaffConflict::description)
+            .unwrap_or_default();
+
+        let modal = Modal::new(&mut self.conflict_modal_state, modal, move |state| {
+            Card::new(
+                Text::new("Gleichzeitige Änderung"),
+                Text::new(&conflict_modal_value),
+            )
+            .foot(
+                Row::new()
+                    .spacing(10)
+                    .padding(5)
+                    .push(
+                        components::modal_button(&mut state.overwrite_state, "Meine speichern")
+                            .on_press(ManagementMessage::OverwriteConflict),
+                    )
+                    .push(
+                        components::modal_button(&mut state.discard_state, "Andere übernehmen")
+                            .on_press(ManagementMessage::DiscardConflict),
+                    )
+                    .push(
+                        components::modal_button(&mut state.cancel_state, "Zurück")
+                            .on_press(ManagementMessage::CancelConflict),
+                    ),
+            )
+            .max_width(700)
+            .on_close(ManagementMessage::CancelConflict)
+            .into()
+        })
+        .backdrop(ManagementMessage::CancelConflict)
+        .on_esc(ManagementMessage::CancelConflict);
+
+        modal.into()
+    }
+
+    pub(super) fn public_view(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Element<'_, ManagementMessage> {
+        if shared.prompt_modal_state.is_shown() {
+            self.admin_password_state.unfocus();
+        }
+
+        let mut password = Column::new()
+            .push(
+                Row::new()
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink))
+                    .push(
+                        stechuhr::style::text_input(
+                            &mut self.admin_password_state,
+                            "Administrator Passwort",
+                            &self.admin_password_value,
+                            ManagementMessage::ChangePasswordInput,
+                        )
+                        .password()
+                        .on_submit(ManagementMessage::SubmitPassword)
+                        .width(Length::FillPortion(3)),
+                    )
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
+            )
+            .spacing(20)
+            .align_items(Alignment::Center);
+        if shared.settings.kiosk.on_screen_keyboard {
+            password = password.push(self.keyboard.view(ManagementMessage::PasswordKey));
+        }
+
+        let content = Column::new()
+            .push(Space::new(Length::Fill, Length::Units(100)))
+            .push(password)
+            .push(
+                Button::new(
+                    &mut self.whoami_button_state,
+                    Text::new("Dongle prüfen").horizontal_alignment(Horizontal::Center),
+                )
+                .on_press(ManagementMessage::Whoami),
+            )
+            // .padding(100)
+            .spacing(100)
+            .align_items(Alignment::Center);
+
+        let whoami_modal = Modal::new(&mut self.whoami_modal_state, content, move |state| {
+            Card::new(Text::new("Dongle-Diagnose"), {
+                state.input_state.focus();
+                stechuhr::style::text_input(
+                    &mut state.input_state,
+                    "",
+                    &state.input_value,
+                    ManagementMessage::ChangeWhoami,
+                )
+                .on_submit(ManagementMessage::SubmitWhoami)
+            })
+            .max_width(300)
+            .width(Length::Fill)
+            .on_close(ManagementMessage::CancelWhoami)
+            .into()
+        })
+        .backdrop(ManagementMessage::CancelWhoami)
+        .on_esc(ManagementMessage::CancelWhoami);
+
+        whoami_modal.into()
+    }
+}
@@ -1,3 +1,6 @@
+pub mod components;
 pub mod management;
+pub mod requests;
+pub mod settings;
 pub mod statistics;
 pub mod timetrack;
@@ -0,0 +1,17 @@
+//! Requests of one tab to another. A tab only gets the shared data in its update, so instead of reaching into
+//! the other tabs it queues a request with `SharedData::request`. The application routes it to the receiving tab
+//! after the update, see `Stechuhr::route_requests`. Each receiving tab defines what can be requested from it.
+use std::path::PathBuf;
+
+use super::management::ManagementRequest;
+use crate::ExitMode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabRequest {
+    Management(ManagementRequest),
+    /// Close the application itself.
+    Exit(ExitMode),
+    /// Open the folder dialog of the system in the given directory. It runs asynchronously so that the window keeps
+    /// updating, the chosen directory is sent back as `StatsMessage::ExportDirChosen`.
+    PickExportDir(PathBuf),
+}
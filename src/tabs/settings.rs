@@ -0,0 +1,824 @@
+//! Tab to change the runtime settings of Stechuhr.
+use iced::{
+    alignment::Horizontal, button, keyboard, pick_list, text_input, window, Alignment, Button,
+    Checkbox, Column, Element, Length, PickList, Row, Space, Text,
+};
+use iced_aw::TabLabel;
+use iced_native::Event;
+use std::path::PathBuf;
+use stechuhr::{
+    audit,
+    export_sink::SinkSettings,
+    settings::{
+        ClosePolicy, DatevSettings, KioskSettings, NightSummaryMode, Rounding, Settings,
+        SettingsError,
+    },
+};
+
+use crate::tabs::components::{self, OnScreenKey, OnScreenKeyboard};
+use crate::tabs::management::ManagementError;
+use crate::tabs::requests::TabRequest;
+use crate::{ExitMode, Message, SharedData, StechuhrError, Tab, TAB_PADDING};
+
+pub struct SettingsTab {
+    /* wether we are logged in */
+    authorized: bool,
+    admin_password_value: String,
+    admin_password_state: text_input::State,
+    keyboard: OnScreenKeyboard,
+    /* inputs for the settings */
+    export_dir_value: String,
+    export_dir_state: text_input::State,
+    cutoff_value: String,
+    cutoff_state: text_input::State,
+    bucket_values: [String; 3],
+    bucket_states: [text_input::State; 3],
+    locale_value: String,
+    locale_state: text_input::State,
+    export_locale_value: String,
+    export_locale_state: text_input::State,
+    end_event_word_value: String,
+    end_event_word_state: text_input::State,
+    sunday_buckets: bool,
+    saturday_buckets: bool,
+    surcharge_values: [String; 6],
+    surcharge_states: [text_input::State; 6],
+    /* numbers for the DATEV export, an empty wage type is not configured */
+    consultant_number_value: String,
+    consultant_number_state: text_input::State,
+    client_number_value: String,
+    client_number_state: text_input::State,
+    wage_type_values: [String; 6],
+    wage_type_states: [text_input::State; 6],
+    xlsx_export: bool,
+    wage_column: bool,
+    show_former_names: bool,
+    rounding: Rounding,
+    rounding_state: pick_list::State<Rounding>,
+    night_summary: NightSummaryMode,
+    night_summary_state: pick_list::State<NightSummaryMode>,
+    fullscreen: bool,
+    colorblind_icons: bool,
+    on_screen_keyboard: bool,
+    correct_keyboard_layout: bool,
+    personnel_number_login: bool,
+    close_policy: ClosePolicy,
+    close_policy_state: pick_list::State<ClosePolicy>,
+    /* name and enabled flag of the export sinks from the settings file */
+    export_sinks: Vec<(String, bool)>,
+    columns_value: String,
+    columns_state: text_input::State,
+    save_state: button::State,
+    reset_state: button::State,
+    exit_states: [button::State; 3],
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    /* Pre Login */
+    ChangePasswordInput(String),
+    SubmitPassword,
+    PasswordKey(OnScreenKey),
+    /* After Login */
+    InputKey(OnScreenKey),
+    ChangeExportDir(String),
+    ChangeCutoff(String),
+    ChangeBucket(usize, String),
+    ChangeLocale(String),
+    ChangeExportLocale(String),
+    ChangeEndEventWord(String),
+    ToggleSundayBuckets(bool),
+    ToggleSaturdayBuckets(bool),
+    ChangeSurcharge(usize, String),
+    ChangeConsultantNumber(String),
+    ChangeClientNumber(String),
+    ChangeWageType(usize, String),
+    ToggleXlsxExport(bool),
+    ToggleWageColumn(bool),
+    ToggleShowFormerNames(bool),
+    ChooseRounding(Rounding),
+    ChooseNightSummary(NightSummaryMode),
+    ToggleFullscreen(bool),
+    ToggleColorblindIcons(bool),
+    ToggleOnScreenKeyboard(bool),
+    ToggleCorrectKeyboardLayout(bool),
+    TogglePersonnelNumberLogin(bool),
+    ChooseClosePolicy(ClosePolicy),
+    ToggleExportSink(usize, bool),
+    ChangeColumns(String),
+    Save,
+    Reset,
+    Exit(ExitMode),
+    HandleEvent(Event),
+}
+
+impl SettingsTab {
+    pub fn new() -> Self {
+        SettingsTab {
+            authorized: false,
+            admin_password_value: String::new(),
+            admin_password_state: text_input::State::default(),
+            keyboard: OnScreenKeyboard::default(),
+            export_dir_value: String::new(),
+            export_dir_state: text_input::State::default(),
+            cutoff_value: String::new(),
+            cutoff_state: text_input::State::default(),
+            bucket_values: Default::default(),
+            bucket_states: Default::default(),
+            locale_value: String::new(),
+            locale_state: text_input::State::default(),
+            export_locale_value: String::new(),
+            export_locale_state: text_input::State::default(),
+            end_event_word_value: String::new(),
+            end_event_word_state: text_input::State::default(),
+            sunday_buckets: false,
+            saturday_buckets: false,
+            surcharge_values: Default::default(),
+            surcharge_states: Default::default(),
+            consultant_number_value: String::new(),
+            consultant_number_state: text_input::State::default(),
+            client_number_value: String::new(),
+            client_number_state: text_input::State::default(),
+            wage_type_values: Default::default(),
+            wage_type_states: Default::default(),
+            xlsx_export: false,
+            wage_column: false,
+            show_former_names: true,
+            rounding: Rounding::Off,
+            rounding_state: pick_list::State::default(),
+            night_summary: NightSummaryMode::Off,
+            night_summary_state: pick_list::State::default(),
+            fullscreen: true,
+            colorblind_icons: false,
+            on_screen_keyboard: false,
+            correct_keyboard_layout: false,
+            personnel_number_login: false,
+            close_policy: ClosePolicy::Block,
+            close_policy_state: pick_list::State::default(),
+            export_sinks: Vec::new(),
+            columns_value: String::new(),
+            columns_state: text_input::State::default(),
+            save_state: button::State::default(),
+            reset_state: button::State::default(),
+            exit_states: Default::default(),
+        }
+    }
+
+    pub fn deauth(&mut self) {
+        self.authorized = false;
+    }
+
+    /// Fill the inputs with the currently active settings.
+    fn load_values(&mut self, settings: &Settings) {
+        self.export_dir_value = settings.export_dir.to_string_lossy().into_owned();
+        self.cutoff_value = settings.cutoff_time.format("%H:%M").to_string();
+        for (value, bucket_start) in self.bucket_values.iter_mut().zip(settings.bucket_starts) {
+            *value = bucket_start.to_string();
+        }
+        self.locale_value.clone_from(&settings.locale);
+        self.export_locale_value.clone_from(&settings.export_locale);
+        self.end_event_word_value
+            .clone_from(&settings.end_event_word);
+        self.sunday_buckets = settings.sunday_buckets;
+        self.saturday_buckets = settings.saturday_buckets;
+        for (value, surcharge) in self
+            .surcharge_values
+            .iter_mut()
+            .zip(settings.wage_surcharges)
+        {
+            *value = surcharge.to_string();
+        }
+        self.consultant_number_value
+            .clone_from(&settings.datev.consultant_number);
+        self.client_number_value
+            .clone_from(&settings.datev.client_number);
+        for (value, wage_type) in self
+            .wage_type_values
+            .iter_mut()
+            .zip(settings.datev.wage_types)
+        {
+            *value = if wage_type == 0 {
+                String::new()
+            } else {
+                wage_type.to_string()
+            };
+        }
+        self.xlsx_export = settings.xlsx_export;
+        self.wage_column = settings.wage_column;
+        self.show_former_names = settings.show_former_names;
+        self.rounding = settings.rounding;
+        self.night_summary = settings.night_summary;
+        self.fullscreen = settings.kiosk.fullscreen;
+        self.colorblind_icons = settings.kiosk.colorblind_icons;
+        self.on_screen_keyboard = settings.kiosk.on_screen_keyboard;
+        self.correct_keyboard_layout = settings.kiosk.correct_keyboard_layout;
+        self.personnel_number_login = settings.kiosk.personnel_number_login;
+        self.close_policy = settings.kiosk.close_policy;
+        self.columns_value = settings.kiosk.dashboard_columns.to_string();
+        self.export_sinks = settings
+            .export_sinks
+            .iter()
+            .map(|sink| (sink.name.clone(), sink.enabled))
+            .collect();
+    }
+
+    /// Parse and validate the inputs. Settings without an input are taken from the current settings.
+    fn parse_values(&self, current: &Settings) -> Result<Settings, StechuhrError> {
+        let mut bucket_starts = [0; 3];
+        for (bucket_start, value) in bucket_starts.iter_mut().zip(&self.bucket_values) {
+            *bucket_start = Settings::parse_bucket(value)?;
+        }
+        let mut wage_surcharges = [0; 6];
+        for (surcharge, value) in wage_surcharges.iter_mut().zip(&self.surcharge_values) {
+            *surcharge = Settings::parse_surcharge(value)?;
+        }
+        let mut wage_types = [0; 6];
+        for (wage_type, value) in wage_types.iter_mut().zip(&self.wage_type_values) {
+            *wage_type = Settings::parse_wage_type(value)?;
+        }
+
+        let settings = Settings {
+            export_dir: PathBuf::from(self.export_dir_value.trim()),
+            cutoff_time: Settings::parse_cutoff(&self.cutoff_value)?,
+            bucket_starts,
+            locale: self.locale_value.trim().to_owned(),
+            export_locale: self.export_locale_value.trim().to_owned(),
+            end_event_word: self.end_event_word_value.trim().to_owned(),
+            sunday_buckets: self.sunday_buckets,
+            saturday_buckets: self.saturday_buckets,
+            xlsx_export: self.xlsx_export,
+            wage_column: self.wage_column,
+            wage_surcharges,
+            datev: DatevSettings {
+                consultant_number: Settings::parse_consultant_number(
+                    &self.consultant_number_value,
+                )?,
+                client_number: Settings::parse_client_number(&self.client_number_value)?,
+                wage_types,
+            },
+            rounding: self.rounding,
+            show_former_names: self.show_former_names,
+            night_summary: self.night_summary,
+            export_sinks: current
+                .export_sinks
+                .iter()
+                .zip(&self.export_sinks)
+                .map(|(sink, (_, enabled))| SinkSettings {
+                    enabled: *enabled,
+                    ..sink.clone()
+                })
+                .collect(),
+            kiosk: KioskSettings {
+                fullscreen: self.fullscreen,
+                dashboard_columns: Settings::parse_columns(&self.columns_value)?,
+                colorblind_icons: self.colorblind_icons,
+                on_screen_keyboard: self.on_screen_keyboard,
+                correct_keyboard_layout: self.correct_keyboard_layout,
+                personnel_number_login: self.personnel_number_login,
+                close_policy: self.close_policy,
+                reader_device: current.kiosk.reader_device.clone(),
+            },
+            ..current.clone()
+        };
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Text inputs with their values in the order in which they are shown, for moving the focus with the tab key and
+    /// for the on-screen keyboard.
+    fn inputs(&mut self) -> Vec<(&mut text_input::State, &mut String)> {
+        if !self.authorized {
+            return vec![(
+                &mut self.admin_password_state,
+                &mut self.admin_password_value,
+            )];
+        }
+
+        let mut inputs = vec![
+            (&mut self.export_dir_state, &mut self.export_dir_value),
+            (&mut self.cutoff_state, &mut self.cutoff_value),
+        ];
+        inputs.extend(self.bucket_states.iter_mut().zip(&mut self.bucket_values));
+        inputs.push((&mut self.locale_state, &mut self.locale_value));
+        inputs.push((&mut self.export_locale_state, &mut self.export_locale_value));
+        inputs.extend(
+            self.surcharge_states
+                .iter_mut()
+                .zip(&mut self.surcharge_values),
+        );
+        inputs.push((
+            &mut self.consultant_number_state,
+            &mut self.consultant_number_value,
+        ));
+        inputs.push((&mut self.client_number_state, &mut self.client_number_value));
+        inputs.extend(
+            self.wage_type_states
+                .iter_mut()
+                .zip(&mut self.wage_type_values),
+        );
+        inputs.push((
+            &mut self.end_event_word_state,
+            &mut self.end_event_word_value,
+        ));
+        inputs.push((&mut self.columns_state, &mut self.columns_value));
+        inputs
+    }
+
+    /// Save the settings to the settings file and apply them immediately.
+    fn save(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let settings = self.parse_values(&shared.settings)?;
+        // Personnel numbers are only kept apart from PINs and dongles while the login is on, see `check_idents_disjoint`.
+        if settings.kiosk.personnel_number_login {
+            let problems = audit::audit_staff(&shared.staff, true);
+            if !problems.is_empty() {
+                return Err(SettingsError::IdentsNotDisjoint(
+                    problems.iter().map(|problem| problem.to_string()).collect(),
+                )
+                .into());
+            }
+        }
+        settings.save()?;
+
+        if settings.kiosk.fullscreen != shared.settings.kiosk.fullscreen {
+            shared.window_mode = if settings.kiosk.fullscreen {
+                window::Mode::Fullscreen
+            } else {
+                window::Mode::Windowed
+            };
+        }
+        shared.settings = settings;
+        shared.log_info(String::from("Einstellungen erfolgreich gespeichert."));
+
+        Ok(())
+    }
+
+    fn setting_row<'a>(
+        label: &str,
+        input: impl Into<Element<'a, SettingsMessage>>,
+    ) -> Row<'a, SettingsMessage> {
+        Row::new()
+            .push(Text::new(label).width(Length::FillPortion(2)))
+            .push(Row::new().push(input).width(Length::FillPortion(3)))
+            .spacing(20)
+            .align_items(Alignment::Center)
+    }
+
+    fn internal_view(&mut self, on_screen_keyboard: bool) -> Element<'_, SettingsMessage> {
+        let bucket_inputs = self
+            .bucket_states
+            .iter_mut()
+            .zip(&self.bucket_values)
+            .enumerate()
+            .fold(Row::new().spacing(10), |row, (idx, (state, value))| {
+                row.push(
+                    stechuhr::style::text_input(state, "Stunde", value, move |s| {
+                        SettingsMessage::ChangeBucket(idx, s)
+                    })
+                    .on_submit(SettingsMessage::Save)
+                    .width(Length::Units(80)),
+                )
+            });
+        let surcharge_inputs = self
+            .surcharge_states
+            .iter_mut()
+            .zip(&self.surcharge_values)
+            .enumerate()
+            .fold(Row::new().spacing(10), |row, (idx, (state, value))| {
+                row.push(
+                    stechuhr::style::text_input(state, "%", value, move |s| {
+                        SettingsMessage::ChangeSurcharge(idx, s)
+                    })
+                    .on_submit(SettingsMessage::Save)
+                    .width(Length::Units(60)),
+                )
+            });
+        let wage_type_inputs = self
+            .wage_type_states
+            .iter_mut()
+            .zip(&self.wage_type_values)
+            .enumerate()
+            .fold(Row::new().spacing(10), |row, (idx, (state, value))| {
+                row.push(
+                    stechuhr::style::text_input(state, "Nr.", value, move |s| {
+                        SettingsMessage::ChangeWageType(idx, s)
+                    })
+                    .on_submit(SettingsMessage::Save)
+                    .width(Length::Units(60)),
+                )
+            });
+        let sink_inputs = if self.export_sinks.is_empty() {
+            Column::new().push(Text::new("keine in der Einstellungsdatei eingetragen"))
+        } else {
+            self.export_sinks.iter().enumerate().fold(
+                Column::new().spacing(10),
+                |column, (idx, (name, enabled))| {
+                    column.push(Checkbox::new(*enabled, name, move |b| {
+                        SettingsMessage::ToggleExportSink(idx, b)
+                    }))
+                },
+            )
+        };
+
+        let settings = Column::new()
+            .push(SettingsTab::setting_row(
+                "Exportverzeichnis",
+                stechuhr::style::text_input(
+                    &mut self.export_dir_state,
+                    "./auswertung",
+                    &self.export_dir_value,
+                    SettingsMessage::ChangeExportDir,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Tagesgrenze (HH:MM)",
+                stechuhr::style::text_input(
+                    &mut self.cutoff_state,
+                    "06:00",
+                    &self.cutoff_value,
+                    SettingsMessage::ChangeCutoff,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Beginn der Zeitabschnitte (Tag, Abend, Nacht, gleiche Stunde entfällt)",
+                bucket_inputs,
+            ))
+            .push(SettingsTab::setting_row(
+                "Sonn- und Feiertage getrennt auswerten",
+                Checkbox::new(
+                    self.sunday_buckets,
+                    "",
+                    SettingsMessage::ToggleSundayBuckets,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Samstage wie Sonntage auswerten",
+                Checkbox::new(
+                    self.saturday_buckets,
+                    "",
+                    SettingsMessage::ToggleSaturdayBuckets,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Sprache",
+                stechuhr::style::text_input(
+                    &mut self.locale_state,
+                    "de_DE",
+                    &self.locale_value,
+                    SettingsMessage::ChangeLocale,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Sprache der Exporte",
+                stechuhr::style::text_input(
+                    &mut self.export_locale_state,
+                    "de_DE",
+                    &self.export_locale_value,
+                    SettingsMessage::ChangeExportLocale,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Auswertung zusätzlich als Excel-Datei",
+                Checkbox::new(self.xlsx_export, "", SettingsMessage::ToggleXlsxExport),
+            ))
+            .push(SettingsTab::setting_row(
+                "Lohn aus dem Stundenlohn berechnen",
+                Checkbox::new(self.wage_column, "", SettingsMessage::ToggleWageColumn),
+            ))
+            .push(SettingsTab::setting_row(
+                "Zuschläge in Prozent (Tag, Abend, Nacht, dann dieselben an Sonn- und Feiertagen)",
+                surcharge_inputs,
+            ))
+            .push(SettingsTab::setting_row(
+                "DATEV Beraternummer",
+                stechuhr::style::text_input(
+                    &mut self.consultant_number_state,
+                    "vom Steuerbüro",
+                    &self.consultant_number_value,
+                    SettingsMessage::ChangeConsultantNumber,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "DATEV Mandantennummer",
+                stechuhr::style::text_input(
+                    &mut self.client_number_state,
+                    "vom Steuerbüro",
+                    &self.client_number_value,
+                    SettingsMessage::ChangeClientNumber,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "DATEV Lohnarten (Tag, Abend, Nacht, dann dieselben an Sonn- und Feiertagen)",
+                wage_type_inputs,
+            ))
+            .push(SettingsTab::setting_row(
+                "Rundung der Minuten in der Auswertung",
+                PickList::new(
+                    &mut self.rounding_state,
+                    &Rounding::ALL[..],
+                    Some(self.rounding),
+                    SettingsMessage::ChooseRounding,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Exporte zusätzlich senden an",
+                sink_inputs,
+            ))
+            .push(SettingsTab::setting_row(
+                "Bestätigungswort für \"Event beenden\"",
+                stechuhr::style::text_input(
+                    &mut self.end_event_word_state,
+                    "leer lassen für kein Bestätigungswort",
+                    &self.end_event_word_value,
+                    SettingsMessage::ChangeEndEventWord,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Frühere Namen im Protokoll anzeigen",
+                Checkbox::new(
+                    self.show_former_names,
+                    "",
+                    SettingsMessage::ToggleShowFormerNames,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Zusammenfassung am Ende des Betriebstags",
+                PickList::new(
+                    &mut self.night_summary_state,
+                    &NightSummaryMode::ALL[..],
+                    Some(self.night_summary),
+                    SettingsMessage::ChooseNightSummary,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Vollbild beim Start",
+                Checkbox::new(self.fullscreen, "", SettingsMessage::ToggleFullscreen),
+            ))
+            .push(SettingsTab::setting_row(
+                "Spalten in der Stechuhr-Übersicht",
+                stechuhr::style::text_input(
+                    &mut self.columns_state,
+                    "3",
+                    &self.columns_value,
+                    SettingsMessage::ChangeColumns,
+                )
+                .on_submit(SettingsMessage::Save),
+            ))
+            .push(SettingsTab::setting_row(
+                "Farbenblind-freundliche Symbole",
+                Checkbox::new(
+                    self.colorblind_icons,
+                    "",
+                    SettingsMessage::ToggleColorblindIcons,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Bildschirmtastatur",
+                Checkbox::new(
+                    self.on_screen_keyboard,
+                    "",
+                    SettingsMessage::ToggleOnScreenKeyboard,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Tastaturbelegung des Dongle-Lesers korrigieren",
+                Checkbox::new(
+                    self.correct_keyboard_layout,
+                    "",
+                    SettingsMessage::ToggleCorrectKeyboardLayout,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Anmeldung mit Personalnummer",
+                Checkbox::new(
+                    self.personnel_number_login,
+                    "",
+                    SettingsMessage::TogglePersonnelNumberLogin,
+                ),
+            ))
+            .push(SettingsTab::setting_row(
+                "Schließen während Personen arbeiten",
+                PickList::new(
+                    &mut self.close_policy_state,
+                    &ClosePolicy::ALL[..],
+                    Some(self.close_policy),
+                    SettingsMessage::ChooseClosePolicy,
+                ),
+            ))
+            .push(
+                Row::new()
+                    .push(
+                        Button::new(
+                            &mut self.save_state,
+                            Text::new("Speichern").horizontal_alignment(Horizontal::Center),
+                        )
+                        .on_press(SettingsMessage::Save),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.reset_state,
+                            Text::new("Zurücksetzen").horizontal_alignment(Horizontal::Center),
+                        )
+                        .on_press(SettingsMessage::Reset),
+                    )
+                    .spacing(20),
+            )
+            .push(SettingsTab::setting_row(
+                "Stechuhr schließen",
+                ExitMode::ALL.iter().zip(self.exit_states.iter_mut()).fold(
+                    Row::new().spacing(20),
+                    |row, (mode, state)| {
+                        row.push(
+                            Button::new(
+                                state,
+                                Text::new(mode.to_string())
+                                    .horizontal_alignment(Horizontal::Center),
+                            )
+                            .on_press(SettingsMessage::Exit(*mode)),
+                        )
+                    },
+                ),
+            ))
+            .spacing(20)
+            .max_width(900);
+
+        if on_screen_keyboard {
+            Row::new()
+                .push(settings)
+                .push(self.keyboard.view(SettingsMessage::InputKey))
+                .spacing(20)
+                .into()
+        } else {
+            settings.into()
+        }
+    }
+
+    fn public_view(&mut self, on_screen_keyboard: bool) -> Element<'_, SettingsMessage> {
+        let mut content = Column::new()
+            .push(Space::new(Length::Fill, Length::Units(100)))
+            .push(
+                Row::new()
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink))
+                    .push(
+                        stechuhr::style::text_input(
+                            &mut self.admin_password_state,
+                            "Administrator Passwort",
+                            &self.admin_password_value,
+                            SettingsMessage::ChangePasswordInput,
+                        )
+                        .password()
+                        .on_submit(SettingsMessage::SubmitPassword)
+                        .width(Length::FillPortion(3)),
+                    )
+                    .push(Space::new(Length::FillPortion(2), Length::Shrink)),
+            )
+            .spacing(20)
+            .align_items(Alignment::Center);
+
+        if on_screen_keyboard {
+            content = content.push(self.keyboard.view(SettingsMessage::PasswordKey));
+        }
+        content.into()
+    }
+}
+
+impl Tab for SettingsTab {
+    type Message = SettingsMessage;
+
+    fn title(&self) -> String {
+        String::from("Einstellungen")
+    }
+
+    fn tab_label(&self) -> TabLabel {
+        TabLabel::Text(self.title())
+    }
+
+    fn content(&mut self, shared: &mut SharedData) -> Element<'_, Message> {
+        let content: Element<'_, SettingsMessage> = if self.authorized {
+            self.admin_password_state.unfocus();
+            let focused = self
+                .inputs()
+                .iter()
+                .position(|(state, _)| state.is_focused());
+            self.keyboard.track_focus(focused);
+
+            self.internal_view(shared.settings.kiosk.on_screen_keyboard)
+        } else {
+            /* Unfocus when the prompt is shown, else it captures the 'enter' meant to close it. */
+            if shared.prompt_modal_state.is_shown() {
+                self.admin_password_state.unfocus();
+            } else {
+                self.admin_password_state.focus();
+            }
+
+            self.public_view(shared.settings.kiosk.on_screen_keyboard)
+        };
+
+        let content: Element<'_, SettingsMessage> =
+            iced::Container::new(content).padding(TAB_PADDING).into();
+        content.map(Message::Settings)
+    }
+
+    fn update_result(
+        &mut self,
+        shared: &mut SharedData,
+        message: SettingsMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            SettingsMessage::ChangePasswordInput(password) => {
+                self.admin_password_value = password;
+            }
+            SettingsMessage::SubmitPassword => {
+                let verified = shared
+                    .storage
+                    .verify_password(self.admin_password_value.trim());
+                self.admin_password_value.clear();
+                if verified {
+                    self.load_values(&shared.settings);
+                    self.authorized = true;
+                } else {
+                    return Err(ManagementError::InvalidPassword.into());
+                }
+            }
+            SettingsMessage::PasswordKey(key) => {
+                if self.keyboard.press(key, &mut self.admin_password_value) {
+                    return self.update_result(shared, SettingsMessage::SubmitPassword);
+                }
+            }
+            SettingsMessage::InputKey(key) => {
+                // The keyboard is taken out of the tab while it types into one of the tab's inputs.
+                let mut keyboard = std::mem::take(&mut self.keyboard);
+                let submit = keyboard.press_input(key, self.inputs());
+                self.keyboard = keyboard;
+                if submit {
+                    self.save(shared)?;
+                }
+            }
+            SettingsMessage::ChangeExportDir(value) => self.export_dir_value = value,
+            SettingsMessage::ChangeCutoff(value) => self.cutoff_value = value,
+            SettingsMessage::ChangeBucket(idx, value) => {
+                if let Some(bucket_value) = self.bucket_values.get_mut(idx) {
+                    *bucket_value = value;
+                }
+            }
+            SettingsMessage::ChangeLocale(value) => self.locale_value = value,
+            SettingsMessage::ChangeExportLocale(value) => self.export_locale_value = value,
+            SettingsMessage::ChangeEndEventWord(value) => self.end_event_word_value = value,
+            SettingsMessage::ToggleFullscreen(b) => self.fullscreen = b,
+            SettingsMessage::ToggleSundayBuckets(b) => self.sunday_buckets = b,
+            SettingsMessage::ToggleSaturdayBuckets(b) => self.saturday_buckets = b,
+            SettingsMessage::ChangeSurcharge(idx, value) => {
+                if let Some(surcharge_value) = self.surcharge_values.get_mut(idx) {
+                    *surcharge_value = value;
+                }
+            }
+            SettingsMessage::ChangeConsultantNumber(value) => self.consultant_number_value = value,
+            SettingsMessage::ChangeClientNumber(value) => self.client_number_value = value,
+            SettingsMessage::ChangeWageType(idx, value) => {
+                if let Some(wage_type_value) = self.wage_type_values.get_mut(idx) {
+                    *wage_type_value = value;
+                }
+            }
+            SettingsMessage::ToggleXlsxExport(b) => self.xlsx_export = b,
+            SettingsMessage::ToggleWageColumn(b) => self.wage_column = b,
+            SettingsMessage::ToggleShowFormerNames(b) => self.show_former_names = b,
+            SettingsMessage::ToggleColorblindIcons(b) => self.colorblind_icons = b,
+            SettingsMessage::ToggleOnScreenKeyboard(b) => self.on_screen_keyboard = b,
+            SettingsMessage::ToggleCorrectKeyboardLayout(b) => self.correct_keyboard_layout = b,
+            SettingsMessage::TogglePersonnelNumberLogin(b) => self.personnel_number_login = b,
+            SettingsMessage::ChooseClosePolicy(policy) => self.close_policy = policy,
+            SettingsMessage::ChooseRounding(rounding) => self.rounding = rounding,
+            SettingsMessage::ChooseNightSummary(mode) => self.night_summary = mode,
+            SettingsMessage::ToggleExportSink(idx, b) => {
+                if let Some((_, enabled)) = self.export_sinks.get_mut(idx) {
+                    *enabled = b;
+                }
+            }
+            SettingsMessage::ChangeColumns(value) => self.columns_value = value,
+            SettingsMessage::Save => self.save(shared)?,
+            SettingsMessage::Reset => self.load_values(&shared.settings),
+            SettingsMessage::Exit(mode) => shared.request(TabRequest::Exit(mode)),
+            SettingsMessage::HandleEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Tab,
+                modifiers,
+            })) => {
+                let mut inputs = self
+                    .inputs()
+                    .into_iter()
+                    .map(|(state, _)| state)
+                    .collect::<Vec<_>>();
+                components::cycle_focus(&mut inputs, modifiers.shift());
+            }
+            // fallthrough to ignore events
+            SettingsMessage::HandleEvent(_) => {}
+        }
+        Ok(())
+    }
+}
@@ -9,31 +9,112 @@
 // 3. go through events and compute sum of timeslices between Working-Away pairs
 // 4. dump the result in csv
 
+mod bucket_preview;
+mod closing;
+mod datev;
+mod error_review;
 mod event_eval;
-mod time_eval;
-
+mod event_query;
+mod event_sessions;
+mod export_history;
+mod export_options;
+mod exports;
+mod hours_chart;
+mod interval_export;
+mod json_export;
+mod period;
+mod person_preview;
+mod staff_hours;
+mod staff_report;
+mod staff_selection;
+mod time_records;
+mod timesheets;
+mod view;
+mod xlsx_export;
+mod year_summary;
+
+use std::path::PathBuf;
 use std::{error, fmt};
 
-use chrono::{Date, Duration, Local, Locale, NaiveDate, NaiveDateTime, TimeZone};
-use iced::{button, window, Alignment, Button, Column, Container, Element, Length, Row, Text};
-use iced_aw::{
-    date_picker::{self, DatePicker},
-    TabLabel,
-};
+use chrono::{Date, Local, NaiveDate, NaiveDateTime};
+use iced::{button, window, Element};
+use iced_aw::{date_picker, TabLabel};
 use iced_native::Event;
-use stechuhr::models::StaffMember;
-
-use crate::{Message, SharedData, StechuhrError, Tab, TAB_PADDING};
-use stechuhr::TEXT_SIZE_BIG;
-
-use self::time_eval::WorkDuration;
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::disk_space;
+use stechuhr::evaluation::EvaluationError;
+use stechuhr::settings::Settings;
+use stechuhr::storage::Storage;
+
+use crate::tabs::requests::TabRequest;
+use crate::{Message, SharedData, StechuhrError, Tab};
+
+use self::closing::{ClosingMessage, MonthClosing};
+use self::error_review::{ErrorReview, ErrorReviewMessage, PendingEvaluation};
+use self::event_query::{EventQuery, EventQueryMessage};
+use self::export_history::{ExportHistory, ExportHistoryMessage};
+use self::export_options::ExportOptions;
+pub use self::export_options::ExportOptionsMessage;
+use self::hours_chart::{HoursChart, HoursChartMessage};
+use self::period::EvaluationPeriod;
+use self::person_preview::{PersonPreview, PersonPreviewMessage};
+use self::staff_hours::{PersonHoursCSV, StaffHours};
+use self::staff_report::{StaffReport, StaffReportMessage};
+use self::staff_selection::{StaffSelection, StaffSelectionMessage};
 
 pub struct StatsTab {
-    date: Date<Local>,
+    period: EvaluationPeriod,
+    options: ExportOptions,
     // widget states
     month_picker: date_picker::State,
     date_button_state: button::State,
     generate_button_state: button::State,
+    staff_selection_button_state: button::State,
+    range_picker: date_picker::State,
+    range_button_state: button::State,
+    range_generate_button_state: button::State,
+    months_picker: date_picker::State,
+    months_button_state: button::State,
+    months_generate_button_state: button::State,
+    log_button_state: button::State,
+    datev_button_state: button::State,
+    json_button_state: button::State,
+    sessions_button_state: button::State,
+    intervals_button_state: button::State,
+    time_records_button_state: button::State,
+    timesheets_button_state: button::State,
+    year_summary_button_state: button::State,
+    year_to_date_button_state: button::State,
+    staff_report_button_state: button::State,
+    person_preview_button_state: button::State,
+    event_query_button_state: button::State,
+    hours_chart_button_state: button::State,
+    retry_button_state: button::State,
+    export_dir_button_state: button::State,
+    email_button_state: button::State,
+    closing_button_state: button::State,
+    closing: MonthClosing,
+    error_review: ErrorReview,
+    staff_selection: StaffSelection,
+    staff_report: StaffReport,
+    person_preview: PersonPreview,
+    hours_chart: HoursChart,
+    event_query: EventQuery,
+    history: ExportHistory,
+    /* set when the last evaluation could not be written to the export directory */
+    failover: Option<Failover>,
+    /* name and files of the last evaluation, which can be sent to the bookkeeping */
+    last_evaluation: Option<(String, Vec<PathBuf>)>,
+}
+
+/// An evaluation that was written to the temp directory instead of the export directory.
+struct Failover {
+    export_dir: PathBuf,
+    temp_dir: PathBuf,
+    /// First day of the evaluated month, not set for a free range, which is not kept in the export history.
+    month: Option<NaiveDate>,
+    /// The complete evaluation comes first, followed by the files of the departments.
+    files: Vec<(String, Option<String>)>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,120 +122,95 @@ pub enum StatsMessage {
     ChooseDate,
     CancelDate,
     SubmitDate(date_picker::Date),
+    ChooseExportDir,
+    /// The directory chosen in the file dialog, nothing if it was cancelled.
+    ExportDirChosen(Option<PathBuf>),
     Generate,
+    ChooseRangeEnd,
+    CancelRangeEnd,
+    SubmitRangeEnd(date_picker::Date),
+    GenerateRange,
+    ChooseMonthsEnd,
+    CancelMonthsEnd,
+    SubmitMonthsEnd(date_picker::Date),
+    GenerateMonths,
+    ExportLog,
+    ExportDatev,
+    ExportJson,
+    ExportSessions,
+    ExportIntervals,
+    ExportTimeRecords,
+    ExportTimesheets,
+    ExportYearSummary,
+    ExportYearToDate,
+    RetryExport,
+    SendEmail,
+    Options(ExportOptionsMessage),
+    Closing(ClosingMessage),
+    ErrorReview(ErrorReviewMessage),
+    StaffSelection(StaffSelectionMessage),
+    StaffReport(StaffReportMessage),
+    PersonPreview(PersonPreviewMessage),
+    HoursChart(HoursChartMessage),
+    EventQuery(EventQueryMessage),
+    History(ExportHistoryMessage),
     HandleEvent(Event),
 }
 
-/// The result of the computation done by EventSM.
-#[derive(Debug)]
-pub struct PersonHours<'a> {
-    staff_member: &'a StaffMember,
-    duration: WorkDuration,
-}
-
-impl<'a> PersonHours<'a> {
-    fn new(staff_member: &'a StaffMember) -> Self {
-        Self {
-            staff_member,
-            duration: WorkDuration::zero(),
-        }
-    }
-
-    fn staff_member(&self) -> &StaffMember {
-        &self.staff_member
-    }
-
-    fn duration(&self) -> &WorkDuration {
-        &self.duration
-    }
-}
-
-#[derive(Debug, Serialize)]
-struct PersonHoursCSV {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Minuten 6 - 22 Uhr")]
-    minutes_1: i64,
-    #[serde(rename = "Minuten 22 - 24 Uhr")]
-    minutes_2: i64,
-    #[serde(rename = "Minuten 24 - 6 Uhr")]
-    minutes_3: i64,
-}
-
-impl<'a> From<PersonHours<'a>> for PersonHoursCSV {
-    fn from(hours: PersonHours<'a>) -> Self {
-        let [minutes_1, minutes_2, minutes_3] = hours.duration().num_minutes();
-
-        Self {
-            name: hours.staff_member().name.clone(),
-            minutes_1,
-            minutes_2,
-            minutes_3,
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct StaffHours {
-    hours_csv: Vec<PersonHoursCSV>,
-    soft_errors: Vec<SoftStatisticsError>,
-}
-
-impl StaffHours {
-    pub(self) fn hours(&self) -> &[PersonHoursCSV] {
-        &self.hours_csv
-    }
-    pub(self) fn errors(&self) -> &[SoftStatisticsError] {
-        &self.soft_errors
-    }
-}
-
 impl StatsTab {
-    pub fn new() -> Self {
+    pub fn new(storage: &mut dyn Storage) -> Self {
         StatsTab {
-            date: Local::today(),
+            period: EvaluationPeriod::new(Local::today()),
+            options: ExportOptions::default(),
             month_picker: date_picker::State::now(),
             date_button_state: button::State::default(),
             generate_button_state: button::State::default(),
+            staff_selection_button_state: button::State::default(),
+            range_picker: date_picker::State::now(),
+            range_button_state: button::State::default(),
+            range_generate_button_state: button::State::default(),
+            months_picker: date_picker::State::now(),
+            months_button_state: button::State::default(),
+            months_generate_button_state: button::State::default(),
+            log_button_state: button::State::default(),
+            datev_button_state: button::State::default(),
+            json_button_state: button::State::default(),
+            sessions_button_state: button::State::default(),
+            intervals_button_state: button::State::default(),
+            time_records_button_state: button::State::default(),
+            timesheets_button_state: button::State::default(),
+            year_summary_button_state: button::State::default(),
+            year_to_date_button_state: button::State::default(),
+            staff_report_button_state: button::State::default(),
+            person_preview_button_state: button::State::default(),
+            event_query_button_state: button::State::default(),
+            hours_chart_button_state: button::State::default(),
+            retry_button_state: button::State::default(),
+            export_dir_button_state: button::State::default(),
+            email_button_state: button::State::default(),
+            closing_button_state: button::State::default(),
+            closing: MonthClosing::new(),
+            error_review: ErrorReview::new(),
+            staff_selection: StaffSelection::new(),
+            staff_report: StaffReport::new(),
+            person_preview: PersonPreview::new(),
+            hours_chart: HoursChart::new(),
+            event_query: EventQuery::new(),
+            history: ExportHistory::new(storage),
+            failover: None,
+            last_evaluation: None,
         }
     }
 
-    fn generate_csv(
-        shared: &mut SharedData,
-        date: Date<Local>,
-        staff_hours: StaffHours,
-    ) -> Result<(), StechuhrError> {
-        std::fs::create_dir("./auswertung").ok();
-
-        // Write everyting into a CSV file.
-        let filename = format!(
-            "./auswertung/{}.tsv",
-            date.format_localized("%Y-%m %B", Locale::de_DE).to_string()
-        );
-
-        let mut wtr = csv::WriterBuilder::new()
-            // Use Tab as delimiter so that Excel automatically imports it correctly.
-            .delimiter(b'\t')
-            // Enable flexible writer since errors are just one field.
-            .flexible(true)
-            .from_path(&filename)?;
-
-        for hours in staff_hours.hours() {
-            wtr.serialize(hours)?;
-        }
-        for error in staff_hours.errors() {
-            shared.log_error(error.to_string());
-            // pad with units to put errors into a separate column
-            wtr.serialize(((), (), (), (), (), error.to_string()))?;
-        }
-        wtr.flush()?;
+    /// Name of the files of a monthly evaluation, e.g. "2022-04 April".
+    fn month_name(settings: &Settings, date: Date<Local>) -> String {
+        date.format_localized("%Y-%m %B", settings.export_locale())
+            .to_string()
+    }
 
-        shared.prompt_message(format!(
-            "Arbeitszeit wurde in der Datei {} gespeichert",
-            filename,
-        ));
-        opener::open(filename)?;
-        Ok(())
+    /// Reload the list of recent exports, see `Stechuhr::resync`.
+    pub fn resync(&mut self, storage: &mut dyn Storage) {
+        self.history.reload(storage);
     }
 }
 
@@ -169,55 +225,8 @@ impl Tab for StatsTab {
         TabLabel::Text(self.title())
     }
 
-    fn content(&mut self, _shared: &mut SharedData) -> Element<'_, Message> {
-        let date = Container::new(
-            Text::new(
-                self.date
-                    .format_localized("%B %Y", Locale::de_DE)
-                    .to_string(),
-            )
-            .size(TEXT_SIZE_BIG),
-        )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .center_x()
-        .center_y();
-
-        let datepicker = DatePicker::new(
-            &mut self.month_picker,
-            date,
-            StatsMessage::CancelDate,
-            StatsMessage::SubmitDate,
-        );
-
-        let content = Row::new()
-            .push(datepicker)
-            .push(
-                Container::new(
-                    Column::new()
-                        .push(
-                            Button::new(&mut self.date_button_state, Text::new("Datum auswählen"))
-                                .on_press(StatsMessage::ChooseDate),
-                        )
-                        .push(
-                            Button::new(
-                                &mut self.generate_button_state,
-                                Text::new("CSV Generieren"),
-                            )
-                            .on_press(StatsMessage::Generate),
-                        )
-                        .spacing(20),
-                )
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .center_x()
-                .center_y(),
-            )
-            .align_items(Alignment::Center);
-
-        let content: Element<'_, StatsMessage> =
-            Container::new(content).padding(TAB_PADDING).into();
-        content.map(Message::Statistics)
+    fn content(&mut self, shared: &mut SharedData) -> Element<'_, Message> {
+        self.view(shared).map(Message::Statistics)
     }
 
     fn update_result(
@@ -225,6 +234,29 @@ impl Tab for StatsTab {
         shared: &mut SharedData,
         message: StatsMessage,
     ) -> Result<(), StechuhrError> {
+        // Refuse to start an export on a nearly full disk instead of leaving a truncated file behind.
+        if matches!(
+            message,
+            StatsMessage::Generate
+                | StatsMessage::GenerateRange
+                | StatsMessage::GenerateMonths
+                | StatsMessage::ExportLog
+                | StatsMessage::ExportDatev
+                | StatsMessage::ExportJson
+                | StatsMessage::ExportSessions
+                | StatsMessage::ExportIntervals
+                | StatsMessage::ExportTimeRecords
+                | StatsMessage::ExportTimesheets
+                | StatsMessage::ExportYearSummary
+                | StatsMessage::ExportYearToDate
+                | StatsMessage::RetryExport
+                | StatsMessage::ErrorReview(ErrorReviewMessage::Proceed)
+                | StatsMessage::Closing(ClosingMessage::Step(closing::ClosingStep::Export))
+                | StatsMessage::History(ExportHistoryMessage::Regenerate(_))
+        ) {
+            disk_space::preflight(&shared.settings.export_dir)?;
+        }
+
         match message {
             StatsMessage::ChooseDate => {
                 self.month_picker.reset();
@@ -234,15 +266,163 @@ impl Tab for StatsTab {
                 self.month_picker.show(false);
             }
             StatsMessage::SubmitDate(date) => {
-                let naive_date = NaiveDate::from(date);
-                self.date = Local.from_local_date(&naive_date).unwrap();
+                self.period.set_date(NaiveDate::from(date));
                 self.month_picker.show(false);
             }
+            StatsMessage::ChooseExportDir => {
+                // The dialog would be hidden behind the fullscreen window. It is only opened after this update, once
+                // the window is no longer fullscreen.
+                shared.window_mode = window::Mode::Windowed;
+                shared.request(TabRequest::PickExportDir(
+                    shared.settings.export_dir.clone(),
+                ));
+            }
+            StatsMessage::ExportDirChosen(export_dir) => {
+                if let Some(export_dir) = export_dir {
+                    self.set_export_dir(shared, export_dir)?
+                }
+            }
             StatsMessage::Generate => {
                 // Set windowed to help people find the generated CSV.
                 shared.window_mode = window::Mode::Windowed;
-                let hours = event_eval::evaluate_hours_for_month(shared, self.date)?;
-                StatsTab::generate_csv(shared, self.date, hours)?;
+                let hours = event_eval::evaluate_hours_for_month(
+                    shared,
+                    self.period.date(),
+                    self.options.include_inactive(),
+                )?;
+                self.review_and_generate(
+                    shared,
+                    PendingEvaluation {
+                        name: StatsTab::month_name(&shared.settings, self.period.date()),
+                        month: Some(self.period.date().naive_local().first_dom()),
+                        hours,
+                    },
+                )?;
+            }
+            StatsMessage::ChooseRangeEnd => {
+                self.range_picker.reset();
+                self.range_picker.show(true);
+            }
+            StatsMessage::CancelRangeEnd => {
+                self.range_picker.show(false);
+            }
+            StatsMessage::SubmitRangeEnd(date) => {
+                self.range_picker.show(false);
+                self.period.set_range_end(NaiveDate::from(date))?;
+            }
+            StatsMessage::GenerateRange => {
+                if let Some((name, start_time, end_time)) = self.period.range(&shared.settings) {
+                    shared.window_mode = window::Mode::Windowed;
+                    let hours = event_eval::evaluate_hours_for_range(
+                        shared,
+                        start_time,
+                        end_time,
+                        self.options.include_inactive(),
+                    )?;
+                    self.review_and_generate(
+                        shared,
+                        PendingEvaluation {
+                            name,
+                            month: None,
+                            hours,
+                        },
+                    )?;
+                }
+            }
+            StatsMessage::ChooseMonthsEnd => {
+                self.months_picker.reset();
+                self.months_picker.show(true);
+            }
+            StatsMessage::CancelMonthsEnd => {
+                self.months_picker.show(false);
+            }
+            StatsMessage::SubmitMonthsEnd(date) => {
+                self.months_picker.show(false);
+                self.period.set_months_end(NaiveDate::from(date))?;
+            }
+            StatsMessage::GenerateMonths => {
+                if let Some(months_end) = self.period.months_end() {
+                    shared.window_mode = window::Mode::Windowed;
+                    self.generate_months(shared, months_end)?;
+                }
+            }
+            StatsMessage::ExportLog => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_log(shared)?;
+            }
+            StatsMessage::ExportDatev => self.export_datev(shared)?,
+            StatsMessage::ExportJson => self.export_json(shared)?,
+            StatsMessage::ExportSessions => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_sessions(shared)?;
+            }
+            StatsMessage::ExportIntervals => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_intervals(shared)?;
+            }
+            StatsMessage::ExportTimeRecords => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_time_records(shared)?;
+            }
+            StatsMessage::ExportTimesheets => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_timesheets(shared)?;
+            }
+            StatsMessage::ExportYearSummary => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_year_summary(shared)?;
+            }
+            StatsMessage::ExportYearToDate => {
+                shared.window_mode = window::Mode::Windowed;
+                self.export_year_to_date(shared)?;
+            }
+            StatsMessage::RetryExport => self.retry_export(shared)?,
+            StatsMessage::SendEmail => self.send_email(shared)?,
+            StatsMessage::Options(options_message) => self.options.update(options_message),
+            StatsMessage::Closing(ClosingMessage::Step(closing::ClosingStep::Export)) => {
+                self.closing_export(shared)?
+            }
+            StatsMessage::Closing(closing_message) => self.closing.update(
+                shared,
+                self.period.date(),
+                self.options.include_inactive(),
+                closing_message,
+            )?,
+            StatsMessage::ErrorReview(error_review_message) => {
+                if let Some(pending) = self.error_review.update(error_review_message) {
+                    self.generate_csv(shared, &pending.name, pending.month, &pending.hours)?;
+                }
+            }
+            StatsMessage::StaffSelection(staff_selection_message) => self
+                .staff_selection
+                .update(&shared.staff, staff_selection_message),
+            StatsMessage::StaffReport(staff_report_message) => {
+                self.staff_report
+                    .update(shared, self.period.date(), staff_report_message)?
+            }
+            StatsMessage::PersonPreview(person_preview_message) => self.person_preview.update(
+                shared,
+                self.period.date(),
+                self.options.include_inactive(),
+                person_preview_message,
+            )?,
+            StatsMessage::HoursChart(hours_chart_message) => self.hours_chart.update(
+                shared,
+                self.period.date(),
+                self.options.include_inactive(),
+                hours_chart_message,
+            )?,
+            StatsMessage::EventQuery(event_query_message) => {
+                self.event_query
+                    .update(shared, self.period.date(), event_query_message)
+            }
+            StatsMessage::History(ExportHistoryMessage::Open(idx)) => {
+                shared.window_mode = window::Mode::Windowed;
+                self.history.open(idx)?;
+            }
+            StatsMessage::History(ExportHistoryMessage::Regenerate(idx)) => {
+                shared.window_mode = window::Mode::Windowed;
+                self.regenerate_export(shared, idx)?;
             }
             // fallthrough to ignore events
             StatsMessage::HandleEvent(_) => (),
@@ -253,44 +433,58 @@ impl Tab for StatsTab {
 
 #[derive(Debug, Clone)]
 pub enum StatisticsError {
-    DurationError(Duration, Duration),
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum SoftStatisticsError {
-    AlreadyWorking(NaiveDateTime, String),
-    AlreadyAway(NaiveDateTime, String),
-    StaffStillWorking(NaiveDateTime, String),
+    Evaluation(EvaluationError),
+    ParseTime(String),
+    ParseDate(String),
+    OutsideMonth(NaiveDateTime, String),
+    MonthClosed(String),
+    DatevNotConfigured,
 }
 
 impl error::Error for StatisticsError {}
-impl error::Error for SoftStatisticsError {}
 
-impl fmt::Display for StatisticsError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let description = match self {
-            Self::DurationError(d1, d2) => {
-                format!("Error adding durations {} and {}", d1, d2)
-            }
-        };
-        f.write_str(&description)
+impl From<EvaluationError> for StatisticsError {
+    fn from(e: EvaluationError) -> Self {
+        Self::Evaluation(e)
     }
 }
 
-impl fmt::Display for SoftStatisticsError {
+/// Department names are free text, so we replace characters that are not allowed in file names.
+pub fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for StatisticsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let description = match self {
-            Self::AlreadyWorking(date, name) => format!(
-                "Um {} wurde der Status von {} auf 'Arbeiten' gesetzt während er/sie schon am Arbeiten war. Inkonsistente Datenbank, bitte Adrian Bescheid sagen.",
-                date, name
+            Self::Evaluation(e) => e.to_string(),
+            Self::ParseTime(s) => format!(
+                "Zeitpunkt muss im Format TT.MM.JJJJ HH:MM angegeben werden: \"{}\"",
+                s
+            ),
+            Self::ParseDate(s) => format!(
+                "Datum muss im Format TT.MM.JJJJ angegeben werden: \"{}\"",
+                s
+            ),
+            Self::OutsideMonth(time, month) => format!(
+                "Der Zeitpunkt {} liegt nicht im Monat {}",
+                time.format("%d.%m.%Y %H:%M"),
+                month
             ),
-            Self::AlreadyAway(date, name) => format!(
-                "Um {} wurde der Status von {} auf 'Pause' gesetzt während er/sie schon in der Pause war. Inkonsistente Datenbank, bitte Adrian Bescheid sagen.",
-                date, name
+            Self::MonthClosed(month) => format!(
+                "Der Monat {} ist bereits abgeschlossen, es sind keine Korrekturen mehr möglich",
+                month
             ),
-            Self::StaffStillWorking(date, name) => format!(
-                "Um {} arbeitet {} noch um 6 Uhr morgens. Es wurde wahrscheinlich vergessen sich abzumelden.",
-                date, name
+            Self::DatevNotConfigured => String::from(
+                "Für den DATEV-Export müssen Beraternummer, Mandantennummer und Lohnarten in den Einstellungen eingetragen sein",
             ),
         };
         f.write_str(&description)
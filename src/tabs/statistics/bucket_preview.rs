@@ -0,0 +1,110 @@
+//! Timeline of a day colored by the buckets of the evaluation, so that admins can see
+//! which hours end up in which column of the export before generating it.
+use chrono::Timelike;
+use iced::{
+    alignment::Horizontal, Alignment, Color, Column, Container, Element, Length, Row, Text,
+};
+use stechuhr::evaluation::BucketBounds;
+use stechuhr::settings::Settings;
+use stechuhr::style::TimelineHourStyle;
+
+use super::PersonHoursCSV;
+
+/// Colors of the day, evening and night bucket.
+const BUCKET_COLORS: [Color; 3] = [
+    Color::from_rgb(1.0, 0.9, 0.6),
+    Color::from_rgb(0.98, 0.67, 0.35),
+    Color::from_rgb(0.47, 0.59, 0.86),
+];
+const LABEL_SIZE: u16 = 16;
+
+/// Bucket of each hour of the day, starting at midnight.
+fn hour_buckets(bounds: &BucketBounds) -> [usize; 24] {
+    let mut buckets = [0; 24];
+    for (hour, bucket) in buckets.iter_mut().enumerate() {
+        *bucket = bounds.bucket_of_hour(hour as u32);
+    }
+    buckets
+}
+
+fn swatch<'a, M: 'a>(color: Color, is_cutoff: bool) -> Element<'a, M> {
+    Container::new(Text::new(""))
+        .width(Length::Units(20))
+        .height(Length::Units(20))
+        .style(TimelineHourStyle { color, is_cutoff })
+        .into()
+}
+
+pub fn view<'a, M: 'a>(settings: &Settings) -> Element<'a, M> {
+    let bounds = BucketBounds::new(settings.bucket_starts);
+    let cutoff_hour = settings.cutoff_time.hour();
+
+    let timeline = hour_buckets(&bounds).iter().enumerate().fold(
+        Row::new().width(Length::Fill),
+        |row, (hour, bucket)| {
+            row.push(
+                Container::new(
+                    Text::new(hour.to_string())
+                        .size(LABEL_SIZE)
+                        .horizontal_alignment(Horizontal::Center),
+                )
+                .width(Length::FillPortion(1))
+                .center_x()
+                .padding(2)
+                .style(TimelineHourStyle {
+                    color: BUCKET_COLORS[*bucket],
+                    is_cutoff: hour as u32 == cutoff_hour,
+                }),
+            )
+        },
+    );
+
+    // The same labels as the columns of the export.
+    let header = PersonHoursCSV::header(settings);
+    let mut legend = Row::new().spacing(20).align_items(Alignment::Center);
+    for ((idx, _, _), label) in settings.bucket_ranges().into_iter().zip(&header[1..]) {
+        legend = legend.push(
+            Row::new()
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(swatch(BUCKET_COLORS[idx], false))
+                .push(Text::new(label).size(LABEL_SIZE)),
+        );
+    }
+    legend = legend.push(
+        Row::new()
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .push(swatch(Color::WHITE, true))
+            .push(
+                Text::new(format!(
+                    "Tagesende {} Uhr",
+                    settings.cutoff_time.format("%H:%M")
+                ))
+                .size(LABEL_SIZE),
+            ),
+    );
+
+    Column::new()
+        .spacing(5)
+        .push(Text::new("Zeitbereiche der Auswertung"))
+        .push(timeline)
+        .push(legend)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_hour_buckets() {
+        let buckets = hour_buckets(&BucketBounds::new(Settings::default().bucket_starts));
+        assert_eq!(buckets[0], 2);
+        assert_eq!(buckets[3], 2);
+        assert_eq!(buckets[4], 0);
+        assert_eq!(buckets[19], 0);
+        assert_eq!(buckets[20], 1);
+        assert_eq!(buckets[23], 1);
+    }
+}
@@ -0,0 +1,525 @@
+//! Guided monthly closing, so that no step is forgotten when the evaluation is due.
+use std::fmt;
+use std::path::PathBuf;
+
+use chrono::{Date, Local, NaiveDateTime};
+use iced::{
+    alignment::Horizontal, button, pick_list, scrollable, text_input, Alignment, Button, Column,
+    Element, Length, PickList, Row, Scrollable, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::{
+    correction,
+    date_ext::NaiveDateExt,
+    models::{NewClosedMonth, NewWorkEventT, WorkEvent, WorkStatus},
+};
+
+use super::{event_eval, StaffHours, StatisticsError, StatsMessage};
+use crate::{SharedData, StechuhrError};
+
+const CORRECTION_TIME_FORMAT: &str = "%d.%m.%Y %H:%M";
+const STATUS_OPTIONS: [WorkStatus; 2] = [WorkStatus::Working, WorkStatus::Away];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosingStep {
+    Evaluate,
+    ReviewErrors,
+    Corrections,
+    Lock,
+    Export,
+    Email,
+}
+
+impl ClosingStep {
+    const ALL: [ClosingStep; 6] = [
+        ClosingStep::Evaluate,
+        ClosingStep::ReviewErrors,
+        ClosingStep::Corrections,
+        ClosingStep::Lock,
+        ClosingStep::Export,
+        ClosingStep::Email,
+    ];
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+
+    fn action(self) -> &'static str {
+        match self {
+            ClosingStep::Evaluate => "Berechnen",
+            ClosingStep::ReviewErrors => "Geprüft",
+            ClosingStep::Corrections => "Fertig",
+            ClosingStep::Lock => "Abschließen",
+            ClosingStep::Export => "Exportieren",
+            ClosingStep::Email => "E-Mail öffnen",
+        }
+    }
+}
+
+impl fmt::Display for ClosingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ClosingStep::Evaluate => "1. Auswertung berechnen",
+            ClosingStep::ReviewErrors => "2. Fehler prüfen",
+            ClosingStep::Corrections => "3. Korrekturen eintragen",
+            ClosingStep::Lock => "4. Monat abschließen",
+            ClosingStep::Export => "5. Dateien exportieren",
+            ClosingStep::Email => "6. Per E-Mail versenden",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum StepStatus {
+    #[default]
+    Open,
+    Done,
+    Skipped,
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            StepStatus::Open => "offen",
+            StepStatus::Done => "erledigt",
+            StepStatus::Skipped => "übersprungen",
+        };
+
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// Staff member that can be chosen for a correction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaffOption(i32, String);
+
+impl fmt::Display for StaffOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.1)
+    }
+}
+
+#[derive(Default)]
+struct ClosingModalState {
+    month_name: String,
+    statuses: [StepStatus; 6],
+    /* result of the last evaluation, computed again after each correction */
+    hours: Option<StaffHours>,
+    exported: Option<PathBuf>,
+    email: String,
+    staff_options: Vec<StaffOption>,
+    correction_staff: Option<StaffOption>,
+    correction_status: Option<WorkStatus>,
+    correction_time_value: String,
+    // widget states
+    step_states: [button::State; 6],
+    skip_state: button::State,
+    errors_scroll_state: scrollable::State,
+    correction_staff_state: pick_list::State<StaffOption>,
+    correction_status_state: pick_list::State<WorkStatus>,
+    correction_time_state: text_input::State,
+    correction_submit_state: button::State,
+    close_state: button::State,
+}
+
+impl ClosingModalState {
+    /// The first step that still has to be done. Steps must be done in order.
+    fn current_step(&self) -> Option<ClosingStep> {
+        ClosingStep::ALL
+            .iter()
+            .copied()
+            .find(|step| self.statuses[step.idx()] == StepStatus::Open)
+    }
+
+    fn finish(&mut self, step: ClosingStep) {
+        self.statuses[step.idx()] = StepStatus::Done;
+    }
+
+    fn detail(&self, step: ClosingStep) -> String {
+        let status = self.statuses[step.idx()];
+        match (step, status, &self.hours, &self.exported) {
+            (ClosingStep::Evaluate, StepStatus::Done, Some(hours), _)
+            | (ClosingStep::ReviewErrors, StepStatus::Open, Some(hours), _) => format!(
+                "{} Personen, {} Fehler",
+                hours.hours().len(),
+                hours.errors().len()
+            ),
+            (ClosingStep::Export, StepStatus::Done, _, Some(path)) => path.display().to_string(),
+            (ClosingStep::Email, StepStatus::Skipped, _, _) if self.email.is_empty() => {
+                String::from("keine E-Mail-Adresse eingestellt")
+            }
+            (ClosingStep::Lock, StepStatus::Done, _, _) => String::from("abgeschlossen"),
+            _ => status.to_string(),
+        }
+    }
+}
+
+pub struct MonthClosing {
+    date: Date<Local>,
+    /* also evaluate hidden and deactivated staff members, see `event_eval::evaluate_hours_for_month` */
+    include_inactive: bool,
+    modal_state: modal::State<ClosingModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClosingMessage {
+    Open,
+    Step(ClosingStep),
+    SkipEmail,
+    ChooseStaff(StaffOption),
+    ChooseStatus(WorkStatus),
+    ChangeCorrectionTime(String),
+    SubmitCorrection,
+    Close,
+}
+
+/// Encode a value for a mailto link.
+fn mailto_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl MonthClosing {
+    pub fn new() -> Self {
+        MonthClosing {
+            date: Local::today(),
+            include_inactive: false,
+            modal_state: modal::State::default(),
+        }
+    }
+
+    pub fn date(&self) -> Date<Local> {
+        self.date
+    }
+
+    /// Start the monthly closing for the month of the given date.
+    fn open(&mut self, shared: &mut SharedData, date: Date<Local>, include_inactive: bool) {
+        let is_closed = shared
+            .storage
+            .is_month_closed(date.naive_local().first_dom());
+
+        self.date = date;
+        self.include_inactive = include_inactive;
+        let state = self.modal_state.inner_mut();
+        *state = ClosingModalState::default();
+        state.month_name = date
+            .format_localized("%B %Y", shared.settings.locale())
+            .to_string();
+        state.email = shared.settings.closing_email.trim().to_owned();
+        state.staff_options = shared
+            .staff
+            .iter()
+            .filter(|staff_member| staff_member.is_visible)
+            .map(|staff_member| StaffOption(staff_member.uuid(), staff_member.name.clone()))
+            .collect();
+
+        // A closed month cannot be corrected anymore, but it can still be exported again.
+        if is_closed {
+            state.statuses[ClosingStep::Corrections.idx()] = StepStatus::Skipped;
+            state.statuses[ClosingStep::Lock.idx()] = StepStatus::Done;
+        }
+        if state.email.is_empty() {
+            state.statuses[ClosingStep::Email.idx()] = StepStatus::Skipped;
+        }
+        self.modal_state.show(true);
+    }
+
+    /// The export is done by the statistics tab, which hands out the hours for that.
+    pub fn take_hours(&mut self) -> Option<StaffHours> {
+        self.modal_state.inner_mut().hours.take()
+    }
+
+    pub fn finish_export(&mut self, hours: StaffHours, exported: Option<PathBuf>) {
+        let state = self.modal_state.inner_mut();
+        state.hours = Some(hours);
+        if exported.is_some() {
+            state.exported = exported;
+            state.finish(ClosingStep::Export);
+        }
+    }
+
+    fn step(&mut self, shared: &mut SharedData, step: ClosingStep) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        if state.current_step() != Some(step) {
+            return Ok(());
+        }
+
+        match step {
+            ClosingStep::Evaluate => {
+                state.hours = Some(event_eval::evaluate_hours_for_month(
+                    shared,
+                    self.date,
+                    self.include_inactive,
+                )?);
+            }
+            ClosingStep::Lock => {
+                let first_day = self.date.naive_local().first_dom();
+                if !shared.storage.is_month_closed(first_day) {
+                    shared.storage.insert_closed_month(NewClosedMonth {
+                        month: first_day,
+                        closed_at: shared.current_time.naive_local(),
+                    })?;
+                }
+                shared.log_info(format!("Monat {} wurde abgeschlossen.", state.month_name));
+            }
+            ClosingStep::Email => {
+                let subject = format!("Arbeitszeiten {}", state.month_name);
+                let body = match &state.exported {
+                    Some(path) => format!(
+                        "Hallo,\n\nanbei die Arbeitszeiten für {}.\nDie Datei liegt unter {}.\n",
+                        state.month_name,
+                        path.display()
+                    ),
+                    None => format!(
+                        "Hallo,\n\nanbei die Arbeitszeiten für {}.\n",
+                        state.month_name
+                    ),
+                };
+                opener::open(format!(
+                    "mailto:{}?subject={}&body={}",
+                    state.email,
+                    mailto_encode(&subject),
+                    mailto_encode(&body)
+                ))?;
+                shared.log_info(format!(
+                    "E-Mail mit den Arbeitszeiten für {} an {} vorbereitet.",
+                    state.month_name, state.email
+                ));
+            }
+            ClosingStep::ReviewErrors | ClosingStep::Corrections | ClosingStep::Export => {}
+        }
+
+        state.finish(step);
+        Ok(())
+    }
+
+    /// Insert a status change that was forgotten, e.g. somebody who did not sign off.
+    fn submit_correction(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        let (uuid, name, status) = match (&state.correction_staff, state.correction_status) {
+            (Some(StaffOption(uuid, name)), Some(status)) => (*uuid, name.clone(), status),
+            _ => return Ok(()),
+        };
+
+        let input = state.correction_time_value.trim();
+        let time = NaiveDateTime::parse_from_str(input, CORRECTION_TIME_FORMAT)
+            .map_err(|_| StatisticsError::ParseTime(input.to_owned()))?;
+        let (start_time, end_time) = event_eval::month_bounds(&shared.settings, self.date);
+        if time < start_time || time >= end_time {
+            return Err(StatisticsError::OutsideMonth(time, state.month_name.clone()).into());
+        }
+        if shared
+            .storage
+            .is_month_closed(self.date.naive_local().first_dom())
+        {
+            return Err(StatisticsError::MonthClosed(state.month_name.clone()).into());
+        }
+        correction::check(
+            uuid,
+            time,
+            status,
+            shared.current_time.naive_local(),
+            &shared.settings,
+            shared.storage.as_mut(),
+        )?;
+
+        shared.storage.insert_event(NewWorkEventT::new(
+            time,
+            WorkEvent::StatusChange(uuid, String::new(), status),
+        ));
+        let description = format!(
+            "Status wurde nachträglich am {} auf \"{}\" gesetzt.",
+            time.format(CORRECTION_TIME_FORMAT),
+            status
+        );
+        // The correction is recorded at the corrected time so that it is counted in the evaluation of that month.
+        shared.storage.insert_event(NewWorkEventT::new(
+            time,
+            WorkEvent::Correction(uuid, String::new(), description.clone()),
+        ));
+        shared.log_info(format!("Korrektur für {}: {}", name, description));
+        state.correction_time_value.clear();
+
+        // Evaluate again so that the list of errors reflects the correction.
+        state.hours = Some(event_eval::evaluate_hours_for_month(
+            shared,
+            self.date,
+            self.include_inactive,
+        )?);
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        *self.modal_state.inner_mut() = ClosingModalState::default();
+        self.modal_state.show(false);
+    }
+
+    /// Wraps the statistics tab in the dialog of the monthly closing.
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let modal = Modal::new(&mut self.modal_state, content, |state| {
+            let current_step = state.current_step();
+            let details = ClosingStep::ALL.map(|step| state.detail(step));
+
+            // The error list and the correction form are shown below their steps.
+            let errors = state.hours.as_ref().map(|hours| {
+                let errors = hours.errors().iter().fold(
+                    Scrollable::new(&mut state.errors_scroll_state).spacing(5),
+                    |errors, error| errors.push(Text::new(error.to_string()).size(16)),
+                );
+                Element::from(errors.max_height(150))
+            });
+            let corrections = if current_step == Some(ClosingStep::Corrections) {
+                let mut submit = Button::new(
+                    &mut state.correction_submit_state,
+                    Text::new("Eintragen").horizontal_alignment(Horizontal::Center),
+                );
+                if state.correction_staff.is_some() && state.correction_status.is_some() {
+                    submit =
+                        submit.on_press(StatsMessage::Closing(ClosingMessage::SubmitCorrection));
+                }
+
+                let form = Row::new()
+                    .push(
+                        PickList::new(
+                            &mut state.correction_staff_state,
+                            &state.staff_options[..],
+                            state.correction_staff.clone(),
+                            |staff| StatsMessage::Closing(ClosingMessage::ChooseStaff(staff)),
+                        )
+                        .width(Length::FillPortion(2)),
+                    )
+                    .push(
+                        stechuhr::style::text_input(
+                            &mut state.correction_time_state,
+                            "TT.MM.JJJJ HH:MM",
+                            &state.correction_time_value,
+                            |s| StatsMessage::Closing(ClosingMessage::ChangeCorrectionTime(s)),
+                        )
+                        .width(Length::FillPortion(2)),
+                    )
+                    .push(
+                        PickList::new(
+                            &mut state.correction_status_state,
+                            &STATUS_OPTIONS[..],
+                            state.correction_status,
+                            |status| StatsMessage::Closing(ClosingMessage::ChooseStatus(status)),
+                        )
+                        .width(Length::FillPortion(1)),
+                    )
+                    .push(submit)
+                    .spacing(10)
+                    .align_items(Alignment::Center);
+                Some(Element::from(form))
+            } else {
+                None
+            };
+            let mut extras = [
+                (ClosingStep::ReviewErrors, errors),
+                (ClosingStep::Corrections, corrections),
+            ];
+
+            let mut steps = Column::new().spacing(10);
+            for ((step, button_state), detail) in ClosingStep::ALL
+                .iter()
+                .copied()
+                .zip(state.step_states.iter_mut())
+                .zip(details)
+            {
+                let mut action = Button::new(
+                    button_state,
+                    Text::new(step.action()).horizontal_alignment(Horizontal::Center),
+                )
+                .width(Length::Units(150));
+                if current_step == Some(step) {
+                    action = action.on_press(StatsMessage::Closing(ClosingMessage::Step(step)));
+                }
+
+                steps = steps.push(
+                    Row::new()
+                        .push(Text::new(step.to_string()).width(Length::FillPortion(2)))
+                        .push(Text::new(detail).width(Length::FillPortion(2)))
+                        .push(action)
+                        .spacing(10)
+                        .align_items(Alignment::Center),
+                );
+                for (extra_step, extra) in extras.iter_mut() {
+                    if *extra_step == step {
+                        if let Some(extra) = extra.take() {
+                            steps = steps.push(extra);
+                        }
+                    }
+                }
+            }
+            let mut foot = Row::new().spacing(10).padding(5).width(Length::Fill);
+            if current_step == Some(ClosingStep::Email) {
+                foot = foot.push(
+                    Button::new(
+                        &mut state.skip_state,
+                        Text::new("E-Mail überspringen").horizontal_alignment(Horizontal::Center),
+                    )
+                    .width(Length::Shrink)
+                    .on_press(StatsMessage::Closing(ClosingMessage::SkipEmail)),
+                );
+            }
+            foot = foot.push(
+                Button::new(
+                    &mut state.close_state,
+                    Text::new("Schließen").horizontal_alignment(Horizontal::Center),
+                )
+                .width(Length::Shrink)
+                .on_press(StatsMessage::Closing(ClosingMessage::Close)),
+            );
+
+            Card::new(
+                Text::new(format!("Monatsabschluss {}", state.month_name)),
+                steps,
+            )
+            .foot(foot)
+            .max_width(900)
+            .on_close(StatsMessage::Closing(ClosingMessage::Close))
+            .into()
+        })
+        .on_esc(StatsMessage::Closing(ClosingMessage::Close));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+        message: ClosingMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            ClosingMessage::Open => self.open(shared, date, include_inactive),
+            ClosingMessage::Step(step) => self.step(shared, step)?,
+            ClosingMessage::SkipEmail => {
+                let state = self.modal_state.inner_mut();
+                if state.current_step() == Some(ClosingStep::Email) {
+                    state.statuses[ClosingStep::Email.idx()] = StepStatus::Skipped;
+                }
+            }
+            ClosingMessage::ChooseStaff(staff) => {
+                self.modal_state.inner_mut().correction_staff = Some(staff);
+            }
+            ClosingMessage::ChooseStatus(status) => {
+                self.modal_state.inner_mut().correction_status = Some(status);
+            }
+            ClosingMessage::ChangeCorrectionTime(value) => {
+                self.modal_state.inner_mut().correction_time_value = value;
+            }
+            ClosingMessage::SubmitCorrection => self.submit_correction(shared)?,
+            ClosingMessage::Close => self.close(),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+//! Export of the evaluation in the ASCII import format of DATEV Lohn und Gehalt, so that the tax office does not
+//! have to type in the hours. The file starts with a line "Beraternummer;Mandantennummer;MM/JJJJ" followed by one
+//! line "Personalnummer;Lohnart;Stunden" for each bucket in which a staff member worked.
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use stechuhr::settings::Settings;
+
+use super::{PersonHoursCSV, StaffHours, StatisticsError};
+use crate::StechuhrError;
+
+/// DATEV expects hours as decimal number with a comma and two decimal places, independent of the export locale.
+fn format_hours(minutes: i64) -> String {
+    let hundredths = (minutes * 100 + 30) / 60;
+    format!("{},{:02}", hundredths / 100, hundredths % 100)
+}
+
+/// Write the DATEV file for the month starting at the given date.
+/// Staff members without a personnel number cannot be imported, their names are returned so that they can be added by hand.
+pub fn write(
+    path: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    month: NaiveDate,
+) -> Result<Vec<String>, StechuhrError> {
+    let datev = &settings.datev;
+    if !datev.is_configured(&PersonHoursCSV::columns(settings)) {
+        return Err(StatisticsError::DatevNotConfigured.into());
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        // The import is done on Windows.
+        .terminator(csv::Terminator::CRLF)
+        .has_headers(false)
+        .from_path(path)?;
+
+    wtr.write_record([
+        datev.consultant_number.trim().to_owned(),
+        datev.client_number.trim().to_owned(),
+        month.format("%m/%Y").to_string(),
+    ])?;
+
+    let mut skipped = Vec::new();
+    for hours in staff_hours.hours() {
+        let personnel_number = match &hours.personnel_number {
+            Some(personnel_number) => personnel_number,
+            None => {
+                if hours.minutes().iter().any(|minutes| *minutes > 0) {
+                    skipped.push(hours.staff_name.clone());
+                }
+                continue;
+            }
+        };
+        // The minutes are in the order of the configured wage types.
+        for (minutes, wage_type) in hours.minutes().iter().zip(datev.wage_types) {
+            if *minutes > 0 {
+                wtr.write_record([
+                    personnel_number.clone(),
+                    wage_type.to_string(),
+                    format_hours(*minutes),
+                ])?;
+            }
+        }
+    }
+    wtr.flush()?;
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stechuhr::settings::DatevSettings;
+
+    #[test]
+    fn write_datev() {
+        let hours = |name: &str, personnel_number: Option<&str>| PersonHoursCSV {
+            minutes_1: 450,
+            minutes_2: 20,
+            personnel_number: personnel_number.map(String::from),
+            ..PersonHoursCSV::for_test(name)
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![hours("Aaron", Some("1001")), hours("Beeron", None)],
+            soft_errors: Vec::new(),
+        };
+        let mut settings = Settings::default();
+        let path = std::env::temp_dir().join("stechuhr_datev_test.txt");
+        let month = NaiveDate::from_ymd(2022, 4, 1);
+        assert!(write(&path, &settings, &staff_hours, month).is_err());
+
+        settings.datev = DatevSettings {
+            consultant_number: String::from("12345"),
+            client_number: String::from("678"),
+            wage_types: [100, 120, 130, 0, 0, 0],
+        };
+        let skipped = write(&path, &settings, &staff_hours, month).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(skipped, vec![String::from("Beeron")]);
+        assert_eq!(
+            written,
+            "12345;678;04/2022\r\n1001;100;7,50\r\n1001;120;0,33\r\n"
+        );
+    }
+}
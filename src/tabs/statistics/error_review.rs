@@ -0,0 +1,150 @@
+//! Review of the inconsistencies of an evaluation before its files are written. In the file they are only a column
+//! after the hours, which is easily missed, so the admin sees them first and can abort to correct the events.
+use chrono::NaiveDate;
+use iced::{button, scrollable, Color, Element, Length, Row, Scrollable, Text};
+use iced_aw::{modal, Card, Modal};
+
+use super::{StaffHours, StatsMessage};
+use crate::tabs::components;
+
+/// An evaluation that is written once the admin proceeds, with the arguments of `StatsTab::generate_csv`.
+pub struct PendingEvaluation {
+    pub name: String,
+    pub month: Option<NaiveDate>,
+    pub hours: StaffHours,
+}
+
+#[derive(Default)]
+struct ErrorReviewModalState {
+    pending: Option<PendingEvaluation>,
+    scroll_state: scrollable::State,
+    proceed_state: button::State,
+    abort_state: button::State,
+}
+
+pub struct ErrorReview {
+    modal_state: modal::State<ErrorReviewModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ErrorReviewMessage {
+    Proceed,
+    Abort,
+}
+
+impl ErrorReview {
+    pub fn new() -> Self {
+        ErrorReview {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Show the errors of the evaluation. An evaluation without errors is handed back to be written right away.
+    pub fn review(&mut self, evaluation: PendingEvaluation) -> Option<PendingEvaluation> {
+        if evaluation.hours.errors().is_empty() {
+            return Some(evaluation);
+        }
+        self.modal_state.inner_mut().pending = Some(evaluation);
+        self.modal_state.show(true);
+        None
+    }
+
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::ErrorReview(message);
+
+        Modal::new(&mut self.modal_state, content, move |state| {
+            let (title, errors) = match &state.pending {
+                Some(pending) => (
+                    format!(
+                        "{} Fehler in der Auswertung {}",
+                        pending.hours.errors().len(),
+                        pending.name
+                    ),
+                    pending.hours.errors(),
+                ),
+                None => (String::new(), &[][..]),
+            };
+
+            let mut list = Scrollable::new(&mut state.scroll_state).spacing(10);
+            for error in errors {
+                list = list.push(
+                    Row::new()
+                        .spacing(10)
+                        .push(Text::new(error.staff_name()).width(Length::Units(200)))
+                        .push(
+                            Text::new(error.to_string())
+                                .size(16)
+                                .color(Color::from_rgb8(200, 0, 0)),
+                        ),
+                );
+            }
+
+            Card::new(Text::new(title), list.max_height(450))
+                .foot(components::confirm_foot(
+                    &mut state.proceed_state,
+                    "Trotzdem speichern",
+                    Some(message(ErrorReviewMessage::Proceed)),
+                    &mut state.abort_state,
+                    "Abbrechen",
+                    message(ErrorReviewMessage::Abort),
+                ))
+                .max_width(1000)
+                .on_close(message(ErrorReviewMessage::Abort))
+                .into()
+        })
+        .backdrop(message(ErrorReviewMessage::Abort))
+        .on_esc(message(ErrorReviewMessage::Abort))
+        .into()
+    }
+
+    /// The evaluation to write if the admin proceeds, nothing if they abort.
+    pub fn update(&mut self, message: ErrorReviewMessage) -> Option<PendingEvaluation> {
+        let pending = self.modal_state.inner_mut().pending.take();
+        self.modal_state.show(false);
+        match message {
+            ErrorReviewMessage::Proceed => pending,
+            ErrorReviewMessage::Abort => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::SoftStatisticsError;
+
+    use super::*;
+
+    #[test]
+    fn review_before_writing() {
+        let evaluation = |soft_errors| PendingEvaluation {
+            name: String::from("2000-01"),
+            month: Some(NaiveDate::from_ymd(2000, 1, 1)),
+            hours: StaffHours {
+                hours_csv: Vec::new(),
+                soft_errors,
+            },
+        };
+        let error = || {
+            vec![SoftStatisticsError::StaffStillWorking(
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(5, 59, 59),
+                String::from("Aaron"),
+            )]
+        };
+        let mut error_review = ErrorReview::new();
+
+        // Without errors there is nothing to review.
+        assert!(error_review.review(evaluation(Vec::new())).is_some());
+        assert!(!error_review.modal_state.is_shown());
+
+        assert!(error_review.review(evaluation(error())).is_none());
+        assert!(error_review.modal_state.is_shown());
+        assert!(error_review.update(ErrorReviewMessage::Abort).is_none());
+        assert!(!error_review.modal_state.is_shown());
+
+        error_review.review(evaluation(error()));
+        let pending = error_review.update(ErrorReviewMessage::Proceed).unwrap();
+        assert_eq!(pending.hours.errors().len(), 1);
+        assert!(error_review.update(ErrorReviewMessage::Proceed).is_none());
+    }
+}
@@ -1,223 +1,368 @@
-use super::{
-    time_eval::WorkDuration, PersonHours, PersonHoursCSV, SoftStatisticsError, StaffHours,
-    StatisticsError,
-};
+use super::{StaffHours, StatisticsError};
 use crate::{SharedData, StechuhrError};
-use chrono::{Date, Local, Locale, NaiveDateTime, NaiveTime, TimeZone};
+use chrono::{Date, Duration, Local, NaiveDateTime, TimeZone};
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
 use stechuhr::{
     date_ext::NaiveDateExt,
-    db,
-    models::{DBStaffMember, StaffMember, WorkEvent, WorkEventT, WorkStatus},
+    evaluation::{self, BucketBounds},
+    models::{DBStaffMember, WorkEvent, WorkEventT},
+    settings::{Rounding, Settings},
+    youth_protection,
 };
 
-enum EventSMLabel {
-    Working(NaiveDateTime),
-    Away,
-}
-
-/// State machine to compute the WorkDuration of a StaffMember based on a collection of events.
-pub struct EventSM<'a> {
-    hours_raw: PersonHours<'a>,
-    soft_errors: Vec<SoftStatisticsError>,
-    label: EventSMLabel,
-}
-
-impl<'a> EventSM<'a> {
-    pub fn new(staff_member: &'a StaffMember, initial_start_time: Option<NaiveDateTime>) -> Self {
-        let label = if let Some(start_time) = initial_start_time {
-            EventSMLabel::Working(start_time)
-        } else {
-            EventSMLabel::Away
-        };
-
-        Self {
-            hours_raw: PersonHours::new(staff_member),
-            soft_errors: Vec::new(),
-            label,
-        }
-    }
-
-    fn append_soft_error(&mut self, error: SoftStatisticsError) {
-        self.soft_errors.push(error);
-    }
-
-    fn add_time(
-        &mut self,
-        start_time: NaiveDateTime,
-        end_time: NaiveDateTime,
-    ) -> Result<(), StatisticsError> {
-        let additional_work_time = WorkDuration::from_start_end_time(start_time, end_time);
-        let new_duration = self.hours_raw.duration.checked_add(&additional_work_time)?;
-        self.hours_raw.duration = new_duration;
-        Ok(())
-    }
-
-    pub fn process(&mut self, event: &WorkEventT) -> Result<(), StatisticsError> {
-        match self.label {
-            EventSMLabel::Away => match event.event {
-                WorkEvent::StatusChange(uuid, _, WorkStatus::Working)
-                    if self.hours_raw.staff_member.uuid() == uuid =>
-                {
-                    self.label = EventSMLabel::Working(event.created_at);
-                    Ok(())
-                }
-                WorkEvent::StatusChange(uuid, _, WorkStatus::Away)
-                    if self.hours_raw.staff_member.uuid() == uuid =>
-                {
-                    self.append_soft_error(SoftStatisticsError::AlreadyAway(
-                        event.created_at,
-                        self.hours_raw.staff_member.name.clone(),
-                    ));
-                    Ok(())
-                }
-                _ => Ok(()),
-            },
-            EventSMLabel::Working(start_time) => match event.event {
-                WorkEvent::StatusChange(uuid, _, WorkStatus::Away)
-                    if self.hours_raw.staff_member.uuid() == uuid =>
-                {
-                    self.add_time(start_time, event.created_at)?;
-                    self.label = EventSMLabel::Away;
-                    Ok(())
-                }
-                WorkEvent::StatusChange(uuid, _, WorkStatus::Working)
-                    if self.hours_raw.staff_member.uuid() == uuid =>
-                {
-                    self.append_soft_error(SoftStatisticsError::AlreadyWorking(
-                        event.created_at,
-                        self.hours_raw.staff_member.name.clone(),
-                    ));
-                    Ok(())
-                }
-                WorkEvent::_6am => {
-                    self.append_soft_error(SoftStatisticsError::StaffStillWorking(
-                        event.created_at,
-                        self.hours_raw.staff_member.name.clone(),
-                    ));
-                    self.add_time(start_time, event.created_at)?;
-                    self.label = EventSMLabel::Away;
-                    Ok(())
-                }
-                _ => Ok(()),
-            },
-        }
-    }
-
-    pub fn finish(self) -> (PersonHours<'a>, Vec<SoftStatisticsError>) {
-        (self.hours_raw, self.soft_errors)
-    }
+/// The start and end time will be first and last day of the selected month, respectively.
+pub fn month_bounds(settings: &Settings, date: Date<Local>) -> (NaiveDateTime, NaiveDateTime) {
+    let cutoff = settings.cutoff_time;
+    let start_time = date.naive_local().first_dom().and_time(cutoff);
+    let end_time = date.naive_local().last_dom().succ().and_time(cutoff);
+    (start_time, end_time)
 }
 
+/// With `include_inactive` hidden and deactivated staff members are evaluated as well, see `EvaluationStaff`.
 pub fn evaluate_hours_for_month(
     shared: &mut SharedData,
     date: Date<Local>,
+    include_inactive: bool,
 ) -> Result<StaffHours, StechuhrError> {
-    // The start and end time will be first and last day of the selected month, respectively.
-    let _6am = NaiveTime::from_hms(6, 0, 0);
-    let start_time = date.naive_local().first_dom().and_time(_6am);
-    let end_time = date.naive_local().last_dom().succ().and_time(_6am);
+    let (start_time, end_time) = month_bounds(&shared.settings, date);
+    let locale = shared.settings.locale();
 
     let start_time_local = Local.from_local_datetime(&start_time).unwrap();
     let end_time_local = Local.from_local_datetime(&end_time).unwrap();
 
     shared.log_info(format!(
         "Starte Auswertung für {}, zwischen {} und {}",
-        date.format_localized("%B %Y", Locale::de_DE).to_string(),
-        start_time_local
-            .format_localized("%d. %B (%R)", Locale::de_DE)
-            .to_string(),
-        end_time_local
-            .format_localized("%d. %B (%R)", Locale::de_DE)
-            .to_string()
+        date.format_localized("%B %Y", locale),
+        start_time_local.format_localized("%d. %B (%R)", locale),
+        end_time_local.format_localized("%d. %B (%R)", locale)
     ));
 
-    evaluate_hours_for_time(shared, start_time, end_time)
+    evaluate_hours_for_time(shared, start_time, end_time, include_inactive)
 }
 
-fn evaluate_hours_for_time(
+/// Evaluate a free range, e.g. a single weekend event, with the same rules as a month.
+pub fn evaluate_hours_for_range(
     shared: &mut SharedData,
     start_time: NaiveDateTime,
     end_time: NaiveDateTime,
+    include_inactive: bool,
 ) -> Result<StaffHours, StechuhrError> {
-    // Load events before the evaluation period in order to set the correct initial status for staff members.
-    let previous_events = db::load_events_between(None, Some(start_time), &mut shared.connection);
-    let events = db::load_events_between(Some(start_time), Some(end_time), &mut shared.connection);
-    let raw_staff = shared
-        .staff
+    let locale = shared.settings.locale();
+    let start_time_local = Local.from_local_datetime(&start_time).unwrap();
+    let end_time_local = Local.from_local_datetime(&end_time).unwrap();
+
+    shared.log_info(format!(
+        "Starte Auswertung zwischen {} und {}",
+        start_time_local.format_localized("%d. %B %Y (%R)", locale),
+        end_time_local.format_localized("%d. %B %Y (%R)", locale)
+    ));
+    evaluate_hours_for_time(shared, start_time, end_time, include_inactive)
+}
+
+/// Evaluate each month from the month of `from` to the month of `to`, e.g. for the bookkeeping at the end of the
+/// year. The events of all months are loaded at once and split at the month bounds.
+pub fn evaluate_hours_for_months(
+    shared: &mut SharedData,
+    from: Date<Local>,
+    to: Date<Local>,
+    include_inactive: bool,
+) -> Result<Vec<(Date<Local>, StaffHours)>, StechuhrError> {
+    let months = months_between(from, to);
+    let bounds = months
         .iter()
-        // Only do calculation for visible staff members.
-        .filter(|staff_member| staff_member.is_visible)
-        // Turn everyone into DBStaffMember to forget the working status.
-        .map(|staff_member| DBStaffMember::from(Cow::Borrowed(staff_member)))
+        .map(|month| month_bounds(&shared.settings, *month))
         .collect::<Vec<_>>();
+    let (start_time, end_time) = match (bounds.first(), bounds.last()) {
+        (Some((start_time, _)), Some((_, end_time))) => (*start_time, *end_time),
+        _ => return Ok(Vec::new()),
+    };
+    let locale = shared.settings.locale();
+    shared.log_info(format!(
+        "Starte Auswertung für {} Monate von {} bis {}",
+        months.len(),
+        from.format_localized("%B %Y", locale),
+        to.format_localized("%B %Y", locale)
+    ));
+
+    // The events before each month determine who is already working at its start, so the events of the previous
+    // months are appended to the status events before the first one.
+    let mut events = shared.storage.load_status_events_before(start_time);
+    let first = events.len();
+    events.extend(shared.storage.load_events_of_kinds_between(
+        EVALUATED_KINDS,
+        start_time,
+        end_time,
+    ));
+    let (staff, bucket_bounds) = evaluation_input(shared, include_inactive);
+    let rounding = shared.settings.rounding;
+    let week_events = if staff.has_minors() {
+        with_edge_weeks(shared, &events[first..], start_time, end_time)
+    } else {
+        Vec::new()
+    };
 
-    evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time)
+    months
+        .into_iter()
+        .zip(month_ranges(&events[first..], &bounds))
+        .zip(bounds)
+        .map(|((month, range), (month_start, month_end))| {
+            let range = first + range.start..first + range.end;
+            let raw_staff = staff.for_events(&events[range.clone()]);
+            let minors = minors(&raw_staff);
+            let mut hours = evaluate_hours_for_events(
+                raw_staff,
+                &events[range.clone()],
+                &events[..range.start],
+                month_start,
+                &bucket_bounds,
+                rounding,
+            )?;
+            hours.check_rest_periods(&shared.settings);
+            hours.check_youth_weeks(
+                &minors,
+                &week_events,
+                month_start,
+                month_end,
+                shared.settings.cutoff_time,
+            );
+            Ok((month, hours))
+        })
+        .collect()
 }
 
-fn evaluate_hours_for_events(
-    raw_staff: Vec<DBStaffMember>,
+/// The first day of each month from the month of `from` to the month of `to`.
+fn months_between(from: Date<Local>, to: Date<Local>) -> Vec<Date<Local>> {
+    let mut months = Vec::new();
+    let mut month = from.naive_local().first_dom();
+    while month <= to.naive_local() {
+        months.push(Local.from_local_date(&month).unwrap());
+        month = month.last_dom().succ();
+    }
+    months
+}
+
+/// Indices of the events in each of the given periods, the events must be ordered by time.
+fn month_ranges(
+    events: &[WorkEventT],
+    bounds: &[(NaiveDateTime, NaiveDateTime)],
+) -> Vec<Range<usize>> {
+    bounds
+        .iter()
+        .map(|(start_time, end_time)| {
+            events.partition_point(|eventt| eventt.created_at < *start_time)
+                ..events.partition_point(|eventt| eventt.created_at < *end_time)
+        })
+        .collect()
+}
+
+/// The events of the whole weeks at the start and the end of the period from `start_time` to `end_time`, with the
+/// given `events` of the period in between, so that the weekly limit of minors also counts the days outside of it.
+fn with_edge_weeks(
+    shared: &mut SharedData,
     events: &[WorkEventT],
-    previous_events: &[WorkEventT],
     start_time: NaiveDateTime,
-) -> Result<StaffHours, StechuhrError> {
-    // Set the initial status for staff members.
-    // Atm we only do evaluation starting at 6am on the 1st of the month, so no one will be working as we set everyone to non-working at 6am.
-    let staff = raw_staff
-        .into_iter()
-        // Compute the initial status.
-        .map(|staff_member| db::staff_member_compute_status(staff_member, &previous_events))
-        .collect::<Vec<_>>();
+    end_time: NaiveDateTime,
+) -> Vec<WorkEventT> {
+    let cutoff = shared.settings.cutoff_time;
+    let week_start = youth_protection::week_start_time(start_time, cutoff);
+    let week_end = youth_protection::week_start_time(end_time - Duration::seconds(1), cutoff)
+        + Duration::weeks(1);
 
-    let (hours, soft_errors): (Vec<PersonHours>, Vec<Vec<SoftStatisticsError>>) = staff
+    let mut week_events =
+        shared
+            .storage
+            .load_events_of_kinds_between(EVALUATED_KINDS, week_start, start_time);
+    week_events.extend_from_slice(events);
+    week_events.extend(shared.storage.load_events_of_kinds_between(
+        EVALUATED_KINDS,
+        end_time,
+        week_end,
+    ));
+    week_events
+}
+
+/// The staff members under 18, for which the limits of the youth labor law are checked.
+fn minors(staff: &[DBStaffMember]) -> Vec<DBStaffMember> {
+    staff
         .iter()
-        // Associate with each staff member a WorkDuration, which counts the minutes of work time
-        .map(move |staff_member| evaluate_hours_for_staff_member(staff_member, &events, start_time))
-        .collect::<Result<Vec<(PersonHours, Vec<SoftStatisticsError>)>, StatisticsError>>()?
-        .into_iter()
-        .unzip();
+        .filter(|staff_member| staff_member.is_minor())
+        .cloned()
+        .collect()
+}
 
-    let hours_csv: Vec<PersonHoursCSV> = hours
-        .into_iter()
-        // Transform the calculated WorkDuration into a PersonHours struct for serialization.
-        .map(PersonHoursCSV::from)
-        .collect();
-
-    Ok(StaffHours {
-        hours_csv,
-        soft_errors: soft_errors.into_iter().flatten().collect(),
-    })
+/// Info and Error events do not influence the work time.
+const EVALUATED_KINDS: &[&str] = &[
+    WorkEvent::KIND_STATUS_CHANGE,
+    WorkEvent::KIND_6AM,
+    WorkEvent::KIND_CORRECTION,
+];
+
+/// Staff members of an evaluation. Hidden and deactivated ones are only evaluated on request, and then only in the
+/// periods with events about them, so that long gone staff members do not fill the exports with zeros.
+struct EvaluationStaff {
+    visible: Vec<DBStaffMember>,
+    others: Vec<DBStaffMember>,
 }
 
-/// Create a EventSM state machine and feed all WorkEventT events to it to compute the StaffMemberHours.
-fn evaluate_hours_for_staff_member<'a>(
-    staff_member: &'a StaffMember,
-    events: &[WorkEventT],
-    start_time: NaiveDateTime,
-) -> Result<(PersonHours<'a>, Vec<SoftStatisticsError>), StatisticsError> {
-    let initial_start_time = if staff_member.status == WorkStatus::Working {
-        Some(start_time)
+impl EvaluationStaff {
+    /// The staff members to evaluate for a period with the given events.
+    fn for_events(&self, events: &[WorkEventT]) -> Vec<DBStaffMember> {
+        let with_events = events
+            .iter()
+            .filter_map(|eventt| eventt.event.staff_id())
+            .collect::<HashSet<_>>();
+        self.visible
+            .iter()
+            .chain(
+                self.others
+                    .iter()
+                    .filter(|staff_member| with_events.contains(&staff_member.uuid())),
+            )
+            .cloned()
+            .collect()
+    }
+
+    /// Whether any of the staff members is under 18, only then the weeks around a period are loaded.
+    fn has_minors(&self) -> bool {
+        self.visible
+            .iter()
+            .chain(&self.others)
+            .any(DBStaffMember::is_minor)
+    }
+}
+
+/// The staff members and the buckets for an evaluation with the current settings.
+fn evaluation_input(
+    shared: &mut SharedData,
+    include_inactive: bool,
+) -> (EvaluationStaff, BucketBounds) {
+    let visible = shared
+        .staff
+        .iter()
+        // Only do calculation for visible staff members.
+        .filter(|staff_member| staff_member.is_visible)
+        // Turn everyone into DBStaffMember to forget the working status.
+        .map(|staff_member| DBStaffMember::from(Cow::Borrowed(staff_member)))
+        .collect::<Vec<_>>();
+    let others = if include_inactive {
+        shared
+            .storage
+            .load_staff_including_inactive()
+            .into_iter()
+            .filter(|record| {
+                !visible
+                    .iter()
+                    .any(|staff_member| staff_member.uuid() == record.uuid())
+            })
+            .collect()
     } else {
-        None
+        Vec::new()
     };
+    (
+        EvaluationStaff { visible, others },
+        bucket_bounds(&shared.settings),
+    )
+}
+
+/// The buckets of the settings, with the Sunday buckets if they are enabled, without too short intervals and with the
+/// limit for too long ones.
+fn bucket_bounds(settings: &Settings) -> BucketBounds {
+    let mut bounds = BucketBounds::new(settings.bucket_starts)
+        .with_min_interval(Duration::seconds(i64::from(settings.min_interval_seconds)));
+    if settings.max_shift_hours > 0 {
+        bounds = bounds.with_max_interval(Duration::hours(i64::from(settings.max_shift_hours)));
+    }
+    if settings.sunday_buckets {
+        bounds
+            .with_sunday_buckets(&settings.holidays)
+            .with_saturdays(settings.saturday_buckets)
+    } else {
+        bounds
+    }
+}
 
-    let mut event_sm = EventSM::new(staff_member, initial_start_time);
+fn evaluate_hours_for_time(
+    shared: &mut SharedData,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    include_inactive: bool,
+) -> Result<StaffHours, StechuhrError> {
+    // Load events before the evaluation period in order to set the correct initial status for staff members.
+    let previous_events = shared.storage.load_status_events_before(start_time);
+    let events = shared
+        .storage
+        .load_events_of_kinds_between(EVALUATED_KINDS, start_time, end_time);
+    let (staff, bounds) = evaluation_input(shared, include_inactive);
+    let raw_staff = staff.for_events(&events);
+    let minors = minors(&raw_staff);
 
-    for event in events {
-        event_sm.process(event)?;
+    let mut hours = evaluate_hours_for_events(
+        raw_staff,
+        &events,
+        &previous_events,
+        start_time,
+        &bounds,
+        shared.settings.rounding,
+    )?;
+    hours.check_rest_periods(&shared.settings);
+    if !minors.is_empty() {
+        let week_events = with_edge_weeks(shared, &events, start_time, end_time);
+        hours.check_youth_weeks(
+            &minors,
+            &week_events,
+            start_time,
+            end_time,
+            shared.settings.cutoff_time,
+        );
     }
+    Ok(hours)
+}
 
-    Ok(event_sm.finish())
+/// The evaluation of the library, with the minutes prepared for the exports.
+fn evaluate_hours_for_events(
+    raw_staff: Vec<DBStaffMember>,
+    events: &[WorkEventT],
+    previous_events: &[WorkEventT],
+    start_time: NaiveDateTime,
+    bounds: &BucketBounds,
+    rounding: Rounding,
+) -> Result<StaffHours, StechuhrError> {
+    let evaluation = evaluation::evaluate_hours_for_events(
+        raw_staff,
+        events,
+        previous_events,
+        start_time,
+        bounds,
+    )
+    .map_err(StatisticsError::from)?;
+    Ok(StaffHours::new(
+        evaluation,
+        bounds.has_sunday_buckets(),
+        rounding,
+    ))
 }
 
+#[cfg(test)]
+mod scenarios;
+
 #[cfg(test)]
 mod tests {
-    use chrono::NaiveDate;
+    use chrono::{Duration, Local, NaiveDate, NaiveTime, TimeZone};
     use stechuhr::models::{DBStaffMember, WorkEvent, WorkEventT, WorkStatus};
+    use stechuhr::settings::{Rounding, Settings};
+    use stechuhr::youth_protection::Violation;
+
+    use stechuhr::evaluation::{BucketBounds, SoftStatisticsError};
 
-    use crate::tabs::statistics::SoftStatisticsError;
+    use super::{
+        evaluate_hours_for_events, month_bounds, month_ranges, months_between, EvaluationStaff,
+    };
 
-    use super::evaluate_hours_for_events;
+    fn default_bounds() -> BucketBounds {
+        BucketBounds::new(Settings::default().bucket_starts)
+    }
 
     /// evaluate_hours_for_events where staff member has no StatusChange events.
     #[test]
@@ -228,13 +373,21 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![];
         let previous_events = vec![];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert!(hours.errors().is_empty());
 
@@ -243,6 +396,54 @@ mod tests {
         assert_eq!(hours.hours()[0].minutes_3, 0);
     }
 
+    /// evaluate_hours_for_events with quarter-hour rounding of each bucket.
+    #[test]
+    fn rounded_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(19, 53, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(23, 52, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let evaluate = |rounding| {
+            evaluate_hours_for_events(
+                raw_staff.clone(),
+                &events,
+                &[],
+                start_time,
+                &default_bounds(),
+                rounding,
+            )
+            .unwrap()
+        };
+
+        let hours = evaluate(Rounding::Off);
+        assert_eq!(hours.hours()[0].minutes_1, 7);
+        assert_eq!(hours.hours()[0].minutes_2, 3 * 60 + 52);
+        let hours = evaluate(Rounding::Nearest15);
+        assert_eq!(hours.hours()[0].minutes_1, 0);
+        assert_eq!(hours.hours()[0].minutes_2, 3 * 60 + 45);
+        let hours = evaluate(Rounding::Up15);
+        assert_eq!(hours.hours()[0].minutes_1, 15);
+        assert_eq!(hours.hours()[0].minutes_2, 4 * 60);
+    }
+
     /// evaluate_hours_for_events where staff member has some worktime in all slots.
     #[test]
     fn normal_worktime() {
@@ -252,6 +453,7 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![
             WorkEventT::new(
@@ -288,14 +490,281 @@ mod tests {
         let previous_events = vec![];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert!(hours.errors().is_empty());
 
         assert_eq!(hours.hours()[0].minutes_1, 3 * 60);
         assert_eq!(hours.hours()[0].minutes_2, 1 * 60 + 30);
         assert_eq!(hours.hours()[0].minutes_3, 3 * 60);
+        // Each interval is kept for the export of single shifts.
+        let intervals = &hours.hours()[0].intervals;
+        assert_eq!(intervals.len(), 3);
+        assert_eq!(intervals[1].start_time, events[2].created_at);
+        assert_eq!(intervals[1].end_time, events[3].created_at);
+        assert_eq!(intervals[1].minutes, [0, 60, 2 * 60, 0, 0, 0]);
+    }
+
+    /// evaluate_hours_for_events with buckets 6 - 22, 22 - 24 and 24 - 6 Uhr.
+    #[test]
+    fn custom_buckets_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(21, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(1, 30, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &BucketBounds::new([6, 22, 0]),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert!(hours.errors().is_empty());
+
+        assert_eq!(hours.hours()[0].minutes_1, 60);
+        assert_eq!(hours.hours()[0].minutes_2, 2 * 60);
+        assert_eq!(hours.hours()[0].minutes_3, 60 + 30);
+    }
+
+    /// evaluate_hours_for_events with only two buckets 6 - 22 and 22 - 6 Uhr, starting to work in the second one.
+    #[test]
+    fn two_buckets_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let status = |id, time, status| {
+            WorkEventT::new(
+                id,
+                time,
+                WorkEvent::StatusChange(1, String::from("Aaron"), status),
+            )
+        };
+        let events = vec![
+            status(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0),
+                WorkStatus::Working,
+            ),
+            status(
+                2,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(21, 0, 0),
+                WorkStatus::Away,
+            ),
+            status(
+                3,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(23, 0, 0),
+                WorkStatus::Working,
+            ),
+            status(
+                4,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(1, 30, 0),
+                WorkStatus::Away,
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &BucketBounds::new([6, 22, 22]),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert!(hours.errors().is_empty());
+
+        assert_eq!(hours.hours()[0].minutes_1, 60);
+        assert_eq!(hours.hours()[0].minutes_2, 0);
+        assert_eq!(hours.hours()[0].minutes_3, 2 * 60 + 30);
+    }
+
+    /// evaluate_hours_for_events with separate buckets for sundays and holidays.
+    #[test]
+    fn sunday_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        // 2000-01-01 is a saturday, the shift goes into the sunday.
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(22, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(2, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+            WorkEventT::new(
+                3,
+                NaiveDate::from_ymd(2000, 1, 3).and_hms(10, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                4,
+                NaiveDate::from_ymd(2000, 1, 3).and_hms(11, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+        let holidays = [NaiveDate::from_ymd(2000, 1, 3)];
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds().with_sunday_buckets(&holidays),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert!(hours.errors().is_empty());
+
+        assert_eq!(hours.hours()[0].minutes_1, 0);
+        assert_eq!(hours.hours()[0].minutes_2, 2 * 60);
+        assert_eq!(hours.hours()[0].minutes_3, 0);
+        assert_eq!(hours.hours()[0].sunday_minutes_1, Some(60));
+        assert_eq!(hours.hours()[0].sunday_minutes_2, Some(0));
+        assert_eq!(hours.hours()[0].sunday_minutes_3, Some(2 * 60));
+    }
+
+    /// evaluate_hours_for_events where Saturdays are counted like Sundays, 2000-01-01 was a Saturday.
+    #[test]
+    fn saturday_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(18, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let evaluate = |bounds: &BucketBounds| {
+            evaluate_hours_for_events(
+                raw_staff.clone(),
+                &events,
+                &previous_events,
+                start_time,
+                bounds,
+                Rounding::Off,
+            )
+            .unwrap()
+        };
+
+        let hours = evaluate(&default_bounds().with_sunday_buckets(&[]));
+        assert_eq!(hours.hours()[0].minutes_1, 2 * 60);
+        assert_eq!(hours.hours()[0].sunday_minutes_1, Some(0));
+
+        let hours = evaluate(
+            &default_bounds()
+                .with_sunday_buckets(&[])
+                .with_saturdays(true),
+        );
+        assert_eq!(hours.hours()[0].minutes_1, 0);
+        assert_eq!(hours.hours()[0].sunday_minutes_1, Some(2 * 60));
+    }
+
+    /// evaluate_hours_for_events with fractional seconds around midnight, where rounding each day separately
+    /// would lose a second and fail the balance check.
+    #[test]
+    fn balanced_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms_milli(23, 59, 59, 500),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 3).and_hms_milli(0, 0, 0, 700),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds().with_sunday_buckets(&[]),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert!(hours.errors().is_empty());
+        // The one second is counted on the sunday evening.
+        assert_eq!(hours.hours()[0].sunday_minutes_2, Some(1));
     }
 
     /// evaluate_hours_for_events where staff member has been working before the time starts.
@@ -307,6 +776,7 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![WorkEventT::new(
             2,
@@ -320,8 +790,15 @@ mod tests {
         )];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(19, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert!(hours.errors().is_empty());
 
@@ -339,6 +816,7 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![
             WorkEventT::new(
@@ -355,8 +833,15 @@ mod tests {
         let previous_events = vec![];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert_eq!(
             hours.errors()[0],
@@ -371,6 +856,173 @@ mod tests {
         assert_eq!(hours.hours()[0].minutes_3, 0);
     }
 
+    /// evaluate_hours_for_events where the 6am event of a day exists twice and the staff member
+    /// starts working between them.
+    #[test]
+    fn duplicate_6am() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(5, 59, 59),
+                WorkEvent::_6am,
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(6, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                3,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(6, 30, 0),
+                WorkEvent::_6am,
+            ),
+            WorkEventT::new(
+                4,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(8, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert_eq!(hours.errors(), &[]);
+        assert_eq!(hours.hours()[0].minutes_1, 2 * 60);
+        assert_eq!(hours.hours()[0].minutes_2, 0);
+        assert_eq!(hours.hours()[0].minutes_3, 0);
+    }
+
+    /// evaluate_hours_for_events where a staff member under 18 works after midnight.
+    #[test]
+    fn error_worktime_minor() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )
+        .with_minor(true)];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(22, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(1, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert_eq!(
+            hours.errors(),
+            [SoftStatisticsError::YouthProtection(
+                String::from("Aaron"),
+                Violation::Night(NaiveDate::from_ymd(2000, 1, 2).and_hms(0, 0, 0))
+            )]
+        );
+        // The work after midnight is not credited.
+        assert_eq!(hours.hours()[0].minutes_2, 2 * 60);
+        assert_eq!(hours.hours()[0].minutes_3, 0);
+    }
+
+    /// check_youth_weeks where the week of a minor begins on the last day of the month.
+    #[test]
+    fn error_worktime_minor_week_at_month_end() {
+        let aaron = DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )
+        .with_minor(true);
+        // 2000-01-31 is a monday, only the first of six days with 8 hours is in January.
+        let events = (0..6)
+            .flat_map(|days| {
+                let date = NaiveDate::from_ymd(2000, 1, 31) + Duration::days(days);
+                [
+                    WorkEventT::new(
+                        2 * days as i32,
+                        date.and_hms(10, 0, 0),
+                        WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+                    ),
+                    WorkEventT::new(
+                        2 * days as i32 + 1,
+                        date.and_hms(18, 0, 0),
+                        WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+                    ),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let cutoff = NaiveTime::from_hms(6, 0, 0);
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_time(cutoff);
+        let end_time = NaiveDate::from_ymd(2000, 2, 1).and_time(cutoff);
+
+        let evaluate = |week_events: &[WorkEventT]| {
+            let mut hours = evaluate_hours_for_events(
+                vec![aaron.clone()],
+                &events[..2],
+                &[],
+                start_time,
+                &default_bounds(),
+                Rounding::Off,
+            )
+            .unwrap();
+            hours.check_youth_weeks(
+                std::slice::from_ref(&aaron),
+                week_events,
+                start_time,
+                end_time,
+                cutoff,
+            );
+            hours
+        };
+
+        // Only the day in January is not enough for the weekly limit.
+        assert_eq!(evaluate(&events[..2]).errors(), []);
+        assert_eq!(
+            evaluate(&events).errors(),
+            [SoftStatisticsError::YouthProtection(
+                String::from("Aaron"),
+                Violation::Weekly(NaiveDate::from_ymd(2000, 1, 31), Duration::hours(48))
+            )]
+        );
+    }
+
     /// evaluate_hours_for_events where staff member has two consecutive StatusChange events to Working
     #[test]
     fn error_worktime_already_working() {
@@ -380,6 +1032,7 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![
             WorkEventT::new(
@@ -401,8 +1054,15 @@ mod tests {
         let previous_events = vec![];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert_eq!(
             hours.errors()[0],
@@ -426,6 +1086,7 @@ mod tests {
             String::from("1111"),
             String::from("1111111111"),
             true,
+            None,
         )];
         let events = vec![
             WorkEventT::new(
@@ -447,8 +1108,15 @@ mod tests {
         let previous_events = vec![];
         let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
 
-        let hours =
-            evaluate_hours_for_events(raw_staff, &events, &previous_events, start_time).unwrap();
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
 
         assert_eq!(
             hours.errors()[0],
@@ -462,4 +1130,126 @@ mod tests {
         assert_eq!(hours.hours()[0].minutes_2, 0);
         assert_eq!(hours.hours()[0].minutes_3, 0);
     }
+
+    /// evaluate_hours_for_events where the events of the staff member were corrected by an admin.
+    #[test]
+    fn corrected_worktime() {
+        let raw_staff = vec![DBStaffMember::new(
+            1,
+            String::from("Aaron"),
+            String::from("1111"),
+            String::from("1111111111"),
+            true,
+            None,
+        )];
+        let events = vec![
+            WorkEventT::new(
+                1,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(21, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Away),
+            ),
+            WorkEventT::new(
+                3,
+                NaiveDate::from_ymd(2000, 1, 1).and_hms(21, 0, 0),
+                WorkEvent::Correction(1, String::from("Aaron"), String::from("Abgemeldet")),
+            ),
+        ];
+        let previous_events = vec![];
+        let start_time = NaiveDate::from_ymd(2000, 1, 1).and_hms(6, 0, 0);
+
+        let hours = evaluate_hours_for_events(
+            raw_staff,
+            &events,
+            &previous_events,
+            start_time,
+            &default_bounds(),
+            Rounding::Off,
+        )
+        .unwrap();
+
+        assert!(hours.errors().is_empty());
+
+        assert_eq!(hours.hours()[0].name, "Aaron *");
+        assert_eq!(hours.hours()[0].corrections, 1);
+        assert_eq!(hours.hours()[0].minutes_2, 60);
+    }
+
+    #[test]
+    fn split_months() {
+        let settings = Settings::default();
+        let date = |y, m, d| {
+            Local
+                .from_local_date(&NaiveDate::from_ymd(y, m, d))
+                .unwrap()
+        };
+        let months = months_between(date(2021, 11, 15), date(2022, 2, 1));
+        assert_eq!(
+            months,
+            vec![
+                date(2021, 11, 1),
+                date(2021, 12, 1),
+                date(2022, 1, 1),
+                date(2022, 2, 1)
+            ]
+        );
+
+        let bounds = months
+            .iter()
+            .map(|month| month_bounds(&settings, *month))
+            .collect::<Vec<_>>();
+        let event = |id, time| WorkEventT::new(id, time, WorkEvent::_6am);
+        let events = vec![
+            event(1, NaiveDate::from_ymd(2021, 11, 2).and_hms(5, 59, 59)),
+            // Before the cutoff on the first day still belongs to the previous month.
+            event(2, NaiveDate::from_ymd(2022, 1, 1).and_hms(5, 59, 59)),
+            event(3, NaiveDate::from_ymd(2022, 1, 1).and_hms(6, 0, 0)),
+        ];
+        assert_eq!(month_ranges(&events, &bounds), vec![0..1, 1..2, 2..3, 3..3]);
+    }
+
+    /// Hidden and deleted staff members are only evaluated in periods with events about them.
+    #[test]
+    fn inactive_with_events() {
+        let staff_member = |uuid, name: &str, is_visible| {
+            DBStaffMember::new(
+                uuid,
+                String::from(name),
+                String::new(),
+                String::new(),
+                is_visible,
+                None,
+            )
+        };
+        let staff = EvaluationStaff {
+            visible: vec![staff_member(1, "Aaron", true)],
+            others: vec![
+                staff_member(2, "Beeron", false),
+                staff_member(3, "Cem", true),
+            ],
+        };
+        let time = NaiveDate::from_ymd(2000, 1, 1).and_hms(20, 0, 0);
+        let events = vec![
+            WorkEventT::new(1, time, WorkEvent::_6am),
+            WorkEventT::new(
+                2,
+                time,
+                WorkEvent::StatusChange(3, String::new(), WorkStatus::Working),
+            ),
+        ];
+
+        let uuids = |events: &[WorkEventT]| {
+            staff
+                .for_events(events)
+                .iter()
+                .map(DBStaffMember::uuid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uuids(&events), vec![1, 3]);
+        assert_eq!(uuids(&events[..1]), vec![1]);
+    }
 }
@@ -0,0 +1,238 @@
+//! Evaluation scenarios from the fixture files in `testdata/scenarios`. Each file describes the staff, the events and
+//! the settings of an evaluation together with the expected minutes, so that edge cases can be added without writing
+//! Rust and the evaluation can be refactored safely. See `testdata/scenarios/README.md` for the format.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use stechuhr::models::{DBStaffMember, WorkEvent, WorkEventT, WorkStatus};
+use stechuhr::settings::Settings;
+
+use super::{bucket_bounds, evaluate_hours_for_events};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    /// What the scenario checks, shown when it fails.
+    description: String,
+    /// Only the settings that differ from the defaults.
+    #[serde(default)]
+    settings: Settings,
+    start_time: NaiveDateTime,
+    staff: Vec<ScenarioStaffMember>,
+    /// Events before the start time, from which the status at the start is known.
+    #[serde(default)]
+    previous_events: Vec<ScenarioEvent>,
+    events: Vec<ScenarioEvent>,
+    expected: Vec<ExpectedHours>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioStaffMember {
+    uuid: i32,
+    name: String,
+    #[serde(default)]
+    is_minor: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum ScenarioEvent {
+    SignIn {
+        time: NaiveDateTime,
+        uuid: i32,
+    },
+    SignOff {
+        time: NaiveDateTime,
+        uuid: i32,
+    },
+    #[serde(rename = "6am")]
+    SixAm {
+        time: NaiveDateTime,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedHours {
+    name: String,
+    minutes: [i64; 3],
+    /// Only checked if the Sunday buckets are enabled.
+    #[serde(default)]
+    sunday_minutes: Option<[i64; 3]>,
+    /// Number of soft errors about this staff member.
+    #[serde(default)]
+    errors: usize,
+}
+
+impl ScenarioEvent {
+    fn time(&self) -> NaiveDateTime {
+        match *self {
+            ScenarioEvent::SignIn { time, .. }
+            | ScenarioEvent::SignOff { time, .. }
+            | ScenarioEvent::SixAm { time } => time,
+        }
+    }
+}
+
+impl Scenario {
+    fn load(path: &Path) -> Scenario {
+        let json = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&json)
+            .unwrap_or_else(|e| panic!("{} kann nicht gelesen werden: {}", path.display(), e))
+    }
+
+    fn events(&self, events: &[ScenarioEvent]) -> Vec<WorkEventT> {
+        let name = |uuid| {
+            self.staff
+                .iter()
+                .find(|staff_member| staff_member.uuid == uuid)
+                .map(|staff_member| staff_member.name.clone())
+                .unwrap_or_default()
+        };
+        events
+            .iter()
+            .zip(1..)
+            .map(|(event, id)| match *event {
+                ScenarioEvent::SignIn { time, uuid } => WorkEventT::new(
+                    id,
+                    time,
+                    WorkEvent::StatusChange(uuid, name(uuid), WorkStatus::Working),
+                ),
+                ScenarioEvent::SignOff { time, uuid } => WorkEventT::new(
+                    id,
+                    time,
+                    WorkEvent::StatusChange(uuid, name(uuid), WorkStatus::Away),
+                ),
+                ScenarioEvent::SixAm { time } => WorkEventT::new(id, time, WorkEvent::_6am),
+            })
+            .collect()
+    }
+
+    /// Evaluate the scenario like the statistics tab does and describe every difference to the expected hours.
+    fn mismatches(&self) -> Vec<String> {
+        // The evaluation expects the events in the order of the database, so the files must list them by time.
+        for (name, events) in [
+            ("previous_events", &self.previous_events),
+            ("events", &self.events),
+        ] {
+            if !events.windows(2).all(|w| w[0].time() <= w[1].time()) {
+                return vec![format!("{} sind nicht nach der Zeit sortiert", name)];
+            }
+        }
+
+        let raw_staff = self
+            .staff
+            .iter()
+            .map(|staff_member| {
+                DBStaffMember::new(
+                    staff_member.uuid,
+                    staff_member.name.clone(),
+                    String::new(),
+                    String::new(),
+                    true,
+                    None,
+                )
+                .with_minor(staff_member.is_minor)
+            })
+            .collect();
+        let mut hours = match evaluate_hours_for_events(
+            raw_staff,
+            &self.events(&self.events),
+            &self.events(&self.previous_events),
+            self.start_time,
+            &bucket_bounds(&self.settings),
+            self.settings.rounding,
+        ) {
+            Ok(hours) => hours,
+            Err(e) => return vec![format!("Auswertung fehlgeschlagen: {}", e)],
+        };
+        hours.check_rest_periods(&self.settings);
+
+        let mut mismatches = Vec::new();
+        for expected in &self.expected {
+            let actual = match hours
+                .hours()
+                .iter()
+                .find(|hours| hours.staff_name == expected.name)
+            {
+                Some(actual) => actual,
+                None => {
+                    mismatches.push(format!("{} fehlt in der Auswertung", expected.name));
+                    continue;
+                }
+            };
+            let minutes = [actual.minutes_1, actual.minutes_2, actual.minutes_3];
+            if minutes != expected.minutes {
+                mismatches.push(format!(
+                    "{}: Minuten {:?} statt {:?}",
+                    expected.name, minutes, expected.minutes
+                ));
+            }
+            if let Some(sunday_minutes) = expected.sunday_minutes {
+                let actual_sunday_minutes = [
+                    actual.sunday_minutes_1,
+                    actual.sunday_minutes_2,
+                    actual.sunday_minutes_3,
+                ];
+                if actual_sunday_minutes != sunday_minutes.map(Some) {
+                    mismatches.push(format!(
+                        "{}: Sonntagsminuten {:?} statt {:?}",
+                        expected.name, actual_sunday_minutes, sunday_minutes
+                    ));
+                }
+            }
+            let errors = hours
+                .errors()
+                .iter()
+                .filter(|error| error.staff_name() == expected.name)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            if errors.len() != expected.errors {
+                mismatches.push(format!(
+                    "{}: {} Fehler statt {}: {:?}",
+                    expected.name,
+                    errors.len(),
+                    expected.errors,
+                    errors
+                ));
+            }
+        }
+        mismatches
+    }
+}
+
+fn scenario_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/scenarios");
+    let mut files = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("json".as_ref()))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// All scenarios are run before failing, so that one broken edge case does not hide the others.
+#[test]
+fn evaluation_scenarios() {
+    let files = scenario_files();
+    assert!(!files.is_empty());
+
+    let mut failures = Vec::new();
+    for path in files {
+        let scenario = Scenario::load(&path);
+        let mismatches = scenario.mismatches();
+        if !mismatches.is_empty() {
+            failures.push(format!(
+                "{} ({}):\n  {}",
+                path.file_name().unwrap().to_string_lossy(),
+                scenario.description,
+                mismatches.join("\n  ")
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
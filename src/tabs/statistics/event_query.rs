@@ -0,0 +1,528 @@
+//! Questions about the events that come up often, e.g. how often someone signed in last week or which corrections were
+//! made this month, without exporting the log and filtering it in Excel. The admin picks a person, a period and a kind
+//! of event and sees the matching events with their number and the time worked between the sign-ins and sign-offs.
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{Date, Duration, Local, NaiveDateTime};
+use iced::{
+    alignment::Horizontal, button, pick_list, scrollable, Alignment, Button, Column, Element,
+    Length, PickList, Row, Scrollable, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::models::{WorkEvent, WorkEventT, WorkStatus};
+
+use super::{event_eval, person_preview::format_minutes, StatsMessage};
+use crate::SharedData;
+
+/// More events are counted but not listed, the dialog would become unusably slow.
+const MAX_LISTED_EVENTS: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryPerson {
+    uuid: Option<i32>,
+    name: String,
+}
+
+impl QueryPerson {
+    fn everybody() -> Self {
+        QueryPerson {
+            uuid: None,
+            name: String::from("Alle Personen"),
+        }
+    }
+}
+
+impl fmt::Display for QueryPerson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryPeriod {
+    Today,
+    LastWeek,
+    SelectedMonth,
+    LastYear,
+}
+
+impl QueryPeriod {
+    const ALL: [QueryPeriod; 4] = [
+        QueryPeriod::Today,
+        QueryPeriod::LastWeek,
+        QueryPeriod::SelectedMonth,
+        QueryPeriod::LastYear,
+    ];
+
+    /// Start and end of the period in whole business days. The selected month is the one of the statistics tab, the
+    /// last 12 months start with the first day of the month eleven months ago like "12 Monate je Person".
+    fn bounds(
+        self,
+        shared: &SharedData,
+        date: Date<Local>,
+        now: NaiveDateTime,
+    ) -> (NaiveDateTime, NaiveDateTime) {
+        let settings = &shared.settings;
+        let today = settings.business_date(now);
+        let end_time = settings.business_day_start(today.succ());
+        match self {
+            QueryPeriod::Today => (settings.business_day_start(today), end_time),
+            QueryPeriod::LastWeek => (
+                settings.business_day_start(today - Duration::days(6)),
+                end_time,
+            ),
+            QueryPeriod::SelectedMonth => event_eval::month_bounds(settings, date),
+            QueryPeriod::LastYear => {
+                let first_month =
+                    (0..11).fold(today.first_dom(), |month, _| month.pred().first_dom());
+                (settings.business_day_start(first_month), end_time)
+            }
+        }
+    }
+}
+
+impl fmt::Display for QueryPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            QueryPeriod::Today => "Heute",
+            QueryPeriod::LastWeek => "Letzte 7 Tage",
+            QueryPeriod::SelectedMonth => "Ausgewählter Monat",
+            QueryPeriod::LastYear => "Letzte 12 Monate",
+        };
+        fmt::Display::fmt(str, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    All,
+    SignIn,
+    SignOff,
+    Correction,
+    Marker,
+    Error,
+    Info,
+    Lifecycle,
+    Boundary,
+}
+
+impl QueryKind {
+    const ALL: [QueryKind; 9] = [
+        QueryKind::All,
+        QueryKind::SignIn,
+        QueryKind::SignOff,
+        QueryKind::Correction,
+        QueryKind::Marker,
+        QueryKind::Error,
+        QueryKind::Info,
+        QueryKind::Lifecycle,
+        QueryKind::Boundary,
+    ];
+
+    /// The kinds of events to load from the database, nothing for all of them. The 6am events end the shifts of
+    /// everyone, so they are needed for the time worked.
+    fn kinds(self) -> Option<&'static [&'static str]> {
+        match self {
+            QueryKind::All => None,
+            QueryKind::SignIn | QueryKind::SignOff => {
+                Some(&[WorkEvent::KIND_STATUS_CHANGE, WorkEvent::KIND_6AM])
+            }
+            QueryKind::Correction => Some(&[WorkEvent::KIND_CORRECTION]),
+            QueryKind::Marker => Some(&[WorkEvent::KIND_MARKER]),
+            QueryKind::Error => Some(&[WorkEvent::KIND_ERROR]),
+            QueryKind::Info => Some(&[WorkEvent::KIND_INFO]),
+            QueryKind::Lifecycle => Some(&[WorkEvent::KIND_LIFECYCLE]),
+            QueryKind::Boundary => Some(&[WorkEvent::KIND_6AM]),
+        }
+    }
+
+    fn matches(self, event: &WorkEvent) -> bool {
+        match (self, event) {
+            (QueryKind::All, _) => true,
+            (QueryKind::SignIn, WorkEvent::StatusChange(_, _, status)) => {
+                *status == WorkStatus::Working
+            }
+            (QueryKind::SignOff, WorkEvent::StatusChange(_, _, status)) => {
+                *status == WorkStatus::Away
+            }
+            (QueryKind::SignIn | QueryKind::SignOff, _) => false,
+            (kind, event) => kind
+                .kinds()
+                .is_some_and(|kinds| kinds.contains(&event.kind())),
+        }
+    }
+
+    /// Markers, errors, hints, the program start and the day boundaries are not about a staff member, so the chosen
+    /// person does not apply to them.
+    fn has_person(self) -> bool {
+        matches!(
+            self,
+            QueryKind::All | QueryKind::SignIn | QueryKind::SignOff | QueryKind::Correction
+        )
+    }
+
+    /// The time worked is only shown for the kinds that contain sign-ins and sign-offs.
+    fn has_worked_time(self) -> bool {
+        matches!(
+            self,
+            QueryKind::All | QueryKind::SignIn | QueryKind::SignOff
+        )
+    }
+}
+
+impl fmt::Display for QueryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            QueryKind::All => "Alle Ereignisse",
+            QueryKind::SignIn => "Anmeldungen",
+            QueryKind::SignOff => "Abmeldungen",
+            QueryKind::Correction => "Korrekturen",
+            QueryKind::Marker => "Markierungen",
+            QueryKind::Error => "Fehler",
+            QueryKind::Info => "Hinweise",
+            QueryKind::Lifecycle => "Programmstart und -ende",
+            QueryKind::Boundary => "Tageswechsel",
+        };
+        fmt::Display::fmt(str, f)
+    }
+}
+
+/// The matching events of a query and the time worked in them.
+#[derive(Debug, Default)]
+struct QueryResult {
+    events: Vec<WorkEventT>,
+    /// Sum of the time between each sign-in and the following sign-off of the same person, if the kind contains them.
+    /// Like in the evaluation a shift without sign-off ends at the next 6am event.
+    /// A sign-in before the start or a sign-off after the end of the period is not known, so that shift is missing.
+    worked: Option<Duration>,
+}
+
+impl QueryResult {
+    fn new(mut events: Vec<WorkEventT>, kind: QueryKind) -> Self {
+        events.sort_by_key(|eventt| (eventt.created_at, eventt.id()));
+
+        let worked = kind.has_worked_time().then(|| {
+            let mut signed_in = HashMap::new();
+            let mut seconds = 0;
+            for eventt in &events {
+                match &eventt.event {
+                    WorkEvent::StatusChange(uuid, _, WorkStatus::Working) => {
+                        // Signing in twice keeps the first sign-in.
+                        signed_in.entry(*uuid).or_insert(eventt.created_at);
+                    }
+                    WorkEvent::StatusChange(uuid, _, WorkStatus::Away) => {
+                        if let Some(start) = signed_in.remove(uuid) {
+                            seconds += (eventt.created_at - start).num_seconds();
+                        }
+                    }
+                    WorkEvent::_6am => {
+                        for (_, start) in signed_in.drain() {
+                            seconds += (eventt.created_at - start).num_seconds();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Duration::seconds(seconds)
+        });
+
+        events.retain(|eventt| kind.matches(&eventt.event));
+        QueryResult { events, worked }
+    }
+}
+
+struct EventQueryModalState {
+    people: Vec<QueryPerson>,
+    person: QueryPerson,
+    period: QueryPeriod,
+    kind: QueryKind,
+    result: QueryResult,
+    /* descriptions of the listed events, with the names and texts of the log */
+    listed: Vec<(String, String)>,
+    // widget states
+    person_state: pick_list::State<QueryPerson>,
+    period_state: pick_list::State<QueryPeriod>,
+    kind_state: pick_list::State<QueryKind>,
+    scroll_state: scrollable::State,
+    close_state: button::State,
+}
+
+impl Default for EventQueryModalState {
+    fn default() -> Self {
+        EventQueryModalState {
+            people: Vec::new(),
+            person: QueryPerson::everybody(),
+            period: QueryPeriod::LastWeek,
+            kind: QueryKind::All,
+            result: QueryResult::default(),
+            listed: Vec::new(),
+            person_state: pick_list::State::default(),
+            period_state: pick_list::State::default(),
+            kind_state: pick_list::State::default(),
+            scroll_state: scrollable::State::default(),
+            close_state: button::State::default(),
+        }
+    }
+}
+
+pub struct EventQuery {
+    modal_state: modal::State<EventQueryModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EventQueryMessage {
+    Open,
+    ChoosePerson(QueryPerson),
+    ChoosePeriod(QueryPeriod),
+    ChooseKind(QueryKind),
+    Close,
+}
+
+impl EventQuery {
+    pub fn new() -> Self {
+        EventQuery {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Load the events for the chosen filters. Only the events of the chosen person and kinds are loaded.
+    fn query(&mut self, shared: &mut SharedData, date: Date<Local>) {
+        let state = self.modal_state.inner_mut();
+        let (start_time, end_time) = state
+            .period
+            .bounds(shared, date, Local::now().naive_local());
+        let person = state.person.uuid.filter(|_| state.kind.has_person());
+        // Sign-ins, sign-offs and the 6am events are all needed for the time worked.
+        let events = match (person, state.kind.kinds()) {
+            (Some(uuid), Some(_)) if state.kind.has_worked_time() => shared
+                .storage
+                .load_staff_status_events_between(uuid, start_time, end_time),
+            (Some(uuid), Some(_)) => shared
+                .storage
+                .load_staff_events_between(uuid, start_time, end_time),
+            (Some(uuid), None) => {
+                let mut events = shared
+                    .storage
+                    .load_staff_events_between(uuid, start_time, end_time);
+                events.extend(shared.storage.load_events_of_kinds_between(
+                    &[WorkEvent::KIND_6AM],
+                    start_time,
+                    end_time,
+                ));
+                events
+            }
+            (None, Some(kinds)) => shared
+                .storage
+                .load_events_of_kinds_between(kinds, start_time, end_time),
+            (None, None) => shared
+                .storage
+                .load_events_between(Some(start_time), Some(end_time)),
+        };
+        state.result = QueryResult::new(events, state.kind);
+        state.listed = state
+            .result
+            .events
+            .iter()
+            .take(MAX_LISTED_EVENTS)
+            .map(|eventt| {
+                (
+                    format!(
+                        "{} {}",
+                        shared.settings.format_export_date(eventt.created_at.date()),
+                        eventt.created_at.format("%H:%M:%S")
+                    ),
+                    shared.staff_names.describe(
+                        &eventt.event,
+                        shared.settings.show_former_names,
+                        &shared.settings.log_texts,
+                    ),
+                )
+            })
+            .collect();
+    }
+
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::EventQuery(message);
+
+        Modal::new(&mut self.modal_state, content, move |state| {
+            let person: Element<'_, StatsMessage> = if state.kind.has_person() {
+                PickList::new(
+                    &mut state.person_state,
+                    state.people.clone(),
+                    Some(state.person.clone()),
+                    move |person| message(EventQueryMessage::ChoosePerson(person)),
+                )
+                .into()
+            } else {
+                Text::new(QueryPerson::everybody().name).into()
+            };
+            let filters = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(person)
+                .push(PickList::new(
+                    &mut state.period_state,
+                    &QueryPeriod::ALL[..],
+                    Some(state.period),
+                    move |period| message(EventQueryMessage::ChoosePeriod(period)),
+                ))
+                .push(PickList::new(
+                    &mut state.kind_state,
+                    &QueryKind::ALL[..],
+                    Some(state.kind),
+                    move |kind| message(EventQueryMessage::ChooseKind(kind)),
+                ));
+
+            let mut aggregates = Column::new().spacing(5).push(Text::new(format!(
+                "Ereignisse: {}",
+                state.result.events.len()
+            )));
+            if let Some(worked) = state.result.worked {
+                aggregates = aggregates.push(Text::new(format!(
+                    "Arbeitszeit zwischen An- und Abmeldungen: {}",
+                    format_minutes(worked.num_minutes())
+                )));
+            }
+
+            let mut list = Scrollable::new(&mut state.scroll_state).spacing(5);
+            if state.result.events.is_empty() {
+                list = list.push(Text::new("Keine passenden Ereignisse."));
+            }
+            for (time, description) in &state.listed {
+                list = list.push(
+                    Row::new()
+                        .spacing(10)
+                        .push(Text::new(time).size(16).width(Length::Units(200)))
+                        .push(Text::new(description).size(16)),
+                );
+            }
+            if state.result.events.len() > state.listed.len() {
+                list = list.push(Text::new(format!(
+                    "Nur die ersten {} Ereignisse werden angezeigt.",
+                    state.listed.len()
+                )));
+            }
+
+            let body = Column::new()
+                .spacing(20)
+                .push(filters)
+                .push(aggregates)
+                .push(list.max_height(400));
+
+            Card::new(Text::new("Abfragen"), body)
+                .foot(
+                    Row::new().padding(5).width(Length::Fill).push(
+                        Button::new(
+                            &mut state.close_state,
+                            Text::new("Schließen").horizontal_alignment(Horizontal::Center),
+                        )
+                        .on_press(message(EventQueryMessage::Close)),
+                    ),
+                )
+                .max_width(1000)
+                .on_close(message(EventQueryMessage::Close))
+                .into()
+        })
+        .backdrop(message(EventQueryMessage::Close))
+        .on_esc(message(EventQueryMessage::Close))
+        .into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        message: EventQueryMessage,
+    ) {
+        let state = self.modal_state.inner_mut();
+        match message {
+            EventQueryMessage::Open => {
+                state.people = std::iter::once(QueryPerson::everybody())
+                    .chain(shared.staff.iter().map(|staff_member| QueryPerson {
+                        uuid: Some(staff_member.uuid()),
+                        name: staff_member.name.clone(),
+                    }))
+                    .collect();
+                if !state.people.contains(&state.person) {
+                    state.person = QueryPerson::everybody();
+                }
+                self.modal_state.show(true);
+            }
+            EventQueryMessage::ChoosePerson(person) => state.person = person,
+            EventQueryMessage::ChoosePeriod(period) => state.period = period,
+            EventQueryMessage::ChooseKind(kind) => state.kind = kind,
+            EventQueryMessage::Close => {
+                self.modal_state.show(false);
+                return;
+            }
+        }
+        self.query(shared, date);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn count_and_worked_time() {
+        let time = |day, hour, min| NaiveDate::from_ymd(2000, 1, day).and_hms(hour, min, 0);
+        let status = |id, created_at, uuid, status| {
+            WorkEventT::new(
+                id,
+                created_at,
+                WorkEvent::StatusChange(uuid, String::new(), status),
+            )
+        };
+        let events = || {
+            vec![
+                status(1, time(1, 18, 0), 1, WorkStatus::Working),
+                status(2, time(1, 19, 0), 2, WorkStatus::Working),
+                WorkEventT::new(
+                    3,
+                    time(1, 20, 0),
+                    WorkEvent::Marker(String::from("Polizei")),
+                ),
+                // Signing in twice does not restart the shift.
+                status(4, time(1, 21, 0), 1, WorkStatus::Working),
+                status(5, time(2, 1, 30), 1, WorkStatus::Away),
+                WorkEventT::new(6, time(2, 5, 59), WorkEvent::_6am),
+                // Forgot to sign off, the shift ends at the next 6am event.
+                status(7, time(2, 18, 0), 1, WorkStatus::Working),
+                WorkEventT::new(9, time(3, 5, 59), WorkEvent::_6am),
+                // So this sign-off after the 6am event is not paired with the sign-in before it.
+                status(10, time(3, 18, 0), 1, WorkStatus::Away),
+                // Still signed in at the end of the period, so not counted.
+                status(11, time(3, 20, 0), 2, WorkStatus::Working),
+                status(8, time(1, 23, 0), 2, WorkStatus::Away),
+            ]
+        };
+        let worked = Duration::minutes(7 * 60 + 30 + 4 * 60 + 11 * 60 + 59);
+
+        let result = QueryResult::new(events(), QueryKind::All);
+        assert_eq!(result.events.len(), 11);
+        // Sorted by time.
+        assert_eq!(result.events[5].id(), 5);
+        assert_eq!(result.worked, Some(worked));
+
+        let result = QueryResult::new(events(), QueryKind::SignIn);
+        assert_eq!(
+            result.events.iter().map(WorkEventT::id).collect::<Vec<_>>(),
+            vec![1, 2, 4, 7, 11]
+        );
+        assert_eq!(result.worked, Some(worked));
+
+        let result = QueryResult::new(events(), QueryKind::Marker);
+        assert_eq!(result.events.len(), 1);
+        assert_eq!(result.worked, None);
+
+        assert_eq!(
+            QueryResult::new(events(), QueryKind::Boundary).events.len(),
+            2
+        );
+    }
+}
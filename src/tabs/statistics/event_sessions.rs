@@ -0,0 +1,63 @@
+//! Export of the events of a month that were detected from the number of staff members working, see
+//! `event_detection`. Together with the evaluation it shows how much work time each event needed.
+use std::fs;
+use std::path::Path;
+
+use chrono::{Date, Local};
+use stechuhr::date_ext::NaiveDateExt;
+
+use crate::{SharedData, StechuhrError};
+
+/// Write the events of the month containing the given date. Returns the number of events.
+pub fn write(
+    path: &Path,
+    shared: &mut SharedData,
+    date: Date<Local>,
+) -> Result<usize, StechuhrError> {
+    let first_day = date.naive_local().first_dom();
+    let last_day = date.naive_local().last_dom();
+
+    // Detect the events again since the status changes may have been corrected after the business day ended.
+    let today = shared
+        .settings
+        .business_date(shared.current_time.naive_local());
+    let mut day = first_day;
+    while day <= last_day.min(today) {
+        shared.record_event_sessions(day)?;
+        day = day.succ();
+    }
+    let sessions = shared
+        .storage
+        .load_event_sessions_between(first_day, last_day);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut wtr = csv::WriterBuilder::new()
+        // Use Tab as delimiter so that Excel automatically imports it correctly.
+        .delimiter(b'\t')
+        .from_path(path)?;
+
+    wtr.write_record([
+        "Datum",
+        "Beginn",
+        "Ende",
+        "Dauer (Minuten)",
+        "Personen gleichzeitig",
+        "Arbeitszeit (Minuten)",
+    ])?;
+    for session in &sessions {
+        wtr.write_record([
+            session.business_date.format("%d.%m.%Y").to_string(),
+            session.started_at.format("%H:%M").to_string(),
+            session.ended_at.format("%H:%M").to_string(),
+            (session.ended_at - session.started_at)
+                .num_minutes()
+                .to_string(),
+            session.peak_count.to_string(),
+            session.staff_minutes.to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(sessions.len())
+}
@@ -0,0 +1,184 @@
+//! History of the generated files, so that it can be looked up which file was sent for a month.
+use std::path::Path;
+use std::{fs, io};
+
+use chrono::NaiveDate;
+use iced::{button, scrollable, Alignment, Button, Column, Element, Length, Row, Scrollable, Text};
+use sha2::{Digest, Sha256};
+use stechuhr::date_ext::LocalizedFormat;
+use stechuhr::models::{Export, ExportKind, NewExport};
+use stechuhr::settings::Settings;
+use stechuhr::storage::Storage;
+
+use super::StatsMessage;
+use crate::{SharedData, StechuhrError};
+
+/// Number of exports listed in the "Auswertung" tab.
+const HISTORY_LENGTH: i64 = 20;
+/// Number of hex digits of the checksum shown in the list, enough to compare two files by eye.
+const CHECKSUM_DIGITS: usize = 12;
+
+/// SHA-256 of the file content in hex.
+pub fn checksum(path: &Path) -> io::Result<String> {
+    let digest = Sha256::digest(fs::read(path)?);
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[derive(Debug, Clone)]
+pub enum ExportHistoryMessage {
+    Open(usize),
+    Regenerate(usize),
+}
+
+pub struct ExportHistory {
+    exports: Vec<Export>,
+    // widget states
+    scroll_state: scrollable::State,
+    open_states: Vec<button::State>,
+    regenerate_states: Vec<button::State>,
+}
+
+impl ExportHistory {
+    pub fn new(storage: &mut dyn Storage) -> Self {
+        let mut history = ExportHistory {
+            exports: Vec::new(),
+            scroll_state: scrollable::State::default(),
+            open_states: Vec::new(),
+            regenerate_states: Vec::new(),
+        };
+        history.reload(storage);
+        history
+    }
+
+    pub fn reload(&mut self, storage: &mut dyn Storage) {
+        self.exports = storage.load_exports(HISTORY_LENGTH);
+        self.open_states
+            .resize_with(self.exports.len(), button::State::default);
+        self.regenerate_states
+            .resize_with(self.exports.len(), button::State::default);
+    }
+
+    pub fn get(&self, idx: usize) -> Result<&Export, StechuhrError> {
+        self.exports
+            .get(idx)
+            .ok_or_else(|| StechuhrError::Str(format!("Export {} existiert nicht", idx)))
+    }
+
+    /// Record a file that was just written. Returns its checksum.
+    pub fn record(
+        &mut self,
+        shared: &mut SharedData,
+        kind: ExportKind,
+        month: NaiveDate,
+        department: Option<&str>,
+        path: &Path,
+    ) -> Result<String, StechuhrError> {
+        let checksum = checksum(path)?;
+        shared.storage.insert_export(NewExport::new(
+            kind,
+            month,
+            department.map(String::from),
+            path,
+            checksum.clone(),
+            shared.current_time.naive_local(),
+        ))?;
+        self.reload(shared.storage.as_mut());
+        Ok(checksum)
+    }
+
+    /// Open a previously generated file, if it still exists.
+    pub fn open(&self, idx: usize) -> Result<(), StechuhrError> {
+        let export = self.get(idx)?;
+        if !Path::new(&export.path).exists() {
+            return Err(StechuhrError::Str(format!(
+                "Die Datei {} existiert nicht mehr, sie kann aber neu erzeugt werden.",
+                export.path
+            )));
+        }
+        opener::open(&export.path)?;
+        Ok(())
+    }
+
+    /// Name of an export in the list, e.g. "Auswertung Mai 2022 (Bar)".
+    pub fn describe(export: &Export, settings: &Settings) -> String {
+        let kind = export
+            .kind()
+            .map_or_else(|| String::from("Unbekannt"), |kind| kind.to_string());
+        let month = export.month.format_localized("%B %Y", settings.locale());
+        match &export.department {
+            Some(department) => format!("{} {} ({})", kind, month, department),
+            None => format!("{} {}", kind, month),
+        }
+    }
+
+    pub fn view<'a>(&'a mut self, settings: &Settings) -> Element<'a, StatsMessage> {
+        let mut list = Scrollable::new(&mut self.scroll_state).spacing(5);
+
+        if self.exports.is_empty() {
+            list = list.push(Text::new("Noch keine Dateien exportiert."));
+        }
+        for (idx, ((export, open_state), regenerate_state)) in self
+            .exports
+            .iter()
+            .zip(self.open_states.iter_mut())
+            .zip(self.regenerate_states.iter_mut())
+            .enumerate()
+        {
+            let checksum = export
+                .checksum
+                .get(..CHECKSUM_DIGITS)
+                .unwrap_or(&export.checksum);
+
+            let row = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Text::new(export.created_at.format("%d.%m.%Y %H:%M").to_string())
+                        .width(Length::Units(150)),
+                )
+                .push(
+                    Column::new()
+                        .push(Text::new(ExportHistory::describe(export, settings)))
+                        .push(Text::new(&export.path).size(16))
+                        .width(Length::Fill),
+                )
+                .push(
+                    Column::new()
+                        .push(Text::new(checksum).size(16))
+                        .push(Text::new(&export.generated_by).size(16))
+                        .width(Length::Units(150)),
+                )
+                .push(
+                    Button::new(open_state, Text::new("Öffnen"))
+                        .on_press(StatsMessage::History(ExportHistoryMessage::Open(idx))),
+                )
+                .push(
+                    Button::new(regenerate_state, Text::new("Neu erzeugen"))
+                        .on_press(StatsMessage::History(ExportHistoryMessage::Regenerate(idx))),
+                );
+            list = list.push(row);
+        }
+
+        Column::new()
+            .spacing(10)
+            .push(Text::new("Exportierte Dateien"))
+            .push(list)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_of_file() {
+        let path = std::env::temp_dir().join("stechuhr_checksum_test.tsv");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            checksum(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
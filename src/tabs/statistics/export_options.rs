@@ -0,0 +1,197 @@
+//! Options that apply to every evaluation of the tab: which staff members are evaluated and which departments get
+//! an additional file with only their staff members.
+use std::collections::BTreeSet;
+
+use stechuhr::models::StaffMember;
+use stechuhr::settings::Settings;
+
+use super::{file_name_part, xlsx_export, StaffHours};
+
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    /* departments that get an additional file with only their staff members */
+    departments: BTreeSet<String>,
+    /* also evaluate hidden and deactivated staff members who have events in the evaluated period */
+    include_inactive: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExportOptionsMessage {
+    ToggleDepartment(String, bool),
+    ToggleIncludeInactive(bool),
+}
+
+impl ExportOptions {
+    pub fn include_inactive(&self) -> bool {
+        self.include_inactive
+    }
+
+    pub fn update(&mut self, message: ExportOptionsMessage) {
+        match message {
+            ExportOptionsMessage::ToggleDepartment(department, is_selected) => {
+                if is_selected {
+                    self.departments.insert(department);
+                } else {
+                    self.departments.remove(&department);
+                }
+            }
+            ExportOptionsMessage::ToggleIncludeInactive(include_inactive) => {
+                self.include_inactive = include_inactive;
+            }
+        }
+    }
+
+    /// The departments of the staff members in alphabetical order, and whether each one is selected.
+    pub fn department_choices(&self, staff: &[StaffMember]) -> Vec<(String, bool)> {
+        staff
+            .iter()
+            .filter_map(|staff_member| staff_member.department.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|department| {
+                let is_selected = self.departments.contains(&department);
+                (department, is_selected)
+            })
+            .collect()
+    }
+
+    /// Names of the files of an evaluation: the complete one first, then one for each selected department that
+    /// still has staff members, and the Excel files of all of them if enabled.
+    pub fn files(
+        &self,
+        settings: &Settings,
+        name: &str,
+        staff_hours: &StaffHours,
+    ) -> Vec<(String, Option<String>)> {
+        let mut files = vec![(format!("{}.tsv", name), None)];
+        files.extend(
+            self.departments
+                .iter()
+                // Skip departments that no longer have any staff members.
+                .filter(|department| {
+                    staff_hours
+                        .hours()
+                        .iter()
+                        .any(|hours| hours.department.as_ref() == Some(*department))
+                })
+                .map(|department| {
+                    (
+                        format!("{} {}.tsv", name, file_name_part(department)),
+                        Some(department.clone()),
+                    )
+                }),
+        );
+        // The Excel files come after the CSV files, so that the first file is still the complete evaluation.
+        if settings.xlsx_export {
+            let xlsx_files = files
+                .iter()
+                .map(|(filename, department)| {
+                    (xlsx_export::file_name(filename), department.clone())
+                })
+                .collect::<Vec<_>>();
+            files.extend(xlsx_files);
+        }
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stechuhr::models::{DBStaffMember, WorkStatus};
+
+    use super::super::PersonHoursCSV;
+    use super::*;
+
+    #[test]
+    fn files_of_departments() {
+        let staff = ["Bar", "Security GmbH/Nord", "Bar"]
+            .iter()
+            .enumerate()
+            .map(|(idx, department)| {
+                DBStaffMember::new(
+                    idx as i32 + 1,
+                    format!("Person {}", idx),
+                    String::new(),
+                    String::new(),
+                    true,
+                    None,
+                )
+                .with_department(Some(String::from(*department)))
+                .with_status(WorkStatus::Away)
+            })
+            .collect::<Vec<_>>();
+        let staff_hours = StaffHours {
+            hours_csv: vec![PersonHoursCSV {
+                department: Some(String::from("Security GmbH/Nord")),
+                ..PersonHoursCSV::for_test("Person 1")
+            }],
+            soft_errors: Vec::new(),
+        };
+        let mut options = ExportOptions::default();
+        let files = |options: &ExportOptions, settings: &Settings| {
+            options
+                .files(settings, "2000-01 Januar", &staff_hours)
+                .into_iter()
+                .map(|(filename, _)| filename)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            options.department_choices(&staff),
+            [
+                (String::from("Bar"), false),
+                (String::from("Security GmbH/Nord"), false)
+            ]
+        );
+        assert_eq!(
+            files(&options, &Settings::default()),
+            ["2000-01 Januar.tsv"]
+        );
+
+        options.update(ExportOptionsMessage::ToggleDepartment(
+            String::from("Bar"),
+            true,
+        ));
+        options.update(ExportOptionsMessage::ToggleDepartment(
+            String::from("Security GmbH/Nord"),
+            true,
+        ));
+        assert_eq!(
+            options.department_choices(&staff),
+            [
+                (String::from("Bar"), true),
+                (String::from("Security GmbH/Nord"), true)
+            ]
+        );
+        // Nobody of the bar worked, so it gets no file.
+        assert_eq!(
+            files(&options, &Settings::default()),
+            [
+                "2000-01 Januar.tsv",
+                "2000-01 Januar Security GmbH_Nord.tsv"
+            ]
+        );
+        let settings = Settings {
+            xlsx_export: true,
+            ..Settings::default()
+        };
+        assert_eq!(
+            files(&options, &settings),
+            [
+                "2000-01 Januar.tsv",
+                "2000-01 Januar Security GmbH_Nord.tsv",
+                "2000-01 Januar.xlsx",
+                "2000-01 Januar Security GmbH_Nord.xlsx"
+            ]
+        );
+
+        options.update(ExportOptionsMessage::ToggleDepartment(
+            String::from("Bar"),
+            false,
+        ));
+        assert_eq!(
+            options.department_choices(&staff)[0],
+            (String::from("Bar"), false)
+        );
+    }
+}
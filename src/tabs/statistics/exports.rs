@@ -0,0 +1,771 @@
+//! The exports of the tab. They write the evaluation and the other files of the selected month into the export
+//! directory, deliver them to the export sinks and record them in the export history.
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use chrono::{Date, Datelike, Local, NaiveDate, TimeZone};
+use iced::window;
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::models::ExportKind;
+use stechuhr::settings::Settings;
+
+use super::error_review::PendingEvaluation;
+use super::{
+    datev, event_eval, event_sessions, interval_export, json_export, time_records, timesheets,
+    xlsx_export, year_summary, Failover, StaffHours, StatsTab,
+};
+use crate::{SharedData, StechuhrError};
+
+impl StatsTab {
+    /// Write the files of `ExportOptions::files` into the given directory.
+    fn write_files(
+        dir: &Path,
+        settings: &Settings,
+        staff_hours: &StaffHours,
+        files: &[(String, Option<String>)],
+    ) -> Result<(), StechuhrError> {
+        files.iter().try_for_each(|(filename, department)| {
+            let path = dir.join(filename);
+            if path.extension() == Some("xlsx".as_ref()) {
+                xlsx_export::write(&path, settings, staff_hours, department.as_deref())
+            } else {
+                staff_hours.write_csv(&path, settings, department.as_deref())
+            }
+        })
+    }
+
+    /// Write the evaluation into the export directory, together with one file for each selected department.
+    /// If that fails (e.g. the directory is read-only or the disk is full) we write into the temp directory
+    /// so that the evaluation is not lost, and offer to retry copying it to the export directory later.
+    /// The files are named after `name`, e.g. the month. Only evaluations of a `month` are recorded in the export history.
+    /// Returns the path of the file with the complete evaluation.
+    pub(super) fn generate_csv(
+        &mut self,
+        shared: &mut SharedData,
+        name: &str,
+        month: Option<NaiveDate>,
+        staff_hours: &StaffHours,
+    ) -> Result<PathBuf, StechuhrError> {
+        for error in staff_hours.errors() {
+            shared.log_error(error.to_string());
+        }
+
+        // Write everyting into CSV files.
+        let files = self.options.files(&shared.settings, name, staff_hours);
+        let export_dir = shared.settings.export_dir.clone();
+        let write_all =
+            |dir: &Path| StatsTab::write_files(dir, &shared.settings, staff_hours, &files);
+
+        let (dir, msg) = match write_all(&export_dir) {
+            Ok(()) => {
+                self.failover = None;
+                let msg = format!(
+                    "Arbeitszeit wurde in der Datei {} gespeichert",
+                    export_dir.join(&files[0].0).display(),
+                );
+                (export_dir, msg)
+            }
+            Err(e) => {
+                let temp_dir = env::temp_dir().join("stechuhr");
+                write_all(&temp_dir)?;
+
+                shared.log_error(format!(
+                    "Auswertung konnte nicht in {} gespeichert werden: {}",
+                    export_dir.join(&files[0].0).display(),
+                    e
+                ));
+                let msg = format!(
+                    "Arbeitszeit konnte nicht in der Datei {} gespeichert werden ({}). Sie wurde stattdessen in der Datei {} gespeichert.",
+                    export_dir.join(&files[0].0).display(),
+                    e,
+                    temp_dir.join(&files[0].0).display(),
+                );
+                self.failover = Some(Failover {
+                    export_dir,
+                    temp_dir: temp_dir.clone(),
+                    month,
+                    files: files.clone(),
+                });
+                (temp_dir, msg)
+            }
+        };
+
+        if let Some(month) = month {
+            for (filename, department) in &files {
+                self.record_export(
+                    shared,
+                    ExportKind::Evaluation,
+                    month,
+                    department.as_deref(),
+                    &dir.join(filename),
+                );
+            }
+        }
+
+        let department_files = files[1..]
+            .iter()
+            .map(|(filename, department)| {
+                format!(
+                    "\nAbteilung {}: {}",
+                    department.as_deref().unwrap_or_default(),
+                    dir.join(filename).display()
+                )
+            })
+            .collect::<String>();
+        let paths = files
+            .iter()
+            .map(|(filename, _)| dir.join(filename))
+            .collect::<Vec<_>>();
+        let sinks = StatsTab::deliver_to_sinks(shared, &paths);
+        shared.prompt_message(msg + &department_files + &sinks);
+        self.last_evaluation = Some((name.to_owned(), paths));
+
+        let path = dir.join(&files[0].0);
+        opener::open(&path)?;
+        Ok(path)
+    }
+
+    /// Write the evaluation of each month from the selected month to the month of `months_end` into its own files,
+    /// like "CSV Generieren" for each month but with a single message at the end. The events are loaded only once.
+    /// Unlike for a single month there is no fallback to the temp directory.
+    pub(super) fn generate_months(
+        &mut self,
+        shared: &mut SharedData,
+        months_end: Date<Local>,
+    ) -> Result<(), StechuhrError> {
+        let months = event_eval::evaluate_hours_for_months(
+            shared,
+            self.period.date(),
+            months_end,
+            self.options.include_inactive(),
+        )?;
+        let export_dir = shared.settings.export_dir.clone();
+        fs::create_dir_all(&export_dir)?;
+
+        let mut paths = Vec::new();
+        for (date, staff_hours) in &months {
+            for error in staff_hours.errors() {
+                shared.log_error(error.to_string());
+            }
+            let name = StatsTab::month_name(&shared.settings, *date);
+            let files = self.options.files(&shared.settings, &name, staff_hours);
+            StatsTab::write_files(&export_dir, &shared.settings, staff_hours, &files)?;
+            for (filename, department) in &files {
+                let path = export_dir.join(filename);
+                self.record_export(
+                    shared,
+                    ExportKind::Evaluation,
+                    date.naive_local().first_dom(),
+                    department.as_deref(),
+                    &path,
+                );
+                paths.push(path);
+            }
+        }
+
+        shared.log_info(format!(
+            "Auswertung von {} Monaten in {} gespeichert.",
+            months.len(),
+            export_dir.display()
+        ));
+        let msg = format!(
+            "Arbeitszeit von {} Monaten wurde im Ordner {} gespeichert",
+            months.len(),
+            export_dir.display()
+        ) + &StatsTab::deliver_to_sinks(shared, &paths);
+        shared.prompt_message(msg);
+        opener::open(&export_dir)?;
+        Ok(())
+    }
+
+    /// Write the evaluation of the selected staff members right away if it has no errors, otherwise only after the
+    /// admin reviewed them.
+    pub(super) fn review_and_generate(
+        &mut self,
+        shared: &mut SharedData,
+        evaluation: PendingEvaluation,
+    ) -> Result<(), StechuhrError> {
+        let evaluation = self.staff_selection.apply(&shared.staff, evaluation);
+        if let Some(pending) = self.error_review.review(evaluation) {
+            self.generate_csv(shared, &pending.name, pending.month, &pending.hours)?;
+        }
+        Ok(())
+    }
+
+    /// The export step of the monthly closing writes the evaluation just like "CSV Generieren".
+    pub(super) fn closing_export(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        if let Some(hours) = self.closing.take_hours() {
+            shared.window_mode = window::Mode::Windowed;
+            let date = self.closing.date();
+            let result = self.generate_csv(
+                shared,
+                &StatsTab::month_name(&shared.settings, date),
+                Some(date.naive_local().first_dom()),
+                &hours,
+            );
+            self.closing
+                .finish_export(hours, result.as_ref().ok().cloned());
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Send the written files to the export sinks that are enabled in the settings.
+    /// A failing sink does not stop the others. Returns a line for each sink to show in the prompt.
+    fn deliver_to_sinks(shared: &mut SharedData, paths: &[PathBuf]) -> String {
+        let sinks = shared
+            .settings
+            .export_sinks
+            .iter()
+            .filter(|sink| sink.enabled)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut lines = String::new();
+        for sink in sinks {
+            match sink.kind.sink().deliver(paths) {
+                Ok(()) => {
+                    shared.log_info(format!("Export an {} gesendet.", sink.name));
+                    lines += &format!("\n{}: gesendet", sink.name);
+                }
+                Err(e) => {
+                    shared.log_error(format!("Export an {} fehlgeschlagen: {}", sink.name, e));
+                    lines += &format!("\n{}: fehlgeschlagen ({})", sink.name, e);
+                }
+            }
+        }
+        lines
+    }
+
+    /// Save the export directory that the admin chose in the file dialog of the system in the settings, so that the
+    /// dialog starts there the next time and all exports are written there.
+    pub(super) fn set_export_dir(
+        &mut self,
+        shared: &mut SharedData,
+        export_dir: PathBuf,
+    ) -> Result<(), StechuhrError> {
+        let mut settings = shared.settings.clone();
+        settings.export_dir = export_dir;
+        settings.save()?;
+        shared.log_info(format!(
+            "Exportverzeichnis wurde auf {} geändert.",
+            settings.export_dir.display()
+        ));
+        shared.settings = settings;
+        // A failed evaluation is retried into the new directory.
+        if let Some(failover) = &mut self.failover {
+            failover.export_dir = shared.settings.export_dir.clone();
+        }
+        Ok(())
+    }
+
+    /// Send the files of the last evaluation to the address of the bookkeeping.
+    pub(super) fn send_email(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let (email, (name, paths)) =
+            match (&shared.settings.bookkeeping_email, &self.last_evaluation) {
+                (Some(email), Some(last_evaluation)) => (email.clone(), last_evaluation.clone()),
+                _ => return Ok(()),
+            };
+
+        match email.send(&format!("Stechuhr Auswertung {}", name), &paths) {
+            Ok(()) => {
+                shared.log_info(format!(
+                    "Auswertung {} per E-Mail an {} gesendet.",
+                    name, email.to
+                ));
+                shared.prompt_message(format!(
+                    "Auswertung {} wurde per E-Mail an {} gesendet",
+                    name, email.to
+                ));
+                Ok(())
+            }
+            Err(e) => {
+                let msg = format!(
+                    "Auswertung {} konnte nicht per E-Mail an {} gesendet werden: {}",
+                    name, email.to, e
+                );
+                shared.log_error(msg.clone());
+                Err(StechuhrError::Str(msg))
+            }
+        }
+    }
+
+    /// Add a written file to the export history. A failure is only logged since the file itself was written.
+    fn record_export(
+        &mut self,
+        shared: &mut SharedData,
+        kind: ExportKind,
+        month: NaiveDate,
+        department: Option<&str>,
+        path: &Path,
+    ) {
+        if let Err(e) = self.history.record(shared, kind, month, department, path) {
+            shared.log_error(format!(
+                "Export von {} konnte nicht im Verlauf gespeichert werden: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    /// Write all events of the month, including markers and info messages, to give context to the evaluation.
+    pub(super) fn export_log(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        fs::create_dir_all(&shared.settings.export_dir)?;
+        let path = shared.settings.export_dir.join(format!(
+            "{} Protokoll.tsv",
+            self.period
+                .date()
+                .format_localized("%Y-%m %B", shared.settings.export_locale())
+        ));
+
+        let count = StatsTab::write_log(shared, self.period.date(), &path)?;
+        self.record_export(
+            shared,
+            ExportKind::Log,
+            self.period.date().naive_local().first_dom(),
+            None,
+            &path,
+        );
+
+        shared.log_info(format!(
+            "Protokoll mit {} Ereignissen in {} gespeichert.",
+            count,
+            path.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, std::slice::from_ref(&path));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Write the evaluation of the month in the import format of DATEV Lohn und Gehalt, see `datev`.
+    pub(super) fn export_datev(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(
+            shared,
+            self.period.date(),
+            self.options.include_inactive(),
+        )?;
+        let path = shared.settings.export_dir.join(format!(
+            "{} DATEV.txt",
+            self.period
+                .date()
+                .format_localized("%Y-%m %B", shared.settings.export_locale())
+        ));
+        let month = self.period.date().naive_local().first_dom();
+
+        let skipped = datev::write(&path, &shared.settings, &hours, month)?;
+        self.record_export(shared, ExportKind::Datev, month, None, &path);
+        shared.log_info(format!("DATEV-Export in {} gespeichert.", path.display()));
+
+        let mut msg = format!(
+            "DATEV-Export wurde in der Datei {} gespeichert",
+            path.display()
+        );
+        if !skipped.is_empty() {
+            msg += &format!(
+                "\nOhne Personalnummer, bitte von Hand eintragen: {}",
+                skipped.join(", ")
+            );
+        }
+        if !hours.errors().is_empty() {
+            msg += &format!(
+                "\nDie Auswertung enthält {} Fehler, bitte vor dem Import prüfen (siehe CSV Generieren).",
+                hours.errors().len()
+            );
+        }
+        msg += &StatsTab::deliver_to_sinks(shared, &[path]);
+        shared.prompt_message(msg);
+        Ok(())
+    }
+
+    /// Evaluate the month of the given date and write it as JSON to the export directory, see `json_export`.
+    /// Also used without the window by `stechuhr --export`.
+    pub(crate) fn write_json(
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+    ) -> Result<PathBuf, StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(shared, date, include_inactive)?;
+        let path = shared.settings.export_dir.join(format!(
+            "{}.json",
+            StatsTab::month_name(&shared.settings, date)
+        ));
+        json_export::write(
+            &path,
+            &shared.settings,
+            &hours,
+            date.naive_local().first_dom(),
+        )?;
+        Ok(path)
+    }
+
+    /// Write the evaluation of the month as JSON for scripts, see `json_export`.
+    pub(super) fn export_json(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let path =
+            StatsTab::write_json(shared, self.period.date(), self.options.include_inactive())?;
+        let month = self.period.date().naive_local().first_dom();
+        self.record_export(shared, ExportKind::Json, month, None, &path);
+        shared.log_info(format!("JSON-Export in {} gespeichert.", path.display()));
+
+        let msg = format!(
+            "JSON-Export wurde in der Datei {} gespeichert",
+            path.display()
+        ) + &StatsTab::deliver_to_sinks(shared, &[path]);
+        shared.prompt_message(msg);
+        Ok(())
+    }
+
+    /// Write the events of the month that were detected from the number of staff members working, see `event_sessions`.
+    pub(super) fn export_sessions(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let path = shared.settings.export_dir.join(format!(
+            "{} Veranstaltungen.tsv",
+            self.period
+                .date()
+                .format_localized("%Y-%m %B", shared.settings.export_locale())
+        ));
+
+        let count = event_sessions::write(&path, shared, self.period.date())?;
+        self.record_export(
+            shared,
+            ExportKind::Sessions,
+            self.period.date().naive_local().first_dom(),
+            None,
+            &path,
+        );
+
+        shared.log_info(format!(
+            "{} Veranstaltungen in {} gespeichert.",
+            count,
+            path.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, std::slice::from_ref(&path));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Write each interval of work in the month as its own row, for payroll systems that need single shifts,
+    /// see `interval_export`.
+    pub(super) fn export_intervals(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(
+            shared,
+            self.period.date(),
+            self.options.include_inactive(),
+        )?;
+        let path = shared.settings.export_dir.join(format!(
+            "{} Schichten.tsv",
+            self.period
+                .date()
+                .format_localized("%Y-%m %B", shared.settings.export_locale())
+        ));
+        let month = self.period.date().naive_local().first_dom();
+
+        let count = interval_export::write(&path, &shared.settings, &hours)?;
+        self.record_export(shared, ExportKind::Intervals, month, None, &path);
+
+        shared.log_info(format!(
+            "{} einzelne Schichten in {} gespeichert.",
+            count,
+            path.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, std::slice::from_ref(&path));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Write the start, end and duration of each day's work in one file per staff member, see `time_records`.
+    /// The files are not kept in the export history.
+    pub(super) fn export_time_records(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(
+            shared,
+            self.period.date(),
+            self.options.include_inactive(),
+        )?;
+        let dir = shared.settings.export_dir.join(format!(
+            "{} Arbeitszeitnachweise",
+            self.period
+                .date()
+                .format_localized("%Y-%m %B", shared.settings.export_locale())
+        ));
+        let month_name = self
+            .period
+            .date()
+            .format_localized("%B %Y", shared.settings.export_locale())
+            .to_string();
+
+        let paths = time_records::write(&dir, &shared.settings, &hours, &month_name)?;
+
+        shared.log_info(format!(
+            "{} Arbeitszeitnachweise in {} gespeichert.",
+            paths.len(),
+            dir.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, &paths);
+        opener::open(&dir)?;
+        Ok(())
+    }
+
+    /// Write a timesheet with only their own shifts for each staff member into a directory of the month,
+    /// see `timesheets`. The files are not kept in the export history.
+    pub(super) fn export_timesheets(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(
+            shared,
+            self.period.date(),
+            self.options.include_inactive(),
+        )?;
+        let dir = shared
+            .settings
+            .export_dir
+            .join(self.period.date().format("%Y-%m").to_string());
+        let month_name = self
+            .period
+            .date()
+            .format_localized("%B %Y", shared.settings.export_locale())
+            .to_string();
+
+        let paths = timesheets::write(&dir, &shared.settings, &hours, &month_name)?;
+
+        shared.log_info(format!(
+            "{} Stundenzettel in {} gespeichert.",
+            paths.len(),
+            dir.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, &paths);
+        opener::open(&dir)?;
+        Ok(())
+    }
+
+    /// Write the hours of each staff member in the twelve months up to the selected month, see `year_summary`.
+    /// The file is not kept in the export history.
+    pub(super) fn export_year_summary(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Result<(), StechuhrError> {
+        let first_month = (0..11).fold(self.period.date().naive_local().first_dom(), |month, _| {
+            month.pred().first_dom()
+        });
+        let months = event_eval::evaluate_hours_for_months(
+            shared,
+            Local.from_local_date(&first_month).unwrap(),
+            self.period.date(),
+            self.options.include_inactive(),
+        )?;
+        let path = shared.settings.export_dir.join(format!(
+            "{} bis {} Stunden je Person.tsv",
+            first_month.format("%Y-%m"),
+            self.period.date().format("%Y-%m")
+        ));
+
+        let count = year_summary::write(&path, &shared.settings, &months)?;
+
+        shared.log_info(format!(
+            "Stunden von {} Personen über 12 Monate in {} gespeichert.",
+            count,
+            path.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, std::slice::from_ref(&path));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Write the hours of each staff member in the months of the selected year, up to the current month if it is
+    /// the current year, see `year_summary`. The file is not kept in the export history.
+    pub(super) fn export_year_to_date(
+        &mut self,
+        shared: &mut SharedData,
+    ) -> Result<(), StechuhrError> {
+        let year = self.period.date().year();
+        let today = shared.current_time.date();
+        let last_month = if year == today.year() {
+            today
+        } else {
+            Local.ymd(year, 12, 1)
+        };
+        let months = event_eval::evaluate_hours_for_months(
+            shared,
+            Local.ymd(year, 1, 1),
+            last_month,
+            self.options.include_inactive(),
+        )?;
+        let path = shared
+            .settings
+            .export_dir
+            .join(format!("{} Jahresauswertung.tsv", year));
+
+        let count = year_summary::write(&path, &shared.settings, &months)?;
+
+        shared.log_info(format!(
+            "Jahresauswertung {} von {} Personen in {} gespeichert.",
+            year,
+            count,
+            path.display()
+        ));
+        StatsTab::deliver_to_sinks(shared, std::slice::from_ref(&path));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Write the log of the month containing the given date. Returns the number of events.
+    fn write_log(
+        shared: &mut SharedData,
+        date: Date<Local>,
+        path: &Path,
+    ) -> Result<usize, StechuhrError> {
+        let (start_time, end_time) = event_eval::month_bounds(&shared.settings, date);
+        let events = shared
+            .storage
+            .load_events_between(Some(start_time), Some(end_time));
+
+        let mut wtr = csv::WriterBuilder::new()
+            // Use Tab as delimiter so that Excel automatically imports it correctly.
+            .delimiter(b'\t')
+            .from_path(path)?;
+        wtr.write_record(["Zeitpunkt", "Ereignis"])?;
+        for eventt in &events {
+            wtr.write_record([
+                format!(
+                    "{} {}",
+                    shared.settings.format_export_date(eventt.created_at.date()),
+                    eventt.created_at.format("%H:%M:%S")
+                ),
+                shared.staff_names.describe(
+                    &eventt.event,
+                    shared.settings.show_former_names,
+                    &shared.settings.log_texts,
+                ),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(events.len())
+    }
+
+    /// Write a file from the export history again, for the same month and department and to the same path.
+    pub(super) fn regenerate_export(
+        &mut self,
+        shared: &mut SharedData,
+        idx: usize,
+    ) -> Result<(), StechuhrError> {
+        let export = self.history.get(idx)?.clone();
+        let kind = export.kind().ok_or_else(|| {
+            StechuhrError::Str(format!(
+                "Die Datei {} kann nicht neu erzeugt werden.",
+                export.path
+            ))
+        })?;
+        let date = Local.from_local_date(&export.month).unwrap();
+        let path = PathBuf::from(&export.path);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        match kind {
+            ExportKind::Evaluation => {
+                let hours = event_eval::evaluate_hours_for_month(
+                    shared,
+                    date,
+                    self.options.include_inactive(),
+                )?;
+                hours.write_csv(&path, &shared.settings, export.department.as_deref())?;
+            }
+            ExportKind::Log => {
+                StatsTab::write_log(shared, date, &path)?;
+            }
+            ExportKind::Datev => {
+                let hours = event_eval::evaluate_hours_for_month(
+                    shared,
+                    date,
+                    self.options.include_inactive(),
+                )?;
+                datev::write(&path, &shared.settings, &hours, export.month)?;
+            }
+            ExportKind::Json => {
+                let hours = event_eval::evaluate_hours_for_month(
+                    shared,
+                    date,
+                    self.options.include_inactive(),
+                )?;
+                json_export::write(&path, &shared.settings, &hours, export.month)?;
+            }
+            ExportKind::Sessions => {
+                event_sessions::write(&path, shared, date)?;
+            }
+            ExportKind::Intervals => {
+                let hours = event_eval::evaluate_hours_for_month(
+                    shared,
+                    date,
+                    self.options.include_inactive(),
+                )?;
+                interval_export::write(&path, &shared.settings, &hours)?;
+            }
+        }
+        let checksum = self.history.record(
+            shared,
+            kind,
+            export.month,
+            export.department.as_deref(),
+            &path,
+        )?;
+
+        let generated_at = export.created_at.format("%d.%m.%Y %H:%M");
+        let comparison = if checksum == export.checksum {
+            format!("Sie ist identisch mit der Fassung vom {}.", generated_at)
+        } else {
+            format!(
+                "Sie weicht von der Fassung vom {} ab, die Daten des Monats wurden seitdem geändert.",
+                generated_at
+            )
+        };
+        shared.prompt_message(format!(
+            "Die Datei {} wurde neu erzeugt. {}",
+            path.display(),
+            comparison
+        ));
+        opener::open(&path)?;
+        Ok(())
+    }
+
+    /// Copy an evaluation from the temp directory to its original location in the export directory.
+    pub(super) fn retry_export(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        if let Some(failover) = self.failover.take() {
+            fs::create_dir_all(&failover.export_dir)?;
+            for (filename, _) in &failover.files {
+                if let Err(e) = fs::copy(
+                    failover.temp_dir.join(filename),
+                    failover.export_dir.join(filename),
+                ) {
+                    self.failover = Some(failover);
+                    return Err(e.into());
+                }
+            }
+            if let Some(month) = failover.month {
+                for (filename, department) in &failover.files {
+                    self.record_export(
+                        shared,
+                        ExportKind::Evaluation,
+                        month,
+                        department.as_deref(),
+                        &failover.export_dir.join(filename),
+                    );
+                }
+            }
+
+            let original = failover.export_dir.join(&failover.files[0].0);
+            shared.log_info(format!(
+                "Auswertung wurde nachträglich in {} gespeichert.",
+                original.display()
+            ));
+            shared.prompt_message(format!(
+                "Arbeitszeit wurde in der Datei {} gespeichert",
+                original.display(),
+            ));
+        }
+        Ok(())
+    }
+}
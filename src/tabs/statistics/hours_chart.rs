@@ -0,0 +1,209 @@
+//! Bar chart of the hours of each staff member in the month, so that the distribution can be seen at a glance
+//! without exporting. The bars are scaled so that the longest one fills the width.
+use chrono::{Date, Local};
+use iced::{
+    alignment::Horizontal, button, scrollable, Alignment, Button, Color, Container, Element,
+    Length, Row, Scrollable, Space, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::style::ChartBarStyle;
+
+use super::{event_eval, person_preview::format_minutes, StaffHours, StatsMessage};
+use crate::{SharedData, StechuhrError};
+
+const BAR_COLOR: Color = Color::from_rgb(0.47, 0.59, 0.86);
+const BAR_HEIGHT: u16 = 24;
+/// Resolution of the bars, the width is split into this many portions.
+const PORTIONS: u16 = 1000;
+
+/// Name and total minutes of each staff member, the most hours first.
+fn totals(hours: &StaffHours) -> Vec<(String, i64)> {
+    let mut totals = hours
+        .hours()
+        .iter()
+        .map(|hours| {
+            (
+                hours.staff_name.clone(),
+                hours.minutes().iter().sum::<i64>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    totals.sort_by(|(name1, minutes1), (name2, minutes2)| {
+        minutes2.cmp(minutes1).then_with(|| name1.cmp(name2))
+    });
+    totals
+}
+
+/// Portions of the width taken by the bar and by the space after it.
+fn bar_portions(minutes: i64, max_minutes: i64) -> (u16, u16) {
+    if max_minutes <= 0 {
+        return (0, PORTIONS);
+    }
+    let bar = (minutes.max(0) * i64::from(PORTIONS) / max_minutes) as u16;
+    (bar, PORTIONS - bar)
+}
+
+#[derive(Default)]
+struct HoursChartModalState {
+    month_name: String,
+    totals: Vec<(String, i64)>,
+    // widget states
+    scroll_state: scrollable::State,
+    close_state: button::State,
+}
+
+pub struct HoursChart {
+    modal_state: modal::State<HoursChartModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum HoursChartMessage {
+    Open,
+    Close,
+}
+
+impl HoursChart {
+    pub fn new() -> Self {
+        HoursChart {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Evaluate the month of the given date with the same rules as the export.
+    fn open(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+    ) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(shared, date, include_inactive)?;
+        let state = self.modal_state.inner_mut();
+        *state = HoursChartModalState::default();
+        state.month_name = date
+            .format_localized("%B %Y", shared.settings.locale())
+            .to_string();
+        state.totals = totals(&hours);
+        self.modal_state.show(true);
+        Ok(())
+    }
+
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::HoursChart(message);
+
+        let modal = Modal::new(&mut self.modal_state, content, move |state| {
+            let max_minutes = state
+                .totals
+                .iter()
+                .map(|(_, minutes)| *minutes)
+                .max()
+                .unwrap_or_default();
+
+            let mut chart = Scrollable::new(&mut state.scroll_state).spacing(5);
+            if state.totals.is_empty() {
+                chart = chart.push(Text::new("Niemand ist sichtbar."));
+            }
+            for (name, minutes) in &state.totals {
+                let (bar, space) = bar_portions(*minutes, max_minutes);
+                let mut bars = Row::new().width(Length::Fill);
+                if bar > 0 {
+                    bars = bars.push(
+                        Container::new(Space::new(Length::Fill, Length::Units(BAR_HEIGHT)))
+                            .width(Length::FillPortion(bar))
+                            .style(ChartBarStyle(BAR_COLOR)),
+                    );
+                }
+                if space > 0 {
+                    bars = bars.push(Space::new(
+                        Length::FillPortion(space),
+                        Length::Units(BAR_HEIGHT),
+                    ));
+                }
+                chart = chart.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new(name).size(16).width(Length::Units(200)))
+                        .push(bars)
+                        .push(
+                            Text::new(format_minutes(*minutes))
+                                .size(16)
+                                .width(Length::Units(80))
+                                .horizontal_alignment(Horizontal::Right),
+                        ),
+                );
+            }
+
+            Card::new(
+                Text::new(format!("Stunden {}", state.month_name)),
+                chart.max_height(500),
+            )
+            .foot(
+                Row::new().padding(5).width(Length::Fill).push(
+                    Button::new(
+                        &mut state.close_state,
+                        Text::new("Schließen").horizontal_alignment(Horizontal::Center),
+                    )
+                    .on_press(message(HoursChartMessage::Close)),
+                ),
+            )
+            .max_width(1000)
+            .on_close(message(HoursChartMessage::Close))
+            .into()
+        })
+        .backdrop(message(HoursChartMessage::Close))
+        .on_esc(message(HoursChartMessage::Close));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+        message: HoursChartMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            HoursChartMessage::Open => self.open(shared, date, include_inactive)?,
+            HoursChartMessage::Close => self.modal_state.show(false),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tabs::statistics::PersonHoursCSV;
+
+    #[test]
+    fn totals_and_bars() {
+        let person = |name: &str, minutes_1, sunday_minutes_1| PersonHoursCSV {
+            minutes_1,
+            minutes_2: 30,
+            sunday_minutes_1,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let hours = StaffHours {
+            hours_csv: vec![
+                person("Aaron", 90, None),
+                person("Berta", 60, Some(120)),
+                person("Cem", 0, None),
+            ],
+            soft_errors: Vec::new(),
+        };
+
+        let totals = totals(&hours);
+        assert_eq!(
+            totals,
+            vec![
+                (String::from("Berta"), 210),
+                (String::from("Aaron"), 120),
+                (String::from("Cem"), 30),
+            ]
+        );
+        assert_eq!(bar_portions(210, 210), (1000, 0));
+        assert_eq!(bar_portions(30, 210), (142, 858));
+        assert_eq!(bar_portions(0, 0), (0, 1000));
+    }
+}
@@ -0,0 +1,121 @@
+//! Export of every contiguous interval of work as its own row, e.g. a split shift from 18 to 22 and from 1 to 5
+//! Uhr gives two rows. Some payroll systems require single shifts instead of the sums of the month.
+//! Like in the evaluation the minutes of each bucket are rounded up, so the rows can add up to slightly more.
+use std::fs;
+use std::path::Path;
+
+use stechuhr::settings::Settings;
+
+use super::{PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+/// Write the intervals of all staff members, ordered by staff member and time. Returns the number of intervals.
+pub fn write(
+    path: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+) -> Result<usize, StechuhrError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut wtr = csv::WriterBuilder::new()
+        // Use Tab as delimiter so that Excel automatically imports it correctly.
+        .delimiter(b'\t')
+        .from_path(path)?;
+
+    let buckets = PersonHoursCSV::header(settings);
+    // The header starts with the name and ends with the corrections.
+    let buckets = &buckets[1..buckets.len() - 1];
+    let columns = PersonHoursCSV::columns(settings);
+    let mut header = [
+        "Name",
+        "Personalnummer",
+        "Betriebstag",
+        "Beginn",
+        "Ende",
+        "Minuten",
+    ]
+    .map(String::from)
+    .to_vec();
+    header.extend(buckets.iter().cloned());
+    wtr.write_record(&header)?;
+
+    let mut count = 0;
+    for hours in staff_hours.hours() {
+        for interval in &hours.intervals {
+            let minutes = columns
+                .iter()
+                .map(|idx| interval.minutes[*idx])
+                .collect::<Vec<_>>();
+            let mut record = vec![
+                hours.staff_name.clone(),
+                hours.personnel_number.clone().unwrap_or_default(),
+                settings.format_export_date(settings.business_date(interval.start_time)),
+                interval.start_time.format("%H:%M").to_string(),
+                interval.end_time.format("%H:%M").to_string(),
+                minutes.iter().sum::<i64>().to_string(),
+            ];
+            record.extend(minutes.iter().map(|minutes| minutes.to_string()));
+            wtr.write_record(&record)?;
+            count += 1;
+        }
+    }
+    wtr.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::IntervalMinutes;
+
+    #[test]
+    fn write_split_shift() {
+        let day = NaiveDate::from_ymd(2022, 4, 1);
+        let interval = |start_time, end_time, minutes| IntervalMinutes {
+            start_time,
+            end_time,
+            minutes,
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![PersonHoursCSV {
+                minutes_1: 120,
+                minutes_2: 120,
+                minutes_3: 120,
+                personnel_number: Some(String::from("1001")),
+                intervals: vec![
+                    interval(
+                        day.and_hms(18, 0, 0),
+                        day.and_hms(22, 0, 0),
+                        [120, 120, 0, 0, 0, 0],
+                    ),
+                    interval(
+                        day.succ().and_hms(1, 0, 0),
+                        day.succ().and_hms(5, 0, 0),
+                        [0, 0, 120, 0, 0, 0],
+                    ),
+                ],
+                ..PersonHoursCSV::for_test("Aaron")
+            }],
+            soft_errors: Vec::new(),
+        };
+        let settings = Settings {
+            bucket_starts: [6, 20, 0],
+            ..Settings::default()
+        };
+        let path = std::env::temp_dir().join("stechuhr_interval_test.tsv");
+
+        let count = write(&path, &settings, &staff_hours).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            written,
+            "Name\tPersonalnummer\tBetriebstag\tBeginn\tEnde\tMinuten\tMinuten 6 - 20 Uhr\tMinuten 20 - 24 Uhr\tMinuten 24 - 6 Uhr\n\
+             Aaron\t1001\t01.04.2022\t18:00\t22:00\t240\t120\t120\t0\n\
+             Aaron\t1001\t01.04.2022\t01:00\t05:00\t120\t0\t0\t120\n"
+        );
+    }
+}
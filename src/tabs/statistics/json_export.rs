@@ -0,0 +1,157 @@
+//! Export of the evaluation as JSON for scripts, e.g. the payroll script of the venue.
+//! Unlike the CSV file the structure does not depend on the export locale and errors are assigned to their staff member.
+//! Besides the sums of the month it contains every contiguous interval of work, like the interval export.
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use stechuhr::settings::Settings;
+
+use super::{PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+#[derive(Debug, Serialize)]
+struct MonthJson<'a> {
+    /// e.g. "2022-04"
+    month: String,
+    /// Names of the buckets in the order of `PersonJson::minutes`, e.g. "Minuten 4 - 20 Uhr".
+    buckets: Vec<String>,
+    staff: Vec<PersonJson<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct PersonJson<'a> {
+    name: &'a str,
+    personnel_number: Option<&'a str>,
+    department: Option<&'a str>,
+    minutes: Vec<i64>,
+    total_minutes: i64,
+    corrections: usize,
+    errors: Vec<String>,
+    intervals: Vec<IntervalJson>,
+}
+
+#[derive(Debug, Serialize)]
+struct IntervalJson {
+    /// e.g. "2022-04-01T18:00:00"
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    /// In the order of `MonthJson::buckets`.
+    minutes: Vec<i64>,
+}
+
+impl<'a> PersonJson<'a> {
+    fn new(hours: &'a PersonHoursCSV, columns: &[usize], staff_hours: &StaffHours) -> Self {
+        let minutes = columns
+            .iter()
+            .map(|idx| hours.minutes()[*idx])
+            .collect::<Vec<_>>();
+
+        PersonJson {
+            name: &hours.staff_name,
+            personnel_number: hours.personnel_number.as_deref(),
+            department: hours.department.as_deref(),
+            total_minutes: minutes.iter().sum(),
+            minutes,
+            corrections: hours.corrections,
+            errors: staff_hours
+                .errors()
+                .iter()
+                .filter(|error| error.staff_name() == hours.staff_name)
+                .map(|error| error.to_string())
+                .collect(),
+            intervals: hours
+                .intervals
+                .iter()
+                .map(|interval| IntervalJson {
+                    start_time: interval.start_time,
+                    end_time: interval.end_time,
+                    minutes: columns.iter().map(|idx| interval.minutes[*idx]).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Write the evaluation of the month starting at the given date.
+pub fn write(
+    path: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    month: NaiveDate,
+) -> Result<(), StechuhrError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let header = super::PersonHoursCSV::header(settings);
+    let columns = super::PersonHoursCSV::columns(settings);
+    let export = MonthJson {
+        month: month.format("%Y-%m").to_string(),
+        // The header starts with the name and ends with the corrections.
+        buckets: header[1..header.len() - 1].to_vec(),
+        staff: staff_hours
+            .hours()
+            .iter()
+            .map(|hours| PersonJson::new(hours, &columns, staff_hours))
+            .collect(),
+    };
+
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, &export).map_err(|e| StechuhrError::Str(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stechuhr::evaluation::{IntervalMinutes, SoftStatisticsError};
+
+    #[test]
+    fn write_json() {
+        let staff_hours = StaffHours {
+            hours_csv: vec![PersonHoursCSV {
+                name: String::from("Aaron *"),
+                minutes_1: 60,
+                minutes_2: 30,
+                corrections: 1,
+                personnel_number: Some(String::from("1001")),
+                intervals: vec![IntervalMinutes {
+                    start_time: NaiveDate::from_ymd(2022, 4, 1).and_hms(19, 0, 0),
+                    end_time: NaiveDate::from_ymd(2022, 4, 1).and_hms(20, 30, 0),
+                    minutes: [60, 30, 0, 0, 0, 0],
+                }],
+                ..PersonHoursCSV::for_test("Aaron")
+            }],
+            soft_errors: vec![SoftStatisticsError::StaffStillWorking(
+                NaiveDate::from_ymd(2022, 4, 2).and_hms(5, 59, 59),
+                String::from("Aaron"),
+            )],
+        };
+        let path = std::env::temp_dir().join("stechuhr_json_test.json");
+
+        write(
+            &path,
+            &Settings::default(),
+            &staff_hours,
+            NaiveDate::from_ymd(2022, 4, 1),
+        )
+        .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(written["month"], "2022-04");
+        assert_eq!(written["buckets"][0], "Minuten 4 - 20 Uhr");
+        let aaron = &written["staff"][0];
+        assert_eq!(aaron["name"], "Aaron");
+        assert_eq!(aaron["personnel_number"], "1001");
+        assert_eq!(aaron["minutes"], serde_json::json!([60, 30, 0]));
+        assert_eq!(aaron["total_minutes"], 90);
+        assert_eq!(aaron["errors"].as_array().unwrap().len(), 1);
+        let interval = &aaron["intervals"][0];
+        assert_eq!(interval["start_time"], "2022-04-01T19:00:00");
+        assert_eq!(interval["minutes"], serde_json::json!([60, 30, 0]));
+    }
+}
@@ -0,0 +1,164 @@
+//! The period that the tab evaluates: the month of the selected date, a free range of business days that starts at
+//! it (e.g. for a single weekend event), or a series of months that starts at its month.
+//! The date pickers only choose the dates, the checks and the texts of the buttons are done here.
+use chrono::{Date, Local, NaiveDate, NaiveDateTime, TimeZone};
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::settings::Settings;
+
+use crate::StechuhrError;
+
+pub struct EvaluationPeriod {
+    date: Date<Local>,
+    /* last business day of a free range that starts at `date` */
+    range_end: Option<Date<Local>>,
+    /* last month of a series of months that starts at the month of `date`, each evaluated into its own files */
+    months_end: Option<Date<Local>>,
+}
+
+impl EvaluationPeriod {
+    pub fn new(date: Date<Local>) -> Self {
+        EvaluationPeriod {
+            date,
+            range_end: None,
+            months_end: None,
+        }
+    }
+
+    pub fn date(&self) -> Date<Local> {
+        self.date
+    }
+
+    pub fn range_end(&self) -> Option<Date<Local>> {
+        self.range_end
+    }
+
+    pub fn months_end(&self) -> Option<Date<Local>> {
+        self.months_end
+    }
+
+    /// Select another date. A range must not end before it starts, so ends before the new date are dropped.
+    pub fn set_date(&mut self, date: NaiveDate) {
+        self.date = Local.from_local_date(&date).unwrap();
+        if self
+            .range_end
+            .is_some_and(|range_end| range_end < self.date)
+        {
+            self.range_end = None;
+        }
+        if self.months_end.is_some_and(|months_end| {
+            months_end.naive_local() < self.date.naive_local().first_dom()
+        }) {
+            self.months_end = None;
+        }
+    }
+
+    pub fn set_range_end(&mut self, date: NaiveDate) -> Result<(), StechuhrError> {
+        let range_end = Local.from_local_date(&date).unwrap();
+        if range_end < self.date {
+            return Err(StechuhrError::Str(format!(
+                "Das Ende des Zeitraums muss am oder nach dem {} liegen.",
+                self.date.format("%d.%m.%Y")
+            )));
+        }
+        self.range_end = Some(range_end);
+        Ok(())
+    }
+
+    pub fn set_months_end(&mut self, date: NaiveDate) -> Result<(), StechuhrError> {
+        let months_end = Local.from_local_date(&date).unwrap();
+        if months_end.naive_local() < self.date.naive_local().first_dom() {
+            return Err(StechuhrError::Str(format!(
+                "Der letzte Monat muss im oder nach dem {} liegen.",
+                self.date.format("%m.%Y")
+            )));
+        }
+        self.months_end = Some(months_end);
+        Ok(())
+    }
+
+    /// Text of the button that chooses the end of the range.
+    pub fn range_label(&self) -> String {
+        match self.range_end {
+            Some(range_end) => format!(
+                "{} bis {}",
+                self.date.format("%d.%m."),
+                range_end.format("%d.%m.%Y")
+            ),
+            None => String::from("Zeitraum bis ..."),
+        }
+    }
+
+    /// Text of the button that chooses the last month of the series.
+    pub fn months_label(&self) -> String {
+        match self.months_end {
+            Some(months_end) => format!(
+                "{} bis {}",
+                self.date.format("%m."),
+                months_end.format("%m.%Y")
+            ),
+            None => String::from("Monate bis ..."),
+        }
+    }
+
+    /// Name of the files and start and end of the range, which contains whole business days, from the start of the
+    /// first to the start of the day after the last. None if no end was chosen.
+    pub fn range(&self, settings: &Settings) -> Option<(String, NaiveDateTime, NaiveDateTime)> {
+        let range_end = self.range_end?;
+        let name = format!(
+            "{} bis {}",
+            self.date.format("%Y-%m-%d"),
+            range_end.format("%Y-%m-%d")
+        );
+        let start_time = settings.business_day_start(self.date.naive_local());
+        let end_time = settings.business_day_start(range_end.naive_local().succ());
+        Some((name, start_time, end_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choose_period() {
+        let mut period = EvaluationPeriod::new(Local.ymd(2000, 1, 15));
+        assert_eq!(period.range_label(), "Zeitraum bis ...");
+        assert_eq!(period.months_label(), "Monate bis ...");
+        assert!(period.range(&Settings::default()).is_none());
+
+        assert!(period
+            .set_range_end(NaiveDate::from_ymd(2000, 1, 14))
+            .is_err());
+        period
+            .set_range_end(NaiveDate::from_ymd(2000, 1, 16))
+            .unwrap();
+        assert_eq!(period.range_label(), "15.01. bis 16.01.2000");
+        assert_eq!(
+            period.range(&Settings::default()),
+            Some((
+                String::from("2000-01-15 bis 2000-01-16"),
+                NaiveDate::from_ymd(2000, 1, 15).and_hms(6, 0, 0),
+                NaiveDate::from_ymd(2000, 1, 17).and_hms(6, 0, 0),
+            ))
+        );
+
+        // Any day of the first month is fine for the last month.
+        period
+            .set_months_end(NaiveDate::from_ymd(2000, 1, 1))
+            .unwrap();
+        assert!(period
+            .set_months_end(NaiveDate::from_ymd(1999, 12, 31))
+            .is_err());
+        period
+            .set_months_end(NaiveDate::from_ymd(2000, 3, 1))
+            .unwrap();
+        assert_eq!(period.months_label(), "01. bis 03.2000");
+
+        // Ends before the new date are dropped.
+        period.set_date(NaiveDate::from_ymd(2000, 2, 1));
+        assert_eq!(period.range_end(), None);
+        assert_eq!(period.months_end(), Some(Local.ymd(2000, 3, 1)));
+        period.set_date(NaiveDate::from_ymd(2000, 4, 1));
+        assert_eq!(period.months_end(), None);
+    }
+}
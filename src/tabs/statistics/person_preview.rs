@@ -0,0 +1,287 @@
+//! Evaluation of a single staff member for the month, with each interval of work and the minutes it adds to the
+//! buckets, so that the totals in the exported files can be checked before they go to payroll.
+//! The Lohnart of each bucket is shown if it is configured for the DATEV export, the pay itself is calculated
+//! by payroll.
+use chrono::{Date, Local};
+use iced::{
+    alignment::Horizontal, button, pick_list, scrollable, Alignment, Button, Color, Column,
+    Element, Length, PickList, Row, Scrollable, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::settings::Settings;
+
+use super::{event_eval, PersonHoursCSV, StaffHours, StatsMessage};
+use crate::{SharedData, StechuhrError};
+
+const COLUMN_WIDTH: u16 = 120;
+
+/// Minutes as hours and minutes, e.g. 450 as "7:30".
+pub(super) fn format_minutes(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Label, minutes and Lohnart of each bucket that is exported.
+fn bucket_totals(settings: &Settings, hours: &PersonHoursCSV) -> Vec<(String, i64, Option<u32>)> {
+    let header = PersonHoursCSV::header(settings);
+    let minutes = hours.minutes();
+    PersonHoursCSV::columns(settings)
+        .into_iter()
+        .zip(&header[1..])
+        .map(|(idx, label)| {
+            let wage_type = settings.datev.wage_types[idx];
+            (
+                label.clone(),
+                minutes[idx],
+                (wage_type != 0).then_some(wage_type),
+            )
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct PersonPreviewModalState {
+    month_name: String,
+    /* evaluation of the month, computed when the preview is opened */
+    hours: Option<StaffHours>,
+    names: Vec<String>,
+    selected: Option<String>,
+    // widget states
+    name_state: pick_list::State<String>,
+    scroll_state: scrollable::State,
+    close_state: button::State,
+}
+
+pub struct PersonPreview {
+    modal_state: modal::State<PersonPreviewModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PersonPreviewMessage {
+    Open,
+    Choose(String),
+    Close,
+}
+
+impl PersonPreview {
+    pub fn new() -> Self {
+        PersonPreview {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Evaluate the month of the given date and show the first staff member who worked in it.
+    fn open(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+    ) -> Result<(), StechuhrError> {
+        let hours = event_eval::evaluate_hours_for_month(shared, date, include_inactive)?;
+        let state = self.modal_state.inner_mut();
+        *state = PersonPreviewModalState::default();
+        state.month_name = date
+            .format_localized("%B %Y", shared.settings.locale())
+            .to_string();
+        state.names = hours
+            .hours()
+            .iter()
+            .map(|hours| hours.staff_name.clone())
+            .collect();
+        state.selected = hours
+            .hours()
+            .iter()
+            .find(|hours| hours.minutes().iter().any(|minutes| *minutes > 0))
+            .map(|hours| hours.staff_name.clone());
+        state.hours = Some(hours);
+        self.modal_state.show(true);
+        Ok(())
+    }
+
+    pub fn view<'a>(
+        &'a mut self,
+        settings: &Settings,
+        content: Element<'a, StatsMessage>,
+    ) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::PersonPreview(message);
+        let settings = settings.clone();
+
+        let modal = Modal::new(&mut self.modal_state, content, move |state| {
+            let choose = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new("Mitarbeiter:in"))
+                .push(PickList::new(
+                    &mut state.name_state,
+                    state.names.clone(),
+                    state.selected.clone(),
+                    move |name| message(PersonPreviewMessage::Choose(name)),
+                ));
+
+            let person = state.hours.as_ref().and_then(|staff_hours| {
+                let selected = state.selected.as_ref()?;
+                let hours = staff_hours
+                    .hours()
+                    .iter()
+                    .find(|hours| &hours.staff_name == selected)?;
+                Some((staff_hours, hours))
+            });
+
+            let mut list = Scrollable::new(&mut state.scroll_state).spacing(5);
+            match person {
+                None => list = list.push(Text::new("Bitte eine Person auswählen.")),
+                Some((staff_hours, hours)) => {
+                    let totals = bucket_totals(&settings, hours);
+                    let total = totals.iter().map(|(_, minutes, _)| minutes).sum::<i64>();
+                    for (label, minutes, wage_type) in &totals {
+                        let wage_type = wage_type
+                            .map(|wage_type| format!("Lohnart {}", wage_type))
+                            .unwrap_or_default();
+                        list = list.push(
+                            Row::new()
+                                .spacing(10)
+                                .push(Text::new(label).width(Length::Units(350)))
+                                .push(
+                                    Text::new(format_minutes(*minutes))
+                                        .width(Length::Units(COLUMN_WIDTH))
+                                        .horizontal_alignment(Horizontal::Right),
+                                )
+                                .push(Text::new(wage_type).size(16)),
+                        );
+                    }
+                    list = list.push(
+                        Row::new()
+                            .spacing(10)
+                            .push(Text::new("Summe").width(Length::Units(350)))
+                            .push(
+                                Text::new(format_minutes(total))
+                                    .width(Length::Units(COLUMN_WIDTH))
+                                    .horizontal_alignment(Horizontal::Right),
+                            ),
+                    );
+                    if hours.corrections > 0 {
+                        list = list.push(Text::new(format!(
+                            "{} manuelle Korrekturen in diesem Monat",
+                            hours.corrections
+                        )));
+                    }
+                    for error in staff_hours
+                        .errors()
+                        .iter()
+                        .filter(|error| error.staff_name() == hours.staff_name)
+                    {
+                        list = list.push(
+                            Text::new(error.to_string())
+                                .size(16)
+                                .color(Color::from_rgb8(200, 0, 0)),
+                        );
+                    }
+
+                    // Each interval with the same columns as the totals.
+                    let columns = PersonHoursCSV::columns(&settings);
+                    list = list.push(Text::new("Schichten"));
+                    if hours.intervals.is_empty() {
+                        list = list.push(Text::new("Keine Arbeitszeit in diesem Monat."));
+                    }
+                    for interval in &hours.intervals {
+                        let mut row = Row::new().spacing(10).push(
+                            Text::new(format!(
+                                "{} {}–{}",
+                                settings.format_export_date(
+                                    settings.business_date(interval.start_time)
+                                ),
+                                interval.start_time.format("%H:%M"),
+                                interval.end_time.format("%H:%M")
+                            ))
+                            .size(16)
+                            .width(Length::Units(350)),
+                        );
+                        for idx in &columns {
+                            row = row.push(
+                                Text::new(format_minutes(interval.minutes[*idx]))
+                                    .size(16)
+                                    .width(Length::Units(COLUMN_WIDTH))
+                                    .horizontal_alignment(Horizontal::Right),
+                            );
+                        }
+                        list = list.push(row);
+                    }
+                }
+            }
+
+            let body = Column::new()
+                .spacing(20)
+                .push(choose)
+                .push(list.max_height(450));
+
+            Card::new(
+                Text::new(format!("Einzelansicht {}", state.month_name)),
+                body,
+            )
+            .foot(
+                Row::new().padding(5).width(Length::Fill).push(
+                    Button::new(
+                        &mut state.close_state,
+                        Text::new("Schließen").horizontal_alignment(Horizontal::Center),
+                    )
+                    .on_press(message(PersonPreviewMessage::Close)),
+                ),
+            )
+            .max_width(1000)
+            .on_close(message(PersonPreviewMessage::Close))
+            .into()
+        })
+        .backdrop(message(PersonPreviewMessage::Close))
+        .on_esc(message(PersonPreviewMessage::Close));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        include_inactive: bool,
+        message: PersonPreviewMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            PersonPreviewMessage::Open => self.open(shared, date, include_inactive)?,
+            PersonPreviewMessage::Choose(name) => {
+                self.modal_state.inner_mut().selected = Some(name)
+            }
+            PersonPreviewMessage::Close => self.modal_state.show(false),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stechuhr::settings::DatevSettings;
+
+    #[test]
+    fn bucket_totals_with_wage_types() {
+        let hours = PersonHoursCSV {
+            minutes_1: 450,
+            minutes_3: 95,
+            ..PersonHoursCSV::for_test("Aaron")
+        };
+        let settings = Settings {
+            bucket_starts: [6, 22, 22],
+            datev: DatevSettings {
+                wage_types: [100, 0, 120, 0, 0, 0],
+                ..DatevSettings::default()
+            },
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            bucket_totals(&settings, &hours),
+            vec![
+                (String::from("Minuten 6 - 22 Uhr"), 450, Some(100)),
+                (String::from("Minuten 22 - 6 Uhr"), 95, Some(120)),
+            ]
+        );
+        assert_eq!(format_minutes(95), "1:35");
+    }
+}
@@ -0,0 +1,487 @@
+//! Hours of each staff member as they are written into the exports, computed from an evaluation.
+//! Every export of the tab is written from these rows, without any widgets, so they can be tested on their own.
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use stechuhr::date_ext::{LocalizedFormat, NaiveDateTimeExt};
+use stechuhr::evaluation::{Evaluation, IntervalMinutes, PersonHours, SoftStatisticsError};
+use stechuhr::models::{DBStaffMember, WorkEventT};
+use stechuhr::rest_period;
+use stechuhr::settings::{Rounding, Settings};
+use stechuhr::youth_protection;
+
+use crate::StechuhrError;
+
+/// The header row and the rows are generated from the configured buckets, see `PersonHoursCSV::header`.
+/// The buckets of Sundays and holidays are only written if they are enabled in the settings, empty buckets never.
+/// Names of staff members whose events were corrected manually are marked with an asterisk, so that payroll can check them.
+/// The wage is only written if it is enabled in the settings, see `PersonHoursCSV::wage_cents`.
+/// The minutes of each bucket are rounded as configured, so the wage is computed from the rounded minutes.
+#[derive(Debug)]
+pub(super) struct PersonHoursCSV {
+    pub(super) name: String,
+    pub(super) minutes_1: i64,
+    pub(super) minutes_2: i64,
+    pub(super) minutes_3: i64,
+    pub(super) sunday_minutes_1: Option<i64>,
+    pub(super) sunday_minutes_2: Option<i64>,
+    pub(super) sunday_minutes_3: Option<i64>,
+    pub(super) corrections: usize,
+    pub(super) staff_name: String,
+    pub(super) personnel_number: Option<String>,
+    pub(super) department: Option<String>,
+    pub(super) wage_cents_per_hour: Option<i32>,
+    pub(super) intervals: Vec<IntervalMinutes>,
+}
+
+impl PersonHoursCSV {
+    /// Indices into `PersonHoursCSV::minutes` of the bucket columns, in the order of the header.
+    pub(super) fn columns(settings: &Settings) -> Vec<usize> {
+        let buckets = settings
+            .bucket_ranges()
+            .into_iter()
+            .map(|(idx, _, _)| idx)
+            .collect::<Vec<_>>();
+        let mut columns = buckets.clone();
+        if settings.sunday_buckets {
+            columns.extend(buckets.iter().map(|idx| idx + 3));
+        }
+        columns
+    }
+
+    pub(super) fn header(settings: &Settings) -> Vec<String> {
+        // Midnight is written as 24 Uhr, both at the start and at the end of a bucket.
+        let hour = |h: u32| if h == 0 { 24 } else { h };
+        let ranges = settings
+            .bucket_ranges()
+            .into_iter()
+            .map(|(_, start, end)| (start, end))
+            .collect::<Vec<_>>();
+
+        let mut header = vec![String::from("Name")];
+        header.extend(
+            ranges
+                .iter()
+                .map(|(start, end)| format!("Minuten {} - {} Uhr", hour(*start), hour(*end))),
+        );
+        if settings.sunday_buckets {
+            // 2000-01-02 was a sunday, the name of the weekday is localized like the rest of the export.
+            let weekday = |date: NaiveDate| {
+                date.format_localized("%A", settings.export_locale())
+                    .to_string()
+            };
+            let mut sunday = weekday(NaiveDate::from_ymd(2000, 1, 2));
+            if settings.saturday_buckets {
+                sunday = format!("{}/{}", weekday(NaiveDate::from_ymd(2000, 1, 1)), sunday);
+            }
+            header.extend(ranges.iter().map(|(start, end)| {
+                format!(
+                    "Minuten {}/Feiertag {} - {} Uhr",
+                    sunday,
+                    hour(*start),
+                    hour(*end)
+                )
+            }));
+        }
+        header.push(String::from("Korrekturen"));
+        if settings.wage_column {
+            header.push(String::from("Lohn"));
+        }
+        header
+    }
+
+    /// Minutes of the three buckets followed by the buckets of Sundays and holidays, which are zero if disabled.
+    pub(super) fn minutes(&self) -> [i64; 6] {
+        [
+            self.minutes_1,
+            self.minutes_2,
+            self.minutes_3,
+            self.sunday_minutes_1.unwrap_or_default(),
+            self.sunday_minutes_2.unwrap_or_default(),
+            self.sunday_minutes_3.unwrap_or_default(),
+        ]
+    }
+
+    /// Wage in cents for the minutes in all buckets, each with the surcharge of its bucket, rounded to whole cents.
+    /// None if the staff member has no hourly wage.
+    pub(super) fn wage_cents(&self, settings: &Settings) -> Option<i64> {
+        let wage = i64::from(self.wage_cents_per_hour?);
+        // in cents per hour times percent
+        let total = self
+            .minutes()
+            .iter()
+            .zip(settings.wage_surcharges)
+            .map(|(minutes, surcharge)| minutes * wage * (100 + i64::from(surcharge)))
+            .sum::<i64>();
+        Some((total + 3000) / 6000)
+    }
+
+    /// Row of the CSV file with the same columns as `PersonHoursCSV::header`.
+    pub(super) fn record(&self, settings: &Settings, columns: &[usize]) -> Vec<String> {
+        let minutes = self.minutes();
+        let mut record = vec![self.name.clone()];
+        record.extend(columns.iter().map(|idx| minutes[*idx].to_string()));
+        record.push(self.corrections.to_string());
+        if settings.wage_column {
+            record.push(
+                self.wage_cents(settings)
+                    .map(|cents| settings.format_export_decimal(cents as f64 / 100.0, 2))
+                    .unwrap_or_default(),
+            );
+        }
+        record
+    }
+
+    pub(super) fn new(hours: PersonHours, sunday_buckets: bool, rounding: Rounding) -> Self {
+        let [minutes_1, minutes_2, minutes_3, sunday_1, sunday_2, sunday_3] = hours
+            .duration
+            .num_minutes()
+            .map(|minutes| rounding.apply(minutes));
+        let name = if hours.corrections > 0 {
+            format!("{} *", hours.staff_member.name)
+        } else {
+            hours.staff_member.name.clone()
+        };
+
+        Self {
+            name,
+            minutes_1,
+            minutes_2,
+            minutes_3,
+            sunday_minutes_1: sunday_buckets.then_some(sunday_1),
+            sunday_minutes_2: sunday_buckets.then_some(sunday_2),
+            sunday_minutes_3: sunday_buckets.then_some(sunday_3),
+            corrections: hours.corrections,
+            staff_name: hours.staff_member.name,
+            personnel_number: hours.staff_member.personnel_number,
+            department: hours.staff_member.department,
+            wage_cents_per_hour: hours.staff_member.wage_cents_per_hour,
+            intervals: hours.intervals,
+        }
+    }
+}
+
+#[cfg(test)]
+impl PersonHoursCSV {
+    /// A staff member without any work, for tests to fill in the fields they need.
+    pub(super) fn for_test(name: &str) -> Self {
+        PersonHoursCSV {
+            name: String::from(name),
+            minutes_1: 0,
+            minutes_2: 0,
+            minutes_3: 0,
+            sunday_minutes_1: None,
+            sunday_minutes_2: None,
+            sunday_minutes_3: None,
+            corrections: 0,
+            staff_name: String::from(name),
+            personnel_number: None,
+            department: None,
+            wage_cents_per_hour: None,
+            intervals: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StaffHours {
+    pub(super) hours_csv: Vec<PersonHoursCSV>,
+    pub(super) soft_errors: Vec<SoftStatisticsError>,
+}
+
+impl StaffHours {
+    pub(super) fn new(evaluation: Evaluation, sunday_buckets: bool, rounding: Rounding) -> Self {
+        Self {
+            hours_csv: evaluation
+                .hours
+                .into_iter()
+                // Transform the calculated WorkDuration into a PersonHoursCSV struct for the exports.
+                .map(|hours| PersonHoursCSV::new(hours, sunday_buckets, rounding))
+                .collect(),
+            soft_errors: evaluation.soft_errors,
+        }
+    }
+
+    pub(super) fn hours(&self) -> &[PersonHoursCSV] {
+        &self.hours_csv
+    }
+    pub(super) fn errors(&self) -> &[SoftStatisticsError] {
+        &self.soft_errors
+    }
+
+    /// Hours and errors of only the staff members with the given names, see `StaffSelection`.
+    pub(super) fn only_staff(self, names: &HashSet<String>) -> StaffHours {
+        StaffHours {
+            hours_csv: self
+                .hours_csv
+                .into_iter()
+                .filter(|hours| names.contains(&hours.staff_name))
+                .collect(),
+            soft_errors: self
+                .soft_errors
+                .into_iter()
+                .filter(|error| names.contains(error.staff_name()))
+                .collect(),
+        }
+    }
+
+    /// Hours and errors of all staff members, or only of those in the given department.
+    pub(super) fn for_department(
+        &self,
+        department: Option<&str>,
+    ) -> (Vec<&PersonHoursCSV>, Vec<&SoftStatisticsError>) {
+        let hours = self
+            .hours()
+            .iter()
+            .filter(|hours| department.is_none() || hours.department.as_deref() == department)
+            .collect::<Vec<_>>();
+        // Errors only name the staff member, so we filter them by name to not leak other departments' data.
+        let names = hours
+            .iter()
+            .map(|hours| hours.staff_name.as_str())
+            .collect::<HashSet<_>>();
+        let errors = self
+            .errors()
+            .iter()
+            .filter(|error| department.is_none() || names.contains(error.staff_name()))
+            .collect();
+        (hours, errors)
+    }
+
+    /// Add an error for each rest between two business days that was shorter than configured, see `rest_period`.
+    /// Only the intervals of the evaluated period are known, so a short rest right at its start is not found.
+    pub(super) fn check_rest_periods(&mut self, settings: &Settings) {
+        if settings.min_rest_hours == 0 {
+            return;
+        }
+        let min_rest = Duration::hours(i64::from(settings.min_rest_hours));
+        for hours in &self.hours_csv {
+            let intervals = hours
+                .intervals
+                .iter()
+                .map(|interval| (interval.start_time, interval.end_time))
+                .collect::<Vec<_>>();
+            self.soft_errors.extend(
+                rest_period::check(&intervals, settings.cutoff_time, min_rest)
+                    .into_iter()
+                    .map(|violation| {
+                        SoftStatisticsError::RestPeriod(hours.staff_name.clone(), violation)
+                    }),
+            );
+        }
+    }
+
+    /// Add an error for each week in which one of the `minors` worked more than allowed, see
+    /// `youth_protection::check_weeks`. The weeks at the edges of the period from `start_time` to `end_time` also
+    /// count the days outside of it, so `events` must cover these weeks, see `event_eval::with_edge_weeks`.
+    pub(super) fn check_youth_weeks(
+        &mut self,
+        minors: &[DBStaffMember],
+        events: &[WorkEventT],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        cutoff: NaiveTime,
+    ) {
+        let until = events.last().map_or(end_time, |eventt| eventt.created_at);
+        let first_day = start_time.business_date(cutoff);
+        let last_day = (end_time - Duration::seconds(1)).business_date(cutoff);
+        for staff_member in minors {
+            let intervals = youth_protection::work_intervals(events, staff_member.uuid(), until);
+            self.soft_errors.extend(
+                youth_protection::check_weeks(&intervals, cutoff, first_day, last_day)
+                    .into_iter()
+                    .map(|violation| {
+                        SoftStatisticsError::YouthProtection(staff_member.name().clone(), violation)
+                    }),
+            );
+        }
+    }
+
+    /// Write the evaluation of all staff members, or only of those in the given department.
+    pub(super) fn write_csv(
+        &self,
+        path: &Path,
+        settings: &Settings,
+        department: Option<&str>,
+    ) -> Result<(), StechuhrError> {
+        let (hours, errors) = self.for_department(department);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut wtr = csv::WriterBuilder::new()
+            // Use Tab as delimiter so that Excel automatically imports it correctly.
+            .delimiter(b'\t')
+            // Enable flexible writer since errors are just one field.
+            .flexible(true)
+            // The header depends on the configured buckets so we write it ourselves.
+            .has_headers(false)
+            .from_path(path)?;
+
+        let header = PersonHoursCSV::header(settings);
+        wtr.write_record(&header)?;
+        let columns = PersonHoursCSV::columns(settings);
+        for hours in hours {
+            wtr.write_record(hours.record(settings, &columns))?;
+        }
+        for error in errors {
+            // pad with empty fields to put errors into a separate column
+            let mut record = vec![String::new(); header.len() + 1];
+            record.push(error.to_string());
+            wtr.write_record(&record)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::tabs::statistics::file_name_part;
+
+    #[test]
+    fn write_csv_for_department() {
+        let hours = |name: &str, department: Option<&str>| PersonHoursCSV {
+            minutes_1: 60,
+            department: department.map(String::from),
+            ..PersonHoursCSV::for_test(name)
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![hours("Aaron", None), hours("Beeron", Some("Security GmbH"))],
+            soft_errors: vec![SoftStatisticsError::StaffStillWorking(
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(5, 59, 59),
+                String::from("Aaron"),
+            )],
+        };
+        let path = env::temp_dir()
+            .join("stechuhr-test")
+            .join(format!("{}.tsv", file_name_part("Security GmbH/Nord")));
+
+        staff_hours
+            .write_csv(&path, &Settings::default(), Some("Security GmbH"))
+            .unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(path.ends_with("Security GmbH_Nord.tsv"));
+        assert!(written.contains("Beeron"));
+        assert!(!written.contains("Aaron"));
+    }
+
+    #[test]
+    fn hours_of_department() {
+        let hours = |name: &str, department: Option<&str>| PersonHoursCSV {
+            department: department.map(String::from),
+            ..PersonHoursCSV::for_test(name)
+        };
+        let still_working = |name: &str| {
+            SoftStatisticsError::StaffStillWorking(
+                NaiveDate::from_ymd(2000, 1, 2).and_hms(5, 59, 59),
+                String::from(name),
+            )
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![
+                hours("Aaron", None),
+                hours("Beeron", Some("Bar")),
+                hours("Ceeron", Some("Security GmbH")),
+            ],
+            soft_errors: vec![still_working("Aaron"), still_working("Ceeron")],
+        };
+        fn names<'a>(
+            (hours, errors): (Vec<&'a PersonHoursCSV>, Vec<&SoftStatisticsError>),
+        ) -> (Vec<&'a str>, usize) {
+            (
+                hours
+                    .iter()
+                    .map(|hours| hours.staff_name.as_str())
+                    .collect::<Vec<_>>(),
+                errors.len(),
+            )
+        }
+
+        assert_eq!(
+            names(staff_hours.for_department(None)),
+            (vec!["Aaron", "Beeron", "Ceeron"], 2)
+        );
+        assert_eq!(
+            names(staff_hours.for_department(Some("Bar"))),
+            (vec!["Beeron"], 0)
+        );
+        // The error of Aaron is not leaked into the file of another department.
+        assert_eq!(
+            names(staff_hours.for_department(Some("Security GmbH"))),
+            (vec!["Ceeron"], 1)
+        );
+    }
+
+    #[test]
+    fn write_csv_two_buckets() {
+        let staff_hours = StaffHours {
+            hours_csv: vec![PersonHoursCSV {
+                name: String::from("Aaron *"),
+                minutes_1: 60,
+                minutes_3: 150,
+                corrections: 1,
+                ..PersonHoursCSV::for_test("Aaron")
+            }],
+            soft_errors: Vec::new(),
+        };
+        let settings = Settings {
+            bucket_starts: [6, 22, 22],
+            ..Settings::default()
+        };
+        let path = env::temp_dir()
+            .join("stechuhr-test")
+            .join("two_buckets.tsv");
+
+        staff_hours.write_csv(&path, &settings, None).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            written,
+            "Name\tMinuten 6 - 22 Uhr\tMinuten 22 - 6 Uhr\tKorrekturen\n\
+             Aaron *\t60\t150\t1\n"
+        );
+    }
+
+    #[test]
+    fn write_csv_wage() {
+        let hours = |name: &str, wage_cents_per_hour| PersonHoursCSV {
+            minutes_1: 90,
+            minutes_3: 45,
+            wage_cents_per_hour,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![hours("Aaron", Some(1250)), hours("Beeron", None)],
+            soft_errors: Vec::new(),
+        };
+        let settings = Settings {
+            bucket_starts: [6, 22, 22],
+            wage_column: true,
+            wage_surcharges: [0, 0, 25, 0, 0, 0],
+            ..Settings::default()
+        };
+        let path = env::temp_dir().join("stechuhr-test").join("wage.tsv");
+
+        staff_hours.write_csv(&path, &settings, None).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // 1,5 hours at 12,50 and 0,75 hours at 12,50 plus 25% are 18,75 + 11,72
+        assert_eq!(
+            written,
+            "Name\tMinuten 6 - 22 Uhr\tMinuten 22 - 6 Uhr\tKorrekturen\tLohn\n\
+             Aaron\t90\t45\t0\t30,47\n\
+             Beeron\t90\t45\t0\t\n"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Report of the changes of the staff list between two dates, so that the owner can review them every month.
+//! Several changes of a staff member are combined, e.g. renaming someone twice shows the first and the last name.
+use chrono::{Date, Duration, Local, NaiveDate};
+use iced::{
+    alignment::Horizontal, button, scrollable, text_input, Alignment, Button, Color, Column,
+    Element, Length, Row, Scrollable, Text,
+};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::models::{StaffHistoryEntry, StaffHistoryKind, StaffNames};
+
+use super::{StatisticsError, StatsMessage};
+use crate::{SharedData, StechuhrError};
+
+const DATE_FORMAT: &str = "%d.%m.%Y";
+
+/// The combined changes of one staff member in the report period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StaffDiff {
+    staff_id: i32,
+    name: String,
+    added: bool,
+    deleted: bool,
+    /// Changed fields with the value before and after the period. Values of PINs are not recorded.
+    fields: Vec<(String, Option<String>, Option<String>)>,
+}
+
+impl StaffDiff {
+    fn new(staff_id: i32, name: String) -> Self {
+        StaffDiff {
+            staff_id,
+            name,
+            added: false,
+            deleted: false,
+            fields: Vec::new(),
+        }
+    }
+
+    fn is_deactivated(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|(field, _, new)| field == "Sichtbar" && new.as_deref() == Some("nein"))
+    }
+
+    fn label(&self) -> &'static str {
+        match (self.added, self.deleted) {
+            (true, true) => "Neu und gelöscht",
+            (true, false) => "Neu",
+            (false, true) => "Gelöscht",
+            (false, false) if self.is_deactivated() => "Deaktiviert",
+            (false, false) => "Geändert",
+        }
+    }
+
+    fn color(&self) -> Color {
+        if self.deleted || self.is_deactivated() {
+            Color::from_rgb8(200, 0, 0)
+        } else if self.added {
+            Color::from_rgb8(0, 140, 0)
+        } else {
+            Color::BLACK
+        }
+    }
+
+    fn details(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(field, old, new)| match (old, new) {
+                (Some(old), Some(new)) => format!("{}: \"{}\" → \"{}\"", field, old, new),
+                _ => format!("{} geändert", field),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Combine the entries of the staff history, which must be ordered from oldest to newest.
+fn combine(entries: &[StaffHistoryEntry], names: &StaffNames) -> Vec<StaffDiff> {
+    let mut diffs: Vec<StaffDiff> = Vec::new();
+
+    for entry in entries {
+        let idx = match diffs
+            .iter()
+            .position(|diff| diff.staff_id == entry.staff_id)
+        {
+            Some(idx) => idx,
+            None => {
+                diffs.push(StaffDiff::new(
+                    entry.staff_id,
+                    names.resolve(entry.staff_id, "", false),
+                ));
+                diffs.len() - 1
+            }
+        };
+        let diff = &mut diffs[idx];
+
+        match (entry.kind(), &entry.field) {
+            (Some(StaffHistoryKind::Added), _) => diff.added = true,
+            (Some(StaffHistoryKind::Deleted), _) => diff.deleted = true,
+            (Some(StaffHistoryKind::Edited), Some(field)) => {
+                match diff.fields.iter_mut().find(|(f, _, _)| f == field) {
+                    Some((_, _, new)) => *new = entry.new_value.clone(),
+                    None => diff.fields.push((
+                        field.clone(),
+                        entry.old_value.clone(),
+                        entry.new_value.clone(),
+                    )),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Fields that were changed back are not a change.
+    for diff in diffs.iter_mut() {
+        diff.fields
+            .retain(|(_, old, new)| old.is_none() || new.is_none() || old != new);
+    }
+    diffs.retain(|diff| diff.added || diff.deleted || !diff.fields.is_empty());
+    diffs
+}
+
+#[derive(Default)]
+struct StaffReportModalState {
+    from_value: String,
+    to_value: String,
+    diffs: Vec<StaffDiff>,
+    // widget states
+    from_state: text_input::State,
+    to_state: text_input::State,
+    show_state: button::State,
+    scroll_state: scrollable::State,
+    close_state: button::State,
+}
+
+pub struct StaffReport {
+    modal_state: modal::State<StaffReportModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StaffReportMessage {
+    Open,
+    ChangeFrom(String),
+    ChangeTo(String),
+    Show,
+    Close,
+}
+
+impl StaffReport {
+    pub fn new() -> Self {
+        StaffReport {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    fn parse_date(s: &str) -> Result<NaiveDate, StatisticsError> {
+        NaiveDate::parse_from_str(s.trim(), DATE_FORMAT)
+            .map_err(|_| StatisticsError::ParseDate(s.to_owned()))
+    }
+
+    /// Open the report for the month of the given date.
+    fn open(&mut self, shared: &mut SharedData, date: Date<Local>) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        *state = StaffReportModalState::default();
+        state.from_value = date
+            .naive_local()
+            .first_dom()
+            .format(DATE_FORMAT)
+            .to_string();
+        state.to_value = date
+            .naive_local()
+            .last_dom()
+            .format(DATE_FORMAT)
+            .to_string();
+        self.modal_state.show(true);
+        self.show(shared)
+    }
+
+    /// Load the changes from the start of the first date until the end of the second date.
+    fn show(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        let from = StaffReport::parse_date(&state.from_value)?;
+        let to = StaffReport::parse_date(&state.to_value)?;
+
+        let entries = shared.storage.load_staff_history_between(
+            from.and_hms(0, 0, 0),
+            (to + Duration::days(1)).and_hms(0, 0, 0),
+        );
+        state.diffs = combine(&entries, &shared.staff_names);
+        Ok(())
+    }
+
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::StaffReport(message);
+
+        let modal = Modal::new(&mut self.modal_state, content, move |state| {
+            let form = Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Text::new("Von"))
+                .push(
+                    stechuhr::style::text_input(
+                        &mut state.from_state,
+                        "TT.MM.JJJJ",
+                        &state.from_value,
+                        move |s| message(StaffReportMessage::ChangeFrom(s)),
+                    )
+                    .on_submit(message(StaffReportMessage::Show))
+                    .width(Length::Units(150)),
+                )
+                .push(Text::new("bis"))
+                .push(
+                    stechuhr::style::text_input(
+                        &mut state.to_state,
+                        "TT.MM.JJJJ",
+                        &state.to_value,
+                        move |s| message(StaffReportMessage::ChangeTo(s)),
+                    )
+                    .on_submit(message(StaffReportMessage::Show))
+                    .width(Length::Units(150)),
+                )
+                .push(
+                    Button::new(
+                        &mut state.show_state,
+                        Text::new("Anzeigen").horizontal_alignment(Horizontal::Center),
+                    )
+                    .on_press(message(StaffReportMessage::Show)),
+                );
+
+            let mut list = Scrollable::new(&mut state.scroll_state).spacing(5);
+            if state.diffs.is_empty() {
+                list = list.push(Text::new("Keine Änderungen in diesem Zeitraum."));
+            }
+            for diff in &state.diffs {
+                list = list.push(
+                    Row::new()
+                        .spacing(10)
+                        .push(
+                            Text::new(diff.label())
+                                .color(diff.color())
+                                .width(Length::Units(170)),
+                        )
+                        .push(Text::new(&diff.name).width(Length::Units(200)))
+                        .push(Text::new(diff.details()).size(16).width(Length::Fill)),
+                );
+            }
+
+            let body = Column::new()
+                .spacing(20)
+                .push(form)
+                .push(list.max_height(400));
+
+            Card::new(Text::new("Änderungen der Mitarbeiterliste"), body)
+                .foot(
+                    Row::new().padding(5).width(Length::Fill).push(
+                        Button::new(
+                            &mut state.close_state,
+                            Text::new("Schließen").horizontal_alignment(Horizontal::Center),
+                        )
+                        .on_press(message(StaffReportMessage::Close)),
+                    ),
+                )
+                .max_width(900)
+                .on_close(message(StaffReportMessage::Close))
+                .into()
+        })
+        .backdrop(message(StaffReportMessage::Close))
+        .on_esc(message(StaffReportMessage::Close));
+
+        modal.into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        date: Date<Local>,
+        message: StaffReportMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            StaffReportMessage::Open => self.open(shared, date)?,
+            StaffReportMessage::ChangeFrom(value) => {
+                self.modal_state.inner_mut().from_value = value
+            }
+            StaffReportMessage::ChangeTo(value) => self.modal_state.inner_mut().to_value = value,
+            StaffReportMessage::Show => self.show(shared)?,
+            StaffReportMessage::Close => self.modal_state.show(false),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use stechuhr::db;
+    use stechuhr::models::{NewStaffHistoryEntry, NewStaffMember};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn combine_changes() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let aaron = db::insert_staff(
+            NewStaffMember::new(
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                String::new(),
+            )
+            .unwrap(),
+            &mut connection,
+        )
+        .unwrap();
+        let time = NaiveDate::from_ymd(2022, 4, 1).and_hms(12, 0, 0);
+
+        let mut renamed = aaron.clone();
+        renamed.name = String::from("Aaronia");
+        renamed.pin = String::from("4444");
+        let mut renamed_back = renamed.clone();
+        renamed_back.name = String::from("Aaron");
+        renamed_back.is_visible = false;
+        let mut entries = NewStaffHistoryEntry::edits(&renamed, &aaron, time);
+        entries.extend(NewStaffHistoryEntry::edits(&renamed_back, &renamed, time));
+        db::insert_staff_history(&entries, &mut connection).unwrap();
+
+        let entries =
+            db::load_staff_history_between(time, time + Duration::seconds(1), &mut connection);
+        let diffs = combine(&entries, &db::load_staff_names(&mut connection));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].label(), "Deaktiviert");
+        assert_eq!(
+            diffs[0].details(),
+            "PIN geändert, Sichtbar: \"ja\" → \"nein\""
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Selection of the staff members for whom "CSV Generieren" writes the evaluation, e.g. only the bar team.
+//! Without a selection everybody is evaluated. The files of a selection get their own name and are not kept in the
+//! export history, since regenerating them from there would give the evaluation of everybody.
+use std::collections::{BTreeSet, HashSet};
+
+use iced::{button, scrollable, Checkbox, Element, Length, Scrollable, Text};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::models::StaffMember;
+
+use super::error_review::PendingEvaluation;
+use super::StatsMessage;
+use crate::tabs::components;
+
+#[derive(Default)]
+struct StaffSelectionModalState {
+    /* uuid and name of the visible staff members when the dialog was opened */
+    staff: Vec<(i32, String)>,
+    selected: BTreeSet<i32>,
+    scroll_state: scrollable::State,
+    close_state: button::State,
+    all_state: button::State,
+}
+
+pub struct StaffSelection {
+    modal_state: modal::State<StaffSelectionModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StaffSelectionMessage {
+    Open,
+    Toggle(i32, bool),
+    SelectAll,
+    Close,
+}
+
+impl StaffSelection {
+    pub fn new() -> Self {
+        StaffSelection {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    /// Label of the button that opens the selection.
+    pub fn label(&self, staff: &[StaffMember]) -> String {
+        match self.names(staff) {
+            None => String::from("Personen: alle"),
+            Some(names) => format!("Personen: {} ausgewählt", names.len()),
+        }
+    }
+
+    /// Names of the selected staff members, nothing if everybody is evaluated. That is also the case if none of the
+    /// selected staff members exists anymore, instead of an evaluation of nobody.
+    /// Errors only name the staff member, so the evaluation is filtered by name like for departments.
+    pub fn names(&self, staff: &[StaffMember]) -> Option<HashSet<String>> {
+        let selected = &self.modal_state.inner().selected;
+        let names = staff
+            .iter()
+            .filter(|staff_member| selected.contains(&staff_member.uuid()))
+            .map(|staff_member| staff_member.name.clone())
+            .collect::<HashSet<_>>();
+        if names.is_empty() {
+            None
+        } else {
+            Some(names)
+        }
+    }
+
+    /// Restrict the evaluation to the selected staff members. A selection gets its own file name and no month, so that
+    /// it does not replace the complete evaluation of the month in the export history.
+    pub fn apply(&self, staff: &[StaffMember], evaluation: PendingEvaluation) -> PendingEvaluation {
+        match self.names(staff) {
+            Some(names) => PendingEvaluation {
+                name: format!("{} Auswahl", evaluation.name),
+                month: None,
+                hours: evaluation.hours.only_staff(&names),
+            },
+            None => evaluation,
+        }
+    }
+
+    pub fn view<'a>(&'a mut self, content: Element<'a, StatsMessage>) -> Element<'a, StatsMessage> {
+        let message = |message| StatsMessage::StaffSelection(message);
+
+        Modal::new(&mut self.modal_state, content, move |state| {
+            let mut list = Scrollable::new(&mut state.scroll_state).spacing(10);
+            for (uuid, name) in &state.staff {
+                let uuid = *uuid;
+                list = list.push(Checkbox::new(
+                    state.selected.contains(&uuid),
+                    name,
+                    move |b| message(StaffSelectionMessage::Toggle(uuid, b)),
+                ));
+            }
+
+            Card::new(
+                Text::new("Personen für CSV Generieren auswählen"),
+                list.max_height(450).width(Length::Fill),
+            )
+            .foot(components::confirm_foot(
+                &mut state.close_state,
+                "Übernehmen",
+                Some(message(StaffSelectionMessage::Close)),
+                &mut state.all_state,
+                "Alle Personen",
+                message(StaffSelectionMessage::SelectAll),
+            ))
+            .max_width(600)
+            .on_close(message(StaffSelectionMessage::Close))
+            .into()
+        })
+        .backdrop(message(StaffSelectionMessage::Close))
+        .on_esc(message(StaffSelectionMessage::Close))
+        .into()
+    }
+
+    pub fn update(&mut self, staff: &[StaffMember], message: StaffSelectionMessage) {
+        let state = self.modal_state.inner_mut();
+        match message {
+            StaffSelectionMessage::Open => {
+                state.staff = staff
+                    .iter()
+                    .filter(|staff_member| staff_member.is_visible)
+                    .map(|staff_member| (staff_member.uuid(), staff_member.name.clone()))
+                    .collect();
+                // Forget staff members who were hidden in the meantime.
+                let visible = state
+                    .staff
+                    .iter()
+                    .map(|(uuid, _)| *uuid)
+                    .collect::<HashSet<_>>();
+                state.selected.retain(|uuid| visible.contains(uuid));
+                self.modal_state.show(true);
+            }
+            StaffSelectionMessage::Toggle(uuid, is_selected) => {
+                if is_selected {
+                    state.selected.insert(uuid);
+                } else {
+                    state.selected.remove(&uuid);
+                }
+            }
+            StaffSelectionMessage::SelectAll => {
+                state.selected.clear();
+                self.modal_state.show(false);
+            }
+            StaffSelectionMessage::Close => self.modal_state.show(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::SoftStatisticsError;
+    use stechuhr::models::{DBStaffMember, WorkStatus};
+
+    use super::super::{PersonHoursCSV, StaffHours};
+    use super::*;
+
+    #[test]
+    fn evaluate_selected_staff() {
+        let staff_member = |uuid, name: &str| {
+            DBStaffMember::new(
+                uuid,
+                String::from(name),
+                String::new(),
+                String::new(),
+                true,
+                None,
+            )
+            .with_status(WorkStatus::Away)
+        };
+        let staff = vec![staff_member(1, "Aaron"), staff_member(2, "Beeron")];
+        let hours = |name: &str| PersonHoursCSV {
+            minutes_1: 60,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let evaluation = || PendingEvaluation {
+            name: String::from("2000-01"),
+            month: Some(NaiveDate::from_ymd(2000, 1, 1)),
+            hours: StaffHours {
+                hours_csv: vec![hours("Aaron"), hours("Beeron")],
+                soft_errors: vec![SoftStatisticsError::StaffStillWorking(
+                    NaiveDate::from_ymd(2000, 1, 2).and_hms(5, 59, 59),
+                    String::from("Aaron"),
+                )],
+            },
+        };
+        let mut staff_selection = StaffSelection::new();
+
+        // Without a selection everybody is evaluated.
+        staff_selection.update(&staff, StaffSelectionMessage::Open);
+        assert_eq!(staff_selection.label(&staff), "Personen: alle");
+        let pending = staff_selection.apply(&staff, evaluation());
+        assert_eq!(pending.name, "2000-01");
+        assert_eq!(pending.hours.hours().len(), 2);
+
+        staff_selection.update(&staff, StaffSelectionMessage::Toggle(2, true));
+        staff_selection.update(&staff, StaffSelectionMessage::Close);
+        assert_eq!(staff_selection.label(&staff), "Personen: 1 ausgewählt");
+        let pending = staff_selection.apply(&staff, evaluation());
+        assert_eq!(pending.name, "2000-01 Auswahl");
+        assert_eq!(pending.month, None);
+        assert_eq!(pending.hours.hours().len(), 1);
+        assert_eq!(pending.hours.hours()[0].staff_name, "Beeron");
+        assert!(pending.hours.errors().is_empty());
+
+        // Everybody is evaluated if the selected staff member was deleted in the meantime.
+        assert!(staff_selection.names(&staff[..1]).is_none());
+        assert_eq!(staff_selection.label(&staff[..1]), "Personen: alle");
+        let pending = staff_selection.apply(&staff[..1], evaluation());
+        assert_eq!(pending.name, "2000-01");
+        assert_eq!(pending.hours.hours().len(), 2);
+
+        staff_selection.update(&staff, StaffSelectionMessage::SelectAll);
+        assert!(staff_selection.names(&staff).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+//! Record of the daily working time as required by § 17 MiLoG: start, end and duration of the work of each day,
+//! in one file per staff member who worked in the month. A split shift gives one row per interval.
+//! Unlike the buckets of the evaluation the durations are not rounded up, only to the nearest minute.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use stechuhr::settings::Settings;
+
+use super::{file_name_part, person_preview::format_minutes, PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+/// Minutes between start and end of each interval, rounded to the nearest minute.
+fn interval_minutes(hours: &PersonHoursCSV) -> Vec<i64> {
+    hours
+        .intervals
+        .iter()
+        .map(|interval| ((interval.end_time - interval.start_time).num_seconds() + 30) / 60)
+        .collect()
+}
+
+/// Write the record of each staff member who worked in the month as `<name>.tsv` into the given directory, and return
+/// the paths of the records. The .tsv files open in Excel like the other exports.
+pub fn write(
+    dir: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    month_name: &str,
+) -> Result<Vec<PathBuf>, StechuhrError> {
+    fs::create_dir_all(dir)?;
+
+    let mut paths = Vec::new();
+    for hours in staff_hours.hours() {
+        if hours.intervals.is_empty() {
+            continue;
+        }
+        let path = dir.join(format!("{}.tsv", file_name_part(&hours.staff_name)));
+        let mut wtr = csv::WriterBuilder::new()
+            // Use Tab as delimiter so that Excel automatically imports it correctly.
+            .delimiter(b'\t')
+            .flexible(true)
+            .from_path(&path)?;
+        wtr.write_record(["Name", hours.staff_name.as_str()])?;
+        wtr.write_record([
+            "Personalnummer",
+            hours.personnel_number.as_deref().unwrap_or_default(),
+        ])?;
+        wtr.write_record(["Monat", month_name])?;
+        wtr.write_record(["Datum", "Beginn", "Ende", "Dauer"])?;
+
+        let minutes = interval_minutes(hours);
+        for (interval, minutes) in hours.intervals.iter().zip(&minutes) {
+            wtr.write_record([
+                settings.format_export_date(settings.business_date(interval.start_time)),
+                interval.start_time.format("%H:%M").to_string(),
+                interval.end_time.format("%H:%M").to_string(),
+                format_minutes(*minutes),
+            ])?;
+        }
+        wtr.write_record([
+            String::from("Summe"),
+            String::new(),
+            String::new(),
+            format_minutes(minutes.iter().sum()),
+        ])?;
+        wtr.flush()?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::IntervalMinutes;
+
+    #[test]
+    fn write_per_person() {
+        let day = NaiveDate::from_ymd(2022, 4, 1);
+        let person = |name: &str, intervals| PersonHoursCSV {
+            intervals,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let interval = |start_time, end_time| IntervalMinutes {
+            start_time,
+            end_time,
+            minutes: [0; 6],
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![
+                person(
+                    "Aaron",
+                    vec![
+                        interval(day.and_hms(18, 0, 0), day.and_hms(22, 15, 0)),
+                        interval(day.succ().and_hms(1, 0, 0), day.succ().and_hms(5, 59, 59)),
+                    ],
+                ),
+                person("Berta", Vec::new()),
+            ],
+            soft_errors: Vec::new(),
+        };
+        let dir = std::env::temp_dir().join("stechuhr_time_records_test");
+
+        let paths = write(&dir, &Settings::default(), &staff_hours, "April 2022").unwrap();
+        let written = fs::read_to_string(&paths[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(paths, vec![dir.join("Aaron.tsv")]);
+        assert_eq!(
+            written,
+            "Name\tAaron\n\
+             Personalnummer\t\n\
+             Monat\tApril 2022\n\
+             Datum\tBeginn\tEnde\tDauer\n\
+             01.04.2022\t18:00\t22:15\t4:15\n\
+             01.04.2022\t01:00\t05:59\t5:00\n\
+             Summe\t\t\t9:15\n"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Personal timesheet of each staff member with only their own shifts of the month, so that it can be handed out
+//! without showing the hours of everybody else. One row per interval of work like in `interval_export`, followed by
+//! the sums of the evaluation. Since the rows are rounded up per bucket they can add up to slightly more.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use stechuhr::settings::Settings;
+
+use super::{file_name_part, PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+/// Write the timesheet of each staff member who worked in the month as `<name>.csv` into the given directory, and
+/// return the paths of the timesheets.
+pub fn write(
+    dir: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    month_name: &str,
+) -> Result<Vec<PathBuf>, StechuhrError> {
+    fs::create_dir_all(dir)?;
+
+    let columns = PersonHoursCSV::columns(settings);
+    let buckets = PersonHoursCSV::header(settings);
+    // The header starts with the name, the buckets follow.
+    let buckets = &buckets[1..1 + columns.len()];
+    let mut header = ["Betriebstag", "Beginn", "Ende", "Minuten"]
+        .map(String::from)
+        .to_vec();
+    header.extend(buckets.iter().cloned());
+
+    let mut paths = Vec::new();
+    for hours in staff_hours.hours() {
+        if hours.intervals.is_empty() {
+            continue;
+        }
+        let path = dir.join(format!("{}.csv", file_name_part(&hours.staff_name)));
+        let mut wtr = csv::WriterBuilder::new()
+            // Semicolons, since Excel with a German locale splits .csv files at them.
+            .delimiter(b';')
+            .flexible(true)
+            .from_path(&path)?;
+        wtr.write_record(["Name", hours.staff_name.as_str()])?;
+        wtr.write_record([
+            "Personalnummer",
+            hours.personnel_number.as_deref().unwrap_or_default(),
+        ])?;
+        wtr.write_record(["Monat", month_name])?;
+        wtr.write_record(&header)?;
+
+        for interval in &hours.intervals {
+            let minutes = columns
+                .iter()
+                .map(|idx| interval.minutes[*idx])
+                .collect::<Vec<_>>();
+            let mut record = vec![
+                settings.format_export_date(settings.business_date(interval.start_time)),
+                interval.start_time.format("%H:%M").to_string(),
+                interval.end_time.format("%H:%M").to_string(),
+                minutes.iter().sum::<i64>().to_string(),
+            ];
+            record.extend(minutes.iter().map(|minutes| minutes.to_string()));
+            wtr.write_record(&record)?;
+        }
+
+        let minutes = hours.minutes();
+        let minutes = columns.iter().map(|idx| minutes[*idx]).collect::<Vec<_>>();
+        let mut record = vec![
+            String::from("Summe"),
+            String::new(),
+            String::new(),
+            minutes.iter().sum::<i64>().to_string(),
+        ];
+        record.extend(minutes.iter().map(|minutes| minutes.to_string()));
+        wtr.write_record(&record)?;
+        wtr.flush()?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::IntervalMinutes;
+
+    #[test]
+    fn write_own_shifts() {
+        let day = NaiveDate::from_ymd(2022, 4, 1);
+        let person = |name: &str, minutes_1, minutes_2, minutes_3, intervals| PersonHoursCSV {
+            minutes_1,
+            minutes_2,
+            minutes_3,
+            personnel_number: Some(String::from("17")),
+            intervals,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let interval = |start_time, end_time, minutes| IntervalMinutes {
+            start_time,
+            end_time,
+            minutes,
+        };
+        let staff_hours = StaffHours {
+            hours_csv: vec![
+                person(
+                    "Aaron",
+                    180,
+                    120,
+                    180,
+                    vec![
+                        interval(
+                            day.and_hms(18, 0, 0),
+                            day.and_hms(22, 0, 0),
+                            [120, 120, 0, 0, 0, 0],
+                        ),
+                        interval(
+                            day.succ().and_hms(1, 0, 0),
+                            day.succ().and_hms(5, 0, 0),
+                            [60, 0, 180, 0, 0, 0],
+                        ),
+                    ],
+                ),
+                person("Berta", 0, 0, 0, Vec::new()),
+            ],
+            soft_errors: Vec::new(),
+        };
+        let dir = std::env::temp_dir().join("stechuhr_timesheets_test");
+
+        let paths = write(&dir, &Settings::default(), &staff_hours, "April 2022").unwrap();
+        let written = fs::read_to_string(&paths[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(paths, vec![dir.join("Aaron.csv")]);
+        assert_eq!(
+            written,
+            "Name;Aaron\n\
+             Personalnummer;17\n\
+             Monat;April 2022\n\
+             Betriebstag;Beginn;Ende;Minuten;Minuten 4 - 20 Uhr;Minuten 20 - 24 Uhr;Minuten 24 - 4 Uhr\n\
+             01.04.2022;18:00;22:00;240;120;120;0\n\
+             01.04.2022;01:00;05:00;240;60;0;180\n\
+             Summe;;;480;180;120;180\n"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Widgets of the tab. The texts of the buttons come from the view models, see `EvaluationPeriod` and `ExportOptions`.
+use iced::{Alignment, Button, Checkbox, Column, Container, Element, Length, Row, Text};
+use iced_aw::date_picker::DatePicker;
+use stechuhr::TEXT_SIZE_BIG;
+
+use super::closing::ClosingMessage;
+use super::event_query::EventQueryMessage;
+use super::export_options::ExportOptionsMessage;
+use super::hours_chart::HoursChartMessage;
+use super::person_preview::PersonPreviewMessage;
+use super::staff_report::StaffReportMessage;
+use super::staff_selection::StaffSelectionMessage;
+use super::{bucket_preview, StatsMessage, StatsTab};
+use crate::{SharedData, TAB_PADDING};
+
+impl StatsTab {
+    pub(super) fn view(&mut self, shared: &mut SharedData) -> Element<'_, StatsMessage> {
+        let date = Container::new(
+            Text::new(
+                self.period
+                    .date()
+                    .format_localized("%B %Y", shared.settings.locale())
+                    .to_string(),
+            )
+            .size(TEXT_SIZE_BIG),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y();
+
+        let datepicker = DatePicker::new(
+            &mut self.month_picker,
+            date,
+            StatsMessage::CancelDate,
+            StatsMessage::SubmitDate,
+        );
+
+        let mut buttons = Column::new()
+            .push(
+                Button::new(&mut self.date_button_state, Text::new("Datum auswählen"))
+                    .on_press(StatsMessage::ChooseDate),
+            )
+            .push(
+                Button::new(
+                    &mut self.export_dir_button_state,
+                    Text::new(format!(
+                        "Speicherort: {}",
+                        shared.settings.export_dir.display()
+                    )),
+                )
+                .on_press(StatsMessage::ChooseExportDir),
+            )
+            .push(
+                Row::new()
+                    .push(
+                        Button::new(&mut self.generate_button_state, Text::new("CSV Generieren"))
+                            .on_press(StatsMessage::Generate),
+                    )
+                    .push(
+                        Button::new(
+                            &mut self.staff_selection_button_state,
+                            Text::new(self.staff_selection.label(&shared.staff)),
+                        )
+                        .on_press(StatsMessage::StaffSelection(StaffSelectionMessage::Open)),
+                    )
+                    .spacing(10),
+            )
+            .push(
+                Row::new()
+                    .push(DatePicker::new(
+                        &mut self.range_picker,
+                        Button::new(
+                            &mut self.range_button_state,
+                            Text::new(self.period.range_label()),
+                        )
+                        .on_press(StatsMessage::ChooseRangeEnd),
+                        StatsMessage::CancelRangeEnd,
+                        StatsMessage::SubmitRangeEnd,
+                    ))
+                    .push({
+                        let button = Button::new(
+                            &mut self.range_generate_button_state,
+                            Text::new("Zeitraum auswerten"),
+                        );
+                        if self.period.range_end().is_some() {
+                            button.on_press(StatsMessage::GenerateRange)
+                        } else {
+                            button
+                        }
+                    })
+                    .spacing(10),
+            )
+            .push(
+                Row::new()
+                    .push(DatePicker::new(
+                        &mut self.months_picker,
+                        Button::new(
+                            &mut self.months_button_state,
+                            Text::new(self.period.months_label()),
+                        )
+                        .on_press(StatsMessage::ChooseMonthsEnd),
+                        StatsMessage::CancelMonthsEnd,
+                        StatsMessage::SubmitMonthsEnd,
+                    ))
+                    .push({
+                        let button = Button::new(
+                            &mut self.months_generate_button_state,
+                            Text::new("Monate auswerten"),
+                        );
+                        if self.period.months_end().is_some() {
+                            button.on_press(StatsMessage::GenerateMonths)
+                        } else {
+                            button
+                        }
+                    })
+                    .spacing(10),
+            )
+            .push(
+                Button::new(
+                    &mut self.log_button_state,
+                    Text::new("Protokoll exportieren"),
+                )
+                .on_press(StatsMessage::ExportLog),
+            )
+            .push(
+                Button::new(&mut self.datev_button_state, Text::new("DATEV exportieren"))
+                    .on_press(StatsMessage::ExportDatev),
+            )
+            .push(
+                Button::new(&mut self.json_button_state, Text::new("JSON exportieren"))
+                    .on_press(StatsMessage::ExportJson),
+            )
+            .push(
+                Button::new(
+                    &mut self.sessions_button_state,
+                    Text::new("Veranstaltungen"),
+                )
+                .on_press(StatsMessage::ExportSessions),
+            )
+            .push(
+                Button::new(
+                    &mut self.intervals_button_state,
+                    Text::new("Schichten einzeln"),
+                )
+                .on_press(StatsMessage::ExportIntervals),
+            )
+            .push(
+                Button::new(
+                    &mut self.time_records_button_state,
+                    Text::new("Arbeitszeitnachweise"),
+                )
+                .on_press(StatsMessage::ExportTimeRecords),
+            )
+            .push(
+                Button::new(
+                    &mut self.timesheets_button_state,
+                    Text::new("Stundenzettel"),
+                )
+                .on_press(StatsMessage::ExportTimesheets),
+            )
+            .push(
+                Button::new(
+                    &mut self.year_summary_button_state,
+                    Text::new("12 Monate je Person"),
+                )
+                .on_press(StatsMessage::ExportYearSummary),
+            )
+            .push(
+                Button::new(
+                    &mut self.year_to_date_button_state,
+                    Text::new("Jahresauswertung"),
+                )
+                .on_press(StatsMessage::ExportYearToDate),
+            )
+            .push(
+                Button::new(&mut self.closing_button_state, Text::new("Monatsabschluss"))
+                    .on_press(StatsMessage::Closing(ClosingMessage::Open)),
+            )
+            .push(
+                Button::new(
+                    &mut self.staff_report_button_state,
+                    Text::new("Personaländerungen"),
+                )
+                .on_press(StatsMessage::StaffReport(StaffReportMessage::Open)),
+            )
+            .push(
+                Button::new(
+                    &mut self.person_preview_button_state,
+                    Text::new("Einzelansicht"),
+                )
+                .on_press(StatsMessage::PersonPreview(PersonPreviewMessage::Open)),
+            )
+            .push(
+                Button::new(&mut self.hours_chart_button_state, Text::new("Diagramm"))
+                    .on_press(StatsMessage::HoursChart(HoursChartMessage::Open)),
+            )
+            .push(
+                Button::new(&mut self.event_query_button_state, Text::new("Abfragen"))
+                    .on_press(StatsMessage::EventQuery(EventQueryMessage::Open)),
+            )
+            .spacing(20)
+            .push(Checkbox::new(
+                self.options.include_inactive(),
+                "Ausgeblendete und gelöschte Personen einbeziehen",
+                |b| StatsMessage::Options(ExportOptionsMessage::ToggleIncludeInactive(b)),
+            ));
+
+        // Departments can be selected to get an additional file with only their staff members.
+        let departments = self.options.department_choices(&shared.staff);
+        if !departments.is_empty() {
+            buttons = buttons.push(Text::new("Zusätzliche Datei je Abteilung:"));
+            for (department, is_selected) in departments {
+                buttons = buttons.push(Checkbox::new(is_selected, department.clone(), move |b| {
+                    StatsMessage::Options(ExportOptionsMessage::ToggleDepartment(
+                        department.clone(),
+                        b,
+                    ))
+                }));
+            }
+        }
+        if self.failover.is_some() {
+            buttons = buttons.push(
+                Button::new(
+                    &mut self.retry_button_state,
+                    Text::new("Erneut im Exportverzeichnis speichern"),
+                )
+                .on_press(StatsMessage::RetryExport),
+            );
+        }
+        if shared.settings.bookkeeping_email.is_some() {
+            if let Some((name, _)) = &self.last_evaluation {
+                buttons = buttons.push(
+                    Button::new(
+                        &mut self.email_button_state,
+                        Text::new(format!("{} per E-Mail senden", name)),
+                    )
+                    .on_press(StatsMessage::SendEmail),
+                );
+            }
+        }
+
+        let content = Row::new()
+            .push(datepicker)
+            .push(
+                Container::new(buttons)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x()
+                    .center_y(),
+            )
+            .align_items(Alignment::Center)
+            .height(Length::FillPortion(60));
+        let content = Column::new()
+            .spacing(20)
+            .push(content)
+            .push(bucket_preview::view(&shared.settings))
+            .push(
+                Container::new(self.history.view(&shared.settings)).height(Length::FillPortion(40)),
+            );
+
+        let content: Element<'_, StatsMessage> =
+            Container::new(content).padding(TAB_PADDING).into();
+        let content = self.closing.view(content);
+        let content = self.error_review.view(content);
+        let content = self.staff_selection.view(content);
+        let content = self.staff_report.view(content);
+        let content = self.person_preview.view(&shared.settings, content);
+        let content = self.hours_chart.view(content);
+        let content = self.event_query.view(content);
+        content
+    }
+}
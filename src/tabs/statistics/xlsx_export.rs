@@ -0,0 +1,132 @@
+//! Export of the evaluation as Excel workbook, because spreadsheets do not always import the CSV files correctly,
+//! depending on their locale. The first sheet has the same columns as the CSV file, the second lists the errors.
+use std::fs;
+use std::path::Path;
+
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use stechuhr::settings::Settings;
+
+use super::{PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+/// Whole minutes with a thousands separator, which Excel localizes itself.
+const MINUTES_FORMAT: &str = "#,##0";
+/// Euro with cents.
+const WAGE_FORMAT: &str = "#,##0.00";
+
+/// Name of the Excel file that is written next to the CSV file with the given name.
+pub fn file_name(csv_file_name: &str) -> String {
+    Path::new(csv_file_name)
+        .with_extension("xlsx")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Write the evaluation of all staff members, or only of those in the given department.
+pub fn write(
+    path: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    department: Option<&str>,
+) -> Result<(), StechuhrError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_workbook(path, settings, staff_hours, department)
+        .map_err(|e| StechuhrError::Str(format!("Excel-Datei {}: {}", path.display(), e)))
+}
+
+fn write_workbook(
+    path: &Path,
+    settings: &Settings,
+    staff_hours: &StaffHours,
+    department: Option<&str>,
+) -> Result<(), XlsxError> {
+    let (hours, errors) = staff_hours.for_department(department);
+    let bold = Format::new().set_bold();
+    let minutes_format = Format::new().set_num_format(MINUTES_FORMAT);
+    let wage_format = Format::new().set_num_format(WAGE_FORMAT);
+    let mut workbook = Workbook::new();
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Arbeitszeit")?;
+    let header = PersonHoursCSV::header(settings);
+    for (col, title) in header.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, title, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 1)?;
+    let columns = PersonHoursCSV::columns(settings);
+    for (row, hours) in hours.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, &hours.name)?;
+        let minutes = hours.minutes();
+        for (col, idx) in columns.iter().enumerate() {
+            sheet.write_number_with_format(
+                row,
+                col as u16 + 1,
+                minutes[*idx] as f64,
+                &minutes_format,
+            )?;
+        }
+        sheet.write_number(row, columns.len() as u16 + 1, hours.corrections as f64)?;
+        if let Some(cents) = hours.wage_cents(settings).filter(|_| settings.wage_column) {
+            sheet.write_number_with_format(
+                row,
+                columns.len() as u16 + 2,
+                cents as f64 / 100.0,
+                &wage_format,
+            )?;
+        }
+    }
+    sheet.set_column_width(0, 30)?;
+    for col in 1..header.len() {
+        sheet.set_column_width(col as u16, 22)?;
+    }
+
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Fehler")?;
+    sheet.write_string_with_format(0, 0, "Name", &bold)?;
+    sheet.write_string_with_format(0, 1, "Fehler", &bold)?;
+    for (row, error) in errors.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string(row, 0, error.staff_name())?;
+        sheet.write_string(row, 1, error.to_string())?;
+    }
+    sheet.set_column_width(0, 30)?;
+    sheet.set_column_width(1, 120)?;
+
+    workbook.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use stechuhr::evaluation::SoftStatisticsError;
+
+    #[test]
+    fn write_workbook_with_errors() {
+        let staff_hours = StaffHours {
+            hours_csv: vec![PersonHoursCSV {
+                minutes_1: 60,
+                minutes_2: 30,
+                ..PersonHoursCSV::for_test("Aaron")
+            }],
+            soft_errors: vec![SoftStatisticsError::StaffStillWorking(
+                NaiveDate::from_ymd(2022, 4, 2).and_hms(5, 59, 59),
+                String::from("Aaron"),
+            )],
+        };
+        let path = std::env::temp_dir()
+            .join("stechuhr-test")
+            .join(file_name("2022-04 April.tsv"));
+
+        write(&path, &Settings::default(), &staff_hours, None).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(path.ends_with("2022-04 April.xlsx"));
+        // An xlsx file is a zip archive.
+        assert!(written.starts_with(b"PK"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Hours of each staff member in each month of a period and their sum, e.g. of the last twelve months for renewing
+//! contracts or of the year so far, which otherwise needs the evaluations of all months side by side.
+//! Only the total of all buckets is shown per month. The last row is the sum of all staff members.
+use std::fs;
+use std::path::Path;
+
+use chrono::{Date, Local};
+use stechuhr::settings::Settings;
+
+use super::{PersonHoursCSV, StaffHours};
+use crate::StechuhrError;
+
+/// The staff members in the order in which they first appear, with their personnel number and minutes per month.
+fn rows(
+    settings: &Settings,
+    months: &[(Date<Local>, StaffHours)],
+) -> Vec<(String, Option<String>, Vec<i64>)> {
+    let columns = PersonHoursCSV::columns(settings);
+    let mut rows: Vec<(String, Option<String>, Vec<i64>)> = Vec::new();
+    for (idx, (_, staff_hours)) in months.iter().enumerate() {
+        for hours in staff_hours.hours() {
+            let minutes = hours.minutes();
+            let total = columns.iter().map(|column| minutes[*column]).sum::<i64>();
+            let row = match rows
+                .iter()
+                .position(|(name, _, _)| *name == hours.staff_name)
+            {
+                Some(row) => &mut rows[row],
+                None => {
+                    rows.push((
+                        hours.staff_name.clone(),
+                        hours.personnel_number.clone(),
+                        vec![0; months.len()],
+                    ));
+                    rows.last_mut().unwrap()
+                }
+            };
+            row.2[idx] += total;
+        }
+    }
+    rows.retain(|(_, _, minutes)| minutes.iter().any(|minutes| *minutes > 0));
+    rows
+}
+
+/// Write the hours of the given months with one column per month. Returns the number of staff members.
+/// Staff members without work in all of the months are left out.
+pub fn write(
+    path: &Path,
+    settings: &Settings,
+    months: &[(Date<Local>, StaffHours)],
+) -> Result<usize, StechuhrError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut wtr = csv::WriterBuilder::new()
+        // Use Tab as delimiter so that Excel automatically imports it correctly.
+        .delimiter(b'\t')
+        .from_path(path)?;
+
+    let hours = |minutes: i64| settings.format_export_decimal(minutes as f64 / 60.0, 2);
+    let mut header = vec![String::from("Name"), String::from("Personalnummer")];
+    header.extend(
+        months
+            .iter()
+            .map(|(month, _)| format!("Stunden {}", month.format("%m/%Y"))),
+    );
+    header.push(String::from("Stunden gesamt"));
+    wtr.write_record(&header)?;
+
+    let rows = rows(settings, months);
+    for (name, personnel_number, minutes) in &rows {
+        let mut record = vec![name.clone(), personnel_number.clone().unwrap_or_default()];
+        record.extend(minutes.iter().map(|minutes| hours(*minutes)));
+        record.push(hours(minutes.iter().sum()));
+        wtr.write_record(&record)?;
+    }
+    let totals = (0..months.len())
+        .map(|idx| rows.iter().map(|(_, _, minutes)| minutes[idx]).sum::<i64>())
+        .collect::<Vec<_>>();
+    let mut record = vec![String::from("Summe"), String::new()];
+    record.extend(totals.iter().map(|minutes| hours(*minutes)));
+    record.push(hours(totals.iter().sum()));
+    wtr.write_record(&record)?;
+    wtr.flush()?;
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn write_months() {
+        let hours = |name: &str, minutes_1, minutes_2| PersonHoursCSV {
+            minutes_1,
+            minutes_2,
+            ..PersonHoursCSV::for_test(name)
+        };
+        let month = |month, hours_csv| {
+            (
+                Local.ymd(2022, month, 1),
+                StaffHours {
+                    hours_csv,
+                    soft_errors: Vec::new(),
+                },
+            )
+        };
+        let months = vec![
+            month(4, vec![hours("Aaron", 60, 30), hours("Ceeron", 0, 0)]),
+            month(5, vec![hours("Beeron", 45, 0), hours("Aaron", 120, 0)]),
+        ];
+        let path = std::env::temp_dir().join("stechuhr_year_summary_test.tsv");
+
+        let count = write(&path, &Settings::default(), &months).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Ceeron did not work and is left out.
+        assert_eq!(count, 2);
+        assert_eq!(
+            written,
+            "Name\tPersonalnummer\tStunden 04/2022\tStunden 05/2022\tStunden gesamt\n\
+             Aaron\t\t1,50\t2,00\t3,50\n\
+             Beeron\t\t0,00\t0,75\t0,75\n\
+             Summe\t\t1,50\t2,75\t4,25\n"
+        );
+    }
+}
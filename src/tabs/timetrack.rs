@@ -1,28 +1,75 @@
+mod dashboard;
+mod group_sign_in;
+mod pin_change;
+mod quick_admin;
+mod upcoming_actions;
+
 use std::cmp::min;
+use std::fs;
+use std::time::Instant;
 
-use chrono::Locale;
+use chrono::Duration;
 use iced::{
-    alignment::Horizontal, button, keyboard, scrollable, text_input, Alignment, Button, Column,
-    Container, Element, Length, Row, Scrollable, Space, Text,
+    alignment::Horizontal, button, keyboard, scrollable, text_input, window, Alignment, Button,
+    Color, Column, Container, Element, Length, Row, Scrollable, Space, Text,
 };
 use iced_aw::{modal, Card, Modal, TabLabel};
-use iced_native::Event;
+use iced_native::{mouse, touch, Event};
+use stechuhr::event_bus::EventSubscriber;
+use stechuhr::icons::{self, TEXT_SIZE_EMOJI};
 use stechuhr::models::*;
-use stechuhr::{TEXT_SIZE, TEXT_SIZE_BIG};
+use stechuhr::quick_action::{self, QuickActionStep};
+use stechuhr::{
+    disk_space, keyboard_layout, rest_period, write_latency, youth_protection, TEXT_SIZE,
+    TEXT_SIZE_BIG,
+};
+
+use dashboard::{Dashboard, WorkedToday};
+use group_sign_in::GroupSignIn;
+pub use group_sign_in::GroupSignInMessage;
+use pin_change::PinChange;
+pub use pin_change::PinChangeMessage;
+use quick_admin::QuickAdmin;
+pub use quick_admin::QuickAdminMessage;
 
+use crate::tabs::components::{self, OnScreenKey, OnScreenKeyboard};
+use crate::tabs::management::{ManagementError, ManagementRequest};
+use crate::tabs::requests::TabRequest;
 use crate::{Message, SharedData, StechuhrError, Tab, TAB_PADDING};
 
 const PIN_LENGTH: usize = 4;
 const CARDID_LENGTH: usize = 10;
+/// How long a name on the dashboard must be pressed to hide the staff member.
+const LONG_PRESS: std::time::Duration = std::time::Duration::from_millis(1000);
+/// Names of staff members who exceeded a limit of the work time, see `WorkTimeWarnings`.
+const WARNING_COLOR: Color = Color::from_rgb(0.8, 0.4, 0.0);
 
 pub struct TimetrackTab {
     break_input_value: String,
     break_input_uuid: Option<i32>,
+    /* youth labor law and rest period warnings for a staff member who is about to sign in */
+    break_warnings: Vec<String>,
     // widget states
     break_input_state: text_input::State,
     break_modal_state: modal::State<BreakModalState>,
 
+    /* work time of the current business day for the dashboard */
+    worked_today: WorkedToday,
     staff_scroll_state: scrollable::State,
+    name_button_states: Vec<button::State>,
+    /* when the mouse/finger was last pressed, to detect long presses on names */
+    press_started: Option<Instant>,
+    hide_modal_state: modal::State<HideModalState>,
+
+    snapshot_button_state: button::State,
+    pin_change_button_state: button::State,
+    pin_change: PinChange,
+    group_sign_in_button_state: button::State,
+    group_sign_in: GroupSignIn,
+    quick_admin_button_state: button::State,
+    quick_admin: QuickAdmin,
+    end_event_button_state: button::State,
+    end_event_modal_state: modal::State<EndEventModalState>,
 }
 
 #[derive(Default)]
@@ -31,12 +78,45 @@ struct BreakModalState {
     cancel_state: button::State,
 }
 
+#[derive(Default)]
+struct HideModalState {
+    uuid: Option<i32>,
+    name: String,
+    password_value: String,
+    password_state: text_input::State,
+    keyboard: OnScreenKeyboard,
+    confirm_state: button::State,
+    cancel_state: button::State,
+}
+
+#[derive(Default)]
+struct EndEventModalState {
+    word_value: String,
+    word_state: text_input::State,
+    confirm_state: button::State,
+    cancel_state: button::State,
+}
+
 #[derive(Debug, Clone)]
 pub enum TimetrackMessage {
     ChangeBreakInput(String),
     SubmitBreakInput,
     ConfirmSubmitBreakInput,
     CancelSubmitBreakInput,
+    ExportSnapshot,
+    PinChange(PinChangeMessage),
+    GroupSignIn(GroupSignInMessage),
+    QuickAdmin(QuickAdminMessage),
+    RunQuickAction(usize),
+    EndEvent,
+    ChangeEndEventWord(String),
+    ConfirmEndEvent,
+    CancelEndEvent,
+    PressName(i32),
+    ChangeHidePassword(String),
+    HidePasswordKey(OnScreenKey),
+    ConfirmHide,
+    CancelHide,
     HandleEvent(Event),
 }
 
@@ -45,9 +125,335 @@ impl TimetrackTab {
         TimetrackTab {
             break_input_value: String::new(),
             break_input_uuid: None,
+            break_warnings: Vec::new(),
             break_input_state: text_input::State::default(),
             break_modal_state: modal::State::default(),
+            worked_today: WorkedToday::default(),
             staff_scroll_state: scrollable::State::default(),
+            name_button_states: Vec::new(),
+            press_started: None,
+            hide_modal_state: modal::State::default(),
+            snapshot_button_state: button::State::default(),
+            pin_change_button_state: button::State::default(),
+            pin_change: PinChange::new(),
+            group_sign_in_button_state: button::State::default(),
+            group_sign_in: GroupSignIn::new(),
+            quick_admin_button_state: button::State::default(),
+            quick_admin: QuickAdmin::new(),
+            end_event_button_state: button::State::default(),
+            end_event_modal_state: modal::State::default(),
+        }
+    }
+
+    /// Buttons emit their message when released, so we compare with the time the press started.
+    fn handle_press_name(&mut self, shared: &SharedData, uuid: i32) {
+        let is_long_press = self
+            .press_started
+            .take()
+            .is_some_and(|press_started| press_started.elapsed() >= LONG_PRESS);
+        if !is_long_press {
+            return;
+        }
+
+        if let Some(staff_member) = StaffMember::get_by_uuid(&shared.staff, uuid) {
+            let state = self.hide_modal_state.inner_mut();
+            state.uuid = Some(uuid);
+            state.name = staff_member.shown_name().to_owned();
+            state.password_value.clear();
+            self.hide_modal_state.show(true);
+        }
+    }
+
+    /// Hiding a staff member needs the admin password since the dashboard is accessible to everyone.
+    fn handle_confirm_hide(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.hide_modal_state.inner_mut();
+        let verified = shared.storage.verify_password(state.password_value.trim());
+        state.password_value.clear();
+        if !verified {
+            return Err(ManagementError::InvalidPassword.into());
+        }
+
+        // Hiding goes through the same path as the checkbox in the management tab.
+        if let Some(uuid) = state.uuid.take() {
+            shared.request(TabRequest::Management(ManagementRequest::SetVisible(
+                uuid, false,
+            )));
+        }
+        self.hide_modal_state.show(false);
+        Ok(())
+    }
+
+    fn cancel_hide(&mut self) {
+        let state = self.hide_modal_state.inner_mut();
+        state.uuid = None;
+        state.password_value.clear();
+        self.hide_modal_state.show(false);
+    }
+
+    /// Write a list of everyone who is working right now, e.g. for the evacuation list of fire safety.
+    fn export_snapshot(&self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let now = shared.current_time.naive_local();
+        let working_since = self.worked_today.working_since();
+
+        let mut working = shared
+            .staff
+            .iter()
+            .filter(|staff_member| staff_member.status == WorkStatus::Working)
+            .map(|staff_member| {
+                let since = working_since
+                    .get(&staff_member.uuid())
+                    .map(|since| since.format("%H:%M").to_string())
+                    .unwrap_or_default();
+                (staff_member.name.clone(), since)
+            })
+            .collect::<Vec<_>>();
+        working.sort();
+
+        disk_space::preflight(&shared.settings.export_dir)?;
+        fs::create_dir_all(&shared.settings.export_dir)?;
+        let filename = shared.settings.export_dir.join(format!(
+            "Anwesenheit {}.tsv",
+            now.format("%Y-%m-%d %H-%M-%S")
+        ));
+
+        let mut wtr = csv::WriterBuilder::new()
+            // Use Tab as delimiter so that Excel automatically imports it correctly.
+            .delimiter(b'\t')
+            .flexible(true)
+            .from_path(&filename)?;
+        wtr.write_record([
+            String::from("Anwesend am"),
+            format!(
+                "{} {}",
+                shared.settings.format_export_date(now.date()),
+                now.format("%H:%M:%S")
+            ),
+        ])?;
+        wtr.write_record(["Name", "Arbeitet seit"])?;
+        for (name, since) in &working {
+            wtr.write_record([name, since])?;
+        }
+        wtr.write_record([String::from("Gesamt"), working.len().to_string()])?;
+        wtr.flush()?;
+
+        shared.log_info(format!(
+            "Anwesenheitsliste mit {} Personen exportiert.",
+            working.len()
+        ));
+        opener::open(filename)?;
+        Ok(())
+    }
+
+    /// Run the steps of a custom quick action from the settings file one after another, see `quick_action`.
+    /// A failing step stops the action, the steps before it stay done.
+    fn run_quick_action(
+        &mut self,
+        shared: &mut SharedData,
+        idx: usize,
+    ) -> Result<(), StechuhrError> {
+        self.quick_admin.ensure_unlocked(shared)?;
+        let action = match shared.settings.quick_actions.get(idx) {
+            Some(action) => action.clone(),
+            None => return Ok(()),
+        };
+
+        for step in &action.steps {
+            match step {
+                QuickActionStep::SignOff { department } => {
+                    for uuid in quick_action::working_in(&shared.staff, department.as_deref()) {
+                        QuickAdmin::correct_status(
+                            shared,
+                            uuid,
+                            WorkStatus::Away,
+                            format!(
+                                "Wurde an der Stechuhr mit der Aktion \"{}\" abgemeldet.",
+                                action.label
+                            ),
+                        );
+                    }
+                }
+                QuickActionStep::Marker { text } => {
+                    shared.create_event(WorkEvent::Marker(text.clone()))
+                }
+                QuickActionStep::ExportSnapshot => {
+                    shared.window_mode = window::Mode::Windowed;
+                    self.export_snapshot(shared)?;
+                }
+            }
+        }
+        shared.log_info(format!("Aktion \"{}\" ausgeführt.", action.label));
+        Ok(())
+    }
+
+    /// Ending the event signs off everyone who is still working.
+    fn handle_confirm_end_event(&mut self, shared: &mut SharedData) {
+        let word = &shared.settings.end_event_word;
+        if !word.is_empty() && self.end_event_modal_state.inner().word_value.trim() != word {
+            return;
+        }
+
+        let sign_off_events = shared.sign_off_all_staff(shared.current_time.naive_local());
+        let count = sign_off_events.len();
+        for new_eventt in sign_off_events {
+            shared.log_eventt(new_eventt);
+        }
+        shared.log_info(format!("Event beendet, {} Personen abgemeldet.", count));
+
+        self.cancel_end_event();
+    }
+
+    fn cancel_end_event(&mut self) {
+        self.end_event_modal_state.inner_mut().word_value.clear();
+        self.end_event_modal_state.show(false);
+    }
+
+    /// Describe the consequences of ending the event so that nobody does it by accident.
+    fn end_event_description(shared: &SharedData) -> String {
+        let working = shared
+            .staff
+            .iter()
+            .filter(|staff_member| staff_member.status == WorkStatus::Working)
+            .map(StaffMember::shown_name)
+            .collect::<Vec<_>>();
+
+        if working.is_empty() {
+            String::from("Es ist niemand am Arbeiten, es wird niemand abgemeldet.")
+        } else {
+            format!(
+                "{} Personen werden um {} abgemeldet: {}",
+                working.len(),
+                shared
+                    .current_time
+                    .format_localized("%T", shared.settings.locale()),
+                working.join(", ")
+            )
+        }
+    }
+
+    /// A dongle ID only consists of digits. If the card reader sends other characters it probably uses a different
+    /// keyboard layout than the terminal, which we either correct or report.
+    fn guard_keyboard_layout(
+        &mut self,
+        shared: &mut SharedData,
+        input: String,
+    ) -> Result<String, StechuhrError> {
+        if input.chars().count() != CARDID_LENGTH || input.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(input);
+        }
+
+        match keyboard_layout::translate(&input) {
+            Some((layout, digits)) if shared.settings.kiosk.correct_keyboard_layout => {
+                log::warn!(
+                    "Dongle-Eingabe \"{}\" mit Tastaturbelegung {} zu \"{}\" korrigiert",
+                    input,
+                    layout,
+                    digits
+                );
+                Ok(digits)
+            }
+            Some((layout, _)) => {
+                self.break_input_value.clear();
+                Err(StechuhrError::Str(format!(
+                    "Tastaturlayout prüfen: Der Dongle-Leser sendet \"{}\" statt Ziffern, er verwendet vermutlich die Tastaturbelegung {}.",
+                    input, layout
+                )))
+            }
+            None => {
+                self.break_input_value.clear();
+                Err(StechuhrError::Str(format!(
+                    "Tastaturlayout prüfen: Die Dongle-ID \"{}\" enthält Zeichen, die keine Ziffern sind.",
+                    input
+                )))
+            }
+        }
+    }
+
+    /// Check the minimum rest and, for a minor, the youth labor law limits for a staff member who is about to sign in.
+    fn sign_in_warnings(shared: &mut SharedData, staff_member: &StaffMember) -> Vec<String> {
+        if staff_member.status == WorkStatus::Working {
+            return Vec::new();
+        }
+
+        let now = shared.current_time.naive_local();
+        let cutoff = shared.settings.cutoff_time;
+        let mut warnings = Vec::new();
+        if shared.settings.min_rest_hours > 0 {
+            let min_rest = Duration::hours(i64::from(shared.settings.min_rest_hours));
+            // A business day ends at the cutoff, so its intervals started at most a day before the rest.
+            let events = shared.storage.load_staff_events_between(
+                staff_member.uuid(),
+                now - min_rest - Duration::days(1),
+                now,
+            );
+            let intervals = youth_protection::work_intervals(&events, staff_member.uuid(), now);
+            warnings.extend(
+                rest_period::sign_in_warning(&intervals, now, cutoff, min_rest)
+                    .map(|violation| format!("Ruhezeit: {}", violation)),
+            );
+        }
+        if staff_member.is_minor {
+            let events = shared.storage.load_staff_events_between(
+                staff_member.uuid(),
+                youth_protection::week_start_time(now, cutoff),
+                now,
+            );
+            let intervals = youth_protection::work_intervals(&events, staff_member.uuid(), now);
+            warnings.extend(
+                youth_protection::sign_in_warnings(&intervals, now, cutoff)
+                    .iter()
+                    .map(|violation| format!("Jugendschutz: {}", violation)),
+            );
+        }
+        warnings
+    }
+
+    /// Minors must not work after midnight, so they cannot sign in then, see `youth_protection::refuse_sign_in`.
+    fn guard_youth_protection(
+        shared: &SharedData,
+        staff_member: &StaffMember,
+    ) -> Result<(), StechuhrError> {
+        if !staff_member.is_minor || staff_member.status == WorkStatus::Working {
+            return Ok(());
+        }
+        match youth_protection::refuse_sign_in(shared.current_time.naive_local()) {
+            Some(violation) => Err(StechuhrError::Str(format!(
+                "Jugendschutz: {} kann nicht angemeldet werden. {}",
+                staff_member.shown_name(),
+                violation
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// A second swipe right after a status change is usually a dongle swiped twice by accident. It would create an
+    /// interval shorter than `min_interval_seconds`, which the evaluation does not count anyway, so it is refused.
+    fn guard_double_swipe(
+        shared: &mut SharedData,
+        staff_member: &StaffMember,
+    ) -> Result<(), StechuhrError> {
+        let min_interval_seconds = shared.settings.min_interval_seconds;
+        if min_interval_seconds == 0 {
+            return Ok(());
+        }
+        let now = shared.current_time.naive_local();
+        let min_interval = Duration::seconds(i64::from(min_interval_seconds));
+        // Events are created with the wall clock, which can be a bit ahead of the last tick.
+        let events = shared.storage.load_staff_events_between(
+            staff_member.uuid(),
+            now - min_interval,
+            now + min_interval,
+        );
+        let changed_recently = events
+            .iter()
+            .any(|eventt| matches!(eventt.event, WorkEvent::StatusChange(..)));
+        if changed_recently {
+            Err(StechuhrError::Str(format!(
+                "{} wurde gerade erst an- oder abgemeldet. Eine weitere Statusänderung ist erst nach {} Sekunden möglich.",
+                staff_member.shown_name(),
+                min_interval_seconds
+            )))
+        } else {
+            Ok(())
         }
     }
 
@@ -58,32 +464,80 @@ impl TimetrackTab {
             let name = staff_member.name.clone();
             let new_status = staff_member.status.toggle();
             staff_member.status = new_status;
-            shared.create_event(WorkEvent::StatusChange(break_uuid, name, new_status));
+            shared.create_event(WorkEvent::StatusChange(
+                break_uuid,
+                String::new(),
+                new_status,
+            ));
+            // Keep a record that the sign-in happened despite the warnings.
+            for warning in self.break_warnings.drain(..) {
+                shared.log_info(format!("{} trotz Warnung angemeldet. {}", name, warning));
+            }
             self.break_modal_state.show(false);
             self.break_input_uuid = None;
             self.break_input_value.clear();
+            if new_status == WorkStatus::Working {
+                self.quick_admin.suggest_sign_off(shared, break_uuid);
+            }
         }
     }
 
+    /// Explain the status symbols below the clock.
+    fn get_legend(colorblind: bool) -> Row<'static, TimetrackMessage> {
+        DashboardStatus::ALL
+            .iter()
+            .fold(Row::new().spacing(30), |legend, status| {
+                legend.push(
+                    Row::new()
+                        .push(status.to_icon(colorblind))
+                        .push(Text::new(status.to_string()))
+                        .spacing(5)
+                        .align_items(Alignment::Center),
+                )
+            })
+            .align_items(Alignment::Center)
+    }
+
     /// Generate a column of names and icons signalling their work status.
-    /// Have to annotate return type as 'static, else it takes the argument's lifetime
-    fn get_staff_column(staff: &[&StaffMember]) -> Element<'static, TimetrackMessage> {
+    /// Names are buttons so that they can be long-pressed to hide the staff member.
+    fn get_staff_column<'a>(
+        staff: &[&StaffMember],
+        button_states: &'a mut [button::State],
+        dashboard: &Dashboard,
+        colorblind: bool,
+    ) -> Element<'a, TimetrackMessage> {
         let names = Column::new()
             .width(Length::FillPortion(80))
             .spacing(10)
             .align_items(Alignment::End);
 
-        let names = staff.iter().fold(names, |names, staff_member| {
-            let icon = staff_member.status.to_unicode();
-
-            let name = Text::new(format!(
-                "{}: {}",
-                staff_member.name,
-                staff_member.status.to_string()
-            ))
+        let staff = staff.iter().zip(button_states.iter_mut());
+        let names = staff.fold(names, |names, (staff_member, button_state)| {
+            let icon = dashboard.status(staff_member).to_icon(colorblind);
+            let warning = dashboard.warning(staff_member);
+            let mut name = Text::new(match warning {
+                Some(warning) => format!("{} – {}", dashboard.label(staff_member), warning),
+                None => dashboard.label(staff_member),
+            })
             .size(TEXT_SIZE);
+            if warning.is_some() {
+                name = name.color(WARNING_COLOR);
+            }
+            let name = Button::new(button_state, name)
+                .padding(0)
+                .style(stechuhr::style::NameButtonStyle)
+                .on_press(TimetrackMessage::PressName(staff_member.uuid()));
 
-            let r = Row::new()
+            let mut r = Row::new();
+            if warning.is_some() {
+                // the symbol keeps the warning visible without colors
+                r = r.push(icons::icon(
+                    icons::emoji::warning
+                        .with_color(Some(WARNING_COLOR))
+                        .with_size(TEXT_SIZE_EMOJI + 4),
+                ));
+            }
+            let r = r
                 .push(name)
                 .push(icon)
                 .spacing(10)
@@ -100,16 +554,22 @@ impl TimetrackTab {
     }
 
     /// Generate the timetrack dashboard composed of columns of names and icons signalling their work status.
-    /// Have to annotate return type as 'static, else it takes the argument's lifetime
-    fn get_staff_view(staff: &[StaffMember]) -> Container<'static, TimetrackMessage> {
+    fn get_staff_view<'a>(
+        staff: &[StaffMember],
+        button_states: &'a mut Vec<button::State>,
+        dashboard: &Dashboard,
+        columns: usize,
+        colorblind: bool,
+    ) -> Container<'a, TimetrackMessage> {
         let staff = staff
             .iter()
             .filter(|staff_member| staff_member.is_visible)
             .collect::<Vec<_>>();
+        button_states.resize_with(staff.len(), button::State::default);
+        let mut button_states = &mut button_states[..];
 
-        const COLUMNS: usize = 3;
-        let column_size = staff.len() / COLUMNS;
-        let mut extra = staff.len() % COLUMNS;
+        let column_size = staff.len() / columns;
+        let mut extra = staff.len() % columns;
 
         let padding1 = Space::new(Length::Shrink, Length::Shrink);
         let padding2 = Space::new(Length::FillPortion(5), Length::Shrink);
@@ -117,7 +577,7 @@ impl TimetrackTab {
         let mut staff_view = Row::new().spacing(10).push(padding1);
         let mut start = 0;
 
-        for _ in 0..COLUMNS {
+        for _ in 0..columns {
             let end = start
                 + column_size
                 + if extra > 0 {
@@ -127,7 +587,14 @@ impl TimetrackTab {
                     0
                 };
             let end = min(staff.len(), end);
-            let staff_column = TimetrackTab::get_staff_column(&staff[start..end]);
+            let (column_states, rest) = button_states.split_at_mut(end - start);
+            button_states = rest;
+            let staff_column = TimetrackTab::get_staff_column(
+                &staff[start..end],
+                column_states,
+                dashboard,
+                colorblind,
+            );
             staff_view = staff_view.push(staff_column);
 
             start = end;
@@ -138,6 +605,18 @@ impl TimetrackTab {
 
 impl SharedData {}
 
+impl EventSubscriber for TimetrackTab {
+    fn on_event(&mut self, eventt: &WorkEventT) {
+        self.worked_today.on_event(eventt);
+        self.quick_admin.on_event(eventt);
+    }
+
+    fn on_new_business_day(&mut self) {
+        self.worked_today.on_new_business_day();
+        self.quick_admin.on_new_business_day();
+    }
+}
+
 impl Tab for TimetrackTab {
     type Message = TimetrackMessage;
 
@@ -152,7 +631,14 @@ impl Tab for TimetrackTab {
     fn content(&mut self, shared: &mut SharedData) -> Element<'_, Message> {
         /* Normally the textinput must be focussed so that we can just swipe a rfid tag anytime.
          * But when the modal is open, we must unfocus, else it will capture an 'enter' press meant to close the modal that should be handled in the subcriptions in main.rs */
-        if self.break_modal_state.is_shown() || shared.prompt_modal_state.is_shown() {
+        if self.break_modal_state.is_shown()
+            || self.end_event_modal_state.is_shown()
+            || self.hide_modal_state.is_shown()
+            || self.pin_change.is_shown()
+            || self.group_sign_in.is_shown()
+            || self.quick_admin.is_shown()
+            || shared.prompt_modal_state.is_shown()
+        {
             self.break_input_state.unfocus();
         } else {
             self.break_input_state.focus();
@@ -162,13 +648,112 @@ impl Tab for TimetrackTab {
         let clock = Text::new(
             shared
                 .current_time
-                .format_localized("%A, %e. %B - %T", Locale::de_DE)
+                .format_localized("%A, %e. %B - %T", shared.settings.locale())
                 .to_string(),
         )
         .horizontal_alignment(Horizontal::Center)
         .size(TEXT_SIZE_BIG);
 
-        let staff_view = TimetrackTab::get_staff_view(&shared.staff);
+        // daily totals of the current business day
+        let dashboard = Dashboard::new(
+            &shared.staff,
+            &self.worked_today,
+            &shared.shifts,
+            &shared.settings.work_time_warnings,
+            shared.current_time.naive_local(),
+        );
+        let totals = Text::new(format!(
+            "Heute gearbeitet: {}",
+            Dashboard::format_duration(dashboard.total)
+        ))
+        .size(TEXT_SIZE);
+
+        // big counter of working staff so that the door lead can call in backups early
+        let mut counter = Text::new(dashboard.counter_label()).size(TEXT_SIZE_BIG);
+        if dashboard.is_understaffed() {
+            counter = counter.color(Color::from_rgb8(200, 0, 0));
+        }
+        let header = Row::new()
+            .spacing(40)
+            .align_items(Alignment::Center)
+            .push(counter)
+            .push(totals);
+
+        let legend = TimetrackTab::get_legend(shared.settings.kiosk.colorblind_icons);
+
+        // stays until the reader is plugged in again, see `Stechuhr::check_reader`
+        let reader_warning = shared.reader_missing.then(|| {
+            Text::new("Kartenleser nicht verbunden, Dongles werden nicht erkannt. Bitte das USB-Kabel prüfen.")
+                .size(TEXT_SIZE)
+                .color(Color::from_rgb8(200, 0, 0))
+        });
+
+        // set by an admin in the management tab
+        let now = shared.current_time.naive_local();
+        let announcement = shared
+            .announcement
+            .as_ref()
+            .filter(|announcement| announcement.is_shown(now))
+            .map(|announcement| {
+                Text::new(announcement.text.as_str())
+                    .size(TEXT_SIZE)
+                    .color(Color::from_rgb8(0, 90, 160))
+            });
+
+        // stays until space is freed, see `Stechuhr::check_disk_space`
+        let disk_space_warning = shared.disk_space_low.map(|available| {
+            Text::new(format!(
+                "Nur noch {} auf dem Datenträger frei, Exporte können fehlschlagen. Bitte Platz schaffen.",
+                disk_space::format_bytes(available)
+            ))
+            .size(TEXT_SIZE)
+            .color(Color::from_rgb8(200, 0, 0))
+        });
+
+        // shown for some seconds after a slow write, see `Stechuhr::check_write_latency`
+        let slow_write_warning = shared.slow_write.map(|(_, duration)| {
+            let is_warning =
+                duration.as_millis() >= u128::from(shared.settings.slow_write_warning_ms);
+            Text::new(if is_warning {
+                format!(
+                    "Die Datenbank ist langsam: Speichern dauerte {}. Bitte die Verbindung prüfen.",
+                    write_latency::format_duration(duration)
+                )
+            } else {
+                format!(
+                    "Speichern dauerte {}",
+                    write_latency::format_duration(duration)
+                )
+            })
+            .size(TEXT_SIZE)
+            .color(if is_warning {
+                Color::from_rgb8(200, 0, 0)
+            } else {
+                Color::from_rgb8(120, 120, 120)
+            })
+        });
+
+        // announce what happens on its own in the next hours, e.g. the sign-off at the end of the business day
+        let upcoming =
+            upcoming_actions::upcoming(&shared.settings, shared.current_time.naive_local());
+        let upcoming_banner = (!upcoming.is_empty()).then(|| {
+            Text::new(
+                upcoming
+                    .iter()
+                    .map(|action| action.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" · "),
+            )
+            .color(Color::from_rgb8(0, 90, 160))
+        });
+
+        let staff_view = TimetrackTab::get_staff_view(
+            &shared.staff,
+            &mut self.name_button_states,
+            &dashboard,
+            shared.settings.kiosk.dashboard_columns,
+            shared.settings.kiosk.colorblind_icons,
+        );
         let staff_view =
             Scrollable::new(&mut self.staff_scroll_state).push(staff_view.height(Length::Shrink));
 
@@ -182,54 +767,198 @@ impl Tab for TimetrackTab {
         .size(TEXT_SIZE)
         .width(Length::Units(300));
 
+        let end_event_button = Button::new(
+            &mut self.end_event_button_state,
+            Text::new("Event beenden").horizontal_alignment(Horizontal::Center),
+        )
+        .on_press(TimetrackMessage::EndEvent);
+
+        let pin_change_button = Button::new(
+            &mut self.pin_change_button_state,
+            Text::new("PIN ändern").horizontal_alignment(Horizontal::Center),
+        )
+        .on_press(TimetrackMessage::PinChange(PinChangeMessage::Open));
+
+        let group_sign_in_button = Button::new(
+            &mut self.group_sign_in_button_state,
+            Text::new("Gruppe anmelden").horizontal_alignment(Horizontal::Center),
+        )
+        .on_press(TimetrackMessage::GroupSignIn(GroupSignInMessage::Open));
+
+        let quick_admin_button = Button::new(
+            &mut self.quick_admin_button_state,
+            Text::new("Admin").horizontal_alignment(Horizontal::Center),
+        )
+        .on_press(TimetrackMessage::QuickAdmin(QuickAdminMessage::Open));
+
+        let snapshot_button = Button::new(
+            &mut self.snapshot_button_state,
+            Text::new("Anwesenheitsliste").horizontal_alignment(Horizontal::Center),
+        )
+        .on_press(TimetrackMessage::ExportSnapshot);
+
         let content = Column::new()
             .align_items(Alignment::Center)
             .width(Length::Fill)
             .padding(TAB_PADDING)
             .spacing(10)
-            .push(clock.height(Length::FillPortion(10)))
+            .push(clock.height(Length::FillPortion(10)));
+        let content = match announcement {
+            Some(announcement) => content.push(announcement),
+            None => content,
+        };
+        let content = match reader_warning {
+            Some(reader_warning) => content.push(reader_warning),
+            None => content,
+        };
+        let content = match disk_space_warning {
+            Some(disk_space_warning) => content.push(disk_space_warning),
+            None => content,
+        };
+        let content = match slow_write_warning {
+            Some(slow_write_warning) => content.push(slow_write_warning),
+            None => content,
+        };
+        let content = match upcoming_banner {
+            Some(upcoming_banner) => content.push(upcoming_banner),
+            None => content,
+        };
+        let content = content
+            .push(header)
+            .push(legend)
             .push(staff_view.height(Length::FillPortion(70)))
-            .push(dongle_input);
+            .push(
+                Row::new()
+                    .push(Container::new(snapshot_button).width(Length::Units(200)))
+                    .push(Container::new(pin_change_button).width(Length::Units(150)))
+                    .push(Container::new(group_sign_in_button).width(Length::Units(200)))
+                    .push(Container::new(quick_admin_button).width(Length::Units(100)))
+                    .push(dongle_input)
+                    .push(
+                        Container::new(end_event_button)
+                            .width(Length::Units(200))
+                            .align_x(Horizontal::Right),
+                    )
+                    .spacing(20)
+                    .align_items(Alignment::Center),
+            );
+
+        let end_event_description = TimetrackTab::end_event_description(shared);
+        let end_event_word = shared.settings.end_event_word.clone();
+        let end_event_modal =
+            Modal::new(&mut self.end_event_modal_state, content, move |state| {
+                let word_matches =
+                    end_event_word.is_empty() || state.word_value.trim() == end_event_word;
+
+                let mut body = Column::new()
+                    .spacing(10)
+                    .push(Text::new(end_event_description.clone()))
+                    .push(Text::new(
+                        "Danach sind alle Personen auf \"Pause\" gesetzt. Das kann nicht rückgängig gemacht werden.",
+                    ));
+                if !end_event_word.is_empty() {
+                    state.word_state.focus();
+                    body = body.push(
+                        stechuhr::style::text_input(
+                            &mut state.word_state,
+                            &format!("Zum Bestätigen \"{}\" eingeben", end_event_word),
+                            &state.word_value,
+                            TimetrackMessage::ChangeEndEventWord,
+                        )
+                        .on_submit(TimetrackMessage::ConfirmEndEvent),
+                    );
+                }
+
+                Card::new(Text::new("Event beenden"), body)
+                    .foot(components::confirm_foot(
+                        &mut state.confirm_state,
+                        "Event beenden",
+                        word_matches.then_some(TimetrackMessage::ConfirmEndEvent),
+                        &mut state.cancel_state,
+                        "Zurück",
+                        TimetrackMessage::CancelEndEvent,
+                    ))
+                    .max_width(600)
+                    .on_close(TimetrackMessage::CancelEndEvent)
+                    .into()
+            })
+            .backdrop(TimetrackMessage::CancelEndEvent)
+            .on_esc(TimetrackMessage::CancelEndEvent);
 
         let break_modal_value = if let Some(break_uuid) = self.break_input_uuid {
             let staff_member = StaffMember::get_by_uuid_mut(&mut shared.staff, break_uuid)
                 .expect("uuid does not yield a staff member");
-            format!(
+            let mut value = format!(
                 "{} wird auf '{}' gesetzt. Korrekt?",
-                staff_member.name,
+                staff_member.shown_name(),
                 staff_member.status.toggle()
-            )
+            );
+            for warning in &self.break_warnings {
+                value.push('\n');
+                value.push_str(warning);
+            }
+            value
         } else {
             String::from("Warnung: kein Mitarbeiter ausgewählt. Bitte Adrian Bescheid geben.")
         };
 
-        let modal = Modal::new(&mut self.break_modal_state, content, move |state| {
-            Card::new(
-                Text::new("Änderung des Arbeitsstatus"),
-                Text::new(break_modal_value.clone()),
-            )
-            .foot(
-                Row::new()
+        let quick_admin_modal = self.quick_admin.view(shared, end_event_modal.into());
+        let pin_change_modal = self.pin_change.view(quick_admin_modal);
+        let pin_change_modal = self.group_sign_in.view(pin_change_modal);
+
+        let on_screen_keyboard = shared.settings.kiosk.on_screen_keyboard;
+        let hide_modal = Modal::new(&mut self.hide_modal_state, pin_change_modal, move |state| {
+            state.password_state.focus();
+
+            let mut body = Column::new()
                     .spacing(10)
-                    .padding(5)
-                    .width(Length::Fill)
-                    .push(
-                        Button::new(
-                            &mut state.confirm_state,
-                            Text::new("Ok").horizontal_alignment(Horizontal::Center),
-                        )
-                        .width(Length::Shrink)
-                        .on_press(TimetrackMessage::ConfirmSubmitBreakInput),
-                    )
+                    .push(Text::new(format!(
+                        "{} wird auf der Stechuhr nicht mehr angezeigt. In der Verwaltung kann die Person wieder eingeblendet werden.",
+                        state.name
+                    )))
                     .push(
-                        Button::new(
-                            &mut state.cancel_state,
-                            Text::new("Zurück").horizontal_alignment(Horizontal::Center),
+                        stechuhr::style::text_input(
+                            &mut state.password_state,
+                            "Admin-Passwort",
+                            &state.password_value,
+                            TimetrackMessage::ChangeHidePassword,
                         )
-                        .width(Length::Shrink)
-                        .on_press(TimetrackMessage::CancelSubmitBreakInput),
-                    ),
+                        .password()
+                        .on_submit(TimetrackMessage::ConfirmHide),
+                    );
+            if on_screen_keyboard {
+                body = body.push(state.keyboard.view(TimetrackMessage::HidePasswordKey));
+            }
+
+            Card::new(Text::new("Person ausblenden"), body)
+            .foot(components::confirm_foot(
+                &mut state.confirm_state,
+                "Ausblenden",
+                Some(TimetrackMessage::ConfirmHide),
+                &mut state.cancel_state,
+                "Zurück",
+                TimetrackMessage::CancelHide,
+            ))
+            .max_width(600)
+            .on_close(TimetrackMessage::CancelHide)
+            .into()
+        })
+        .backdrop(TimetrackMessage::CancelHide)
+        .on_esc(TimetrackMessage::CancelHide);
+
+        let modal = Modal::new(&mut self.break_modal_state, hide_modal, move |state| {
+            Card::new(
+                Text::new("Änderung des Arbeitsstatus"),
+                Text::new(break_modal_value.clone()),
             )
+            .foot(components::confirm_foot(
+                &mut state.confirm_state,
+                "Ok",
+                Some(TimetrackMessage::ConfirmSubmitBreakInput),
+                &mut state.cancel_state,
+                "Zurück",
+                TimetrackMessage::CancelSubmitBreakInput,
+            ))
             // .max_width(300)
             .width(Length::Shrink)
             .on_close(TimetrackMessage::CancelSubmitBreakInput)
@@ -253,12 +982,28 @@ impl Tab for TimetrackTab {
             }
             TimetrackMessage::SubmitBreakInput => {
                 let input = self.break_input_value.trim().to_owned();
+                let input = self.guard_keyboard_layout(shared, input)?;
 
-                if input.len() == PIN_LENGTH || input.len() == CARDID_LENGTH {
+                let personnel_number_login = shared.settings.kiosk.personnel_number_login;
+                if input.len() == PIN_LENGTH
+                    || input.len() == CARDID_LENGTH
+                    || personnel_number_login
+                {
                     if let Some(staff_member) =
-                        StaffMember::get_by_pin_or_card_id(&shared.staff, &input)
+                        StaffMember::get_by_ident(&shared.staff, &input, personnel_number_login)
                     {
                         if staff_member.is_visible {
+                            let staff_member = staff_member.clone();
+                            if let Err(e) = TimetrackTab::guard_double_swipe(shared, &staff_member)
+                                .and_then(|()| {
+                                    TimetrackTab::guard_youth_protection(shared, &staff_member)
+                                })
+                            {
+                                self.break_input_value.clear();
+                                return Err(e);
+                            }
+                            self.break_warnings =
+                                TimetrackTab::sign_in_warnings(shared, &staff_member);
                             self.break_modal_state.show(true);
                             self.break_input_uuid = Some(staff_member.uuid());
                         } else {
@@ -269,7 +1014,13 @@ impl Tab for TimetrackTab {
                         }
                     } else {
                         self.break_input_value.clear();
-                        return Err(StechuhrError::Str(String::from("Unbekannte PIN/Dongle")));
+                        return Err(StechuhrError::Str(String::from(
+                            if personnel_number_login {
+                                "Unbekannte PIN/Dongle/Personalnummer"
+                            } else {
+                                "Unbekannte PIN/Dongle"
+                            },
+                        )));
                     }
                 } else {
                     self.break_input_value.clear();
@@ -283,10 +1034,44 @@ impl Tab for TimetrackTab {
                 self.handle_confirm_submit_break_input(shared)
             }
             TimetrackMessage::CancelSubmitBreakInput => {
+                self.break_warnings.clear();
                 self.break_modal_state.show(false);
                 self.break_input_uuid = None;
                 self.break_input_value.clear();
             }
+            TimetrackMessage::ExportSnapshot => {
+                // Set windowed so that the list can be printed.
+                shared.window_mode = window::Mode::Windowed;
+                self.export_snapshot(shared)?;
+            }
+            TimetrackMessage::PinChange(message) => self.pin_change.update(shared, message)?,
+            TimetrackMessage::GroupSignIn(message) => self.group_sign_in.update(shared, message)?,
+            TimetrackMessage::QuickAdmin(message) => self.quick_admin.update(shared, message)?,
+            TimetrackMessage::RunQuickAction(idx) => self.run_quick_action(shared, idx)?,
+            TimetrackMessage::EndEvent => {
+                self.end_event_modal_state.show(true);
+            }
+            TimetrackMessage::ChangeEndEventWord(value) => {
+                self.end_event_modal_state.inner_mut().word_value = value;
+            }
+            TimetrackMessage::ConfirmEndEvent => self.handle_confirm_end_event(shared),
+            TimetrackMessage::CancelEndEvent => self.cancel_end_event(),
+            TimetrackMessage::PressName(uuid) => self.handle_press_name(shared, uuid),
+            TimetrackMessage::ChangeHidePassword(value) => {
+                self.hide_modal_state.inner_mut().password_value = value;
+            }
+            TimetrackMessage::HidePasswordKey(key) => {
+                let state = self.hide_modal_state.inner_mut();
+                if state.keyboard.press(key, &mut state.password_value) {
+                    self.handle_confirm_hide(shared)?;
+                }
+            }
+            TimetrackMessage::ConfirmHide => self.handle_confirm_hide(shared)?,
+            TimetrackMessage::CancelHide => self.cancel_hide(),
+            TimetrackMessage::HandleEvent(
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                | Event::Touch(touch::Event::FingerPressed { .. }),
+            ) => self.press_started = Some(Instant::now()),
             TimetrackMessage::HandleEvent(Event::Keyboard(keyboard::Event::KeyPressed {
                 key_code: keyboard::KeyCode::Enter,
                 ..
@@ -0,0 +1,387 @@
+//! View model of the dashboard. It is computed from the shared data without any widgets, so it can be tested on its own.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{Duration, NaiveDateTime};
+use stechuhr::event_bus::EventSubscriber;
+use stechuhr::models::*;
+use stechuhr::settings::WorkTimeWarnings;
+
+/// Work intervals of the current business day, kept up to date by the event bus.
+#[derive(Debug, Default)]
+pub struct WorkedToday {
+    /// Sum of the finished intervals of each staff member.
+    finished: HashMap<i32, Duration>,
+    /// Since when each staff member that is currently working is working.
+    working_since: HashMap<i32, NaiveDateTime>,
+}
+
+impl WorkedToday {
+    pub fn working_since(&self) -> &HashMap<i32, NaiveDateTime> {
+        &self.working_since
+    }
+
+    /// How long each staff member has worked during the current business day.
+    fn worked(&self, now: NaiveDateTime) -> HashMap<i32, Duration> {
+        let mut worked = self.finished.clone();
+        for (uuid, start) in &self.working_since {
+            let total = worked.entry(*uuid).or_insert_with(Duration::zero);
+            *total = *total + (now - *start);
+        }
+        worked
+    }
+
+    /// The exceeded limits of each staff member, the continuous work first since it needs a break the soonest.
+    fn warnings(
+        &self,
+        worked: &HashMap<i32, Duration>,
+        limits: &WorkTimeWarnings,
+        now: NaiveDateTime,
+    ) -> HashMap<i32, WorkTimeWarning> {
+        let exceeds = |duration: Duration, minutes: u32| {
+            minutes > 0 && duration >= Duration::minutes(i64::from(minutes))
+        };
+
+        let mut warnings = worked
+            .iter()
+            .filter(|(_, duration)| exceeds(**duration, limits.daily_minutes))
+            .map(|(uuid, duration)| (*uuid, WorkTimeWarning::Daily(*duration)))
+            .collect::<HashMap<_, _>>();
+        for (uuid, start) in &self.working_since {
+            let continuous = now - *start;
+            if exceeds(continuous, limits.continuous_minutes) {
+                warnings.insert(*uuid, WorkTimeWarning::Continuous(continuous));
+            }
+        }
+        warnings
+    }
+}
+
+/// A limit of `WorkTimeWarnings` that a staff member has exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkTimeWarning {
+    /// Working for the given time since the last sign-in.
+    Continuous(Duration),
+    /// Worked for the given time during the current business day.
+    Daily(Duration),
+}
+
+impl fmt::Display for WorkTimeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkTimeWarning::Continuous(duration) => {
+                write!(f, "{} am Stück", Dashboard::format_duration(*duration))
+            }
+            WorkTimeWarning::Daily(duration) => {
+                write!(f, "{} heute", Dashboard::format_duration(*duration))
+            }
+        }
+    }
+}
+
+impl EventSubscriber for WorkedToday {
+    fn on_event(&mut self, eventt: &WorkEventT) {
+        match eventt.event {
+            WorkEvent::StatusChange(uuid, _, WorkStatus::Working) => {
+                self.working_since.entry(uuid).or_insert(eventt.created_at);
+            }
+            WorkEvent::StatusChange(uuid, _, WorkStatus::Away) => {
+                if let Some(start) = self.working_since.remove(&uuid) {
+                    let total = self.finished.entry(uuid).or_insert_with(Duration::zero);
+                    *total = *total + (eventt.created_at - start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Only the events of the current business day are counted.
+    fn on_new_business_day(&mut self) {
+        self.finished.clear();
+        self.working_since.clear();
+    }
+}
+
+pub struct Dashboard {
+    /// Worked time of each staff member, see `WorkedToday::worked`.
+    pub worked: HashMap<i32, Duration>,
+    pub statuses: HashMap<i32, DashboardStatus>,
+    /// Staff members who exceeded a limit of the work time.
+    pub warnings: HashMap<i32, WorkTimeWarning>,
+    /// Sum of the work time of everyone during the current business day.
+    pub total: Duration,
+    pub working_count: usize,
+    /// Number of staff members whose shift is running right now. None if there is no shift plan for the current business day.
+    pub planned_count: Option<usize>,
+}
+
+impl Dashboard {
+    /// The shifts must be those of the current business day.
+    pub fn new(
+        staff: &[StaffMember],
+        worked_today: &WorkedToday,
+        shifts: &[Shift],
+        limits: &WorkTimeWarnings,
+        now: NaiveDateTime,
+    ) -> Self {
+        let worked = worked_today.worked(now);
+        let statuses = Dashboard::statuses(staff, &worked, shifts);
+        let warnings = worked_today.warnings(&worked, limits, now);
+        let total = worked
+            .values()
+            .fold(Duration::zero(), |total, duration| total + *duration);
+        let working_count = staff
+            .iter()
+            .filter(|staff_member| staff_member.status == WorkStatus::Working)
+            .count();
+        let planned_count = Dashboard::planned_now(shifts, now);
+
+        Dashboard {
+            worked,
+            statuses,
+            warnings,
+            total,
+            working_count,
+            planned_count,
+        }
+    }
+
+    pub fn status(&self, staff_member: &StaffMember) -> DashboardStatus {
+        self.statuses
+            .get(&staff_member.uuid())
+            .copied()
+            .unwrap_or(DashboardStatus::Away)
+    }
+
+    pub fn warning(&self, staff_member: &StaffMember) -> Option<WorkTimeWarning> {
+        self.warnings.get(&staff_member.uuid()).copied()
+    }
+
+    /// Text shown next to the status icon of a staff member.
+    pub fn label(&self, staff_member: &StaffMember) -> String {
+        let status = self.status(staff_member);
+        match self.worked.get(&staff_member.uuid()) {
+            Some(duration) => format!(
+                "{}: {} ({})",
+                staff_member.shown_name(),
+                status,
+                Dashboard::format_duration(*duration)
+            ),
+            None => format!("{}: {}", staff_member.shown_name(), status),
+        }
+    }
+
+    /// Counter of working staff for the door management, compared to the shift plan if there is one.
+    pub fn counter_label(&self) -> String {
+        match self.planned_count {
+            Some(planned) => format!("{} / {} eingeplant", self.working_count, planned),
+            None => format!("{} am Arbeiten", self.working_count),
+        }
+    }
+
+    /// If fewer staff members are working than the shift plan requires right now.
+    pub fn is_understaffed(&self) -> bool {
+        self.planned_count
+            .is_some_and(|planned| self.working_count < planned)
+    }
+
+    pub fn format_duration(duration: Duration) -> String {
+        format!(
+            "{}:{:02} h",
+            duration.num_hours(),
+            duration.num_minutes() % 60
+        )
+    }
+
+    /// Count the staff members with a shift that covers the current time.
+    fn planned_now(shifts: &[Shift], now: NaiveDateTime) -> Option<usize> {
+        if shifts.is_empty() {
+            return None;
+        }
+
+        let planned = shifts
+            .iter()
+            .filter(|shift| shift.start_time <= now && now < shift.end_time)
+            .map(|shift| shift.staff_id)
+            .collect::<HashSet<_>>();
+        Some(planned.len())
+    }
+
+    /// Compute the status shown on the dashboard.
+    /// If there is no shift plan for the current business day, everybody counts as scheduled.
+    fn statuses(
+        staff: &[StaffMember],
+        worked: &HashMap<i32, Duration>,
+        shifts: &[Shift],
+    ) -> HashMap<i32, DashboardStatus> {
+        let scheduled = shifts
+            .iter()
+            .map(|shift| shift.staff_id)
+            .collect::<HashSet<_>>();
+
+        staff
+            .iter()
+            .map(|staff_member| {
+                let uuid = staff_member.uuid();
+                let status = DashboardStatus::new(
+                    staff_member.status,
+                    worked.contains_key(&uuid),
+                    shifts.is_empty() || scheduled.contains(&uuid),
+                );
+                (uuid, status)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+    use stechuhr::event_bus::EventSubscriber;
+    use stechuhr::models::*;
+    use stechuhr::settings::WorkTimeWarnings;
+
+    use super::{Dashboard, WorkTimeWarning, WorkedToday};
+
+    #[test]
+    fn worked_today() {
+        let staff = vec![
+            DBStaffMember::new(
+                1,
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                true,
+                None,
+            )
+            .with_display_name(Some(String::from("DJ Max")))
+            .with_status(WorkStatus::Working),
+            DBStaffMember::new(
+                2,
+                String::from("Beeron"),
+                String::from("2222"),
+                String::from("2222222222"),
+                true,
+                None,
+            )
+            .with_status(WorkStatus::Away),
+        ];
+        let day = NaiveDate::from_ymd(2000, 1, 1);
+        let events = vec![
+            WorkEventT::new(
+                1,
+                day.and_hms(18, 0, 0),
+                WorkEvent::StatusChange(2, String::from("Beeron"), WorkStatus::Working),
+            ),
+            WorkEventT::new(
+                2,
+                day.and_hms(19, 30, 0),
+                WorkEvent::StatusChange(2, String::from("Beeron"), WorkStatus::Away),
+            ),
+            WorkEventT::new(
+                3,
+                day.and_hms(20, 0, 0),
+                WorkEvent::StatusChange(1, String::from("Aaron"), WorkStatus::Working),
+            ),
+        ];
+
+        let mut worked_today = WorkedToday::default();
+        for eventt in &events {
+            worked_today.on_event(eventt);
+        }
+
+        let limits = WorkTimeWarnings::default();
+        let dashboard = Dashboard::new(&staff, &worked_today, &[], &limits, day.and_hms(21, 0, 0));
+
+        assert_eq!(dashboard.working_count, 1);
+        assert_eq!(dashboard.total.num_minutes(), 150);
+        assert_eq!(dashboard.status(&staff[0]), DashboardStatus::Working);
+        assert_eq!(dashboard.status(&staff[1]), DashboardStatus::Break);
+        assert_eq!(dashboard.label(&staff[1]), "Beeron: Pause (1:30 h)");
+        assert!(dashboard.label(&staff[0]).starts_with("DJ Max: "));
+        assert_eq!(dashboard.counter_label(), "1 am Arbeiten");
+        assert!(!dashboard.is_understaffed());
+
+        let shifts = vec![
+            Shift::new(1, 1, day.and_hms(20, 0, 0), day.and_hms(23, 0, 0)),
+            Shift::new(2, 2, day.and_hms(20, 30, 0), day.and_hms(23, 0, 0)),
+            Shift::new(3, 2, day.and_hms(18, 0, 0), day.and_hms(19, 30, 0)),
+        ];
+        let dashboard = Dashboard::new(
+            &staff,
+            &worked_today,
+            &shifts,
+            &limits,
+            day.and_hms(21, 0, 0),
+        );
+
+        assert_eq!(dashboard.counter_label(), "1 / 2 eingeplant");
+        assert!(dashboard.is_understaffed());
+        assert_eq!(dashboard.warning(&staff[0]), None);
+    }
+
+    #[test]
+    fn work_time_warnings() {
+        let staff = vec![
+            DBStaffMember::new(
+                1,
+                String::from("Aaron"),
+                String::from("1111"),
+                String::from("1111111111"),
+                true,
+                None,
+            )
+            .with_status(WorkStatus::Working),
+            DBStaffMember::new(
+                2,
+                String::from("Beeron"),
+                String::from("2222"),
+                String::from("2222222222"),
+                true,
+                None,
+            )
+            .with_status(WorkStatus::Working),
+        ];
+        let day = NaiveDate::from_ymd(2000, 1, 1);
+        let status = |id, time, uuid, name: &str, status| {
+            WorkEventT::new(
+                id,
+                time,
+                WorkEvent::StatusChange(uuid, String::from(name), status),
+            )
+        };
+        let events = vec![
+            status(1, day.and_hms(12, 0, 0), 1, "Aaron", WorkStatus::Working),
+            status(2, day.and_hms(14, 0, 0), 2, "Beeron", WorkStatus::Working),
+            status(3, day.and_hms(18, 0, 0), 2, "Beeron", WorkStatus::Away),
+            status(4, day.and_hms(19, 0, 0), 2, "Beeron", WorkStatus::Working),
+        ];
+        let mut worked_today = WorkedToday::default();
+        for eventt in &events {
+            worked_today.on_event(eventt);
+        }
+        let limits = WorkTimeWarnings {
+            continuous_minutes: 600,
+            daily_minutes: 420,
+        };
+
+        let dashboard = Dashboard::new(&staff, &worked_today, &[], &limits, day.and_hms(22, 30, 0));
+
+        assert_eq!(
+            dashboard.warning(&staff[0]),
+            Some(WorkTimeWarning::Continuous(
+                Duration::hours(10) + Duration::minutes(30)
+            ))
+        );
+        assert_eq!(
+            dashboard.warning(&staff[1]),
+            Some(WorkTimeWarning::Daily(
+                Duration::hours(7) + Duration::minutes(30)
+            ))
+        );
+        assert_eq!(
+            dashboard.warning(&staff[1]).unwrap().to_string(),
+            "7:30 h heute"
+        );
+    }
+}
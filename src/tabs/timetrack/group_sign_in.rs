@@ -0,0 +1,319 @@
+//! Dialog in which the lead of a team that arrives together, e.g. the security, signs in several staff members at
+//! once instead of letting everyone queue at the terminal. Each one still gets their own status change, all with
+//! the same time.
+use std::collections::HashSet;
+
+use iced::{button, scrollable, text_input, Checkbox, Column, Element, Length, Scrollable, Text};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::models::*;
+use stechuhr::TEXT_SIZE;
+
+use super::{TimetrackMessage, TimetrackTab};
+use crate::tabs::components;
+use crate::{SharedData, StechuhrError};
+
+/// A staff member who can be signed in by the lead.
+struct Candidate {
+    uuid: i32,
+    name: String,
+    /* shown in the list instead of the legal name, see `StaffMember::display_name` */
+    shown_name: String,
+    /* youth labor law and rest period warnings, like for signing in alone */
+    warnings: Vec<String>,
+    is_selected: bool,
+}
+
+#[derive(Default)]
+struct GroupSignInModalState {
+    /* the lead, once they identified themselves, with their legal and shown name */
+    lead: Option<(i32, String, String)>,
+    candidates: Vec<Candidate>,
+    input_value: String,
+    input_state: text_input::State,
+    scroll_state: scrollable::State,
+    confirm_state: button::State,
+    cancel_state: button::State,
+}
+
+pub struct GroupSignIn {
+    modal_state: modal::State<GroupSignInModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GroupSignInMessage {
+    Open,
+    ChangeInput(String),
+    Submit,
+    Toggle(i32, bool),
+    Confirm,
+    Cancel,
+}
+
+/// Staff members who are away and scheduled for the current business day, or everybody who is away if there is no
+/// shift plan. Those of the department of the lead come first.
+fn candidates(staff: &[StaffMember], shifts: &[Shift], lead: &StaffMember) -> Vec<i32> {
+    let scheduled = shifts
+        .iter()
+        .map(|shift| shift.staff_id)
+        .collect::<HashSet<_>>();
+    let mut candidates = staff
+        .iter()
+        .filter(|staff_member| {
+            staff_member.is_visible
+                && staff_member.status == WorkStatus::Away
+                && (shifts.is_empty() || scheduled.contains(&staff_member.uuid()))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|staff_member| staff_member.department != lead.department);
+    candidates.into_iter().map(StaffMember::uuid).collect()
+}
+
+impl GroupSignIn {
+    pub fn new() -> Self {
+        GroupSignIn {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.modal_state.is_shown()
+    }
+
+    /// The lead identifies themselves like at the terminal, then the candidates are listed with the lead selected.
+    fn submit(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        let input = state.input_value.trim().to_owned();
+        state.input_value.clear();
+
+        let lead = match StaffMember::get_by_ident(
+            &shared.staff,
+            &input,
+            shared.settings.kiosk.personnel_number_login,
+        ) {
+            Some(lead) if lead.is_visible => lead.clone(),
+            Some(_) => {
+                return Err(StechuhrError::Str(String::from(
+                    "Person mit dieser PIN/diesem Dongle ist deaktiviert.",
+                )))
+            }
+            None => return Err(StechuhrError::Str(String::from("Unbekannte PIN/Dongle"))),
+        };
+
+        let mut candidates = Vec::new();
+        for uuid in self::candidates(&shared.staff, &shared.shifts, &lead) {
+            if let Some(staff_member) = StaffMember::get_by_uuid(&shared.staff, uuid).cloned() {
+                // Minors who must not sign in now are not offered at all.
+                if TimetrackTab::guard_youth_protection(shared, &staff_member).is_err() {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    uuid,
+                    name: staff_member.name.clone(),
+                    shown_name: staff_member.shown_name().to_owned(),
+                    warnings: TimetrackTab::sign_in_warnings(shared, &staff_member),
+                    is_selected: uuid == lead.uuid(),
+                });
+            }
+        }
+        let state = self.modal_state.inner_mut();
+        state.lead = Some((lead.uuid(), lead.name.clone(), lead.shown_name().to_owned()));
+        state.candidates = candidates;
+        Ok(())
+    }
+
+    /// Sign in the selected staff members with one time. Those who signed in on their own in the meantime are skipped.
+    fn confirm(&mut self, shared: &mut SharedData) {
+        let now = shared.current_time.naive_local();
+        let state = self.modal_state.inner_mut();
+        let lead_name = match &state.lead {
+            Some((_, name, _)) => name.clone(),
+            None => return,
+        };
+
+        let mut names = Vec::new();
+        for candidate in state
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.is_selected)
+        {
+            let staff_member = match StaffMember::get_by_uuid_mut(&mut shared.staff, candidate.uuid)
+            {
+                Some(staff_member) if staff_member.status == WorkStatus::Away => staff_member,
+                _ => continue,
+            };
+            staff_member.status = WorkStatus::Working;
+            shared.log_eventt(NewWorkEventT::new(
+                now,
+                WorkEvent::StatusChange(candidate.uuid, String::new(), WorkStatus::Working),
+            ));
+            // Keep a record that the sign-in happened despite the warnings.
+            for warning in &candidate.warnings {
+                shared.log_info(format!(
+                    "{} trotz Warnung angemeldet. {}",
+                    candidate.name, warning
+                ));
+            }
+            names.push(candidate.name.clone());
+        }
+
+        if !names.is_empty() {
+            shared.log_info(format!(
+                "{} hat {} Personen gemeinsam angemeldet: {}",
+                lead_name,
+                names.len(),
+                names.join(", ")
+            ));
+        }
+        self.cancel();
+    }
+
+    fn cancel(&mut self) {
+        *self.modal_state.inner_mut() = GroupSignInModalState::default();
+        self.modal_state.show(false);
+    }
+
+    /// Wraps the given content in the group sign-in dialog.
+    pub fn view<'a>(
+        &'a mut self,
+        underlay: Element<'a, TimetrackMessage>,
+    ) -> Element<'a, TimetrackMessage> {
+        let message = |message| TimetrackMessage::GroupSignIn(message);
+
+        Modal::new(&mut self.modal_state, underlay, move |state| {
+            let body: Element<'_, TimetrackMessage> = match &state.lead {
+                None => {
+                    state.input_state.focus();
+                    Column::new()
+                        .spacing(10)
+                        .push(Text::new("Teamleitung: PIN eingeben oder Dongle swipen"))
+                        .push(
+                            stechuhr::style::text_input(
+                                &mut state.input_state,
+                                "PIN eingeben/Dongle swipen",
+                                &state.input_value,
+                                move |s| message(GroupSignInMessage::ChangeInput(s)),
+                            )
+                            .on_submit(message(GroupSignInMessage::Submit))
+                            .password()
+                            .size(TEXT_SIZE),
+                        )
+                        .into()
+                }
+                Some((_, _, lead_name)) => {
+                    let mut list =
+                        Scrollable::new(&mut state.scroll_state)
+                            .spacing(10)
+                            .push(Text::new(format!(
+                                "{}, wer wird jetzt angemeldet?",
+                                lead_name
+                            )));
+                    if state.candidates.is_empty() {
+                        list = list.push(Text::new("Niemand ist eingeplant und abwesend."));
+                    }
+                    for candidate in &state.candidates {
+                        let uuid = candidate.uuid;
+                        list = list.push(
+                            Checkbox::new(candidate.is_selected, &candidate.shown_name, move |b| {
+                                message(GroupSignInMessage::Toggle(uuid, b))
+                            })
+                            .text_size(TEXT_SIZE),
+                        );
+                        for warning in &candidate.warnings {
+                            list = list.push(Text::new(warning.as_str()));
+                        }
+                    }
+                    list.max_height(500).into()
+                }
+            };
+
+            let count = state
+                .candidates
+                .iter()
+                .filter(|candidate| candidate.is_selected)
+                .count();
+            Card::new(Text::new("Gruppe anmelden"), body)
+                .foot(components::confirm_foot(
+                    &mut state.confirm_state,
+                    &format!("{} Personen anmelden", count),
+                    (count > 0).then(|| message(GroupSignInMessage::Confirm)),
+                    &mut state.cancel_state,
+                    "Abbrechen",
+                    message(GroupSignInMessage::Cancel),
+                ))
+                .max_width(600)
+                .width(Length::Shrink)
+                .on_close(message(GroupSignInMessage::Cancel))
+                .into()
+        })
+        .backdrop(message(GroupSignInMessage::Cancel))
+        .on_esc(message(GroupSignInMessage::Cancel))
+        .into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: GroupSignInMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            GroupSignInMessage::Open => self.modal_state.show(true),
+            GroupSignInMessage::ChangeInput(value) => {
+                self.modal_state.inner_mut().input_value = value
+            }
+            GroupSignInMessage::Submit => self.submit(shared)?,
+            GroupSignInMessage::Toggle(uuid, is_selected) => {
+                let state = self.modal_state.inner_mut();
+                if let Some(candidate) = state
+                    .candidates
+                    .iter_mut()
+                    .find(|candidate| candidate.uuid == uuid)
+                {
+                    candidate.is_selected = is_selected;
+                }
+            }
+            GroupSignInMessage::Confirm => self.confirm(shared),
+            GroupSignInMessage::Cancel => self.cancel(),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use stechuhr::models::*;
+
+    use super::candidates;
+
+    #[test]
+    fn scheduled_candidates() {
+        let staff_member = |uuid, name: &str, department: Option<&str>, status| {
+            DBStaffMember::new(
+                uuid,
+                String::from(name),
+                uuid.to_string().repeat(4),
+                uuid.to_string().repeat(10),
+                true,
+                None,
+            )
+            .with_department(department.map(String::from))
+            .with_status(status)
+        };
+        let staff = vec![
+            staff_member(1, "Aaron", None, WorkStatus::Away),
+            staff_member(2, "Berta", Some("Security"), WorkStatus::Away),
+            staff_member(3, "Cem", Some("Security"), WorkStatus::Working),
+            staff_member(4, "Dora", Some("Security"), WorkStatus::Away),
+            staff_member(5, "Emil", None, WorkStatus::Away),
+        ];
+        let day = NaiveDate::from_ymd(2000, 1, 1);
+        let shift =
+            |id, staff_id| Shift::new(id, staff_id, day.and_hms(20, 0, 0), day.and_hms(23, 0, 0));
+
+        assert_eq!(candidates(&staff, &[], &staff[3]), vec![2, 4, 1, 5]);
+        assert_eq!(
+            candidates(&staff, &[shift(1, 1), shift(2, 3), shift(3, 4)], &staff[3]),
+            vec![4, 1]
+        );
+    }
+}
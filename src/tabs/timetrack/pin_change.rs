@@ -0,0 +1,215 @@
+//! Dialog in which staff change their own PIN by swiping their dongle and entering the old PIN.
+use std::str::FromStr;
+
+use iced::{button, text_input, Column, Element, Text};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::models::*;
+use stechuhr::TEXT_SIZE;
+
+use super::TimetrackMessage;
+use crate::tabs::components;
+use crate::tabs::management::ManagementRequest;
+use crate::tabs::requests::TabRequest;
+use crate::{SharedData, StechuhrError};
+
+/// Staff change their PIN in several steps, all entered in the same input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum PinChangeStage {
+    #[default]
+    Dongle,
+    OldPin,
+    NewPin,
+    RepeatPin,
+}
+
+impl PinChangeStage {
+    fn prompt(&self) -> &'static str {
+        match self {
+            PinChangeStage::Dongle => "Dongle swipen",
+            PinChangeStage::OldPin => "Alte PIN eingeben",
+            PinChangeStage::NewPin => "Neue PIN eingeben",
+            PinChangeStage::RepeatPin => "Neue PIN wiederholen",
+        }
+    }
+}
+
+#[derive(Default)]
+struct PinChangeModalState {
+    stage: PinChangeStage,
+    uuid: Option<i32>,
+    new_pin: String,
+    input_value: String,
+    input_state: text_input::State,
+    cancel_state: button::State,
+}
+
+pub struct PinChange {
+    modal_state: modal::State<PinChangeModalState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PinChangeMessage {
+    Open,
+    ChangeInput(String),
+    Submit,
+    Cancel,
+}
+
+impl PinChange {
+    pub fn new() -> Self {
+        PinChange {
+            modal_state: modal::State::default(),
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.modal_state.is_shown()
+    }
+
+    /// Handle one step of the PIN change. Errors in the old PIN abort the whole change.
+    fn submit(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        let input = state.input_value.trim().to_owned();
+        state.input_value.clear();
+
+        match state.stage {
+            PinChangeStage::Dongle => match StaffMember::get_by_card_id(&shared.staff, &input) {
+                Some(staff_member) if staff_member.is_visible => {
+                    state.uuid = Some(staff_member.uuid());
+                    state.stage = PinChangeStage::OldPin;
+                }
+                Some(_) => {
+                    return Err(StechuhrError::Str(String::from(
+                        "Person mit diesem Dongle ist deaktiviert.",
+                    )))
+                }
+                None => return Err(StechuhrError::Str(String::from("Unbekannter Dongle"))),
+            },
+            PinChangeStage::OldPin => {
+                let is_correct = state
+                    .uuid
+                    .and_then(|uuid| StaffMember::get_by_uuid(&shared.staff, uuid))
+                    .is_some_and(|staff_member| staff_member.pin == input);
+                if !is_correct {
+                    self.cancel();
+                    return Err(StechuhrError::Str(String::from(
+                        "Falsche PIN. Die Änderung wurde abgebrochen.",
+                    )));
+                }
+                state.stage = PinChangeStage::NewPin;
+            }
+            PinChangeStage::NewPin => {
+                PIN::from_str(&input)?;
+                let uuid = state.uuid.unwrap_or_default();
+                // INVARIANT: pins, cardids and (if enabled) personnel numbers are disjoint
+                if StaffMember::is_ident_taken(
+                    &shared.staff,
+                    &input,
+                    uuid,
+                    shared.settings.kiosk.personnel_number_login,
+                ) {
+                    return Err(StechuhrError::Str(String::from(
+                        "Diese PIN ist bereits vergeben, bitte eine andere wählen.",
+                    )));
+                }
+                state.new_pin = input;
+                state.stage = PinChangeStage::RepeatPin;
+            }
+            PinChangeStage::RepeatPin => {
+                if input != state.new_pin {
+                    state.new_pin.clear();
+                    state.stage = PinChangeStage::NewPin;
+                    return Err(StechuhrError::Str(String::from(
+                        "Die PINs stimmen nicht überein, bitte die neue PIN nochmal eingeben.",
+                    )));
+                }
+
+                if let Some(staff_member) = state
+                    .uuid
+                    .and_then(|uuid| StaffMember::get_by_uuid_mut(&mut shared.staff, uuid))
+                {
+                    let previous = staff_member.clone();
+                    staff_member.pin = input;
+                    shared.storage.save_staff_member(staff_member)?;
+
+                    let name = staff_member.name.clone();
+                    let entries = NewStaffHistoryEntry::edits(
+                        staff_member,
+                        &previous,
+                        shared.current_time.naive_local(),
+                    );
+                    let uuid = staff_member.uuid();
+                    shared.record_staff_history(entries);
+                    shared.request(TabRequest::Management(ManagementRequest::ReloadRow(uuid)));
+                    shared.log_info(format!("{} hat die eigene PIN geändert.", name));
+                    shared.prompt_message(String::from("Die PIN wurde geändert."));
+                }
+                self.cancel();
+            }
+        }
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
+        *self.modal_state.inner_mut() = PinChangeModalState::default();
+        self.modal_state.show(false);
+    }
+
+    /// Wraps the given content in the PIN change dialog.
+    pub fn view<'a>(
+        &'a mut self,
+        underlay: Element<'a, TimetrackMessage>,
+    ) -> Element<'a, TimetrackMessage> {
+        Modal::new(&mut self.modal_state, underlay, |state| {
+            state.input_state.focus();
+
+            let input = stechuhr::style::text_input(
+                &mut state.input_state,
+                state.stage.prompt(),
+                &state.input_value,
+                |s| TimetrackMessage::PinChange(PinChangeMessage::ChangeInput(s)),
+            )
+            .on_submit(TimetrackMessage::PinChange(PinChangeMessage::Submit))
+            .size(TEXT_SIZE);
+            let input = if state.stage == PinChangeStage::Dongle {
+                input
+            } else {
+                input.password()
+            };
+
+            Card::new(
+                Text::new("PIN ändern"),
+                Column::new()
+                    .spacing(10)
+                    .push(Text::new(state.stage.prompt()))
+                    .push(input),
+            )
+            .foot(
+                components::modal_button(&mut state.cancel_state, "Abbrechen")
+                    .on_press(TimetrackMessage::PinChange(PinChangeMessage::Cancel)),
+            )
+            .max_width(400)
+            .on_close(TimetrackMessage::PinChange(PinChangeMessage::Cancel))
+            .into()
+        })
+        .backdrop(TimetrackMessage::PinChange(PinChangeMessage::Cancel))
+        .on_esc(TimetrackMessage::PinChange(PinChangeMessage::Cancel))
+        .into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: PinChangeMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            PinChangeMessage::Open => self.modal_state.show(true),
+            PinChangeMessage::ChangeInput(value) => {
+                self.modal_state.inner_mut().input_value = value
+            }
+            PinChangeMessage::Submit => self.submit(shared)?,
+            PinChangeMessage::Cancel => self.cancel(),
+        }
+        Ok(())
+    }
+}
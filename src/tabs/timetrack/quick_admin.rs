@@ -0,0 +1,505 @@
+//! Admin actions that can be done directly on the dashboard after entering the admin password.
+//! The password only unlocks them for a short time since the terminal is accessible to everyone.
+use chrono::{Duration, NaiveDateTime};
+use iced::{button, text_input, Alignment, Column, Element, Length, Row, Text};
+use iced_aw::{modal, Card, Modal};
+use stechuhr::correction;
+use stechuhr::date_ext::NaiveDateExt;
+use stechuhr::event_bus::EventSubscriber;
+use stechuhr::forgotten_sign_off::{self, ForgottenSignOff};
+use stechuhr::models::*;
+
+use super::TimetrackMessage;
+use crate::tabs::components::{self, OnScreenKey, OnScreenKeyboard};
+use crate::tabs::management::ManagementError;
+use crate::{SharedData, StechuhrError};
+
+/// How long the quick actions stay unlocked after entering the password.
+const UNLOCK_MINUTES: i64 = 2;
+const END_TIME_FORMAT: &str = "%d.%m.%Y %H:%M";
+
+#[derive(Default)]
+struct QuickAdminModalState {
+    password_value: String,
+    password_state: text_input::State,
+    keyboard: OnScreenKeyboard,
+    unlock_state: button::State,
+    undo_state: button::State,
+    sign_off_states: Vec<button::State>,
+    action_states: Vec<button::State>,
+    end_value: String,
+    end_state: text_input::State,
+    end_submit_state: button::State,
+    lock_state: button::State,
+    close_state: button::State,
+}
+
+pub struct QuickAdmin {
+    modal_state: modal::State<QuickAdminModalState>,
+    /* the quick actions are unlocked until this time */
+    unlocked_until: Option<NaiveDateTime>,
+    /* the most recent status change of the business day, unless it was already corrected */
+    last_change: Option<(i32, WorkStatus)>,
+    /* shift that was probably not signed off, with the name of the staff member */
+    forgotten: Option<(ForgottenSignOff, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum QuickAdminMessage {
+    Open,
+    ChangePassword(String),
+    PasswordKey(OnScreenKey),
+    Unlock,
+    Undo,
+    SignOff(i32),
+    ChangeForgottenEnd(String),
+    SubmitForgottenEnd,
+    Lock,
+    Close,
+}
+
+impl QuickAdmin {
+    pub fn new() -> Self {
+        QuickAdmin {
+            modal_state: modal::State::default(),
+            unlocked_until: None,
+            last_change: None,
+            forgotten: None,
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.modal_state.is_shown()
+    }
+
+    fn is_unlocked(&self, now: NaiveDateTime) -> bool {
+        self.unlocked_until
+            .is_some_and(|unlocked_until| now < unlocked_until)
+    }
+
+    fn unlock(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        let state = self.modal_state.inner_mut();
+        let verified = shared.storage.verify_password(state.password_value.trim());
+        state.password_value.clear();
+        if !verified {
+            return Err(ManagementError::InvalidPassword.into());
+        }
+
+        self.unlocked_until =
+            Some(shared.current_time.naive_local() + Duration::minutes(UNLOCK_MINUTES));
+        shared.log_info(String::from(
+            "Admin-Aktionen auf der Stechuhr freigeschaltet.",
+        ));
+        Ok(())
+    }
+
+    /// Check the unlock before each action, the time may have run out while the dialog was open.
+    pub(super) fn ensure_unlocked(&mut self, shared: &SharedData) -> Result<(), StechuhrError> {
+        if self.is_unlocked(shared.current_time.naive_local()) {
+            Ok(())
+        } else {
+            self.unlocked_until = None;
+            Err(StechuhrError::Str(String::from(
+                "Die Freischaltung ist abgelaufen, bitte das Admin-Passwort nochmal eingeben.",
+            )))
+        }
+    }
+
+    fn describe_last_change(&self, shared: &SharedData) -> Option<String> {
+        let (uuid, status) = self.last_change?;
+        let staff_member = StaffMember::get_by_uuid(&shared.staff, uuid)?;
+        Some(format!(
+            "{} wurde auf '{}' gesetzt",
+            staff_member.name, status
+        ))
+    }
+
+    /// Change the status of a staff member and record why, so that the change shows up in the evaluation.
+    pub(super) fn correct_status(
+        shared: &mut SharedData,
+        uuid: i32,
+        new_status: WorkStatus,
+        description: String,
+    ) {
+        let name = match StaffMember::get_by_uuid_mut(&mut shared.staff, uuid) {
+            Some(staff_member) => {
+                staff_member.status = new_status;
+                staff_member.name.clone()
+            }
+            None => return,
+        };
+        shared.create_event(WorkEvent::StatusChange(uuid, String::new(), new_status));
+        shared.create_event(WorkEvent::Correction(
+            uuid,
+            String::new(),
+            description.clone(),
+        ));
+        shared.log_info(format!("Korrektur für {}: {}", name, description));
+    }
+
+    fn undo(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        self.ensure_unlocked(shared)?;
+        let (uuid, status) = self.last_change.ok_or_else(|| {
+            StechuhrError::Str(String::from(
+                "Es gibt keine Änderung, die rückgängig gemacht werden kann.",
+            ))
+        })?;
+        QuickAdmin::correct_status(
+            shared,
+            uuid,
+            status.toggle(),
+            format!(
+                "Änderung auf \"{}\" wurde an der Stechuhr rückgängig gemacht.",
+                status
+            ),
+        );
+        Ok(())
+    }
+
+    fn sign_off(&mut self, shared: &mut SharedData, uuid: i32) -> Result<(), StechuhrError> {
+        self.ensure_unlocked(shared)?;
+        let is_working = StaffMember::get_by_uuid(&shared.staff, uuid)
+            .is_some_and(|staff_member| staff_member.status == WorkStatus::Working);
+        if is_working {
+            QuickAdmin::correct_status(
+                shared,
+                uuid,
+                WorkStatus::Away,
+                String::from("Wurde an der Stechuhr von einem Admin abgemeldet."),
+            );
+        }
+        Ok(())
+    }
+
+    /// Called when somebody signs in. If their last shift was ended by the 6am boundary, the dialog is opened
+    /// with a suggested end of that shift, see `forgotten_sign_off`.
+    pub fn suggest_sign_off(&mut self, shared: &mut SharedData, uuid: i32) {
+        let now = shared.current_time.naive_local();
+        let events = shared.storage.load_staff_status_events_between(
+            uuid,
+            now - Duration::days(forgotten_sign_off::HISTORY_DAYS),
+            now,
+        );
+        let forgotten = match forgotten_sign_off::detect(&events, uuid) {
+            Some(forgotten) => forgotten,
+            None => return,
+        };
+        // A closed month cannot be corrected anymore.
+        let month = shared.settings.business_date(forgotten.start).first_dom();
+        if shared.storage.is_month_closed(month) {
+            return;
+        }
+        let name = match StaffMember::get_by_uuid(&shared.staff, uuid) {
+            Some(staff_member) => staff_member.name.clone(),
+            None => return,
+        };
+
+        self.modal_state.inner_mut().end_value =
+            forgotten.suggested_end.format(END_TIME_FORMAT).to_string();
+        self.forgotten = Some((forgotten, name));
+        self.modal_state.show(true);
+    }
+
+    fn describe_forgotten(forgotten: &ForgottenSignOff, name: &str) -> String {
+        let usual = match forgotten.usual_length {
+            Some(length) => format!(
+                "Die Schichten dauern meistens {}:{:02} Stunden.",
+                length.num_hours(),
+                length.num_minutes() % 60
+            ),
+            None => String::from("Es gibt zu wenige Schichten, um die übliche Dauer zu bestimmen."),
+        };
+        format!(
+            "{} war am {} ab {} Uhr bis zum Tageswechsel angemeldet und hat sich vermutlich nicht abgemeldet. {} Das Ende kann hier eingetragen werden.",
+            name,
+            forgotten.start.format("%d.%m.%Y"),
+            forgotten.start.format("%H:%M"),
+            usual
+        )
+    }
+
+    /// Sign off the staff member at the entered end of the forgotten shift and record why.
+    /// The events are recorded at that time so that they are counted in the evaluation of that day.
+    fn submit_forgotten_end(&mut self, shared: &mut SharedData) -> Result<(), StechuhrError> {
+        self.ensure_unlocked(shared)?;
+        let (forgotten, name) = match &self.forgotten {
+            Some(forgotten) => forgotten.clone(),
+            None => return Ok(()),
+        };
+
+        let state = self.modal_state.inner_mut();
+        let input = state.end_value.trim();
+        let end = NaiveDateTime::parse_from_str(input, END_TIME_FORMAT).map_err(|_| {
+            StechuhrError::Str(format!(
+                "\"{}\" ist keine Zeit im Format TT.MM.JJJJ HH:MM.",
+                input
+            ))
+        })?;
+        if end <= forgotten.start || end >= forgotten.boundary {
+            return Err(StechuhrError::Str(format!(
+                "Das Ende muss zwischen {} und {} liegen.",
+                forgotten.start.format(END_TIME_FORMAT),
+                forgotten.boundary.format(END_TIME_FORMAT)
+            )));
+        }
+
+        let uuid = forgotten.uuid;
+        correction::check(
+            uuid,
+            end,
+            WorkStatus::Away,
+            shared.current_time.naive_local(),
+            &shared.settings,
+            shared.storage.as_mut(),
+        )?;
+        shared.storage.insert_event(NewWorkEventT::new(
+            end,
+            WorkEvent::StatusChange(uuid, String::new(), WorkStatus::Away),
+        ));
+        let description = format!(
+            "Vergessene Abmeldung, das Ende der Schicht ab {} wurde nachträglich auf {} gesetzt.",
+            forgotten.start.format(END_TIME_FORMAT),
+            end.format(END_TIME_FORMAT)
+        );
+        shared.storage.insert_event(NewWorkEventT::new(
+            end,
+            WorkEvent::Correction(uuid, String::new(), description.clone()),
+        ));
+        shared.log_info(format!("Korrektur für {}: {}", name, description));
+
+        state.end_value.clear();
+        self.forgotten = None;
+        Ok(())
+    }
+
+    /// Closing the dialog dismisses a suggested end of a forgotten shift.
+    fn close(&mut self) {
+        let state = self.modal_state.inner_mut();
+        state.password_value.clear();
+        state.end_value.clear();
+        self.forgotten = None;
+        self.modal_state.show(false);
+    }
+
+    /// Wraps the given content in the dialog of the admin actions.
+    pub fn view<'a>(
+        &'a mut self,
+        shared: &SharedData,
+        underlay: Element<'a, TimetrackMessage>,
+    ) -> Element<'a, TimetrackMessage> {
+        let now = shared.current_time.naive_local();
+        let remaining = self
+            .unlocked_until
+            .filter(|_| self.is_unlocked(now))
+            .map(|unlocked_until| (unlocked_until - now).num_seconds());
+        let last_change = self.describe_last_change(shared);
+        let working: Vec<(i32, String)> = shared
+            .staff
+            .iter()
+            .filter(|staff_member| staff_member.status == WorkStatus::Working)
+            .map(|staff_member| (staff_member.uuid(), staff_member.name.clone()))
+            .collect();
+        let actions: Vec<String> = shared
+            .settings
+            .quick_actions
+            .iter()
+            .map(|action| action.label.clone())
+            .collect();
+        let on_screen_keyboard = shared.settings.kiosk.on_screen_keyboard;
+        let forgotten = self
+            .forgotten
+            .as_ref()
+            .map(|(forgotten, name)| QuickAdmin::describe_forgotten(forgotten, name));
+
+        let message = |message| TimetrackMessage::QuickAdmin(message);
+
+        Modal::new(&mut self.modal_state, underlay, move |state| {
+            let body = match remaining {
+                None => {
+                    state.password_state.focus();
+
+                    let mut body = Column::new()
+                        .spacing(10)
+                        .push(Text::new(format!(
+                            "Mit dem Admin-Passwort werden Korrekturen für {} Minuten freigeschaltet.",
+                            UNLOCK_MINUTES
+                        )))
+                        .push(
+                            stechuhr::style::text_input(
+                                &mut state.password_state,
+                                "Admin-Passwort",
+                                &state.password_value,
+                                move |s| message(QuickAdminMessage::ChangePassword(s)),
+                            )
+                            .password()
+                            .on_submit(message(QuickAdminMessage::Unlock)),
+                        )
+                        .push(
+                            components::modal_button(&mut state.unlock_state, "Freischalten")
+                                .on_press(message(QuickAdminMessage::Unlock)),
+                        );
+                    if on_screen_keyboard {
+                        body = body.push(
+                            state
+                                .keyboard
+                                .view(move |key| message(QuickAdminMessage::PasswordKey(key))),
+                        );
+                    }
+                    body
+                }
+                Some(seconds) => {
+                    state.password_state.unfocus();
+
+                    let mut undo_button = components::modal_button(
+                        &mut state.undo_state,
+                        "Letzte Änderung rückgängig",
+                    );
+                    if last_change.is_some() {
+                        undo_button = undo_button.on_press(message(QuickAdminMessage::Undo));
+                    }
+
+                    let mut body = Column::new()
+                        .spacing(10)
+                        .push(Text::new(format!(
+                            "Freigeschaltet für noch {}:{:02} Minuten.",
+                            seconds / 60,
+                            seconds % 60
+                        )))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(undo_button)
+                                .push(Text::new(last_change.clone().unwrap_or_else(|| {
+                                    String::from("Keine Änderung, die rückgängig gemacht werden kann.")
+                                }))),
+                        )
+                        .push(Text::new("Abmelden:"));
+
+                    state
+                        .sign_off_states
+                        .resize_with(working.len(), button::State::default);
+                    if working.is_empty() {
+                        body = body.push(Text::new("Niemand ist am Arbeiten."));
+                    }
+                    for ((uuid, name), sign_off_state) in
+                        working.iter().zip(state.sign_off_states.iter_mut())
+                    {
+                        body = body.push(
+                            components::modal_button(sign_off_state, name)
+                                .width(Length::Units(200))
+                                .on_press(message(QuickAdminMessage::SignOff(*uuid))),
+                        );
+                    }
+                    if !actions.is_empty() {
+                        body = body.push(Text::new("Aktionen:"));
+                    }
+                    state
+                        .action_states
+                        .resize_with(actions.len(), button::State::default);
+                    for (idx, (label, action_state)) in
+                        actions.iter().zip(state.action_states.iter_mut()).enumerate()
+                    {
+                        body = body.push(
+                            components::modal_button(action_state, label)
+                                .width(Length::Units(300))
+                                .on_press(TimetrackMessage::RunQuickAction(idx)),
+                        );
+                    }
+                    if forgotten.is_some() {
+                        body = body.push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(
+                                    stechuhr::style::text_input(
+                                        &mut state.end_state,
+                                        "TT.MM.JJJJ HH:MM",
+                                        &state.end_value,
+                                        move |s| message(QuickAdminMessage::ChangeForgottenEnd(s)),
+                                    )
+                                    .on_submit(message(QuickAdminMessage::SubmitForgottenEnd))
+                                    .width(Length::Units(250)),
+                                )
+                                .push(
+                                    components::modal_button(
+                                        &mut state.end_submit_state,
+                                        "Ende eintragen",
+                                    )
+                                    .on_press(message(QuickAdminMessage::SubmitForgottenEnd)),
+                                ),
+                        );
+                    }
+                    body
+                }
+            };
+            let body = match &forgotten {
+                Some(forgotten) => Column::new()
+                    .spacing(20)
+                    .push(Text::new(forgotten.as_str()))
+                    .push(body),
+                None => body,
+            };
+
+            Card::new(Text::new("Admin-Aktionen"), body)
+                .foot(components::confirm_foot(
+                    &mut state.lock_state,
+                    "Sperren",
+                    remaining.map(|_| message(QuickAdminMessage::Lock)),
+                    &mut state.close_state,
+                    "Schließen",
+                    message(QuickAdminMessage::Close),
+                ))
+                .max_width(600)
+                .on_close(message(QuickAdminMessage::Close))
+                .into()
+        })
+        .backdrop(message(QuickAdminMessage::Close))
+        .on_esc(message(QuickAdminMessage::Close))
+        .into()
+    }
+
+    pub fn update(
+        &mut self,
+        shared: &mut SharedData,
+        message: QuickAdminMessage,
+    ) -> Result<(), StechuhrError> {
+        match message {
+            QuickAdminMessage::Open => self.modal_state.show(true),
+            QuickAdminMessage::ChangePassword(value) => {
+                self.modal_state.inner_mut().password_value = value
+            }
+            QuickAdminMessage::PasswordKey(key) => {
+                let state = self.modal_state.inner_mut();
+                if state.keyboard.press(key, &mut state.password_value) {
+                    self.unlock(shared)?;
+                }
+            }
+            QuickAdminMessage::Unlock => self.unlock(shared)?,
+            QuickAdminMessage::Undo => self.undo(shared)?,
+            QuickAdminMessage::SignOff(uuid) => self.sign_off(shared, uuid)?,
+            QuickAdminMessage::ChangeForgottenEnd(value) => {
+                self.modal_state.inner_mut().end_value = value
+            }
+            QuickAdminMessage::SubmitForgottenEnd => self.submit_forgotten_end(shared)?,
+            QuickAdminMessage::Lock => self.unlocked_until = None,
+            QuickAdminMessage::Close => self.close(),
+        }
+        Ok(())
+    }
+}
+
+/// Undoing is recorded as a correction, so that an undo cannot be undone again by accident.
+impl EventSubscriber for QuickAdmin {
+    fn on_event(&mut self, eventt: &WorkEventT) {
+        match eventt.event {
+            WorkEvent::StatusChange(uuid, _, status) => self.last_change = Some((uuid, status)),
+            WorkEvent::Correction(..) => self.last_change = None,
+            _ => {}
+        }
+    }
+
+    fn on_new_business_day(&mut self) {
+        self.last_change = None;
+    }
+}
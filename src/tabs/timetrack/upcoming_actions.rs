@@ -0,0 +1,91 @@
+//! Actions that the application does on its own, announced on the dashboard some hours in advance so that
+//! nobody is surprised when e.g. everyone is signed off at the end of the business day.
+use std::fmt;
+
+use chrono::{Datelike, Duration, NaiveDateTime};
+use stechuhr::settings::Settings;
+
+/// How long before an action it is announced.
+const ANNOUNCE_HOURS: i64 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpcomingAction {
+    /// Everyone who is still working is signed off at the end of the business day.
+    SignOff(NaiveDateTime),
+    /// The weekly database audit, see `SharedData::run_audit`.
+    Audit(NaiveDateTime),
+}
+
+impl UpcomingAction {
+    fn time(self) -> NaiveDateTime {
+        match self {
+            UpcomingAction::SignOff(time) | UpcomingAction::Audit(time) => time,
+        }
+    }
+}
+
+impl fmt::Display for UpcomingAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpcomingAction::SignOff(time) => {
+                write!(f, "Automatische Abmeldung um {}", time.format("%H:%M"))
+            }
+            UpcomingAction::Audit(time) => write!(
+                f,
+                "Automatische Datenbankprüfung am {} um {}",
+                time.format("%d.%m."),
+                time.format("%H:%M")
+            ),
+        }
+    }
+}
+
+/// The actions in the next hours, ordered by time.
+pub fn upcoming(settings: &Settings, now: NaiveDateTime) -> Vec<UpcomingAction> {
+    // The sign-off happens a second before the new business day, but it is announced with the start of the day.
+    let sign_off = settings.business_day_start(settings.business_date(now) + Duration::days(1));
+    let audit = (0..=7)
+        .map(|days| (now.date() + Duration::days(days)).and_time(settings.audit_time))
+        .find(|time| time.weekday() == settings.audit_weekday && *time > now);
+
+    let mut actions = vec![UpcomingAction::SignOff(sign_off)];
+    actions.extend(audit.map(UpcomingAction::Audit));
+    actions.retain(|action| action.time() - now <= Duration::hours(ANNOUNCE_HOURS));
+    actions.sort_by_key(|action| action.time());
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+    use stechuhr::settings::Settings;
+
+    use super::*;
+
+    #[test]
+    fn upcoming_actions() {
+        let settings = Settings {
+            audit_weekday: Weekday::Mon,
+            audit_time: NaiveTime::from_hms(3, 0, 0),
+            ..Settings::default()
+        };
+        // 2022-05-01 is a Sunday.
+        let sunday = |h| NaiveDate::from_ymd(2022, 5, 1).and_hms(h, 0, 0);
+
+        assert_eq!(upcoming(&settings, sunday(12)), Vec::new());
+
+        let actions = upcoming(&settings, sunday(20));
+        assert_eq!(
+            actions,
+            vec![
+                UpcomingAction::Audit(NaiveDate::from_ymd(2022, 5, 2).and_hms(3, 0, 0)),
+                UpcomingAction::SignOff(NaiveDate::from_ymd(2022, 5, 2).and_hms(6, 0, 0)),
+            ]
+        );
+        assert_eq!(
+            actions[0].to_string(),
+            "Automatische Datenbankprüfung am 02.05. um 03:00"
+        );
+        assert_eq!(actions[1].to_string(), "Automatische Abmeldung um 06:00");
+    }
+}
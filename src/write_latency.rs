@@ -0,0 +1,276 @@
+//! Duration of the writes to the database. If the database lives on a slow network share an insert can take seconds,
+//! in which the window does not react because the database is used during the update. So we measure the writes and
+//! show on the dashboard that saving was slow, with a warning once a write takes longer than the threshold.
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::QueryResult;
+
+use crate::db::{SaveStaffError, StaffChanges};
+use crate::models::{
+    Announcement, DBStaffMember, EventSession, Export, NewAnnouncement, NewClosedMonth,
+    NewEventSession, NewExport, NewShift, NewStaffHistoryEntry, NewStaffMember, NewWorkEventT,
+    Shift, StaffHistoryEntry, StaffMember, StaffNames, WorkEventT,
+};
+use crate::storage::Storage;
+
+/// Writes that take at least this long are shown on the dashboard.
+pub const SLOW_WRITE_MILLIS: u64 = 500;
+/// Seconds for which a slow write stays shown.
+pub const SLOW_WRITE_SHOWN_SECONDS: i64 = 10;
+
+/// The slowest write since it was last taken, shared between the storage and the application.
+#[derive(Debug, Clone, Default)]
+pub struct SlowestWrite(Rc<Cell<Option<Duration>>>);
+
+impl SlowestWrite {
+    fn record(&self, duration: Duration) {
+        if self.0.get().is_none_or(|slowest| duration > slowest) {
+            self.0.set(Some(duration));
+        }
+    }
+
+    pub fn take(&self) -> Option<Duration> {
+        self.0.take()
+    }
+}
+
+/// Format a duration in seconds with one decimal, as shown to the users.
+pub fn format_duration(duration: Duration) -> String {
+    format!("{:.1} s", duration.as_secs_f64()).replace('.', ",")
+}
+
+/// Storage that measures how long the writes of another storage take. Reads are passed through.
+pub struct TimedStorage {
+    inner: Box<dyn Storage>,
+    slowest: SlowestWrite,
+}
+
+impl TimedStorage {
+    /// The storage and a handle to its slowest write, which stays readable after the storage was moved.
+    pub fn new(inner: Box<dyn Storage>) -> (Self, SlowestWrite) {
+        let slowest = SlowestWrite::default();
+        let storage = TimedStorage {
+            inner,
+            slowest: slowest.clone(),
+        };
+        (storage, slowest)
+    }
+
+    fn timed<T>(&mut self, write: impl FnOnce(&mut dyn Storage) -> T) -> T {
+        let started = Instant::now();
+        let result = write(self.inner.as_mut());
+        self.slowest.record(started.elapsed());
+        result
+    }
+}
+
+impl Storage for TimedStorage {
+    fn load_state(&mut self, current_time: NaiveDateTime) -> Vec<StaffMember> {
+        self.inner.load_state(current_time)
+    }
+
+    fn load_staff_names(&mut self) -> StaffNames {
+        self.inner.load_staff_names()
+    }
+
+    fn load_all_staff_ids(&mut self) -> Vec<i32> {
+        self.inner.load_all_staff_ids()
+    }
+
+    fn load_staff_added_at(&mut self, uuid: i32) -> Option<NaiveDateTime> {
+        self.inner.load_staff_added_at(uuid)
+    }
+
+    fn refresh_staff(
+        &mut self,
+        staff: &mut Vec<StaffMember>,
+        previous_events: &[WorkEventT],
+    ) -> StaffChanges {
+        self.inner.refresh_staff(staff, previous_events)
+    }
+
+    fn load_staff_including_inactive(&mut self) -> Vec<DBStaffMember> {
+        self.inner.load_staff_including_inactive()
+    }
+
+    fn insert_staff(&mut self, staff_member: NewStaffMember) -> QueryResult<StaffMember> {
+        self.timed(|storage| storage.insert_staff(staff_member))
+    }
+
+    fn save_staff_member(&mut self, staff_member: &mut StaffMember) -> Result<(), SaveStaffError> {
+        self.timed(|storage| storage.save_staff_member(staff_member))
+    }
+
+    fn save_staff(&mut self, staff: &mut [StaffMember]) -> QueryResult<()> {
+        self.timed(|storage| storage.save_staff(staff))
+    }
+
+    fn delete_staff_member(&mut self, staff_member: StaffMember) -> QueryResult<()> {
+        self.timed(|storage| storage.delete_staff_member(staff_member))
+    }
+
+    fn insert_staff_history(&mut self, entries: &[NewStaffHistoryEntry]) -> QueryResult<usize> {
+        self.timed(|storage| storage.insert_staff_history(entries))
+    }
+
+    fn load_staff_history_between(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<StaffHistoryEntry> {
+        self.inner.load_staff_history_between(start, end)
+    }
+
+    fn verify_password(&mut self, password: &str) -> bool {
+        self.inner.verify_password(password)
+    }
+
+    fn insert_event(&mut self, new_event: NewWorkEventT) -> Option<WorkEventT> {
+        self.timed(|storage| storage.insert_event(new_event))
+    }
+
+    fn load_events_between(
+        &mut self,
+        start_time: Option<NaiveDateTime>,
+        end_time: Option<NaiveDateTime>,
+    ) -> Vec<WorkEventT> {
+        self.inner.load_events_between(start_time, end_time)
+    }
+
+    fn load_events_of_kinds_between(
+        &mut self,
+        kinds: &[&str],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.inner
+            .load_events_of_kinds_between(kinds, start_time, end_time)
+    }
+
+    fn load_status_events_before(&mut self, before: NaiveDateTime) -> Vec<WorkEventT> {
+        self.inner.load_status_events_before(before)
+    }
+
+    fn load_latest_events_of_kind(&mut self, event_kind: &str, limit: i64) -> Vec<WorkEventT> {
+        self.inner.load_latest_events_of_kind(event_kind, limit)
+    }
+
+    fn load_last_event_time(&mut self, before: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.inner.load_last_event_time(before)
+    }
+
+    fn ensure_6am_event(&mut self, time: NaiveDateTime) -> bool {
+        self.timed(|storage| storage.ensure_6am_event(time))
+    }
+
+    fn load_staff_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.inner
+            .load_staff_events_between(uuid, start_time, end_time)
+    }
+
+    fn load_staff_status_events_between(
+        &mut self,
+        uuid: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Vec<WorkEventT> {
+        self.inner
+            .load_staff_status_events_between(uuid, start_time, end_time)
+    }
+
+    fn load_all_staff_events(&mut self, uuid: i32) -> Vec<WorkEventT> {
+        self.inner.load_all_staff_events(uuid)
+    }
+
+    fn load_shifts_between(&mut self, start: NaiveDateTime, end: NaiveDateTime) -> Vec<Shift> {
+        self.inner.load_shifts_between(start, end)
+    }
+
+    fn insert_shifts(&mut self, new_shifts: &[NewShift]) -> QueryResult<usize> {
+        self.timed(|storage| storage.insert_shifts(new_shifts))
+    }
+
+    fn load_announcement(&mut self) -> Option<Announcement> {
+        self.inner.load_announcement()
+    }
+
+    fn insert_announcement(&mut self, new_announcement: NewAnnouncement) -> QueryResult<usize> {
+        self.timed(|storage| storage.insert_announcement(new_announcement))
+    }
+
+    fn load_event_sessions_between(
+        &mut self,
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+    ) -> Vec<EventSession> {
+        self.inner
+            .load_event_sessions_between(first_date, last_date)
+    }
+
+    fn replace_event_sessions(
+        &mut self,
+        date: NaiveDate,
+        sessions: &[NewEventSession],
+    ) -> QueryResult<usize> {
+        self.timed(|storage| storage.replace_event_sessions(date, sessions))
+    }
+
+    fn is_month_closed(&mut self, first_day: NaiveDate) -> bool {
+        self.inner.is_month_closed(first_day)
+    }
+
+    fn insert_closed_month(&mut self, closed_month: NewClosedMonth) -> QueryResult<usize> {
+        self.timed(|storage| storage.insert_closed_month(closed_month))
+    }
+
+    fn load_exports(&mut self, limit: i64) -> Vec<Export> {
+        self.inner.load_exports(limit)
+    }
+
+    fn insert_export(&mut self, new_export: NewExport) -> QueryResult<usize> {
+        self.timed(|storage| storage.insert_export(new_export))
+    }
+
+    /// Not timed, a backup copies the whole database and is expected to take a while.
+    fn backup(&mut self, path: &Path) -> QueryResult<()> {
+        self.inner.backup(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WorkEvent;
+    use diesel::{Connection, SqliteConnection};
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+    #[test]
+    fn measure_writes() {
+        let mut connection = SqliteConnection::establish(":memory:").unwrap();
+        connection.run_pending_migrations(MIGRATIONS).unwrap();
+        let (mut storage, slowest) = TimedStorage::new(Box::new(connection));
+
+        storage.load_events_between(None, None);
+        assert_eq!(slowest.take(), None);
+
+        storage.insert_event(NewWorkEventT::new(
+            NaiveDate::from_ymd(2000, 1, 1).and_hms(5, 59, 59),
+            WorkEvent::_6am,
+        ));
+        assert!(slowest.take().is_some());
+        assert_eq!(slowest.take(), None);
+
+        assert_eq!(format_duration(Duration::from_millis(3200)), "3,2 s");
+    }
+}
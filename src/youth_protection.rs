@@ -0,0 +1,309 @@
+//! Limits of the youth labor law (Jugendarbeitsschutzgesetz) for staff members under 18.
+use crate::date_ext::NaiveDateTimeExt;
+use crate::models::{WorkEvent, WorkEventT, WorkStatus};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub const MAX_DAILY_HOURS: i64 = 8;
+pub const MAX_WEEKLY_HOURS: i64 = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Minors must not work between midnight and 6am.
+    Night(NaiveDateTime),
+    /// Worked time on a business day.
+    Daily(NaiveDate, Duration),
+    /// Worked time in the week starting at the given monday.
+    Weekly(NaiveDate, Duration),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Violation::Night(time) => format!(
+                "Arbeit nach Mitternacht am {}",
+                time.format("%d.%m.%Y um %H:%M")
+            ),
+            Violation::Daily(date, duration) => format!(
+                "{}:{:02} h gearbeitet am {} (höchstens {} h pro Tag)",
+                duration.num_hours(),
+                duration.num_minutes() % 60,
+                date.format("%d.%m.%Y"),
+                MAX_DAILY_HOURS
+            ),
+            Violation::Weekly(monday, duration) => format!(
+                "{}:{:02} h gearbeitet in der Woche ab {} (höchstens {} h pro Woche)",
+                duration.num_hours(),
+                duration.num_minutes() % 60,
+                monday.format("%d.%m.%Y"),
+                MAX_WEEKLY_HOURS
+            ),
+        };
+        f.write_str(&description)
+    }
+}
+
+fn is_night(time: NaiveDateTime) -> bool {
+    time.time() < NaiveTime::from_hms(6, 0, 0)
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_isoywd(date.iso_week().year(), date.iso_week().week(), Weekday::Mon)
+}
+
+/// Part of a work interval that is credited to a minor in the evaluation. Work after midnight is not allowed, so
+/// an interval is cut at midnight and nothing is credited of an interval that starts in the night.
+pub fn credited_interval(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if is_night(start) {
+        return None;
+    }
+    let midnight = start.date().succ().and_hms(0, 0, 0);
+    Some((start, end.min(midnight)))
+}
+
+/// The reason why a minor must not sign in at `now`, which the terminal refuses instead of only warning.
+pub fn refuse_sign_in(now: NaiveDateTime) -> Option<Violation> {
+    is_night(now).then_some(Violation::Night(now))
+}
+
+/// Intervals in which the staff member was working. An interval that is still open ends at `until`.
+/// The 6am boundary ends all intervals.
+pub fn work_intervals<'e>(
+    events: impl IntoIterator<Item = &'e WorkEventT>,
+    uuid: i32,
+    until: NaiveDateTime,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut intervals = Vec::new();
+    let mut working_since = None;
+
+    for eventt in events {
+        match eventt.event {
+            WorkEvent::StatusChange(id, _, WorkStatus::Working) if id == uuid => {
+                working_since.get_or_insert(eventt.created_at);
+            }
+            WorkEvent::StatusChange(id, _, WorkStatus::Away) if id == uuid => {
+                if let Some(start) = working_since.take() {
+                    intervals.push((start, eventt.created_at));
+                }
+            }
+            WorkEvent::_6am => {
+                if let Some(start) = working_since.take() {
+                    intervals.push((start, eventt.created_at));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = working_since {
+        intervals.push((start, until));
+    }
+
+    intervals
+}
+
+/// Worked time per business day.
+pub fn daily_durations(
+    intervals: &[(NaiveDateTime, NaiveDateTime)],
+    cutoff: NaiveTime,
+) -> BTreeMap<NaiveDate, Duration> {
+    let mut daily = BTreeMap::new();
+    for (start, end) in intervals {
+        let day = daily
+            .entry(start.business_date(cutoff))
+            .or_insert_with(Duration::zero);
+        *day = *day + (*end - *start);
+    }
+    daily
+}
+
+/// Check the work intervals of a minor against the night and daily limits, see `check_weeks` for the weekly one.
+/// Days are business days ending at the cutoff time.
+pub fn check(intervals: &[(NaiveDateTime, NaiveDateTime)], cutoff: NaiveTime) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for (start, end) in intervals {
+        // Only the night directly after midnight of the interval's start is checked, intervals never span more than a day.
+        let midnight = start.date().succ().and_hms(0, 0, 0);
+        if is_night(*start) {
+            violations.push(Violation::Night(*start));
+        } else if *end > midnight {
+            violations.push(Violation::Night(midnight));
+        }
+    }
+
+    violations.extend(
+        daily_durations(intervals, cutoff)
+            .into_iter()
+            .filter(|(_, duration)| *duration > Duration::hours(MAX_DAILY_HOURS))
+            .map(|(date, duration)| Violation::Daily(date, duration)),
+    );
+    violations
+}
+
+/// Check the weekly limit for every week that overlaps the business days from `first_day` to `last_day`.
+/// A week at the edge of a month also has days outside of it, so the intervals must cover the whole weeks.
+pub fn check_weeks(
+    intervals: &[(NaiveDateTime, NaiveDateTime)],
+    cutoff: NaiveTime,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+) -> Vec<Violation> {
+    let mut weekly: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+    for (date, duration) in daily_durations(intervals, cutoff) {
+        let week = weekly
+            .entry(week_start(date))
+            .or_insert_with(Duration::zero);
+        *week = *week + duration;
+    }
+
+    weekly
+        .range(week_start(first_day)..=last_day)
+        .filter(|(_, duration)| **duration > Duration::hours(MAX_WEEKLY_HOURS))
+        .map(|(monday, duration)| Violation::Weekly(*monday, *duration))
+        .collect()
+}
+
+/// Warnings for a minor who signs in at `now`, given the work intervals of the current week.
+pub fn sign_in_warnings(
+    intervals: &[(NaiveDateTime, NaiveDateTime)],
+    now: NaiveDateTime,
+    cutoff: NaiveTime,
+) -> Vec<Violation> {
+    let mut warnings = Vec::new();
+    if is_night(now) {
+        warnings.push(Violation::Night(now));
+    }
+
+    let today = now.business_date(cutoff);
+    let worked = |from: NaiveDate| {
+        intervals
+            .iter()
+            .filter(|(start, _)| start.business_date(cutoff) >= from)
+            .fold(Duration::zero(), |total, (start, end)| {
+                total + (*end - *start)
+            })
+    };
+    let worked_today = worked(today);
+    if worked_today >= Duration::hours(MAX_DAILY_HOURS) {
+        warnings.push(Violation::Daily(today, worked_today));
+    }
+    let monday = week_start(today);
+    let worked_week = worked(monday);
+    if worked_week >= Duration::hours(MAX_WEEKLY_HOURS) {
+        warnings.push(Violation::Weekly(monday, worked_week));
+    }
+
+    warnings
+}
+
+/// First moment of the business week containing `now`.
+pub fn week_start_time(now: NaiveDateTime, cutoff: NaiveTime) -> NaiveDateTime {
+    week_start(now.business_date(cutoff)).and_time(cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minor_violations() {
+        let cutoff = NaiveTime::from_hms(6, 0, 0);
+        // 2000-01-03 is a monday
+        let day = |d, h, m| NaiveDate::from_ymd(2000, 1, d).and_hms(h, m, 0);
+        let intervals = vec![
+            // past midnight
+            (day(3, 20, 0), day(4, 0, 30)),
+            // 9 hours
+            (day(4, 12, 0), day(4, 21, 0)),
+            (day(5, 12, 0), day(5, 20, 0)),
+            (day(6, 12, 0), day(6, 20, 0)),
+            // 11 hours
+            (day(7, 10, 0), day(7, 18, 0)),
+            (day(7, 19, 0), day(7, 22, 0)),
+        ];
+        let week = Violation::Weekly(
+            NaiveDate::from_ymd(2000, 1, 3),
+            Duration::minutes(40 * 60 + 30),
+        );
+
+        assert_eq!(
+            check(&intervals, cutoff),
+            vec![
+                Violation::Night(day(4, 0, 0)),
+                Violation::Daily(NaiveDate::from_ymd(2000, 1, 4), Duration::hours(9)),
+                Violation::Daily(NaiveDate::from_ymd(2000, 1, 7), Duration::hours(11)),
+            ]
+        );
+        assert_eq!(
+            check_weeks(
+                &intervals,
+                cutoff,
+                NaiveDate::from_ymd(2000, 1, 1),
+                NaiveDate::from_ymd(2000, 1, 31)
+            ),
+            vec![week.clone()]
+        );
+        assert_eq!(
+            credited_interval(day(3, 20, 0), day(4, 0, 30)),
+            Some((day(3, 20, 0), day(4, 0, 0)))
+        );
+        assert_eq!(
+            credited_interval(day(4, 12, 0), day(4, 21, 0)),
+            Some((day(4, 12, 0), day(4, 21, 0)))
+        );
+        assert_eq!(credited_interval(day(5, 1, 0), day(5, 2, 0)), None);
+        assert_eq!(refuse_sign_in(day(4, 23, 59)), None);
+        assert_eq!(
+            refuse_sign_in(day(5, 0, 0)),
+            Some(Violation::Night(day(5, 0, 0)))
+        );
+        // Signing in after midnight still belongs to friday.
+        assert_eq!(
+            sign_in_warnings(&intervals, day(8, 1, 0), cutoff),
+            vec![
+                Violation::Night(day(8, 1, 0)),
+                Violation::Daily(NaiveDate::from_ymd(2000, 1, 7), Duration::hours(11)),
+                week,
+            ]
+        );
+    }
+
+    #[test]
+    fn weeks_at_month_edges() {
+        let cutoff = NaiveTime::from_hms(6, 0, 0);
+        // 2000-01-31 is a monday, so the week has one day in January and six in February.
+        let intervals = (0..6)
+            .map(|days| {
+                let date = NaiveDate::from_ymd(2000, 1, 31) + Duration::days(days);
+                (date.and_hms(10, 0, 0), date.and_hms(18, 0, 0))
+            })
+            .collect::<Vec<_>>();
+        let week = vec![Violation::Weekly(
+            NaiveDate::from_ymd(2000, 1, 31),
+            Duration::hours(48),
+        )];
+
+        let january = (
+            NaiveDate::from_ymd(2000, 1, 1),
+            NaiveDate::from_ymd(2000, 1, 31),
+        );
+        let february = (
+            NaiveDate::from_ymd(2000, 2, 1),
+            NaiveDate::from_ymd(2000, 2, 29),
+        );
+        let march = (
+            NaiveDate::from_ymd(2000, 3, 1),
+            NaiveDate::from_ymd(2000, 3, 31),
+        );
+        assert_eq!(check_weeks(&intervals, cutoff, january.0, january.1), week);
+        assert_eq!(
+            check_weeks(&intervals, cutoff, february.0, february.1),
+            week
+        );
+        assert_eq!(check_weeks(&intervals, cutoff, march.0, march.1), []);
+    }
+}